@@ -5,6 +5,7 @@ use crate::js::WasmerEnv;
 use js_sys::Function;
 use js_sys::WebAssembly::{Memory, Table};
 use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::fmt;
 use std::sync::Arc;
 use wasm_bindgen::{JsCast, JsValue};
@@ -129,47 +130,53 @@ impl Export {
     }
 }
 
-impl From<(JsValue, ExternType)> for Export {
-    fn from((val, extern_type): (JsValue, ExternType)) -> Export {
+impl TryFrom<(JsValue, ExternType)> for Export {
+    type Error = &'static str;
+
+    fn try_from((val, extern_type): (JsValue, ExternType)) -> Result<Self, Self::Error> {
         match extern_type {
             ExternType::Memory(memory_type) => {
                 if val.is_instance_of::<Memory>() {
-                    return Export::Memory(VMMemory::new(
+                    Ok(Export::Memory(VMMemory::new(
                         val.unchecked_into::<Memory>(),
                         memory_type,
-                    ));
+                    )))
                 } else {
-                    panic!("Extern type doesn't match js value type");
+                    Err("Extern type doesn't match js value type")
                 }
             }
             ExternType::Global(global_type) => {
                 if val.is_instance_of::<Global>() {
-                    return Export::Global(VMGlobal::new(
+                    Ok(Export::Global(VMGlobal::new(
                         val.unchecked_into::<Global>(),
                         global_type,
-                    ));
+                    )))
                 } else {
-                    panic!("Extern type doesn't match js value type");
+                    Err("Extern type doesn't match js value type")
                 }
             }
             ExternType::Function(function_type) => {
                 if val.is_instance_of::<Function>() {
-                    return Export::Function(VMFunction::new(
+                    Ok(Export::Function(VMFunction::new(
                         val.unchecked_into::<Function>(),
                         function_type,
                         None,
-                    ));
+                    )))
                 } else {
-                    panic!("Extern type doesn't match js value type");
+                    Err("Extern type doesn't match js value type")
                 }
             }
             ExternType::Table(table_type) => {
                 if val.is_instance_of::<Table>() {
-                    return Export::Table(VMTable::new(val.unchecked_into::<Table>(), table_type));
+                    Ok(Export::Table(VMTable::new(
+                        val.unchecked_into::<Table>(),
+                        table_type,
+                    )))
                 } else {
-                    panic!("Extern type doesn't match js value type");
+                    Err("Extern type doesn't match js value type")
                 }
             }
+            ExternType::Tag(_) => Err("tags are not supported by the js API yet"),
         }
     }
 }
@@ -7,9 +7,11 @@ use crate::js::module::Module;
 use crate::js::store::Store;
 use crate::js::trap::RuntimeError;
 use js_sys::WebAssembly;
+use std::convert::TryFrom;
 use std::fmt;
 #[cfg(feature = "std")]
 use thiserror::Error;
+use wasmer_types::ExternType;
 
 /// A WebAssembly Instance is a stateful, executable
 /// instance of a WebAssembly [`Module`].
@@ -123,6 +125,8 @@ impl Instance {
         let instance_exports = instance.exports();
         let exports = module
             .exports()
+            // Tags have no `Extern` in the js API, so their exports are skipped.
+            .filter(|export_type| !matches!(export_type.ty(), ExternType::Tag(_)))
             .map(|export_type| {
                 let name = export_type.name();
                 let extern_type = export_type.ty().clone();
@@ -133,7 +137,9 @@ impl Instance {
                             &name
                         ))
                     })?;
-                let export: Export = (js_export, extern_type).into();
+                let export = Export::try_from((js_export, extern_type)).map_err(|e| {
+                    InstantiationError::Link(format!("Can't use export {}: {}", &name, e))
+                })?;
                 let extern_ = Extern::from_vm_export(store, export);
                 Ok((name.to_string(), extern_))
             })
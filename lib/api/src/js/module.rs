@@ -171,7 +171,7 @@ impl Module {
         // The module is now validated, so we can safely parse it's types
         #[cfg(feature = "wasm-types-polyfill")]
         let (type_hints, name) = {
-            let info = crate::js::module_info_polyfill::translate_module(binary).unwrap();
+            let info = crate::js::module_info_polyfill::translate_module(binary)?;
 
             (
                 Some(ModuleTypeHints {
@@ -426,6 +426,7 @@ impl Module {
                 ExternType::Global(_) => "global",
                 ExternType::Memory(_) => "memory",
                 ExternType::Table(_) => "table",
+                ExternType::Tag(_) => "tag",
             };
             if expected_kind != kind.as_str() {
                 return Err(format!("The provided type hint for the export {} is {} which doesn't match the expected kind: {}", i, kind.as_str(), expected_kind));
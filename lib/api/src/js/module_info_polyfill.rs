@@ -5,6 +5,7 @@
 //! for the Wasm imports and exports.
 //!  
 //! https://github.com/WebAssembly/js-types/blob/master/proposals/js-types/Overview.md
+use crate::js::error::{CompileError, WasmError};
 use core::convert::TryFrom;
 use std::vec::Vec;
 use wasmer_types::entity::EntityRef;
@@ -15,12 +16,12 @@ use wasmer_types::{
 
 use wasmparser::{
     self, BinaryReaderError, Export, ExportSectionReader, ExternalKind, FuncType as WPFunctionType,
-    FunctionSectionReader, GlobalSectionReader, GlobalType as WPGlobalType, ImportSectionEntryType,
-    ImportSectionReader, MemorySectionReader, MemoryType as WPMemoryType, NameSectionReader,
-    Parser, Payload, TableSectionReader, TypeDef, TypeSectionReader,
+    FunctionSectionReader, GlobalSectionReader, GlobalType as WPGlobalType, ImportSectionReader,
+    MemorySectionReader, MemoryType as WPMemoryType, NameSectionReader, Parser, Payload,
+    TableSectionReader, Type as WPType, TypeRef, TypeSectionReader, ValType,
 };
 
-pub type WasmResult<T> = Result<T, String>;
+pub type WasmResult<T> = Result<T, CompileError>;
 
 #[derive(Default)]
 pub struct ModuleInfoPolyfill {
@@ -244,8 +245,11 @@ impl ModuleInfoPolyfill {
     }
 }
 
-fn transform_err(err: BinaryReaderError) -> String {
-    err.message().into()
+fn transform_err(err: BinaryReaderError) -> CompileError {
+    CompileError::Wasm(WasmError::InvalidWebAssembly {
+        message: err.message().into(),
+        offset: err.offset(),
+    })
 }
 
 /// Translate a sequence of bytes forming a valid Wasm binary into a
@@ -283,15 +287,13 @@ pub fn translate_module<'data>(data: &'data [u8]) -> WasmResult<ModuleInfoPolyfi
                 parse_export_section(exports, &mut module_info)?;
             }
 
-            Payload::CustomSection {
-                name: "name",
-                data,
-                data_offset,
-                ..
-            } => parse_name_section(
-                NameSectionReader::new(data, data_offset).map_err(transform_err)?,
-                &mut module_info,
-            )?,
+            Payload::CustomSection(sectionreader) if sectionreader.name() == "name" => {
+                parse_name_section(
+                    NameSectionReader::new(sectionreader.data(), sectionreader.data_offset())
+                        .map_err(transform_err)?,
+                    &mut module_info,
+                )?
+            }
 
             _ => {}
         }
@@ -301,16 +303,15 @@ pub fn translate_module<'data>(data: &'data [u8]) -> WasmResult<ModuleInfoPolyfi
 }
 
 /// Helper function translating wasmparser types to Wasm Type.
-pub fn wptype_to_type(ty: wasmparser::Type) -> WasmResult<Type> {
+pub fn wptype_to_type(ty: ValType) -> WasmResult<Type> {
     match ty {
-        wasmparser::Type::I32 => Ok(Type::I32),
-        wasmparser::Type::I64 => Ok(Type::I64),
-        wasmparser::Type::F32 => Ok(Type::F32),
-        wasmparser::Type::F64 => Ok(Type::F64),
-        wasmparser::Type::V128 => Ok(Type::V128),
-        wasmparser::Type::ExternRef => Ok(Type::ExternRef),
-        wasmparser::Type::FuncRef => Ok(Type::FuncRef),
-        ty => Err(format!("wptype_to_type: wasmparser type {:?}", ty)),
+        ValType::I32 => Ok(Type::I32),
+        ValType::I64 => Ok(Type::I64),
+        ValType::F32 => Ok(Type::F32),
+        ValType::F64 => Ok(Type::F64),
+        ValType::V128 => Ok(Type::V128),
+        ValType::ExternRef => Ok(Type::ExternRef),
+        ValType::FuncRef => Ok(Type::FuncRef),
     }
 }
 
@@ -323,26 +324,23 @@ pub fn parse_type_section(
     module_info.reserve_signatures(count)?;
 
     for entry in types {
-        if let Ok(TypeDef::Func(WPFunctionType { params, returns })) = entry {
-            let sig_params: Vec<Type> = params
-                .iter()
-                .map(|ty| {
-                    wptype_to_type(*ty)
-                        .expect("only numeric types are supported in function signatures")
-                })
-                .collect();
-            let sig_returns: Vec<Type> = returns
-                .iter()
-                .map(|ty| {
-                    wptype_to_type(*ty)
-                        .expect("only numeric types are supported in function signatures")
-                })
-                .collect();
-            let sig = FunctionType::new(sig_params, sig_returns);
-            module_info.declare_signature(sig)?;
-        } else {
-            unimplemented!("module linking not implemented yet")
-        }
+        let WPType::Func(WPFunctionType { params, returns }) = entry.map_err(transform_err)?;
+        let sig_params: Vec<Type> = params
+            .iter()
+            .map(|ty| {
+                wptype_to_type(*ty)
+                    .expect("only numeric types are supported in function signatures")
+            })
+            .collect();
+        let sig_returns: Vec<Type> = returns
+            .iter()
+            .map(|ty| {
+                wptype_to_type(*ty)
+                    .expect("only numeric types are supported in function signatures")
+            })
+            .collect();
+        let sig = FunctionType::new(sig_params, sig_returns);
+        module_info.declare_signature(sig)?;
     }
 
     Ok(())
@@ -358,23 +356,22 @@ pub fn parse_import_section<'data>(
     for entry in imports {
         let import = entry.map_err(transform_err)?;
         let module_name = import.module;
-        let field_name = import.field;
+        let field_name = import.name;
 
         match import.ty {
-            ImportSectionEntryType::Function(sig) => {
+            TypeRef::Func(sig) => {
                 module_info.declare_func_import(
                     SignatureIndex::from_u32(sig),
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
-            ImportSectionEntryType::Module(_) | ImportSectionEntryType::Instance(_) => {
-                unimplemented!("module linking not implemented yet")
-            }
-            ImportSectionEntryType::Tag(_) => {
-                unimplemented!("exception handling not implemented yet")
+            TypeRef::Tag(_) => {
+                return Err(CompileError::UnsupportedFeature(
+                    "exception handling".to_string(),
+                ));
             }
-            ImportSectionEntryType::Memory(WPMemoryType {
+            TypeRef::Memory(WPMemoryType {
                 shared,
                 memory64,
                 initial,
@@ -390,20 +387,20 @@ pub fn parse_import_section<'data>(
                         shared,
                     },
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
-            ImportSectionEntryType::Global(ref ty) => {
+            TypeRef::Global(ref ty) => {
                 module_info.declare_global_import(
                     GlobalType {
                         ty: wptype_to_type(ty.content_type).unwrap(),
                         mutability: ty.mutable.into(),
                     },
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
-            ImportSectionEntryType::Table(ref tab) => {
+            TypeRef::Table(ref tab) => {
                 module_info.declare_table_import(
                     TableType {
                         ty: wptype_to_type(tab.element_type).unwrap(),
@@ -411,7 +408,7 @@ pub fn parse_import_section<'data>(
                         maximum: tab.maximum,
                     },
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
         }
@@ -512,7 +509,7 @@ pub fn parse_export_section<'data>(
 
     for entry in exports {
         let Export {
-            field,
+            name: field,
             ref kind,
             index,
         } = entry.map_err(transform_err)?;
@@ -522,7 +519,7 @@ pub fn parse_export_section<'data>(
         // becomes a concern here.
         let index = index as usize;
         match *kind {
-            ExternalKind::Function => {
+            ExternalKind::Func => {
                 module_info.declare_func_export(FunctionIndex::new(index), field)?
             }
             ExternalKind::Table => {
@@ -534,11 +531,10 @@ pub fn parse_export_section<'data>(
             ExternalKind::Global => {
                 module_info.declare_global_export(GlobalIndex::new(index), field)?
             }
-            ExternalKind::Type | ExternalKind::Module | ExternalKind::Instance => {
-                unimplemented!("module linking not implemented yet")
-            }
             ExternalKind::Tag => {
-                unimplemented!("exception handling not implemented yet")
+                return Err(CompileError::UnsupportedFeature(
+                    "exception handling".to_string(),
+                ));
             }
         }
     }
@@ -1,4 +1,4 @@
-use crate::sys::externals::{Extern, Function, Global, Memory, Table};
+use crate::sys::externals::{Extern, Function, Global, Memory, Table, Tag};
 use crate::sys::native::TypedFunction;
 use crate::sys::WasmTypeList;
 use indexmap::IndexMap;
@@ -103,7 +103,7 @@ impl Exports {
     ///
     /// If you want to get an export dynamically with type checking
     /// please use the following functions: `get_func`, `get_memory`,
-    /// `get_table` or `get_global` instead.
+    /// `get_table`, `get_global` or `get_tag` instead.
     ///
     /// If you want to get an export dynamically handling manually
     /// type checking manually, please use `get_extern`.
@@ -129,6 +129,11 @@ impl Exports {
         self.get(name)
     }
 
+    /// Get an export as a `Tag`.
+    pub fn get_tag(&self, name: &str) -> Result<&Tag, ExportError> {
+        self.get(name)
+    }
+
     /// Get an export as a `Func`.
     pub fn get_function(&self, name: &str) -> Result<&Function, ExportError> {
         self.get(name)
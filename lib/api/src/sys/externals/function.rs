@@ -11,12 +11,16 @@ pub use inner::{FromToNativeWasmType, HostFunction, WasmTypeList, WithEnv, Witho
 use std::cmp::max;
 use std::ffi::c_void;
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::Poll;
 use wasmer_compiler::{Export, ExportFunction, ExportFunctionMetadata};
 use wasmer_vm::{
-    on_host_stack, raise_user_trap, resume_panic, wasmer_call_trampoline, ImportInitializerFuncPtr,
-    VMCallerCheckedAnyfunc, VMDynamicFunctionContext, VMFuncRef, VMFunction, VMFunctionBody,
-    VMFunctionEnvironment, VMFunctionKind, VMTrampoline,
+    block_on_host, on_host_stack, raise_user_trap, resume_panic, wasmer_call_trampoline,
+    wasmer_call_trampoline_async, ImportInitializerFuncPtr, VMCallerCheckedAnyfunc,
+    VMDynamicFunctionContext, VMFuncRef, VMFunction, VMFunctionBody, VMFunctionEnvironment,
+    VMFunctionKind, VMTrampoline,
 };
 
 /// A WebAssembly `function` instance.
@@ -202,12 +206,91 @@ impl Function {
         FT: Into<FunctionType>,
         F: Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
         Env: Sized + WasmerEnv + 'static,
+    {
+        Self::new_dynamic(store, ty, env, DynamicCallback::Sync(Arc::new(func)))
+    }
+
+    /// Creates a new asynchronous host `Function` (dynamic) with the provided
+    /// signature.
+    ///
+    /// The returned future is awaited when the function is called by an
+    /// async call, such as [`Function::call_async`]: the WebAssembly code is
+    /// suspended until it's ready, without blocking the thread. Calling the
+    /// function from a synchronous call traps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    ///
+    /// let f = Function::new_async(&store, &signature, |args| async move {
+    ///     let sum = args[0].unwrap_i32() + args[1].unwrap_i32();
+    ///     Ok(vec![Value::I32(sum)])
+    /// });
+    /// ```
+    pub fn new_async<FT, F, Fut>(store: &Store, ty: FT, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(Vec<Val>) -> Fut + 'static + Send + Sync,
+        Fut: Future<Output = Result<Vec<Val>, RuntimeError>> + 'static + Send,
+    {
+        let wrapped_func = move |_env: &WithoutEnv, args: Vec<Val>| func(args);
+        Self::new_async_with_env(store, ty, WithoutEnv, wrapped_func)
+    }
+
+    /// Creates a new asynchronous host `Function` (dynamic) with the provided
+    /// signature and environment.
+    ///
+    /// See [`Function::new_async`]. The returned future can't borrow the
+    /// environment, clone what it needs instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value, WasmerEnv};
+    /// # let store = Store::default();
+    /// #
+    /// #[derive(WasmerEnv, Clone)]
+    /// struct Env {
+    ///   multiplier: i32,
+    /// };
+    /// let env = Env { multiplier: 2 };
+    ///
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    ///
+    /// let f = Function::new_async_with_env(&store, &signature, env, |env, args| {
+    ///     let multiplier = env.multiplier;
+    ///     async move {
+    ///         let result = multiplier * (args[0].unwrap_i32() + args[1].unwrap_i32());
+    ///         Ok(vec![Value::I32(result)])
+    ///     }
+    /// });
+    /// ```
+    pub fn new_async_with_env<FT, F, Fut, Env>(store: &Store, ty: FT, env: Env, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&Env, Vec<Val>) -> Fut + 'static + Send + Sync,
+        Fut: Future<Output = Result<Vec<Val>, RuntimeError>> + 'static + Send,
+        Env: Sized + WasmerEnv + 'static,
+    {
+        let func =
+            move |env: &Env, args: &[Val]| -> HostFuture { Box::pin(func(env, args.to_vec())) };
+        Self::new_dynamic(store, ty, env, DynamicCallback::Async(Arc::new(func)))
+    }
+
+    fn new_dynamic<FT, Env>(store: &Store, ty: FT, env: Env, func: DynamicCallback<Env>) -> Self
+    where
+        FT: Into<FunctionType>,
+        Env: Sized + WasmerEnv + 'static,
     {
         let ty: FunctionType = ty.into();
         let dynamic_ctx: VMDynamicFunctionContext<DynamicFunction<Env>> =
             VMDynamicFunctionContext::from_context(DynamicFunction {
                 env: Box::new(env),
-                func: Arc::new(func),
+                func,
                 store: store.clone(),
                 function_type: ty.clone(),
             });
@@ -386,6 +469,52 @@ impl Function {
         params: &[Val],
         results: &mut [Val],
     ) -> Result<(), RuntimeError> {
+        let mut values_vec = self.values_vec(params, results)?;
+
+        // Call the trampoline.
+        if let Err(error) = unsafe {
+            wasmer_call_trampoline(
+                &self.store,
+                self.exported.vm_function.vmctx,
+                trampoline,
+                self.exported.vm_function.address,
+                values_vec.as_mut_ptr() as *mut u8,
+            )
+        } {
+            return Err(RuntimeError::from_trap(error));
+        }
+
+        self.read_results(&values_vec, results);
+        Ok(())
+    }
+
+    async fn call_wasm_async(
+        &self,
+        trampoline: VMTrampoline,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<(), RuntimeError> {
+        let mut values_vec = self.values_vec(params, results)?;
+
+        // Call the trampoline, `values_vec` outlives the call.
+        let call = unsafe {
+            wasmer_call_trampoline_async(
+                Box::new(self.store.clone()),
+                self.exported.vm_function.vmctx,
+                trampoline,
+                self.exported.vm_function.address,
+                values_vec.as_mut_ptr() as *mut u8,
+            )
+        };
+        call.await.map_err(RuntimeError::from_trap)?;
+
+        self.read_results(&values_vec, results);
+        Ok(())
+    }
+
+    /// Checks the types of `params` and `results`, and returns the buffer
+    /// passed to the call trampoline with the arguments stored in it.
+    fn values_vec(&self, params: &[Val], results: &[Val]) -> Result<Vec<i128>, RuntimeError> {
         let format_types_for_error_message = |items: &[Val]| {
             items
                 .iter()
@@ -426,28 +555,17 @@ impl Function {
             }
         }
 
-        // Call the trampoline.
-        if let Err(error) = unsafe {
-            wasmer_call_trampoline(
-                &self.store,
-                self.exported.vm_function.vmctx,
-                trampoline,
-                self.exported.vm_function.address,
-                values_vec.as_mut_ptr() as *mut u8,
-            )
-        } {
-            return Err(RuntimeError::from_trap(error));
-        }
+        Ok(values_vec)
+    }
 
-        // Load the return values out of `values_vec`.
-        for (index, &value_type) in signature.results().iter().enumerate() {
+    /// Loads the return values out of `values_vec`.
+    fn read_results(&self, values_vec: &[i128], results: &mut [Val]) {
+        for (index, &value_type) in self.ty().results().iter().enumerate() {
             unsafe {
                 let ptr = values_vec.as_ptr().add(index);
                 results[index] = Val::read_value_from(&self.store, ptr, value_type);
             }
         }
-
-        Ok(())
     }
 
     /// Returns the number of parameters that this function takes.
@@ -542,6 +660,84 @@ impl Function {
         }
     }
 
+    /// Call the `Function` function asynchronously.
+    ///
+    /// The WebAssembly code runs on its own stack, and is suspended whenever
+    /// it calls a host function created with [`Function::new_async`] whose
+    /// future isn't ready: the returned future is then pending, and the
+    /// thread is free to run other tasks.
+    ///
+    /// Dropping the returned future while the call is suspended abandons the
+    /// call, its instance should not be used any more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmer::{imports, wat2wasm, Function, FunctionType, Instance, Module, Store, Type, Value};
+    /// # let store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (import "host" "double" (func $double (param i32) (result i32)))
+    /// #   (func (export "quadruple") (param $x i32) (result i32)
+    /// #     local.get $x
+    /// #     call $double
+    /// #     call $double
+    /// #   ))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// let signature = FunctionType::new(vec![Type::I32], vec![Type::I32]);
+    /// let double = Function::new_async(&store, &signature, |args| async move {
+    ///     Ok(vec![Value::I32(args[0].unwrap_i32() * 2)])
+    /// });
+    /// let import_object = imports! { "host" => { "double" => double } };
+    /// let instance = Instance::new(&module, &import_object).unwrap();
+    /// let quadruple = instance.exports.get_function("quadruple").unwrap();
+    ///
+    /// let result = quadruple.call_async(&[Value::I32(3)]);
+    /// # let result = {
+    /// #     use std::future::Future;
+    /// #     use std::sync::Arc;
+    /// #     use std::task::{Context, Poll, Wake};
+    /// #     struct NoopWaker;
+    /// #     impl Wake for NoopWaker {
+    /// #         fn wake(self: Arc<Self>) {}
+    /// #     }
+    /// #     let waker = Arc::new(NoopWaker).into();
+    /// #     let mut result = Box::pin(result);
+    /// #     match result.as_mut().poll(&mut Context::from_waker(&waker)) {
+    /// #         Poll::Ready(result) => result,
+    /// #         Poll::Pending => unreachable!(),
+    /// #     }
+    /// # };
+    /// // Awaited by your executor, e.g. `result.await`:
+    /// assert_eq!(result.unwrap().to_vec(), vec![Value::I32(12)]);
+    /// ```
+    pub async fn call_async(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        // If it's a function defined in the Wasm, it will always have a call_trampoline
+        if let Some(trampoline) = self.exported.vm_function.call_trampoline {
+            let mut results = vec![Val::null(); self.result_arity()];
+            self.call_wasm_async(trampoline, params, &mut results)
+                .await?;
+            return Ok(results.into_boxed_slice());
+        }
+
+        // If it's a function defined in the host
+        match self.exported.vm_function.kind {
+            VMFunctionKind::Dynamic => {
+                let future = unsafe {
+                    type VMContextWithEnv =
+                        VMDynamicFunctionContext<DynamicFunction<std::ffi::c_void>>;
+                    let ctx = self.exported.vm_function.vmctx.host_env as *mut VMContextWithEnv;
+                    (*ctx).ctx.call_async(params)
+                };
+                Ok(future.await?.into_boxed_slice())
+            }
+            VMFunctionKind::Static => Err(RuntimeError::new(
+                "Native function definitions can't be directly called from the host yet",
+            )),
+        }
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportFunction) -> Self {
         Self {
             store: store.clone(),
@@ -731,20 +927,40 @@ impl fmt::Debug for Function {
     }
 }
 
+/// The future returned by an asynchronous host function.
+pub(crate) type HostFuture = Pin<Box<dyn Future<Output = Result<Vec<Val>, RuntimeError>> + Send>>;
+
 /// This trait is one that all dynamic functions must fulfill.
 pub(crate) trait VMDynamicFunction: Send + Sync {
     fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError>;
+    fn call_async(&self, args: &[Val]) -> HostFuture;
+    fn is_async(&self) -> bool;
     fn function_type(&self) -> &FunctionType;
     fn store(&self) -> &Store;
 }
 
+/// The host function called by a dynamic function.
+#[allow(clippy::type_complexity)]
+pub(crate) enum DynamicCallback<Env> {
+    Sync(Arc<dyn Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync>),
+    Async(Arc<dyn Fn(&Env, &[Val]) -> HostFuture + 'static + Send + Sync>),
+}
+
+impl<Env> Clone for DynamicCallback<Env> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Sync(func) => Self::Sync(func.clone()),
+            Self::Async(func) => Self::Async(func.clone()),
+        }
+    }
+}
+
 pub(crate) struct DynamicFunction<Env>
 where
     Env: Sized + 'static + Send + Sync,
 {
     function_type: FunctionType,
-    #[allow(clippy::type_complexity)]
-    func: Arc<dyn Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync>,
+    func: DynamicCallback<Env>,
     store: Store,
     env: Box<Env>,
 }
@@ -765,7 +981,21 @@ where
     Env: Sized + 'static + Send + Sync,
 {
     fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError> {
-        (*self.func)(&*self.env, args)
+        match &self.func {
+            DynamicCallback::Sync(func) => func(&*self.env, args),
+            DynamicCallback::Async(_) => Err(RuntimeError::new(
+                "asynchronous host functions can only be called asynchronously",
+            )),
+        }
+    }
+    fn call_async(&self, args: &[Val]) -> HostFuture {
+        match &self.func {
+            DynamicCallback::Sync(func) => Box::pin(std::future::ready(func(&*self.env, args))),
+            DynamicCallback::Async(func) => func(&*self.env, args),
+        }
+    }
+    fn is_async(&self) -> bool {
+        matches!(self.func, DynamicCallback::Async(_))
     }
     fn function_type(&self) -> &FunctionType {
         &self.function_type
@@ -802,15 +1032,46 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
         values_vec: *mut i128,
     ) {
         use std::panic::{self, AssertUnwindSafe};
-        let result = on_host_stack(|| {
+        let func_ty = self.ctx.function_type();
+        let started = on_host_stack(|| {
             panic::catch_unwind(AssertUnwindSafe(|| {
-                let func_ty = self.ctx.function_type();
                 let mut args = Vec::with_capacity(func_ty.params().len());
                 let store = self.ctx.store();
                 for (i, ty) in func_ty.params().iter().enumerate() {
                     args.push(Val::read_value_from(store, values_vec.add(i), *ty));
                 }
-                let returns = self.ctx.call(&args)?;
+                if self.ctx.is_async() {
+                    Err(self.ctx.call_async(&args))
+                } else {
+                    Ok(self.ctx.call(&args))
+                }
+            }))
+        });
+        let returns = match started {
+            Ok(Ok(returns)) => returns,
+            Ok(Err(mut future)) => {
+                // Polled on the host stack, the Wasm stack is suspended while
+                // the future is pending.
+                let returns = block_on_host(move |cx| {
+                    match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                        Ok(poll) => poll.map(Ok),
+                        Err(panic) => Poll::Ready(Err(panic)),
+                    }
+                });
+                match returns {
+                    Some(Ok(returns)) => returns,
+                    Some(Err(panic)) => resume_panic(panic),
+                    None => Err(RuntimeError::new(
+                        "asynchronous host functions can only be called asynchronously",
+                    )),
+                }
+            }
+            Err(panic) => resume_panic(panic),
+        };
+
+        let result = on_host_stack(|| {
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                let returns = returns?;
 
                 // We need to dynamically check that the returns
                 // match the expected types, as well as expected length.
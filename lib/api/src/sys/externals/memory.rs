@@ -1,5 +1,5 @@
 use crate::sys::exports::{ExportError, Exportable};
-use crate::sys::externals::Extern;
+use crate::sys::externals::{Extern, SharedMemory};
 use crate::sys::store::Store;
 use crate::sys::MemoryType;
 use crate::MemoryAccessError;
@@ -10,7 +10,7 @@ use std::slice;
 use std::sync::Arc;
 use wasmer_compiler::Export;
 use wasmer_types::Pages;
-use wasmer_vm::{MemoryError, VMMemory};
+use wasmer_vm::{MemoryError, VMMemory, WaitQueue};
 
 /// A WebAssembly `memory` instance.
 ///
@@ -166,6 +166,30 @@ impl Memory {
         self.vm_memory.from.grow(delta.into())
     }
 
+    /// Returns the memory as a [`SharedMemory`], or `None` if its type isn't
+    /// shared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, Some(1), true)).unwrap();
+    /// assert!(m.as_shared().is_some());
+    ///
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// assert!(m.as_shared().is_none());
+    /// ```
+    pub fn as_shared(&self) -> Option<SharedMemory> {
+        SharedMemory::from_memory(self.clone())
+    }
+
+    /// Returns the queue of threads waiting on this memory, if it's shared.
+    pub(crate) fn wait_queue(&self) -> Option<&WaitQueue> {
+        self.vm_memory.from.wait_queue()
+    }
+
     pub(crate) fn from_vm_export(store: &Store, vm_memory: VMMemory) -> Self {
         Self {
             store: store.clone(),
@@ -0,0 +1,244 @@
+use crate::sys::externals::{Extern, Memory};
+use crate::sys::store::Store;
+use crate::sys::MemoryType;
+use crate::MemoryAccessError;
+use std::convert::TryFrom;
+use std::mem;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+use wasmer_types::Pages;
+use wasmer_vm::{MemoryError, WaitResult};
+
+/// A WebAssembly `memory` that can be shared by several instances, running on
+/// different threads.
+///
+/// A shared memory has a [`MemoryType`] marked as `shared` and must declare a
+/// maximum size. Its address space is reserved up to this maximum when it's
+/// created, so that it never moves when it grows.
+///
+/// Besides the accessors of [`Memory`], a shared memory offers atomic
+/// accesses, and can wait and notify like `memory.atomic.wait32` and
+/// `memory.atomic.notify` do in WebAssembly.
+///
+/// Spec: <https://github.com/WebAssembly/threads/blob/main/proposals/threads/Overview.md>
+#[derive(Debug, Clone)]
+pub struct SharedMemory {
+    memory: Memory,
+}
+
+impl SharedMemory {
+    /// Creates a new host `SharedMemory` from the provided [`MemoryType`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{MemoryType, SharedMemory, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = SharedMemory::new(&store, MemoryType::new(1, Some(16), true)).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory type isn't shared or has no maximum.
+    pub fn new(store: &Store, ty: MemoryType) -> Result<Self, MemoryError> {
+        if !ty.shared {
+            return Err(MemoryError::InvalidMemory {
+                reason: "the memory type isn't shared".to_string(),
+            });
+        }
+        Ok(Self {
+            memory: Memory::new(store, ty)?,
+        })
+    }
+
+    /// Returns `memory` as a `SharedMemory`, or `None` if its type isn't
+    /// shared.
+    pub(crate) fn from_memory(memory: Memory) -> Option<Self> {
+        if memory.ty().shared {
+            Some(Self { memory })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the underlying [`Memory`], to import it or access its data.
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Returns the [`MemoryType`] of the `SharedMemory`.
+    pub fn ty(&self) -> MemoryType {
+        self.memory.ty()
+    }
+
+    /// Returns the size (in [`Pages`]) of the `SharedMemory`.
+    pub fn size(&self) -> Pages {
+        self.memory.size()
+    }
+
+    /// Grow memory by the specified amount of WebAssembly [`Pages`] and return
+    /// the previous memory size.
+    ///
+    /// The memory doesn't move, other threads can keep accessing it while it
+    /// grows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory would grow beyond its maximum.
+    pub fn grow<IntoPages>(&self, delta: IntoPages) -> Result<Pages, MemoryError>
+    where
+        IntoPages: Into<Pages>,
+    {
+        self.memory.grow(delta)
+    }
+
+    /// Returns a pointer to the `T` at `offset`, checking that it's in bounds
+    /// and aligned.
+    fn atomic_ptr<T>(&self, offset: u64) -> Result<*const T, MemoryAccessError> {
+        let size = mem::size_of::<T>() as u64;
+        let end = offset
+            .checked_add(size)
+            .ok_or(MemoryAccessError::Overflow)?;
+        if end > self.memory.data_size() {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        if offset % size != 0 {
+            return Err(MemoryAccessError::Unaligned);
+        }
+        // The memory never moves, so the pointer stays valid until the memory
+        // is dropped.
+        Ok(unsafe { self.memory.data_ptr().add(offset as usize) } as *const T)
+    }
+
+    /// Atomically loads the `u32` at `offset`.
+    pub fn atomic_load32(&self, offset: u64) -> Result<u32, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU32>(offset)?;
+        Ok(unsafe { (*ptr).load(Ordering::SeqCst) })
+    }
+
+    /// Atomically loads the `u64` at `offset`.
+    pub fn atomic_load64(&self, offset: u64) -> Result<u64, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU64>(offset)?;
+        Ok(unsafe { (*ptr).load(Ordering::SeqCst) })
+    }
+
+    /// Atomically stores `value` at `offset`.
+    pub fn atomic_store32(&self, offset: u64, value: u32) -> Result<(), MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU32>(offset)?;
+        unsafe { (*ptr).store(value, Ordering::SeqCst) };
+        Ok(())
+    }
+
+    /// Atomically stores `value` at `offset`.
+    pub fn atomic_store64(&self, offset: u64, value: u64) -> Result<(), MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU64>(offset)?;
+        unsafe { (*ptr).store(value, Ordering::SeqCst) };
+        Ok(())
+    }
+
+    /// Atomically adds `value` to the `u32` at `offset`, wrapping around on
+    /// overflow, and returns the previous value.
+    pub fn atomic_add32(&self, offset: u64, value: u32) -> Result<u32, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU32>(offset)?;
+        Ok(unsafe { (*ptr).fetch_add(value, Ordering::SeqCst) })
+    }
+
+    /// Atomically adds `value` to the `u64` at `offset`, wrapping around on
+    /// overflow, and returns the previous value.
+    pub fn atomic_add64(&self, offset: u64, value: u64) -> Result<u64, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU64>(offset)?;
+        Ok(unsafe { (*ptr).fetch_add(value, Ordering::SeqCst) })
+    }
+
+    /// Atomically replaces the `u32` at `offset` with `new` if it's equal to
+    /// `current`, and returns the previous value.
+    pub fn atomic_compare_exchange32(
+        &self,
+        offset: u64,
+        current: u32,
+        new: u32,
+    ) -> Result<u32, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU32>(offset)?;
+        let previous =
+            unsafe { (*ptr).compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst) };
+        Ok(previous.unwrap_or_else(|previous| previous))
+    }
+
+    /// Atomically replaces the `u64` at `offset` with `new` if it's equal to
+    /// `current`, and returns the previous value.
+    pub fn atomic_compare_exchange64(
+        &self,
+        offset: u64,
+        current: u64,
+        new: u64,
+    ) -> Result<u64, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU64>(offset)?;
+        let previous =
+            unsafe { (*ptr).compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst) };
+        Ok(previous.unwrap_or_else(|previous| previous))
+    }
+
+    /// Blocks the current thread until it's notified on `offset`, if the `u32`
+    /// there is `expected`, like `memory.atomic.wait32`.
+    ///
+    /// With no `timeout` the thread waits forever.
+    pub fn atomic_wait32(
+        &self,
+        offset: u64,
+        expected: u32,
+        timeout: Option<Duration>,
+    ) -> Result<WaitResult, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU32>(offset)?;
+        let matches = || unsafe { (*ptr).load(Ordering::SeqCst) == expected };
+        Ok(self.wait_queue().wait(address(offset), matches, timeout))
+    }
+
+    /// Blocks the current thread until it's notified on `offset`, if the `u64`
+    /// there is `expected`, like `memory.atomic.wait64`.
+    ///
+    /// With no `timeout` the thread waits forever.
+    pub fn atomic_wait64(
+        &self,
+        offset: u64,
+        expected: u64,
+        timeout: Option<Duration>,
+    ) -> Result<WaitResult, MemoryAccessError> {
+        let ptr = self.atomic_ptr::<AtomicU64>(offset)?;
+        let matches = || unsafe { (*ptr).load(Ordering::SeqCst) == expected };
+        Ok(self.wait_queue().wait(address(offset), matches, timeout))
+    }
+
+    /// Wakes up to `count` threads waiting on `offset`, like
+    /// `memory.atomic.notify`, and returns how many were woken.
+    ///
+    /// Threads waiting from WebAssembly and from the host are both woken.
+    pub fn atomic_notify(&self, offset: u64, count: u32) -> Result<u32, MemoryAccessError> {
+        self.atomic_ptr::<AtomicU32>(offset)?;
+        Ok(self.wait_queue().notify(address(offset), count))
+    }
+
+    fn wait_queue(&self) -> &wasmer_vm::WaitQueue {
+        self.memory
+            .wait_queue()
+            .expect("shared memories can be waited on")
+    }
+}
+
+/// Converts an offset to the address waiters are keyed by, the offset is in
+/// bounds so it fits in the 32-bit address space.
+fn address(offset: u64) -> u32 {
+    u32::try_from(offset).unwrap()
+}
+
+impl From<SharedMemory> for Memory {
+    fn from(shared: SharedMemory) -> Self {
+        shared.memory
+    }
+}
+
+impl From<SharedMemory> for Extern {
+    fn from(shared: SharedMemory) -> Self {
+        Self::Memory(shared.memory)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::sys::exports::{ExportError, Exportable};
+use crate::sys::externals::Extern;
+use crate::sys::store::Store;
+use crate::sys::TagType;
+use std::fmt;
+use std::sync::Arc;
+use wasmer_compiler::Export;
+use wasmer_vm::{Tag as RuntimeTag, VMTag};
+
+/// A WebAssembly `tag` instance.
+///
+/// A tag is what `throw` and `catch` match exceptions with: a `catch`
+/// only handles the exceptions thrown with its own tag, even if another
+/// tag has the same type.
+///
+/// Spec: <https://webassembly.github.io/exception-handling/core/exec/runtime.html#tag-instances>
+pub struct Tag {
+    store: Store,
+    vm_tag: VMTag,
+}
+
+impl Tag {
+    /// Create a new `Tag` of the given type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Store, Tag, TagType, Type};
+    /// # let store = Store::default();
+    /// #
+    /// let tag = Tag::new(&store, TagType::new([Type::I32]));
+    ///
+    /// assert_eq!(tag.ty().params(), &[Type::I32]);
+    /// ```
+    pub fn new(store: &Store, ty: TagType) -> Self {
+        Self {
+            store: store.clone(),
+            vm_tag: VMTag {
+                from: Arc::new(RuntimeTag::new(ty)),
+                instance_ref: None,
+            },
+        }
+    }
+
+    /// Returns the [`TagType`] of the `Tag`.
+    pub fn ty(&self) -> &TagType {
+        self.vm_tag.ty()
+    }
+
+    /// Returns the [`Store`] where the `Tag` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub(crate) fn from_vm_export(store: &Store, vm_tag: VMTag) -> Self {
+        Self {
+            store: store.clone(),
+            vm_tag,
+        }
+    }
+
+    /// Returns whether or not these two tags refer to the same data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Store, Tag, TagType, Type};
+    /// # let store = Store::default();
+    /// #
+    /// let t = Tag::new(&store, TagType::new([Type::I32]));
+    /// let u = Tag::new(&store, TagType::new([Type::I32]));
+    ///
+    /// assert!(t.same(&t));
+    /// assert!(!t.same(&u));
+    /// ```
+    pub fn same(&self, other: &Self) -> bool {
+        self.vm_tag.same(&other.vm_tag)
+    }
+}
+
+impl Clone for Tag {
+    fn clone(&self) -> Self {
+        let mut vm_tag = self.vm_tag.clone();
+        vm_tag.upgrade_instance_ref().unwrap();
+
+        Self {
+            store: self.store.clone(),
+            vm_tag,
+        }
+    }
+}
+
+impl fmt::Debug for Tag {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Tag")
+            .field("ty", &self.ty())
+            .finish()
+    }
+}
+
+impl<'a> Exportable<'a> for Tag {
+    fn to_export(&self) -> Export {
+        self.vm_tag.clone().into()
+    }
+
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Tag(tag) => Ok(tag),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+
+    fn convert_to_weak_instance_ref(&mut self) {
+        if let Some(v) = self.vm_tag.instance_ref.as_mut() {
+            *v = v.downgrade();
+        }
+    }
+}
@@ -216,6 +216,45 @@ impl Instance {
         self.module.store()
     }
 
+    /// Returns the fuel left to this instance, or `None` if its module
+    /// wasn't compiled with fuel metering.
+    ///
+    /// See [`CompilerConfig::fuel_metering`].
+    ///
+    /// [`CompilerConfig::fuel_metering`]: crate::CompilerConfig::fuel_metering
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.module.info().initial_fuel?;
+        Some(self.handle.lock().unwrap().remaining_fuel())
+    }
+
+    /// Whether this instance trapped because it ran out of fuel.
+    ///
+    /// It can run again once it's given more fuel with
+    /// [`Instance::add_fuel`] or [`Instance::set_fuel`].
+    pub fn fuel_exhausted(&self) -> bool {
+        self.module.info().initial_fuel.is_some() && self.handle.lock().unwrap().fuel_exhausted()
+    }
+
+    /// Sets the fuel left to this instance.
+    ///
+    /// This has no effect if its module wasn't compiled with fuel metering.
+    pub fn set_fuel(&self, fuel: u64) {
+        self.handle.lock().unwrap().set_fuel(fuel)
+    }
+
+    /// Adds `fuel` to the fuel left to this instance.
+    ///
+    /// This has no effect if its module wasn't compiled with fuel metering.
+    ///
+    /// The fuel is added in a single atomic update, so concurrent calls
+    /// don't lose fuel. The running code consumes its fuel without
+    /// synchronizing with the host though, so fuel added while the
+    /// instance runs on another thread may be lost; add it between calls
+    /// into the instance.
+    pub fn add_fuel(&self, fuel: u64) {
+        self.handle.lock().unwrap().add_fuel(fuel)
+    }
+
     #[doc(hidden)]
     pub fn vmctx_ptr(&self) -> *mut VMContext {
         self.handle.lock().unwrap().vmctx_ptr()
@@ -25,6 +25,9 @@ pub enum MemoryAccessError {
     /// String is not valid UTF-8.
     #[error("string is not valid utf-8")]
     NonUtf8String,
+    /// Atomic memory access isn't aligned to its size.
+    #[error("unaligned atomic memory access")]
+    Unaligned,
 }
 
 impl From<MemoryAccessError> for RuntimeError {
@@ -29,7 +29,8 @@ pub mod internals {
 pub use crate::sys::env::{HostEnvInitError, LazyInit, WasmerEnv};
 pub use crate::sys::exports::{ExportError, Exportable, Exports, ExportsIterator};
 pub use crate::sys::externals::{
-    Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
+    Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, SharedMemory, Table, Tag,
+    WasmTypeList,
 };
 pub use crate::sys::imports::Imports;
 pub use crate::sys::instance::{Instance, InstantiationError};
@@ -39,16 +40,17 @@ pub use crate::sys::native::TypedFunction;
 
 pub use crate::sys::ptr::{Memory32, Memory64, MemorySize, WasmPtr, WasmPtr64};
 pub use crate::sys::store::{Store, StoreObject};
-pub use crate::sys::tunables::BaseTunables;
+pub use crate::sys::tunables::{BaseTunables, PoolingTunables};
 pub use crate::sys::types::{
     ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
-    TableType, Val, ValType,
+    TableType, TagType, Val, ValType,
 };
 pub use crate::sys::types::{Val as Value, ValType as Type};
 pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
 #[cfg(feature = "compiler")]
 pub use wasmer_compiler::{
-    wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareReaderState, ModuleMiddleware,
+    wasmparser, CompilerConfig, FuelMetering, FunctionMiddleware, MiddlewareReaderState,
+    ModuleMiddleware,
 };
 pub use wasmer_compiler::{
     CpuFeature, Engine, Export, Features, FrameInfo, LinkError, RuntimeError, Target, Tunables,
@@ -64,7 +66,10 @@ pub use wasmer_types::{
 };
 
 // TODO: should those be moved into wasmer::vm as well?
-pub use wasmer_vm::{raise_user_trap, MemoryError};
+pub use wasmer_vm::{
+    raise_user_trap, EpochCounter, EpochDeadline, MemoryError, PoolingAllocator, PoolingLimits,
+    WaitResult,
+};
 pub mod vm {
     //! The `vm` module re-exports wasmer-vm types.
 
@@ -287,6 +287,8 @@ impl Module {
                     .map(crate::Extern::to_export)
                     .collect::<Vec<_>>(),
                 Box::new(self.clone()),
+                self.store.engine().epoch_counter().clone(),
+                self.store.epoch_deadline().clone(),
             )?;
 
             // After the instance handle is created, we need to initialize
@@ -423,6 +425,19 @@ impl Module {
         &self.store
     }
 
+    /// Returns the module bound to `store` instead, sharing its compiled
+    /// contents, so that its instances use the tunables, trap handler and
+    /// epoch deadline of `store`.
+    ///
+    /// Returns `None` if `store` doesn't have the engine the module was
+    /// compiled with.
+    pub fn with_store(&self, store: &Store) -> Option<Self> {
+        if !Store::same(&self.store, store) {
+            return None;
+        }
+        Some(Self::from_artifact(store, self.artifact.clone()))
+    }
+
     /// The ABI of the ModuleInfo is very unstable, we refactor it very often.
     /// This function is public because in some cases it can be useful to get some
     /// extra information from the module.
@@ -221,6 +221,67 @@ macro_rules! impl_native_traits {
                 }
             }
 
+            /// Call the typed func asynchronously and return results.
+            ///
+            /// See [`Function::call_async`].
+            #[allow(clippy::too_many_arguments)]
+            pub async fn call_async(&self, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                if self.is_host() {
+                    if let VMFunctionKind::Static = self.arg_kind() {
+                        return self.call($( $x, )*);
+                    }
+                    let params_list = [ $( $x.to_native().to_value() ),* ];
+                    let results = {
+                        type VMContextWithEnv = VMDynamicFunctionContext<DynamicFunction<std::ffi::c_void>>;
+                        unsafe {
+                            let ctx = self.vmctx().host_env as *mut VMContextWithEnv;
+                            (*ctx).ctx.call_async(&params_list)
+                        }
+                    }.await?;
+                    let mut rets_list_array = Rets::empty_array();
+                    let mut_rets = rets_list_array.as_mut() as *mut [i128] as *mut i128;
+                    for (i, ret) in results.iter().enumerate() {
+                        unsafe {
+                            ret.write_value_to(mut_rets.add(i));
+                        }
+                    }
+                    return Ok(Rets::from_array(rets_list_array));
+                }
+
+                // We assume the trampoline is always going to be present for
+                // Wasm functions
+                let trampoline = self.exported.vm_function.call_trampoline.expect("Call trampoline not found in wasm function");
+                let mut params_list = [ $( $x.to_native().to_binary() ),* ];
+                let mut rets_list_array = Rets::empty_array();
+                let num_rets = rets_list_array.as_mut().len();
+                let using_rets_array = params_list.len() <= num_rets;
+                if using_rets_array {
+                    let rets_list = rets_list_array.as_mut();
+                    for (i, &arg) in params_list.iter().enumerate() {
+                        rets_list[i] = arg;
+                    }
+                }
+                let args_rets = if using_rets_array {
+                    rets_list_array.as_mut().as_mut_ptr()
+                } else {
+                    params_list.as_mut().as_mut_ptr()
+                };
+                // The arrays outlive the call.
+                let call = unsafe {
+                    wasmer_vm::wasmer_call_trampoline_async(
+                        Box::new(self.store.clone()),
+                        self.vmctx(),
+                        trampoline,
+                        self.address(),
+                        args_rets as *mut u8,
+                    )
+                };
+                call.await?;
+                if !using_rets_array && num_rets > 0 {
+                    rets_list_array.as_mut().copy_from_slice(&params_list[..num_rets]);
+                }
+                Ok(Rets::from_array(rets_list_array))
+            }
         }
 
         #[allow(unused_parens)]
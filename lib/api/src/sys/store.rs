@@ -3,7 +3,7 @@ use std::fmt;
 use std::sync::{Arc, RwLock};
 use wasmer_compiler::CompilerConfig;
 use wasmer_compiler::{Engine, Tunables, Universal};
-use wasmer_vm::{init_traps, TrapHandler, TrapHandlerFn};
+use wasmer_vm::{init_traps, EpochDeadline, TrapHandler, TrapHandlerFn};
 
 /// The store represents all global state that can be manipulated by
 /// WebAssembly programs. It consists of the runtime representation
@@ -20,6 +20,7 @@ pub struct Store {
     engine: Arc<dyn Engine + Send + Sync>,
     tunables: Arc<dyn Tunables + Send + Sync>,
     trap_handler: Arc<RwLock<Option<Box<TrapHandlerFn>>>>,
+    epoch_deadline: EpochDeadline,
 }
 
 impl Store {
@@ -43,6 +44,27 @@ impl Store {
         *m = handler;
     }
 
+    /// Interrupts the wasm running in this store once the engine's epoch
+    /// has advanced `ticks_beyond_current` times from now.
+    ///
+    /// Only code compiled with epoch interruption enabled (see
+    /// [`CompilerConfig::epoch_interruption`]) checks the deadline. It
+    /// traps with `TrapCode::Interrupt` when the deadline is reached.
+    /// The epoch is advanced with [`EpochCounter::increment`] on the
+    /// counter returned by [`Engine::epoch_counter`].
+    ///
+    /// [`EpochCounter::increment`]: crate::EpochCounter::increment
+    pub fn set_epoch_deadline(&self, ticks_beyond_current: u64) {
+        let current = self.engine.epoch_counter().get();
+        self.epoch_deadline
+            .set(current.saturating_add(ticks_beyond_current));
+    }
+
+    /// Returns the deadline checked by the instances of this store.
+    pub(crate) fn epoch_deadline(&self) -> &EpochDeadline {
+        &self.epoch_deadline
+    }
+
     /// Creates a new `Store` with a specific [`Engine`] and [`Tunables`].
     pub fn new_with_tunables<E>(engine: &E, tunables: impl Tunables + Send + Sync + 'static) -> Self
     where
@@ -56,6 +78,7 @@ impl Store {
             engine: engine.cloned(),
             tunables: Arc::new(tunables),
             trap_handler: Arc::new(RwLock::new(None)),
+            epoch_deadline: EpochDeadline::new(),
         }
     }
 
@@ -5,8 +5,8 @@ use target_lexicon::PointerWidth;
 use wasmer_compiler::{Target, Tunables};
 use wasmer_vm::MemoryError;
 use wasmer_vm::{
-    LinearMemory, LinearTable, Memory, MemoryStyle, Table, TableStyle, VMMemoryDefinition,
-    VMTableDefinition,
+    LinearMemory, LinearTable, Memory, MemoryStyle, PoolingAllocator, PoolingLimits, Table,
+    TableStyle, VMMemoryDefinition, VMTableDefinition,
 };
 
 /// Tunable parameters for WebAssembly compilation.
@@ -146,6 +146,118 @@ impl Tunables for BaseTunables {
     }
 }
 
+/// Tunable parameters that allocate instances, with their memories and
+/// tables, from a [`PoolingAllocator`].
+///
+/// The pool reserves the address space of all its slots up front, so that
+/// instantiating a module doesn't map memory, and caps the number of
+/// instances alive at the same time. Memories and tables created by the host
+/// are allocated by the wrapped `BaseTunables`.
+///
+/// # Example
+///
+/// ```
+/// # use wasmer::{BaseTunables, PoolingLimits, PoolingTunables, Store, Target, Universal, Cranelift};
+/// let base = BaseTunables::for_target(&Target::default());
+/// let limits = PoolingLimits {
+///     instances: 10,
+///     ..PoolingLimits::default()
+/// };
+/// let tunables = PoolingTunables::new(base, limits).unwrap();
+/// let store = Store::new_with_tunables(&Universal::new(Cranelift::default()).engine(), tunables);
+/// ```
+#[derive(Clone)]
+pub struct PoolingTunables {
+    base: BaseTunables,
+    pool: PoolingAllocator,
+}
+
+impl PoolingTunables {
+    /// Creates `PoolingTunables` with a new pool of the given `limits`.
+    pub fn new(base: BaseTunables, limits: PoolingLimits) -> Result<Self, MemoryError> {
+        Ok(Self {
+            base,
+            pool: PoolingAllocator::new(limits)?,
+        })
+    }
+
+    /// Returns the pool the instances are allocated from.
+    pub fn pool(&self) -> &PoolingAllocator {
+        &self.pool
+    }
+}
+
+impl Tunables for PoolingTunables {
+    /// Get the `MemoryStyle` of the pooled memories, whatever the `MemoryType`.
+    fn memory_style(&self, _memory: &MemoryType) -> MemoryStyle {
+        self.pool.memory_style()
+    }
+
+    /// Get a [`TableStyle`] for the provided [`TableType`].
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base.create_host_memory(ty, style)
+    }
+
+    /// Create a memory owned by the VM in a slot of the pool.
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid, owned `VMMemoryDefinition`,
+    ///   for example in `VMContext`.
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(self.pool.create_memory(
+            ty,
+            style,
+            vm_definition_location,
+        )?))
+    }
+
+    /// Create a table owned by the host given a [`TableType`] and a [`TableStyle`].
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    /// Create a table owned by the VM in a slot of the pool.
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid, owned `VMTableDefinition`,
+    ///   for example in `VMContext`.
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        Ok(Arc::new(self.pool.create_table(
+            ty,
+            style,
+            vm_definition_location,
+        )?))
+    }
+
+    /// Allocate the instances from the pool.
+    fn instance_pool(&self) -> Option<&PoolingAllocator> {
+        Some(&self.pool)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,13 @@
 #[cfg(feature = "sys")]
 mod sys {
     use anyhow::Result;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread;
+    use std::time::Duration;
     use wasmer::*;
 
     #[test]
@@ -179,6 +186,138 @@ mod sys {
         Ok(())
     }
 
+    #[test]
+    fn shared_memory_new() -> Result<()> {
+        let store = Store::default();
+
+        let memory = SharedMemory::new(&store, MemoryType::new(1, Some(4), true))?;
+        assert_eq!(memory.size(), Pages(1));
+        assert!(memory.ty().shared);
+        assert!(Memory::from(memory).as_shared().is_some());
+
+        let result = SharedMemory::new(&store, MemoryType::new(1, Some(4), false));
+        assert!(matches!(result, Err(MemoryError::InvalidMemory { .. })));
+        let result = SharedMemory::new(&store, MemoryType::new(1, None, true));
+        assert!(matches!(result, Err(MemoryError::InvalidMemory { .. })));
+
+        let memory = Memory::new(&store, MemoryType::new(1, Some(4), false))?;
+        assert!(memory.as_shared().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_memory_grow() -> Result<()> {
+        // A tiny static bound makes the memory dynamic, which is when it
+        // would otherwise move as it grows.
+        let tunables = BaseTunables {
+            static_memory_bound: Pages(1),
+            static_memory_offset_guard_size: 0x1_0000,
+            dynamic_memory_offset_guard_size: 0x1_0000,
+        };
+        let store =
+            Store::new_with_tunables(&Universal::new(Cranelift::default()).engine(), tunables);
+
+        let memory = SharedMemory::new(&store, MemoryType::new(1, Some(100), true))?;
+        memory.atomic_store32(0, 42)?;
+        let data_ptr = memory.memory().data_ptr();
+        assert_eq!(memory.grow(99)?, Pages(1));
+        assert_eq!(memory.size(), Pages(100));
+        assert_eq!(memory.memory().data_ptr(), data_ptr);
+        assert_eq!(memory.atomic_load32(0)?, 42);
+        assert!(memory.grow(1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_memory_atomics() -> Result<()> {
+        let store = Store::default();
+        let memory = SharedMemory::new(&store, MemoryType::new(1, Some(1), true))?;
+
+        memory.atomic_store32(4, 1)?;
+        assert_eq!(memory.atomic_add32(4, 2)?, 1);
+        assert_eq!(memory.atomic_compare_exchange32(4, 0, 7)?, 3);
+        assert_eq!(memory.atomic_compare_exchange32(4, 3, 7)?, 3);
+        assert_eq!(memory.atomic_load32(4)?, 7);
+
+        memory.atomic_store64(8, u64::MAX)?;
+        assert_eq!(memory.atomic_add64(8, 2)?, u64::MAX);
+        assert_eq!(memory.atomic_load64(8)?, 1);
+        assert_eq!(memory.atomic_compare_exchange64(8, 1, 5)?, 1);
+        assert_eq!(memory.atomic_load64(8)?, 5);
+
+        assert!(matches!(
+            memory.atomic_load32(2),
+            Err(MemoryAccessError::Unaligned)
+        ));
+        assert!(matches!(
+            memory.atomic_load64(4),
+            Err(MemoryAccessError::Unaligned)
+        ));
+        assert!(matches!(
+            memory.atomic_load32(65536),
+            Err(MemoryAccessError::HeapOutOfBounds)
+        ));
+        assert!(matches!(
+            memory.atomic_notify(u64::MAX - 1, 1),
+            Err(MemoryAccessError::Overflow)
+        ));
+        assert_eq!(memory.atomic_wait32(4, 0, None)?, WaitResult::NotEqual);
+        assert_eq!(
+            memory.atomic_wait64(8, 5, Some(Duration::from_millis(1)))?,
+            WaitResult::TimedOut
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shared_memory_across_instances() -> Result<()> {
+        let mut features = Features::default();
+        features.threads(true);
+        let store = Store::new_with_engine(
+            &Universal::new(Cranelift::default())
+                .features(features)
+                .engine(),
+        );
+        let module = Module::new(
+            &store,
+            r#"
+    (module
+      (import "env" "memory" (memory 1 1 shared))
+      (func (export "wait") (param $addr i32) (result i32)
+        (memory.atomic.wait32 (local.get $addr) (i32.const 0) (i64.const -1)))
+      (func (export "increment") (param $addr i32) (result i32)
+        (i32.atomic.rmw.add (local.get $addr) (i32.const 1))))
+"#,
+        )?;
+        let memory = SharedMemory::new(&store, MemoryType::new(1, Some(1), true))?;
+        let imports = imports! {
+            "env" => {
+                "memory" => memory.clone(),
+            },
+        };
+        let first = Instance::new(&module, &imports)?;
+        let second = Instance::new(&module, &imports)?;
+
+        let increment = first.exports.get_native_function::<i32, i32>("increment")?;
+        assert_eq!(increment.call(0)?, 0);
+        let increment = second
+            .exports
+            .get_native_function::<i32, i32>("increment")?;
+        assert_eq!(increment.call(0)?, 1);
+        assert_eq!(memory.atomic_load32(0)?, 2);
+
+        // A thread waiting in WebAssembly is woken by the host.
+        let wait = second.exports.get_native_function::<i32, i32>("wait")?;
+        let waiter = thread::spawn(move || wait.call(8).unwrap());
+        let mut woken = 0;
+        while woken == 0 {
+            woken = memory.atomic_notify(8, 1)?;
+            thread::yield_now();
+        }
+        assert_eq!(waiter.join().unwrap(), WaitResult::Woken as i32);
+        Ok(())
+    }
+
     #[test]
     fn function_new() -> Result<()> {
         let store = Store::default();
@@ -381,6 +520,144 @@ mod sys {
         Ok(())
     }
 
+    /// A future that is pending the first time it's polled.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    fn async_instance(store: &Store, dropped: Arc<AtomicBool>) -> Result<Instance> {
+        let wat = r#"(module
+            (import "host" "double" (func $double (param i32) (result i32)))
+            (func (export "quadruple") (param i32) (result i32)
+                local.get 0
+                call $double
+                call $double))"#;
+        let module = Module::new(store, wat)?;
+        let double = Function::new_async(store, ([Type::I32], [Type::I32]), move |args| {
+            let dropped = dropped.clone();
+            async move {
+                let _flag = DropFlag(dropped);
+                YieldOnce(false).await;
+                Ok(vec![Value::I32(args[0].unwrap_i32() * 2)])
+            }
+        });
+        let imports = imports! { "host" => { "double" => double } };
+        Ok(Instance::new(&module, &imports)?)
+    }
+
+    /// Sets the flag when it's dropped.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn function_call_async() -> Result<()> {
+        let store = Store::default();
+        let instance = async_instance(&store, Arc::new(AtomicBool::new(false)))?;
+        let quadruple = instance.exports.get_function("quadruple")?.clone();
+
+        let mut call = Box::pin(async move { quadruple.call_async(&[Value::I32(3)]).await });
+        assert!(poll_once(&mut call).is_pending());
+        // The suspended call can be resumed on another thread.
+        let result = thread::spawn(move || loop {
+            if let Poll::Ready(result) = poll_once(&mut call) {
+                break result;
+            }
+        })
+        .join()
+        .unwrap()?;
+        assert_eq!(result.to_vec(), vec![Value::I32(12)]);
+
+        let quadruple: TypedFunction<i32, i32> =
+            instance.exports.get_native_function("quadruple")?;
+        let mut call = Box::pin(quadruple.call_async(5));
+        let mut polls = 0;
+        let result = loop {
+            polls += 1;
+            if let Poll::Ready(result) = poll_once(&mut call) {
+                break result;
+            }
+        };
+        assert_eq!(result?, 20);
+        assert_eq!(polls, 3, "each host call is pending once");
+
+        Ok(())
+    }
+
+    #[test]
+    fn function_call_async_cancel() -> Result<()> {
+        let store = Store::default();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let instance = async_instance(&store, dropped.clone())?;
+        let quadruple = instance.exports.get_function("quadruple")?;
+
+        let mut call = Box::pin(quadruple.call_async(&[Value::I32(3)]));
+        assert!(poll_once(&mut call).is_pending());
+        assert!(!dropped.load(Ordering::SeqCst));
+        drop(call);
+        assert!(dropped.load(Ordering::SeqCst), "the host future is dropped");
+
+        Ok(())
+    }
+
+    #[test]
+    fn async_host_function_in_sync_call() -> Result<()> {
+        let store = Store::default();
+        let instance = async_instance(&store, Arc::new(AtomicBool::new(false)))?;
+        let quadruple = instance.exports.get_function("quadruple")?;
+
+        let error = quadruple.call(&[Value::I32(3)]).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "asynchronous host functions can only be called asynchronously"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn static_host_function_call_async() -> Result<()> {
+        let store = Store::default();
+        let function = Function::new_native(&store, || -> i32 { 1 });
+
+        let mut call = Box::pin(function.call_async(&[]));
+        match poll_once(&mut call) {
+            Poll::Ready(result) => assert_eq!(
+                result.unwrap_err().message(),
+                "Native function definitions can't be directly called from the host yet"
+            ),
+            Poll::Pending => panic!("the call isn't ready"),
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn function_outlives_instance() -> Result<()> {
         let store = Store::default();
@@ -67,4 +67,102 @@ mod sys {
 
         Ok(())
     }
+
+    #[test]
+    fn pooled_instances() -> Result<()> {
+        let limits = PoolingLimits {
+            instances: 2,
+            memory_pages: Pages(16),
+            memory_offset_guard_size: 0x1_0000,
+            ..PoolingLimits::default()
+        };
+        let tunables = PoolingTunables::new(BaseTunables::for_target(&Target::default()), limits)?;
+        let pool = tunables.pool().clone();
+        let store =
+            Store::new_with_tunables(&Universal::new(Cranelift::default()).engine(), tunables);
+        let module = Module::new(
+            &store,
+            "
+    (module
+      (memory (export \"memory\") 1)
+      (table 2 funcref)
+      (func (export \"load\") (result i32)
+        (i32.load (i32.const 16)))
+      (func (export \"store\") (param i32)
+        (i32.store (i32.const 16) (local.get 0))))
+",
+        )?;
+
+        let imports = Imports::new();
+        let first = Instance::new(&module, &imports)?;
+        let store_fn = first.exports.get_native_function::<i32, ()>("store")?;
+        store_fn.call(42)?;
+        let second = Instance::new(&module, &imports)?;
+        assert_eq!(pool.instances_in_use(), 2);
+        assert!(matches!(
+            Instance::new(&module, &imports),
+            Err(InstantiationError::Link(LinkError::Resource(_)))
+        ));
+
+        let memory = second.exports.get_memory("memory")?;
+        assert_eq!(memory.grow(15)?, Pages(1));
+        assert!(memory.grow(1).is_err(), "memories can't outgrow their slot");
+
+        // The slots of the first instance are reset and reused.
+        drop(store_fn);
+        drop(first);
+        assert_eq!(pool.instances_in_use(), 1);
+        let third = Instance::new(&module, &imports)?;
+        let load = third.exports.get_native_function::<(), i32>("load")?;
+        assert_eq!(load.call()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn instances_get_their_own_initial_memory() -> Result<()> {
+        let limits = PoolingLimits {
+            instances: 1,
+            memory_pages: Pages(16),
+            memory_offset_guard_size: 0x1_0000,
+            ..PoolingLimits::default()
+        };
+        let pooled = PoolingTunables::new(BaseTunables::for_target(&Target::default()), limits)?;
+        let engine = Universal::new(Cranelift::default()).engine();
+        for store in [
+            Store::new_with_engine(&engine),
+            Store::new_with_tunables(&engine, pooled),
+        ] {
+            // Enough data for the memory to be initialized from an image.
+            let module = Module::new(
+                &store,
+                format!(
+                    "
+    (module
+      (memory (export \"memory\") 2)
+      (data (i32.const 65536) \"wasm{}\")
+      (func (export \"load\") (param i32) (result i32)
+        (i32.load (local.get 0)))
+      (func (export \"store\") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1))))
+",
+                    "\\01".repeat(65532)
+                ),
+            )?;
+            let expected = i32::from_le_bytes(*b"wasm");
+
+            for _ in 0..2 {
+                let instance = Instance::new(&module, &Imports::new())?;
+                let load = instance.exports.get_native_function::<i32, i32>("load")?;
+                let store_fn = instance
+                    .exports
+                    .get_native_function::<(i32, i32), ()>("store")?;
+                assert_eq!(load.call(65536)?, expected);
+                assert_eq!(load.call(0)?, 0);
+                store_fn.call(65536, 1)?;
+                store_fn.call(0, 2)?;
+                assert_eq!(load.call(65536)?, 1);
+            }
+        }
+        Ok(())
+    }
 }
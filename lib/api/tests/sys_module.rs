@@ -26,6 +26,22 @@ mod sys {
         Ok(())
     }
 
+    #[test]
+    fn module_with_store() -> Result<()> {
+        let store = Store::default();
+        let module = Module::new(&store, r#"(module $name)"#)?;
+
+        let other_store = store.clone();
+        let rebound = module.with_store(&other_store).unwrap();
+        assert!(Store::same(rebound.store(), &other_store));
+        assert_eq!(rebound.name(), Some("name"));
+
+        // Stores of other engines can't run the module.
+        assert!(module.with_store(&Store::default()).is_none());
+
+        Ok(())
+    }
+
     #[test]
     fn imports() -> Result<()> {
         let store = Store::default();
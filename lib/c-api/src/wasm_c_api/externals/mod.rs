@@ -116,9 +116,11 @@ impl Clone for wasm_extern_t {
     }
 }
 
-impl From<Extern> for wasm_extern_t {
-    fn from(other: Extern) -> Self {
-        match other {
+impl wasm_extern_t {
+    /// Wraps `other`, or returns `None` if it's a tag, as the C API has no
+    /// extern kind for tags.
+    pub(crate) fn new(other: Extern) -> Option<Self> {
+        Some(match other {
             Extern::Function(function) => Self {
                 inner: wasm_extern_inner {
                     function: mem::ManuallyDrop::new(wasm_func_t::new(function)),
@@ -139,7 +141,8 @@ impl From<Extern> for wasm_extern_t {
                     global: mem::ManuallyDrop::new(wasm_global_t::new(global)),
                 },
             },
-        }
+            Extern::Tag(_) => return None,
+        })
     }
 }
 
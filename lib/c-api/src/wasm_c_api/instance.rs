@@ -1,4 +1,4 @@
-use super::externals::wasm_extern_vec_t;
+use super::externals::{wasm_extern_t, wasm_extern_vec_t};
 use super::module::wasm_module_t;
 use super::store::wasm_store_t;
 use super::trap::wasm_trap_t;
@@ -189,7 +189,8 @@ pub unsafe extern "C" fn wasm_instance_exports(
     let extern_vec = instance
         .exports
         .iter()
-        .map(|(_name, r#extern)| Some(Box::new(r#extern.clone().into())))
+        .filter_map(|(_name, r#extern)| wasm_extern_t::new(r#extern.clone()))
+        .map(|r#extern| Some(Box::new(r#extern)))
         .collect();
 
     out.set_buffer(extern_vec);
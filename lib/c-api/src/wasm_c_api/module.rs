@@ -1,5 +1,8 @@
 use super::store::wasm_store_t;
-use super::types::{wasm_byte_vec_t, wasm_exporttype_vec_t, wasm_importtype_vec_t};
+use super::types::{
+    wasm_byte_vec_t, wasm_exporttype_t, wasm_exporttype_vec_t, wasm_importtype_t,
+    wasm_importtype_vec_t,
+};
 use crate::error::update_last_error;
 use std::ptr::NonNull;
 use std::sync::Arc;
@@ -229,7 +232,8 @@ pub unsafe extern "C" fn wasm_module_exports(
     let exports = module
         .inner
         .exports()
-        .map(|export| Some(Box::new(export.into())))
+        .filter_map(|export| wasm_exporttype_t::new(&export))
+        .map(|export| Some(Box::new(export)))
         .collect();
 
     out.set_buffer(exports);
@@ -369,7 +373,8 @@ pub unsafe extern "C" fn wasm_module_imports(
     let imports = module
         .inner
         .imports()
-        .map(|import| Some(Box::new(import.into())))
+        .filter_map(|import| wasm_importtype_t::new(&import))
+        .map(|import| Some(Box::new(import)))
         .collect();
 
     out.set_buffer(imports);
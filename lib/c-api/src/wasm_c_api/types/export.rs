@@ -32,17 +32,13 @@ pub extern "C" fn wasm_exporttype_type(export_type: &wasm_exporttype_t) -> &wasm
     &export_type.extern_type
 }
 
-impl From<ExportType> for wasm_exporttype_t {
-    fn from(other: ExportType) -> Self {
-        (&other).into()
-    }
-}
-
-impl From<&ExportType> for wasm_exporttype_t {
-    fn from(other: &ExportType) -> Self {
+impl wasm_exporttype_t {
+    /// Returns `None` for the export of a tag, as the C API has no extern
+    /// kind for tags.
+    pub(crate) fn new(other: &ExportType) -> Option<Self> {
         let name: wasm_name_t = other.name().to_string().into();
-        let extern_type: wasm_externtype_t = other.ty().into();
+        let extern_type = wasm_externtype_t::new(other.ty().clone())?;
 
-        wasm_exporttype_t { name, extern_type }
+        Some(wasm_exporttype_t { name, extern_type })
     }
 }
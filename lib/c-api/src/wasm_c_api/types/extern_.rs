@@ -1,4 +1,4 @@
-use super::super::externals::wasm_extern_t;
+use super::super::externals::{wasm_extern_t, CApiExternTag};
 use super::{
     wasm_functype_t, wasm_globaltype_t, wasm_memorytype_t, wasm_tabletype_t, WasmFunctionType,
     WasmGlobalType, WasmMemoryType, WasmTableType,
@@ -20,22 +20,6 @@ pub enum wasm_externkind_enum {
     WASM_EXTERN_MEMORY = 3,
 }
 
-impl From<ExternType> for wasm_externkind_enum {
-    fn from(other: ExternType) -> Self {
-        (&other).into()
-    }
-}
-impl From<&ExternType> for wasm_externkind_enum {
-    fn from(other: &ExternType) -> Self {
-        match other {
-            ExternType::Function(_) => Self::WASM_EXTERN_FUNC,
-            ExternType::Global(_) => Self::WASM_EXTERN_GLOBAL,
-            ExternType::Table(_) => Self::WASM_EXTERN_TABLE,
-            ExternType::Memory(_) => Self::WASM_EXTERN_MEMORY,
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 pub(crate) enum WasmExternType {
     Function(WasmFunctionType),
@@ -51,8 +35,9 @@ pub struct wasm_externtype_t {
 }
 
 impl wasm_externtype_t {
-    pub(crate) fn new(extern_type: ExternType) -> Self {
-        Self {
+    /// Returns `None` for a tag, as the C API has no extern kind for tags.
+    pub(crate) fn new(extern_type: ExternType) -> Option<Self> {
+        Some(Self {
             inner: match extern_type {
                 ExternType::Function(function_type) => {
                     WasmExternType::Function(WasmFunctionType::new(function_type))
@@ -66,31 +51,27 @@ impl wasm_externtype_t {
                 ExternType::Memory(memory_type) => {
                     WasmExternType::Memory(WasmMemoryType::new(memory_type))
                 }
+                ExternType::Tag(_) => return None,
             },
-        }
-    }
-}
-
-impl From<ExternType> for wasm_externtype_t {
-    fn from(extern_type: ExternType) -> Self {
-        Self::new(extern_type)
-    }
-}
-
-impl From<&ExternType> for wasm_externtype_t {
-    fn from(other: &ExternType) -> Self {
-        other.clone().into()
+        })
     }
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn wasm_extern_type(r#extern: &wasm_extern_t) -> Box<wasm_externtype_t> {
-    Box::new(wasm_externtype_t::new(r#extern.ty()))
+pub unsafe extern "C" fn wasm_extern_type(
+    r#extern: &wasm_extern_t,
+) -> Option<Box<wasm_externtype_t>> {
+    Some(Box::new(wasm_externtype_t::new(r#extern.ty())?))
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn wasm_extern_kind(r#extern: &wasm_extern_t) -> wasm_externkind_t {
-    wasm_externkind_enum::from(r#extern.ty()) as wasm_externkind_t
+    (match r#extern.get_tag() {
+        CApiExternTag::Function => wasm_externkind_enum::WASM_EXTERN_FUNC,
+        CApiExternTag::Global => wasm_externkind_enum::WASM_EXTERN_GLOBAL,
+        CApiExternTag::Table => wasm_externkind_enum::WASM_EXTERN_TABLE,
+        CApiExternTag::Memory => wasm_externkind_enum::WASM_EXTERN_MEMORY,
+    }) as wasm_externkind_t
 }
 
 #[no_mangle]
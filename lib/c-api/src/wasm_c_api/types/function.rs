@@ -1,6 +1,6 @@
 use super::{wasm_externtype_t, wasm_valtype_vec_t, WasmExternType};
 use std::fmt;
-use wasmer_api::{ExternType, FunctionType, ValType};
+use wasmer_api::{FunctionType, ValType};
 
 pub(crate) struct WasmFunctionType {
     pub(crate) function_type: FunctionType,
@@ -51,7 +51,9 @@ pub struct wasm_functype_t {
 impl wasm_functype_t {
     pub(crate) fn new(function_type: FunctionType) -> Self {
         Self {
-            extern_type: wasm_externtype_t::new(ExternType::Function(function_type)),
+            extern_type: wasm_externtype_t {
+                inner: WasmExternType::Function(WasmFunctionType::new(function_type)),
+            },
         }
     }
 
@@ -3,7 +3,7 @@ use super::{
     wasm_valtype_t, WasmExternType,
 };
 use std::convert::TryInto;
-use wasmer_api::{ExternType, GlobalType};
+use wasmer_api::GlobalType;
 
 #[derive(Debug, Clone)]
 pub(crate) struct WasmGlobalType {
@@ -32,7 +32,9 @@ pub struct wasm_globaltype_t {
 impl wasm_globaltype_t {
     pub(crate) fn new(global_type: GlobalType) -> Self {
         Self {
-            extern_type: wasm_externtype_t::new(ExternType::Global(global_type)),
+            extern_type: wasm_externtype_t {
+                inner: WasmExternType::Global(WasmGlobalType::new(global_type)),
+            },
         }
     }
 
@@ -43,22 +43,18 @@ pub extern "C" fn wasm_importtype_type(import_type: &wasm_importtype_t) -> &wasm
 #[no_mangle]
 pub unsafe extern "C" fn wasm_importtype_delete(_import_type: Option<Box<wasm_importtype_t>>) {}
 
-impl From<ImportType> for wasm_importtype_t {
-    fn from(other: ImportType) -> Self {
-        (&other).into()
-    }
-}
-
-impl From<&ImportType> for wasm_importtype_t {
-    fn from(other: &ImportType) -> Self {
+impl wasm_importtype_t {
+    /// Returns `None` for the import of a tag, as the C API has no extern
+    /// kind for tags.
+    pub(crate) fn new(other: &ImportType) -> Option<Self> {
         let module: wasm_name_t = other.module().to_string().into();
         let name: wasm_name_t = other.name().to_string().into();
-        let extern_type: wasm_externtype_t = other.ty().into();
+        let extern_type = wasm_externtype_t::new(other.ty().clone())?;
 
-        wasm_importtype_t {
+        Some(wasm_importtype_t {
             module,
             name,
             extern_type,
-        }
+        })
     }
 }
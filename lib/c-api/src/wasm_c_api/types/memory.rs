@@ -1,5 +1,5 @@
 use super::{wasm_externtype_t, WasmExternType};
-use wasmer_api::{ExternType, MemoryType, Pages};
+use wasmer_api::{MemoryType, Pages};
 
 #[derive(Debug, Clone)]
 pub(crate) struct WasmMemoryType {
@@ -34,7 +34,9 @@ pub struct wasm_memorytype_t {
 impl wasm_memorytype_t {
     pub(crate) fn new(memory_type: MemoryType) -> Self {
         Self {
-            extern_type: wasm_externtype_t::new(ExternType::Memory(memory_type)),
+            extern_type: wasm_externtype_t {
+                inner: WasmExternType::Memory(WasmMemoryType::new(memory_type)),
+            },
         }
     }
 
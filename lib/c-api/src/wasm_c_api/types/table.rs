@@ -1,7 +1,7 @@
 use super::{
     wasm_externtype_t, wasm_limits_t, wasm_valtype_delete, wasm_valtype_t, WasmExternType,
 };
-use wasmer_api::{ExternType, TableType};
+use wasmer_api::TableType;
 
 #[allow(non_camel_case_types)]
 pub type wasm_table_size_t = u32;
@@ -41,7 +41,9 @@ pub struct wasm_tabletype_t {
 impl wasm_tabletype_t {
     pub(crate) fn new(table_type: TableType) -> Self {
         Self {
-            extern_type: wasm_externtype_t::new(ExternType::Table(table_type)),
+            extern_type: wasm_externtype_t {
+                inner: WasmExternType::Table(WasmTableType::new(table_type)),
+            },
         }
     }
 
@@ -175,16 +175,16 @@ fn wasi_get_unordered_imports_inner(
     imports.set_buffer(
         import_object
             .into_iter()
-            .map(|((module, name), extern_)| {
+            .filter_map(|((module, name), extern_)| {
                 let module = module.into();
                 let name = name.into();
                 let extern_inner = Extern::from_vm_export(store, extern_.to_export());
 
-                Some(Box::new(wasmer_named_extern_t {
+                Some(Some(Box::new(wasmer_named_extern_t {
                     module,
                     name,
-                    r#extern: Box::new(extern_inner.into()),
-                }))
+                    r#extern: Box::new(wasm_extern_t::new(extern_inner)?),
+                })))
             })
             .collect::<Vec<_>>(),
     );
@@ -4,7 +4,7 @@
 
 pub use super::unstable::wasi::wasi_get_unordered_imports;
 use super::{
-    externals::{wasm_extern_vec_t, wasm_func_t},
+    externals::{wasm_extern_t, wasm_extern_vec_t, wasm_func_t},
     instance::wasm_instance_t,
     module::wasm_module_t,
     store::wasm_store_t,
@@ -359,8 +359,15 @@ fn wasi_get_imports_inner(
                     )
                 })?;
             let inner = Extern::from_vm_export(store, ext.to_export());
-
-            Ok(Some(Box::new(inner.into())))
+            let r#extern = wasm_extern_t::new(inner).ok_or_else(|| {
+                format!(
+                    "Import \"{}\" \"{}\" is a tag, which the C API doesn't support",
+                    import_type.module(),
+                    import_type.name()
+                )
+            })?;
+
+            Ok(Some(Box::new(r#extern)))
         })
         .collect::<Result<Vec<_>, String>>()));
 
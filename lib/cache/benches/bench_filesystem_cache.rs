@@ -5,7 +5,7 @@ use rand::{thread_rng, Rng};
 use tempfile::TempDir;
 use wasmer::{Module, Store};
 use wasmer_cache::Cache;
-use wasmer_cache::{FileSystemCache, Hash};
+use wasmer_cache::{FileSystemCache, Hash, MemoryCache, TieredCache};
 use wasmer_compiler::Universal;
 use wasmer_compiler_singlepass::Singlepass;
 
@@ -87,9 +87,29 @@ pub fn load_cache_native(c: &mut Criterion) {
     });
 }
 
+pub fn load_tiered_cache_universal(c: &mut Criterion) {
+    let tmp_dir = TempDir::new().unwrap();
+    let fs_cache = FileSystemCache::new(tmp_dir.path()).unwrap();
+    let mut tiered_cache = TieredCache::new(MemoryCache::new(), fs_cache);
+    let compiler = Singlepass::default();
+    let store = Store::new_with_engine(&Universal::new(compiler).engine());
+    let module = Module::new(
+        &store,
+        std::fs::read("../../lib/c-api/examples/assets/qjs.wasm").unwrap(),
+    )
+    .unwrap();
+    let key = Hash::new([0u8; 32]);
+    tiered_cache.store(key, &module).unwrap();
+
+    c.bench_function("load universal module in tiered cache", |b| {
+        b.iter(|| unsafe { tiered_cache.load(&store, key).unwrap() })
+    });
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default().sample_size(300);
-    targets = store_cache_universal, load_cache_universal, store_cache_native, load_cache_native
+    targets = store_cache_universal, load_cache_universal, store_cache_native, load_cache_native,
+        load_tiered_cache_universal
 }
 criterion_main!(benches);
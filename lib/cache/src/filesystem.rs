@@ -1,9 +1,12 @@
 #![cfg_attr(not(feature = "filesystem"), allow(unused))]
 use crate::cache::Cache;
 use crate::hash::Hash;
-use std::fs::{create_dir_all, File};
-use std::io::{self, Write};
-use std::path::PathBuf;
+use crate::index::{write_atomically, Access, CacheEntry, CacheStats, Index, IndexEntry};
+use std::ffi::OsStr;
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use wasmer::{DeserializeError, Module, SerializeError, Store};
 
 /// Representation of a directory that contains compiled wasm artifacts.
@@ -11,6 +14,21 @@ use wasmer::{DeserializeError, Module, SerializeError, Store};
 /// The `FileSystemCache` type implements the [`Cache`] trait, which allows it to be used
 /// generically when some sort of cache is required.
 ///
+/// The cache keeps an index of its modules in the directory, with when
+/// they were last used and how many times the cache was hit or missed.
+/// See [`FileSystemCache::entries`] and [`FileSystemCache::stats`].
+///
+/// The cache can be given a capacity, in bytes with
+/// [`FileSystemCache::set_max_size`] or in modules with
+/// [`FileSystemCache::set_max_entries`]. Storing a module then evicts the
+/// least recently used ones until the cache fits.
+///
+/// Several processes can share the directory: artifacts are written to a
+/// temporary file and renamed, so they are never seen half written, and
+/// the index is only rewritten with the directory locked. Loads only
+/// append a line to a journal of the index, with a shared lock, so they
+/// don't wait for each other.
+///
 /// # Usage
 ///
 /// ```
@@ -34,6 +52,8 @@ use wasmer::{DeserializeError, Module, SerializeError, Store};
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    max_size: Option<u64>,
+    max_entries: Option<usize>,
 }
 
 #[cfg(feature = "filesystem")]
@@ -45,7 +65,12 @@ impl FileSystemCache {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Ok(Self {
+                        path,
+                        ext: None,
+                        max_size: None,
+                        max_entries: None,
+                    })
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -72,7 +97,12 @@ impl FileSystemCache {
                     format!("failed to create cache directory: {}", path.display()),
                 ))
             } else {
-                Ok(Self { path, ext: None })
+                Ok(Self {
+                    path,
+                    ext: None,
+                    max_size: None,
+                    max_entries: None,
+                })
             }
         }
     }
@@ -84,6 +114,122 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
+
+    /// Set the maximum total size of the artifacts in the cache, in bytes.
+    ///
+    /// When a module is stored, the least recently used modules are
+    /// evicted until the cache fits, but the stored module is kept even
+    /// if it is bigger than the cache.
+    pub fn set_max_size(&mut self, max_size: Option<u64>) {
+        self.max_size = max_size;
+    }
+
+    /// Set the maximum number of modules in the cache.
+    ///
+    /// When a module is stored, the least recently used modules are
+    /// evicted until the cache fits.
+    pub fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+    }
+
+    /// Returns the modules in the cache, the least recently used first.
+    pub fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        self.entries_in(&Index::read(&self.path)?)
+    }
+
+    /// Returns the modules in the cache, the least recently used first,
+    /// with what `index` knows about them.
+    fn entries_in(&self, index: &Index) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.path)? {
+            let dir_entry = dir_entry?;
+            let key = match self.key_of(&dir_entry.file_name()) {
+                Some(key) => key,
+                None => continue,
+            };
+            let metadata = match dir_entry.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                Ok(_) => continue,
+                // Removed since the directory was read.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let modified = metadata.modified()?;
+            let indexed = index.entries.get(&key);
+            entries.push(CacheEntry {
+                key,
+                path: dir_entry.path(),
+                size: metadata.len(),
+                modified,
+                last_accessed: indexed.map_or(modified, IndexEntry::last_accessed),
+                hits: indexed.map_or(0, |entry| entry.hits),
+            });
+        }
+        entries.sort_by_key(|entry| (entry.last_accessed, entry.modified));
+        Ok(entries)
+    }
+
+    /// Returns how many times modules were loaded from the cache, or
+    /// couldn't be.
+    pub fn stats(&self) -> io::Result<CacheStats> {
+        Ok(Index::read(&self.path)?.stats)
+    }
+
+    /// Removes the module of `key` from the cache, if it is there.
+    pub fn remove(&mut self, key: Hash) -> io::Result<()> {
+        Index::update(&self.path, |index| {
+            remove_artifact(&self.path_of(key))?;
+            index.entries.remove(&key);
+            Ok(())
+        })
+    }
+
+    /// Evicts the least recently used modules but `kept` until the cache
+    /// fits in its capacity.
+    fn evict(&self, index: &mut Index, kept: Hash) -> io::Result<()> {
+        if self.max_size.is_none() && self.max_entries.is_none() {
+            return Ok(());
+        }
+        let entries = self.entries_in(index)?;
+        let mut size: u64 = entries.iter().map(|entry| entry.size).sum();
+        let mut count = entries.len();
+        for entry in entries {
+            let too_big = matches!(self.max_size, Some(max_size) if size > max_size);
+            let too_many = matches!(self.max_entries, Some(max_entries) if count > max_entries);
+            if !too_big && !too_many {
+                break;
+            }
+            if entry.key == kept {
+                continue;
+            }
+            remove_artifact(&entry.path)?;
+            index.entries.remove(&entry.key);
+            size -= entry.size;
+            count -= 1;
+        }
+        Ok(())
+    }
+
+    /// The path of the artifact of the module of `key`.
+    fn path_of(&self, key: Hash) -> PathBuf {
+        let filename = if let Some(ref ext) = self.ext {
+            format!("{}.{}", key.to_string(), ext)
+        } else {
+            key.to_string()
+        };
+        self.path.join(filename)
+    }
+
+    /// The key of the module of the artifact named `filename`, if it is
+    /// one.
+    fn key_of(&self, filename: &OsStr) -> Option<Hash> {
+        let filename = filename.to_str()?;
+        let key = match &self.ext {
+            Some(ext) => filename.strip_suffix(ext.as_str())?.strip_suffix('.')?,
+            None => filename,
+        };
+        Hash::from_str(key).ok()
+    }
 }
 
 #[cfg(feature = "filesystem")]
@@ -92,27 +238,166 @@ impl Cache for FileSystemCache {
     type SerializeError = SerializeError;
 
     unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{}.{}", key.to_string(), ext)
-        } else {
-            key.to_string()
+        let result = Module::deserialize_from_file(store, self.path_of(key));
+        let access = match result {
+            Ok(_) => Access::hit(key),
+            Err(_) => Access::Miss(key),
         };
-        let path = self.path.join(filename);
-        Module::deserialize_from_file(store, path)
+        // The index only adds to the cache, so it doesn't fail loads.
+        let _ = Index::record(&self.path, access);
+        result
     }
 
     fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{}.{}", key.to_string(), ext)
-        } else {
-            key.to_string()
-        };
-        let path = self.path.join(filename);
-        let mut file = File::create(path)?;
-
         let buffer = module.serialize()?;
-        file.write_all(&buffer)?;
+        write_atomically(&self.path_of(key), &buffer)?;
 
+        // The module is stored by now, so failing to update the index or
+        // to evict other modules doesn't fail the store.
+        let _ = Index::update(&self.path, |index| {
+            index.stored(key);
+            self.evict(index, key)
+        });
         Ok(())
     }
 }
+
+/// Removes the artifact at `path`, if it is there.
+fn remove_artifact(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use wasmer_compiler::Universal;
+    use wasmer_compiler_singlepass::Singlepass;
+
+    /// An empty module, with `types` function types.
+    fn module(store: &Store, types: u8) -> Module {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        if types > 0 {
+            wasm.extend_from_slice(&[0x01, 1 + 3 * types, types]);
+            for _ in 0..types {
+                wasm.extend_from_slice(&[0x60, 0, 0]);
+            }
+        }
+        Module::new(store, wasm).unwrap()
+    }
+
+    #[test]
+    fn entries_and_stats() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = FileSystemCache::new(dir.path()).unwrap();
+        cache.set_cache_extension(Some("wasmu"));
+        let store = Store::new_with_engine(&Universal::new(Singlepass::default()).engine());
+        let (first, second) = (Hash::new([1; 32]), Hash::new([2; 32]));
+        cache.store(first, &module(&store, 0)).unwrap();
+        cache.store(second, &module(&store, 1)).unwrap();
+        // Files that aren't artifacts of the cache are ignored.
+        fs::write(dir.path().join("README"), "").unwrap();
+        fs::write(dir.path().join(format!("{}.o", first.to_string())), "").unwrap();
+
+        unsafe {
+            cache.load(&store, first).unwrap();
+            cache.load(&store, first).unwrap();
+            cache.load(&store, Hash::new([3; 32])).unwrap_err();
+        }
+        assert_eq!(cache.stats().unwrap(), CacheStats { hits: 2, misses: 1 });
+
+        let mut entries = cache.entries().unwrap();
+        entries.sort_by_key(|entry| entry.hits);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, second);
+        assert_eq!(entries[0].hits, 0);
+        assert_eq!(entries[1].key, first);
+        assert_eq!(entries[1].hits, 2);
+        assert_eq!(
+            entries[1].size,
+            fs::metadata(&entries[1].path).unwrap().len()
+        );
+
+        cache.remove(first).unwrap();
+        cache.remove(first).unwrap();
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, second);
+        assert_eq!(cache.stats().unwrap(), CacheStats { hits: 2, misses: 1 });
+    }
+
+    fn cached_keys(cache: &FileSystemCache) -> Vec<Hash> {
+        let mut keys: Vec<Hash> = cache.entries().unwrap().iter().map(|e| e.key).collect();
+        keys.sort_by_key(|key| key.to_string());
+        keys
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = FileSystemCache::new(dir.path()).unwrap();
+        let store = Store::new_with_engine(&Universal::new(Singlepass::default()).engine());
+        let keys: Vec<Hash> = (1..=4).map(|i| Hash::new([i; 32])).collect();
+        // The index records when modules are used to the millisecond.
+        let tick = || std::thread::sleep(std::time::Duration::from_millis(10));
+
+        cache.set_max_entries(Some(2));
+        cache.store(keys[0], &module(&store, 0)).unwrap();
+        tick();
+        cache.store(keys[1], &module(&store, 0)).unwrap();
+        tick();
+        unsafe { cache.load(&store, keys[0]).unwrap() };
+        tick();
+        cache.store(keys[2], &module(&store, 0)).unwrap();
+        assert_eq!(cached_keys(&cache), [keys[0], keys[2]]);
+
+        cache.set_max_entries(None);
+        let size = cache.entries().unwrap()[0].size;
+        cache.set_max_size(Some(2 * size + 1));
+        tick();
+        cache.store(keys[3], &module(&store, 0)).unwrap();
+        assert_eq!(cached_keys(&cache), [keys[2], keys[3]]);
+
+        // The stored module is kept even if it doesn't fit.
+        cache.set_max_size(Some(1));
+        tick();
+        cache.store(keys[1], &module(&store, 3)).unwrap();
+        assert_eq!(cached_keys(&cache), [keys[1]]);
+    }
+
+    #[test]
+    fn processes_share_the_index() {
+        let dir = TempDir::new().unwrap();
+        let key = Hash::new([1; 32]);
+        {
+            let mut cache = FileSystemCache::new(dir.path()).unwrap();
+            let store = Store::new_with_engine(&Universal::new(Singlepass::default()).engine());
+            cache.store(key, &module(&store, 0)).unwrap();
+        }
+        // Each thread opens the lock file on its own, like a process.
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let path = dir.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let cache = FileSystemCache::new(path).unwrap();
+                    let store =
+                        Store::new_with_engine(&Universal::new(Singlepass::default()).engine());
+                    for _ in 0..10 {
+                        unsafe { cache.load(&store, key).unwrap() };
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let cache = FileSystemCache::new(dir.path()).unwrap();
+        assert_eq!(cache.stats().unwrap().hits, 40);
+        // No temporary files are left behind, only the artifact, the lock
+        // file, the index and the journal of the loads.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+}
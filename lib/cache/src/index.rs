@@ -0,0 +1,376 @@
+//! The metadata index of a [`FileSystemCache`], stored next to the
+//! artifacts.
+//!
+//! The index records when each module was last used and how many times
+//! the cache was hit and missed. The artifacts in the directory are the
+//! source of truth for which modules are cached: the index only adds to
+//! them, and a missing or corrupted index is treated as an empty one.
+//!
+//! Loads don't rewrite the index: they append what they did to a
+//! journal next to it, which is folded into the index whenever it is
+//! rewritten, and read along with it.
+//!
+//! [`FileSystemCache`]: crate::FileSystemCache
+
+#![cfg_attr(not(feature = "filesystem"), allow(unused))]
+use crate::hash::Hash;
+use crate::lock::DirLock;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The name of the index file in the cache directory.
+const INDEX_FILE: &str = "wasmer-cache-index";
+
+/// The first line of the index, with the version of its format.
+const HEADER: &str = "wasmer-cache-index 2";
+
+/// The name of the journal of the index in the cache directory.
+const JOURNAL_FILE: &str = "wasmer-cache-journal";
+
+/// The size of the journal past which a load folds it into the index.
+const MAX_JOURNAL_LEN: u64 = 64 * 1024;
+
+/// How many times modules were found in a cache, or not.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of modules loaded from the cache.
+    pub hits: u64,
+    /// The number of modules that couldn't be loaded from the cache.
+    pub misses: u64,
+}
+
+/// A module stored in a [`FileSystemCache`].
+///
+/// [`FileSystemCache`]: crate::FileSystemCache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// The key of the module.
+    pub key: Hash,
+    /// The path of the artifact of the module.
+    pub path: PathBuf,
+    /// The size of the artifact, in bytes.
+    pub size: u64,
+    /// When the module was stored.
+    pub modified: SystemTime,
+    /// When the module was last loaded or stored.
+    pub last_accessed: SystemTime,
+    /// How many times the module was loaded.
+    pub hits: u64,
+}
+
+/// What the index knows about an entry.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IndexEntry {
+    /// When the module was last loaded or stored, in milliseconds since
+    /// the Unix epoch.
+    pub(crate) last_accessed: u64,
+    /// How many times the module was loaded.
+    pub(crate) hits: u64,
+}
+
+impl IndexEntry {
+    pub(crate) fn last_accessed(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.last_accessed)
+    }
+}
+
+/// A load recorded in the journal of the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// The module of the key was loaded, at the given time in
+    /// milliseconds since the Unix epoch.
+    Hit(Hash, u64),
+    /// The module of the key couldn't be loaded.
+    Miss(Hash),
+}
+
+impl Access {
+    /// Records that the module of `key` was just loaded.
+    pub(crate) fn hit(key: Hash) -> Self {
+        Self::Hit(key, now())
+    }
+
+    /// Parses a line of the journal.
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split(' ').collect();
+        match fields[..] {
+            ["hit", key, at] => Some(Self::Hit(Hash::from_str(key).ok()?, at.parse().ok()?)),
+            ["miss", key] => Some(Self::Miss(Hash::from_str(key).ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hit(key, at) => write!(f, "hit {} {}", key.to_string(), at),
+            Self::Miss(key) => write!(f, "miss {}", key.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Index {
+    pub(crate) stats: CacheStats,
+    pub(crate) entries: HashMap<Hash, IndexEntry>,
+}
+
+impl Index {
+    /// Reads the index of the cache in `dir`, with the loads recorded in
+    /// its journal.
+    pub(crate) fn read(dir: &Path) -> io::Result<Self> {
+        let mut index = match read_if_exists(&dir.join(INDEX_FILE))? {
+            Some(contents) => Self::parse(&contents),
+            None => Self::default(),
+        };
+        if let Some(journal) = read_if_exists(&dir.join(JOURNAL_FILE))? {
+            // Only the lines that were appended whole are read. A line that
+            // was cut short when its process died doesn't parse, and the
+            // next line starts after it, see `record`.
+            let end = journal.rfind('\n').unwrap_or(0);
+            for access in journal[..end].lines().filter_map(Access::parse) {
+                index.apply(access);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Reads the index of the cache in `dir`, lets `f` change it and
+    /// writes it back, with its journal folded in.
+    ///
+    /// The directory is locked meanwhile, so that the processes sharing
+    /// it don't lose each other's updates, and `f` can change the
+    /// artifacts along with the index. The index is written back even if
+    /// `f` fails, to keep the changes it made before failing.
+    pub(crate) fn update<T>(
+        dir: &Path,
+        f: impl FnOnce(&mut Self) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let _lock = DirLock::acquire(dir)?;
+        let mut index = Self::read(dir)?;
+        let result = f(&mut index);
+        index.write(dir)?;
+        // If the process dies before the journal is removed, its loads
+        // are counted twice, which is better than not at all.
+        match fs::remove_file(dir.join(JOURNAL_FILE)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        result
+    }
+
+    /// Appends `access` to the journal of the cache in `dir`.
+    ///
+    /// The directory is only locked shared, so that loads don't wait for
+    /// each other, and the line is appended with a single write, so that
+    /// the lines of concurrent loads don't mix. It starts with a newline
+    /// too, in case the last line was cut short. Once the journal grows
+    /// past [`MAX_JOURNAL_LEN`], it is folded into the index.
+    pub(crate) fn record(dir: &Path, access: Access) -> io::Result<()> {
+        let journal_len = {
+            let _lock = DirLock::acquire_shared(dir)?;
+            let mut journal = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(JOURNAL_FILE))?;
+            journal.write_all(format!("\n{}\n", access).as_bytes())?;
+            journal.metadata()?.len()
+        };
+        if journal_len > MAX_JOURNAL_LEN {
+            Self::update(dir, |_| Ok(()))?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, dir: &Path) -> io::Result<()> {
+        write_atomically(&dir.join(INDEX_FILE), self.to_string().as_bytes())
+    }
+
+    /// Parses an index, skipping the lines it doesn't understand.
+    fn parse(contents: &str) -> Self {
+        let mut index = Self::default();
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return index;
+        }
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields[..] {
+                ["stats", hits, misses] => {
+                    if let (Ok(hits), Ok(misses)) = (hits.parse(), misses.parse()) {
+                        index.stats = CacheStats { hits, misses };
+                    }
+                }
+                ["entry", key, last_accessed, hits] => {
+                    if let (Ok(key), Ok(last_accessed), Ok(hits)) =
+                        (Hash::from_str(key), last_accessed.parse(), hits.parse())
+                    {
+                        index.entries.insert(
+                            key,
+                            IndexEntry {
+                                last_accessed,
+                                hits,
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        index
+    }
+
+    /// Records a load of a module.
+    pub(crate) fn apply(&mut self, access: Access) {
+        match access {
+            Access::Hit(key, at) => {
+                self.stats.hits += 1;
+                let entry = self.entries.entry(key).or_default();
+                entry.last_accessed = entry.last_accessed.max(at);
+                entry.hits += 1;
+            }
+            Access::Miss(key) => {
+                self.stats.misses += 1;
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Records that the module of `key` was just stored.
+    pub(crate) fn stored(&mut self, key: Hash) {
+        self.entries.insert(
+            key,
+            IndexEntry {
+                last_accessed: now(),
+                hits: 0,
+            },
+        );
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "stats {} {}", self.stats.hits, self.stats.misses)?;
+        for (key, entry) in &self.entries {
+            writeln!(
+                f,
+                "entry {} {} {}",
+                key.to_string(),
+                entry.last_accessed,
+                entry.hits
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path`, and renames it
+/// to `path`, so that the file is never seen half written.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = PathBuf::from(temp_path);
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| file.write_all(contents))
+        .and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Reads the file at `path`, if it is there.
+fn read_if_exists(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_round_trips() {
+        let mut index = Index::default();
+        index.stored(Hash::new([1; 32]));
+        index.apply(Access::hit(Hash::new([1; 32])));
+        index.stored(Hash::new([2; 32]));
+        index.apply(Access::Miss(Hash::new([3; 32])));
+        assert_eq!(index.stats, CacheStats { hits: 1, misses: 1 });
+        assert_eq!(index.entries[&Hash::new([1; 32])].hits, 1);
+        assert_eq!(Index::parse(&index.to_string()), index);
+    }
+
+    #[test]
+    fn journal_is_folded_into_the_index() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let key = Hash::new([1; 32]);
+        Index::update(dir.path(), |index| {
+            index.stored(key);
+            Ok(())
+        })
+        .unwrap();
+        Index::record(dir.path(), Access::Hit(key, 10)).unwrap();
+        Index::record(dir.path(), Access::Miss(Hash::new([2; 32]))).unwrap();
+        // A line that was being appended when the process died.
+        let mut journal = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(JOURNAL_FILE))
+            .unwrap();
+        journal.write_all(b"hit 01").unwrap();
+        Index::record(dir.path(), Access::Hit(key, 5)).unwrap();
+
+        let index = Index::read(dir.path()).unwrap();
+        assert_eq!(index.stats, CacheStats { hits: 2, misses: 1 });
+        assert_eq!(index.entries[&key].hits, 2);
+        assert!(index.entries[&key].last_accessed >= 10);
+
+        Index::update(dir.path(), |_| Ok(())).unwrap();
+        assert!(!dir.path().join(JOURNAL_FILE).exists());
+        assert_eq!(Index::read(dir.path()).unwrap(), index);
+    }
+
+    #[test]
+    fn unknown_lines_are_skipped() {
+        let key = Hash::new([1; 32]).to_string();
+        let index = Index::parse(&format!(
+            "{}\nstats 3 4\nentry {} 10 2\nentry nothex 10 2\nsomething else\n",
+            HEADER, key
+        ));
+        assert_eq!(index.stats, CacheStats { hits: 3, misses: 4 });
+        assert_eq!(index.entries.len(), 1);
+        assert_eq!(
+            index.entries[&Hash::new([1; 32])],
+            IndexEntry {
+                last_accessed: 10,
+                hits: 2
+            }
+        );
+        assert_eq!(
+            Index::parse("some other format\nstats 3 4\n"),
+            Index::default()
+        );
+    }
+}
@@ -21,11 +21,19 @@
 mod cache;
 mod filesystem;
 mod hash;
+mod index;
+mod lock;
+mod memory;
+mod tiered;
 
 pub use crate::cache::Cache;
 #[cfg(feature = "filesystem")]
 pub use crate::filesystem::FileSystemCache;
 pub use crate::hash::Hash;
+#[cfg(feature = "filesystem")]
+pub use crate::index::{CacheEntry, CacheStats};
+pub use crate::memory::MemoryCache;
+pub use crate::tiered::TieredCache;
 
 // We re-export those for convinience of users
 pub use wasmer::{DeserializeError, SerializeError};
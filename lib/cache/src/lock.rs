@@ -0,0 +1,122 @@
+//! Locking of a cache directory shared by several processes.
+
+#![cfg_attr(not(feature = "filesystem"), allow(unused))]
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+/// The name of the lock file in the cache directory.
+const LOCK_FILE: &str = "wasmer-cache.lock";
+
+/// A lock on a cache directory, released when dropped.
+///
+/// The lock is advisory: it only keeps out the other holders of the
+/// lock. The index of the directory is rewritten with an exclusive lock,
+/// while loads only append to its journal, with a shared lock so that
+/// they don't wait for each other. Artifacts are written and replaced
+/// atomically instead, so that loading them doesn't wait for the lock.
+pub(crate) struct DirLock {
+    file: File,
+}
+
+impl DirLock {
+    /// Waits until the directory `dir` can be locked exclusively, and
+    /// locks it.
+    pub(crate) fn acquire(dir: &Path) -> io::Result<Self> {
+        Self::acquire_with(dir, true)
+    }
+
+    /// Waits until the directory `dir` can be locked shared with the
+    /// other holders of a shared lock, and locks it.
+    pub(crate) fn acquire_shared(dir: &Path) -> io::Result<Self> {
+        Self::acquire_with(dir, false)
+    }
+
+    fn acquire_with(dir: &Path, exclusive: bool) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE))?;
+        lock(&file, exclusive)?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        let _ = unlock(&self.file);
+    }
+}
+
+#[cfg(unix)]
+fn lock(file: &File, exclusive: bool) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let operation = if exclusive {
+        libc::LOCK_EX
+    } else {
+        libc::LOCK_SH
+    };
+    loop {
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unlock(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+fn lock(file: &File, exclusive: bool) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::fileapi::LockFileEx;
+    use winapi::um::minwinbase::{LOCKFILE_EXCLUSIVE_LOCK, OVERLAPPED};
+    let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+    let flags = if exclusive {
+        LOCKFILE_EXCLUSIVE_LOCK
+    } else {
+        0
+    };
+    let locked =
+        unsafe { LockFileEx(file.as_raw_handle() as _, flags, 0, !0, !0, &mut overlapped) };
+    if locked != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+fn unlock(file: &File) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::fileapi::UnlockFile;
+    if unsafe { UnlockFile(file.as_raw_handle() as _, 0, 0, !0, !0) } != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Other platforms have no file locking, so they are left to the races
+/// between processes.
+#[cfg(not(any(unix, windows)))]
+fn lock(_file: &File, _exclusive: bool) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock(_file: &File) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use crate::cache::Cache;
+use crate::hash::Hash;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+
+/// A cache that keeps compiled modules in memory.
+///
+/// The modules are kept compiled, so loading one from the cache only binds
+/// it to the store that loads it, with [`Module::with_store`], without
+/// deserializing it. A module is only loaded with a store of the engine it
+/// was compiled with; other stores miss it.
+///
+/// The cache can be given a capacity in modules with
+/// [`MemoryCache::set_max_entries`]. Storing a module then evicts the
+/// least recently used ones until the cache fits.
+///
+/// Besides the [`Cache`] trait, the cache can be used through a shared
+/// reference, so that it can be shared by several threads.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer::{CompileError, Module, Store};
+/// use wasmer_cache::{Hash, MemoryCache};
+///
+/// fn load_module(cache: &MemoryCache, store: &Store, bytes: &[u8]) -> Result<Module, CompileError> {
+///     let key = Hash::generate(bytes);
+///     if let Some(module) = cache.get(store, key) {
+///         return Ok(module);
+///     }
+///     let module = Module::new(store, bytes)?;
+///     cache.insert(key, module.clone());
+///     Ok(module)
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<Entries>,
+    max_entries: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// The modules, with when they were last used.
+    modules: HashMap<Hash, (Module, u64)>,
+    /// Counts the uses of the modules, to tell which was used last.
+    clock: u64,
+}
+
+impl Entries {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl MemoryCache {
+    /// Construct a new, empty `MemoryCache`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of modules in the cache.
+    ///
+    /// When a module is stored, the least recently used modules are
+    /// evicted until the cache fits.
+    pub fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+        let mut entries = self.entries.lock().unwrap();
+        self.evict(&mut entries);
+    }
+
+    /// Returns the module of `key` bound to `store`, if it is in the cache
+    /// and was compiled with the engine of `store`.
+    pub fn get(&self, store: &Store, key: Hash) -> Option<Module> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = entries.tick();
+        let (module, last_used) = entries.modules.get_mut(&key)?;
+        let module = module.with_store(store)?;
+        *last_used = now;
+        Some(module)
+    }
+
+    /// Stores `module` in the cache with the given `key`, replacing the
+    /// module that was there.
+    pub fn insert(&self, key: Hash, module: Module) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = entries.tick();
+        entries.modules.insert(key, (module, now));
+        self.evict(&mut entries);
+    }
+
+    /// Removes the module of `key` from the cache, returning it if it
+    /// was there.
+    pub fn remove(&self, key: Hash) -> Option<Module> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.modules.remove(&key).map(|(module, _)| module)
+    }
+
+    /// Removes all the modules from the cache.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().modules.clear();
+    }
+
+    /// Returns the number of modules in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().modules.len()
+    }
+
+    /// Returns whether the cache has no modules.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Evicts the least recently used modules until the cache fits in
+    /// its capacity.
+    fn evict(&self, entries: &mut Entries) {
+        let max_entries = match self.max_entries {
+            Some(max_entries) => max_entries,
+            None => return,
+        };
+        while entries.modules.len() > max_entries {
+            let oldest = entries
+                .modules
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            match oldest {
+                Some(key) => entries.modules.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+impl Cache for MemoryCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    /// Loads the module of `key`, failing with a
+    /// [`DeserializeError::Io`] of kind [`io::ErrorKind::NotFound`] if it
+    /// isn't in the cache, like [`FileSystemCache`] does.
+    ///
+    /// [`FileSystemCache`]: crate::FileSystemCache
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        self.get(store, key).ok_or_else(|| not_found(key))
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        self.insert(key, module.clone());
+        Ok(())
+    }
+}
+
+fn not_found(key: Hash) -> DeserializeError {
+    DeserializeError::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("module {} is not in the cache", key.to_string()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer::Tunables;
+    use wasmer_compiler::Universal;
+    use wasmer_compiler_singlepass::Singlepass;
+
+    fn new_store() -> Store {
+        Store::new_with_engine(&Universal::new(Singlepass::default()).engine())
+    }
+
+    fn module(store: &Store) -> Module {
+        Module::new(store, b"\0asm\x01\0\0\0").unwrap()
+    }
+
+    fn bound_to(module: &Module, store: &Store) -> bool {
+        let tunables = module.store().tunables() as *const dyn Tunables as *const u8;
+        tunables == store.tunables() as *const dyn Tunables as *const u8
+    }
+
+    #[test]
+    fn loads_stored_modules() {
+        let store = new_store();
+        let mut cache = MemoryCache::new();
+        let key = Hash::new([1; 32]);
+        unsafe {
+            cache.load(&store, key).unwrap_err();
+        }
+        cache.store(key, &module(&store)).unwrap();
+        let loaded = unsafe { cache.load(&store, key).unwrap() };
+        assert!(bound_to(&loaded, &store));
+
+        // Stores of other engines miss the module.
+        assert!(cache.get(&new_store(), key).is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let store = new_store();
+        let mut cache = MemoryCache::new();
+        cache.set_max_entries(Some(2));
+        let keys: Vec<Hash> = (1..=3).map(|i| Hash::new([i; 32])).collect();
+        cache.insert(keys[0], module(&store));
+        cache.insert(keys[1], module(&store));
+        cache.get(&store, keys[0]).unwrap();
+        cache.insert(keys[2], module(&store));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&store, keys[1]).is_none());
+
+        cache.set_max_entries(Some(1));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&store, keys[2]).is_some());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::cache::Cache;
+use crate::filesystem::FileSystemCache;
+use crate::hash::Hash;
+use crate::memory::MemoryCache;
+use wasmer::{Module, Store};
+
+/// A cache that keeps modules in a [`MemoryCache`] in front of a slower
+/// cache, a [`FileSystemCache`] by default.
+///
+/// Loading a module looks it up in memory first, and falls back to the
+/// slower cache. A module loaded from the slower cache is promoted to
+/// memory, so that loading it again doesn't deserialize it. Storing a
+/// module stores it in both caches.
+///
+/// # Usage
+///
+/// ```
+/// use wasmer::{Module, SerializeError};
+/// use wasmer_cache::{Cache, FileSystemCache, Hash, MemoryCache, TieredCache};
+///
+/// fn store_module(module: &Module, bytes: &[u8]) -> Result<(), SerializeError> {
+///     // Create a new tiered cache, keeping up to 16 modules in memory.
+///     let mut memory = MemoryCache::new();
+///     memory.set_max_entries(Some(16));
+///     let fs_cache = FileSystemCache::new("some/directory/goes/here")?;
+///     let mut cache = TieredCache::new(memory, fs_cache);
+///
+///     // Store a module into both caches given a key
+///     cache.store(Hash::generate(bytes), module)?;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct TieredCache<C = FileSystemCache> {
+    memory: MemoryCache,
+    fallback: C,
+}
+
+impl<C: Cache> TieredCache<C> {
+    /// Construct a new `TieredCache` with `memory` in front of `fallback`.
+    pub fn new(memory: MemoryCache, fallback: C) -> Self {
+        Self { memory, fallback }
+    }
+
+    /// Returns the cache in memory.
+    pub fn memory(&self) -> &MemoryCache {
+        &self.memory
+    }
+
+    /// Returns the cache the memory falls back to.
+    pub fn fallback(&self) -> &C {
+        &self.fallback
+    }
+
+    /// Returns the cache the memory falls back to, mutably.
+    pub fn fallback_mut(&mut self) -> &mut C {
+        &mut self.fallback
+    }
+}
+
+impl<C: Cache> Cache for TieredCache<C> {
+    type DeserializeError = C::DeserializeError;
+    type SerializeError = C::SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        if let Some(module) = self.memory.get(store, key) {
+            return Ok(module);
+        }
+        let module = self.fallback.load(store, key)?;
+        self.memory.insert(key, module.clone());
+        Ok(module)
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        self.fallback.store(key, module)?;
+        self.memory.insert(key, module.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use wasmer::DeserializeError;
+    use wasmer_compiler::Universal;
+    use wasmer_compiler_singlepass::Singlepass;
+
+    /// A cache in memory that counts the modules loaded from it.
+    #[derive(Default)]
+    struct CountingCache {
+        memory: MemoryCache,
+        loads: Cell<usize>,
+    }
+
+    impl Cache for CountingCache {
+        type DeserializeError = DeserializeError;
+        type SerializeError = wasmer::SerializeError;
+
+        unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, DeserializeError> {
+            self.loads.set(self.loads.get() + 1);
+            self.memory.load(store, key)
+        }
+
+        fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+            self.memory.store(key, module)
+        }
+    }
+
+    #[test]
+    fn promotes_modules_on_hit() {
+        let store = Store::new_with_engine(&Universal::new(Singlepass::default()).engine());
+        let module = Module::new(&store, b"\0asm\x01\0\0\0").unwrap();
+        let key = Hash::new([1; 32]);
+        let mut fallback = CountingCache::default();
+        fallback.store(key, &module).unwrap();
+        let mut cache = TieredCache::new(MemoryCache::new(), fallback);
+
+        unsafe {
+            cache.load(&store, key).unwrap();
+            cache.load(&store, key).unwrap();
+            cache.load(&store, Hash::new([2; 32])).unwrap_err();
+        }
+        assert_eq!(cache.fallback().loads.get(), 2);
+        assert_eq!(cache.memory().len(), 1);
+
+        let other_key = Hash::new([3; 32]);
+        cache.store(other_key, &module).unwrap();
+        assert!(cache.memory().get(&store, other_key).is_some());
+        assert_eq!(cache.fallback().memory.len(), 2);
+    }
+}
@@ -1,7 +1,19 @@
 use crate::common::get_cache_dir;
+#[cfg(feature = "cache")]
+use crate::utils::{parse_duration, parse_size};
+#[cfg(feature = "cache")]
+use anyhow::bail;
 use anyhow::{Context, Result};
+#[cfg(feature = "cache")]
+use bytesize::ByteSize;
 use std::fs;
+#[cfg(feature = "cache")]
+use std::time::{Duration, SystemTime};
 use structopt::StructOpt;
+#[cfg(feature = "cache")]
+use wasmer::Triple;
+#[cfg(feature = "cache")]
+use wasmer_cache::{CacheEntry, CacheStats, FileSystemCache};
 
 #[derive(Debug, StructOpt)]
 /// The options for the `wasmer cache` subcommand
@@ -13,6 +25,29 @@ pub enum Cache {
     /// Display the location of the cache
     #[structopt(name = "dir")]
     Dir,
+
+    /// List the modules in the cache
+    #[cfg(feature = "cache")]
+    #[structopt(name = "list")]
+    List,
+
+    /// Remove modules from the cache, the least recently used first
+    #[cfg(feature = "cache")]
+    #[structopt(name = "prune")]
+    Prune {
+        /// Remove modules until the cache is no bigger than this, like `10GiB`
+        #[structopt(long = "max-size", name = "SIZE", parse(try_from_str = parse_size))]
+        max_size: Option<u64>,
+
+        /// Remove modules not used for longer than this, like `7d`
+        #[structopt(long = "older-than", name = "DURATION", parse(try_from_str = parse_duration))]
+        older_than: Option<Duration>,
+    },
+
+    /// Display how many times modules were found in the cache
+    #[cfg(feature = "cache")]
+    #[structopt(name = "stats")]
+    Stats,
 }
 
 impl Cache {
@@ -25,6 +60,23 @@ impl Cache {
             Cache::Dir => {
                 self.dir()?;
             }
+            #[cfg(feature = "cache")]
+            Cache::List => {
+                self.list().context("failed to list wasmer cache.")?;
+            }
+            #[cfg(feature = "cache")]
+            Cache::Prune {
+                max_size,
+                older_than,
+            } => {
+                self.prune(*max_size, *older_than)
+                    .context("failed to prune wasmer cache.")?;
+            }
+            #[cfg(feature = "cache")]
+            Cache::Stats => {
+                self.stats()
+                    .context("failed to get wasmer cache statistics.")?;
+            }
         }
         Ok(())
     }
@@ -41,4 +93,142 @@ impl Cache {
         println!("{}", get_cache_dir().to_string_lossy());
         Ok(())
     }
+    #[cfg(feature = "cache")]
+    fn list(&self) -> Result<()> {
+        let now = SystemTime::now();
+        println!(
+            "{:<64}  {:>10}  {:<22}  {:>10}  {:>10}  {:>6}",
+            "HASH", "SIZE", "ENGINE", "MODIFIED", "LAST USED", "HITS"
+        );
+        for (compiler, cache) in caches()? {
+            for entry in cache.entries()? {
+                println!(
+                    "{:<64}  {:>10}  {:<22}  {:>10}  {:>10}  {:>6}",
+                    entry.key.to_string(),
+                    ByteSize(entry.size).to_string(),
+                    format!("universal/{}", compiler),
+                    age(now, entry.modified),
+                    age(now, entry.last_accessed),
+                    entry.hits
+                );
+            }
+        }
+        Ok(())
+    }
+    #[cfg(feature = "cache")]
+    fn prune(&self, max_size: Option<u64>, older_than: Option<Duration>) -> Result<()> {
+        if max_size.is_none() && older_than.is_none() {
+            bail!("`--max-size` or `--older-than` must be given");
+        }
+        let mut caches = caches()?;
+        let mut entries: Vec<(usize, CacheEntry)> = Vec::new();
+        for (i, (_, cache)) in caches.iter().enumerate() {
+            entries.extend(cache.entries()?.into_iter().map(|entry| (i, entry)));
+        }
+        entries.sort_by_key(|(_, entry)| entry.last_accessed);
+
+        // The entries are sorted by when they were last used, so the ones
+        // to remove are a prefix.
+        let total_size: u64 = entries.iter().map(|(_, entry)| entry.size).sum();
+        let oldest_kept = older_than.and_then(|age| SystemTime::now().checked_sub(age));
+        let mut remaining_size = total_size;
+        let mut removed = 0;
+        for (i, entry) in &entries {
+            let too_old = oldest_kept.map_or(false, |oldest| entry.last_accessed < oldest);
+            let too_big = max_size.map_or(false, |max_size| remaining_size > max_size);
+            if !too_old && !too_big {
+                break;
+            }
+            caches[*i].1.remove(entry.key)?;
+            remaining_size -= entry.size;
+            removed += 1;
+        }
+        eprintln!(
+            "Removed {} modules ({}) from the wasmer cache; {} modules ({}) remain.",
+            removed,
+            ByteSize(total_size - remaining_size),
+            entries.len() - removed,
+            ByteSize(remaining_size)
+        );
+        Ok(())
+    }
+    #[cfg(feature = "cache")]
+    fn stats(&self) -> Result<()> {
+        println!(
+            "{:<22}  {:>8}  {:>10}  {:>8}  {:>8}  {:>8}",
+            "ENGINE", "ENTRIES", "SIZE", "HITS", "MISSES", "HIT RATE"
+        );
+        let (mut total_entries, mut total_size) = (0, 0);
+        let mut total_stats = CacheStats::default();
+        for (compiler, cache) in caches()? {
+            let entries = cache.entries()?;
+            let size = entries.iter().map(|entry| entry.size).sum();
+            let stats = cache.stats()?;
+            print_stats(
+                &format!("universal/{}", compiler),
+                entries.len(),
+                size,
+                stats,
+            );
+            total_entries += entries.len();
+            total_size += size;
+            total_stats.hits += stats.hits;
+            total_stats.misses += stats.misses;
+        }
+        print_stats("total", total_entries, total_size, total_stats);
+        Ok(())
+    }
+}
+
+/// Returns the cache of each compiler, by name.
+#[cfg(feature = "cache")]
+fn caches() -> Result<Vec<(String, FileSystemCache)>> {
+    let cache_dir = get_cache_dir();
+    if !cache_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let extension = wasmer_compiler::UniversalArtifact::get_default_extension(&Triple::host());
+    let mut caches = Vec::new();
+    for dir_entry in fs::read_dir(&cache_dir)? {
+        let dir_entry = dir_entry?;
+        if !dir_entry.file_type()?.is_dir() {
+            continue;
+        }
+        let mut cache = FileSystemCache::new(dir_entry.path())?;
+        cache.set_cache_extension(Some(extension));
+        caches.push((dir_entry.file_name().to_string_lossy().into_owned(), cache));
+    }
+    caches.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(caches)
+}
+
+#[cfg(feature = "cache")]
+fn print_stats(engine: &str, entries: usize, size: u64, stats: CacheStats) {
+    let lookups = stats.hits + stats.misses;
+    let hit_rate = if lookups == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", stats.hits as f64 * 100.0 / lookups as f64)
+    };
+    println!(
+        "{:<22}  {:>8}  {:>10}  {:>8}  {:>8}  {:>8}",
+        engine,
+        entries,
+        ByteSize(size).to_string(),
+        stats.hits,
+        stats.misses,
+        hit_rate
+    );
+}
+
+/// Formats how long ago `time` was, like `5m ago`.
+#[cfg(feature = "cache")]
+fn age(now: SystemTime, time: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or_default().as_secs();
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
 }
@@ -1,10 +1,38 @@
 use crate::store::StoreOptions;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bytesize::ByteSize;
+#[cfg(not(feature = "wat"))]
+use std::borrow::Cow;
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::StructOpt;
 use wasmer::*;
 
+mod report;
+
+use report::Report;
+
+/// The output format of the `wasmer inspect` subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Human readable text
+    Text,
+    /// A JSON object
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => bail!("Format must be `text` or `json`; found `{}`", s),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 /// The options for the `wasmer validate` subcommand
 pub struct Inspect {
@@ -12,6 +40,10 @@ pub struct Inspect {
     #[structopt(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
+    /// Output format, `text` or `json`
+    #[structopt(long = "format", default_value = "text")]
+    format: Format,
+
     #[structopt(flatten)]
     store: StoreOptions,
 }
@@ -26,49 +58,121 @@ impl Inspect {
         let (store, _compiler_type) = self.store.get_store()?;
         let module_contents = std::fs::read(&self.path)?;
         let module = Module::new(&store, &module_contents)?;
-        println!(
-            "Type: {}",
-            if !is_wasm(&module_contents) {
-                "wat"
-            } else {
-                "wasm"
-            }
-        );
-        println!("Size: {}", ByteSize(module_contents.len() as _));
-        println!("Imports:");
-        println!("  Functions:");
-        for f in module.imports().functions() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        #[cfg(feature = "wat")]
+        let wasm = wat2wasm(&module_contents)?;
+        #[cfg(not(feature = "wat"))]
+        let wasm = Cow::Borrowed(&module_contents[..]);
+        let report = Report::new(&module, &module_contents, &wasm)?;
+        match self.format {
+            Format::Text => print_report(&report),
+            Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
         }
-        println!("  Memories:");
-        for f in module.imports().memories() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
-        }
-        println!("  Tables:");
-        for f in module.imports().tables() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        Ok(())
+    }
+}
+
+fn print_report(report: &Report) {
+    println!("Type: {}", report.kind);
+    println!("Size: {}", ByteSize(report.size));
+    println!("Imports:");
+    for (heading, kind) in EXTERN_KINDS {
+        let mut imports = report.imports.iter().filter(|i| i.kind == kind).peekable();
+        if kind == "tag" && imports.peek().is_none() {
+            continue;
         }
-        println!("  Globals:");
-        for f in module.imports().globals() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("  {}:", heading);
+        for i in imports {
+            println!("    \"{}\".\"{}\": {}", i.module, i.name, i.ty);
         }
-        println!("Exports:");
-        println!("  Functions:");
-        for f in module.exports().functions() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+    println!("Exports:");
+    for (heading, kind) in EXTERN_KINDS {
+        let mut exports = report.exports.iter().filter(|e| e.kind == kind).peekable();
+        if kind == "tag" && exports.peek().is_none() {
+            continue;
         }
-        println!("  Memories:");
-        for f in module.exports().memories() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!("  {}:", heading);
+        for e in exports {
+            println!("    \"{}\": {}", e.name, e.ty);
         }
-        println!("  Tables:");
-        for f in module.exports().tables() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+    match &report.start_function {
+        Some(start) => println!("Start function: {}", function_name(start)),
+        None => println!("Start function: none"),
+    }
+    println!("Functions:");
+    println!("  Imported: {}", report.functions.imported);
+    println!("  Defined: {}", report.functions.defined);
+    println!("  Code size: {}", ByteSize(report.functions.code_size));
+    println!("  Code sizes:");
+    for code in &report.functions.code_sizes {
+        println!(
+            "    {}: {}",
+            function_name(&code.function),
+            ByteSize(code.size)
+        );
+    }
+    println!("Data segments:");
+    for segment in &report.data_segments {
+        match segment.memory {
+            Some(memory) => println!(
+                "  {}: {} (memory {})",
+                segment.index,
+                ByteSize(segment.size),
+                memory
+            ),
+            None => println!("  {}: {} (passive)", segment.index, ByteSize(segment.size)),
         }
-        println!("  Globals:");
-        for f in module.exports().globals() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+    println!("Custom sections:");
+    for section in &report.custom_sections {
+        println!("  \"{}\": {}", section.name, ByteSize(section.size));
+    }
+    if let Some(name) = &report.module_name {
+        println!("Module name: \"{}\"", name);
+    }
+    if !report.producers.is_empty() {
+        println!("Producers:");
+        for (field, tools) in &report.producers {
+            println!("  {}:", field);
+            for (name, version) in tools {
+                if version.is_empty() {
+                    println!("    {}", name);
+                } else {
+                    println!("    {} {}", name, version);
+                }
+            }
         }
-        Ok(())
+    }
+    if !report.target_features.is_empty() {
+        println!("Target features: {}", report.target_features.join(", "));
+    }
+    println!("Features: {}", list(&report.feature_names()));
+    #[cfg(feature = "wasi")]
+    println!("WASI versions: {}", list(&report.wasi_versions));
+}
+
+/// The headings of the imports and exports of each kind. Tags are only
+/// listed when there are some.
+const EXTERN_KINDS: [(&str, &str); 5] = [
+    ("Functions", "function"),
+    ("Memories", "memory"),
+    ("Tables", "table"),
+    ("Globals", "global"),
+    ("Tags", "tag"),
+];
+
+fn function_name(function: &report::Function) -> String {
+    match &function.name {
+        Some(name) => format!("{} (`{}`)", function.index, name),
+        None => function.index.to_string(),
+    }
+}
+
+fn list(items: &[&str]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
     }
 }
@@ -0,0 +1,354 @@
+//! The analysis of a module done by the `inspect` subcommand.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use wasmer::{ExternType, Features, Module};
+use wasmparser::{
+    BinaryReader, DataKind, Name, NameSectionReader, Parser, Payload, ProducersSectionReader,
+    Validator, WasmFeatures,
+};
+
+/// The proposals detected by validating the module without them, by
+/// name, with their flag in the validator and in the [`Features`].
+#[allow(clippy::type_complexity)]
+const PROPOSALS: &[(
+    &str,
+    fn(&mut WasmFeatures) -> &mut bool,
+    fn(&mut Features) -> &mut bool,
+)] = &[
+    ("threads", |f| &mut f.threads, |f| &mut f.threads),
+    (
+        "reference_types",
+        |f| &mut f.reference_types,
+        |f| &mut f.reference_types,
+    ),
+    ("simd", |f| &mut f.simd, |f| &mut f.simd),
+    (
+        "bulk_memory",
+        |f| &mut f.bulk_memory,
+        |f| &mut f.bulk_memory,
+    ),
+    (
+        "multi_value",
+        |f| &mut f.multi_value,
+        |f| &mut f.multi_value,
+    ),
+    ("tail_call", |f| &mut f.tail_call, |f| &mut f.tail_call),
+    (
+        "multi_memory",
+        |f| &mut f.multi_memory,
+        |f| &mut f.multi_memory,
+    ),
+    ("memory64", |f| &mut f.memory64, |f| &mut f.memory64),
+    ("exceptions", |f| &mut f.exceptions, |f| &mut f.exceptions),
+    (
+        "relaxed_simd",
+        |f| &mut f.relaxed_simd,
+        |f| &mut f.relaxed_simd,
+    ),
+    (
+        "extended_const",
+        |f| &mut f.extended_const,
+        |f| &mut f.extended_const,
+    ),
+];
+
+/// Everything `wasmer inspect` reports about a module.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Whether the file is `wasm` or `wat`.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// The size of the file, in bytes.
+    pub size: u64,
+    pub imports: Vec<Import>,
+    pub exports: Vec<Export>,
+    pub start_function: Option<Function>,
+    pub functions: Functions,
+    pub data_segments: Vec<DataSegment>,
+    pub custom_sections: Vec<CustomSection>,
+    /// The name of the module in the `name` section.
+    pub module_name: Option<String>,
+    /// The `producers` section, as the names and versions of the tools
+    /// of each field, like `language` or `processed-by`.
+    pub producers: BTreeMap<String, BTreeMap<String, String>>,
+    /// The `target_features` section, like `+simd128`.
+    pub target_features: Vec<String>,
+    /// The proposals the module needs to be enabled to be valid.
+    pub features: Features,
+    /// The WASI versions whose functions the module imports.
+    #[cfg(feature = "wasi")]
+    pub wasi_versions: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub kind: &'static str,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Export {
+    pub name: String,
+    pub kind: &'static str,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Function {
+    pub index: u32,
+    /// The name of the function in the `name` section.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Functions {
+    pub imported: u32,
+    pub defined: u32,
+    /// The size of the code section, in bytes.
+    pub code_size: u64,
+    /// The size of the code of each defined function.
+    pub code_sizes: Vec<FunctionCode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionCode {
+    #[serde(flatten)]
+    pub function: Function,
+    /// The size of the body of the function, in bytes.
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataSegment {
+    pub index: u32,
+    /// The memory the segment initializes, or `None` for passive segments.
+    pub memory: Option<u32>,
+    /// The size of the data, in bytes.
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomSection {
+    pub name: String,
+    /// The size of the contents of the section, in bytes.
+    pub size: u64,
+}
+
+impl Report {
+    /// Analyzes a module compiled from `contents`, the file it was read
+    /// from, and `wasm`, its binary.
+    pub fn new(module: &Module, contents: &[u8], wasm: &[u8]) -> Result<Self> {
+        let mut report = Self {
+            kind: if wasmer::is_wasm(contents) {
+                "wasm"
+            } else {
+                "wat"
+            },
+            size: contents.len() as u64,
+            imports: module
+                .imports()
+                .map(|import| {
+                    let (kind, ty) = describe(import.ty());
+                    Import {
+                        module: import.module().to_string(),
+                        name: import.name().to_string(),
+                        kind,
+                        ty,
+                    }
+                })
+                .collect(),
+            exports: module
+                .exports()
+                .map(|export| {
+                    let (kind, ty) = describe(export.ty());
+                    Export {
+                        name: export.name().to_string(),
+                        kind,
+                        ty,
+                    }
+                })
+                .collect(),
+            start_function: None,
+            functions: Functions::default(),
+            data_segments: Vec::new(),
+            custom_sections: Vec::new(),
+            module_name: None,
+            producers: BTreeMap::new(),
+            target_features: Vec::new(),
+            features: required_features(wasm)?,
+            #[cfg(feature = "wasi")]
+            wasi_versions: wasmer_wasi::get_wasi_versions(module, false)
+                .unwrap_or_default()
+                .iter()
+                .map(|version| version.get_namespace_str())
+                .collect(),
+        };
+        report.functions.imported = module.imports().functions().count() as u32;
+
+        let mut function_names = BTreeMap::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::StartSection { func, .. } => {
+                    report.start_function = Some(Function {
+                        index: func,
+                        name: None,
+                    });
+                }
+                Payload::CodeSectionStart { count, size, .. } => {
+                    report.functions.defined = count;
+                    report.functions.code_size = size as u64;
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let range = body.range();
+                    report.functions.code_sizes.push(FunctionCode {
+                        function: Function {
+                            index: report.functions.imported
+                                + report.functions.code_sizes.len() as u32,
+                            name: None,
+                        },
+                        size: (range.end - range.start) as u64,
+                    });
+                }
+                Payload::DataSection(reader) => {
+                    for (index, data) in reader.into_iter().enumerate() {
+                        let data = data?;
+                        report.data_segments.push(DataSegment {
+                            index: index as u32,
+                            memory: match data.kind {
+                                DataKind::Active { memory_index, .. } => Some(memory_index),
+                                DataKind::Passive => None,
+                            },
+                            size: data.data.len() as u64,
+                        });
+                    }
+                }
+                Payload::CustomSection(section) => {
+                    let (name, data, data_offset) =
+                        (section.name(), section.data(), section.data_offset());
+                    report.custom_sections.push(CustomSection {
+                        name: name.to_string(),
+                        size: data.len() as u64,
+                    });
+                    match name {
+                        "name" => report.read_names(data, data_offset, &mut function_names)?,
+                        "producers" => report.read_producers(data, data_offset)?,
+                        "target_features" => report.read_target_features(data, data_offset)?,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let functions = report.start_function.iter_mut().chain(
+            report
+                .functions
+                .code_sizes
+                .iter_mut()
+                .map(|code| &mut code.function),
+        );
+        for function in functions {
+            function.name = function_names.get(&function.index).cloned();
+        }
+        Ok(report)
+    }
+
+    fn read_names(
+        &mut self,
+        data: &[u8],
+        offset: usize,
+        function_names: &mut BTreeMap<u32, String>,
+    ) -> Result<()> {
+        let mut reader = NameSectionReader::new(data, offset)?;
+        while !reader.eof() {
+            match reader.read()? {
+                Name::Module(name) => self.module_name = Some(name.get_name()?.to_string()),
+                Name::Function(names) => {
+                    let mut names = names.get_map()?;
+                    for _ in 0..names.get_count() {
+                        let naming = names.read()?;
+                        function_names.insert(naming.index, naming.name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn read_producers(&mut self, data: &[u8], offset: usize) -> Result<()> {
+        let mut reader = ProducersSectionReader::new(data, offset)?;
+        for _ in 0..reader.get_count() {
+            let field = reader.read()?;
+            let tools = self.producers.entry(field.name.to_string()).or_default();
+            for value in field.get_producer_field_values_reader()?.into_iter() {
+                let value = value?;
+                tools.insert(value.name.to_string(), value.version.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the `target_features` section, a vector of features with
+    /// a `+`, `-` or `=` prefix.
+    fn read_target_features(&mut self, data: &[u8], offset: usize) -> Result<()> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        for _ in 0..reader.read_var_u32()? {
+            let prefix = reader.read_u8()? as u8 as char;
+            let feature = reader.read_string()?;
+            self.target_features.push(format!("{}{}", prefix, feature));
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the proposals the module needs.
+    pub fn feature_names(&self) -> Vec<&'static str> {
+        let mut features = self.features.clone();
+        PROPOSALS
+            .iter()
+            .filter(|(_, _, flag)| *flag(&mut features))
+            .map(|(name, _, _)| *name)
+            .collect()
+    }
+}
+
+/// Returns the kind of an import or export, and its type.
+fn describe(ty: &ExternType) -> (&'static str, String) {
+    match ty {
+        ExternType::Function(ty) => ("function", ty.to_string()),
+        ExternType::Global(ty) => ("global", ty.to_string()),
+        ExternType::Table(ty) => ("table", ty.to_string()),
+        ExternType::Memory(ty) => ("memory", ty.to_string()),
+        ExternType::Tag(ty) => ("tag", ty.to_string()),
+    }
+}
+
+/// Finds the proposals the module needs, as the ones it isn't valid
+/// without.
+fn required_features(wasm: &[u8]) -> Result<Features> {
+    let mut all = WasmFeatures::default();
+    for (_, flag, _) in PROPOSALS {
+        *flag(&mut all) = true;
+    }
+    Validator::new_with_features(all).validate_all(wasm)?;
+
+    let mut required = Features::new();
+    for (_, flag, required_flag) in PROPOSALS {
+        *required_flag(&mut required) = false;
+        *flag(&mut all) = false;
+        if Validator::new_with_features(all)
+            .validate_all(wasm)
+            .is_err()
+        {
+            *required_flag(&mut required) = true;
+        }
+        *flag(&mut all) = true;
+    }
+    Ok(required)
+}
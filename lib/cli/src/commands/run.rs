@@ -13,9 +13,12 @@ use wasmer_cache::{Cache, FileSystemCache, Hash};
 
 use structopt::StructOpt;
 
+mod limits;
 #[cfg(feature = "wasi")]
 mod wasi;
 
+use limits::Limits;
+
 #[cfg(feature = "wasi")]
 use wasi::Wasi;
 
@@ -51,6 +54,9 @@ pub struct Run {
     #[structopt(flatten)]
     store: StoreOptions,
 
+    #[structopt(flatten)]
+    limits: Limits,
+
     // TODO: refactor WASI structure to allow shared options with Emscripten
     #[cfg(feature = "wasi")]
     #[structopt(flatten)]
@@ -113,9 +119,11 @@ impl Run {
                 let mut em_env = EmEnv::new(&emscripten_globals.data, Default::default());
                 let import_object =
                     generate_emscripten_env(module.store(), &mut emscripten_globals, &em_env);
+                self.limits.start_timeout(module.store());
                 let mut instance = match Instance::new(&module, &import_object) {
                     Ok(instance) => instance,
                     Err(e) => {
+                        self.limits.exit_if_exceeded(None);
                         let err: Result<(), _> = Err(e);
                         #[cfg(feature = "wasi")]
                         {
@@ -127,7 +135,7 @@ impl Run {
                     }
                 };
 
-                run_emscripten_instance(
+                let result = run_emscripten_instance(
                     &mut instance,
                     &mut em_env,
                     &mut emscripten_globals,
@@ -138,11 +146,17 @@ impl Run {
                     },
                     self.args.iter().map(|arg| arg.as_str()).collect(),
                     None, //run.em_entrypoint.clone(),
-                )?;
+                );
+                if result.is_err() {
+                    self.limits.exit_if_exceeded(Some(&instance));
+                }
+                result?;
                 return Ok(());
             }
         }
 
+        self.limits.start_timeout(module.store());
+
         // If WASI is enabled, try to execute it with it
         #[cfg(feature = "wasi")]
         let instance = {
@@ -178,29 +192,38 @@ impl Run {
                                 .map(|f| f.to_string_lossy().to_string())
                         })
                         .unwrap_or_default();
-                    self.wasi
-                        .instantiate(&module, program_name, self.args.clone())
-                        .with_context(|| "failed to instantiate WASI module")?
+                    let result = self
+                        .wasi
+                        .instantiate(&module, program_name, self.args.clone());
+                    if result.is_err() {
+                        self.limits.exit_if_exceeded(None);
+                    }
+                    result.with_context(|| "failed to instantiate WASI module")?
                 }
                 // not WASI
-                _ => Instance::new(&module, &imports! {})?,
+                _ => self.instantiate(&module)?,
             }
         };
         #[cfg(not(feature = "wasi"))]
-        let instance = Instance::new(&module, &imports! {})?;
+        let instance = self.instantiate(&module)?;
 
         // If this module exports an _initialize function, run that first.
         if let Ok(initialize) = instance.exports.get_function("_initialize") {
-            initialize
-                .call(&[])
-                .with_context(|| "failed to run _initialize function")?;
+            let result = initialize.call(&[]);
+            if result.is_err() {
+                self.limits.exit_if_exceeded(Some(&instance));
+            }
+            result.with_context(|| "failed to run _initialize function")?;
         }
 
         // Do we want to invoke a function?
         if let Some(ref invoke) = self.invoke {
-            let imports = imports! {};
-            let instance = Instance::new(&module, &imports)?;
-            let result = self.invoke_function(&instance, invoke, &self.args)?;
+            let instance = self.instantiate(&module)?;
+            let result = self.invoke_function(&instance, invoke, &self.args);
+            if result.is_err() {
+                self.limits.exit_if_exceeded(Some(&instance));
+            }
+            let result = result?;
             println!(
                 "{}",
                 result
@@ -212,6 +235,14 @@ impl Run {
         } else {
             let start: Function = self.try_find_function(&instance, "_start", &[])?;
             let result = start.call(&[]);
+            // WASI programs that exit report their own errors.
+            #[cfg(feature = "wasi")]
+            let failed = matches!(&result, Err(e) if !e.is::<wasmer_wasi::WasiError>());
+            #[cfg(not(feature = "wasi"))]
+            let failed = result.is_err();
+            if failed {
+                self.limits.exit_if_exceeded(Some(&instance));
+            }
             #[cfg(feature = "wasi")]
             self.wasi.handle_result(result)?;
             #[cfg(not(feature = "wasi"))]
@@ -221,17 +252,39 @@ impl Run {
         Ok(())
     }
 
+    /// Instantiates a module without imports.
+    fn instantiate(&self, module: &Module) -> Result<Instance> {
+        Instance::new(module, &imports! {}).map_err(|e| {
+            self.limits.exit_if_exceeded(None);
+            e.into()
+        })
+    }
+
     fn get_module(&self) -> Result<Module> {
         let contents = std::fs::read(self.path.clone())?;
         if wasmer_compiler::UniversalArtifact::is_deserializable(&contents) {
+            if self.limits.has_fuel() {
+                bail!("--fuel is not supported with precompiled modules");
+            }
             let engine = wasmer_compiler::Universal::headless().engine();
-            let store = Store::new_with_engine(&engine);
+            let store = Store::new_with_tunables(&engine, self.limits.tunables(&Target::default()));
             let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
             return Ok(module);
         }
-        let (store, compiler_type) = self.store.get_store()?;
+        let tunables = self.limits.tunables(&Target::default());
+        #[cfg(feature = "compiler")]
+        let (store, compiler_type) = self
+            .store
+            .get_store_with_tunables(|config| self.limits.configure(config), tunables)?;
+        #[cfg(not(feature = "compiler"))]
+        let (store, compiler_type) = self.store.get_store_with_tunables(tunables)?;
+        // The cache is keyed by the contents of the module only, so it can't
+        // tell apart the modules compiled with and without the limits.
         #[cfg(feature = "cache")]
-        let module_result: Result<Module> = if !self.disable_cache && contents.len() > 0x1000 {
+        let module_result: Result<Module> = if !self.disable_cache
+            && !self.limits.affect_compilation()
+            && contents.len() > 0x1000
+        {
             self.get_module_from_cache(&store, &contents, &compiler_type)
         } else {
             Module::new(&store, &contents).map_err(|e| e.into())
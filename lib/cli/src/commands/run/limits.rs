@@ -0,0 +1,315 @@
+use crate::error::PrettyError;
+use crate::utils::{parse_duration, parse_memory_size};
+use anyhow::anyhow;
+use bytesize::ByteSize;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use wasmer::vm::{
+    self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition,
+};
+use wasmer::{BaseTunables, Instance, MemoryType, Pages, Store, TableType, Target, Tunables};
+#[cfg(feature = "compiler")]
+use wasmer_compiler::CompilerConfig;
+#[cfg(feature = "compiler")]
+use wasmer_middlewares::metering::{get_remaining_points, Metering, MeteringPoints};
+use wasmer_vm::{MemoryImage, WaitQueue};
+
+use structopt::StructOpt;
+
+/// The exit code when the module needs more memory than `--max-memory`.
+const MEMORY_EXCEEDED_EXIT_CODE: i32 = 121;
+/// The exit code when the module runs out of `--fuel`.
+#[cfg(feature = "compiler")]
+const FUEL_EXHAUSTED_EXIT_CODE: i32 = 122;
+/// The exit code when the module runs for longer than `--timeout`, the
+/// same as the `timeout` command.
+const TIMED_OUT_EXIT_CODE: i32 = 124;
+
+/// How long a module blocked in a host function, which doesn't check
+/// for interruptions, has to return after the timeout.
+const TIMEOUT_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, StructOpt, Clone, Default)]
+/// Resource limits of the Wasm module
+pub struct Limits {
+    /// Maximum size of each memory of the Wasm module, like `64MiB`
+    #[structopt(long = "max-memory", name = "MEMORY_SIZE", parse(try_from_str = parse_memory_size))]
+    max_memory: Option<Pages>,
+
+    /// Maximum wall-clock time the Wasm module can run for, like `30s` or `500ms`
+    #[structopt(long = "timeout", name = "DURATION", parse(try_from_str = parse_duration))]
+    timeout: Option<Duration>,
+
+    /// Maximum number of Wasm operators the module can execute
+    #[cfg(feature = "compiler")]
+    #[structopt(long = "fuel", name = "FUEL")]
+    fuel: Option<u64>,
+
+    #[structopt(skip)]
+    memory_exceeded: Arc<AtomicBool>,
+
+    #[structopt(skip)]
+    timed_out: Arc<AtomicBool>,
+}
+
+impl Limits {
+    /// Whether the limits change how the module is compiled.
+    #[cfg(feature = "cache")]
+    pub fn affect_compilation(&self) -> bool {
+        #[cfg(feature = "compiler")]
+        if self.fuel.is_some() {
+            return true;
+        }
+        self.timeout.is_some()
+    }
+
+    /// Whether fuel was given to the module.
+    pub fn has_fuel(&self) -> bool {
+        #[cfg(feature = "compiler")]
+        if self.fuel.is_some() {
+            return true;
+        }
+        false
+    }
+
+    /// Sets up the compiler to check for the fuel and the timeout.
+    #[cfg(feature = "compiler")]
+    pub fn configure(&self, config: &mut dyn CompilerConfig) {
+        if let Some(fuel) = self.fuel {
+            config.push_middleware(Arc::new(Metering::new(fuel, |_| 1)));
+        }
+        if self.timeout.is_some() {
+            config.epoch_interruption(true);
+        }
+    }
+
+    /// Returns the tunables that create the memories capped to `--max-memory`.
+    pub fn tunables(&self, target: &Target) -> LimitingTunables {
+        LimitingTunables {
+            base: BaseTunables::for_target(target),
+            max_memory: self.max_memory,
+            memory_exceeded: self.memory_exceeded.clone(),
+        }
+    }
+
+    /// Starts the clock of `--timeout`.
+    ///
+    /// When the time is up, the Wasm code running in `store` traps. If it
+    /// is still running after a grace period, the process exits.
+    pub fn start_timeout(&self, store: &Store) {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        store.set_epoch_deadline(1);
+        let epoch = store.engine().epoch_counter().clone();
+        let timed_out = self.timed_out.clone();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            timed_out.store(true, Ordering::SeqCst);
+            epoch.increment();
+            thread::sleep(TIMEOUT_GRACE_PERIOD);
+            PrettyError::exit_with(timed_out_error(timeout), TIMED_OUT_EXIT_CODE);
+        });
+    }
+
+    /// Exits the process if the module failed because it hit one of
+    /// the limits.
+    ///
+    /// `instance` is the instance that failed, if it was created.
+    pub fn exit_if_exceeded(&self, instance: Option<&Instance>) {
+        if let Some(timeout) = self.timeout {
+            if self.timed_out.load(Ordering::SeqCst) {
+                PrettyError::exit_with(timed_out_error(timeout), TIMED_OUT_EXIT_CODE);
+            }
+        }
+        #[cfg(feature = "compiler")]
+        if let (Some(fuel), Some(instance)) = (self.fuel, instance) {
+            if get_remaining_points(instance) == MeteringPoints::Exhausted {
+                PrettyError::exit_with(
+                    anyhow!("the module ran out of fuel after {} operators", fuel),
+                    FUEL_EXHAUSTED_EXIT_CODE,
+                );
+            }
+        }
+        #[cfg(not(feature = "compiler"))]
+        let _ = instance;
+        if let Some(max_memory) = self.max_memory {
+            if self.memory_exceeded.load(Ordering::SeqCst) {
+                PrettyError::exit_with(
+                    anyhow!(
+                        "the module needed more memory than the limit of {}",
+                        ByteSize(max_memory.bytes().0 as u64).to_string_as(true)
+                    ),
+                    MEMORY_EXCEEDED_EXIT_CODE,
+                );
+            }
+        }
+    }
+}
+
+fn timed_out_error(timeout: Duration) -> anyhow::Error {
+    anyhow!("the module timed out after {:?}", timeout)
+}
+
+/// Tunables that cap the maximum size of the memories to `--max-memory`,
+/// and record when the module needs more memory than that.
+///
+/// Everything else is delegated to the base tunables.
+pub struct LimitingTunables {
+    base: BaseTunables,
+    max_memory: Option<Pages>,
+    memory_exceeded: Arc<AtomicBool>,
+}
+
+impl LimitingTunables {
+    /// Caps the maximum of the memory type requested by the module.
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        if let Some(max_memory) = self.max_memory {
+            adjusted.maximum = Some(match requested.maximum {
+                Some(maximum) if maximum < max_memory => maximum,
+                _ => max_memory,
+            });
+        }
+        adjusted
+    }
+
+    /// Checks that the minimum of the memory type requested by the
+    /// module fits in the limit.
+    fn validate_memory(&self, requested: &MemoryType) -> Result<(), MemoryError> {
+        match self.max_memory {
+            Some(max_memory) if requested.minimum > max_memory => {
+                self.memory_exceeded.store(true, Ordering::SeqCst);
+                Err(MemoryError::MinimumMemoryTooLarge {
+                    min_requested: requested.minimum,
+                    max_allowed: max_memory,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn limit_memory(
+        &self,
+        requested: &MemoryType,
+        memory: Arc<dyn vm::Memory>,
+    ) -> Arc<dyn vm::Memory> {
+        match self.max_memory {
+            Some(max_memory) => Arc::new(LimitedMemory {
+                memory,
+                requested_maximum: requested.maximum,
+                max_memory,
+                memory_exceeded: self.memory_exceeded.clone(),
+            }),
+            None => memory,
+        }
+    }
+}
+
+impl Tunables for LimitingTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        self.validate_memory(ty)?;
+        let memory = self
+            .base
+            .create_host_memory(&self.adjust_memory(ty), style)?;
+        Ok(self.limit_memory(ty, memory))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        self.validate_memory(ty)?;
+        let memory =
+            self.base
+                .create_vm_memory(&self.adjust_memory(ty), style, vm_definition_location)?;
+        Ok(self.limit_memory(ty, memory))
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+/// A memory capped by `--max-memory`, that records when it fails to
+/// grow past the cap but could have grown without it.
+#[derive(Debug)]
+struct LimitedMemory {
+    memory: Arc<dyn vm::Memory>,
+    requested_maximum: Option<Pages>,
+    max_memory: Pages,
+    memory_exceeded: Arc<AtomicBool>,
+}
+
+impl vm::Memory for LimitedMemory {
+    fn ty(&self) -> MemoryType {
+        self.memory.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.memory.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.memory.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let size = self.memory.size();
+        let result = self.memory.grow(delta);
+        if result.is_err() {
+            let attempted = size.0 as u64 + delta.0 as u64;
+            let allowed_by_module = match self.requested_maximum {
+                Some(maximum) => attempted <= maximum.0 as u64,
+                None => true,
+            };
+            if attempted > self.max_memory.0 as u64 && allowed_by_module {
+                self.memory_exceeded.store(true, Ordering::SeqCst);
+            }
+        }
+        result
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.memory.vmmemory()
+    }
+
+    fn wait_queue(&self) -> Option<&WaitQueue> {
+        self.memory.wait_queue()
+    }
+
+    fn initialize_with_image(&self, image: &MemoryImage) -> Result<bool, MemoryError> {
+        self.memory.initialize_with_image(image)
+    }
+}
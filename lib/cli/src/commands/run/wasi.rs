@@ -1,9 +1,19 @@
-use crate::utils::{parse_envvar, parse_mapdir};
-use anyhow::Result;
+use crate::utils::{parse_envvar, parse_mapdir, parse_mount_archive};
+use crate::warning;
+use anyhow::{Context, Result};
 use std::collections::BTreeSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 use wasmer::{Instance, Module, RuntimeError, Val};
-use wasmer_wasi::{get_wasi_versions, is_wasix_module, WasiError, WasiState, WasiVersion};
+use wasmer_vfs::{archive_fs::ArchiveFileSystem, host_fs, FileSystem, FsError};
+use wasmer_vfs::{Metadata, OpenOptions, OpenOptionsConfig, ReadDir, VirtualFile};
+use wasmer_wasi::types::__WASI_SIGINT;
+use wasmer_wasi::{
+    get_wasi_versions, is_wasix_module, signal_exit_code, WasiEnv, WasiError, WasiFsLimits,
+    WasiState, WasiVersion,
+};
 
 use structopt::StructOpt;
 
@@ -30,6 +40,16 @@ pub struct Wasi {
     )]
     mapped_dirs: Vec<(String, PathBuf)>,
 
+    /// Mount a tar, tar.gz or zip archive read-only at a location of the Wasm module
+    #[structopt(
+        long = "mount-archive",
+        name = "GUEST_DIR:ARCHIVE",
+        multiple = true,
+        parse(try_from_str = parse_mount_archive),
+        number_of_values = 1,
+    )]
+    mounted_archives: Vec<(String, PathBuf)>,
+
     /// Pass custom environment variables
     #[structopt(
         long = "env",
@@ -39,6 +59,22 @@ pub struct Wasi {
     )]
     env_vars: Vec<(String, String)>,
 
+    /// Maximum number of bytes the Wasm module can write to files
+    #[structopt(long = "max-bytes-written", name = "BYTES")]
+    max_bytes_written: Option<u64>,
+
+    /// Maximum number of files and directories the Wasm module can track
+    #[structopt(long = "max-inodes", name = "INODES")]
+    max_inodes: Option<usize>,
+
+    /// Maximum number of file descriptors the Wasm module can have open
+    #[structopt(long = "max-open-fds", name = "FDS")]
+    max_open_fds: Option<usize>,
+
+    /// Maximum size of a file written by the Wasm module, in bytes
+    #[structopt(long = "max-file-size", name = "SIZE")]
+    max_file_size: Option<u64>,
+
     /// Enable experimental IO devices
     #[cfg(feature = "experimental-io-devices")]
     #[cfg_attr(
@@ -86,7 +122,29 @@ impl Wasi {
             .args(args)
             .envs(self.env_vars.clone())
             .preopen_dirs(self.pre_opened_directories.clone())?
-            .map_dirs(self.mapped_dirs.clone())?;
+            .map_dirs(self.mapped_dirs.clone())?
+            .fs_limits(WasiFsLimits {
+                max_bytes_written: self.max_bytes_written,
+                max_inodes: self.max_inodes,
+                max_open_fds: self.max_open_fds,
+                max_file_size: self.max_file_size,
+            });
+
+        if !self.mounted_archives.is_empty() {
+            let mut archives = Vec::new();
+            for (alias, archive) in &self.mounted_archives {
+                let fs = ArchiveFileSystem::open(archive)
+                    .with_context(|| format!("failed to read archive {}", archive.display()))?;
+                // The archive is served below its own path, which can't be
+                // a directory on the host
+                wasi_state_builder.preopen(|p| p.directory(archive).alias(alias).read(true))?;
+                archives.push((archive.clone(), fs));
+            }
+            wasi_state_builder.set_fs(Box::new(ArchiveMounts {
+                host: host_fs::FileSystem,
+                archives,
+            }));
+        }
 
         #[cfg(feature = "experimental-io-devices")]
         {
@@ -104,6 +162,10 @@ impl Wasi {
 
         let import_object = wasi_env.import_object_for_all_wasi_versions(module)?;
         let instance = Instance::new(module, &import_object)?;
+        forward_interrupts(
+            wasi_env,
+            instance.exports.get_function("__wasm_signal").is_ok(),
+        );
         Ok(instance)
     }
 
@@ -138,3 +200,138 @@ impl Wasi {
         })
     }
 }
+
+/// How long a program that handles signals has to take Ctrl-C. Signals are
+/// only delivered when the program calls into WASI, which a compute-bound
+/// program may never do.
+const INTERRUPT_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// Delivers Ctrl-C to the program as `SIGINT` when it handles signals, the
+/// program is terminated right away otherwise or if Ctrl-C is pressed again
+/// before it got the first one. It's terminated as well if it didn't take
+/// the signal within [`INTERRUPT_GRACE_PERIOD`].
+fn forward_interrupts(wasi_env: WasiEnv, handles_signals: bool) {
+    let interrupted = AtomicBool::new(false);
+    let result = ctrlc::set_handler(move || {
+        if !handles_signals || interrupted.swap(true, Ordering::AcqRel) {
+            std::process::exit(signal_exit_code(__WASI_SIGINT) as _);
+        }
+        wasi_env.signal(__WASI_SIGINT);
+
+        let wasi_env = wasi_env.clone();
+        thread::spawn(move || {
+            thread::sleep(INTERRUPT_GRACE_PERIOD);
+            if wasi_env.has_pending_signals() {
+                std::process::exit(signal_exit_code(__WASI_SIGINT) as _);
+            }
+        });
+    });
+    if let Err(err) = result {
+        warning!("failed to forward Ctrl-C to the program: {}", err);
+    }
+}
+
+/// The host file system, except below the paths of the mounted archives
+/// which are served by the archives themselves.
+#[derive(Debug, Clone)]
+struct ArchiveMounts {
+    host: host_fs::FileSystem,
+    archives: Vec<(PathBuf, ArchiveFileSystem)>,
+}
+
+impl ArchiveMounts {
+    /// Returns the file system `path` belongs to, and the path in it.
+    fn route(&self, path: &Path) -> (&dyn FileSystem, PathBuf) {
+        self.archives
+            .iter()
+            .find_map(|(archive, fs)| {
+                let path = path.strip_prefix(archive).ok()?;
+                Some((fs as &dyn FileSystem, Path::new("/").join(path)))
+            })
+            .unwrap_or((&self.host, path.to_path_buf()))
+    }
+}
+
+impl FileSystem for ArchiveMounts {
+    fn read_dir(&self, path: &Path) -> wasmer_vfs::Result<ReadDir> {
+        let (fs, inner_path) = self.route(path);
+        // The entries must be named after the path that was asked for
+        let entries = fs
+            .read_dir(&inner_path)?
+            .map(|entry| {
+                entry.map(|mut entry| {
+                    entry.path = path.join(entry.file_name());
+                    entry
+                })
+            })
+            .collect::<wasmer_vfs::Result<_>>()?;
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, path: &Path) -> wasmer_vfs::Result<()> {
+        let (fs, path) = self.route(path);
+        fs.create_dir(&path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> wasmer_vfs::Result<()> {
+        let (fs, path) = self.route(path);
+        fs.remove_dir(&path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> wasmer_vfs::Result<()> {
+        match (self.route(from), self.route(to)) {
+            ((from_fs, from), (to_fs, to)) if std::ptr::eq(from_fs, to_fs) => {
+                from_fs.rename(&from, &to)
+            }
+            // Nothing can be moved in or out of an archive
+            _ => Err(FsError::PermissionDenied),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> wasmer_vfs::Result<Metadata> {
+        let (fs, path) = self.route(path);
+        fs.metadata(&path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> wasmer_vfs::Result<Metadata> {
+        let (fs, path) = self.route(path);
+        fs.symlink_metadata(&path)
+    }
+
+    fn remove_file(&self, path: &Path) -> wasmer_vfs::Result<()> {
+        let (fs, path) = self.route(path);
+        fs.remove_file(&path)
+    }
+
+    fn symlink(&self, original: &Path, link: &Path) -> wasmer_vfs::Result<()> {
+        let (fs, link) = self.route(link);
+        fs.symlink(original, &link)
+    }
+
+    fn readlink(&self, path: &Path) -> wasmer_vfs::Result<PathBuf> {
+        let (fs, path) = self.route(path);
+        fs.readlink(&path)
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(Box::new(ArchiveMountsOpener {
+            mounts: self.clone(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct ArchiveMountsOpener {
+    mounts: ArchiveMounts,
+}
+
+impl wasmer_vfs::FileOpener for ArchiveMountsOpener {
+    fn open(
+        &mut self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> wasmer_vfs::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let (fs, path) = self.mounts.route(path);
+        fs.new_open_options().options(conf.clone()).open(&path)
+    }
+}
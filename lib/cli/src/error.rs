@@ -24,12 +24,15 @@ impl PrettyError {
     pub fn report<T>(result: Result<T, Error>) -> ! {
         std::process::exit(match result {
             Ok(_t) => 0,
-            Err(error) => {
-                eprintln!("{:?}", PrettyError { error });
-                1
-            }
+            Err(error) => Self::exit_with(error, 1),
         });
     }
+
+    /// Print an error and exit the process with the given exit code
+    pub fn exit_with(error: Error, exit_code: i32) -> ! {
+        eprintln!("{:?}", PrettyError { error });
+        std::process::exit(exit_code);
+    }
 }
 
 impl Debug for PrettyError {
@@ -317,6 +317,20 @@ impl StoreOptions {
         Ok((store, compiler_type))
     }
 
+    /// Gets the store for the host target, with the compiler config
+    /// adjusted by `configure` and the given tunables.
+    pub fn get_store_with_tunables(
+        &self,
+        configure: impl FnOnce(&mut dyn CompilerConfig),
+        tunables: impl Tunables + Send + Sync + 'static,
+    ) -> Result<(Store, CompilerType)> {
+        let (mut compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        configure(&mut *compiler_config);
+        let engine = self.get_engine_with_compiler(Target::default(), compiler_config)?;
+        let store = Store::new_with_tunables(&*engine, tunables);
+        Ok((store, compiler_type))
+    }
+
     fn get_engine_with_compiler(
         &self,
         target: Target,
@@ -343,4 +357,14 @@ impl StoreOptions {
         let store = Store::new_with_engine(&*engine);
         Ok((store, CompilerType::Headless))
     }
+
+    /// Get the store (headless engine) with the given tunables
+    pub fn get_store_with_tunables(
+        &self,
+        tunables: impl Tunables + Send + Sync + 'static,
+    ) -> Result<(Store, CompilerType)> {
+        let engine = self.get_engine_headless()?;
+        let store = Store::new_with_tunables(&*engine, tunables);
+        Ok((store, CompilerType::Headless))
+    }
 }
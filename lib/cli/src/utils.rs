@@ -1,7 +1,10 @@
 //! Utility functions for the WebAssembly module
 use anyhow::{bail, Result};
+use bytesize::ByteSize;
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
+use wasmer::{Pages, WASM_MAX_PAGES, WASM_PAGE_SIZE};
 
 /// Whether or not Wasmer should print with color
 pub fn wasmer_should_print_color() -> bool {
@@ -40,6 +43,28 @@ pub fn parse_mapdir(entry: &str) -> Result<(String, PathBuf)> {
     }
 }
 
+/// Parses an archive mount from a string
+pub fn parse_mount_archive(entry: &str) -> Result<(String, PathBuf)> {
+    // The guest directory comes first, so the archive path after it may
+    // contain a `:` itself, like Windows paths do
+    let (alias, archive) = match entry.split_once("::").or_else(|| entry.split_once(':')) {
+        Some((alias, archive)) if !alias.is_empty() && !archive.is_empty() => (alias, archive),
+        _ => bail!(
+            "Archive mounts must consist of two paths separate by a `::` or `:`. Found {}",
+            &entry
+        ),
+    };
+
+    let pb = PathBuf::from(&archive);
+    match pb.metadata() {
+        Ok(metadata) if !metadata.is_file() => {
+            bail!("\"{}\" exists, but it is not a file", &archive)
+        }
+        Ok(_) => Ok((alias.to_string(), pb)),
+        Err(_) => bail!("Archive \"{}\" does not exist", &archive),
+    }
+}
+
 /// Parses an environment variable.
 pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
     let entry = entry.trim();
@@ -64,9 +89,60 @@ pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
     }
 }
 
+/// Parses a size, like `64MiB`, into bytes.
+pub fn parse_size(entry: &str) -> Result<u64> {
+    match entry.trim().parse::<ByteSize>() {
+        Ok(size) => Ok(size.as_u64()),
+        Err(_) => bail!(
+            "Size must be a number of bytes with an optional unit, like `64MiB`; found `{}`",
+            &entry
+        ),
+    }
+}
+
+/// Parses a memory size, like `64MiB`, into whole Wasm pages.
+pub fn parse_memory_size(entry: &str) -> Result<Pages> {
+    let pages = parse_size(entry)? / WASM_PAGE_SIZE as u64;
+    if pages > WASM_MAX_PAGES as u64 {
+        bail!("Memory size can't be greater than 4GiB; found `{}`", &entry);
+    }
+    Ok(Pages(pages as u32))
+}
+
+/// Parses a duration, like `500ms`, `30s`, `2m`, `1h` or `7d`. Durations
+/// without a unit are in seconds.
+pub fn parse_duration(entry: &str) -> Result<Duration> {
+    let entry = entry.trim();
+    let split = entry
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(entry.len());
+    let (value, unit) = entry.split_at(split);
+    let value: u64 = match value.parse() {
+        Ok(value) => value,
+        Err(_) => bail!(
+            "Duration must be a number with an optional unit, like `30s`; found `{}`",
+            &entry
+        ),
+    };
+    Ok(match unit {
+        "ms" => Duration::from_millis(value),
+        "" | "s" => Duration::from_secs(value),
+        "m" => Duration::from_secs(value.saturating_mul(60)),
+        "h" => Duration::from_secs(value.saturating_mul(60 * 60)),
+        "d" => Duration::from_secs(value.saturating_mul(24 * 60 * 60)),
+        _ => bail!(
+            "Duration unit must be one of `ms`, `s`, `m`, `h` or `d`; found `{}`",
+            &entry
+        ),
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_envvar;
+    use super::{parse_duration, parse_envvar, parse_memory_size, parse_mount_archive, parse_size};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use wasmer::Pages;
 
     #[test]
     fn test_parse_envvar() {
@@ -89,4 +165,67 @@ mod tests {
             ("A".into(), "B=C=D".into())
         );
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("2KiB").unwrap(), 2048);
+        assert_eq!(parse_size("1 GB").unwrap(), 1_000_000_000);
+        assert_eq!(
+            parse_size("big").unwrap_err().to_string(),
+            "Size must be a number of bytes with an optional unit, like `64MiB`; found `big`"
+        );
+    }
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!(parse_memory_size("64KiB").unwrap(), Pages(1));
+        assert_eq!(parse_memory_size("64MiB").unwrap(), Pages(1024));
+        assert_eq!(parse_memory_size("100000").unwrap(), Pages(1));
+        assert_eq!(parse_memory_size("4GiB").unwrap(), Pages(65536));
+        assert_eq!(
+            parse_memory_size("5GiB").unwrap_err().to_string(),
+            "Memory size can't be greater than 4GiB; found `5GiB`"
+        );
+        assert!(parse_memory_size("lots").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604800));
+        assert_eq!(
+            parse_duration("1w").unwrap_err().to_string(),
+            "Duration unit must be one of `ms`, `s`, `m`, `h` or `d`; found `1w`"
+        );
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn test_parse_mount_archive() {
+        let archive = std::env::temp_dir().join("wasmer-cli-parse-mount-archive.tar");
+        std::fs::write(&archive, b"").unwrap();
+        let path = archive.to_str().unwrap();
+
+        for entry in [format!("/data:{}", path), format!("/data::{}", path)] {
+            assert_eq!(
+                parse_mount_archive(&entry).unwrap(),
+                ("/data".to_string(), PathBuf::from(path))
+            );
+        }
+        // The archive path keeps the `:` of Windows drives.
+        assert_eq!(
+            parse_mount_archive("/data:C:\\archive.tar")
+                .unwrap_err()
+                .to_string(),
+            "Archive \"C:\\archive.tar\" does not exist"
+        );
+        assert!(parse_mount_archive(path).is_err());
+
+        std::fs::remove_file(&archive).unwrap();
+    }
 }
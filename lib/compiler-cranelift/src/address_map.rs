@@ -3,12 +3,12 @@
 
 use cranelift_codegen::Context;
 use cranelift_codegen::MachSrcLoc;
-use wasmer_compiler::wasmparser::Range;
+use std::ops::Range;
 use wasmer_types::{FunctionAddressMap, InstructionAddressMap, SourceLoc};
 
 pub fn get_function_address_map(
     context: &Context,
-    range: Range,
+    range: Range<usize>,
     body_len: usize,
 ) -> FunctionAddressMap {
     let mut instructions = Vec::new();
@@ -23,8 +23,8 @@ use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use std::sync::Arc;
 use wasmer_compiler::{CallingConvention, ModuleTranslationState, Target};
 use wasmer_compiler::{
-    Compiler, FunctionBinaryReader, FunctionBodyData, MiddlewareBinaryReader, ModuleMiddleware,
-    ModuleMiddlewareChain,
+    Compiler, FuelMetering, FunctionBinaryReader, FunctionBodyData, MiddlewareBinaryReader,
+    ModuleMiddleware, ModuleMiddlewareChain,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
@@ -58,6 +58,10 @@ impl Compiler for CraneliftCompiler {
         &self.config.middlewares
     }
 
+    fn get_fuel_metering(&self) -> Option<&FuelMetering> {
+        self.config.fuel.as_ref()
+    }
+
     /// Compile the module using Cranelift, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -106,6 +110,10 @@ impl Compiler for CraneliftCompiler {
         };
 
         let mut custom_sections = PrimaryMap::new();
+        let epoch_interruption = self.config().epoch_interruption;
+        let fuel = self.config().fuel.as_ref();
+        let exceptions = compile_info.features.exceptions;
+        let tail_call = compile_info.features.tail_call;
 
         #[cfg(not(feature = "rayon"))]
         let mut func_translator = FuncTranslator::new();
@@ -116,6 +124,11 @@ impl Compiler for CraneliftCompiler {
             .into_iter()
             .map(|(i, input)| {
                 let func_index = module.func_index(i);
+                let tail_calls = if tail_call && input.makes_tail_calls()? {
+                    Some(i)
+                } else {
+                    None
+                };
                 let mut context = Context::new();
                 let mut func_env = FuncEnvironment::new(
                     isa.frontend_config(),
@@ -123,6 +136,10 @@ impl Compiler for CraneliftCompiler {
                     &signatures,
                     &memory_styles,
                     &table_styles,
+                    epoch_interruption,
+                    fuel.map(FuelMetering::function_fuel),
+                    exceptions,
+                    tail_calls,
                 );
                 context.func.name = get_function_name(func_index);
                 context.func.signature = signatures[module.functions[func_index]].clone();
@@ -217,6 +234,11 @@ impl Compiler for CraneliftCompiler {
             .par_iter()
             .map_init(FuncTranslator::new, |func_translator, (i, input)| {
                 let func_index = module.func_index(*i);
+                let tail_calls = if tail_call && input.makes_tail_calls()? {
+                    Some(*i)
+                } else {
+                    None
+                };
                 let mut context = Context::new();
                 let mut func_env = FuncEnvironment::new(
                     isa.frontend_config(),
@@ -224,6 +246,10 @@ impl Compiler for CraneliftCompiler {
                     &signatures,
                     memory_styles,
                     table_styles,
+                    epoch_interruption,
+                    fuel.map(FuelMetering::function_fuel),
+                    exceptions,
+                    tail_calls,
                 );
                 context.func.name = get_function_name(func_index);
                 context.func.signature = signatures[module.functions[func_index]].clone();
@@ -436,7 +462,8 @@ fn translate_ir_trapcode(trap: ir::TrapCode) -> TrapCode {
     match trap {
         ir::TrapCode::StackOverflow => TrapCode::StackOverflow,
         ir::TrapCode::HeapOutOfBounds => TrapCode::HeapAccessOutOfBounds,
-        ir::TrapCode::HeapMisaligned => TrapCode::HeapMisaligned,
+        // The translator only checks the alignment of atomic accesses
+        ir::TrapCode::HeapMisaligned => TrapCode::UnalignedAtomic,
         ir::TrapCode::TableOutOfBounds => TrapCode::TableAccessOutOfBounds,
         ir::TrapCode::IndirectCallToNull => TrapCode::IndirectCallToNull,
         ir::TrapCode::BadSignature => TrapCode::BadSignature,
@@ -444,9 +471,11 @@ fn translate_ir_trapcode(trap: ir::TrapCode) -> TrapCode {
         ir::TrapCode::IntegerDivisionByZero => TrapCode::IntegerDivisionByZero,
         ir::TrapCode::BadConversionToInteger => TrapCode::BadConversionToInteger,
         ir::TrapCode::UnreachableCodeReached => TrapCode::UnreachableCodeReached,
-        ir::TrapCode::Interrupt => unimplemented!("Interrupts not supported"),
+        ir::TrapCode::Interrupt => TrapCode::Interrupt,
+        ir::TrapCode::User(user_code) if user_code == TrapCode::OutOfFuel as u16 => {
+            TrapCode::OutOfFuel
+        }
         ir::TrapCode::User(_user_code) => unimplemented!("User trap code not supported"),
-        // ir::TrapCode::Interrupt => TrapCode::Interrupt,
         // ir::TrapCode::User(user_code) => TrapCode::User(user_code),
     }
 }
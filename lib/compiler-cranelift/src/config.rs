@@ -4,7 +4,7 @@ use cranelift_codegen::settings::{self, Configurable};
 use cranelift_codegen::CodegenResult;
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
+    Architecture, Compiler, CompilerConfig, CpuFeature, FuelMetering, ModuleMiddleware, Target,
 };
 
 // Runtime Environment
@@ -33,6 +33,8 @@ pub struct Cranelift {
     enable_nan_canonicalization: bool,
     enable_verifier: bool,
     enable_pic: bool,
+    pub(crate) epoch_interruption: bool,
+    pub(crate) fuel: Option<FuelMetering>,
     opt_level: CraneliftOptLevel,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
@@ -47,6 +49,8 @@ impl Cranelift {
             enable_verifier: false,
             opt_level: CraneliftOptLevel::Speed,
             enable_pic: false,
+            epoch_interruption: false,
+            fuel: None,
             middlewares: vec![],
         }
     }
@@ -60,6 +64,25 @@ impl Cranelift {
         self
     }
 
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the engine's epoch against the store's deadline
+    /// on function entry and at loop headers, and traps once the deadline
+    /// is reached.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.epoch_interruption = enable;
+        self
+    }
+
+    /// Enable fuel metering, or disable it with `None`.
+    ///
+    /// Compiled code charges the fuel of the instance at the end of every
+    /// basic block, and traps once there isn't enough left.
+    pub fn fuel_metering(&mut self, fuel: Option<FuelMetering>) -> &mut Self {
+        self.fuel = fuel;
+        self
+    }
+
     /// The optimization levels when optimizing the IR.
     pub fn opt_level(&mut self, opt_level: CraneliftOptLevel) -> &mut Self {
         self.opt_level = opt_level;
@@ -199,6 +222,14 @@ impl CompilerConfig for Cranelift {
         self.enable_nan_canonicalization = enable;
     }
 
+    fn epoch_interruption(&mut self, enable: bool) {
+        self.epoch_interruption = enable;
+    }
+
+    fn fuel_metering(&mut self, fuel: Option<FuelMetering>) {
+        self.fuel = fuel;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(CraneliftCompiler::new(*self))
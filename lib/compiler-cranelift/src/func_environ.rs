@@ -2,28 +2,32 @@
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
 use crate::translator::{
-    type_to_irtype, FuncEnvironment as BaseFuncEnvironment, GlobalVariable, TargetEnvironment,
+    type_to_irtype, FuncEnvironment as BaseFuncEnvironment, FuncTranslationState, GlobalVariable,
+    TargetEnvironment,
 };
 use cranelift_codegen::cursor::FuncCursor;
 use cranelift_codegen::ir;
 use cranelift_codegen::ir::condcodes::*;
 use cranelift_codegen::ir::immediates::{Offset32, Uimm64};
 use cranelift_codegen::ir::types::*;
-use cranelift_codegen::ir::{AbiParam, ArgumentPurpose, Function, InstBuilder, Signature};
+use cranelift_codegen::ir::{
+    AbiParam, ArgumentPurpose, Function, InstBuilder, Signature, StackSlotData, StackSlotKind,
+};
 use cranelift_codegen::isa::TargetFrontendConfig;
 use cranelift_frontend::{FunctionBuilder, Variable};
 use std::convert::TryFrom;
-use wasmer_compiler::wasmparser::Type;
+use wasmer_compiler::wasmparser::{Operator, ValType};
+use wasmer_compiler::FunctionFuel;
 use wasmer_types::entity::EntityRef;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::VMBuiltinFunctionIndex;
 use wasmer_types::VMOffsets;
 use wasmer_types::{
     FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, ModuleInfo,
-    SignatureIndex, TableIndex, Type as WasmerType,
+    SignatureIndex, TableIndex, TagIndex, Type as WasmerType,
 };
 use wasmer_types::{MemoryStyle, TableStyle};
-use wasmer_types::{WasmError, WasmResult};
+use wasmer_types::{TrapCode, WasmError, WasmResult};
 
 /// Compute an `ir::ExternalName` for a given wasm function index.
 pub fn get_function_name(func_index: FunctionIndex) -> ir::ExternalName {
@@ -82,6 +86,10 @@ pub struct FuncEnvironment<'module_environment> {
     /// (it's the same for both local and imported memories).
     memory_copy_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's `memory.copy`
+    /// between two different memories.
+    memory_copy_between_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `memory.fill`
     /// (it's the same for both local and imported memories).
     memory_fill_sig: Option<ir::SigRef>,
@@ -89,6 +97,21 @@ pub struct FuncEnvironment<'module_environment> {
     /// The external function signature for implementing wasm's `memory.init`.
     memory_init_sig: Option<ir::SigRef>,
 
+    /// The external function signature for implementing wasm's
+    /// `memory.atomic.wait32` (it's the same for both local and imported
+    /// memories).
+    memory_atomic_wait32_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's
+    /// `memory.atomic.wait64` (it's the same for both local and imported
+    /// memories).
+    memory_atomic_wait64_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's
+    /// `memory.atomic.notify` (it's the same for both local and imported
+    /// memories).
+    memory_atomic_notify_sig: Option<ir::SigRef>,
+
     /// The external function signature for implementing wasm's `data.drop`.
     data_drop_sig: Option<ir::SigRef>,
 
@@ -109,6 +132,38 @@ pub struct FuncEnvironment<'module_environment> {
 
     /// The external function signature for implementing reference decrement for `extern.ref`.
     externref_dec_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `throw`.
+    throw_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `rethrow`.
+    rethrow_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `catch`.
+    catch_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `catch_all`.
+    catch_all_sig: Option<ir::SigRef>,
+
+    /// The external function signature for checking whether an exception
+    /// is pending in the running call.
+    exception_pending_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's `return_call`.
+    return_call_sig: Option<ir::SigRef>,
+
+    /// The external function signature for implementing wasm's
+    /// `return_call_indirect`.
+    return_call_indirect_sig: Option<ir::SigRef>,
+
+    /// The external function signature for making the tail call requested
+    /// by a callee.
+    tail_call_sig: Option<ir::SigRef>,
+
+    /// The external function signature for checking, on entry, whether the
+    /// tail call loop called the function.
+    tail_call_entry_sig: Option<ir::SigRef>,
+
     /// Offsets to struct fields accessed by JIT code.
     offsets: VMOffsets,
 
@@ -117,6 +172,24 @@ pub struct FuncEnvironment<'module_environment> {
 
     /// The table styles
     table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
+
+    /// Whether to check the epoch deadline on function entry and at loop
+    /// headers.
+    epoch_interruption: bool,
+
+    /// The cost of the current basic block, if fuel metering is enabled.
+    fuel: Option<FunctionFuel>,
+
+    /// Whether the exception handling proposal is enabled, so that calls
+    /// can return with an exception pending.
+    exceptions: bool,
+
+    /// The function being translated, if it makes tail calls.
+    tail_calls: Option<LocalFunctionIndex>,
+
+    /// Whether the tail call loop called the function, checked on entry if
+    /// it makes tail calls.
+    called_by_tail_call_loop: Option<ir::Value>,
 }
 
 impl<'module_environment> FuncEnvironment<'module_environment> {
@@ -126,6 +199,10 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         signatures: &'module_environment PrimaryMap<SignatureIndex, ir::Signature>,
         memory_styles: &'module_environment PrimaryMap<MemoryIndex, MemoryStyle>,
         table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
+        epoch_interruption: bool,
+        fuel: Option<FunctionFuel>,
+        exceptions: bool,
+        tail_calls: Option<LocalFunctionIndex>,
     ) -> Self {
         Self {
             target_config,
@@ -141,8 +218,12 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             table_init_sig: None,
             elem_drop_sig: None,
             memory_copy_sig: None,
+            memory_copy_between_sig: None,
             memory_fill_sig: None,
             memory_init_sig: None,
+            memory_atomic_wait32_sig: None,
+            memory_atomic_wait64_sig: None,
+            memory_atomic_notify_sig: None,
             table_get_sig: None,
             table_set_sig: None,
             data_drop_sig: None,
@@ -150,9 +231,23 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             table_fill_sig: None,
             externref_inc_sig: None,
             externref_dec_sig: None,
+            throw_sig: None,
+            rethrow_sig: None,
+            catch_sig: None,
+            catch_all_sig: None,
+            exception_pending_sig: None,
+            return_call_sig: None,
+            return_call_indirect_sig: None,
+            tail_call_sig: None,
+            tail_call_entry_sig: None,
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             memory_styles,
             table_styles,
+            epoch_interruption,
+            fuel,
+            exceptions,
+            tail_calls,
+            called_by_tail_call_loop: None,
         }
     }
 
@@ -639,6 +734,33 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         }
     }
 
+    fn get_memory_copy_between_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.memory_copy_between_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Destination memory index.
+                    AbiParam::new(I32),
+                    // Source memory index.
+                    AbiParam::new(I32),
+                    // Destination address.
+                    AbiParam::new(I32),
+                    // Source address.
+                    AbiParam::new(I32),
+                    // Length.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory_copy_between_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_memory_copy_between_index())
+    }
+
     fn get_memory_fill_sig(&mut self, func: &mut Function) -> ir::SigRef {
         let sig = self.memory_fill_sig.unwrap_or_else(|| {
             func.import_signature(Signature {
@@ -682,6 +804,133 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         }
     }
 
+    fn get_memory_atomic_wait32_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory_atomic_wait32_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Address.
+                    AbiParam::new(I32),
+                    // Expected value.
+                    AbiParam::new(I32),
+                    // Timeout.
+                    AbiParam::new(I64),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory_atomic_wait32_sig = Some(sig);
+        sig
+    }
+
+    fn get_memory_atomic_wait32_func(
+        &mut self,
+        func: &mut Function,
+        memory_index: MemoryIndex,
+    ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        let sig = self.get_memory_atomic_wait32_sig(func);
+        if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+            (
+                sig,
+                local_memory_index.index(),
+                VMBuiltinFunctionIndex::get_memory_atomic_wait32_index(),
+            )
+        } else {
+            (
+                sig,
+                memory_index.index(),
+                VMBuiltinFunctionIndex::get_imported_memory_atomic_wait32_index(),
+            )
+        }
+    }
+
+    fn get_memory_atomic_wait64_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory_atomic_wait64_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Address.
+                    AbiParam::new(I32),
+                    // Expected value.
+                    AbiParam::new(I64),
+                    // Timeout.
+                    AbiParam::new(I64),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory_atomic_wait64_sig = Some(sig);
+        sig
+    }
+
+    fn get_memory_atomic_wait64_func(
+        &mut self,
+        func: &mut Function,
+        memory_index: MemoryIndex,
+    ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        let sig = self.get_memory_atomic_wait64_sig(func);
+        if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+            (
+                sig,
+                local_memory_index.index(),
+                VMBuiltinFunctionIndex::get_memory_atomic_wait64_index(),
+            )
+        } else {
+            (
+                sig,
+                memory_index.index(),
+                VMBuiltinFunctionIndex::get_imported_memory_atomic_wait64_index(),
+            )
+        }
+    }
+
+    fn get_memory_atomic_notify_sig(&mut self, func: &mut Function) -> ir::SigRef {
+        let sig = self.memory_atomic_notify_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Memory index.
+                    AbiParam::new(I32),
+                    // Address.
+                    AbiParam::new(I32),
+                    // Count.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.memory_atomic_notify_sig = Some(sig);
+        sig
+    }
+
+    fn get_memory_atomic_notify_func(
+        &mut self,
+        func: &mut Function,
+        memory_index: MemoryIndex,
+    ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
+        let sig = self.get_memory_atomic_notify_sig(func);
+        if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+            (
+                sig,
+                local_memory_index.index(),
+                VMBuiltinFunctionIndex::get_memory_atomic_notify_index(),
+            )
+        } else {
+            (
+                sig,
+                memory_index.index(),
+                VMBuiltinFunctionIndex::get_imported_memory_atomic_notify_index(),
+            )
+        }
+    }
+
     fn get_memory_init_sig(&mut self, func: &mut Function) -> ir::SigRef {
         let sig = self.memory_init_sig.unwrap_or_else(|| {
             func.import_signature(Signature {
@@ -735,6 +984,214 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         (sig, VMBuiltinFunctionIndex::get_data_drop_index())
     }
 
+    fn get_throw_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.throw_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Tag index.
+                    AbiParam::new(I32),
+                    // Values.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.throw_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_throw_index())
+    }
+
+    fn get_rethrow_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.rethrow_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Exception.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.rethrow_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_rethrow_index())
+    }
+
+    fn get_catch_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.catch_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Tag index.
+                    AbiParam::new(I32),
+                    // Values.
+                    AbiParam::new(self.pointer_type()),
+                    // Slot.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![AbiParam::new(self.pointer_type())],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.catch_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_catch_index())
+    }
+
+    fn get_catch_all_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.catch_all_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Slot.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![AbiParam::new(self.pointer_type())],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.catch_all_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_catch_all_index())
+    }
+
+    fn get_exception_pending_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.exception_pending_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![AbiParam::special(
+                    self.pointer_type(),
+                    ArgumentPurpose::VMContext,
+                )],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.exception_pending_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_exception_pending_index())
+    }
+
+    fn get_return_call_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.return_call_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Function index.
+                    AbiParam::new(I32),
+                    // Values.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.return_call_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_return_call_index())
+    }
+
+    fn get_return_call_indirect_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.return_call_indirect_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Table index.
+                    AbiParam::new(I32),
+                    // Signature index.
+                    AbiParam::new(I32),
+                    // Element index.
+                    AbiParam::new(I32),
+                    // Values.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.return_call_indirect_sig = Some(sig);
+        (
+            sig,
+            VMBuiltinFunctionIndex::get_return_call_indirect_index(),
+        )
+    }
+
+    fn get_tail_call_func(&mut self, func: &mut Function) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.tail_call_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Results.
+                    AbiParam::new(self.pointer_type()),
+                ],
+                returns: vec![],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.tail_call_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_tail_call_index())
+    }
+
+    fn get_tail_call_entry_func(
+        &mut self,
+        func: &mut Function,
+    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        let sig = self.tail_call_entry_sig.unwrap_or_else(|| {
+            func.import_signature(Signature {
+                params: vec![
+                    AbiParam::special(self.pointer_type(), ArgumentPurpose::VMContext),
+                    // Function index.
+                    AbiParam::new(I32),
+                ],
+                returns: vec![AbiParam::new(I32)],
+                call_conv: self.target_config.default_call_conv,
+            })
+        });
+        self.tail_call_entry_sig = Some(sig);
+        (sig, VMBuiltinFunctionIndex::get_tail_call_entry_index())
+    }
+
+    /// Returns the address of a new stack slot with room for the values
+    /// carried by an exception with tag `tag_index`, one every 16 bytes.
+    fn translate_payload_slot(
+        &mut self,
+        pos: &mut FuncCursor<'_>,
+        tag_index: TagIndex,
+    ) -> ir::Value {
+        let num_values = self.get_tag_type(tag_index).unwrap().params().len();
+        self.translate_values_slot(pos, num_values)
+    }
+
+    /// Returns the address of a new stack slot that identifies a catch
+    /// clause to the runtime.
+    fn translate_catch_slot(&mut self, pos: &mut FuncCursor<'_>) -> ir::Value {
+        let pointer_type = self.pointer_type();
+        let slot = pos.func.create_stack_slot(StackSlotData::new(
+            StackSlotKind::ExplicitSlot,
+            u32::from(self.offsets.pointer_size),
+        ));
+        pos.ins().stack_addr(pointer_type, slot, 0)
+    }
+
+    /// Returns the address of a new stack slot with room for `num_values`
+    /// values, one every 16 bytes.
+    fn translate_values_slot(&mut self, pos: &mut FuncCursor<'_>, num_values: usize) -> ir::Value {
+        let pointer_type = self.pointer_type();
+        if num_values == 0 {
+            return pos.ins().iconst(pointer_type, 0);
+        }
+        let slot = pos.func.create_stack_slot(StackSlotData::new(
+            StackSlotKind::ExplicitSlot,
+            u32::try_from(num_values * 16).unwrap(),
+        ));
+        pos.ins().stack_addr(pointer_type, slot, 0)
+    }
+
     /// Translates load of builtin function and returns a pair of values `vmctx`
     /// and address of the loaded function.
     fn translate_load_builtin_function_address(
@@ -757,6 +1214,75 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
 
         (base, func_addr)
     }
+
+    /// Traps with `Interrupt` if the epoch counter has reached the epoch
+    /// deadline.
+    fn translate_epoch_check(&mut self, mut pos: FuncCursor) {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(pos.func);
+        let base = pos.ins().global_value(pointer_type, vmctx);
+
+        let mut readonly = ir::MemFlags::trusted();
+        readonly.set_readonly();
+        let counter_ptr = pos.ins().load(
+            pointer_type,
+            readonly,
+            base,
+            i32::try_from(self.offsets.vmctx_epoch_counter()).unwrap(),
+        );
+        let deadline_ptr = pos.ins().load(
+            pointer_type,
+            readonly,
+            base,
+            i32::try_from(self.offsets.vmctx_epoch_deadline()).unwrap(),
+        );
+
+        // Both can be changed by other threads at any time.
+        let mem_flags = ir::MemFlags::trusted();
+        let counter = pos.ins().load(I64, mem_flags, counter_ptr, 0);
+        let deadline = pos.ins().load(I64, mem_flags, deadline_ptr, 0);
+        let f = pos.ins().ifcmp(counter, deadline);
+        pos.ins().trapif(
+            IntCC::UnsignedGreaterThanOrEqual,
+            f,
+            ir::TrapCode::Interrupt,
+        );
+    }
+
+    /// Charges `cost` to the fuel of the instance, or sets the exhausted
+    /// flag and traps with `OutOfFuel` if there isn't enough left.
+    fn translate_fuel_charge(&mut self, builder: &mut FunctionBuilder, cost: u64) {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(builder.func);
+        let base = builder.ins().global_value(pointer_type, vmctx);
+        let remaining_offset = i32::try_from(self.offsets.vmctx_fuel_remaining()).unwrap();
+        let exhausted_offset = i32::try_from(self.offsets.vmctx_fuel_exhausted()).unwrap();
+
+        let mem_flags = ir::MemFlags::trusted();
+        let remaining = builder.ins().load(I64, mem_flags, base, remaining_offset);
+        let cost = builder.ins().iconst(I64, cost as i64);
+        let out_of_fuel = builder.ins().icmp(IntCC::UnsignedLessThan, remaining, cost);
+
+        let out_of_fuel_block = builder.create_block();
+        let continue_block = builder.create_block();
+        builder.ins().brnz(out_of_fuel, out_of_fuel_block, &[]);
+        builder.ins().jump(continue_block, &[]);
+        builder.seal_block(out_of_fuel_block);
+        builder.seal_block(continue_block);
+
+        builder.switch_to_block(out_of_fuel_block);
+        let one = builder.ins().iconst(I64, 1);
+        builder.ins().store(mem_flags, one, base, exhausted_offset);
+        builder
+            .ins()
+            .trap(ir::TrapCode::User(TrapCode::OutOfFuel as u16));
+
+        builder.switch_to_block(continue_block);
+        let remaining = builder.ins().isub(remaining, cost);
+        builder
+            .ins()
+            .store(mem_flags, remaining, base, remaining_offset);
+    }
 }
 
 impl<'module_environment> TargetEnvironment for FuncEnvironment<'module_environment> {
@@ -931,11 +1457,11 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
     fn translate_ref_null(
         &mut self,
         mut pos: cranelift_codegen::cursor::FuncCursor,
-        ty: Type,
+        ty: ValType,
     ) -> WasmResult<ir::Value> {
         Ok(match ty {
-            Type::FuncRef => pos.ins().null(self.reference_type()),
-            Type::ExternRef => pos.ins().null(self.reference_type()),
+            ValType::FuncRef => pos.ins().null(self.reference_type()),
+            ValType::ExternRef => pos.ins().null(self.reference_type()),
             _ => {
                 return Err(WasmError::Unsupported(
                     "`ref.null T` that is not a `funcref` or an `externref`".into(),
@@ -1298,12 +1824,26 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         mut pos: FuncCursor,
         src_index: MemoryIndex,
         _src_heap: ir::Heap,
-        _dst_index: MemoryIndex,
+        dst_index: MemoryIndex,
         _dst_heap: ir::Heap,
         dst: ir::Value,
         src: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
+        if src_index != dst_index {
+            let (func_sig, func_idx) = self.get_memory_copy_between_func(pos.func);
+            let dst_index_arg = pos.ins().iconst(I32, dst_index.index() as i64);
+            let src_index_arg = pos.ins().iconst(I32, src_index.index() as i64);
+            let (vmctx, func_addr) =
+                self.translate_load_builtin_function_address(&mut pos, func_idx);
+            pos.ins().call_indirect(
+                func_sig,
+                func_addr,
+                &[vmctx, dst_index_arg, src_index_arg, dst, src, len],
+            );
+            return Ok(());
+        }
+
         let (func_sig, src_index, func_idx) = self.get_memory_copy_func(pos.func, src_index);
 
         let src_index_arg = pos.ins().iconst(I32, src_index as i64);
@@ -1466,29 +2006,263 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
 
     fn translate_atomic_wait(
         &mut self,
-        _pos: FuncCursor,
-        _index: MemoryIndex,
+        mut pos: FuncCursor,
+        index: MemoryIndex,
         _heap: ir::Heap,
-        _addr: ir::Value,
-        _expected: ir::Value,
-        _timeout: ir::Value,
+        addr: ir::Value,
+        expected: ir::Value,
+        timeout: ir::Value,
     ) -> WasmResult<ir::Value> {
-        Err(WasmError::Unsupported(
-            "wasm atomics (fn translate_atomic_wait)".to_string(),
-        ))
+        let (func_sig, memory_index, func_idx) = match pos.func.dfg.value_type(expected) {
+            I32 => self.get_memory_atomic_wait32_func(pos.func, index),
+            I64 => self.get_memory_atomic_wait64_func(pos.func, index),
+            ty => panic!("unexpected type for memory.atomic.wait: {}", ty),
+        };
+
+        let memory_index_arg = pos.ins().iconst(I32, memory_index as i64);
+
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+
+        let call_inst = pos.ins().call_indirect(
+            func_sig,
+            func_addr,
+            &[vmctx, memory_index_arg, addr, expected, timeout],
+        );
+
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
     }
 
     fn translate_atomic_notify(
         &mut self,
-        _pos: FuncCursor,
-        _index: MemoryIndex,
+        mut pos: FuncCursor,
+        index: MemoryIndex,
         _heap: ir::Heap,
-        _addr: ir::Value,
-        _count: ir::Value,
+        addr: ir::Value,
+        count: ir::Value,
     ) -> WasmResult<ir::Value> {
-        Err(WasmError::Unsupported(
-            "wasm atomics (fn translate_atomic_notify)".to_string(),
-        ))
+        let (func_sig, memory_index, func_idx) =
+            self.get_memory_atomic_notify_func(pos.func, index);
+
+        let memory_index_arg = pos.ins().iconst(I32, memory_index as i64);
+
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+
+        let call_inst =
+            pos.ins()
+                .call_indirect(func_sig, func_addr, &[vmctx, memory_index_arg, addr, count]);
+
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+    }
+
+    fn before_translate_operator(
+        &mut self,
+        op: &Operator,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> WasmResult<()> {
+        if let Some(cost) = self.fuel.as_mut().and_then(|fuel| fuel.feed(op)) {
+            // The cost of unreachable code is dropped along with it.
+            if state.reachable {
+                self.translate_fuel_charge(builder, cost);
+            }
+        }
+        Ok(())
+    }
+
+    fn exceptions_enabled(&self) -> bool {
+        self.exceptions
+    }
+
+    fn translate_exception_maybe_pending(&mut self, mut pos: FuncCursor) -> WasmResult<ir::Value> {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(pos.func);
+        let base = pos.ins().global_value(pointer_type, vmctx);
+
+        let mut readonly = ir::MemFlags::trusted();
+        readonly.set_readonly();
+        let pending_ptr = pos.ins().load(
+            pointer_type,
+            readonly,
+            base,
+            i32::try_from(self.offsets.vmctx_exceptions_pending()).unwrap(),
+        );
+        Ok(pos
+            .ins()
+            .load(pointer_type, ir::MemFlags::trusted(), pending_ptr, 0))
+    }
+
+    fn translate_exception_pending(&mut self, mut pos: FuncCursor) -> WasmResult<ir::Value> {
+        let (func_sig, func_idx) = self.get_exception_pending_func(pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        let call_inst = pos.ins().call_indirect(func_sig, func_addr, &[vmctx]);
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+    }
+
+    fn translate_throw(
+        &mut self,
+        mut pos: FuncCursor,
+        tag_index: TagIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_throw_func(pos.func);
+        let values = self.translate_payload_slot(&mut pos, tag_index);
+        for (i, arg) in args.iter().enumerate() {
+            pos.ins()
+                .store(ir::MemFlags::trusted(), *arg, values, (i * 16) as i32);
+        }
+        let tag_index_arg = pos.ins().iconst(I32, tag_index.index() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, tag_index_arg, values]);
+        Ok(())
+    }
+
+    fn translate_rethrow(&mut self, mut pos: FuncCursor, exception: ir::Value) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_rethrow_func(pos.func);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, exception]);
+        Ok(())
+    }
+
+    fn translate_catch(
+        &mut self,
+        mut pos: FuncCursor,
+        tag_index: TagIndex,
+    ) -> WasmResult<(ir::Value, ir::Value)> {
+        let (func_sig, func_idx) = self.get_catch_func(pos.func);
+        let values = self.translate_payload_slot(&mut pos, tag_index);
+        let slot = self.translate_catch_slot(&mut pos);
+        let tag_index_arg = pos.ins().iconst(I32, tag_index.index() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        let call_inst =
+            pos.ins()
+                .call_indirect(func_sig, func_addr, &[vmctx, tag_index_arg, values, slot]);
+        let exception = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        Ok((exception, values))
+    }
+
+    fn translate_load_payload(
+        &mut self,
+        mut pos: FuncCursor,
+        tag_index: TagIndex,
+        payload: ir::Value,
+    ) -> WasmResult<Vec<ir::Value>> {
+        self.get_tag_type(tag_index)
+            .unwrap()
+            .params()
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let ty = type_to_irtype(*ty, self.target_config)?;
+                Ok(pos
+                    .ins()
+                    .load(ty, ir::MemFlags::trusted(), payload, (i * 16) as i32))
+            })
+            .collect()
+    }
+
+    fn translate_catch_all(&mut self, mut pos: FuncCursor) -> WasmResult<ir::Value> {
+        let (func_sig, func_idx) = self.get_catch_all_func(pos.func);
+        let slot = self.translate_catch_slot(&mut pos);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        let call_inst = pos.ins().call_indirect(func_sig, func_addr, &[vmctx, slot]);
+        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+    }
+
+    fn translate_called_by_tail_call_loop(&mut self, _pos: FuncCursor) -> WasmResult<ir::Value> {
+        Ok(self
+            .called_by_tail_call_loop
+            .expect("only functions that make tail calls request them"))
+    }
+
+    fn translate_tail_call(
+        &mut self,
+        mut pos: FuncCursor,
+        types: &[ir::Type],
+    ) -> WasmResult<Vec<ir::Value>> {
+        let (func_sig, func_idx) = self.get_tail_call_func(pos.func);
+        let results = self.translate_values_slot(&mut pos, types.len());
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, results]);
+        Ok(types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                pos.ins()
+                    .load(*ty, ir::MemFlags::trusted(), results, (i * 16) as i32)
+            })
+            .collect())
+    }
+
+    fn translate_return_call(
+        &mut self,
+        mut pos: FuncCursor,
+        callee_index: FunctionIndex,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_return_call_func(pos.func);
+        let values = self.translate_values_slot(&mut pos, call_args.len());
+        for (i, arg) in call_args.iter().enumerate() {
+            pos.ins()
+                .store(ir::MemFlags::trusted(), *arg, values, (i * 16) as i32);
+        }
+        let callee_index_arg = pos.ins().iconst(I32, callee_index.index() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins()
+            .call_indirect(func_sig, func_addr, &[vmctx, callee_index_arg, values]);
+        Ok(())
+    }
+
+    fn translate_return_call_indirect(
+        &mut self,
+        mut pos: FuncCursor,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let (func_sig, func_idx) = self.get_return_call_indirect_func(pos.func);
+        let values = self.translate_values_slot(&mut pos, call_args.len());
+        for (i, arg) in call_args.iter().enumerate() {
+            pos.ins()
+                .store(ir::MemFlags::trusted(), *arg, values, (i * 16) as i32);
+        }
+        let table_index_arg = pos.ins().iconst(I32, table_index.index() as i64);
+        let sig_index_arg = pos.ins().iconst(I32, sig_index.index() as i64);
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
+        pos.ins().call_indirect(
+            func_sig,
+            func_addr,
+            &[vmctx, table_index_arg, sig_index_arg, callee, values],
+        );
+        Ok(())
+    }
+
+    fn translate_function_entry(&mut self, mut pos: FuncCursor) -> WasmResult<()> {
+        if let Some(function_index) = self.tail_calls {
+            let (func_sig, func_idx) = self.get_tail_call_entry_func(pos.func);
+            let function_index_arg = pos.ins().iconst(I32, function_index.index() as i64);
+            let (vmctx, func_addr) =
+                self.translate_load_builtin_function_address(&mut pos, func_idx);
+            let call_inst =
+                pos.ins()
+                    .call_indirect(func_sig, func_addr, &[vmctx, function_index_arg]);
+            self.called_by_tail_call_loop =
+                Some(*pos.func.dfg.inst_results(call_inst).first().unwrap());
+        }
+        if self.epoch_interruption {
+            self.translate_epoch_check(pos);
+        }
+        Ok(())
+    }
+
+    fn translate_loop_header(&mut self, pos: FuncCursor) -> WasmResult<()> {
+        if self.epoch_interruption {
+            self.translate_epoch_check(pos);
+        }
+        Ok(())
     }
 
     fn get_global_type(&self, global_index: GlobalIndex) -> Option<WasmerType> {
@@ -1525,6 +2299,11 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         self.module.signatures.get(sig_index)
     }
 
+    fn get_tag_type(&self, tag_index: TagIndex) -> Option<&FunctionType> {
+        let sig_idx = self.module.tags.get(tag_index)?;
+        Some(&self.module.signatures[*sig_idx])
+    }
+
     fn translate_drop_locals(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()> {
         // TODO: this allocation can be removed without too much effort but it will require
         //       maneuvering around the borrow checker
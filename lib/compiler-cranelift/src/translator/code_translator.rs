@@ -92,11 +92,11 @@ use cranelift_frontend::{FunctionBuilder, Variable};
 use smallvec::SmallVec;
 use std::vec::Vec;
 
-use wasmer_compiler::wasmparser::{MemoryImmediate, Operator, Type as WPType};
+use wasmer_compiler::wasmparser::{MemoryImmediate, Operator, ValType as WPType};
 use wasmer_compiler::{from_binaryreadererror_wasmerror, wasm_unsupported, ModuleTranslationState};
 use wasmer_types::{
-    FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex, Type as WasmerType,
-    WasmResult,
+    FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex, TagIndex,
+    Type as WasmerType, WasmResult,
 };
 
 // Clippy warns about "align: _" but its important to document that the align field is ignored
@@ -429,6 +429,19 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 // below.
             }
 
+            // The exceptions that no clause of a `try` caught keep going.
+            if let ControlStackFrame::Try {
+                landing_pad,
+                no_match,
+                ..
+            } = frame
+            {
+                if let Some(block) = landing_pad.or(no_match) {
+                    let frames = state.control_stack.len();
+                    forward_exceptions(block, frames, builder, state);
+                }
+            }
+
             builder.switch_to_block(next_block);
             builder.seal_block(next_block);
 
@@ -567,44 +580,65 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             state.reachable = false;
         }
         Operator::Return => {
-            let (return_count, br_destination) = {
-                let frame = &mut state.control_stack[0];
-                if environ.return_mode() == ReturnMode::FallthroughReturn {
-                    frame.set_branched_to_exit();
-                }
-                let return_count = frame.num_return_values();
-                (return_count, frame.br_destination())
+            translate_return(builder, state, environ);
+        }
+        /********************************** Exception handing **********************************
+         * Exceptions don't unwind the native stack: `throw` records the exception as pending
+         * in the store and branches to the landing pad of the innermost `try`, and every call
+         * is followed by a check for an exception pending on return. The clauses of a `try`
+         * are tested in order from its landing pad, and the exceptions that none of them
+         * catch, or that are thrown outside of any `try`, are forwarded to the enclosing
+         * `try`, or returned to the caller with the exception still pending.
+         ***********************************************************************************/
+        Operator::Try { ty } => {
+            let (params, results) = module_translation_state.blocktype_params_results(*ty)?;
+            let next = block_with_params(builder, results, environ)?;
+            let landing_pad = builder.create_block();
+            state.push_try(next, Some(landing_pad), params.len(), results.len());
+        }
+        Operator::Catch { index } => {
+            translate_catch_clause(Some(TagIndex::from_u32(*index)), builder, state, environ)?;
+        }
+        Operator::CatchAll => {
+            translate_catch_clause(None, builder, state, environ)?;
+        }
+        Operator::Throw { index } => {
+            let tag_index = TagIndex::from_u32(*index);
+            let num_args = environ.get_tag_type(tag_index).unwrap().params().len();
+            let (args, _) = state.peekn(num_args);
+            environ.translate_throw(builder.cursor(), tag_index, args)?;
+            state.popn(num_args);
+            let handler = exception_handler(state.control_stack.len(), builder, state);
+            builder.ins().jump(handler, &[]);
+            state.reachable = false;
+        }
+        Operator::Rethrow { relative_depth } => {
+            let i = state.control_stack.len() - 1 - (*relative_depth as usize);
+            let exception = match state.control_stack[i] {
+                ControlStackFrame::Try {
+                    exception: Some(exception),
+                    ..
+                } => exception,
+                _ => unreachable!("rethrow must target a catch clause"),
             };
-            {
-                let (return_args, return_args_metadata) = state.peekn_mut(return_count);
-                // TODO(reftypes): maybe ref count here?
-                let return_types = wasm_param_types(&builder.func.signature.returns, |i| {
-                    environ.is_wasm_return(&builder.func.signature, i)
-                });
-                bitcast_arguments(return_args, &return_types, builder);
-                match environ.return_mode() {
-                    ReturnMode::NormalReturns => builder.ins().return_(return_args),
-                    ReturnMode::FallthroughReturn => canonicalise_then_jump(
-                        builder,
-                        br_destination,
-                        (&*return_args, &*return_args_metadata),
-                    ),
-                };
-            }
-            state.popn(return_count);
+            environ.translate_rethrow(builder.cursor(), exception)?;
+            let handler = exception_handler(state.control_stack.len(), builder, state);
+            builder.ins().jump(handler, &[]);
             state.reachable = false;
         }
-        /********************************** Exception handing **********************************/
-        Operator::Try { .. }
-        | Operator::Catch { .. }
-        | Operator::Throw { .. }
-        | Operator::Rethrow { .. }
-        | Operator::Delegate { .. }
-        | Operator::CatchAll => {
-            return Err(wasm_unsupported!(
-                "proposed exception handling operator {:?}",
-                op
-            ));
+        Operator::Delegate { relative_depth } => {
+            let frame = state.control_stack.pop().unwrap();
+            let return_args = state.peekn(frame.num_return_values());
+            canonicalise_then_jump(builder, frame.following_code(), return_args);
+            translate_delegate(&frame, *relative_depth, builder, state);
+
+            let next_block = frame.following_code();
+            builder.switch_to_block(next_block);
+            builder.seal_block(next_block);
+            frame.truncate_value_stack_to_original_size(&mut state.stack);
+            state
+                .stack
+                .extend_from_slice(builder.block_params(next_block));
         }
         /************************************ Calls ****************************************
          * The call instructions pop off their arguments from the stack and append their
@@ -645,8 +679,11 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             }
             state.popn(num_args);
             state.pushn(inst_results, &results_metadata);
+            translate_exception_check(builder, state, environ)?;
         }
-        Operator::CallIndirect { index, table_index } => {
+        Operator::CallIndirect {
+            index, table_index, ..
+        } => {
             // `index` is the index of the function's signature and `table_index` is the index of
             // the table to search the function in.
             let (sigref, num_args) = state.get_indirect_sig(builder.func, *index, environ)?;
@@ -690,6 +727,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             }
             state.popn(num_args);
             state.pushn(inst_results, &results_metadata);
+            translate_exception_check(builder, state, environ)?;
         }
         /******************************* Memory management ***********************************
          * Memory management is handled by environment. It is usually translated into calls to
@@ -2073,8 +2111,38 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             let b_high = builder.ins().uwiden_high(b);
             state.push1(builder.ins().imul(a_high, b_high));
         }
-        Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
-            return Err(wasm_unsupported!("proposed tail-call operator {:?}", op));
+        /******************************* Tail calls ******************************************
+         * Compiled code can't reuse its own frame for the callee, so `return_call` and
+         * `return_call_indirect` make the tail call pending in the store and return zeros,
+         * and every call is followed by a check for a tail call pending on return, which is
+         * made from a loop at runtime, with a constant stack depth.
+         ************************************************************************************/
+        Operator::ReturnCall { function_index } => {
+            let func_index = FunctionIndex::from_u32(*function_index);
+            let num_args = environ
+                .get_function_type(func_index)
+                .unwrap()
+                .params()
+                .len();
+            let (args, _) = state.peekn(num_args);
+            environ.translate_return_call(builder.cursor(), func_index, args)?;
+            state.popn(num_args);
+            translate_tail_call_return(builder, state, environ)?;
+        }
+        Operator::ReturnCallIndirect { index, table_index } => {
+            let sig_index = SignatureIndex::from_u32(*index);
+            let num_args = environ.get_function_sig(sig_index).unwrap().params().len();
+            let (callee, _) = state.pop1();
+            let (args, _) = state.peekn(num_args);
+            environ.translate_return_call_indirect(
+                builder.cursor(),
+                TableIndex::from_u32(*table_index),
+                sig_index,
+                callee,
+                args,
+            )?;
+            state.popn(num_args);
+            translate_tail_call_return(builder, state, environ)?;
         }
         Operator::I8x16RelaxedSwizzle
         | Operator::I32x4RelaxedTruncSatF32x4S
@@ -2129,6 +2197,30 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
         Operator::Loop { ty: _ } | Operator::Block { ty: _ } => {
             state.push_block(ir::Block::reserved_value(), 0, 0);
         }
+        Operator::Try { ty: _ } => {
+            state.push_try(ir::Block::reserved_value(), None, 0, 0);
+        }
+        Operator::Catch { index } => {
+            translate_catch_clause(Some(TagIndex::from_u32(index)), builder, state, environ)?;
+        }
+        Operator::CatchAll => {
+            translate_catch_clause(None, builder, state, environ)?;
+        }
+        Operator::Delegate { relative_depth } => {
+            let frame = state.control_stack.pop().unwrap();
+            frame.truncate_value_stack_to_original_size(&mut state.stack);
+            translate_delegate(&frame, relative_depth, builder, state);
+
+            if frame.exit_is_branched_to() {
+                let next_block = frame.following_code();
+                builder.switch_to_block(next_block);
+                builder.seal_block(next_block);
+                state
+                    .stack
+                    .extend_from_slice(builder.block_params(next_block));
+                state.reachable = true;
+            }
+        }
         Operator::Else => {
             let i = state.control_stack.len() - 1;
             match state.control_stack[i] {
@@ -2185,6 +2277,20 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
             // Pop unused parameters from stack.
             frame.truncate_value_stack_to_original_size(stack);
 
+            // The exceptions that no clause of a `try` caught keep going.
+            if let ControlStackFrame::Try {
+                landing_pad,
+                no_match,
+                ..
+            } = frame
+            {
+                if let Some(block) = landing_pad.or(no_match) {
+                    let frames = state.control_stack.len();
+                    forward_exceptions(block, frames, builder, state);
+                }
+            }
+            let stack = &mut state.stack;
+
             let reachable_anyway = match frame {
                 // If it is a loop we also have to seal the body loop block
                 ControlStackFrame::Loop { header, .. } => {
@@ -2713,6 +2819,258 @@ fn translate_br_if(
     builder.switch_to_block(next_block);
 }
 
+/// Returns the block that handles the exceptions thrown inside the
+/// `frames` outermost frames of the control stack: the landing pad of the
+/// innermost `try` whose body is among them, or the block that returns to
+/// the caller with the exception pending.
+fn exception_handler(
+    frames: usize,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+) -> ir::Block {
+    let landing_pad = state.control_stack[..frames]
+        .iter()
+        .rev()
+        .find_map(|frame| match frame {
+            ControlStackFrame::Try { landing_pad, .. } => *landing_pad,
+            _ => None,
+        });
+    landing_pad.unwrap_or_else(|| {
+        *state
+            .propagate_block
+            .get_or_insert_with(|| builder.create_block())
+    })
+}
+
+/// Makes the exceptions landing in `block` go to the handler of the
+/// `frames` outermost frames of the control stack.
+fn forward_exceptions(
+    block: ir::Block,
+    frames: usize,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+) {
+    let handler = exception_handler(frames, builder, state);
+    builder.switch_to_block(block);
+    builder.seal_block(block);
+    builder.ins().jump(handler, &[]);
+}
+
+/// Forwards the exceptions thrown in the body of the `try` of `frame`, which
+/// was just popped, to the frame `relative_depth` names.
+fn translate_delegate(
+    frame: &ControlStackFrame,
+    relative_depth: u32,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+) {
+    if let ControlStackFrame::Try {
+        landing_pad: Some(landing_pad),
+        ..
+    } = *frame
+    {
+        let frames = state.control_stack.len() - relative_depth as usize;
+        forward_exceptions(landing_pad, frames, builder, state);
+    }
+}
+
+/// Ends the body or the previous clause of the innermost `try`, and starts
+/// a clause catching the exceptions thrown with `tag_index`, or every
+/// exception if it is `None`.
+fn translate_catch_clause<FE: FuncEnvironment + ?Sized>(
+    tag_index: Option<TagIndex>,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let i = state.control_stack.len() - 1;
+    if state.reachable {
+        let frame = &mut state.control_stack[i];
+        frame.set_branched_to_exit();
+        let (destination, return_count) = (frame.br_destination(), frame.num_return_values());
+        let return_args = state.peekn(return_count);
+        canonicalise_then_jump(builder, destination, return_args);
+    }
+    state.control_stack[i].truncate_value_stack_to_original_size(&mut state.stack);
+
+    // Exceptions come either from the body, or from the previous clause if
+    // it didn't match them.
+    let block = match state.control_stack[i] {
+        ControlStackFrame::Try {
+            ref mut landing_pad,
+            ref mut no_match,
+            ..
+        } => landing_pad.take().or_else(|| no_match.take()),
+        _ => unreachable!("catch must follow a try"),
+    };
+    let block = match block {
+        Some(block) => block,
+        None => {
+            state.reachable = false;
+            return Ok(());
+        }
+    };
+    builder.switch_to_block(block);
+    builder.seal_block(block);
+    state.reachable = true;
+
+    let (exception, no_match) = match tag_index {
+        Some(tag_index) => {
+            let (exception, payload) = environ.translate_catch(builder.cursor(), tag_index)?;
+            let no_match = builder.create_block();
+            let catch_block = builder.create_block();
+            builder.ins().brz(exception, no_match, &[]);
+            builder.ins().jump(catch_block, &[]);
+            builder.seal_block(catch_block);
+            builder.switch_to_block(catch_block);
+            let values = environ.translate_load_payload(builder.cursor(), tag_index, payload)?;
+            state.stack.extend_from_slice(&values);
+            (exception, Some(no_match))
+        }
+        None => (environ.translate_catch_all(builder.cursor())?, None),
+    };
+    if let ControlStackFrame::Try {
+        no_match: ref mut frame_no_match,
+        exception: ref mut frame_exception,
+        ..
+    } = state.control_stack[i]
+    {
+        *frame_no_match = no_match;
+        *frame_exception = Some(exception);
+    }
+    Ok(())
+}
+
+/// Branches to the exception handler if the call just translated returned
+/// with an exception pending.
+fn translate_exception_check<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    if !environ.exceptions_enabled() {
+        return Ok(());
+    }
+    let maybe_pending = environ.translate_exception_maybe_pending(builder.cursor())?;
+    let check_block = builder.create_block();
+    let next_block = builder.create_block();
+    builder.ins().brnz(maybe_pending, check_block, &[]);
+    builder.ins().jump(next_block, &[]);
+    builder.seal_block(check_block); // The only predecessor is the current block.
+
+    builder.switch_to_block(check_block);
+    let pending = environ.translate_exception_pending(builder.cursor())?;
+    let handler = exception_handler(state.control_stack.len(), builder, state);
+    builder.ins().brnz(pending, handler, &[]);
+    builder.ins().jump(next_block, &[]);
+    builder.seal_block(next_block);
+
+    builder.switch_to_block(next_block);
+    Ok(())
+}
+
+/// Returns from the function, with the values at the top of the stack.
+fn translate_return<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) {
+    let (return_count, br_destination) = {
+        let frame = &mut state.control_stack[0];
+        if environ.return_mode() == ReturnMode::FallthroughReturn {
+            frame.set_branched_to_exit();
+        }
+        let return_count = frame.num_return_values();
+        (return_count, frame.br_destination())
+    };
+    {
+        let (return_args, return_args_metadata) = state.peekn_mut(return_count);
+        // TODO(reftypes): maybe ref count here?
+        let return_types = wasm_param_types(&builder.func.signature.returns, |i| {
+            environ.is_wasm_return(&builder.func.signature, i)
+        });
+        bitcast_arguments(return_args, &return_types, builder);
+        match environ.return_mode() {
+            ReturnMode::NormalReturns => builder.ins().return_(return_args),
+            ReturnMode::FallthroughReturn => canonicalise_then_jump(
+                builder,
+                br_destination,
+                (&*return_args, &*return_args_metadata),
+            ),
+        };
+    }
+    state.popn(return_count);
+    state.reachable = false;
+}
+
+/// Returns zeros from the function, after it made a tail call pending.
+fn translate_return_zeros<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) {
+    let return_types = builder.func.signature.returns.clone();
+    for (i, param) in return_types.iter().enumerate() {
+        if environ.is_wasm_return(&builder.func.signature, i) {
+            let zero = zero_value(builder, param.value_type);
+            state.push1(zero);
+        }
+    }
+    translate_return(builder, state, environ);
+}
+
+/// Returns the zero value of type `ty`, or null for references.
+pub(crate) fn zero_value(builder: &mut FunctionBuilder, ty: ir::Type) -> ir::Value {
+    match ty {
+        ty if ty.is_int() => builder.ins().iconst(ty, 0),
+        F32 => builder.ins().f32const(0.0),
+        F64 => builder.ins().f64const(0.0),
+        ty if ty.is_vector() => {
+            let zero = builder.func.dfg.constants.insert(vec![0; 16].into());
+            builder.ins().vconst(ty, zero)
+        }
+        ty => builder.ins().null(ty),
+    }
+}
+
+/// Returns from a function that just requested a tail call. If the loop
+/// that makes tail calls called the function, it makes this one too, and the
+/// function returns zeros. Otherwise the function makes the tail call and
+/// returns its results.
+fn translate_tail_call_return<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let called_by_loop = environ.translate_called_by_tail_call_loop(builder.cursor())?;
+    let tail_call_block = builder.create_block();
+    let return_block = builder.create_block();
+    builder.ins().brz(called_by_loop, tail_call_block, &[]);
+    builder.ins().jump(return_block, &[]);
+    builder.seal_block(tail_call_block); // The only predecessor is the current block.
+    builder.seal_block(return_block); // The only predecessor is the current block.
+
+    builder.switch_to_block(tail_call_block);
+    let types = builder
+        .func
+        .signature
+        .returns
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| environ.is_wasm_return(&builder.func.signature, *i))
+        .map(|(_, param)| param.value_type)
+        .collect::<Vec<_>>();
+    let results = environ.translate_tail_call(builder.cursor(), &types)?;
+    for result in results {
+        state.push1(result);
+    }
+    translate_return(builder, state, environ);
+
+    builder.switch_to_block(return_block);
+    translate_return_zeros(builder, state, environ);
+    Ok(())
+}
+
 fn translate_br_if_args(
     relative_depth: u32,
     state: &mut FuncTranslationState,
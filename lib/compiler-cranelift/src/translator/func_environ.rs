@@ -12,10 +12,10 @@ use cranelift_codegen::ir::immediates::Offset32;
 use cranelift_codegen::ir::{self, InstBuilder};
 use cranelift_codegen::isa::TargetFrontendConfig;
 use cranelift_frontend::FunctionBuilder;
-use wasmer_compiler::wasmparser::{Operator, Type};
+use wasmer_compiler::wasmparser::{Operator, ValType};
 use wasmer_types::{
     FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type as WasmerType, WasmResult,
+    TableIndex, TagIndex, Type as WasmerType, WasmResult,
 };
 
 /// The value of a WebAssembly global variable.
@@ -375,7 +375,7 @@ pub trait FuncEnvironment: TargetEnvironment {
     /// null sentinel is not a null reference type pointer for your type. If you
     /// override this method, then you should also override
     /// `translate_ref_is_null` as well.
-    fn translate_ref_null(&mut self, pos: FuncCursor, ty: Type) -> WasmResult<ir::Value>;
+    fn translate_ref_null(&mut self, pos: FuncCursor, ty: ValType) -> WasmResult<ir::Value>;
     // {
     //     let _ = ty;
     //     Ok(pos.ins().null(self.reference_type(ty)))
@@ -454,6 +454,105 @@ pub trait FuncEnvironment: TargetEnvironment {
         count: ir::Value,
     ) -> WasmResult<ir::Value>;
 
+    /// Whether calls can return with an exception pending, in which case
+    /// the translator checks for one after every call.
+    fn exceptions_enabled(&self) -> bool {
+        false
+    }
+
+    /// Load a pointer-sized value that is zero if no exception is pending.
+    /// Otherwise `translate_exception_pending` tells whether one is.
+    fn translate_exception_maybe_pending(&mut self, pos: FuncCursor) -> WasmResult<ir::Value>;
+
+    /// Ask the runtime whether an exception is pending in the running call,
+    /// as a non-zero `i32`.
+    fn translate_exception_pending(&mut self, pos: FuncCursor) -> WasmResult<ir::Value>;
+
+    /// Translate a `throw` WebAssembly instruction, which makes an exception
+    /// with tag `tag_index` carrying `args` pending.
+    fn translate_throw(
+        &mut self,
+        pos: FuncCursor,
+        tag_index: TagIndex,
+        args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Translate a `rethrow` WebAssembly instruction, which makes the
+    /// `exception` caught by a `catch` clause pending again.
+    fn translate_rethrow(&mut self, pos: FuncCursor, exception: ir::Value) -> WasmResult<()>;
+
+    /// Translate the head of a `catch` clause, which catches the pending
+    /// exception if it was thrown with tag `tag_index`.
+    ///
+    /// Returns the caught exception, which is null if there was no match,
+    /// and the address of its payload, which must only be loaded with
+    /// `translate_load_payload` if there was a match.
+    fn translate_catch(
+        &mut self,
+        pos: FuncCursor,
+        tag_index: TagIndex,
+    ) -> WasmResult<(ir::Value, ir::Value)>;
+
+    /// Load the values carried by an exception with tag `tag_index`, from
+    /// the `payload` address returned by `translate_catch`.
+    fn translate_load_payload(
+        &mut self,
+        pos: FuncCursor,
+        tag_index: TagIndex,
+        payload: ir::Value,
+    ) -> WasmResult<Vec<ir::Value>>;
+
+    /// Translate the head of a `catch_all` clause, which catches the pending
+    /// exception whatever its tag. Returns the caught exception.
+    fn translate_catch_all(&mut self, pos: FuncCursor) -> WasmResult<ir::Value>;
+
+    /// Return the `i32` that tells, in a function that makes tail calls,
+    /// whether the loop that makes the tail calls of the running call called
+    /// it. The function then returns right after requesting a tail call, and
+    /// makes it with `translate_tail_call` otherwise.
+    fn translate_called_by_tail_call_loop(&mut self, pos: FuncCursor) -> WasmResult<ir::Value>;
+
+    /// Make the tail call pending in the running call, and return its
+    /// results, which have the given `types`.
+    fn translate_tail_call(
+        &mut self,
+        pos: FuncCursor,
+        types: &[ir::Type],
+    ) -> WasmResult<Vec<ir::Value>>;
+
+    /// Translate a `return_call` WebAssembly instruction, which makes a tail
+    /// call to `callee_index` with `call_args` pending. The translator then
+    /// returns from the function.
+    fn translate_return_call(
+        &mut self,
+        pos: FuncCursor,
+        callee_index: FunctionIndex,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Translate a `return_call_indirect` WebAssembly instruction, which
+    /// makes a tail call to the function at index `callee` in table
+    /// `table_index` pending, after checking that it has signature
+    /// `sig_index`. The translator then returns from the function.
+    fn translate_return_call_indirect(
+        &mut self,
+        pos: FuncCursor,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        callee: ir::Value,
+        call_args: &[ir::Value],
+    ) -> WasmResult<()>;
+
+    /// Emit code at the beginning of every wasm function, after the locals
+    /// are declared.
+    ///
+    /// This can be used to insert explicit interrupt checking on function
+    /// entry.
+    fn translate_function_entry(&mut self, _pos: FuncCursor) -> WasmResult<()> {
+        // By default, don't emit anything.
+        Ok(())
+    }
+
     /// Emit code at the beginning of every wasm loop.
     ///
     /// This can be used to insert explicit interrupt or safepoint checking at
@@ -506,6 +605,9 @@ pub trait FuncEnvironment: TargetEnvironment {
     /// Get the type of a function with the given signature index.
     fn get_function_sig(&self, sig_index: SignatureIndex) -> Option<&FunctionType>;
 
+    /// Get the type of the values carried by the exceptions of the given tag.
+    fn get_tag_type(&self, tag_index: TagIndex) -> Option<&FunctionType>;
+
     /// Drops all locals that need to be dropped. Useful for returning from functions.
     fn translate_drop_locals(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()>;
 }
@@ -44,8 +44,8 @@ pub enum ElseData {
     },
 }
 
-/// A control stack frame can be an `if`, a `block` or a `loop`, each one having the following
-/// fields:
+/// A control stack frame can be an `if`, a `block`, a `loop` or a `try`, each one having the
+/// following fields:
 ///
 /// - `destination`: reference to the `Block` that will hold the code after the control block;
 /// - `num_return_values`: number of values returned by the control block;
@@ -53,7 +53,8 @@ pub enum ElseData {
 ///
 /// Moreover, the `if` frame has the `branch_inst` field that points to the `brz` instruction
 /// separating the `true` and `false` branch. The `loop` frame has a `header` field that references
-/// the `Block` that contains the beginning of the body of the loop.
+/// the `Block` that contains the beginning of the body of the loop. The `try` frame has the
+/// blocks where the exceptions thrown in its body land.
 #[derive(Debug)]
 pub enum ControlStackFrame {
     If {
@@ -63,7 +64,7 @@ pub enum ControlStackFrame {
         num_return_values: usize,
         original_stack_size: usize,
         exit_is_branched_to: bool,
-        blocktype: wasmer_compiler::wasmparser::TypeOrFuncType,
+        blocktype: wasmer_compiler::wasmparser::BlockType,
         /// Was the head of the `if` reachable?
         head_is_reachable: bool,
         /// What was the reachability at the end of the consequent?
@@ -90,6 +91,22 @@ pub enum ControlStackFrame {
         num_return_values: usize,
         original_stack_size: usize,
     },
+    Try {
+        destination: Block,
+        num_param_values: usize,
+        num_return_values: usize,
+        original_stack_size: usize,
+        exit_is_branched_to: bool,
+        /// Where the exceptions thrown in the body land. This is taken by
+        /// the first `catch`, so it is `None` once the body is translated,
+        /// or if the `try` is unreachable.
+        landing_pad: Option<Block>,
+        /// Where the exceptions that the current `catch` doesn't match go
+        /// to the next clause.
+        no_match: Option<Block>,
+        /// The exception caught by the current clause, for `rethrow`.
+        exception: Option<Value>,
+    },
 }
 
 /// Helper methods for the control stack objects.
@@ -104,6 +121,9 @@ impl ControlStackFrame {
             }
             | Self::Loop {
                 num_return_values, ..
+            }
+            | Self::Try {
+                num_return_values, ..
             } => num_return_values,
         }
     }
@@ -117,6 +137,9 @@ impl ControlStackFrame {
             }
             | Self::Loop {
                 num_param_values, ..
+            }
+            | Self::Try {
+                num_param_values, ..
             } => num_param_values,
         }
     }
@@ -124,12 +147,15 @@ impl ControlStackFrame {
         match *self {
             Self::If { destination, .. }
             | Self::Block { destination, .. }
-            | Self::Loop { destination, .. } => destination,
+            | Self::Loop { destination, .. }
+            | Self::Try { destination, .. } => destination,
         }
     }
     pub fn br_destination(&self) -> Block {
         match *self {
-            Self::If { destination, .. } | Self::Block { destination, .. } => destination,
+            Self::If { destination, .. }
+            | Self::Block { destination, .. }
+            | Self::Try { destination, .. } => destination,
             Self::Loop { header, .. } => header,
         }
     }
@@ -148,12 +174,16 @@ impl ControlStackFrame {
             | Self::Loop {
                 original_stack_size,
                 ..
+            }
+            | Self::Try {
+                original_stack_size,
+                ..
             } => original_stack_size,
         }
     }
     pub fn is_loop(&self) -> bool {
         match *self {
-            Self::If { .. } | Self::Block { .. } => false,
+            Self::If { .. } | Self::Block { .. } | Self::Try { .. } => false,
             Self::Loop { .. } => true,
         }
     }
@@ -167,6 +197,10 @@ impl ControlStackFrame {
             | Self::Block {
                 exit_is_branched_to,
                 ..
+            }
+            | Self::Try {
+                exit_is_branched_to,
+                ..
             } => exit_is_branched_to,
             Self::Loop { .. } => false,
         }
@@ -181,6 +215,10 @@ impl ControlStackFrame {
             | Self::Block {
                 ref mut exit_is_branched_to,
                 ..
+            }
+            | Self::Try {
+                ref mut exit_is_branched_to,
+                ..
             } => *exit_is_branched_to = true,
             Self::Loop { .. } => {}
         }
@@ -235,6 +273,9 @@ pub struct FuncTranslationState {
     /// Is the current translation state still reachable? This is false when translating operators
     /// like End, Return, or Unreachable.
     pub(crate) reachable: bool,
+    /// The block returning to the caller with an exception pending, created
+    /// when something in the function may throw outside of any `try`.
+    pub(crate) propagate_block: Option<Block>,
 
     // Map of global variables that have already been created by `FuncEnvironment::make_global`.
     globals: HashMap<GlobalIndex, GlobalVariable>,
@@ -275,6 +316,7 @@ impl FuncTranslationState {
             //metadata_stack: Vec::new(),
             control_stack: Vec::new(),
             reachable: true,
+            propagate_block: None,
             globals: HashMap::new(),
             heaps: HashMap::new(),
             tables: HashMap::new(),
@@ -287,6 +329,7 @@ impl FuncTranslationState {
         debug_assert!(self.stack.is_empty());
         debug_assert!(self.control_stack.is_empty());
         self.reachable = true;
+        self.propagate_block = None;
         self.globals.clear();
         self.heaps.clear();
         self.tables.clear();
@@ -458,6 +501,27 @@ impl FuncTranslationState {
         });
     }
 
+    /// Push a try on the control stack.
+    pub(crate) fn push_try(
+        &mut self,
+        following_code: Block,
+        landing_pad: Option<Block>,
+        num_param_types: usize,
+        num_result_types: usize,
+    ) {
+        debug_assert!(num_param_types <= self.stack.len());
+        self.control_stack.push(ControlStackFrame::Try {
+            destination: following_code,
+            original_stack_size: self.stack.len() - num_param_types,
+            num_param_values: num_param_types,
+            num_return_values: num_result_types,
+            exit_is_branched_to: false,
+            landing_pad,
+            no_match: None,
+            exception: None,
+        });
+    }
+
     /// Push an if on the control stack.
     pub(crate) fn push_if(
         &mut self,
@@ -465,7 +529,7 @@ impl FuncTranslationState {
         else_data: ElseData,
         num_param_types: usize,
         num_result_types: usize,
-        blocktype: wasmer_compiler::wasmparser::TypeOrFuncType,
+        blocktype: wasmer_compiler::wasmparser::BlockType,
     ) {
         debug_assert!(num_param_types <= self.stack.len());
 
@@ -7,7 +7,7 @@
 //! function to Cranelift IR guided by a `FuncEnvironment` which provides information about the
 //! WebAssembly module and the runtime environment.
 
-use super::code_translator::{bitcast_arguments, translate_operator, wasm_param_types};
+use super::code_translator::{bitcast_arguments, translate_operator, wasm_param_types, zero_value};
 use super::func_environ::{FuncEnvironment, ReturnMode};
 use super::func_state::FuncTranslationState;
 use super::translation_utils::get_vmctx_value_label;
@@ -17,9 +17,7 @@ use cranelift_codegen::timing;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
 use tracing::info;
 use wasmer_compiler::wasmparser;
-use wasmer_compiler::{
-    wasm_unsupported, wptype_to_type, FunctionBinaryReader, ModuleTranslationState,
-};
+use wasmer_compiler::{wptype_to_type, FunctionBinaryReader, ModuleTranslationState};
 use wasmer_types::{LocalFunctionIndex, WasmResult};
 
 /// WebAssembly to Cranelift IR function translator.
@@ -110,6 +108,7 @@ impl FuncTranslator {
         self.state.initialize(&builder.func.signature, exit_block);
 
         parse_local_decls(reader, &mut builder, num_params, environ)?;
+        environ.translate_function_entry(builder.cursor())?;
         parse_function_body(
             module_translation_state,
             reader,
@@ -182,12 +181,12 @@ fn parse_local_decls<FE: FuncEnvironment + ?Sized>(
 fn declare_locals<FE: FuncEnvironment + ?Sized>(
     builder: &mut FunctionBuilder,
     count: u32,
-    wasm_type: wasmparser::Type,
+    wasm_type: wasmparser::ValType,
     next_local: &mut usize,
     environ: &mut FE,
 ) -> WasmResult<()> {
     // All locals are initialized to 0.
-    use wasmparser::Type::*;
+    use wasmparser::ValType::*;
     let zeroval = match wasm_type {
         I32 => builder.ins().iconst(ir::types::I32, 0),
         I64 => builder.ins().iconst(ir::types::I64, 0),
@@ -199,7 +198,6 @@ fn declare_locals<FE: FuncEnvironment + ?Sized>(
         }
         ExternRef => builder.ins().null(environ.reference_type()),
         FuncRef => builder.ins().null(environ.reference_type()),
-        ty => return Err(wasm_unsupported!("unsupported local type {:?}", ty)),
     };
 
     let wasmer_ty = wptype_to_type(wasm_type).unwrap();
@@ -278,6 +276,22 @@ fn parse_function_body<FE: FuncEnvironment + ?Sized>(
         }
     }
 
+    // The exceptions that nothing caught return to the caller, which finds
+    // them still pending.
+    if let Some(propagate_block) = state.propagate_block.take() {
+        builder.switch_to_block(propagate_block);
+        builder.seal_block(propagate_block);
+        environ.translate_drop_locals(builder)?;
+
+        let return_types = builder.func.signature.returns.clone();
+        let return_values = return_types
+            .iter()
+            .filter(|param| param.purpose == ir::ArgumentPurpose::Normal)
+            .map(|param| zero_value(builder, param.value_type))
+            .collect::<Vec<_>>();
+        builder.ins().return_(&return_values);
+    }
+
     // Discard any remaining values on the stack. Either we just returned them,
     // or the end of the function is unreachable.
     state.stack.clear();
@@ -11,8 +11,8 @@ use rayon::iter::ParallelBridge;
 use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use std::sync::Arc;
 use wasmer_compiler::{
-    Compiler, FunctionBodyData, ModuleMiddleware, ModuleTranslationState, Symbol, SymbolRegistry,
-    Target,
+    Compiler, FuelMetering, FunctionBodyData, ModuleMiddleware, ModuleTranslationState, Symbol,
+    SymbolRegistry, Target,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
@@ -101,6 +101,7 @@ impl LLVMCompiler {
                     self.config(),
                     &compile_info.memory_styles,
                     &compile_info.table_styles,
+                    &compile_info.features,
                     symbol_registry,
                 )?;
                 Ok(module.write_bitcode_to_memory().as_slice().to_vec())
@@ -194,6 +195,10 @@ impl Compiler for LLVMCompiler {
         &self.config.middlewares
     }
 
+    fn get_fuel_metering(&self) -> Option<&FuelMetering> {
+        self.config.fuel.as_ref()
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -255,6 +260,7 @@ impl Compiler for LLVMCompiler {
                         self.config(),
                         memory_styles,
                         table_styles,
+                        &compile_info.features,
                         &ShortNames {},
                     )
                 },
@@ -8,7 +8,7 @@ use itertools::Itertools;
 use std::fmt::Debug;
 use std::sync::Arc;
 use target_lexicon::Architecture;
-use wasmer_compiler::{Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
+use wasmer_compiler::{Compiler, CompilerConfig, FuelMetering, ModuleMiddleware, Target, Triple};
 use wasmer_types::{FunctionType, LocalFunctionIndex};
 
 /// The InkWell ModuleInfo type
@@ -42,6 +42,8 @@ pub struct LLVM {
     pub(crate) enable_nan_canonicalization: bool,
     pub(crate) enable_verifier: bool,
     pub(crate) opt_level: LLVMOptLevel,
+    pub(crate) epoch_interruption: bool,
+    pub(crate) fuel: Option<FuelMetering>,
     is_pic: bool,
     pub(crate) callbacks: Option<Arc<dyn LLVMCallbacks>>,
     /// The middleware chain.
@@ -56,6 +58,8 @@ impl LLVM {
             enable_nan_canonicalization: false,
             enable_verifier: false,
             opt_level: LLVMOptLevel::Aggressive,
+            epoch_interruption: false,
+            fuel: None,
             is_pic: false,
             callbacks: None,
             middlewares: vec![],
@@ -68,6 +72,25 @@ impl LLVM {
         self
     }
 
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the engine's epoch against the store's deadline
+    /// on function entry and at loop headers, and traps once the deadline
+    /// is reached.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.epoch_interruption = enable;
+        self
+    }
+
+    /// Enable fuel metering, or disable it with `None`.
+    ///
+    /// Compiled code charges the fuel of the instance at the end of every
+    /// basic block, and traps once there isn't enough left.
+    pub fn fuel_metering(&mut self, fuel: Option<FuelMetering>) -> &mut Self {
+        self.fuel = fuel;
+        self
+    }
+
     /// Callbacks that will triggered in the different compilation
     /// phases in LLVM.
     pub fn callbacks(&mut self, callbacks: Option<Arc<dyn LLVMCallbacks>>) -> &mut Self {
@@ -211,6 +234,14 @@ impl CompilerConfig for LLVM {
         self.enable_nan_canonicalization = enable;
     }
 
+    fn epoch_interruption(&mut self, enable: bool) {
+        self.epoch_interruption = enable;
+    }
+
+    fn fuel_metering(&mut self, fuel: Option<FuelMetering>) {
+        self.fuel = fuel;
+    }
+
     /// Transform it into the compiler.
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(LLVMCompiler::new(*self))
@@ -88,6 +88,10 @@ where
         "wasmer_vm_imported_memory32_copy".to_string(),
         LibCall::ImportedMemory32Copy,
     );
+    libcalls.insert(
+        "wasmer_vm_memory32_copy_between".to_string(),
+        LibCall::Memory32CopyBetween,
+    );
     libcalls.insert("wasmer_vm_memory32_fill".to_string(), LibCall::Memory32Fill);
     libcalls.insert(
         "wasmer_vm_imported_memory32_fill".to_string(),
@@ -95,6 +99,48 @@ where
     );
     libcalls.insert("wasmer_vm_memory32_init".to_string(), LibCall::Memory32Init);
     libcalls.insert("wasmer_vm_data_drop".to_string(), LibCall::DataDrop);
+    libcalls.insert(
+        "wasmer_vm_memory32_atomic_wait32".to_string(),
+        LibCall::Memory32AtomicWait32,
+    );
+    libcalls.insert(
+        "wasmer_vm_imported_memory32_atomic_wait32".to_string(),
+        LibCall::ImportedMemory32AtomicWait32,
+    );
+    libcalls.insert(
+        "wasmer_vm_memory32_atomic_wait64".to_string(),
+        LibCall::Memory32AtomicWait64,
+    );
+    libcalls.insert(
+        "wasmer_vm_imported_memory32_atomic_wait64".to_string(),
+        LibCall::ImportedMemory32AtomicWait64,
+    );
+    libcalls.insert(
+        "wasmer_vm_memory32_atomic_notify".to_string(),
+        LibCall::Memory32AtomicNotify,
+    );
+    libcalls.insert(
+        "wasmer_vm_imported_memory32_atomic_notify".to_string(),
+        LibCall::ImportedMemory32AtomicNotify,
+    );
+    libcalls.insert("wasmer_vm_throw".to_string(), LibCall::Throw);
+    libcalls.insert("wasmer_vm_rethrow".to_string(), LibCall::Rethrow);
+    libcalls.insert("wasmer_vm_catch".to_string(), LibCall::Catch);
+    libcalls.insert("wasmer_vm_catch_all".to_string(), LibCall::CatchAll);
+    libcalls.insert(
+        "wasmer_vm_exception_pending".to_string(),
+        LibCall::ExceptionPending,
+    );
+    libcalls.insert("wasmer_vm_return_call".to_string(), LibCall::ReturnCall);
+    libcalls.insert(
+        "wasmer_vm_return_call_indirect".to_string(),
+        LibCall::ReturnCallIndirect,
+    );
+    libcalls.insert("wasmer_vm_tail_call".to_string(), LibCall::TailCall);
+    libcalls.insert(
+        "wasmer_vm_tail_call_entry".to_string(),
+        LibCall::TailCallEntry,
+    );
     libcalls.insert("wasmer_vm_raise_trap".to_string(), LibCall::RaiseTrap);
 
     let elf = object::File::parse(contents).map_err(map_object_err)?;
@@ -7,12 +7,13 @@ use super::{
 };
 use inkwell::{
     attributes::AttributeLoc,
+    basic_block::BasicBlock,
     builder::Builder,
     context::Context,
     module::{Linkage, Module},
     passes::PassManager,
     targets::{FileType, TargetMachine},
-    types::{BasicType, FloatMathType, IntType, PointerType, VectorType},
+    types::{BasicType, BasicTypeEnum, FloatMathType, IntType, PointerType, VectorType},
     values::{
         BasicMetadataValueEnum, BasicValue, BasicValueEnum, FloatValue, FunctionValue,
         InstructionOpcode, InstructionValue, IntValue, PhiValue, PointerValue, VectorValue,
@@ -27,13 +28,14 @@ use crate::object_file::{load_object_file, CompiledFunction};
 use std::convert::TryFrom;
 use wasmer_compiler::wasmparser::{MemoryImmediate, Operator};
 use wasmer_compiler::{
-    from_binaryreadererror_wasmerror, wptype_to_type, FunctionBinaryReader, FunctionBodyData,
-    MiddlewareBinaryReader, ModuleMiddlewareChain, ModuleTranslationState, Symbol, SymbolRegistry,
+    from_binaryreadererror_wasmerror, wptype_to_type, FuelMetering, FunctionBinaryReader,
+    FunctionBodyData, FunctionFuel, MiddlewareBinaryReader, ModuleMiddlewareChain,
+    ModuleTranslationState, Symbol, SymbolRegistry,
 };
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{
-    CompileError, FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex,
-    ModuleInfo, RelocationTarget, SignatureIndex, TableIndex, Type,
+    CompileError, Features, FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex,
+    MemoryIndex, ModuleInfo, RelocationTarget, SignatureIndex, TableIndex, TagIndex, Type,
 };
 use wasmer_vm::{MemoryStyle, TableStyle, VMOffsets};
 
@@ -69,6 +71,7 @@ impl FuncTranslator {
         config: &LLVM,
         memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
         _table_styles: &PrimaryMap<TableIndex, TableStyle>,
+        features: &Features,
         symbol_registry: &dyn SymbolRegistry,
     ) -> Result<Module, CompileError> {
         // The function type, used for the callbacks.
@@ -205,6 +208,10 @@ impl FuncTranslator {
             symbol_registry,
             abi: &*self.abi,
             config,
+            fuel: config.fuel.as_ref().map(FuelMetering::function_fuel),
+            exceptions: features.exceptions,
+            called_by_tail_call_loop: None,
+            return_zeros_block: None,
         };
         fcg.ctx.add_func(
             func_index,
@@ -213,6 +220,13 @@ impl FuncTranslator {
             &func_attrs,
         );
 
+        if features.tail_call && function_body.makes_tail_calls()? {
+            fcg.translate_tail_call_entry(*local_func_index);
+        }
+        if config.epoch_interruption {
+            fcg.trap_if_epoch_deadline_reached();
+        }
+
         while fcg.state.has_control_frames() {
             let pos = reader.current_position() as u32;
             let op = reader.read_operator()?;
@@ -280,6 +294,7 @@ impl FuncTranslator {
         config: &LLVM,
         memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
         table_styles: &PrimaryMap<TableIndex, TableStyle>,
+        features: &Features,
         symbol_registry: &dyn SymbolRegistry,
     ) -> Result<CompiledFunction, CompileError> {
         let module = self.translate_to_module(
@@ -290,6 +305,7 @@ impl FuncTranslator {
             config,
             memory_styles,
             table_styles,
+            features,
             symbol_registry,
         )?;
         let function = CompiledKind::Local(*local_func_index);
@@ -1222,6 +1238,559 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         self.builder.position_at_end(continue_block);
     }
 
+    /// Traps with `Interrupt` if the epoch counter has reached the epoch
+    /// deadline.
+    fn trap_if_epoch_deadline_reached(&self) {
+        let vmctx = self.ctx.basic().into_pointer_value();
+        let load_epoch = |offset: u32, name: &str| {
+            let offset = self.intrinsics.i32_ty.const_int(offset.into(), false);
+            let ptr_ptr = unsafe { self.builder.build_gep(vmctx, &[offset], "") };
+            let ptr_ptr = self
+                .builder
+                .build_bitcast(
+                    ptr_ptr,
+                    self.intrinsics.i64_ptr_ty.ptr_type(AddressSpace::Generic),
+                    "",
+                )
+                .into_pointer_value();
+            let ptr = self.builder.build_load(ptr_ptr, "").into_pointer_value();
+            let value = self.builder.build_load(ptr, name);
+            // Other threads change the epoch, so the load must not be
+            // hoisted out of loops.
+            value
+                .as_instruction_value()
+                .unwrap()
+                .set_volatile(true)
+                .unwrap();
+            value.into_int_value()
+        };
+        let offsets = self.ctx.get_offsets();
+        let epoch = load_epoch(offsets.vmctx_epoch_counter(), "epoch");
+        let deadline = load_epoch(offsets.vmctx_epoch_deadline(), "epoch_deadline");
+
+        let reached = self
+            .builder
+            .build_int_compare(IntPredicate::UGE, epoch, deadline, "");
+        let reached = self
+            .builder
+            .build_call(
+                self.intrinsics.expect_i1,
+                &[
+                    reached.into(),
+                    self.intrinsics.i1_ty.const_int(0, false).into(),
+                ],
+                "",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+
+        let continue_block = self
+            .context
+            .append_basic_block(self.function, "epoch_check_continue_block");
+        let interrupt_block = self
+            .context
+            .append_basic_block(self.function, "epoch_interrupt_block");
+        self.builder
+            .build_conditional_branch(reached, interrupt_block, continue_block);
+
+        self.builder.position_at_end(interrupt_block);
+        self.builder.build_call(
+            self.intrinsics.throw_trap,
+            &[self.intrinsics.trap_interrupt.into()],
+            "throw",
+        );
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(continue_block);
+    }
+
+    /// Charges `cost` to the fuel of the instance, or sets the exhausted
+    /// flag and traps with `OutOfFuel` if there isn't enough left.
+    fn charge_fuel(&self, cost: u64) {
+        let vmctx = self.ctx.basic().into_pointer_value();
+        let fuel_ptr = |offset: u32| {
+            let offset = self.intrinsics.i32_ty.const_int(offset.into(), false);
+            let ptr = unsafe { self.builder.build_gep(vmctx, &[offset], "") };
+            self.builder
+                .build_bitcast(ptr, self.intrinsics.i64_ptr_ty, "")
+                .into_pointer_value()
+        };
+        let offsets = self.ctx.get_offsets();
+        let remaining_ptr = fuel_ptr(offsets.vmctx_fuel_remaining());
+        let exhausted_ptr = fuel_ptr(offsets.vmctx_fuel_exhausted());
+
+        let remaining = self
+            .builder
+            .build_load(remaining_ptr, "fuel")
+            .into_int_value();
+        let cost = self.intrinsics.i64_ty.const_int(cost, false);
+        let out_of_fuel = self
+            .builder
+            .build_int_compare(IntPredicate::ULT, remaining, cost, "");
+        let out_of_fuel = self
+            .builder
+            .build_call(
+                self.intrinsics.expect_i1,
+                &[
+                    out_of_fuel.into(),
+                    self.intrinsics.i1_ty.const_int(0, false).into(),
+                ],
+                "",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+
+        let continue_block = self
+            .context
+            .append_basic_block(self.function, "fuel_charged_block");
+        let out_of_fuel_block = self
+            .context
+            .append_basic_block(self.function, "out_of_fuel_block");
+        self.builder
+            .build_conditional_branch(out_of_fuel, out_of_fuel_block, continue_block);
+
+        self.builder.position_at_end(out_of_fuel_block);
+        self.builder
+            .build_store(exhausted_ptr, self.intrinsics.i64_ty.const_int(1, false));
+        self.builder.build_call(
+            self.intrinsics.throw_trap,
+            &[self.intrinsics.trap_out_of_fuel.into()],
+            "throw",
+        );
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(continue_block);
+        let remaining = self.builder.build_int_sub(remaining, cost, "");
+        self.builder.build_store(remaining_ptr, remaining);
+    }
+
+    /// Returns the block that handles the exceptions thrown inside the
+    /// frame at `depth`: the landing pad of the innermost `try` still in its
+    /// body, or the block that returns to the caller with the exception
+    /// pending.
+    fn exception_handler(&mut self, depth: u32) -> Result<BasicBlock<'ctx>, CompileError> {
+        match self.state.landing_pad_at_depth(depth) {
+            Some(landing_pad) => Ok(landing_pad),
+            None => self.return_zeros_block(),
+        }
+    }
+
+    /// Returns the block that returns zeros to the caller, which ignores
+    /// them as an exception is pending or it is the tail call loop.
+    fn return_zeros_block(&mut self) -> Result<BasicBlock<'ctx>, CompileError> {
+        if let Some(return_zeros_block) = self.return_zeros_block {
+            return Ok(return_zeros_block);
+        }
+
+        let current_block = self
+            .builder
+            .get_insert_block()
+            .ok_or_else(|| CompileError::Codegen("not currently in a block".to_string()))?;
+        let return_zeros_block = self
+            .context
+            .append_basic_block(self.function, "return_zeros");
+        self.builder.position_at_end(return_zeros_block);
+        let frame = self.state.outermost_frame()?;
+        for phi in frame.phis() {
+            let zero = phi.as_basic_value().get_type().const_zero();
+            phi.add_incoming(&[(&zero, return_zeros_block)]);
+        }
+        self.builder.build_unconditional_branch(*frame.br_dest());
+        self.builder.position_at_end(current_block);
+
+        self.return_zeros_block = Some(return_zeros_block);
+        Ok(return_zeros_block)
+    }
+
+    /// Makes the exceptions landing in `block` go to the handler of the
+    /// frame at `depth`.
+    fn forward_exceptions(
+        &mut self,
+        block: BasicBlock<'ctx>,
+        depth: u32,
+    ) -> Result<(), CompileError> {
+        let handler = self.exception_handler(depth)?;
+        let current_block = self
+            .builder
+            .get_insert_block()
+            .ok_or_else(|| CompileError::Codegen("not currently in a block".to_string()))?;
+        self.builder.position_at_end(block);
+        self.builder.build_unconditional_branch(handler);
+        self.builder.position_at_end(current_block);
+        Ok(())
+    }
+
+    /// Branches to the exception handler if the call just translated
+    /// returned with an exception pending.
+    ///
+    /// The runtime is only asked whether the exception is pending in the
+    /// running call if a call has one pending on some thread.
+    fn translate_exception_check(&mut self) -> Result<(), CompileError> {
+        let vmctx = self.ctx.basic().into_pointer_value();
+        let offset = self.ctx.get_offsets().vmctx_exceptions_pending();
+        let offset = self.intrinsics.i32_ty.const_int(offset.into(), false);
+        let pending_ptr_ptr = unsafe { self.builder.build_gep(vmctx, &[offset], "") };
+        let pending_ptr_ptr = self
+            .builder
+            .build_bitcast(
+                pending_ptr_ptr,
+                self.intrinsics.isize_ptr_ty.ptr_type(AddressSpace::Generic),
+                "",
+            )
+            .into_pointer_value();
+        let pending_ptr = self
+            .builder
+            .build_load(pending_ptr_ptr, "")
+            .into_pointer_value();
+        let maybe_pending = self
+            .builder
+            .build_load(pending_ptr, "exceptions_pending")
+            .into_int_value();
+        let maybe_pending = self.builder.build_int_compare(
+            IntPredicate::NE,
+            maybe_pending,
+            self.intrinsics.isize_ty.const_zero(),
+            "",
+        );
+        let maybe_pending = self
+            .builder
+            .build_call(
+                self.intrinsics.expect_i1,
+                &[
+                    maybe_pending.into(),
+                    self.intrinsics.i1_ty.const_int(0, false).into(),
+                ],
+                "",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+
+        let check_block = self
+            .context
+            .append_basic_block(self.function, "exception_check_block");
+        let continue_block = self
+            .context
+            .append_basic_block(self.function, "exception_check_continue_block");
+        self.builder
+            .build_conditional_branch(maybe_pending, check_block, continue_block);
+
+        self.builder.position_at_end(check_block);
+        let pending = self
+            .builder
+            .build_call(
+                self.intrinsics.exception_pending,
+                &[self.ctx.basic().into()],
+                "pending_exception",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let pending =
+            self.builder
+                .build_int_compare(IntPredicate::NE, pending, self.intrinsics.i32_zero, "");
+        let handler = self.exception_handler(0)?;
+        self.builder
+            .build_conditional_branch(pending, handler, continue_block);
+        self.builder.position_at_end(continue_block);
+        Ok(())
+    }
+
+    /// Asks the runtime, on entry of a function that makes tail calls,
+    /// whether the loop that makes the tail calls of the running call called
+    /// it.
+    fn translate_tail_call_entry(&mut self, local_func_index: LocalFunctionIndex) {
+        let function_index = self
+            .intrinsics
+            .i32_ty
+            .const_int(local_func_index.as_u32().into(), false);
+        let called_by_loop = self
+            .builder
+            .build_call(
+                self.intrinsics.tail_call_entry,
+                &[self.ctx.basic().into(), function_index.into()],
+                "called_by_tail_call_loop",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        self.called_by_tail_call_loop = Some(called_by_loop);
+    }
+
+    /// Returns from a function that just requested a tail call. If the loop
+    /// that makes tail calls called the function, it makes this one too, and
+    /// the function returns zeros. Otherwise the function makes the tail
+    /// call and returns its results.
+    fn translate_tail_call_return(&mut self) -> Result<(), CompileError> {
+        let called_by_loop = self
+            .called_by_tail_call_loop
+            .ok_or_else(|| CompileError::Codegen("tail call entry wasn't checked".to_string()))?;
+        let called_by_loop = self.builder.build_int_compare(
+            IntPredicate::NE,
+            called_by_loop,
+            self.intrinsics.i32_zero,
+            "",
+        );
+        let return_zeros_block = self.return_zeros_block()?;
+        let tail_call_block = self
+            .context
+            .append_basic_block(self.function, "tail_call_block");
+        self.builder
+            .build_conditional_branch(called_by_loop, return_zeros_block, tail_call_block);
+
+        self.builder.position_at_end(tail_call_block);
+        let frame = self.state.outermost_frame()?;
+        let phis = frame.phis().to_vec();
+        let return_block = *frame.br_dest();
+        let results = self.values_array(phis.len(), "tail_call_results");
+        let results_ptr = self
+            .builder
+            .build_pointer_cast(results, self.intrinsics.i8_ptr_ty, "");
+        self.builder.build_call(
+            self.intrinsics.tail_call,
+            &[self.ctx.basic().into(), results_ptr.into()],
+            "",
+        );
+        for (i, phi) in phis.iter().enumerate() {
+            let ptr = self.values_element(results, i, phi.as_basic_value().get_type());
+            let result = self.builder.build_load(ptr, "");
+            phi.add_incoming(&[(&result, tail_call_block)]);
+        }
+        self.builder.build_unconditional_branch(return_block);
+        self.state.reachable = false;
+        Ok(())
+    }
+
+    /// Stores `values`, of types `types`, in a new array allocated with
+    /// `values_array`, and returns it as an `i8*`.
+    fn store_values(
+        &mut self,
+        values: &[(BasicValueEnum<'ctx>, ExtraInfo)],
+        types: &[Type],
+    ) -> Result<PointerValue<'ctx>, CompileError> {
+        let array = self.values_array(values.len(), "values");
+        for (i, ((value, info), wasm_ty)) in values.iter().zip(types.iter()).enumerate() {
+            let ty = type_to_llvm(self.intrinsics, *wasm_ty)?;
+            let value = self.apply_pending_canonicalization(*value, *info);
+            let value = self.builder.build_bitcast(value, ty, "");
+            let ptr = self.values_element(array, i, ty);
+            self.builder.build_store(ptr, value);
+        }
+        Ok(self
+            .builder
+            .build_pointer_cast(array, self.intrinsics.i8_ptr_ty, ""))
+    }
+
+    /// Allocates room for the values carried by the exceptions of
+    /// `tag_index`, one every 16 bytes, and returns it with their types.
+    fn exception_payload(&self, tag_index: TagIndex) -> (PointerValue<'ctx>, Vec<Type>) {
+        let params = self.wasm_module.signatures[self.wasm_module.tags[tag_index]]
+            .params()
+            .to_vec();
+        let payload = self.values_array(params.len(), "exception_payload");
+        (payload, params)
+    }
+
+    /// Allocates the stack slot that identifies a catch clause to the
+    /// runtime.
+    fn catch_slot(&self) -> PointerValue<'ctx> {
+        self.alloca_builder
+            .build_alloca(self.intrinsics.i8_ty, "catch_slot")
+    }
+
+    /// Allocates room for `len` values, one every 16 bytes, as the runtime
+    /// expects them.
+    fn values_array(&self, len: usize, name: &str) -> PointerValue<'ctx> {
+        let array_ty = self.intrinsics.i128_ty.array_type(len as u32);
+        self.alloca_builder.build_alloca(array_ty, name)
+    }
+
+    /// Returns a pointer to the `index`th value of an array allocated with
+    /// `values_array`.
+    fn values_element(
+        &self,
+        payload: PointerValue<'ctx>,
+        index: usize,
+        ty: BasicTypeEnum<'ctx>,
+    ) -> PointerValue<'ctx> {
+        let index = self.intrinsics.i32_ty.const_int(index as u64, false);
+        let ptr = unsafe {
+            self.builder
+                .build_in_bounds_gep(payload, &[self.intrinsics.i32_zero, index], "")
+        };
+        self.builder
+            .build_pointer_cast(ptr, ty.ptr_type(AddressSpace::Generic), "")
+    }
+
+    /// Ends the body or the previous clause of the innermost `try`, and
+    /// starts a clause catching the exceptions thrown with `tag_index`, or
+    /// every exception if it is `None`.
+    fn translate_catch_clause(&mut self, tag_index: Option<TagIndex>) -> Result<(), CompileError> {
+        let mut frame = self.state.pop_frame()?;
+        if self.state.reachable {
+            let current_block = self
+                .builder
+                .get_insert_block()
+                .ok_or_else(|| CompileError::Codegen("not currently in a block".to_string()))?;
+            for phi in frame.phis().iter().rev() {
+                let (value, info) = self.state.pop1_extra()?;
+                let value = self.apply_pending_canonicalization(value, info);
+                phi.add_incoming(&[(&value, current_block)]);
+            }
+            self.builder.build_unconditional_branch(*frame.code_after());
+        }
+        self.state.reset_stack(&frame);
+
+        // Exceptions come either from the body, or from the previous clause
+        // if it didn't match them.
+        let block = match frame {
+            ControlFrame::Try {
+                ref mut landing_pad,
+                ref mut no_match,
+                ..
+            } => landing_pad.take().or_else(|| no_match.take()),
+            _ => return Err(CompileError::Codegen("catch must follow a try".to_string())),
+        };
+        let block = match block {
+            Some(block) => block,
+            None => {
+                self.state.reachable = false;
+                self.state.push_frame(frame);
+                return Ok(());
+            }
+        };
+        self.builder.position_at_end(block);
+        self.state.reachable = true;
+
+        let (exception, next_no_match) = match tag_index {
+            Some(tag_index) => {
+                let (payload, params) = self.exception_payload(tag_index);
+                let payload_arg =
+                    self.builder
+                        .build_pointer_cast(payload, self.intrinsics.i8_ptr_ty, "");
+                let tag_index_arg = self
+                    .intrinsics
+                    .i32_ty
+                    .const_int(tag_index.as_u32().into(), false);
+                let slot = self.catch_slot();
+                let exception = self
+                    .builder
+                    .build_call(
+                        self.intrinsics.catch,
+                        &[
+                            self.ctx.basic().into(),
+                            tag_index_arg.into(),
+                            payload_arg.into(),
+                            slot.into(),
+                        ],
+                        "exception",
+                    )
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_pointer_value();
+
+                let no_match = self
+                    .context
+                    .append_basic_block(self.function, "catch_no_match");
+                let catch_block = self.context.append_basic_block(self.function, "catch");
+                let caught = self.builder.build_is_not_null(exception, "");
+                self.builder
+                    .build_conditional_branch(caught, catch_block, no_match);
+                self.builder.position_at_end(catch_block);
+                for (i, wasm_ty) in params.iter().enumerate() {
+                    let ty = type_to_llvm(self.intrinsics, *wasm_ty)?;
+                    let ptr = self.values_element(payload, i, ty);
+                    let value = self.builder.build_load(ptr, "");
+                    self.state.push1(value);
+                }
+                (exception, Some(no_match))
+            }
+            None => {
+                let slot = self.catch_slot();
+                let exception = self
+                    .builder
+                    .build_call(
+                        self.intrinsics.catch_all,
+                        &[self.ctx.basic().into(), slot.into()],
+                        "exception",
+                    )
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_pointer_value();
+                (exception, None)
+            }
+        };
+        if let ControlFrame::Try {
+            ref mut no_match,
+            exception: ref mut frame_exception,
+            ..
+        } = frame
+        {
+            *no_match = next_no_match;
+            *frame_exception = Some(exception);
+        }
+        self.state.push_frame(frame);
+        Ok(())
+    }
+
+    /// Adds the static offset of `memarg` to the address of a
+    /// `memory.atomic.wait` or `memory.atomic.notify`, trapping if the sum
+    /// overflows. The builtin checks the bounds and alignment of the result.
+    fn resolve_atomic_wait_notify_address(
+        &self,
+        memarg: &MemoryImmediate,
+        addr: IntValue<'ctx>,
+    ) -> IntValue<'ctx> {
+        if memarg.offset == 0 {
+            return addr;
+        }
+
+        let addr = self
+            .builder
+            .build_int_z_extend(addr, self.intrinsics.i64_ty, "");
+        let addr = self.builder.build_int_add(
+            addr,
+            self.intrinsics.i64_ty.const_int(memarg.offset, false),
+            "",
+        );
+        let in_range = self.builder.build_int_compare(
+            IntPredicate::ULE,
+            addr,
+            self.intrinsics.i64_ty.const_int(u32::MAX.into(), false),
+            "",
+        );
+
+        let continue_block = self
+            .context
+            .append_basic_block(self.function, "in_range_continue_block");
+        let out_of_range_block = self
+            .context
+            .append_basic_block(self.function, "out_of_range_trap_block");
+        self.builder
+            .build_conditional_branch(in_range, continue_block, out_of_range_block);
+
+        self.builder.position_at_end(out_of_range_block);
+        self.builder.build_call(
+            self.intrinsics.throw_trap,
+            &[self.intrinsics.trap_memory_oob.into()],
+            "throw",
+        );
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(continue_block);
+        self.builder
+            .build_int_truncate(addr, self.intrinsics.i32_ty, "")
+    }
+
     fn finalize(&mut self, wasm_fn_type: &FunctionType) -> Result<(), CompileError> {
         let func_type = self.function.get_type();
 
@@ -1380,6 +1949,17 @@ pub struct LLVMFunctionCodeGenerator<'ctx, 'a> {
     symbol_registry: &'a dyn SymbolRegistry,
     abi: &'a dyn Abi,
     config: &'a LLVM,
+    fuel: Option<FunctionFuel>,
+    /// Whether the exception handling proposal is enabled, so that calls
+    /// can return with an exception pending.
+    exceptions: bool,
+    /// Whether the loop that makes the tail calls of the running call called
+    /// the function, if it makes tail calls.
+    called_by_tail_call_loop: Option<IntValue<'ctx>>,
+    /// The block returning zeros to the caller, which ignores them as an
+    /// exception is pending or it is the tail call loop. Created on first
+    /// use.
+    return_zeros_block: Option<BasicBlock<'ctx>>,
 }
 
 impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
@@ -1390,18 +1970,28 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
 
         //let opcode_offset: Option<usize> = None;
 
+        if let Some(cost) = self.fuel.as_mut().and_then(|fuel| fuel.feed(&op)) {
+            // The cost of unreachable code is dropped along with it.
+            if self.state.reachable {
+                self.charge_fuel(cost);
+            }
+        }
+
         if !self.state.reachable {
             match op {
-                Operator::Block { ty: _ } | Operator::Loop { ty: _ } | Operator::If { ty: _ } => {
+                Operator::Block { ty: _ }
+                | Operator::Loop { ty: _ }
+                | Operator::If { ty: _ }
+                | Operator::Try { ty: _ } => {
                     self.unreachable_depth += 1;
                     return Ok(());
                 }
-                Operator::Else => {
+                Operator::Else | Operator::Catch { .. } | Operator::CatchAll => {
                     if self.unreachable_depth != 0 {
                         return Ok(());
                     }
                 }
-                Operator::End => {
+                Operator::End | Operator::Delegate { .. } => {
                     if self.unreachable_depth != 0 {
                         self.unreachable_depth -= 1;
                         return Ok(());
@@ -1488,6 +2078,10 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     self.state.push1(phi.as_basic_value());
                 }
 
+                if self.config.epoch_interruption {
+                    self.trap_if_epoch_deadline_reached();
+                }
+
                 /*
                 if self.track_state {
                     if let Some(offset) = opcode_offset {
@@ -1770,7 +2364,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 };
             }
 
-            Operator::End => {
+            Operator::End | Operator::Delegate { .. } => {
                 let frame = self.state.pop_frame()?;
                 let current_block = self
                     .builder
@@ -1787,6 +2381,24 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     self.builder.build_unconditional_branch(*frame.code_after());
                 }
 
+                // `delegate` forwards the exceptions thrown in the body to the
+                // frame it names, and `end` forwards the ones that no clause
+                // caught to the enclosing frames.
+                if let ControlFrame::Try {
+                    landing_pad,
+                    no_match,
+                    ..
+                } = &frame
+                {
+                    let (block, depth) = match op {
+                        Operator::Delegate { relative_depth } => (*landing_pad, relative_depth),
+                        _ => (landing_pad.or(*no_match), 0),
+                    };
+                    if let Some(block) = block {
+                        self.forward_exceptions(block, depth)?;
+                    }
+                }
+
                 if let ControlFrame::IfElse {
                     if_else,
                     next,
@@ -1819,6 +2431,88 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     }
                 }
             }
+            Operator::Try { ty } => {
+                let current_block = self
+                    .builder
+                    .get_insert_block()
+                    .ok_or_else(|| CompileError::Codegen("not currently in a block".to_string()))?;
+
+                let end_block = self.context.append_basic_block(self.function, "try_end");
+                self.builder.position_at_end(end_block);
+
+                let (params, results) = self.module_translation.blocktype_params_results(ty)?;
+                let phis: SmallVec<[PhiValue<'ctx>; 1]> = results
+                    .iter()
+                    .map(|&wp_ty| {
+                        wptype_to_type(wp_ty)
+                            .map_err(to_compile_error)
+                            .and_then(|wasm_ty| {
+                                type_to_llvm(self.intrinsics, wasm_ty)
+                                    .map(|ty| self.builder.build_phi(ty, ""))
+                            })
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                let landing_pad = self
+                    .context
+                    .append_basic_block(self.function, "landing_pad");
+                self.state
+                    .push_try(end_block, landing_pad, params.len(), phis);
+                self.builder.position_at_end(current_block);
+            }
+            Operator::Catch { index } => {
+                self.translate_catch_clause(Some(TagIndex::from_u32(index)))?;
+            }
+            Operator::CatchAll => {
+                self.translate_catch_clause(None)?;
+            }
+            Operator::Throw { index } => {
+                let tag_index = TagIndex::from_u32(index);
+                let (payload, params) = self.exception_payload(tag_index);
+                let values = self.state.popn_save_extra(params.len())?;
+                for (i, ((value, info), wasm_ty)) in values.iter().zip(params.iter()).enumerate() {
+                    let ty = type_to_llvm(self.intrinsics, *wasm_ty)?;
+                    let value = self.apply_pending_canonicalization(*value, *info);
+                    let value = self.builder.build_bitcast(value, ty, "");
+                    let ptr = self.values_element(payload, i, ty);
+                    self.builder.build_store(ptr, value);
+                }
+                let payload =
+                    self.builder
+                        .build_pointer_cast(payload, self.intrinsics.i8_ptr_ty, "");
+                let tag_index = self.intrinsics.i32_ty.const_int(index.into(), false);
+                self.builder.build_call(
+                    self.intrinsics.throw,
+                    &[self.ctx.basic().into(), tag_index.into(), payload.into()],
+                    "",
+                );
+
+                let handler = self.exception_handler(0)?;
+                self.builder.build_unconditional_branch(handler);
+                self.state.reachable = false;
+            }
+            Operator::Rethrow { relative_depth } => {
+                let exception = match self.state.frame_at_depth(relative_depth)? {
+                    ControlFrame::Try {
+                        exception: Some(exception),
+                        ..
+                    } => *exception,
+                    _ => {
+                        return Err(CompileError::Codegen(
+                            "rethrow must target a catch clause".to_string(),
+                        ))
+                    }
+                };
+                self.builder.build_call(
+                    self.intrinsics.rethrow,
+                    &[self.ctx.basic().into(), exception.into()],
+                    "",
+                );
+
+                let handler = self.exception_handler(0)?;
+                self.builder.build_unconditional_branch(handler);
+                self.state.reachable = false;
+            }
             Operator::Return => {
                 let current_block = self
                     .builder
@@ -1837,6 +2531,50 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 self.state.reachable = false;
             }
 
+            Operator::ReturnCall { function_index } => {
+                let func_index = FunctionIndex::from_u32(function_index);
+                let func_type =
+                    &self.wasm_module.signatures[self.wasm_module.functions[func_index]];
+                let args = self.state.popn_save_extra(func_type.params().len())?;
+                let values = self.store_values(&args, func_type.params())?;
+                let function_index = self
+                    .intrinsics
+                    .i32_ty
+                    .const_int(function_index.into(), false);
+                self.builder.build_call(
+                    self.intrinsics.return_call,
+                    &[
+                        self.ctx.basic().into(),
+                        function_index.into(),
+                        values.into(),
+                    ],
+                    "",
+                );
+
+                self.translate_tail_call_return()?;
+            }
+            Operator::ReturnCallIndirect { index, table_index } => {
+                let func_type = &self.wasm_module.signatures[SignatureIndex::from_u32(index)];
+                let callee = self.state.pop1()?.into_int_value();
+                let args = self.state.popn_save_extra(func_type.params().len())?;
+                let values = self.store_values(&args, func_type.params())?;
+                let table_index = self.intrinsics.i32_ty.const_int(table_index.into(), false);
+                let sig_index = self.intrinsics.i32_ty.const_int(index.into(), false);
+                self.builder.build_call(
+                    self.intrinsics.return_call_indirect,
+                    &[
+                        self.ctx.basic().into(),
+                        table_index.into(),
+                        sig_index.into(),
+                        callee.into(),
+                        values.into(),
+                    ],
+                    "",
+                );
+
+                self.translate_tail_call_return()?;
+            }
+
             Operator::Unreachable => {
                 // Emit an unreachable instruction.
                 // If llvm cannot prove that this is never reached,
@@ -2265,8 +3003,14 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     .rets_from_call(&self.builder, self.intrinsics, call_site, func_type)
                     .iter()
                     .for_each(|ret| self.state.push1(*ret));
+
+                if self.exceptions {
+                    self.translate_exception_check()?;
+                }
             }
-            Operator::CallIndirect { index, table_index } => {
+            Operator::CallIndirect {
+                index, table_index, ..
+            } => {
                 let sigindex = SignatureIndex::from_u32(index);
                 let func_type = &self.wasm_module.signatures[sigindex];
                 let expected_dynamic_sigindex =
@@ -2559,6 +3303,10 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     .rets_from_call(&self.builder, self.intrinsics, call_site, func_type)
                     .iter()
                     .for_each(|ret| self.state.push1(*ret));
+
+                if self.exceptions {
+                    self.translate_exception_check()?;
+                }
             }
 
             /***************************
@@ -7612,7 +8360,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
              ***************************/
             Operator::I32Load { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7631,7 +8379,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7650,7 +8398,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::F32Load { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7669,7 +8417,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::F64Load { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7688,7 +8436,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7805,7 +8553,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32Store { ref memarg } => {
                 let value = self.state.pop1()?;
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7826,7 +8574,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64Store { ref memarg } => {
                 let value = self.state.pop1()?;
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7848,7 +8596,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let (v, i) = self.state.pop1_extra()?;
                 let v = self.apply_pending_canonicalization(v, i);
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7870,7 +8618,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let (v, i) = self.state.pop1_extra()?;
                 let v = self.apply_pending_canonicalization(v, i);
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -7892,7 +8640,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let (v, i) = self.state.pop1_extra()?;
                 let v = self.apply_pending_canonicalization(v, i);
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8012,7 +8760,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I32Load8S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8036,7 +8784,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I32Load16S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8060,7 +8808,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load8S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8085,7 +8833,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load16S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8110,7 +8858,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load32S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8135,7 +8883,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
 
             Operator::I32Load8U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8159,7 +8907,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I32Load16U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8183,7 +8931,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load8U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8207,7 +8955,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load16U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8231,7 +8979,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64Load32U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8257,7 +9005,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32Store8 { ref memarg } | Operator::I64Store8 { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8281,7 +9029,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32Store16 { ref memarg } | Operator::I64Store16 { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8305,7 +9053,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64Store32 { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8687,7 +9435,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load8x8S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8708,7 +9456,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load8x8U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8729,7 +9477,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load16x4S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8750,7 +9498,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load16x4U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8771,7 +9519,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load32x2S { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8792,7 +9540,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load32x2U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8813,7 +9561,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load32Zero { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8837,7 +9585,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load64Zero { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8861,7 +9609,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load8Splat { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8882,7 +9630,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load16Splat { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8903,7 +9651,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load32Splat { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8924,7 +9672,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::V128Load64Splat { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8954,7 +9702,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I32AtomicLoad { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8972,7 +9720,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64AtomicLoad { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -8990,7 +9738,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I32AtomicLoad8U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9014,7 +9762,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I32AtomicLoad16U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9038,7 +9786,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64AtomicLoad8U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9062,7 +9810,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64AtomicLoad16U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9086,7 +9834,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::I64AtomicLoad32U { ref memarg } => {
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9111,7 +9859,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicStore { ref memarg } => {
                 let value = self.state.pop1()?;
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9129,7 +9877,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicStore { ref memarg } => {
                 let value = self.state.pop1()?;
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9147,7 +9895,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicStore8 { ref memarg } | Operator::I64AtomicStore8 { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9169,7 +9917,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             | Operator::I64AtomicStore16 { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9190,7 +9938,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicStore32 { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9211,7 +9959,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw8AddU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9246,7 +9994,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw16AddU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9281,7 +10029,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmwAdd { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9310,7 +10058,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw8AddU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9345,7 +10093,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw16AddU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9380,7 +10128,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw32AddU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9415,7 +10163,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmwAdd { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9444,7 +10192,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw8SubU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9479,7 +10227,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw16SubU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9514,7 +10262,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmwSub { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9543,7 +10291,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw8SubU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9578,7 +10326,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw16SubU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9613,7 +10361,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw32SubU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9648,7 +10396,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmwSub { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9677,7 +10425,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw8AndU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9712,7 +10460,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw16AndU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9747,7 +10495,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmwAnd { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9776,7 +10524,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw8AndU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9811,7 +10559,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw16AndU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9846,7 +10594,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw32AndU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9881,7 +10629,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmwAnd { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9910,7 +10658,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw8OrU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9945,7 +10693,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw16OrU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -9980,7 +10728,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmwOr { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10012,7 +10760,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw8OrU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10047,7 +10795,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw16OrU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10082,7 +10830,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw32OrU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10117,7 +10865,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmwOr { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10146,7 +10894,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw8XorU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10181,7 +10929,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw16XorU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10216,7 +10964,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmwXor { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10245,7 +10993,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw8XorU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10280,7 +11028,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw16XorU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10315,7 +11063,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw32XorU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10350,7 +11098,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmwXor { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10379,7 +11127,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw8XchgU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10414,7 +11162,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmw16XchgU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10449,7 +11197,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I32AtomicRmwXchg { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10478,7 +11226,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw8XchgU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10513,7 +11261,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw16XchgU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10548,7 +11296,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmw32XchgU { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10583,7 +11331,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             Operator::I64AtomicRmwXchg { ref memarg } => {
                 let value = self.state.pop1()?.into_int_value();
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10615,7 +11363,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10662,7 +11410,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10709,7 +11457,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10743,7 +11491,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10790,7 +11538,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10837,7 +11585,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10884,7 +11632,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                 let new = self.apply_pending_canonicalization(new, new_info);
                 let (cmp, new) = (cmp.into_int_value(), new.into_int_value());
                 let offset = self.state.pop1()?.into_int_value();
-                let memory_index = MemoryIndex::from_u32(0);
+                let memory_index = MemoryIndex::from_u32(memarg.memory);
                 let effective_address = self.resolve_memory_ptr(
                     memory_index,
                     memarg,
@@ -10915,6 +11663,11 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
 
             Operator::MemoryGrow { mem, mem_byte: _ } => {
                 let memory_index = MemoryIndex::from_u32(mem);
+                // The builtins for local memories take a local index.
+                let mem = self
+                    .wasm_module
+                    .local_memory_index(memory_index)
+                    .map_or(mem, |local_memory_index| local_memory_index.as_u32());
                 let delta = self.state.pop1()?;
                 let grow_fn_ptr = self.ctx.memory_grow(memory_index, self.intrinsics);
                 let callable_func = inkwell::values::CallableValue::try_from(grow_fn_ptr).unwrap();
@@ -10931,6 +11684,11 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             Operator::MemorySize { mem, mem_byte: _ } => {
                 let memory_index = MemoryIndex::from_u32(mem);
+                // The builtins for local memories take a local index.
+                let mem = self
+                    .wasm_module
+                    .local_memory_index(memory_index)
+                    .map_or(mem, |local_memory_index| local_memory_index.as_u32());
                 let size_fn_ptr = self.ctx.memory_size(memory_index, self.intrinsics);
                 let callable_func = inkwell::values::CallableValue::try_from(size_fn_ptr).unwrap();
                 let size = self.builder.build_call(
@@ -10969,9 +11727,24 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     "",
                 );
             }
-            Operator::MemoryCopy { src, dst } => {
-                // ignored until we support multiple memories
-                let _dst = dst;
+            Operator::MemoryCopy { src, dst } if src != dst => {
+                let (dest_pos, src_pos, len) = self.state.pop3()?;
+                let dst_index = self.intrinsics.i32_ty.const_int(dst.into(), false);
+                let src_index = self.intrinsics.i32_ty.const_int(src.into(), false);
+                self.builder.build_call(
+                    self.intrinsics.memory_copy_between,
+                    &[
+                        vmctx.as_basic_value_enum().into(),
+                        dst_index.into(),
+                        src_index.into(),
+                        dest_pos.into(),
+                        src_pos.into(),
+                        len.into(),
+                    ],
+                    "",
+                );
+            }
+            Operator::MemoryCopy { src, dst: _ } => {
                 let (memory_copy, src) = if let Some(local_memory_index) = self
                     .wasm_module
                     .local_memory_index(MemoryIndex::from_u32(src))
@@ -11019,6 +11792,83 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     "",
                 );
             }
+            Operator::MemoryAtomicWait32 { ref memarg } => {
+                let (memory_wait32, mem) = if let Some(local_memory_index) = self
+                    .wasm_module
+                    .local_memory_index(MemoryIndex::from_u32(memarg.memory))
+                {
+                    (self.intrinsics.memory_wait32, local_memory_index.as_u32())
+                } else {
+                    (self.intrinsics.imported_memory_wait32, memarg.memory)
+                };
+
+                let (dst, val, timeout) = self.state.pop3()?;
+                let dst = self.resolve_atomic_wait_notify_address(memarg, dst.into_int_value());
+                let mem_index = self.intrinsics.i32_ty.const_int(mem.into(), false);
+                let ret = self.builder.build_call(
+                    memory_wait32,
+                    &[
+                        vmctx.as_basic_value_enum().into(),
+                        mem_index.into(),
+                        dst.into(),
+                        val.into(),
+                        timeout.into(),
+                    ],
+                    "",
+                );
+                self.state.push1(ret.try_as_basic_value().left().unwrap());
+            }
+            Operator::MemoryAtomicWait64 { ref memarg } => {
+                let (memory_wait64, mem) = if let Some(local_memory_index) = self
+                    .wasm_module
+                    .local_memory_index(MemoryIndex::from_u32(memarg.memory))
+                {
+                    (self.intrinsics.memory_wait64, local_memory_index.as_u32())
+                } else {
+                    (self.intrinsics.imported_memory_wait64, memarg.memory)
+                };
+
+                let (dst, val, timeout) = self.state.pop3()?;
+                let dst = self.resolve_atomic_wait_notify_address(memarg, dst.into_int_value());
+                let mem_index = self.intrinsics.i32_ty.const_int(mem.into(), false);
+                let ret = self.builder.build_call(
+                    memory_wait64,
+                    &[
+                        vmctx.as_basic_value_enum().into(),
+                        mem_index.into(),
+                        dst.into(),
+                        val.into(),
+                        timeout.into(),
+                    ],
+                    "",
+                );
+                self.state.push1(ret.try_as_basic_value().left().unwrap());
+            }
+            Operator::MemoryAtomicNotify { ref memarg } => {
+                let (memory_notify, mem) = if let Some(local_memory_index) = self
+                    .wasm_module
+                    .local_memory_index(MemoryIndex::from_u32(memarg.memory))
+                {
+                    (self.intrinsics.memory_notify, local_memory_index.as_u32())
+                } else {
+                    (self.intrinsics.imported_memory_notify, memarg.memory)
+                };
+
+                let (dst, count) = self.state.pop2()?;
+                let dst = self.resolve_atomic_wait_notify_address(memarg, dst.into_int_value());
+                let mem_index = self.intrinsics.i32_ty.const_int(mem.into(), false);
+                let ret = self.builder.build_call(
+                    memory_notify,
+                    &[
+                        vmctx.as_basic_value_enum().into(),
+                        mem_index.into(),
+                        dst.into(),
+                        count.into(),
+                    ],
+                    "",
+                );
+                self.state.push1(ret.try_as_basic_value().left().unwrap());
+            }
             /***************************
              * Reference types.
              * https://github.com/WebAssembly/reference-types/blob/master/proposals/reference-types/Overview.md
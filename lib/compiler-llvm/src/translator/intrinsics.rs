@@ -217,6 +217,8 @@ pub struct Intrinsics<'ctx> {
     pub trap_bad_conversion_to_integer: BasicValueEnum<'ctx>,
     pub trap_unaligned_atomic: BasicValueEnum<'ctx>,
     pub trap_table_access_oob: BasicValueEnum<'ctx>,
+    pub trap_interrupt: BasicValueEnum<'ctx>,
+    pub trap_out_of_fuel: BasicValueEnum<'ctx>,
 
     pub experimental_stackmap: FunctionValue<'ctx>,
 
@@ -238,8 +240,24 @@ pub struct Intrinsics<'ctx> {
     pub elem_drop: FunctionValue<'ctx>,
     pub memory_copy: FunctionValue<'ctx>,
     pub imported_memory_copy: FunctionValue<'ctx>,
+    pub memory_copy_between: FunctionValue<'ctx>,
     pub memory_fill: FunctionValue<'ctx>,
     pub imported_memory_fill: FunctionValue<'ctx>,
+    pub memory_wait32: FunctionValue<'ctx>,
+    pub imported_memory_wait32: FunctionValue<'ctx>,
+    pub memory_wait64: FunctionValue<'ctx>,
+    pub imported_memory_wait64: FunctionValue<'ctx>,
+    pub memory_notify: FunctionValue<'ctx>,
+    pub imported_memory_notify: FunctionValue<'ctx>,
+    pub throw: FunctionValue<'ctx>,
+    pub rethrow: FunctionValue<'ctx>,
+    pub catch: FunctionValue<'ctx>,
+    pub catch_all: FunctionValue<'ctx>,
+    pub exception_pending: FunctionValue<'ctx>,
+    pub return_call: FunctionValue<'ctx>,
+    pub return_call_indirect: FunctionValue<'ctx>,
+    pub tail_call: FunctionValue<'ctx>,
+    pub tail_call_entry: FunctionValue<'ctx>,
 
     pub throw_trap: FunctionValue<'ctx>,
 
@@ -360,6 +378,7 @@ impl<'ctx> Intrinsics<'ctx> {
         let externref_ty = funcref_ty;
         let anyref_ty = i8_ptr_ty;
         let anyref_ty_basic_md: BasicMetadataTypeEnum = anyref_ty.into();
+        let i8_ptr_ty_basic_md: BasicMetadataTypeEnum = i8_ptr_ty.into();
 
         let ret_i8x16_take_i8x16 = i8x16_ty.fn_type(&[i8x16_ty_basic_md], false);
         let ret_i8x16_take_i8x16_i8x16 =
@@ -780,6 +799,12 @@ impl<'ctx> Intrinsics<'ctx> {
             trap_table_access_oob: i32_ty
                 .const_int(TrapCode::TableAccessOutOfBounds as _, false)
                 .as_basic_value_enum(),
+            trap_interrupt: i32_ty
+                .const_int(TrapCode::Interrupt as _, false)
+                .as_basic_value_enum(),
+            trap_out_of_fuel: i32_ty
+                .const_int(TrapCode::OutOfFuel as _, false)
+                .as_basic_value_enum(),
 
             experimental_stackmap: module.add_function(
                 "llvm.experimental.stackmap",
@@ -959,6 +984,21 @@ impl<'ctx> Intrinsics<'ctx> {
                 ),
                 None,
             ),
+            memory_copy_between: module.add_function(
+                "wasmer_vm_memory32_copy_between",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
             memory_fill: module.add_function(
                 "wasmer_vm_memory32_fill",
                 void_ty.fn_type(
@@ -987,6 +1027,88 @@ impl<'ctx> Intrinsics<'ctx> {
                 ),
                 None,
             ),
+            memory_wait32: module.add_function(
+                "wasmer_vm_memory32_atomic_wait32",
+                i32_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i64_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            imported_memory_wait32: module.add_function(
+                "wasmer_vm_imported_memory32_atomic_wait32",
+                i32_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i64_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            memory_wait64: module.add_function(
+                "wasmer_vm_memory32_atomic_wait64",
+                i32_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i64_ty_basic_md,
+                        i64_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            imported_memory_wait64: module.add_function(
+                "wasmer_vm_imported_memory32_atomic_wait64",
+                i32_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i64_ty_basic_md,
+                        i64_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            memory_notify: module.add_function(
+                "wasmer_vm_memory32_atomic_notify",
+                i32_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            imported_memory_notify: module.add_function(
+                "wasmer_vm_imported_memory32_atomic_notify",
+                i32_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
             data_drop: module.add_function(
                 "wasmer_vm_data_drop",
                 void_ty.fn_type(&[ctx_ptr_ty_basic_md, i32_ty_basic_md], false),
@@ -1002,6 +1124,74 @@ impl<'ctx> Intrinsics<'ctx> {
                 void_ty.fn_type(&[ctx_ptr_ty_basic_md, i32_ty_basic_md], false),
                 None,
             ),
+            throw: module.add_function(
+                "wasmer_vm_throw",
+                void_ty.fn_type(
+                    &[ctx_ptr_ty_basic_md, i32_ty_basic_md, i8_ptr_ty_basic_md],
+                    false,
+                ),
+                None,
+            ),
+            rethrow: module.add_function(
+                "wasmer_vm_rethrow",
+                void_ty.fn_type(&[ctx_ptr_ty_basic_md, i8_ptr_ty_basic_md], false),
+                None,
+            ),
+            catch: module.add_function(
+                "wasmer_vm_catch",
+                i8_ptr_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i8_ptr_ty_basic_md,
+                        i8_ptr_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            catch_all: module.add_function(
+                "wasmer_vm_catch_all",
+                i8_ptr_ty.fn_type(&[ctx_ptr_ty_basic_md, i8_ptr_ty_basic_md], false),
+                None,
+            ),
+            exception_pending: module.add_function(
+                "wasmer_vm_exception_pending",
+                i32_ty.fn_type(&[ctx_ptr_ty_basic_md], false),
+                None,
+            ),
+            return_call: module.add_function(
+                "wasmer_vm_return_call",
+                void_ty.fn_type(
+                    &[ctx_ptr_ty_basic_md, i32_ty_basic_md, i8_ptr_ty_basic_md],
+                    false,
+                ),
+                None,
+            ),
+            return_call_indirect: module.add_function(
+                "wasmer_vm_return_call_indirect",
+                void_ty.fn_type(
+                    &[
+                        ctx_ptr_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i32_ty_basic_md,
+                        i8_ptr_ty_basic_md,
+                    ],
+                    false,
+                ),
+                None,
+            ),
+            tail_call: module.add_function(
+                "wasmer_vm_tail_call",
+                void_ty.fn_type(&[ctx_ptr_ty_basic_md, i8_ptr_ty_basic_md], false),
+                None,
+            ),
+            tail_call_entry: module.add_function(
+                "wasmer_vm_tail_call_entry",
+                i32_ty.fn_type(&[ctx_ptr_ty_basic_md, i32_ty_basic_md], false),
+                None,
+            ),
             throw_trap: module.add_function(
                 "wasmer_vm_raise_trap",
                 void_ty.fn_type(&[i32_ty_basic_md], false),
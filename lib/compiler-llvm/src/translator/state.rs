@@ -1,6 +1,6 @@
 use inkwell::{
     basic_block::BasicBlock,
-    values::{BasicValue, BasicValueEnum, PhiValue},
+    values::{BasicValue, BasicValueEnum, PhiValue, PointerValue},
 };
 use smallvec::SmallVec;
 use std::ops::{BitAnd, BitOr, BitOrAssign};
@@ -30,6 +30,19 @@ pub enum ControlFrame<'ctx> {
         stack_size_snapshot: usize,
         if_else_state: IfElseState,
     },
+    Try {
+        next: BasicBlock<'ctx>,
+        phis: SmallVec<[PhiValue<'ctx>; 1]>,
+        stack_size_snapshot: usize,
+        /// Where the exceptions thrown in the body land. This is taken by
+        /// the first `catch`, so it is `None` once the body is translated.
+        landing_pad: Option<BasicBlock<'ctx>>,
+        /// Where the exceptions that the current `catch` doesn't match go
+        /// to the next clause.
+        no_match: Option<BasicBlock<'ctx>>,
+        /// The exception caught by the current clause, for `rethrow`.
+        exception: Option<PointerValue<'ctx>>,
+    },
 }
 
 #[derive(Debug)]
@@ -43,22 +56,25 @@ impl<'ctx> ControlFrame<'ctx> {
         match self {
             ControlFrame::Block { ref next, .. }
             | ControlFrame::Loop { ref next, .. }
-            | ControlFrame::IfElse { ref next, .. } => next,
+            | ControlFrame::IfElse { ref next, .. }
+            | ControlFrame::Try { ref next, .. } => next,
         }
     }
 
     pub fn br_dest(&self) -> &BasicBlock<'ctx> {
         match self {
-            ControlFrame::Block { ref next, .. } | ControlFrame::IfElse { ref next, .. } => next,
+            ControlFrame::Block { ref next, .. }
+            | ControlFrame::IfElse { ref next, .. }
+            | ControlFrame::Try { ref next, .. } => next,
             ControlFrame::Loop { ref body, .. } => body,
         }
     }
 
     pub fn phis(&self) -> &[PhiValue<'ctx>] {
         match self {
-            ControlFrame::Block { ref phis, .. } | ControlFrame::Loop { ref phis, .. } => {
-                phis.as_slice()
-            }
+            ControlFrame::Block { ref phis, .. }
+            | ControlFrame::Loop { ref phis, .. }
+            | ControlFrame::Try { ref phis, .. } => phis.as_slice(),
             ControlFrame::IfElse { ref next_phis, .. } => next_phis.as_slice(),
         }
     }
@@ -66,7 +82,9 @@ impl<'ctx> ControlFrame<'ctx> {
     /// PHI nodes for stack values in the loop body.
     pub fn loop_body_phis(&self) -> &[PhiValue<'ctx>] {
         match self {
-            ControlFrame::Block { .. } | ControlFrame::IfElse { .. } => &[],
+            ControlFrame::Block { .. } | ControlFrame::IfElse { .. } | ControlFrame::Try { .. } => {
+                &[]
+            }
             ControlFrame::Loop {
                 ref loop_body_phis, ..
             } => loop_body_phis.as_slice(),
@@ -235,6 +253,10 @@ impl<'ctx> State<'ctx> {
             | ControlFrame::IfElse {
                 stack_size_snapshot,
                 ..
+            }
+            | ControlFrame::Try {
+                stack_size_snapshot,
+                ..
             } => *stack_size_snapshot,
         };
         self.stack.truncate(stack_size_snapshot);
@@ -271,6 +293,19 @@ impl<'ctx> State<'ctx> {
         Ok(&mut self.control_stack[index])
     }
 
+    /// Returns the landing pad of the innermost `try` still in its body,
+    /// among the frame at `depth` and the frames enclosing it.
+    pub fn landing_pad_at_depth(&self, depth: u32) -> Option<BasicBlock<'ctx>> {
+        let frames = self.control_stack.len().saturating_sub(depth as usize);
+        self.control_stack[..frames]
+            .iter()
+            .rev()
+            .find_map(|frame| match frame {
+                ControlFrame::Try { landing_pad, .. } => *landing_pad,
+                _ => None,
+            })
+    }
+
     pub fn pop_frame(&mut self) -> Result<ControlFrame<'ctx>, CompileError> {
         self.control_stack.pop().ok_or_else(|| {
             CompileError::Codegen("pop_frame: cannot pop from control stack".to_string())
@@ -392,6 +427,10 @@ impl<'ctx> State<'ctx> {
         Ok(())
     }
 
+    pub fn push_frame(&mut self, frame: ControlFrame<'ctx>) {
+        self.control_stack.push(frame);
+    }
+
     pub fn push_block(&mut self, next: BasicBlock<'ctx>, phis: SmallVec<[PhiValue<'ctx>; 1]>) {
         self.control_stack.push(ControlFrame::Block {
             next,
@@ -416,6 +455,25 @@ impl<'ctx> State<'ctx> {
         });
     }
 
+    pub fn push_try(
+        &mut self,
+        next: BasicBlock<'ctx>,
+        landing_pad: BasicBlock<'ctx>,
+        num_params: usize,
+        phis: SmallVec<[PhiValue<'ctx>; 1]>,
+    ) {
+        // The clauses start from the stack the `try` started from, without
+        // its parameters.
+        self.control_stack.push(ControlFrame::Try {
+            next,
+            phis,
+            stack_size_snapshot: self.stack.len() - num_params,
+            landing_pad: Some(landing_pad),
+            no_match: None,
+            exception: None,
+        });
+    }
+
     pub fn push_if(
         &mut self,
         if_then: BasicBlock<'ctx>,
@@ -10,15 +10,17 @@ use gimli::write::Address;
 use smallvec::{smallvec, SmallVec};
 use std::cmp;
 use std::iter;
-use wasmer_compiler::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
-use wasmer_compiler::{CallingConvention, FunctionBodyData};
+use wasmer_compiler::wasmparser::{
+    BlockType as WpBlockType, MemoryImmediate, Operator, ValType as WpType,
+};
+use wasmer_compiler::{CallingConvention, FuelMetering, FunctionBodyData, FunctionFuel};
 #[cfg(feature = "unwind")]
 use wasmer_types::CompiledFunctionUnwindInfo;
 use wasmer_types::{
     entity::{EntityRef, PrimaryMap},
-    FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, LocalMemoryIndex, MemoryIndex,
-    MemoryStyle, ModuleInfo, Relocation, RelocationTarget, SectionIndex, SignatureIndex,
-    TableIndex, TableStyle, TrapCode, Type, VMBuiltinFunctionIndex, VMOffsets,
+    FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, MemoryStyle,
+    ModuleInfo, Relocation, RelocationTarget, SectionIndex, SignatureIndex, TableIndex, TableStyle,
+    TrapCode, Type, VMBuiltinFunctionIndex, VMOffsets,
 };
 use wasmer_types::{CompiledFunction, CompiledFunctionFrameInfo, FunctionBody};
 
@@ -83,6 +85,9 @@ pub struct FuncGen<'a, M: Machine> {
 
     /// Calling convention to use.
     calling_convention: CallingConvention,
+
+    /// The cost of the current basic block, if fuel metering is enabled.
+    fuel: Option<FunctionFuel>,
 }
 
 struct SpecialLabelSet {
@@ -92,6 +97,8 @@ struct SpecialLabelSet {
     table_access_oob: Label,
     indirect_call_null: Label,
     bad_signature: Label,
+    epoch_interrupt: Label,
+    out_of_fuel: Label,
 }
 
 /// Metadata about a floating-point value.
@@ -237,6 +244,77 @@ fn type_to_wp_type(ty: Type) -> WpType {
     }
 }
 
+/// Whether `op` accesses memory atomically. `memory.atomic.wait` and
+/// `memory.atomic.notify` are calls to the runtime instead.
+fn is_atomic_access(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::I32AtomicLoad { .. }
+            | Operator::I32AtomicLoad8U { .. }
+            | Operator::I32AtomicLoad16U { .. }
+            | Operator::I32AtomicStore { .. }
+            | Operator::I32AtomicStore8 { .. }
+            | Operator::I32AtomicStore16 { .. }
+            | Operator::I64AtomicLoad { .. }
+            | Operator::I64AtomicLoad8U { .. }
+            | Operator::I64AtomicLoad16U { .. }
+            | Operator::I64AtomicLoad32U { .. }
+            | Operator::I64AtomicStore { .. }
+            | Operator::I64AtomicStore8 { .. }
+            | Operator::I64AtomicStore16 { .. }
+            | Operator::I64AtomicStore32 { .. }
+            | Operator::I32AtomicRmwAdd { .. }
+            | Operator::I64AtomicRmwAdd { .. }
+            | Operator::I32AtomicRmw8AddU { .. }
+            | Operator::I32AtomicRmw16AddU { .. }
+            | Operator::I64AtomicRmw8AddU { .. }
+            | Operator::I64AtomicRmw16AddU { .. }
+            | Operator::I64AtomicRmw32AddU { .. }
+            | Operator::I32AtomicRmwSub { .. }
+            | Operator::I64AtomicRmwSub { .. }
+            | Operator::I32AtomicRmw8SubU { .. }
+            | Operator::I32AtomicRmw16SubU { .. }
+            | Operator::I64AtomicRmw8SubU { .. }
+            | Operator::I64AtomicRmw16SubU { .. }
+            | Operator::I64AtomicRmw32SubU { .. }
+            | Operator::I32AtomicRmwAnd { .. }
+            | Operator::I64AtomicRmwAnd { .. }
+            | Operator::I32AtomicRmw8AndU { .. }
+            | Operator::I32AtomicRmw16AndU { .. }
+            | Operator::I64AtomicRmw8AndU { .. }
+            | Operator::I64AtomicRmw16AndU { .. }
+            | Operator::I64AtomicRmw32AndU { .. }
+            | Operator::I32AtomicRmwOr { .. }
+            | Operator::I64AtomicRmwOr { .. }
+            | Operator::I32AtomicRmw8OrU { .. }
+            | Operator::I32AtomicRmw16OrU { .. }
+            | Operator::I64AtomicRmw8OrU { .. }
+            | Operator::I64AtomicRmw16OrU { .. }
+            | Operator::I64AtomicRmw32OrU { .. }
+            | Operator::I32AtomicRmwXor { .. }
+            | Operator::I64AtomicRmwXor { .. }
+            | Operator::I32AtomicRmw8XorU { .. }
+            | Operator::I32AtomicRmw16XorU { .. }
+            | Operator::I64AtomicRmw8XorU { .. }
+            | Operator::I64AtomicRmw16XorU { .. }
+            | Operator::I64AtomicRmw32XorU { .. }
+            | Operator::I32AtomicRmwXchg { .. }
+            | Operator::I64AtomicRmwXchg { .. }
+            | Operator::I32AtomicRmw8XchgU { .. }
+            | Operator::I32AtomicRmw16XchgU { .. }
+            | Operator::I64AtomicRmw8XchgU { .. }
+            | Operator::I64AtomicRmw16XchgU { .. }
+            | Operator::I64AtomicRmw32XchgU { .. }
+            | Operator::I32AtomicRmwCmpxchg { .. }
+            | Operator::I64AtomicRmwCmpxchg { .. }
+            | Operator::I32AtomicRmw8CmpxchgU { .. }
+            | Operator::I32AtomicRmw16CmpxchgU { .. }
+            | Operator::I64AtomicRmw8CmpxchgU { .. }
+            | Operator::I64AtomicRmw16CmpxchgU { .. }
+            | Operator::I64AtomicRmw32CmpxchgU { .. }
+    )
+}
+
 /// Abstraction for a 2-input, 1-output operator. Can be an integer/floating-point
 /// binop/cmpop.
 struct I2O1<R: Reg, S: Reg> {
@@ -954,29 +1032,133 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         Ok(())
     }
 
-    /// Emits a memory operation.
-    fn op_memory<F: FnOnce(&mut Self, bool, bool, i32, Label)>(&mut self, cb: F) {
-        let need_check = match self.memory_styles[MemoryIndex::new(0)] {
+    /// Emits a memory operation on the memory of `memarg`.
+    fn op_memory<F: FnOnce(&mut Self, bool, bool, i32, Label)>(
+        &mut self,
+        memarg: &MemoryImmediate,
+        cb: F,
+    ) {
+        let memory_index = MemoryIndex::new(memarg.memory as usize);
+        let need_check = match self.memory_styles[memory_index] {
             MemoryStyle::Static { .. } => false,
             MemoryStyle::Dynamic { .. } => true,
         };
 
-        let offset = if self.module.num_imported_memories != 0 {
-            self.vmoffsets
-                .vmctx_vmmemory_import_definition(MemoryIndex::new(0))
-        } else {
-            self.vmoffsets
-                .vmctx_vmmemory_definition(LocalMemoryIndex::new(0))
+        let local_memory_index = self.module.local_memory_index(memory_index);
+        let offset = match local_memory_index {
+            Some(local_memory_index) => {
+                self.vmoffsets.vmctx_vmmemory_definition(local_memory_index)
+            }
+            None => self
+                .vmoffsets
+                .vmctx_vmmemory_import_definition(memory_index),
         };
         cb(
             self,
             need_check,
-            self.module.num_imported_memories != 0,
+            local_memory_index.is_none(),
             offset as i32,
             self.special_labels.heap_access_oob,
         );
     }
 
+    /// Selects the builtin for `memory_index` from the one for locally
+    /// defined memories and the one for imported memories, and returns it
+    /// with the index it takes: the local index of local memories, and the
+    /// module index of imported ones.
+    fn memory_builtin(
+        &self,
+        memory_index: MemoryIndex,
+        local_builtin: VMBuiltinFunctionIndex,
+        imported_builtin: VMBuiltinFunctionIndex,
+    ) -> (VMBuiltinFunctionIndex, u32) {
+        match self.module.local_memory_index(memory_index) {
+            Some(local_memory_index) => (local_builtin, local_memory_index.as_u32()),
+            None => (imported_builtin, memory_index.as_u32()),
+        }
+    }
+
+    /// Emits a call to the builtin implementing `memory.atomic.wait32`,
+    /// `memory.atomic.wait64` or `memory.atomic.notify`, and pushes its
+    /// result.
+    ///
+    /// The first of `params` is the address, the static offset of `memarg` is
+    /// added to it before the call.
+    fn op_memory_atomic_builtin(
+        &mut self,
+        memarg: &MemoryImmediate,
+        local_builtin: VMBuiltinFunctionIndex,
+        imported_builtin: VMBuiltinFunctionIndex,
+        params: &[Location<M::GPR, M::SIMD>],
+        params_type: &[WpType],
+    ) -> Result<(), CodegenError> {
+        let (builtin, memory_index) = self.memory_builtin(
+            MemoryIndex::new(memarg.memory as usize),
+            local_builtin,
+            imported_builtin,
+        );
+
+        let mut params = params.to_vec();
+        let mut locations = params.clone();
+        if memarg.offset != 0 {
+            let addr = self.acquire_locations(
+                &[(WpType::I32, MachineValue::WasmStack(self.value_stack.len()))],
+                false,
+            )[0];
+            let tmp = self.machine.acquire_temp_gpr().unwrap();
+            self.machine
+                .move_location(Size::S32, params[0], Location::GPR(tmp));
+            self.machine.location_add(
+                Size::S32,
+                Location::Imm32(memarg.offset as u32),
+                Location::GPR(tmp),
+                true,
+            );
+            self.machine
+                .jmp_on_overflow(self.special_labels.heap_access_oob);
+            self.machine
+                .move_location(Size::S32, Location::GPR(tmp), addr);
+            self.machine.release_gpr(tmp);
+            params[0] = addr;
+            locations.push(addr);
+        }
+        self.release_locations_only_regs(&locations);
+
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(
+                self.machine.get_vmctx_reg(),
+                self.vmoffsets.vmctx_builtin_function(builtin) as i32,
+            ),
+            Location::GPR(self.machine.get_grp_for_call()),
+        );
+
+        self.release_locations_only_osr_state(locations.len());
+
+        self.emit_call_native(
+            |this| {
+                this.machine
+                    .emit_call_register(this.machine.get_grp_for_call());
+            },
+            // [vmctx, memory_index, params...]
+            iter::once(Location::Imm32(memory_index)).chain(params.iter().cloned()),
+            iter::once(WpType::I32).chain(params_type.iter().cloned()),
+        )?;
+        self.release_locations_only_stack(&locations);
+
+        let ret = self.acquire_locations(
+            &[(WpType::I32, MachineValue::WasmStack(self.value_stack.len()))],
+            false,
+        )[0];
+        self.value_stack.push(ret);
+        self.machine.move_location(
+            Size::S32,
+            Location::GPR(self.machine.get_gpr_for_ret()),
+            ret,
+        );
+        Ok(())
+    }
+
     pub fn get_state_diff(&mut self) -> usize {
         if !self.track_state {
             return std::usize::MAX;
@@ -991,6 +1173,78 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         id
     }
 
+    /// Traps with `Interrupt` if the epoch counter has reached the epoch
+    /// deadline.
+    fn emit_epoch_check(&mut self) {
+        let counter = self.machine.acquire_temp_gpr().unwrap();
+        let deadline = self.machine.acquire_temp_gpr().unwrap();
+
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(
+                self.machine.get_vmctx_reg(),
+                self.vmoffsets.vmctx_epoch_counter() as i32,
+            ),
+            Location::GPR(counter),
+        );
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(counter, 0),
+            Location::GPR(counter),
+        );
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(
+                self.machine.get_vmctx_reg(),
+                self.vmoffsets.vmctx_epoch_deadline() as i32,
+            ),
+            Location::GPR(deadline),
+        );
+        self.machine.move_location(
+            Size::S64,
+            Location::Memory(deadline, 0),
+            Location::GPR(deadline),
+        );
+
+        self.machine
+            .location_cmp(Size::S64, Location::GPR(deadline), Location::GPR(counter));
+        self.machine
+            .jmp_on_aboveequal(self.special_labels.epoch_interrupt);
+
+        self.machine.release_gpr(deadline);
+        self.machine.release_gpr(counter);
+    }
+
+    /// Charges `cost` to the fuel of the instance, or jumps to the
+    /// `out_of_fuel` trap if there isn't enough left.
+    fn emit_fuel_charge(&mut self, cost: u64) {
+        let remaining = self.machine.acquire_temp_gpr().unwrap();
+        let tmp = self.machine.acquire_temp_gpr().unwrap();
+        let remaining_location = Location::Memory(
+            self.machine.get_vmctx_reg(),
+            self.vmoffsets.vmctx_fuel_remaining() as i32,
+        );
+
+        self.machine
+            .move_location(Size::S64, remaining_location, Location::GPR(remaining));
+        self.machine
+            .move_location(Size::S64, Location::Imm64(cost), Location::GPR(tmp));
+        self.machine
+            .location_cmp(Size::S64, Location::GPR(remaining), Location::GPR(tmp));
+        self.machine.jmp_on_above(self.special_labels.out_of_fuel);
+        self.machine.location_sub(
+            Size::S64,
+            Location::GPR(tmp),
+            Location::GPR(remaining),
+            false,
+        );
+        self.machine
+            .move_location(Size::S64, Location::GPR(remaining), remaining_location);
+
+        self.machine.release_gpr(tmp);
+        self.machine.release_gpr(remaining);
+    }
+
     fn emit_head(&mut self) -> Result<(), CodegenError> {
         self.machine.emit_function_prolog();
 
@@ -1029,7 +1283,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             state_diff_id,
         });
 
-        // TODO: Full preemption by explicit signal checking
+        if self.config.epoch_interruption {
+            self.emit_epoch_check();
+        }
 
         // We insert set StackOverflow as the default trap that can happen
         // anywhere in the function prologue.
@@ -1074,6 +1330,8 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             table_access_oob: machine.get_label(),
             indirect_call_null: machine.get_label(),
             bad_signature: machine.get_label(),
+            epoch_interrupt: machine.get_label(),
+            out_of_fuel: machine.get_label(),
         };
 
         let fsm = FunctionStateMap::new(
@@ -1107,6 +1365,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
             relocations: vec![],
             special_labels,
             calling_convention,
+            fuel: config.fuel.as_ref().map(FuelMetering::function_fuel),
         };
         fg.emit_head()?;
         Ok(fg)
@@ -1121,6 +1380,22 @@ impl<'a, M: Machine> FuncGen<'a, M> {
 
         self.state.wasm_inst_offset = self.state.wasm_inst_offset.wrapping_add(1);
 
+        if let Some(cost) = self.fuel.as_mut().and_then(|fuel| fuel.feed(&op)) {
+            // The cost of unreachable code is dropped along with it.
+            if self.unreachable_depth == 0 {
+                self.emit_fuel_charge(cost);
+            }
+        }
+
+        if is_atomic_access(&op) && !self.machine.arch_supports_atomics() {
+            return Err(CodegenError {
+                message: format!(
+                    "singlepass doesn't support atomic memory accesses like {:?} on this architecture",
+                    op
+                ),
+            });
+        }
+
         //println!("{:?} {}", op, self.value_stack.len());
         let was_unreachable;
 
@@ -2645,7 +2920,9 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     }
                 }
             }
-            Operator::CallIndirect { index, table_index } => {
+            Operator::CallIndirect {
+                index, table_index, ..
+            } => {
                 // TODO: removed restriction on always being table idx 0;
                 // does any code depend on this?
                 let table_index = TableIndex::new(table_index as _);
@@ -2880,8 +3157,8 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     loop_like: false,
                     if_else: IfElseState::If(label_else),
                     returns: match ty {
-                        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => smallvec![],
-                        WpTypeOrFuncType::Type(inner_ty) => smallvec![inner_ty],
+                        WpBlockType::Empty => smallvec![],
+                        WpBlockType::Type(inner_ty) => smallvec![inner_ty],
                         _ => {
                             return Err(CodegenError {
                                 message: "If: multi-value returns not yet implemented".to_string(),
@@ -3003,8 +3280,8 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     loop_like: false,
                     if_else: IfElseState::None,
                     returns: match ty {
-                        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => smallvec![],
-                        WpTypeOrFuncType::Type(inner_ty) => smallvec![inner_ty],
+                        WpBlockType::Empty => smallvec![],
+                        WpBlockType::Type(inner_ty) => smallvec![inner_ty],
                         _ => {
                             return Err(CodegenError {
                                 message: "Block: multi-value returns not yet implemented"
@@ -3030,8 +3307,8 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     loop_like: true,
                     if_else: IfElseState::None,
                     returns: match ty {
-                        WpTypeOrFuncType::Type(WpType::EmptyBlockType) => smallvec![],
-                        WpTypeOrFuncType::Type(inner_ty) => smallvec![inner_ty],
+                        WpBlockType::Empty => smallvec![],
+                        WpBlockType::Type(inner_ty) => smallvec![inner_ty],
                         _ => {
                             return Err(CodegenError {
                                 message: "Loop: multi-value returns not yet implemented"
@@ -3046,22 +3323,22 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 });
                 self.machine.emit_label(label);
 
-                // TODO: Re-enable interrupt signal check without branching
+                if self.config.epoch_interruption {
+                    self.emit_epoch_check();
+                }
             }
             Operator::Nop => {}
             Operator::MemorySize { mem, mem_byte: _ } => {
-                let memory_index = MemoryIndex::new(mem as usize);
+                let (memory_size_index, memory_index) = self.memory_builtin(
+                    MemoryIndex::new(mem as usize),
+                    VMBuiltinFunctionIndex::get_memory32_size_index(),
+                    VMBuiltinFunctionIndex::get_imported_memory32_size_index(),
+                );
                 self.machine.move_location(
                     Size::S64,
                     Location::Memory(
                         self.machine.get_vmctx_reg(),
-                        self.vmoffsets.vmctx_builtin_function(
-                            if self.module.local_memory_index(memory_index).is_some() {
-                                VMBuiltinFunctionIndex::get_memory32_size_index()
-                            } else {
-                                VMBuiltinFunctionIndex::get_imported_memory32_size_index()
-                            },
-                        ) as i32,
+                        self.vmoffsets.vmctx_builtin_function(memory_size_index) as i32,
                     ),
                     Location::GPR(self.machine.get_grp_for_call()),
                 );
@@ -3071,7 +3348,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                             .emit_call_register(this.machine.get_grp_for_call());
                     },
                     // [vmctx, memory_index]
-                    iter::once(Location::Imm32(memory_index.index() as u32)),
+                    iter::once(Location::Imm32(memory_index)),
                     iter::once(WpType::I64),
                 )?;
                 let ret = self.acquire_locations(
@@ -3155,26 +3432,26 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )?;
             }
             Operator::MemoryCopy { src, dst } => {
-                // ignore until we support multiple memories
-                let _dst = dst;
                 let len = self.value_stack.pop().unwrap();
                 let src_pos = self.value_stack.pop().unwrap();
                 let dst_pos = self.value_stack.pop().unwrap();
                 self.release_locations_only_regs(&[len, src_pos, dst_pos]);
 
-                let memory_index = MemoryIndex::new(src as usize);
-                let (memory_copy_index, memory_index) =
-                    if self.module.local_memory_index(memory_index).is_some() {
-                        (
-                            VMBuiltinFunctionIndex::get_memory_copy_index(),
-                            memory_index,
-                        )
-                    } else {
-                        (
-                            VMBuiltinFunctionIndex::get_imported_memory_copy_index(),
-                            memory_index,
-                        )
-                    };
+                // Copies within a memory take its index, copies between two
+                // memories take the module index of both.
+                let (memory_copy_index, memory_indices) = if src == dst {
+                    let (memory_copy_index, memory_index) = self.memory_builtin(
+                        MemoryIndex::new(src as usize),
+                        VMBuiltinFunctionIndex::get_memory_copy_index(),
+                        VMBuiltinFunctionIndex::get_imported_memory_copy_index(),
+                    );
+                    (memory_copy_index, vec![memory_index])
+                } else {
+                    (
+                        VMBuiltinFunctionIndex::get_memory_copy_between_index(),
+                        vec![dst, src],
+                    )
+                };
 
                 self.machine.move_location(
                     Size::S64,
@@ -3188,23 +3465,23 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 // TODO: should this be 3?
                 self.release_locations_only_osr_state(1);
 
+                let params_type = memory_indices
+                    .iter()
+                    .map(|_| WpType::I32)
+                    .chain([WpType::I64, WpType::I64, WpType::I64].iter().cloned())
+                    .collect::<Vec<_>>();
                 self.emit_call_native(
                     |this| {
                         this.machine
                             .emit_call_register(this.machine.get_grp_for_call());
                     },
-                    // [vmctx, memory_index, dst, src, len]
-                    [
-                        Location::Imm32(memory_index.index() as u32),
-                        dst_pos,
-                        src_pos,
-                        len,
-                    ]
-                    .iter()
-                    .cloned(),
-                    [WpType::I32, WpType::I64, WpType::I64, WpType::I64]
-                        .iter()
-                        .cloned(),
+                    // [vmctx, memory_index, dst, src, len] or
+                    // [vmctx, dst_memory_index, src_memory_index, dst, src, len]
+                    memory_indices
+                        .into_iter()
+                        .map(Location::Imm32)
+                        .chain([dst_pos, src_pos, len].iter().cloned()),
+                    params_type.into_iter(),
                 )?;
                 self.release_locations_only_stack(&[dst_pos, src_pos, len]);
             }
@@ -3214,19 +3491,11 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let dst = self.value_stack.pop().unwrap();
                 self.release_locations_only_regs(&[len, val, dst]);
 
-                let memory_index = MemoryIndex::new(mem as usize);
-                let (memory_fill_index, memory_index) =
-                    if self.module.local_memory_index(memory_index).is_some() {
-                        (
-                            VMBuiltinFunctionIndex::get_memory_fill_index(),
-                            memory_index,
-                        )
-                    } else {
-                        (
-                            VMBuiltinFunctionIndex::get_imported_memory_fill_index(),
-                            memory_index,
-                        )
-                    };
+                let (memory_fill_index, memory_index) = self.memory_builtin(
+                    MemoryIndex::new(mem as usize),
+                    VMBuiltinFunctionIndex::get_memory_fill_index(),
+                    VMBuiltinFunctionIndex::get_imported_memory_fill_index(),
+                );
 
                 self.machine.move_location(
                     Size::S64,
@@ -3246,7 +3515,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                             .emit_call_register(this.machine.get_grp_for_call());
                     },
                     // [vmctx, memory_index, dst, src, len]
-                    [Location::Imm32(memory_index.index() as u32), dst, val, len]
+                    [Location::Imm32(memory_index), dst, val, len]
                         .iter()
                         .cloned(),
                     [WpType::I32, WpType::I64, WpType::I64, WpType::I64]
@@ -3255,8 +3524,47 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )?;
                 self.release_locations_only_stack(&[dst, val, len]);
             }
+            Operator::MemoryAtomicWait32 { ref memarg } => {
+                let timeout = self.value_stack.pop().unwrap();
+                let val = self.value_stack.pop().unwrap();
+                let dst = self.value_stack.pop().unwrap();
+                self.op_memory_atomic_builtin(
+                    memarg,
+                    VMBuiltinFunctionIndex::get_memory_atomic_wait32_index(),
+                    VMBuiltinFunctionIndex::get_imported_memory_atomic_wait32_index(),
+                    &[dst, val, timeout],
+                    &[WpType::I32, WpType::I32, WpType::I64],
+                )?;
+            }
+            Operator::MemoryAtomicWait64 { ref memarg } => {
+                let timeout = self.value_stack.pop().unwrap();
+                let val = self.value_stack.pop().unwrap();
+                let dst = self.value_stack.pop().unwrap();
+                self.op_memory_atomic_builtin(
+                    memarg,
+                    VMBuiltinFunctionIndex::get_memory_atomic_wait64_index(),
+                    VMBuiltinFunctionIndex::get_imported_memory_atomic_wait64_index(),
+                    &[dst, val, timeout],
+                    &[WpType::I32, WpType::I64, WpType::I64],
+                )?;
+            }
+            Operator::MemoryAtomicNotify { ref memarg } => {
+                let count = self.value_stack.pop().unwrap();
+                let dst = self.value_stack.pop().unwrap();
+                self.op_memory_atomic_builtin(
+                    memarg,
+                    VMBuiltinFunctionIndex::get_memory_atomic_notify_index(),
+                    VMBuiltinFunctionIndex::get_imported_memory_atomic_notify_index(),
+                    &[dst, count],
+                    &[WpType::I32, WpType::I32],
+                )?;
+            }
             Operator::MemoryGrow { mem, mem_byte: _ } => {
-                let memory_index = MemoryIndex::new(mem as usize);
+                let (memory_grow_index, memory_index) = self.memory_builtin(
+                    MemoryIndex::new(mem as usize),
+                    VMBuiltinFunctionIndex::get_memory32_grow_index(),
+                    VMBuiltinFunctionIndex::get_imported_memory32_grow_index(),
+                );
                 let param_pages = self.value_stack.pop().unwrap();
 
                 self.release_locations_only_regs(&[param_pages]);
@@ -3265,13 +3573,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                     Size::S64,
                     Location::Memory(
                         self.machine.get_vmctx_reg(),
-                        self.vmoffsets.vmctx_builtin_function(
-                            if self.module.local_memory_index(memory_index).is_some() {
-                                VMBuiltinFunctionIndex::get_memory32_grow_index()
-                            } else {
-                                VMBuiltinFunctionIndex::get_imported_memory32_grow_index()
-                            },
-                        ) as i32,
+                        self.vmoffsets.vmctx_builtin_function(memory_grow_index) as i32,
                     ),
                     Location::GPR(self.machine.get_grp_for_call()),
                 );
@@ -3284,8 +3586,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                             .emit_call_register(this.machine.get_grp_for_call());
                     },
                     // [vmctx, val, memory_index]
-                    iter::once(param_pages)
-                        .chain(iter::once(Location::Imm32(memory_index.index() as u32))),
+                    iter::once(param_pages).chain(iter::once(Location::Imm32(memory_index))),
                     [WpType::I64, WpType::I64].iter().cloned(),
                 )?;
 
@@ -3310,6 +3611,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_load(
                             target,
@@ -3333,6 +3635,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 self.fp_stack
                     .push(FloatValue::new(self.value_stack.len() - 1));
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.f32_load(
                             target,
@@ -3354,6 +3657,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_load_8u(
                             target,
@@ -3375,6 +3679,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_load_8s(
                             target,
@@ -3396,6 +3701,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_load_16u(
                             target,
@@ -3417,6 +3723,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_load_16s(
                             target,
@@ -3434,6 +3741,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_save(
                             target_value,
@@ -3453,6 +3761,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let fp = self.fp_stack.pop1()?;
                 let config_nan_canonicalization = self.config.enable_nan_canonicalization;
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.f32_save(
                             target_value,
@@ -3471,6 +3780,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_save_8(
                             target_value,
@@ -3488,6 +3798,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_save_16(
                             target_value,
@@ -3509,6 +3820,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load(
                             target,
@@ -3532,6 +3844,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 self.fp_stack
                     .push(FloatValue::new(self.value_stack.len() - 1));
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.f64_load(
                             target,
@@ -3553,6 +3866,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load_8u(
                             target,
@@ -3574,6 +3888,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load_8s(
                             target,
@@ -3595,6 +3910,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load_16u(
                             target,
@@ -3616,6 +3932,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load_16s(
                             target,
@@ -3637,6 +3954,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load_32u(
                             target,
@@ -3658,6 +3976,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_load_32s(
                             target,
@@ -3676,6 +3995,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_addr = self.pop_value_released();
 
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_save(
                             target_value,
@@ -3695,6 +4015,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let fp = self.fp_stack.pop1()?;
                 let config_nan_canonicalization = self.config.enable_nan_canonicalization;
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.f64_save(
                             target_value,
@@ -3713,6 +4034,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_save_8(
                             target_value,
@@ -3730,6 +4052,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_save_16(
                             target_value,
@@ -3747,6 +4070,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_save_32(
                             target_value,
@@ -4056,6 +4380,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_load(
                             target,
@@ -4077,6 +4402,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_load_8u(
                             target,
@@ -4098,6 +4424,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_load_16u(
                             target,
@@ -4115,6 +4442,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_save(
                             target_value,
@@ -4132,6 +4460,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_save_8(
                             target_value,
@@ -4149,6 +4478,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_save_16(
                             target_value,
@@ -4170,6 +4500,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_load(
                             target,
@@ -4191,6 +4522,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_load_8u(
                             target,
@@ -4212,6 +4544,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_load_16u(
                             target,
@@ -4233,6 +4566,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_load_32u(
                             target,
@@ -4250,6 +4584,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_save(
                             target_value,
@@ -4267,6 +4602,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_save_8(
                             target_value,
@@ -4284,6 +4620,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_save_16(
                             target_value,
@@ -4301,6 +4638,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 let target_value = self.pop_value_released();
                 let target_addr = self.pop_value_released();
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_save_32(
                             target_value,
@@ -4323,6 +4661,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_add(
                             loc,
@@ -4346,6 +4685,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_add(
                             loc,
@@ -4369,6 +4709,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_add_8u(
                             loc,
@@ -4392,6 +4733,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_add_16u(
                             loc,
@@ -4415,6 +4757,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_add_8u(
                             loc,
@@ -4438,6 +4781,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_add_16u(
                             loc,
@@ -4461,6 +4805,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_add_32u(
                             loc,
@@ -4484,6 +4829,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_sub(
                             loc,
@@ -4507,6 +4853,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_sub(
                             loc,
@@ -4530,6 +4877,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_sub_8u(
                             loc,
@@ -4553,6 +4901,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_sub_16u(
                             loc,
@@ -4576,6 +4925,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_sub_8u(
                             loc,
@@ -4599,6 +4949,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_sub_16u(
                             loc,
@@ -4622,6 +4973,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_sub_32u(
                             loc,
@@ -4645,6 +4997,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_and(
                             loc,
@@ -4668,6 +5021,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_and(
                             loc,
@@ -4691,6 +5045,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_and_8u(
                             loc,
@@ -4714,6 +5069,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_and_16u(
                             loc,
@@ -4737,6 +5093,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_and_8u(
                             loc,
@@ -4760,6 +5117,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_and_16u(
                             loc,
@@ -4783,6 +5141,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_and_32u(
                             loc,
@@ -4806,6 +5165,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_or(
                             loc,
@@ -4829,6 +5189,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_or(
                             loc,
@@ -4852,6 +5213,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_or_8u(
                             loc,
@@ -4875,6 +5237,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_or_16u(
                             loc,
@@ -4898,6 +5261,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_or_8u(
                             loc,
@@ -4921,6 +5285,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_or_16u(
                             loc,
@@ -4944,6 +5309,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_or_32u(
                             loc,
@@ -4967,6 +5333,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_xor(
                             loc,
@@ -4990,6 +5357,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xor(
                             loc,
@@ -5013,6 +5381,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_xor_8u(
                             loc,
@@ -5036,6 +5405,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_xor_16u(
                             loc,
@@ -5059,6 +5429,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xor_8u(
                             loc,
@@ -5082,6 +5453,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xor_16u(
                             loc,
@@ -5105,6 +5477,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xor_32u(
                             loc,
@@ -5128,6 +5501,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_xchg(
                             loc,
@@ -5151,6 +5525,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xchg(
                             loc,
@@ -5174,6 +5549,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_xchg_8u(
                             loc,
@@ -5197,6 +5573,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_xchg_16u(
                             loc,
@@ -5220,6 +5597,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xchg_8u(
                             loc,
@@ -5243,6 +5621,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xchg_16u(
                             loc,
@@ -5266,6 +5645,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_xchg_32u(
                             loc,
@@ -5290,6 +5670,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_cmpxchg(
                             new,
@@ -5315,6 +5696,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_cmpxchg(
                             new,
@@ -5340,6 +5722,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_cmpxchg_8u(
                             new,
@@ -5365,6 +5748,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i32_atomic_cmpxchg_16u(
                             new,
@@ -5390,6 +5774,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_cmpxchg_8u(
                             new,
@@ -5415,6 +5800,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_cmpxchg_16u(
                             new,
@@ -5440,6 +5826,7 @@ impl<'a, M: Machine> FuncGen<'a, M> {
                 )[0];
                 self.value_stack.push(ret);
                 self.op_memory(
+                    memarg,
                     |this, need_check, imported_memories, offset, heap_access_oob| {
                         this.machine.i64_atomic_cmpxchg_32u(
                             new,
@@ -5877,6 +6264,24 @@ impl<'a, M: Machine> FuncGen<'a, M> {
         self.machine.emit_label(self.special_labels.bad_signature);
         self.machine.emit_illegal_op(TrapCode::BadSignature);
 
+        self.machine.emit_label(self.special_labels.epoch_interrupt);
+        self.machine.emit_illegal_op(TrapCode::Interrupt);
+
+        self.machine.emit_label(self.special_labels.out_of_fuel);
+        let tmp = self.machine.acquire_temp_gpr().unwrap();
+        self.machine
+            .move_location(Size::S64, Location::Imm32(1), Location::GPR(tmp));
+        self.machine.move_location(
+            Size::S64,
+            Location::GPR(tmp),
+            Location::Memory(
+                self.machine.get_vmctx_reg(),
+                self.vmoffsets.vmctx_fuel_exhausted() as i32,
+            ),
+        );
+        self.machine.release_gpr(tmp);
+        self.machine.emit_illegal_op(TrapCode::OutOfFuel);
+
         // Notify the assembler backend to generate necessary code at end of function.
         self.machine.finalize_function();
 
@@ -20,9 +20,9 @@ use gimli::write::{EhFrame, FrameTable};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, CallingConvention, Compiler, CompilerConfig, CpuFeature, FunctionBinaryReader,
-    FunctionBodyData, MiddlewareBinaryReader, ModuleMiddleware, ModuleMiddlewareChain,
-    ModuleTranslationState, OperatingSystem, Target,
+    Architecture, CallingConvention, Compiler, CompilerConfig, CpuFeature, FuelMetering,
+    FunctionBinaryReader, FunctionBodyData, MiddlewareBinaryReader, ModuleMiddleware,
+    ModuleMiddlewareChain, ModuleTranslationState, OperatingSystem, Target,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
@@ -57,6 +57,10 @@ impl Compiler for SinglepassCompiler {
         &self.config.middlewares
     }
 
+    fn get_fuel_metering(&self) -> Option<&FuelMetering> {
+        self.config.fuel.as_ref()
+    }
+
     /// Compile the module using Singlepass, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -3,12 +3,16 @@
 
 use crate::compiler::SinglepassCompiler;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target};
+use wasmer_compiler::{
+    Compiler, CompilerConfig, CpuFeature, FuelMetering, ModuleMiddleware, Target,
+};
 use wasmer_types::Features;
 
 #[derive(Debug, Clone)]
 pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
+    pub(crate) epoch_interruption: bool,
+    pub(crate) fuel: Option<FuelMetering>,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
@@ -19,6 +23,8 @@ impl Singlepass {
     pub fn new() -> Self {
         Self {
             enable_nan_canonicalization: true,
+            epoch_interruption: false,
+            fuel: None,
             middlewares: vec![],
         }
     }
@@ -31,6 +37,25 @@ impl Singlepass {
         self.enable_nan_canonicalization = enable;
         self
     }
+
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the engine's epoch against the store's deadline
+    /// on function entry and at loop headers, and traps once the deadline
+    /// is reached.
+    pub fn epoch_interruption(&mut self, enable: bool) -> &mut Self {
+        self.epoch_interruption = enable;
+        self
+    }
+
+    /// Enable fuel metering, or disable it with `None`.
+    ///
+    /// Compiled code charges the fuel of the instance at the end of every
+    /// basic block, and traps once there isn't enough left.
+    pub fn fuel_metering(&mut self, fuel: Option<FuelMetering>) -> &mut Self {
+        self.fuel = fuel;
+        self
+    }
 }
 
 impl CompilerConfig for Singlepass {
@@ -39,6 +64,14 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn epoch_interruption(&mut self, enable: bool) {
+        self.epoch_interruption = enable;
+    }
+
+    fn fuel_metering(&mut self, fuel: Option<FuelMetering>) {
+        self.fuel = fuel;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(SinglepassCompiler::new(*self))
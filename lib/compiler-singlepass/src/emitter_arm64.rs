@@ -107,6 +107,14 @@ pub trait EmitterARM64 {
     fn emit_strb(&mut self, sz: Size, reg: Location, dst: Location);
     fn emit_strh(&mut self, sz: Size, reg: Location, dst: Location);
 
+    // The atomic accesses take the size of the access in memory, the loads
+    // zero-extend the value to the whole register.
+    fn emit_ldar(&mut self, sz: Size, reg: Location, addr: GPR);
+    fn emit_stlr(&mut self, sz: Size, reg: Location, addr: GPR);
+    fn emit_ldaxr(&mut self, sz: Size, reg: Location, addr: GPR);
+    fn emit_stlxr(&mut self, sz: Size, status: Location, reg: Location, addr: GPR);
+    fn emit_clrex(&mut self);
+
     fn emit_mov(&mut self, sz: Size, src: Location, dst: Location);
 
     fn emit_movn(&mut self, sz: Size, reg: Location, val: u32);
@@ -745,6 +753,74 @@ impl EmitterARM64 for Assembler {
         }
     }
 
+    fn emit_ldar(&mut self, sz: Size, reg: Location, addr: GPR) {
+        match reg {
+            Location::GPR(reg) => {
+                let reg = reg.into_index() as u32;
+                let addr = addr.into_index() as u32;
+                match sz {
+                    Size::S8 => dynasm!(self ; ldarb W(reg), [X(addr)]),
+                    Size::S16 => dynasm!(self ; ldarh W(reg), [X(addr)]),
+                    Size::S32 => dynasm!(self ; ldar W(reg), [X(addr)]),
+                    Size::S64 => dynasm!(self ; ldar X(reg), [X(addr)]),
+                }
+            }
+            _ => panic!("singlepass can't emit LDAR {:?}, {:?}", reg, addr),
+        }
+    }
+    fn emit_stlr(&mut self, sz: Size, reg: Location, addr: GPR) {
+        match reg {
+            Location::GPR(reg) => {
+                let reg = reg.into_index() as u32;
+                let addr = addr.into_index() as u32;
+                match sz {
+                    Size::S8 => dynasm!(self ; stlrb W(reg), [X(addr)]),
+                    Size::S16 => dynasm!(self ; stlrh W(reg), [X(addr)]),
+                    Size::S32 => dynasm!(self ; stlr W(reg), [X(addr)]),
+                    Size::S64 => dynasm!(self ; stlr X(reg), [X(addr)]),
+                }
+            }
+            _ => panic!("singlepass can't emit STLR {:?}, {:?}", reg, addr),
+        }
+    }
+    fn emit_ldaxr(&mut self, sz: Size, reg: Location, addr: GPR) {
+        match reg {
+            Location::GPR(reg) => {
+                let reg = reg.into_index() as u32;
+                let addr = addr.into_index() as u32;
+                match sz {
+                    Size::S8 => dynasm!(self ; ldaxrb W(reg), [X(addr)]),
+                    Size::S16 => dynasm!(self ; ldaxrh W(reg), [X(addr)]),
+                    Size::S32 => dynasm!(self ; ldaxr W(reg), [X(addr)]),
+                    Size::S64 => dynasm!(self ; ldaxr X(reg), [X(addr)]),
+                }
+            }
+            _ => panic!("singlepass can't emit LDAXR {:?}, {:?}", reg, addr),
+        }
+    }
+    fn emit_stlxr(&mut self, sz: Size, status: Location, reg: Location, addr: GPR) {
+        match (status, reg) {
+            (Location::GPR(status), Location::GPR(reg)) => {
+                let status = status.into_index() as u32;
+                let reg = reg.into_index() as u32;
+                let addr = addr.into_index() as u32;
+                match sz {
+                    Size::S8 => dynasm!(self ; stlxrb W(status), W(reg), [X(addr)]),
+                    Size::S16 => dynasm!(self ; stlxrh W(status), W(reg), [X(addr)]),
+                    Size::S32 => dynasm!(self ; stlxr W(status), W(reg), [X(addr)]),
+                    Size::S64 => dynasm!(self ; stlxr W(status), X(reg), [X(addr)]),
+                }
+            }
+            _ => panic!(
+                "singlepass can't emit STLXR {:?}, {:?}, {:?}",
+                status, reg, addr
+            ),
+        }
+    }
+    fn emit_clrex(&mut self) {
+        dynasm!(self ; clrex);
+    }
+
     fn emit_mov(&mut self, sz: Size, src: Location, dst: Location) {
         match (sz, src, dst) {
             (Size::S64, Location::GPR(src), Location::GPR(dst)) => {
@@ -7,7 +7,7 @@ use dynasmrt::{AssemblyOffset, DynamicLabel};
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 pub use wasmer_compiler::wasmparser::MemoryImmediate;
-use wasmer_compiler::wasmparser::Type as WpType;
+use wasmer_compiler::wasmparser::ValType as WpType;
 use wasmer_compiler::{Architecture, CallingConvention, CpuFeature, Target};
 use wasmer_types::{CustomSection, Relocation, RelocationTarget};
 use wasmer_types::{FunctionBody, InstructionAddressMap, TrapInformation};
@@ -244,6 +244,8 @@ pub trait Machine {
     fn emit_function_return_float(&mut self);
     /// Is NaN canonicalization supported
     fn arch_supports_canonicalize_nan(&self) -> bool;
+    /// Are the atomic memory accesses (loads, stores and read-modify-writes) supported
+    fn arch_supports_atomics(&self) -> bool;
     /// Cannonicalize a NaN (or panic if not supported)
     fn canonicalize_nan(
         &mut self,
@@ -9,7 +9,7 @@ use crate::unwind::{UnwindInstructions, UnwindOps};
 use dynasmrt::{aarch64::Aarch64Relocation, VecAssembler};
 #[cfg(feature = "unwind")]
 use gimli::{write::CallFrameInstruction, AArch64};
-use wasmer_compiler::wasmparser::Type as WpType;
+use wasmer_compiler::wasmparser::ValType as WpType;
 use wasmer_compiler::CallingConvention;
 use wasmer_types::{CustomSection, Relocation, RelocationKind, RelocationTarget};
 use wasmer_types::{FunctionBody, InstructionAddressMap, SourceLoc, TrapInformation};
@@ -18,6 +18,16 @@ use wasmer_types::{FunctionIndex, FunctionType, TrapCode, VMOffsets};
 type Assembler = VecAssembler<Aarch64Relocation>;
 type Location = AbstractLocation<GPR, NEON>;
 
+/// Returns the number of bytes of a value of size `sz`.
+fn size_in_bytes(sz: Size) -> usize {
+    match sz {
+        Size::S8 => 1,
+        Size::S16 => 2,
+        Size::S32 => 4,
+        Size::S64 => 8,
+    }
+}
+
 #[cfg(feature = "unwind")]
 fn dwarf_index(reg: u16) -> gimli::Register {
     static DWARF_GPR: [gimli::Register; 32] = [
@@ -971,15 +981,18 @@ impl MachineARM64 {
         self.release_gpr(tmp_bound);
         self.release_gpr(tmp_base);
 
-        let align = memarg.align;
-        if check_alignment && align != 1 {
+        // Atomic accesses must be aligned to their size, whatever the
+        // alignment hint of `memarg` says
+        if check_alignment && value_size != 1 {
             self.assembler.emit_tst(
                 Size::S64,
-                Location::Imm32((align - 1).into()),
+                Location::Imm32((value_size - 1) as u32),
                 Location::GPR(tmp_addr),
             );
-            self.assembler
-                .emit_bcond_label_far(Condition::Ne, heap_access_oob);
+            let aligned = self.get_label();
+            self.assembler.emit_bcond_label(Condition::Eq, aligned);
+            self.emit_illegal_op(TrapCode::UnalignedAtomic);
+            self.emit_label(aligned);
         }
         let begin = self.assembler.get_offset().0;
         cb(self, tmp_addr);
@@ -989,23 +1002,208 @@ impl MachineARM64 {
         self.release_gpr(tmp_addr);
     }
 
-    /*fn emit_compare_and_swap<F: FnOnce(&mut Self, GPR, GPR)>(
+    /// Loads the `sz` bytes at `addr` with acquire semantics, and
+    /// zero-extends them to the `ret_sz` result.
+    #[allow(clippy::too_many_arguments)]
+    fn emit_atomic_load(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _ret: Location,
-        _memarg: &MemoryImmediate,
-        _value_size: usize,
-        _memory_sz: Size,
-        _stack_sz: Size,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-        _cb: F,
+        sz: Size,
+        ret_sz: Size,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
-    }*/
+        let value = self.acquire_temp_gpr().unwrap();
+        self.memory_op(
+            addr,
+            memarg,
+            true,
+            size_in_bytes(sz),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.assembler.emit_ldar(sz, Location::GPR(value), addr);
+            },
+        );
+        self.move_location(ret_sz, Location::GPR(value), ret);
+        self.release_gpr(value);
+    }
+
+    /// Stores the low `sz` bytes of the `value_sz` value at `target_addr`
+    /// with release semantics.
+    #[allow(clippy::too_many_arguments)]
+    fn emit_atomic_store(
+        &mut self,
+        sz: Size,
+        value_sz: Size,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        let tmp = self.acquire_temp_gpr().unwrap();
+        self.move_location(value_sz, value, Location::GPR(tmp));
+        self.memory_op(
+            target_addr,
+            memarg,
+            true,
+            size_in_bytes(sz),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.assembler.emit_stlr(sz, Location::GPR(tmp), addr);
+            },
+        );
+        self.release_gpr(tmp);
+    }
+
+    /// Replaces the `sz` bytes at `target` with the result of `op` in a
+    /// load-exclusive/store-exclusive loop, and returns their previous value
+    /// zero-extended to `ret_sz`, the size of `loc` as well.
+    ///
+    /// `op(this, sz, old, value, new)` computes `new` from the `old` bytes
+    /// and `value`, on registers of size `sz`.
+    #[allow(clippy::too_many_arguments)]
+    fn emit_atomic_rmw<F: FnOnce(&mut Self, Size, GPR, GPR, GPR)>(
+        &mut self,
+        sz: Size,
+        ret_sz: Size,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+        op: F,
+    ) {
+        let op_sz = if sz == Size::S64 {
+            Size::S64
+        } else {
+            Size::S32
+        };
+        let value = self.acquire_temp_gpr().unwrap();
+        self.move_location(ret_sz, loc, Location::GPR(value));
+        let old = self.acquire_temp_gpr().unwrap();
+        self.memory_op(
+            target,
+            memarg,
+            true,
+            size_in_bytes(sz),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                let new = this.acquire_temp_gpr().unwrap();
+                let status = this.acquire_temp_gpr().unwrap();
+                let retry = this.get_label();
+                this.emit_label(retry);
+                this.assembler.emit_ldaxr(sz, Location::GPR(old), addr);
+                op(this, op_sz, old, value, new);
+                this.assembler
+                    .emit_stlxr(sz, Location::GPR(status), Location::GPR(new), addr);
+                this.assembler
+                    .emit_cbnz_label(Size::S32, Location::GPR(status), retry);
+                this.release_gpr(status);
+                this.release_gpr(new);
+            },
+        );
+        self.move_location(ret_sz, Location::GPR(old), ret);
+        self.release_gpr(old);
+        self.release_gpr(value);
+    }
+
+    /// Replaces the `sz` bytes at `target` with `new` if they are equal to
+    /// the low `sz` bytes of `cmp`, and returns their previous value
+    /// zero-extended to `ret_sz`, the size of `new` and `cmp` as well.
+    #[allow(clippy::too_many_arguments)]
+    fn emit_atomic_cmpxchg(
+        &mut self,
+        sz: Size,
+        ret_sz: Size,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        let op_sz = if sz == Size::S64 {
+            Size::S64
+        } else {
+            Size::S32
+        };
+        let expected = self.acquire_temp_gpr().unwrap();
+        self.move_location(ret_sz, cmp, Location::GPR(expected));
+        // The loaded bytes are zero-extended, so must be the expected ones.
+        match sz {
+            Size::S8 => self.assembler.emit_uxtb(
+                Size::S32,
+                Location::GPR(expected),
+                Location::GPR(expected),
+            ),
+            Size::S16 => self.assembler.emit_uxth(
+                Size::S32,
+                Location::GPR(expected),
+                Location::GPR(expected),
+            ),
+            _ => {}
+        }
+        let value = self.acquire_temp_gpr().unwrap();
+        self.move_location(ret_sz, new, Location::GPR(value));
+        let old = self.acquire_temp_gpr().unwrap();
+        self.memory_op(
+            target,
+            memarg,
+            true,
+            size_in_bytes(sz),
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                let status = this.acquire_temp_gpr().unwrap();
+                let retry = this.get_label();
+                let mismatch = this.get_label();
+                let done = this.get_label();
+                this.emit_label(retry);
+                this.assembler.emit_ldaxr(sz, Location::GPR(old), addr);
+                this.assembler
+                    .emit_cmp(op_sz, Location::GPR(expected), Location::GPR(old));
+                this.assembler.emit_bcond_label(Condition::Ne, mismatch);
+                this.assembler
+                    .emit_stlxr(sz, Location::GPR(status), Location::GPR(value), addr);
+                this.assembler
+                    .emit_cbnz_label(Size::S32, Location::GPR(status), retry);
+                this.assembler.emit_b_label(done);
+                this.emit_label(mismatch);
+                // Nothing is stored, the exclusive access is over.
+                this.assembler.emit_clrex();
+                this.emit_label(done);
+                this.release_gpr(status);
+            },
+        );
+        self.move_location(ret_sz, Location::GPR(old), ret);
+        self.release_gpr(old);
+        self.release_gpr(value);
+        self.release_gpr(expected);
+    }
 
     fn offset_is_ok(&self, size: Size, offset: i32) -> bool {
         if offset < 0 {
@@ -2111,6 +2309,9 @@ impl Machine for MachineARM64 {
     fn arch_supports_canonicalize_nan(&self) -> bool {
         self.assembler.arch_supports_canonicalize_nan()
     }
+    fn arch_supports_atomics(&self) -> bool {
+        true
+    }
     fn canonicalize_nan(&mut self, sz: Size, input: Location, output: Location) {
         let mut tempn = vec![];
         let mut temps = vec![];
@@ -2874,39 +3075,69 @@ impl Machine for MachineARM64 {
     }
     fn i32_atomic_load(
         &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_load(
+            Size::S32,
+            Size::S32,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     fn i32_atomic_load_8u(
         &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_load(
+            Size::S8,
+            Size::S32,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     fn i32_atomic_load_16u(
         &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_load(
+            Size::S16,
+            Size::S32,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     fn i32_save(
         &mut self,
@@ -2982,336 +3213,732 @@ impl Machine for MachineARM64 {
     }
     fn i32_atomic_save(
         &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_store(
+            Size::S32,
+            Size::S32,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     fn i32_atomic_save_8(
         &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_store(
+            Size::S8,
+            Size::S32,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     fn i32_atomic_save_16(
         &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_store(
+            Size::S16,
+            Size::S32,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     // i32 atomic Add with i32
     fn i32_atomic_add(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Add with u8
     fn i32_atomic_add_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Add with u16
     fn i32_atomic_add_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Sub with i32
     fn i32_atomic_sub(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Sub with u8
     fn i32_atomic_sub_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Sub with u16
     fn i32_atomic_sub_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic And with i32
     fn i32_atomic_and(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic And with u8
     fn i32_atomic_and_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic And with u16
     fn i32_atomic_and_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Or with i32
     fn i32_atomic_or(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Or with u8
     fn i32_atomic_or_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Or with u16
     fn i32_atomic_or_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Xor with i32
     fn i32_atomic_xor(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Xor with u8
     fn i32_atomic_xor_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Xor with u16
     fn i32_atomic_xor_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i32 atomic Exchange with i32
     fn i32_atomic_xchg(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
     }
     // i32 atomic Exchange with u8
     fn i32_atomic_xchg_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
     }
     // i32 atomic Exchange with u16
     fn i32_atomic_xchg_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i32 atomic Exchange with i32
-    fn i32_atomic_cmpxchg(
-        &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
-    }
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S32,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
+    }
+    // i32 atomic Exchange with i32
+    fn i32_atomic_cmpxchg(
+        &mut self,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_cmpxchg(
+            Size::S32,
+            Size::S32,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
     // i32 atomic Exchange with u8
     fn i32_atomic_cmpxchg_8u(
         &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_cmpxchg(
+            Size::S8,
+            Size::S32,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     // i32 atomic Exchange with u16
     fn i32_atomic_cmpxchg_16u(
         &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_cmpxchg(
+            Size::S16,
+            Size::S32,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
 
     fn emit_call_with_reloc(
@@ -3734,9 +4361,502 @@ impl Machine for MachineARM64 {
             },
         );
     }
-    fn i64_load_8s(
+    fn i64_load_8s(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            1,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_ldr8s(Size::S64, ret, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_load_16u(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            2,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_ldr16(Size::S64, ret, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_load_16s(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            2,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_ldr16s(Size::S64, ret, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_load_32u(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            4,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_ldr32(Size::S64, ret, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_load_32s(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            addr,
+            memarg,
+            false,
+            4,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_ldr32s(Size::S64, ret, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_atomic_load(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_load(
+            Size::S64,
+            Size::S64,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_atomic_load_8u(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_load(
+            Size::S8,
+            Size::S64,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_atomic_load_16u(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_load(
+            Size::S16,
+            Size::S64,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_atomic_load_32u(
+        &mut self,
+        addr: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_load(
+            Size::S32,
+            Size::S64,
+            addr,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_save(
+        &mut self,
+        target_value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            target_addr,
+            memarg,
+            false,
+            8,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_str64(target_value, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_save_8(
+        &mut self,
+        target_value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            target_addr,
+            memarg,
+            false,
+            1,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_str8(target_value, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_save_16(
+        &mut self,
+        target_value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            target_addr,
+            memarg,
+            false,
+            2,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_str16(target_value, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_save_32(
+        &mut self,
+        target_value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.memory_op(
+            target_addr,
+            memarg,
+            false,
+            4,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, addr| {
+                this.emit_relaxed_str32(target_value, Location::Memory(addr, 0));
+            },
+        );
+    }
+    fn i64_atomic_save(
+        &mut self,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_store(
+            Size::S64,
+            Size::S64,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_atomic_save_8(
+        &mut self,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_store(
+            Size::S8,
+            Size::S64,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_atomic_save_16(
+        &mut self,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_store(
+            Size::S16,
+            Size::S64,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    fn i64_atomic_save_32(
+        &mut self,
+        value: Location,
+        memarg: &MemoryImmediate,
+        target_addr: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_store(
+            Size::S32,
+            Size::S64,
+            value,
+            memarg,
+            target_addr,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
+    }
+    // i64 atomic Add with i64
+    fn i64_atomic_add(
+        &mut self,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_rmw(
+            Size::S64,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
+    }
+    // i64 atomic Add with u8
+    fn i64_atomic_add_8u(
+        &mut self,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
+    }
+    // i64 atomic Add with u16
+    fn i64_atomic_add_16u(
+        &mut self,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
+    ) {
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
+    }
+    // i64 atomic Add with u32
+    fn i64_atomic_add_32u(
         &mut self,
-        addr: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
         ret: Location,
         need_check: bool,
@@ -3744,23 +4864,32 @@ impl Machine for MachineARM64 {
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            addr,
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            1,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_ldr8s(Size::S64, ret, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_add(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_load_16u(
+    // i64 atomic Sub with i64
+    fn i64_atomic_sub(
         &mut self,
-        addr: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
         ret: Location,
         need_check: bool,
@@ -3768,23 +4897,32 @@ impl Machine for MachineARM64 {
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            addr,
+        self.emit_atomic_rmw(
+            Size::S64,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            2,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_ldr16(Size::S64, ret, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_load_16s(
+    // i64 atomic Sub with u8
+    fn i64_atomic_sub_8u(
         &mut self,
-        addr: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
         ret: Location,
         need_check: bool,
@@ -3792,23 +4930,32 @@ impl Machine for MachineARM64 {
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            addr,
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            2,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_ldr16s(Size::S64, ret, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_load_32u(
+    // i64 atomic Sub with u16
+    fn i64_atomic_sub_16u(
         &mut self,
-        addr: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
         ret: Location,
         need_check: bool,
@@ -3816,23 +4963,32 @@ impl Machine for MachineARM64 {
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            addr,
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            4,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_ldr32(Size::S64, ret, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_load_32s(
+    // i64 atomic Sub with u32
+    fn i64_atomic_sub_32u(
         &mut self,
-        addr: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
         ret: Location,
         need_check: bool,
@@ -3840,607 +4996,646 @@ impl Machine for MachineARM64 {
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            addr,
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            4,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_ldr32s(Size::S64, ret, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_sub(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_atomic_load(
-        &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_atomic_load_8u(
-        &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_atomic_load_16u(
-        &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_atomic_load_32u(
-        &mut self,
-        _addr: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_save(
+    // i64 atomic And with i64
+    fn i64_atomic_and(
         &mut self,
-        target_value: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
-        target_addr: Location,
+        ret: Location,
         need_check: bool,
         imported_memories: bool,
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            target_addr,
+        self.emit_atomic_rmw(
+            Size::S64,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            8,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_str64(target_value, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_save_8(
+    // i64 atomic And with u8
+    fn i64_atomic_and_8u(
         &mut self,
-        target_value: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
-        target_addr: Location,
+        ret: Location,
         need_check: bool,
         imported_memories: bool,
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            target_addr,
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            1,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_str8(target_value, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_save_16(
+    // i64 atomic And with u16
+    fn i64_atomic_and_16u(
         &mut self,
-        target_value: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
-        target_addr: Location,
+        ret: Location,
         need_check: bool,
         imported_memories: bool,
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            target_addr,
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            2,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_str16(target_value, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_save_32(
+    // i64 atomic And with u32
+    fn i64_atomic_and_32u(
         &mut self,
-        target_value: Location,
+        loc: Location,
+        target: Location,
         memarg: &MemoryImmediate,
-        target_addr: Location,
+        ret: Location,
         need_check: bool,
         imported_memories: bool,
         offset: i32,
         heap_access_oob: Label,
     ) {
-        self.memory_op(
-            target_addr,
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S64,
+            loc,
+            target,
             memarg,
-            false,
-            4,
+            ret,
             need_check,
             imported_memories,
             offset,
             heap_access_oob,
-            |this, addr| {
-                this.emit_relaxed_str32(target_value, Location::Memory(addr, 0));
+            |this, sz, old, value, new| {
+                this.assembler.emit_and(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
             },
         );
     }
-    fn i64_atomic_save(
-        &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_atomic_save_8(
-        &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_atomic_save_16(
-        &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    fn i64_atomic_save_32(
-        &mut self,
-        _value: Location,
-        _memarg: &MemoryImmediate,
-        _target_addr: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Add with i64
-    fn i64_atomic_add(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Add with u8
-    fn i64_atomic_add_8u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Add with u16
-    fn i64_atomic_add_16u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Add with u32
-    fn i64_atomic_add_32u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Sub with i64
-    fn i64_atomic_sub(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Sub with u8
-    fn i64_atomic_sub_8u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Sub with u16
-    fn i64_atomic_sub_16u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic Sub with u32
-    fn i64_atomic_sub_32u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic And with i64
-    fn i64_atomic_and(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic And with u8
-    fn i64_atomic_and_8u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic And with u16
-    fn i64_atomic_and_16u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
-    // i64 atomic And with u32
-    fn i64_atomic_and_32u(
-        &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
-    ) {
-        unimplemented!();
-    }
     // i64 atomic Or with i64
     fn i64_atomic_or(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S64,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic Or with u8
     fn i64_atomic_or_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic Or with u16
     fn i64_atomic_or_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic Or with u32
     fn i64_atomic_or_32u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_or(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic xor with i64
     fn i64_atomic_xor(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S64,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic xor with u8
     fn i64_atomic_xor_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic xor with u16
     fn i64_atomic_xor_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic xor with u32
     fn i64_atomic_xor_32u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, old, value, new| {
+                this.assembler.emit_eor(
+                    sz,
+                    Location::GPR(old),
+                    Location::GPR(value),
+                    Location::GPR(new),
+                );
+            },
+        );
     }
     // i64 atomic Exchange with i64
     fn i64_atomic_xchg(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S64,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
     }
     // i64 atomic Exchange with u8
     fn i64_atomic_xchg_8u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S8,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
     }
     // i64 atomic Exchange with u16
     fn i64_atomic_xchg_16u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S16,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
     }
     // i64 atomic Exchange with u32
     fn i64_atomic_xchg_32u(
         &mut self,
-        _loc: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        loc: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_rmw(
+            Size::S32,
+            Size::S64,
+            loc,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+            |this, sz, _old, value, new| {
+                this.assembler
+                    .emit_mov(sz, Location::GPR(value), Location::GPR(new));
+            },
+        );
     }
     // i64 atomic Exchange with i64
     fn i64_atomic_cmpxchg(
         &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_cmpxchg(
+            Size::S64,
+            Size::S64,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     // i64 atomic Exchange with u8
     fn i64_atomic_cmpxchg_8u(
         &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_cmpxchg(
+            Size::S8,
+            Size::S64,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     // i64 atomic Exchange with u16
     fn i64_atomic_cmpxchg_16u(
         &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_cmpxchg(
+            Size::S16,
+            Size::S64,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
     // i64 atomic Exchange with u32
     fn i64_atomic_cmpxchg_32u(
         &mut self,
-        _new: Location,
-        _cmp: Location,
-        _target: Location,
-        _memarg: &MemoryImmediate,
-        _ret: Location,
-        _need_check: bool,
-        _imported_memories: bool,
-        _offset: i32,
-        _heap_access_oob: Label,
+        new: Location,
+        cmp: Location,
+        target: Location,
+        memarg: &MemoryImmediate,
+        ret: Location,
+        need_check: bool,
+        imported_memories: bool,
+        offset: i32,
+        heap_access_oob: Label,
     ) {
-        unimplemented!();
+        self.emit_atomic_cmpxchg(
+            Size::S32,
+            Size::S64,
+            new,
+            cmp,
+            target,
+            memarg,
+            ret,
+            need_check,
+            imported_memories,
+            offset,
+            heap_access_oob,
+        );
     }
 
     fn f32_load(
@@ -12,7 +12,7 @@ use dynasmrt::{x64::X64Relocation, DynasmError, VecAssembler};
 #[cfg(feature = "unwind")]
 use gimli::{write::CallFrameInstruction, X86_64};
 use std::ops::{Deref, DerefMut};
-use wasmer_compiler::wasmparser::Type as WpType;
+use wasmer_compiler::wasmparser::ValType as WpType;
 use wasmer_compiler::{CallingConvention, CpuFeature};
 use wasmer_types::{
     CustomSection, CustomSectionProtection, Relocation, RelocationKind, RelocationTarget,
@@ -539,8 +539,9 @@ impl MachineX86_64 {
 
         self.release_gpr(tmp2);
 
-        let align = memarg.align;
-        if check_alignment && align != 1 {
+        // Atomic accesses must be aligned to their size, whatever the
+        // alignment hint of `memarg` says
+        if check_alignment && value_size != 1 {
             let tmp_aligncheck = self.acquire_temp_gpr().unwrap();
             self.assembler.emit_mov(
                 Size::S32,
@@ -549,11 +550,13 @@ impl MachineX86_64 {
             );
             self.assembler.emit_and(
                 Size::S64,
-                Location::Imm32((align - 1).into()),
+                Location::Imm32((value_size - 1) as u32),
                 Location::GPR(tmp_aligncheck),
             );
-            self.assembler
-                .emit_jmp(Condition::NotEqual, heap_access_oob);
+            let aligned = self.get_label();
+            self.assembler.emit_jmp(Condition::Equal, aligned);
+            self.emit_illegal_op(TrapCode::UnalignedAtomic);
+            self.emit_label(aligned);
             self.release_gpr(tmp_aligncheck);
         }
         let begin = self.assembler.get_offset().0;
@@ -2159,19 +2162,44 @@ impl Machine for MachineX86_64 {
             _ => unreachable!(),
         };
         match source {
-            Location::GPR(_)
-            | Location::Memory(_, _)
-            | Location::Memory2(_, _, _, _)
-            | Location::Imm32(_) => match size_val {
-                Size::S32 | Size::S64 => self.assembler.emit_mov(size_val, source, dst),
-                Size::S16 | Size::S8 => {
-                    if signed {
-                        self.assembler.emit_movsx(size_val, source, size_op, dst)
-                    } else {
-                        self.assembler.emit_movzx(size_val, source, size_op, dst)
+            Location::GPR(_) | Location::Memory(_, _) | Location::Memory2(_, _, _, _) => {
+                match size_val {
+                    Size::S32 | Size::S64 => self.assembler.emit_mov(size_val, source, dst),
+                    Size::S16 | Size::S8 => {
+                        if signed {
+                            self.assembler.emit_movsx(size_val, source, size_op, dst)
+                        } else {
+                            self.assembler.emit_movzx(size_val, source, size_op, dst)
+                        }
                     }
                 }
-            },
+            }
+            // Immediates are extended right away
+            Location::Imm8(_) | Location::Imm32(_) | Location::Imm64(_) => {
+                let value = match source {
+                    Location::Imm8(value) => value as u64,
+                    Location::Imm32(value) => value as u64,
+                    Location::Imm64(value) => value,
+                    _ => unreachable!(),
+                };
+                let value = match (size_val, signed) {
+                    (Size::S8, false) => value as u8 as u64,
+                    (Size::S8, true) => value as i8 as u64,
+                    (Size::S16, false) => value as u16 as u64,
+                    (Size::S16, true) => value as i16 as u64,
+                    (Size::S32, false) => value as u32 as u64,
+                    (Size::S32, true) => value as i32 as u64,
+                    (Size::S64, _) => value,
+                };
+                match size_op {
+                    Size::S64 => self
+                        .assembler
+                        .emit_mov(Size::S64, Location::Imm64(value), dst),
+                    _ => self
+                        .assembler
+                        .emit_mov(Size::S32, Location::Imm32(value as u32), dst),
+                }
+            }
             _ => panic!(
                 "unimplemented move_location_extend({:?}, {}, {:?}, {:?}, {:?}",
                 size_val, signed, source, size_op, dest
@@ -2285,6 +2313,9 @@ impl Machine for MachineX86_64 {
     fn arch_supports_canonicalize_nan(&self) -> bool {
         self.assembler.arch_supports_canonicalize_nan()
     }
+    fn arch_supports_atomics(&self) -> bool {
+        true
+    }
     fn canonicalize_nan(&mut self, sz: Size, input: Location, output: Location) {
         let tmp1 = self.acquire_temp_simd().unwrap();
         let tmp2 = self.acquire_temp_simd().unwrap();
@@ -3763,8 +3794,7 @@ impl Machine for MachineX86_64 {
         heap_access_oob: Label,
     ) {
         let value = self.acquire_temp_gpr().unwrap();
-        self.assembler
-            .emit_movzx(Size::S8, loc, Size::S32, Location::GPR(value));
+        self.move_location_extend(Size::S8, false, loc, Size::S32, Location::GPR(value));
         self.memory_op(
             target,
             memarg,
@@ -3795,8 +3825,7 @@ impl Machine for MachineX86_64 {
         heap_access_oob: Label,
     ) {
         let value = self.acquire_temp_gpr().unwrap();
-        self.assembler
-            .emit_movzx(Size::S16, loc, Size::S32, Location::GPR(value));
+        self.move_location_extend(Size::S16, false, loc, Size::S32, Location::GPR(value));
         self.memory_op(
             target,
             memarg,
@@ -4871,7 +4900,7 @@ impl Machine for MachineX86_64 {
             heap_access_oob,
             |this, addr| {
                 this.assembler.emit_lock_xadd(
-                    Size::S32,
+                    Size::S64,
                     Location::GPR(value),
                     Location::Memory(addr, 0),
                 );
@@ -5517,8 +5546,7 @@ impl Machine for MachineX86_64 {
         heap_access_oob: Label,
     ) {
         let value = self.acquire_temp_gpr().unwrap();
-        self.assembler
-            .emit_movzx(Size::S8, loc, Size::S64, Location::GPR(value));
+        self.move_location_extend(Size::S8, false, loc, Size::S64, Location::GPR(value));
         self.memory_op(
             target,
             memarg,
@@ -5549,8 +5577,7 @@ impl Machine for MachineX86_64 {
         heap_access_oob: Label,
     ) {
         let value = self.acquire_temp_gpr().unwrap();
-        self.assembler
-            .emit_movzx(Size::S16, loc, Size::S64, Location::GPR(value));
+        self.move_location_extend(Size::S16, false, loc, Size::S64, Location::GPR(value));
         self.memory_op(
             target,
             memarg,
@@ -5584,8 +5611,7 @@ impl Machine for MachineX86_64 {
         heap_access_oob: Label,
     ) {
         let value = self.acquire_temp_gpr().unwrap();
-        self.assembler
-            .emit_movzx(Size::S32, loc, Size::S64, Location::GPR(value));
+        self.move_location_extend(Size::S32, false, loc, Size::S64, Location::GPR(value));
         self.memory_op(
             target,
             memarg,
@@ -5799,12 +5825,15 @@ impl Machine for MachineX86_64 {
             heap_access_oob,
             |this, addr| {
                 this.assembler.emit_lock_cmpxchg(
-                    Size::S16,
+                    Size::S32,
                     Location::GPR(value),
                     Location::Memory(addr, 0),
                 );
+                // Clears the upper half, which a successful exchange leaves
+                // as it was
                 this.assembler
-                    .emit_mov(Size::S32, Location::GPR(compare), ret);
+                    .emit_mov(Size::S32, Location::GPR(compare), Location::GPR(compare));
+                this.move_location(Size::S64, Location::GPR(compare), ret);
             },
         );
         self.assembler.emit_pop(Size::S64, Location::GPR(value));
@@ -4,7 +4,7 @@
 use crate::lib::std::boxed::Box;
 use crate::lib::std::sync::Arc;
 use crate::target::Target;
-use crate::translator::ModuleMiddleware;
+use crate::translator::{FuelMetering, ModuleMiddleware};
 use crate::FunctionBodyData;
 use crate::ModuleTranslationState;
 use wasmer_types::compilation::function::Compilation;
@@ -55,6 +55,26 @@ pub trait CompilerConfig {
         // in case they create an IR that they can verify.
     }
 
+    /// Enable epoch-based interruption.
+    ///
+    /// Compiled code checks the engine's epoch against the store's deadline
+    /// on function entry and at loop headers, and traps with
+    /// `TrapCode::Interrupt` once the deadline is reached.
+    fn epoch_interruption(&mut self, _enable: bool) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they support interrupting running code.
+    }
+
+    /// Enable fuel metering, or disable it with `None`.
+    ///
+    /// Compiled code charges the fuel of the instance at the end of every
+    /// basic block, and traps with `TrapCode::OutOfFuel` when there isn't
+    /// enough left.
+    fn fuel_metering(&mut self, _fuel: Option<FuelMetering>) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they support fuel metering.
+    }
+
     /// Gets the custom compiler config
     fn compiler(self: Box<Self>) -> Box<dyn Compiler>;
 
@@ -86,7 +106,6 @@ pub trait Compiler: Send {
         features: &Features,
         data: &'data [u8],
     ) -> Result<(), CompileError> {
-        let mut validator = Validator::new();
         let wasm_features = WasmFeatures {
             bulk_memory: features.bulk_memory,
             threads: features.threads,
@@ -94,7 +113,6 @@ pub trait Compiler: Send {
             multi_value: features.multi_value,
             simd: features.simd,
             tail_call: features.tail_call,
-            module_linking: features.module_linking,
             multi_memory: features.multi_memory,
             memory64: features.memory64,
             exceptions: features.exceptions,
@@ -104,8 +122,9 @@ pub trait Compiler: Send {
             mutable_global: true,
             saturating_float_to_int: true,
             sign_extension: true,
+            component_model: false,
         };
-        validator.wasm_features(wasm_features);
+        let mut validator = Validator::new_with_features(wasm_features);
         validator
             .validate_all(data)
             .map_err(|e| CompileError::Validate(format!("{}", e)))?;
@@ -143,6 +162,12 @@ pub trait Compiler: Send {
 
     /// Get the middlewares for this compiler
     fn get_middlewares(&self) -> &[Arc<dyn ModuleMiddleware>];
+
+    /// Get the fuel configuration of this compiler, if fuel metering is
+    /// enabled.
+    fn get_fuel_metering(&self) -> Option<&FuelMetering> {
+        None
+    }
 }
 
 /// The kinds of wasmer_types objects that might be found in a native object file.
@@ -1,12 +1,12 @@
 use crate::CpuFeature;
-use crate::{resolve_imports, Export, InstantiationError, RuntimeError, Tunables};
+use crate::{resolve_imports, Export, InstantiationError, LinkError, RuntimeError, Tunables};
 use crate::{ArtifactCreate, Upcastable};
 use std::any::Any;
 use wasmer_types::entity::BoxedSlice;
 use wasmer_types::{DataInitializer, FunctionIndex, LocalFunctionIndex, SignatureIndex};
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, InstanceAllocator, InstanceHandle, TrapHandler,
-    VMSharedSignatureIndex, VMTrampoline,
+    EpochCounter, EpochDeadline, FuncDataRegistry, FunctionBodyPtr, InstanceAllocator,
+    InstanceHandle, MemoryImages, TrapHandler, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// An `Artifact` is the product that the `Engine`
@@ -42,6 +42,12 @@ pub trait Artifact: Send + Sync + Upcastable + ArtifactCreate {
     /// Get the func data registry
     fn func_data_registry(&self) -> &FuncDataRegistry;
 
+    /// Returns the images of the local memories, mapped into new memories
+    /// instead of copying the data initializers, if the artifact has them.
+    fn memory_images(&self) -> Option<&MemoryImages> {
+        None
+    }
+
     /// Do preinstantiation logic that is executed before instantiating
     fn preinstantiate(&self) -> Result<(), InstantiationError> {
         Ok(())
@@ -56,6 +62,8 @@ pub trait Artifact: Send + Sync + Upcastable + ArtifactCreate {
         tunables: &dyn Tunables,
         imports: &[Export],
         host_state: Box<dyn Any>,
+        epoch_counter: EpochCounter,
+        epoch_deadline: EpochDeadline,
     ) -> Result<InstanceHandle, InstantiationError> {
         // Validate the CPU features this module was compiled with against the
         // host CPU features.
@@ -91,7 +99,11 @@ pub trait Artifact: Send + Sync + Upcastable + ArtifactCreate {
         // Get pointers to where metadata about local tables should live in VM memory.
 
         let (allocator, memory_definition_locations, table_definition_locations) =
-            InstanceAllocator::new(&*module);
+            match tunables.instance_pool() {
+                Some(pool) => InstanceAllocator::new_pooled(&*module, pool)
+                    .map_err(|e| InstantiationError::Link(LinkError::Resource(e)))?,
+                None => InstanceAllocator::new(&*module),
+            };
         let finished_memories = tunables
             .create_memories(&module, self.memory_styles(), &memory_definition_locations)
             .map_err(InstantiationError::Link)?
@@ -119,6 +131,8 @@ pub trait Artifact: Send + Sync + Upcastable + ArtifactCreate {
             self.signatures().clone(),
             host_state,
             import_function_envs,
+            epoch_counter,
+            epoch_deadline,
         )
         .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))?;
         Ok(handle)
@@ -142,7 +156,7 @@ pub trait Artifact: Send + Sync + Upcastable + ArtifactCreate {
             })
             .collect::<Vec<_>>();
         handle
-            .finish_instantiation(trap_handler, &data_initializers)
+            .finish_instantiation(trap_handler, &data_initializers, self.memory_images())
             .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))
     }
 }
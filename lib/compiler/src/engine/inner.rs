@@ -8,7 +8,7 @@ use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::sync::Arc;
 use wasmer_types::{CompileError, DeserializeError, FunctionType};
-use wasmer_vm::{VMCallerCheckedAnyfunc, VMFuncRef, VMSharedSignatureIndex};
+use wasmer_vm::{EpochCounter, VMCallerCheckedAnyfunc, VMFuncRef, VMSharedSignatureIndex};
 
 /// A unimplemented Wasmer `Engine`.
 ///
@@ -67,6 +67,10 @@ pub trait Engine {
     /// of trait representation.
     fn id(&self) -> &EngineId;
 
+    /// The epoch counter checked by code compiled with epoch interruption
+    /// enabled. It's shared by all the stores using this engine.
+    fn epoch_counter(&self) -> &EpochCounter;
+
     /// Clone the engine
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync>;
 }
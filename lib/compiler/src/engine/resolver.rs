@@ -32,6 +32,7 @@ fn get_extern_from_import(module: &ModuleInfo, import_index: &ImportIndex) -> Ex
             let global = module.globals[*index];
             ExternType::Global(global)
         }
+        ImportIndex::Tag(index) => ExternType::Tag(module.tag_type(*index)),
     }
 }
 
@@ -45,6 +46,7 @@ fn get_extern_from_export(_module: &ModuleInfo, export: &Export) -> ExternType {
             let global = g.from.ty();
             ExternType::Global(*global)
         }
+        Export::Tag(ref t) => ExternType::Tag(t.ty().clone()),
     }
 }
 
@@ -65,6 +67,7 @@ pub fn resolve_imports(
     let mut table_imports = PrimaryMap::with_capacity(module.num_imported_tables);
     let mut memory_imports = PrimaryMap::with_capacity(module.num_imported_memories);
     let mut global_imports = PrimaryMap::with_capacity(module.num_imported_globals);
+    let mut tag_imports = PrimaryMap::with_capacity(module.num_imported_tags);
 
     for ((module_name, field, import_idx), import_index) in module.imports.iter() {
         let import_extern = get_extern_from_import(module, import_index);
@@ -205,6 +208,10 @@ pub fn resolve_imports(
                     from: g.from.clone(),
                 });
             }
+
+            Export::Tag(ref t) => {
+                tag_imports.push(t.from.clone());
+            }
         }
     }
 
@@ -214,5 +221,6 @@ pub fn resolve_imports(
         table_imports,
         memory_imports,
         global_imports,
+        tag_imports,
     ))
 }
@@ -7,6 +7,7 @@ use wasmer_types::{
     ModuleInfo, TableIndex, TableType,
 };
 use wasmer_vm::MemoryError;
+use wasmer_vm::PoolingAllocator;
 use wasmer_vm::{Global, Memory, Table};
 use wasmer_vm::{MemoryStyle, TableStyle};
 use wasmer_vm::{VMMemoryDefinition, VMTableDefinition};
@@ -56,6 +57,16 @@ pub trait Tunables {
         vm_definition_location: NonNull<VMTableDefinition>,
     ) -> Result<Arc<dyn Table>, String>;
 
+    /// Returns the pool to allocate instances from, or `None` to allocate
+    /// each instance on its own.
+    ///
+    /// The memories and tables of a pooled instance are still created by
+    /// [`Tunables::create_vm_memory`] and [`Tunables::create_vm_table`], which
+    /// should allocate them from the same pool.
+    fn instance_pool(&self) -> Option<&PoolingAllocator> {
+        None
+    }
+
     /// Create a global with an unset value.
     fn create_global(&self, ty: GlobalType) -> Result<Arc<Global>, String> {
         Ok(Arc::new(Global::new(ty)))
@@ -74,13 +85,14 @@ pub trait Tunables {
         let num_imports = module.num_imported_memories;
         let mut memories: PrimaryMap<LocalMemoryIndex, _> =
             PrimaryMap::with_capacity(module.memories.len() - num_imports);
+        // The definition locations are indexed by local memory, they don't
+        // have entries for the imported memories.
         for (index, mdl) in memory_definition_locations
             .iter()
             .enumerate()
-            .take(module.memories.len())
-            .skip(num_imports)
+            .take(module.memories.len() - num_imports)
         {
-            let mi = MemoryIndex::new(index);
+            let mi = module.memory_index(LocalMemoryIndex::new(index));
             let ty = &module.memories[mi];
             let style = &memory_styles[mi];
             memories.push(
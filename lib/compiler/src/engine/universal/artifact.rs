@@ -19,8 +19,8 @@ use wasmer_types::{
     OwnedDataInitializer, SerializeError, SignatureIndex, TableIndex,
 };
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, TableStyle, VMSharedSignatureIndex,
-    VMTrampoline,
+    FuncDataRegistry, FunctionBodyPtr, MemoryImages, MemoryStyle, TableStyle,
+    VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A compiled wasm module, ready to be instantiated.
@@ -33,6 +33,7 @@ pub struct UniversalArtifact {
     func_data_registry: Arc<FuncDataRegistry>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
     finished_function_lengths: BoxedSlice<LocalFunctionIndex, usize>,
+    memory_images: MemoryImages,
 }
 
 impl UniversalArtifact {
@@ -173,6 +174,9 @@ impl UniversalArtifact {
             finished_dynamic_function_trampolines.into_boxed_slice();
         let signatures = signatures.into_boxed_slice();
         let func_data_registry = engine_inner.func_data().clone();
+        // The first instantiation lays out the initial memories, so that the
+        // following ones map them instead of copying the data initializers.
+        let memory_images = MemoryImages::new(artifact.module_ref(), artifact.data_initializers());
 
         Ok(Self {
             artifact,
@@ -183,6 +187,7 @@ impl UniversalArtifact {
             frame_info_registration: Mutex::new(None),
             finished_function_lengths,
             func_data_registry,
+            memory_images,
         })
     }
     /// Get the default extension when serializing this artifact
@@ -277,4 +282,8 @@ impl Artifact for UniversalArtifact {
     fn func_data_registry(&self) -> &FuncDataRegistry {
         &self.func_data_registry
     }
+
+    fn memory_images(&self) -> Option<&MemoryImages> {
+        Some(&self.memory_images)
+    }
 }
@@ -15,8 +15,8 @@ use wasmer_types::{
 };
 use wasmer_types::{CustomSection, CustomSectionProtection, SectionIndex};
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, SectionBodyPtr, SignatureRegistry, VMCallerCheckedAnyfunc,
-    VMFuncRef, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
+    EpochCounter, FuncDataRegistry, FunctionBodyPtr, SectionBodyPtr, SignatureRegistry,
+    VMCallerCheckedAnyfunc, VMFuncRef, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A WebAssembly `Universal` Engine.
@@ -26,6 +26,7 @@ pub struct UniversalEngine {
     /// The target for the compiler
     target: Arc<Target>,
     engine_id: EngineId,
+    epoch: EpochCounter,
 }
 
 impl UniversalEngine {
@@ -41,6 +42,7 @@ impl UniversalEngine {
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
+            epoch: EpochCounter::new(),
         }
     }
 
@@ -67,6 +69,7 @@ impl UniversalEngine {
             })),
             target: Arc::new(Target::default()),
             engine_id: EngineId::default(),
+            epoch: EpochCounter::new(),
         }
     }
 
@@ -139,6 +142,10 @@ impl Engine for UniversalEngine {
         &self.engine_id
     }
 
+    fn epoch_counter(&self) -> &EpochCounter {
+        &self.epoch
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
@@ -80,9 +80,10 @@ pub use crate::target::{
 };
 #[cfg(feature = "translator")]
 pub use crate::translator::{
-    from_binaryreadererror_wasmerror, translate_module, wptype_to_type, FunctionBinaryReader,
-    FunctionBodyData, FunctionMiddleware, MiddlewareBinaryReader, MiddlewareReaderState,
-    ModuleEnvironment, ModuleMiddleware, ModuleMiddlewareChain, ModuleTranslationState,
+    from_binaryreadererror_wasmerror, translate_module, wptype_to_type, FuelMetering,
+    FunctionBinaryReader, FunctionBodyData, FunctionFuel, FunctionMiddleware,
+    MiddlewareBinaryReader, MiddlewareReaderState, ModuleEnvironment, ModuleMiddleware,
+    ModuleMiddlewareChain, ModuleTranslationState,
 };
 
 pub use wasmer_types::{Addend, CodeOffset, Features};
@@ -1,22 +1,24 @@
 // This file contains code from external sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
+use super::error::from_binaryreadererror_wasmerror;
 use super::state::ModuleTranslationState;
 use crate::lib::std::borrow::ToOwned;
 use crate::lib::std::string::ToString;
 use crate::lib::std::{boxed::Box, string::String, vec::Vec};
 use crate::translate_module;
-use crate::wasmparser::{Operator, Range, Type};
+use crate::wasmparser::{FunctionBody, Operator, ValType};
 use std::convert::{TryFrom, TryInto};
+use std::ops::Range;
 use std::sync::Arc;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::FunctionType;
+use wasmer_types::WasmResult;
 use wasmer_types::{
     CustomSectionIndex, DataIndex, DataInitializer, DataInitializerLocation, ElemIndex,
     ExportIndex, FunctionIndex, GlobalIndex, GlobalInit, GlobalType, ImportIndex,
     LocalFunctionIndex, MemoryIndex, MemoryType, ModuleInfo, SignatureIndex, TableIndex,
-    TableInitializer, TableType,
+    TableInitializer, TableType, TagIndex,
 };
-use wasmer_types::{WasmError, WasmResult};
 
 /// Contains function data: bytecode and its offset in the module.
 #[derive(Hash)]
@@ -28,13 +30,34 @@ pub struct FunctionBodyData<'a> {
     pub module_offset: usize,
 }
 
+impl<'a> FunctionBodyData<'a> {
+    /// Whether the function makes tail calls, with `return_call` or
+    /// `return_call_indirect`.
+    pub fn makes_tail_calls(&self) -> WasmResult<bool> {
+        let body = FunctionBody::new(self.module_offset, self.data);
+        let mut operators = body
+            .get_operators_reader()
+            .map_err(from_binaryreadererror_wasmerror)?;
+        while !operators.eof() {
+            let operator = operators.read().map_err(from_binaryreadererror_wasmerror)?;
+            if matches!(
+                operator,
+                Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. }
+            ) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
 /// Trait for iterating over the operators of a Wasm Function
 pub trait FunctionBinaryReader<'a> {
     /// Read a `count` indicating the number of times to call `read_local_decl`.
     fn read_local_count(&mut self) -> WasmResult<u32>;
 
     /// Read a `(count, value_type)` declaration of local variables of the same type.
-    fn read_local_decl(&mut self) -> WasmResult<(u32, Type)>;
+    fn read_local_decl(&mut self) -> WasmResult<(u32, ValType)>;
 
     /// Reads the next available `Operator`.
     fn read_operator(&mut self) -> WasmResult<Operator<'a>>;
@@ -52,7 +75,7 @@ pub trait FunctionBinaryReader<'a> {
     fn eof(&self) -> bool;
 
     /// Return the range (original offset, original offset + data length)
-    fn range(&self) -> Range;
+    fn range(&self) -> Range<usize>;
 }
 
 /// The result of translating via `ModuleEnvironment`. Function bodies are not
@@ -216,6 +239,27 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_tag_import(
+        &mut self,
+        sig_index: SignatureIndex,
+        module: &str,
+        field: &str,
+    ) -> WasmResult<()> {
+        debug_assert_eq!(
+            self.module.tags.len(),
+            self.module.num_imported_tags,
+            "Imported tags must be declared first"
+        );
+        self.declare_import(
+            ImportIndex::Tag(TagIndex::from_u32(self.module.num_imported_tags as _)),
+            module,
+            field,
+        )?;
+        self.module.tags.push(sig_index);
+        self.module.num_imported_tags += 1;
+        Ok(())
+    }
+
     pub(crate) fn finish_imports(&mut self) -> WasmResult<()> {
         Ok(())
     }
@@ -254,15 +298,22 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn declare_memory(&mut self, memory: MemoryType) -> WasmResult<()> {
-        if memory.shared {
-            return Err(WasmError::Unsupported(
-                "shared memories are not supported yet".to_owned(),
-            ));
-        }
         self.module.memories.push(memory);
         Ok(())
     }
 
+    pub(crate) fn reserve_tags(&mut self, num: u32) -> WasmResult<()> {
+        self.module
+            .tags
+            .reserve_exact(usize::try_from(num).unwrap());
+        Ok(())
+    }
+
+    pub(crate) fn declare_tag(&mut self, sig_index: SignatureIndex) -> WasmResult<()> {
+        self.module.tags.push(sig_index);
+        Ok(())
+    }
+
     pub(crate) fn reserve_globals(&mut self, num: u32) -> WasmResult<()> {
         self.module
             .globals
@@ -317,6 +368,10 @@ impl<'data> ModuleEnvironment<'data> {
         self.declare_export(ExportIndex::Global(global_index), name)
     }
 
+    pub(crate) fn declare_tag_export(&mut self, tag_index: TagIndex, name: &str) -> WasmResult<()> {
+        self.declare_export(ExportIndex::Tag(tag_index), name)
+    }
+
     pub(crate) fn declare_start_function(&mut self, func_index: FunctionIndex) -> WasmResult<()> {
         debug_assert!(self.module.start_function.is_none());
         self.module.start_function = Some(func_index);
@@ -0,0 +1,118 @@
+//! Fuel metering done natively by the compilers.
+//!
+//! Unlike the `Metering` middleware, which rewrites the operators of each
+//! function to update globals, the compilers keep the remaining fuel in the
+//! `VMContext` and charge it directly at the end of every basic block.
+
+use std::fmt;
+use std::sync::Arc;
+use wasmparser::Operator;
+
+/// The fuel configuration of a compiler.
+///
+/// # Example
+///
+/// ```rust
+/// use wasmer_compiler::{CompilerConfig, FuelMetering};
+///
+/// fn enable_fuel(compiler_config: &mut dyn CompilerConfig) {
+///     // Every operator costs one unit of fuel, and instances start with
+///     // enough fuel for a million of them.
+///     compiler_config.fuel_metering(Some(FuelMetering::new(1_000_000, |_| 1)));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct FuelMetering {
+    initial_fuel: u64,
+    cost_function: Arc<dyn Fn(&Operator) -> u64 + Send + Sync>,
+}
+
+impl FuelMetering {
+    /// Creates a fuel configuration where new instances get
+    /// `initial_fuel`, and each operator costs what `cost_function`
+    /// returns for it.
+    pub fn new(
+        initial_fuel: u64,
+        cost_function: impl Fn(&Operator) -> u64 + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            initial_fuel,
+            cost_function: Arc::new(cost_function),
+        }
+    }
+
+    /// The fuel given to new instances.
+    pub fn initial_fuel(&self) -> u64 {
+        self.initial_fuel
+    }
+
+    /// Creates the accumulator used while compiling a function.
+    pub fn function_fuel(&self) -> FunctionFuel {
+        FunctionFuel {
+            cost_function: self.cost_function.clone(),
+            accumulated_cost: 0,
+        }
+    }
+}
+
+impl fmt::Debug for FuelMetering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FuelMetering")
+            .field("initial_fuel", &self.initial_fuel)
+            .field("cost_function", &"<function>")
+            .finish()
+    }
+}
+
+/// Accumulates the cost of the basic block being compiled.
+pub struct FunctionFuel {
+    cost_function: Arc<dyn Fn(&Operator) -> u64 + Send + Sync>,
+    accumulated_cost: u64,
+}
+
+impl FunctionFuel {
+    /// Adds the cost of `operator` to the current basic block.
+    ///
+    /// If `operator` is a possible source or target of a branch, this
+    /// returns the cost of the whole block, which must be charged before
+    /// `operator` executes, and starts a new block. Blocks that cost
+    /// nothing return `None`.
+    ///
+    /// The cost of `operator` itself is charged before it executes, so that
+    /// calls can't escape metering.
+    pub fn feed(&mut self, operator: &Operator) -> Option<u64> {
+        self.accumulated_cost = self
+            .accumulated_cost
+            .saturating_add((self.cost_function)(operator));
+        match operator {
+            Operator::Loop { .. }
+            | Operator::End
+            | Operator::Else
+            | Operator::Br { .. }
+            | Operator::BrTable { .. }
+            | Operator::BrIf { .. }
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::Return
+            | Operator::Catch { .. }
+            | Operator::CatchAll
+            | Operator::Delegate { .. }
+            | Operator::Throw { .. }
+            | Operator::Rethrow { .. }
+                if self.accumulated_cost > 0 =>
+            {
+                Some(std::mem::take(&mut self.accumulated_cost))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for FunctionFuel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FunctionFuel")
+            .field("cost_function", &"<function>")
+            .field("accumulated_cost", &self.accumulated_cost)
+            .finish()
+    }
+}
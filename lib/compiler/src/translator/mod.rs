@@ -6,6 +6,7 @@
 //!
 //! [cranelift-wasm]: https://crates.io/crates/cranelift-wasm/
 mod environ;
+mod fuel;
 mod middleware;
 mod module;
 mod state;
@@ -14,6 +15,7 @@ mod error;
 mod sections;
 
 pub use self::environ::{FunctionBinaryReader, FunctionBodyData, ModuleEnvironment};
+pub use self::fuel::{FuelMetering, FunctionFuel};
 pub use self::middleware::{
     FunctionMiddleware, MiddlewareBinaryReader, MiddlewareReaderState, ModuleMiddleware,
     ModuleMiddlewareChain,
@@ -8,7 +8,7 @@ use super::error::from_binaryreadererror_wasmerror;
 use super::sections::{
     parse_data_section, parse_element_section, parse_export_section, parse_function_section,
     parse_global_section, parse_import_section, parse_memory_section, parse_name_section,
-    parse_start_section, parse_table_section, parse_type_section,
+    parse_start_section, parse_table_section, parse_tag_section, parse_type_section,
 };
 use super::state::ModuleTranslationState;
 use wasmer_types::WasmResult;
@@ -24,7 +24,7 @@ pub fn translate_module<'data>(
 
     for payload in Parser::new(0).parse_all(data) {
         match payload.map_err(from_binaryreadererror_wasmerror)? {
-            Payload::Version { .. } | Payload::End => {}
+            Payload::Version { .. } | Payload::End(_) => {}
 
             Payload::TypeSection(types) => {
                 parse_type_section(types, &mut module_translation_state, environ)?;
@@ -83,29 +83,36 @@ pub fn translate_module<'data>(
                 environ.reserve_passive_data(count)?;
             }
 
-            Payload::InstanceSection(_)
+            Payload::ModuleSection { .. }
+            | Payload::InstanceSection(_)
             | Payload::AliasSection(_)
-            | Payload::ModuleSectionStart { .. }
-            | Payload::ModuleSectionEntry { .. } => {
-                unimplemented!("module linking not implemented yet")
+            | Payload::CoreTypeSection(_)
+            | Payload::ComponentSection { .. }
+            | Payload::ComponentInstanceSection(_)
+            | Payload::ComponentAliasSection(_)
+            | Payload::ComponentTypeSection(_)
+            | Payload::ComponentCanonicalSection(_)
+            | Payload::ComponentStartSection(_)
+            | Payload::ComponentImportSection(_)
+            | Payload::ComponentExportSection(_) => {
+                unimplemented!("component model not implemented yet")
             }
 
-            Payload::TagSection(_) => {
-                unimplemented!("exception handling not implemented yet")
+            Payload::TagSection(tags) => {
+                parse_tag_section(tags, environ)?;
             }
 
-            Payload::CustomSection {
-                name: "name",
-                data,
-                data_offset,
-                ..
-            } => parse_name_section(
-                NameSectionReader::new(data, data_offset)
-                    .map_err(from_binaryreadererror_wasmerror)?,
-                environ,
-            )?,
+            Payload::CustomSection(sectionreader) if sectionreader.name() == "name" => {
+                parse_name_section(
+                    NameSectionReader::new(sectionreader.data(), sectionreader.data_offset())
+                        .map_err(from_binaryreadererror_wasmerror)?,
+                    environ,
+                )?
+            }
 
-            Payload::CustomSection { name, data, .. } => environ.custom_section(name, data)?,
+            Payload::CustomSection(sectionreader) => {
+                environ.custom_section(sectionreader.name(), sectionreader.data())?
+            }
 
             Payload::UnknownSection { .. } => unreachable!(),
         }
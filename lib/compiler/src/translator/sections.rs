@@ -13,7 +13,6 @@
 use super::environ::ModuleEnvironment;
 use super::error::from_binaryreadererror_wasmerror;
 use super::state::ModuleTranslationState;
-use crate::wasm_unsupported;
 use core::convert::TryFrom;
 use std::boxed::Box;
 use std::collections::HashMap;
@@ -22,31 +21,28 @@ use wasmer_types::entity::packed_option::ReservedValue;
 use wasmer_types::entity::EntityRef;
 use wasmer_types::{
     DataIndex, ElemIndex, FunctionIndex, FunctionType, GlobalIndex, GlobalInit, GlobalType,
-    MemoryIndex, MemoryType, Pages, SignatureIndex, TableIndex, TableType, Type, V128,
+    MemoryIndex, MemoryType, Pages, SignatureIndex, TableIndex, TableType, TagIndex, Type, V128,
 };
 use wasmer_types::{WasmError, WasmResult};
 use wasmparser::{
     self, Data, DataKind, DataSectionReader, Element, ElementItem, ElementItems, ElementKind,
     ElementSectionReader, Export, ExportSectionReader, ExternalKind, FuncType as WPFunctionType,
-    FunctionSectionReader, GlobalSectionReader, GlobalType as WPGlobalType, ImportSectionEntryType,
-    ImportSectionReader, MemorySectionReader, MemoryType as WPMemoryType, NameSectionReader,
-    Naming, NamingReader, Operator, TableSectionReader, TypeDef, TypeSectionReader,
+    FunctionSectionReader, GlobalSectionReader, GlobalType as WPGlobalType, ImportSectionReader,
+    MemorySectionReader, MemoryType as WPMemoryType, NameSectionReader, Naming, NamingReader,
+    Operator, TableSectionReader, TagSectionReader, TagType as WPTagType, Type as WPType, TypeRef,
+    TypeSectionReader, ValType,
 };
 
 /// Helper function translating wasmparser types to Wasm Type.
-pub fn wptype_to_type(ty: wasmparser::Type) -> WasmResult<Type> {
+pub fn wptype_to_type(ty: ValType) -> WasmResult<Type> {
     match ty {
-        wasmparser::Type::I32 => Ok(Type::I32),
-        wasmparser::Type::I64 => Ok(Type::I64),
-        wasmparser::Type::F32 => Ok(Type::F32),
-        wasmparser::Type::F64 => Ok(Type::F64),
-        wasmparser::Type::V128 => Ok(Type::V128),
-        wasmparser::Type::ExternRef => Ok(Type::ExternRef),
-        wasmparser::Type::FuncRef => Ok(Type::FuncRef),
-        ty => Err(wasm_unsupported!(
-            "wptype_to_type: wasmparser type {:?}",
-            ty
-        )),
+        ValType::I32 => Ok(Type::I32),
+        ValType::I64 => Ok(Type::I64),
+        ValType::F32 => Ok(Type::F32),
+        ValType::F64 => Ok(Type::F64),
+        ValType::V128 => Ok(Type::V128),
+        ValType::ExternRef => Ok(Type::ExternRef),
+        ValType::FuncRef => Ok(Type::FuncRef),
     }
 }
 
@@ -60,27 +56,25 @@ pub fn parse_type_section(
     environ.reserve_signatures(count)?;
 
     for entry in types {
-        if let Ok(TypeDef::Func(WPFunctionType { params, returns })) = entry {
-            let sig_params: Vec<Type> = params
-                .iter()
-                .map(|ty| {
-                    wptype_to_type(*ty)
-                        .expect("only numeric types are supported in function signatures")
-                })
-                .collect();
-            let sig_returns: Vec<Type> = returns
-                .iter()
-                .map(|ty| {
-                    wptype_to_type(*ty)
-                        .expect("only numeric types are supported in function signatures")
-                })
-                .collect();
-            let sig = FunctionType::new(sig_params, sig_returns);
-            environ.declare_signature(sig)?;
-            module_translation_state.wasm_types.push((params, returns));
-        } else {
-            unimplemented!("module linking not implemented yet")
-        }
+        let WPType::Func(WPFunctionType { params, returns }) =
+            entry.map_err(from_binaryreadererror_wasmerror)?;
+        let sig_params: Vec<Type> = params
+            .iter()
+            .map(|ty| {
+                wptype_to_type(*ty)
+                    .expect("only numeric types are supported in function signatures")
+            })
+            .collect();
+        let sig_returns: Vec<Type> = returns
+            .iter()
+            .map(|ty| {
+                wptype_to_type(*ty)
+                    .expect("only numeric types are supported in function signatures")
+            })
+            .collect();
+        let sig = FunctionType::new(sig_params, sig_returns);
+        environ.declare_signature(sig)?;
+        module_translation_state.wasm_types.push((params, returns));
     }
 
     Ok(())
@@ -96,23 +90,27 @@ pub fn parse_import_section<'data>(
     for entry in imports {
         let import = entry.map_err(from_binaryreadererror_wasmerror)?;
         let module_name = import.module;
-        let field_name = import.field;
+        let field_name = import.name;
 
         match import.ty {
-            ImportSectionEntryType::Function(sig) => {
+            TypeRef::Func(sig) => {
                 environ.declare_func_import(
                     SignatureIndex::from_u32(sig),
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
-            ImportSectionEntryType::Module(_) | ImportSectionEntryType::Instance(_) => {
-                unimplemented!("module linking not implemented yet")
-            }
-            ImportSectionEntryType::Tag(_) => {
-                unimplemented!("exception handling not implemented yet")
+            TypeRef::Tag(WPTagType {
+                func_type_idx: type_index,
+                ..
+            }) => {
+                environ.declare_tag_import(
+                    SignatureIndex::from_u32(type_index),
+                    module_name,
+                    field_name,
+                )?;
             }
-            ImportSectionEntryType::Memory(WPMemoryType {
+            TypeRef::Memory(WPMemoryType {
                 shared,
                 memory64,
                 initial,
@@ -128,20 +126,20 @@ pub fn parse_import_section<'data>(
                         shared,
                     },
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
-            ImportSectionEntryType::Global(ref ty) => {
+            TypeRef::Global(ref ty) => {
                 environ.declare_global_import(
                     GlobalType {
                         ty: wptype_to_type(ty.content_type).unwrap(),
                         mutability: ty.mutable.into(),
                     },
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
-            ImportSectionEntryType::Table(ref tab) => {
+            TypeRef::Table(ref tab) => {
                 environ.declare_table_import(
                     TableType {
                         ty: wptype_to_type(tab.element_type).unwrap(),
@@ -149,7 +147,7 @@ pub fn parse_import_section<'data>(
                         maximum: tab.maximum,
                     },
                     module_name,
-                    field_name.unwrap_or_default(),
+                    field_name,
                 )?;
             }
         }
@@ -226,6 +224,24 @@ pub fn parse_memory_section(
     Ok(())
 }
 
+/// Parses the Tag section of the wasm module.
+pub fn parse_tag_section(
+    tags: TagSectionReader,
+    environ: &mut ModuleEnvironment,
+) -> WasmResult<()> {
+    environ.reserve_tags(tags.get_count())?;
+
+    for entry in tags {
+        let WPTagType {
+            func_type_idx: type_index,
+            ..
+        } = entry.map_err(from_binaryreadererror_wasmerror)?;
+        environ.declare_tag(SignatureIndex::from_u32(type_index))?;
+    }
+
+    Ok(())
+}
+
 /// Parses the Global section of the wasm module.
 pub fn parse_global_section(
     globals: GlobalSectionReader,
@@ -284,7 +300,7 @@ pub fn parse_export_section<'data>(
 
     for entry in exports {
         let Export {
-            field,
+            name: field,
             ref kind,
             index,
         } = entry.map_err(from_binaryreadererror_wasmerror)?;
@@ -294,9 +310,7 @@ pub fn parse_export_section<'data>(
         // becomes a concern here.
         let index = index as usize;
         match *kind {
-            ExternalKind::Function => {
-                environ.declare_func_export(FunctionIndex::new(index), field)?
-            }
+            ExternalKind::Func => environ.declare_func_export(FunctionIndex::new(index), field)?,
             ExternalKind::Table => environ.declare_table_export(TableIndex::new(index), field)?,
             ExternalKind::Memory => {
                 environ.declare_memory_export(MemoryIndex::new(index), field)?
@@ -304,12 +318,7 @@ pub fn parse_export_section<'data>(
             ExternalKind::Global => {
                 environ.declare_global_export(GlobalIndex::new(index), field)?
             }
-            ExternalKind::Type | ExternalKind::Module | ExternalKind::Instance => {
-                unimplemented!("module linking not implemented yet")
-            }
-            ExternalKind::Tag => {
-                unimplemented!("exception handling not implemented yet")
-            }
+            ExternalKind::Tag => environ.declare_tag_export(TagIndex::new(index), field)?,
         }
     }
 
@@ -365,7 +374,7 @@ pub fn parse_element_section<'data>(
             ty,
             range: _,
         } = entry.map_err(from_binaryreadererror_wasmerror)?;
-        if ty != wasmparser::Type::FuncRef {
+        if ty != ValType::FuncRef {
             return Err(wasm_unsupported!(
                 "unsupported table element type: {:?}",
                 ty
@@ -10,7 +10,7 @@ use crate::MetadataHeader;
 use crate::{ArtifactCreate, UniversalEngineBuilder};
 use crate::{CpuFeature, Features, Triple};
 #[cfg(feature = "universal_engine")]
-use crate::{ModuleEnvironment, ModuleMiddlewareChain, Target};
+use crate::{FuelMetering, ModuleEnvironment, ModuleMiddlewareChain, Target};
 use enumset::EnumSet;
 use std::mem;
 use std::sync::Arc;
@@ -59,6 +59,7 @@ impl UniversalArtifactBuild {
         let mut module = translation.module;
         let middlewares = compiler.get_middlewares();
         middlewares.apply_on_module_info(&mut module);
+        module.initial_fuel = compiler.get_fuel_metering().map(FuelMetering::initial_fuel);
 
         let compile_info = CompileModuleInfo {
             module: Arc::new(module),
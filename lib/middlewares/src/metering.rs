@@ -3,6 +3,10 @@
 //! operators executed. The WebAssemblt instance execution is stopped
 //! when the limit is reached.
 //!
+//! [`get_remaining_points`] and [`set_remaining_points`] also work with
+//! instances of modules compiled with the native fuel metering of the
+//! compilers (see `CompilerConfig::fuel_metering`), which is faster.
+//!
 //! # Example
 //!
 //! [See the `metering` detailed and complete
@@ -11,7 +15,7 @@
 use std::convert::TryInto;
 use std::fmt;
 use std::sync::{Arc, Mutex};
-use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
+use wasmer::wasmparser::{BlockType as WpBlockType, Operator};
 use wasmer::{
     ExportIndex, FunctionMiddleware, GlobalInit, GlobalType, Instance, LocalFunctionIndex,
     MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
@@ -234,7 +238,7 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMiddleware for FunctionMeter
                         Operator::GlobalGet { global_index: self.global_indexes.remaining_points().as_u32() },
                         Operator::I64Const { value: self.accumulated_cost as i64 },
                         Operator::I64LtU,
-                        Operator::If { ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType) },
+                        Operator::If { ty: WpBlockType::Empty },
                         Operator::I32Const { value: 1 },
                         Operator::GlobalSet { global_index: self.global_indexes.points_exhausted().as_u32() },
                         Operator::Unreachable,
@@ -266,8 +270,8 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMiddleware for FunctionMeter
 /// # Panic
 ///
 /// The [`Instance`][wasmer::Instance) must have been processed with
-/// the [`Metering`] middleware at compile time, otherwise this will
-/// panic.
+/// the [`Metering`] middleware or compiled with fuel metering,
+/// otherwise this will panic.
 ///
 /// # Example
 ///
@@ -282,6 +286,13 @@ impl<F: Fn(&Operator) -> u64 + Send + Sync> FunctionMiddleware for FunctionMeter
 /// }
 /// ```
 pub fn get_remaining_points(instance: &Instance) -> MeteringPoints {
+    if let Some(fuel) = instance.remaining_fuel() {
+        if instance.fuel_exhausted() {
+            return MeteringPoints::Exhausted;
+        }
+        return MeteringPoints::Remaining(fuel);
+    }
+
     let exhausted: i32 = instance
         .exports
         .get_global("wasmer_metering_points_exhausted")
@@ -314,8 +325,8 @@ pub fn get_remaining_points(instance: &Instance) -> MeteringPoints {
 /// # Panic
 ///
 /// The given [`Instance`][wasmer::Instance] must have been processed
-/// with the [`Metering`] middleware at compile time, otherwise this
-/// will panic.
+/// with the [`Metering`] middleware or compiled with fuel metering,
+/// otherwise this will panic.
 ///
 /// # Example
 ///
@@ -332,6 +343,11 @@ pub fn get_remaining_points(instance: &Instance) -> MeteringPoints {
 /// }
 /// ```
 pub fn set_remaining_points(instance: &Instance, points: u64) {
+    if instance.remaining_fuel().is_some() {
+        instance.set_fuel(points);
+        return;
+    }
+
     instance
         .exports
         .get_global("wasmer_metering_remaining_points")
@@ -352,7 +368,9 @@ mod tests {
     use super::*;
 
     use std::sync::Arc;
-    use wasmer::{imports, wat2wasm, CompilerConfig, Cranelift, Module, Store, Universal};
+    use wasmer::{
+        imports, wat2wasm, CompilerConfig, Cranelift, FuelMetering, Module, Store, Universal,
+    };
 
     fn cost_function(operator: &Operator) -> u64 {
         match operator {
@@ -476,4 +494,42 @@ mod tests {
             MeteringPoints::Remaining(4)
         );
     }
+
+    #[test]
+    fn native_fuel_works() {
+        let mut compiler_config = Cranelift::default();
+        compiler_config.fuel_metering(Some(FuelMetering::new(10, cost_function)));
+        let store = Store::new_with_engine(&Universal::new(compiler_config).engine());
+        let module = Module::new(&store, bytecode()).unwrap();
+
+        // Instantiate
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        assert_eq!(
+            get_remaining_points(&instance),
+            MeteringPoints::Remaining(10)
+        );
+        let add_one = instance
+            .exports
+            .get_function("add_one")
+            .unwrap()
+            .native::<i32, i32>()
+            .unwrap();
+
+        // The costs are the same as with the middleware
+        add_one.call(1).unwrap();
+        add_one.call(1).unwrap();
+        assert_eq!(
+            get_remaining_points(&instance),
+            MeteringPoints::Remaining(2)
+        );
+        assert!(add_one.call(1).is_err());
+        assert_eq!(get_remaining_points(&instance), MeteringPoints::Exhausted);
+
+        set_remaining_points(&instance, 4);
+        add_one.call(1).unwrap();
+        assert_eq!(
+            get_remaining_points(&instance),
+            MeteringPoints::Remaining(0)
+        );
+    }
 }
@@ -224,6 +224,10 @@ pub struct ExternRef {
     inner: VMExternRef,
 }
 
+// The data is `Send + Sync` and its reference count is atomic.
+unsafe impl Send for ExternRef {}
+unsafe impl Sync for ExternRef {}
+
 impl Clone for ExternRef {
     fn clone(&self) -> Self {
         Self {
@@ -231,6 +231,25 @@ impl Features {
         self.memory64 = enable;
         self
     }
+
+    /// Configures whether the WebAssembly exception handling proposal
+    /// will be enabled.
+    ///
+    /// The [WebAssembly exception handling proposal][proposal] is not
+    /// currently fully standardized and is undergoing development.
+    /// Support for this feature can be enabled through this method for
+    /// appropriate WebAssembly modules.
+    ///
+    /// This feature gates tags and the `try`, `catch`, `throw`,
+    /// `rethrow` and `delegate` instructions.
+    ///
+    /// This is `false` by default.
+    ///
+    /// [proposal]: https://github.com/WebAssembly/exception-handling
+    pub fn exceptions(&mut self, enable: bool) -> &mut Self {
+        self.exceptions = enable;
+        self
+    }
 }
 
 impl Default for Features {
@@ -57,6 +57,12 @@ pub struct LocalGlobalIndex(u32);
 entity_impl!(LocalGlobalIndex);
 entity_impl!(ArchivedLocalGlobalIndex);
 
+/// Index type of a tag defined locally inside the WebAssembly module.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct LocalTagIndex(u32);
+entity_impl!(LocalTagIndex);
+
 /// Index type of a function (imported or local) inside the WebAssembly module.
 #[derive(
     Copy,
@@ -137,6 +143,26 @@ pub struct MemoryIndex(u32);
 entity_impl!(MemoryIndex);
 entity_impl!(ArchivedMemoryIndex);
 
+/// Index type of an exception tag (imported or local) inside the WebAssembly module.
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Debug,
+    RkyvSerialize,
+    RkyvDeserialize,
+    Archive,
+)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[archive_attr(derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug))]
+pub struct TagIndex(u32);
+entity_impl!(TagIndex);
+entity_impl!(ArchivedTagIndex);
+
 /// Index type of a signature (imported or local) inside the WebAssembly module.
 #[derive(
     Copy,
@@ -232,6 +258,8 @@ pub enum ExportIndex {
     Memory(MemoryIndex),
     /// Global export.
     Global(GlobalIndex),
+    /// Tag export.
+    Tag(TagIndex),
 }
 
 /// An entity to import.
@@ -249,4 +277,6 @@ pub enum ImportIndex {
     Memory(MemoryIndex),
     /// Global import.
     Global(GlobalIndex),
+    /// Tag import.
+    Tag(TagIndex),
 }
@@ -6,7 +6,8 @@ use std::fmt;
 
 /// The name of a runtime library routine.
 ///
-/// This list is likely to grow over time.
+/// This list is likely to grow over time. New routines are appended, as
+/// serialized artifacts refer to the routines by their index.
 #[derive(
     Copy,
     Clone,
@@ -114,6 +115,54 @@ pub enum LibCall {
     /// probe for stack overflow. These are emitted for functions which need
     /// when the `enable_probestack` setting is true.
     Probestack,
+
+    /// memory.atomic.wait32 for local memories
+    Memory32AtomicWait32,
+
+    /// memory.atomic.wait32 for imported memories
+    ImportedMemory32AtomicWait32,
+
+    /// memory.atomic.wait64 for local memories
+    Memory32AtomicWait64,
+
+    /// memory.atomic.wait64 for imported memories
+    ImportedMemory32AtomicWait64,
+
+    /// memory.atomic.notify for local memories
+    Memory32AtomicNotify,
+
+    /// memory.atomic.notify for imported memories
+    ImportedMemory32AtomicNotify,
+
+    /// throw
+    Throw,
+
+    /// rethrow
+    Rethrow,
+
+    /// catch
+    Catch,
+
+    /// catch_all
+    CatchAll,
+
+    /// return_call
+    ReturnCall,
+
+    /// return_call_indirect
+    ReturnCallIndirect,
+
+    /// The tail call requested by a callee
+    TailCall,
+
+    /// memory.copy between two different memories
+    Memory32CopyBetween,
+
+    /// Whether an exception is pending in the running call
+    ExceptionPending,
+
+    /// Whether the tail call loop called the function that enters
+    TailCallEntry,
 }
 
 impl LibCall {
@@ -156,6 +205,22 @@ impl LibCall {
             Self::Probestack => "_wasmer_vm_probestack",
             #[cfg(not(target_vendor = "apple"))]
             Self::Probestack => "wasmer_vm_probestack",
+            Self::Memory32AtomicWait32 => "wasmer_vm_memory32_atomic_wait32",
+            Self::ImportedMemory32AtomicWait32 => "wasmer_vm_imported_memory32_atomic_wait32",
+            Self::Memory32AtomicWait64 => "wasmer_vm_memory32_atomic_wait64",
+            Self::ImportedMemory32AtomicWait64 => "wasmer_vm_imported_memory32_atomic_wait64",
+            Self::Memory32AtomicNotify => "wasmer_vm_memory32_atomic_notify",
+            Self::ImportedMemory32AtomicNotify => "wasmer_vm_imported_memory32_atomic_notify",
+            Self::Throw => "wasmer_vm_throw",
+            Self::Rethrow => "wasmer_vm_rethrow",
+            Self::Catch => "wasmer_vm_catch",
+            Self::CatchAll => "wasmer_vm_catch_all",
+            Self::ReturnCall => "wasmer_vm_return_call",
+            Self::ReturnCallIndirect => "wasmer_vm_return_call_indirect",
+            Self::TailCall => "wasmer_vm_tail_call",
+            Self::Memory32CopyBetween => "wasmer_vm_memory32_copy_between",
+            Self::ExceptionPending => "wasmer_vm_exception_pending",
+            Self::TailCallEntry => "wasmer_vm_tail_call_entry",
         }
     }
 }
@@ -165,3 +230,16 @@ impl fmt::Display for LibCall {
         fmt::Debug::fmt(self, f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_are_stable() {
+        assert_eq!(LibCall::CeilF32 as u32, 0);
+        assert_eq!(LibCall::DataDrop as u32, 28);
+        assert_eq!(LibCall::RaiseTrap as u32, 29);
+        assert_eq!(LibCall::Probestack as u32, 30);
+    }
+}
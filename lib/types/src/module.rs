@@ -8,8 +8,8 @@ use crate::entity::{EntityRef, PrimaryMap};
 use crate::{
     CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, FunctionIndex,
     FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType, LocalFunctionIndex,
-    LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType, SignatureIndex,
-    TableIndex, TableInitializer, TableType,
+    LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, LocalTagIndex, MemoryIndex, MemoryType,
+    SignatureIndex, TableIndex, TableInitializer, TableType, TagIndex, TagType,
 };
 use indexmap::IndexMap;
 use rkyv::{
@@ -106,6 +106,10 @@ pub struct ModuleInfo {
     /// WebAssembly global variables (imported and local).
     pub globals: PrimaryMap<GlobalIndex, GlobalType>,
 
+    /// WebAssembly exception tags (imported and local), as the signature
+    /// of their payload.
+    pub tags: PrimaryMap<TagIndex, SignatureIndex>,
+
     /// Custom sections in the module.
     pub custom_sections: IndexMap<String, CustomSectionIndex>,
 
@@ -123,6 +127,13 @@ pub struct ModuleInfo {
 
     /// Number of imported globals in the module.
     pub num_imported_globals: usize,
+
+    /// Number of imported tags in the module.
+    pub num_imported_tags: usize,
+
+    /// The fuel given to new instances, if the module was compiled with
+    /// fuel metering.
+    pub initial_fuel: Option<u64>,
 }
 
 /// Mirror version of ModuleInfo that can derive rkyv traits
@@ -142,12 +153,15 @@ pub struct ArchivableModuleInfo {
     tables: PrimaryMap<TableIndex, TableType>,
     memories: PrimaryMap<MemoryIndex, MemoryType>,
     globals: PrimaryMap<GlobalIndex, GlobalType>,
+    tags: PrimaryMap<TagIndex, SignatureIndex>,
     custom_sections: IndexMap<String, CustomSectionIndex>,
     custom_sections_data: PrimaryMap<CustomSectionIndex, Arc<[u8]>>,
     num_imported_functions: usize,
     num_imported_tables: usize,
     num_imported_memories: usize,
     num_imported_globals: usize,
+    num_imported_tags: usize,
+    initial_fuel: Option<u64>,
 }
 
 impl From<ModuleInfo> for ArchivableModuleInfo {
@@ -167,12 +181,15 @@ impl From<ModuleInfo> for ArchivableModuleInfo {
             tables: it.tables,
             memories: it.memories,
             globals: it.globals,
+            tags: it.tags,
             custom_sections: it.custom_sections,
             custom_sections_data: it.custom_sections_data,
             num_imported_functions: it.num_imported_functions,
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            num_imported_tags: it.num_imported_tags,
+            initial_fuel: it.initial_fuel,
         }
     }
 }
@@ -195,12 +212,15 @@ impl From<ArchivableModuleInfo> for ModuleInfo {
             tables: it.tables,
             memories: it.memories,
             globals: it.globals,
+            tags: it.tags,
             custom_sections: it.custom_sections,
             custom_sections_data: it.custom_sections_data,
             num_imported_functions: it.num_imported_functions,
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            num_imported_tags: it.num_imported_tags,
+            initial_fuel: it.initial_fuel,
         }
     }
 }
@@ -255,12 +275,15 @@ impl PartialEq for ModuleInfo {
             && self.tables == other.tables
             && self.memories == other.memories
             && self.globals == other.globals
+            && self.tags == other.tags
             && self.custom_sections == other.custom_sections
             && self.custom_sections_data == other.custom_sections_data
             && self.num_imported_functions == other.num_imported_functions
             && self.num_imported_tables == other.num_imported_tables
             && self.num_imported_memories == other.num_imported_memories
             && self.num_imported_globals == other.num_imported_globals
+            && self.num_imported_tags == other.num_imported_tags
+            && self.initial_fuel == other.initial_fuel
     }
 }
 
@@ -313,6 +336,7 @@ impl ModuleInfo {
                     let global_type = self.globals.get(*i).unwrap();
                     ExternType::Global(*global_type)
                 }
+                ExportIndex::Tag(i) => ExternType::Tag(self.tag_type(*i)),
             };
             ExportType::new(name, extern_type)
         });
@@ -343,6 +367,7 @@ impl ModuleInfo {
                         let global_type = self.globals.get(*i).unwrap();
                         ExternType::Global(*global_type)
                     }
+                    ImportIndex::Tag(i) => ExternType::Tag(self.tag_type(*i)),
                 };
                 ImportType::new(module, field, extern_type)
             });
@@ -436,6 +461,29 @@ impl ModuleInfo {
         index.index() < self.num_imported_globals
     }
 
+    /// Convert a `LocalTagIndex` into a `TagIndex`.
+    pub fn tag_index(&self, local_tag: LocalTagIndex) -> TagIndex {
+        TagIndex::new(self.num_imported_tags + local_tag.index())
+    }
+
+    /// Convert a `TagIndex` into a `LocalTagIndex`. Returns None if the
+    /// index is an imported tag.
+    pub fn local_tag_index(&self, tag: TagIndex) -> Option<LocalTagIndex> {
+        tag.index()
+            .checked_sub(self.num_imported_tags)
+            .map(LocalTagIndex::new)
+    }
+
+    /// Test whether the given tag index is for an imported tag.
+    pub fn is_imported_tag(&self, index: TagIndex) -> bool {
+        index.index() < self.num_imported_tags
+    }
+
+    /// Get the type of the given tag.
+    pub fn tag_type(&self, index: TagIndex) -> TagType {
+        TagType::new(self.signatures[self.tags[index]].params())
+    }
+
     /// Get the Module name
     pub fn name(&self) -> String {
         match self.name {
@@ -512,6 +560,13 @@ impl<I: Iterator<Item = ExportType> + Sized> ExportsIterator<I> {
             _ => None,
         })
     }
+    /// Get only the tags
+    pub fn tags(self) -> impl Iterator<Item = ExportType<TagType>> + Sized {
+        self.iter.filter_map(|extern_| match extern_.ty() {
+            ExternType::Tag(ty) => Some(ExportType::new(extern_.name(), ty.clone())),
+            _ => None,
+        })
+    }
 }
 
 impl<I: Iterator<Item = ExportType> + Sized> Iterator for ExportsIterator<I> {
@@ -575,6 +630,17 @@ impl<I: Iterator<Item = ImportType> + Sized> ImportsIterator<I> {
             _ => None,
         })
     }
+    /// Get only the tags
+    pub fn tags(self) -> impl Iterator<Item = ImportType<TagType>> + Sized {
+        self.iter.filter_map(|extern_| match extern_.ty() {
+            ExternType::Tag(ty) => Some(ImportType::new(
+                extern_.module(),
+                extern_.name(),
+                ty.clone(),
+            )),
+            _ => None,
+        })
+    }
 }
 
 impl<I: Iterator<Item = ImportType> + Sized> Iterator for ImportsIterator<I> {
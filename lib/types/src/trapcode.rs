@@ -61,6 +61,19 @@ pub enum TrapCode {
 
     /// An atomic memory access was attempted with an unaligned pointer.
     UnalignedAtomic = 11,
+
+    /// A `memory.atomic.wait` was attempted on a memory that isn't shared.
+    UnsharedMemoryWait = 12,
+
+    /// Execution was interrupted because the store's epoch deadline was reached.
+    Interrupt = 13,
+
+    /// Execution ran out of fuel.
+    OutOfFuel = 14,
+
+    /// A wasm exception was thrown and not caught before returning to the
+    /// host.
+    UncaughtException = 15,
 }
 
 impl TrapCode {
@@ -79,6 +92,10 @@ impl TrapCode {
             Self::BadConversionToInteger => "invalid conversion to integer",
             Self::UnreachableCodeReached => "unreachable",
             Self::UnalignedAtomic => "unaligned atomic access",
+            Self::UnsharedMemoryWait => "expected shared memory",
+            Self::Interrupt => "interrupted: epoch deadline reached",
+            Self::OutOfFuel => "out of fuel",
+            Self::UncaughtException => "uncaught wasm exception",
         }
     }
 }
@@ -98,6 +115,10 @@ impl Display for TrapCode {
             Self::BadConversionToInteger => "bad_toint",
             Self::UnreachableCodeReached => "unreachable",
             Self::UnalignedAtomic => "unalign_atom",
+            Self::UnsharedMemoryWait => "unshared_wait",
+            Self::Interrupt => "interrupt",
+            Self::OutOfFuel => "out_of_fuel",
+            Self::UncaughtException => "uncaught_exception",
         };
         f.write_str(identifier)
     }
@@ -120,6 +141,10 @@ impl FromStr for TrapCode {
             "bad_toint" => Ok(Self::BadConversionToInteger),
             "unreachable" => Ok(Self::UnreachableCodeReached),
             "unalign_atom" => Ok(Self::UnalignedAtomic),
+            "unshared_wait" => Ok(Self::UnsharedMemoryWait),
+            "interrupt" => Ok(Self::Interrupt),
+            "out_of_fuel" => Ok(Self::OutOfFuel),
+            "uncaught_exception" => Ok(Self::UncaughtException),
             _ => Err(()),
         }
     }
@@ -130,7 +155,7 @@ mod tests {
     use super::*;
 
     // Everything but user-defined codes.
-    const CODES: [TrapCode; 12] = [
+    const CODES: [TrapCode; 16] = [
         TrapCode::StackOverflow,
         TrapCode::HeapAccessOutOfBounds,
         TrapCode::HeapMisaligned,
@@ -143,6 +168,10 @@ mod tests {
         TrapCode::BadConversionToInteger,
         TrapCode::UnreachableCodeReached,
         TrapCode::UnalignedAtomic,
+        TrapCode::UnsharedMemoryWait,
+        TrapCode::Interrupt,
+        TrapCode::OutOfFuel,
+        TrapCode::UncaughtException,
     ];
 
     #[test]
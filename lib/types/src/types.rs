@@ -118,6 +118,8 @@ pub enum ExternType {
     Table(TableType),
     /// This external type is the type of a WebAssembly memory.
     Memory(MemoryType),
+    /// This external type is the type of a WebAssembly exception tag.
+    Tag(TagType),
 }
 
 fn is_global_compatible(exported: GlobalType, imported: GlobalType) -> bool {
@@ -208,6 +210,7 @@ impl ExternType {
         (Global(GlobalType) global unwrap_global)
         (Table(TableType) table unwrap_table)
         (Memory(MemoryType) memory unwrap_memory)
+        (Tag(TagType) tag unwrap_tag)
     }
     /// Check if two externs are compatible
     pub fn is_compatible_with(&self, other: &Self) -> bool {
@@ -216,6 +219,7 @@ impl ExternType {
             (Self::Global(a), Self::Global(b)) => is_global_compatible(*a, *b),
             (Self::Table(a), Self::Table(b)) => is_table_compatible(a, b),
             (Self::Memory(a), Self::Memory(b)) => is_memory_compatible(a, b),
+            (Self::Tag(a), Self::Tag(b)) => a == b,
             // The rest of possibilities, are not compatible
             _ => false,
         }
@@ -530,6 +534,51 @@ impl fmt::Display for MemoryType {
     }
 }
 
+// Tag Types
+
+/// A descriptor for a WebAssembly exception tag.
+///
+/// Tags identify exceptions: a `catch` only handles the exceptions
+/// thrown with its tag, and receives the values listed in `params` as
+/// their payload.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[derive(RkyvSerialize, RkyvDeserialize, Archive)]
+pub struct TagType {
+    /// The types of the payload of the exceptions thrown with this tag.
+    params: Box<[Type]>,
+}
+
+impl TagType {
+    /// Creates a new descriptor for a tag whose exceptions carry values of
+    /// the given types.
+    pub fn new<Params>(params: Params) -> Self
+    where
+        Params: Into<Box<[Type]>>,
+    {
+        Self {
+            params: params.into(),
+        }
+    }
+
+    /// Payload types.
+    pub fn params(&self) -> &[Type] {
+        &self.params
+    }
+}
+
+impl fmt::Display for TagType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params = self
+            .params
+            .iter()
+            .map(|p| format!("{:?}", p))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "[{}]", params)
+    }
+}
+
 // Import Types
 
 /// A descriptor for an imported value into a wasm module.
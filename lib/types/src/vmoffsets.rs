@@ -123,9 +123,82 @@ impl VMBuiltinFunctionIndex {
     pub const fn get_externref_dec_index() -> Self {
         Self(25)
     }
+    /// Returns an index for wasm's `memory.atomic.wait32` instruction for
+    /// local memories.
+    pub const fn get_memory_atomic_wait32_index() -> Self {
+        Self(26)
+    }
+    /// Returns an index for wasm's `memory.atomic.wait32` instruction for
+    /// imported memories.
+    pub const fn get_imported_memory_atomic_wait32_index() -> Self {
+        Self(27)
+    }
+    /// Returns an index for wasm's `memory.atomic.wait64` instruction for
+    /// local memories.
+    pub const fn get_memory_atomic_wait64_index() -> Self {
+        Self(28)
+    }
+    /// Returns an index for wasm's `memory.atomic.wait64` instruction for
+    /// imported memories.
+    pub const fn get_imported_memory_atomic_wait64_index() -> Self {
+        Self(29)
+    }
+    /// Returns an index for wasm's `memory.atomic.notify` instruction for
+    /// local memories.
+    pub const fn get_memory_atomic_notify_index() -> Self {
+        Self(30)
+    }
+    /// Returns an index for wasm's `memory.atomic.notify` instruction for
+    /// imported memories.
+    pub const fn get_imported_memory_atomic_notify_index() -> Self {
+        Self(31)
+    }
+    /// Returns an index for wasm's `throw` instruction.
+    pub const fn get_throw_index() -> Self {
+        Self(32)
+    }
+    /// Returns an index for wasm's `rethrow` instruction.
+    pub const fn get_rethrow_index() -> Self {
+        Self(33)
+    }
+    /// Returns an index for wasm's `catch` instruction.
+    pub const fn get_catch_index() -> Self {
+        Self(34)
+    }
+    /// Returns an index for wasm's `catch_all` instruction.
+    pub const fn get_catch_all_index() -> Self {
+        Self(35)
+    }
+    /// Returns an index for wasm's `return_call` instruction.
+    pub const fn get_return_call_index() -> Self {
+        Self(36)
+    }
+    /// Returns an index for wasm's `return_call_indirect` instruction.
+    pub const fn get_return_call_indirect_index() -> Self {
+        Self(37)
+    }
+    /// Returns an index for making the tail call requested by a callee.
+    pub const fn get_tail_call_index() -> Self {
+        Self(38)
+    }
+    /// Returns an index for wasm's `memory.copy` between two different
+    /// memories.
+    pub const fn get_memory_copy_between_index() -> Self {
+        Self(39)
+    }
+    /// Returns an index for checking whether an exception is pending in the
+    /// running call.
+    pub const fn get_exception_pending_index() -> Self {
+        Self(40)
+    }
+    /// Returns an index for checking whether a function that makes tail
+    /// calls was called by the tail call loop, on its entry.
+    pub const fn get_tail_call_entry_index() -> Self {
+        Self(41)
+    }
     /// Returns the total number of builtin functions.
     pub const fn builtin_functions_total_number() -> u32 {
-        26
+        42
     }
 
     /// Return the index as an u32 number.
@@ -525,8 +598,8 @@ impl VMOffsets {
             .unwrap()
     }
 
-    /// Return the size of the `VMContext` allocation.
-    pub fn size_of_vmctx(&self) -> u32 {
+    /// The offset of the pointer to the engine's epoch counter.
+    pub fn vmctx_epoch_counter(&self) -> u32 {
         self.vmctx_builtin_functions_begin()
             .checked_add(
                 VMBuiltinFunctionIndex::builtin_functions_total_number()
@@ -536,6 +609,39 @@ impl VMOffsets {
             .unwrap()
     }
 
+    /// The offset of the pointer to the store's epoch deadline.
+    pub fn vmctx_epoch_deadline(&self) -> u32 {
+        self.vmctx_epoch_counter()
+            .checked_add(u32::from(self.pointer_size))
+            .unwrap()
+    }
+
+    /// The offset of the fuel left to the instance, as a `u64`.
+    pub fn vmctx_fuel_remaining(&self) -> u32 {
+        self.vmctx_epoch_deadline()
+            .checked_add(u32::from(self.pointer_size))
+            .unwrap()
+    }
+
+    /// The offset of the flag set when the instance ran out of fuel, as a
+    /// `u64`.
+    pub fn vmctx_fuel_exhausted(&self) -> u32 {
+        self.vmctx_fuel_remaining().checked_add(8).unwrap()
+    }
+
+    /// The offset of the pointer to the number of calls into wasm with an
+    /// exception pending, which compiled code checks after every call.
+    pub fn vmctx_exceptions_pending(&self) -> u32 {
+        self.vmctx_fuel_exhausted().checked_add(8).unwrap()
+    }
+
+    /// Return the size of the `VMContext` allocation.
+    pub fn size_of_vmctx(&self) -> u32 {
+        self.vmctx_exceptions_pending()
+            .checked_add(u32::from(self.pointer_size))
+            .unwrap()
+    }
+
     /// Return the offset to `VMSharedSignatureIndex` index `index`.
     pub fn vmctx_vmshared_signature_id(&self, index: SignatureIndex) -> u32 {
         assert_lt!(index.as_u32(), self.num_signature_ids);
@@ -5,6 +5,7 @@ use thiserror::Error;
 
 pub use wasmer_vfs::FileDescriptor;
 pub use wasmer_vfs::StdioMode;
+pub use wasmer_vfs::VirtualFile;
 
 pub type Result<T> = std::result::Result<T, BusError>;
 
@@ -212,6 +213,24 @@ pub trait VirtualBusProcess:
 
     /// Returns a file descriptor used to write to STDERR
     fn stderr_fd(&self) -> Option<FileDescriptor>;
+
+    /// Returns a file used to write to STDIN when the process was
+    /// spawned with `StdioMode::Piped` and has no host file descriptor
+    fn stdin_file(&self) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        None
+    }
+
+    /// Returns a file used to read from STDOUT when the process was
+    /// spawned with `StdioMode::Piped` and has no host file descriptor
+    fn stdout_file(&self) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        None
+    }
+
+    /// Returns a file used to read from STDERR when the process was
+    /// spawned with `StdioMode::Piped` and has no host file descriptor
+    fn stderr_file(&self) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        None
+    }
 }
 
 pub trait VirtualBusInvocation:
@@ -239,6 +258,11 @@ pub enum BusInvocationEvent {
         /// Data returned by the call
         data: Vec<u8>,
     },
+    /// The service has failed to process your call
+    Fault {
+        /// Fault that was raised by the service
+        fault: BusError,
+    },
 }
 
 pub trait VirtualBusListener: fmt::Debug + Send + Sync + 'static {
@@ -263,10 +287,10 @@ pub trait VirtualBusCalled: VirtualBusListener + fmt::Debug + Send + Sync + 'sta
     fn callback(&self, topic: String, format: BusDataFormat, buf: &[u8]) -> Result<()>;
 
     /// Informs the caller that their call has failed
-    fn fault(self, fault: BusError) -> Result<()>;
+    fn fault(self: Box<Self>, fault: BusError) -> Result<()>;
 
     /// Finishes the call and returns a particular response
-    fn reply(self, format: BusDataFormat, buf: &[u8]) -> Result<()>;
+    fn reply(self: Box<Self>, format: BusDataFormat, buf: &[u8]) -> Result<()>;
 }
 
 /// Format that the supplied data is in
@@ -278,6 +302,7 @@ pub enum BusDataFormat {
     Json,
     Yaml,
     Xml,
+    Rkyv,
 }
 
 #[derive(Debug, Default)]
@@ -0,0 +1,1063 @@
+//! A read-only file system that serves the content of a tar archive,
+//! optionally compressed with gzip, or of a zip archive.
+//!
+//! The archive is indexed once when the file system is created, but the
+//! content of a file is only read, and decompressed, when the file is
+//! read. Entries of an uncompressed tar archive are read straight from the
+//! underlying reader, those of a compressed archive are decompressed once
+//! and shared by all the opened files.
+//!
+//! All the modifications are rejected with `FsError::PermissionDenied`; an
+//! [`OverlayFileSystem`](crate::overlay_fs::OverlayFileSystem) can stack a
+//! writable file system over the archive if the program needs to write.
+//!
+//! ```no_run
+//! use wasmer_vfs::{archive_fs::ArchiveFileSystem, FileSystem};
+//! use std::path::Path;
+//!
+//! let fs = ArchiveFileSystem::open("assets.tar.gz").unwrap();
+//! assert!(fs.metadata(Path::new("/index.html")).unwrap().is_file());
+//! // `fs` can now be handed to `WasiStateBuilder::set_fs`.
+//! ```
+
+use crate::{DirEntry, FileDescriptor, FileOpener, FileType, VirtualFile};
+use crate::{FileSystem, FsError, Metadata, OpenOptions, OpenOptionsConfig, ReadDir, Result};
+use flate2::read::GzDecoder;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryInto;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// The maximum number of symlinks that can be followed to resolve a path.
+const MAX_SYMLINKS: usize = 40;
+
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// The first bytes of a zip archive, i.e. of its first local file header.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// The first bytes of an empty zip archive, i.e. of its end of central
+/// directory record.
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+
+/// Anything an archive can be read from.
+pub trait ArchiveReader: Read + Seek + Send {}
+
+impl<T> ArchiveReader for T where T: Read + Seek + Send {}
+
+/// A read-only file system backed by a tar, tar.gz or zip archive.
+///
+/// This type can be cloned, the clones share the same archive.
+#[derive(Debug, Clone)]
+pub struct ArchiveFileSystem {
+    inner: Arc<ArchiveInner>,
+}
+
+#[derive(Debug)]
+struct ArchiveInner {
+    /// The entries of the archive, indexed by their absolute path
+    nodes: HashMap<PathBuf, Node>,
+    archive: Mutex<Archive>,
+    /// The decompressed content of the files that were read
+    loaded: Mutex<HashMap<Data, Arc<[u8]>>>,
+}
+
+enum Archive {
+    Tar(Box<dyn ArchiveReader>),
+    TarGz(GzStream),
+    Zip(ZipArchive<Box<dyn ArchiveReader>>),
+}
+
+/// A gzip compressed tar stream. It's only decompressed from the start
+/// again to read content before the current position.
+struct GzStream {
+    decoder: Option<GzDecoder<Box<dyn ArchiveReader>>>,
+    /// The offset the decoder is at in the decompressed stream
+    position: u64,
+}
+
+impl GzStream {
+    fn new(reader: Box<dyn ArchiveReader>) -> Self {
+        Self {
+            decoder: Some(GzDecoder::new(reader)),
+            position: 0,
+        }
+    }
+
+    /// Reads `len` bytes at `offset` in the decompressed stream.
+    fn read_exact_at(&mut self, offset: u64, len: u64, content: &mut Vec<u8>) -> io::Result<()> {
+        if offset < self.position {
+            // SAFETY: the decoder is only taken here, and put back at once.
+            let mut reader = self.decoder.take().unwrap().into_inner();
+            let rewound = reader.seek(SeekFrom::Start(0));
+            self.decoder = Some(GzDecoder::new(reader));
+            self.position = 0;
+            rewound?;
+        }
+
+        // SAFETY: the decoder is always put back.
+        let decoder = self.decoder.as_mut().unwrap();
+        let skip = offset - self.position;
+        let result =
+            io::copy(&mut decoder.by_ref().take(skip), &mut io::sink()).and_then(|skipped| {
+                if skipped < skip {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                decoder.by_ref().take(len).read_to_end(content)
+            });
+        match result {
+            Ok(read) => {
+                self.position = offset + read as u64;
+                Ok(())
+            }
+            Err(error) => {
+                // Where the decoder stopped is unknown, so the next read
+                // starts over.
+                self.position = u64::MAX;
+                Err(error)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Archive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tar(_) => f.write_str("Tar"),
+            Self::TarGz(stream) => write!(f, "TarGz {{ position: {} }}", stream.position),
+            Self::Zip(_) => f.write_str("Zip"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    kind: NodeKind,
+    len: u64,
+    /// The modification time in nanoseconds as a UNIX timestamp
+    modified: u64,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Directory { children: BTreeSet<OsString> },
+    File(Data),
+    Symlink(PathBuf),
+}
+
+/// Where the content of a file lives in the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Data {
+    /// The offset of the content in the (decompressed) tar stream
+    Tar { offset: u64 },
+    /// The index of the file in the zip archive
+    Zip { index: usize },
+}
+
+impl Node {
+    fn directory(modified: u64) -> Self {
+        Self {
+            kind: NodeKind::Directory {
+                children: BTreeSet::new(),
+            },
+            len: 0,
+            modified,
+        }
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata {
+            ft: FileType {
+                dir: matches!(self.kind, NodeKind::Directory { .. }),
+                file: matches!(self.kind, NodeKind::File(_)),
+                symlink: matches!(self.kind, NodeKind::Symlink(_)),
+                ..Default::default()
+            },
+            accessed: self.modified,
+            created: self.modified,
+            modified: self.modified,
+            len: self.len,
+        }
+    }
+}
+
+/// The tree of entries being built while an archive is indexed.
+struct Tree {
+    nodes: HashMap<PathBuf, Node>,
+}
+
+impl Tree {
+    fn new() -> Self {
+        let mut nodes = HashMap::new();
+        nodes.insert(PathBuf::from("/"), Node::directory(0));
+        Self { nodes }
+    }
+
+    /// Makes sure `path` is a directory, creating the missing ancestors
+    /// along the way.
+    fn ensure_directory(&mut self, path: &Path) {
+        if let Some(Node {
+            kind: NodeKind::Directory { .. },
+            ..
+        }) = self.nodes.get(path)
+        {
+            return;
+        }
+        self.insert(path.to_path_buf(), Node::directory(0));
+    }
+
+    /// Adds `node` at `path`, an absolute and normalized path. A directory
+    /// that already exists keeps its children.
+    fn insert(&mut self, path: PathBuf, mut node: Node) {
+        let (parent, name) = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => (parent.to_path_buf(), name.to_os_string()),
+            // The root can only be a directory
+            _ => {
+                if let (Some(root), NodeKind::Directory { .. }) =
+                    (self.nodes.get_mut(&path), &node.kind)
+                {
+                    root.modified = node.modified;
+                }
+                return;
+            }
+        };
+
+        self.ensure_directory(&parent);
+        if let Some(NodeKind::Directory { children }) =
+            self.nodes.get_mut(&parent).map(|node| &mut node.kind)
+        {
+            children.insert(name);
+        }
+
+        if let (
+            Some(Node {
+                kind: NodeKind::Directory { children },
+                ..
+            }),
+            NodeKind::Directory {
+                children: new_children,
+            },
+        ) = (self.nodes.get_mut(&path), &mut node.kind)
+        {
+            *new_children = std::mem::take(children);
+        }
+        self.nodes.insert(path, node);
+    }
+
+    /// Indexes the entries of a tar archive.
+    fn index_tar<R: Read>(&mut self, entries: tar::Entries<R>) -> Result<()> {
+        for entry in entries {
+            let entry = entry?;
+            let header = entry.header();
+            let path = normalize(&entry.path()?);
+            let modified = header.mtime().unwrap_or(0).saturating_mul(1_000_000_000);
+            let len = entry.size();
+
+            let kind = match header.entry_type() {
+                tar::EntryType::Regular | tar::EntryType::Continuous => NodeKind::File(Data::Tar {
+                    offset: entry.raw_file_position(),
+                }),
+                tar::EntryType::Directory => NodeKind::Directory {
+                    children: BTreeSet::new(),
+                },
+                tar::EntryType::Symlink => match entry.link_name()? {
+                    Some(target) => NodeKind::Symlink(target.into_owned()),
+                    None => return Err(FsError::InvalidData),
+                },
+                // A hard link shares the content of an entry that was
+                // archived before it
+                tar::EntryType::Link => {
+                    let target = match entry.link_name()? {
+                        Some(target) => normalize(&target),
+                        None => return Err(FsError::InvalidData),
+                    };
+                    match self.nodes.get(&target) {
+                        Some(node) if matches!(node.kind, NodeKind::File(_)) => {
+                            let node = node.clone();
+                            self.insert(path, node);
+                            continue;
+                        }
+                        _ => return Err(FsError::InvalidData),
+                    }
+                }
+                // Devices, FIFOs and sparse files can't be served
+                _ => continue,
+            };
+
+            let len = match kind {
+                NodeKind::Symlink(ref target) => target.as_os_str().len() as u64,
+                NodeKind::Directory { .. } => 0,
+                NodeKind::File(_) => len,
+            };
+            self.insert(
+                path,
+                Node {
+                    kind,
+                    len,
+                    modified,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Indexes the entries of a zip archive.
+    fn index_zip<R: Read + Seek>(&mut self, archive: &mut ZipArchive<R>) -> Result<()> {
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index).map_err(zip_error)?;
+            // Entries that would escape the root are ignored
+            let path = match file.enclosed_name() {
+                Some(path) => normalize(path),
+                None => continue,
+            };
+            let modified = zip_time(file.last_modified());
+            let is_dir = file.is_dir();
+            let is_symlink = file
+                .unix_mode()
+                .map_or(false, |mode| mode & 0o170000 == 0o120000);
+            let len = file.size();
+            drop(file);
+
+            let kind = if is_dir {
+                NodeKind::Directory {
+                    children: BTreeSet::new(),
+                }
+            } else if is_symlink {
+                let mut target = String::new();
+                archive
+                    .by_index(index)
+                    .map_err(zip_error)?
+                    .read_to_string(&mut target)?;
+                NodeKind::Symlink(PathBuf::from(target))
+            } else {
+                NodeKind::File(Data::Zip { index })
+            };
+
+            self.insert(
+                path,
+                Node {
+                    kind,
+                    len: if is_dir { 0 } else { len },
+                    modified,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn zip_error(error: ZipError) -> FsError {
+    match error {
+        ZipError::Io(error) => error.into(),
+        ZipError::FileNotFound => FsError::EntityNotFound,
+        _ => FsError::InvalidData,
+    }
+}
+
+/// Converts the MS-DOS date and time of a zip entry into nanoseconds as a
+/// UNIX timestamp.
+fn zip_time(time: zip::DateTime) -> u64 {
+    // Days since the epoch of a date of the proleptic Gregorian calendar,
+    // the zip format can't represent dates before 1980.
+    let (month, day) = (time.month() as u64, time.day() as u64);
+    let year = time.year() as u64 - if month <= 2 { 1 } else { 0 };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).saturating_sub(719_468);
+
+    let seconds = days * 86_400
+        + time.hour() as u64 * 3_600
+        + time.minute() as u64 * 60
+        + time.second() as u64;
+    seconds * 1_000_000_000
+}
+
+/// Turns `path` into an absolute path without `.` and `..` components.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+    normalized
+}
+
+impl ArchiveFileSystem {
+    /// Opens the archive at `path` on the host, see
+    /// [`ArchiveFileSystem::from_reader`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_reader(std::fs::File::open(path)?)
+    }
+
+    /// Creates a file system from a tar, tar.gz or zip archive, the format
+    /// is detected from the first bytes of the archive.
+    pub fn from_reader<R: ArchiveReader + 'static>(mut reader: R) -> Result<Self> {
+        let mut magic = Vec::with_capacity(4);
+        (&mut reader).take(4).read_to_end(&mut magic)?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        if magic.starts_with(GZIP_MAGIC) {
+            Self::from_tar_gz(reader)
+        } else if magic.starts_with(ZIP_MAGIC) || magic.starts_with(EMPTY_ZIP_MAGIC) {
+            Self::from_zip(reader)
+        } else {
+            Self::from_tar(reader)
+        }
+    }
+
+    /// Creates a file system from an uncompressed tar archive.
+    pub fn from_tar<R: ArchiveReader + 'static>(reader: R) -> Result<Self> {
+        let mut tree = Tree::new();
+        let mut archive = tar::Archive::new(reader);
+        tree.index_tar(archive.entries_with_seek()?)?;
+        Ok(Self::with_archive(
+            tree,
+            Archive::Tar(Box::new(archive.into_inner())),
+        ))
+    }
+
+    /// Creates a file system from a gzip compressed tar archive.
+    ///
+    /// A gzip stream can't be seeked, so reading a file decompresses the
+    /// archive up to the end of that file. Reading the files in the order
+    /// of the archive decompresses it only once.
+    pub fn from_tar_gz<R: ArchiveReader + 'static>(mut reader: R) -> Result<Self> {
+        let mut tree = Tree::new();
+        tree.index_tar(tar::Archive::new(GzDecoder::new(&mut reader)).entries()?)?;
+        reader.seek(SeekFrom::Start(0))?;
+        Ok(Self::with_archive(
+            tree,
+            Archive::TarGz(GzStream::new(Box::new(reader))),
+        ))
+    }
+
+    /// Creates a file system from a zip archive.
+    pub fn from_zip<R: ArchiveReader + 'static>(reader: R) -> Result<Self> {
+        let mut tree = Tree::new();
+        let reader: Box<dyn ArchiveReader> = Box::new(reader);
+        let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+        tree.index_zip(&mut archive)?;
+        Ok(Self::with_archive(tree, Archive::Zip(archive)))
+    }
+
+    fn with_archive(tree: Tree, archive: Archive) -> Self {
+        Self {
+            inner: Arc::new(ArchiveInner {
+                nodes: tree.nodes,
+                archive: Mutex::new(archive),
+                loaded: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+}
+
+impl ArchiveInner {
+    /// Walks `path` from the root and returns the absolute path of the
+    /// entry it designates. The symlinks in the middle of the path are
+    /// always followed, the last one only if `follow_last` is true.
+    fn resolve(&self, path: &Path, follow_last: bool) -> Result<(PathBuf, &Node)> {
+        // The components that are left to walk, in reverse order so that
+        // the target of a symlink can be spliced in front of them.
+        let mut pending: Vec<Component> = path.components().rev().collect();
+        let mut real_path = PathBuf::from("/");
+        let mut followed_symlinks = 0;
+
+        while let Some(component) = pending.pop() {
+            match component {
+                Component::RootDir => real_path = PathBuf::from("/"),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    real_path.pop();
+                }
+                Component::Normal(name) => {
+                    match self.nodes.get(&real_path).map(|node| &node.kind) {
+                        Some(NodeKind::Directory { .. }) => {}
+                        _ => return Err(FsError::BaseNotDirectory),
+                    }
+
+                    let next = real_path.join(name);
+                    match self.nodes.get(&next) {
+                        None => return Err(FsError::EntityNotFound),
+                        Some(Node {
+                            kind: NodeKind::Symlink(target),
+                            ..
+                        }) if follow_last || !pending.is_empty() => {
+                            followed_symlinks += 1;
+                            if followed_symlinks > MAX_SYMLINKS {
+                                return Err(FsError::SymlinkLoop);
+                            }
+                            pending.extend(target.components().rev());
+                        }
+                        Some(_) => real_path = next,
+                    }
+                }
+                Component::Prefix(_) => return Err(FsError::InvalidInput),
+            }
+        }
+
+        // SAFETY: only existing entries are pushed to `real_path`.
+        let node = self.nodes.get(&real_path).unwrap();
+        Ok((real_path, node))
+    }
+
+    /// Reads directly from an uncompressed archive, `None` is returned if
+    /// the content must be loaded with [`ArchiveInner::load`] instead.
+    fn read_at(&self, data: Data, offset: u64, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        let mut archive = self.archive.lock().map_err(|_| lock_error())?;
+        match (&mut *archive, data) {
+            (Archive::Tar(reader), Data::Tar { offset: start }) => {
+                reader.seek(SeekFrom::Start(start + offset))?;
+                reader.read(buf).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Decompresses the whole content of a file, or returns it if it was
+    /// already.
+    fn load(&self, data: Data, len: u64) -> io::Result<Arc<[u8]>> {
+        if let Some(content) = self.loaded.lock().map_err(|_| lock_error())?.get(&data) {
+            return Ok(content.clone());
+        }
+
+        let mut archive = self.archive.lock().map_err(|_| lock_error())?;
+        let mut content = Vec::with_capacity(len.try_into().unwrap_or(0));
+        match (&mut *archive, data) {
+            (Archive::Tar(reader), Data::Tar { offset }) => {
+                reader.seek(SeekFrom::Start(offset))?;
+                reader.take(len).read_to_end(&mut content)?;
+            }
+            (Archive::TarGz(stream), Data::Tar { offset }) => {
+                stream.read_exact_at(offset, len, &mut content)?;
+            }
+            (Archive::Zip(archive), Data::Zip { index }) => {
+                archive.by_index(index)?.read_to_end(&mut content)?;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "corrupted index",
+                ))
+            }
+        }
+
+        if (content.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let content: Arc<[u8]> = content.into();
+        self.loaded
+            .lock()
+            .map_err(|_| lock_error())?
+            .insert(data, content.clone());
+        Ok(content)
+    }
+}
+
+fn lock_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "failed to lock the archive")
+}
+
+impl FileSystem for ArchiveFileSystem {
+    fn read_dir(&self, path: &Path) -> Result<ReadDir> {
+        let (real_path, node) = self.inner.resolve(path, true)?;
+        let children = match &node.kind {
+            NodeKind::Directory { children } => children,
+            _ => return Err(FsError::BaseNotDirectory),
+        };
+
+        let entries = children
+            .iter()
+            .map(|name| DirEntry {
+                path: path.join(name),
+                metadata: self
+                    .inner
+                    .nodes
+                    .get(&real_path.join(name))
+                    .map(Node::metadata)
+                    .ok_or(FsError::EntityNotFound),
+            })
+            .collect();
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, _path: &Path) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn remove_dir(&self, _path: &Path) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        self.inner
+            .resolve(path, true)
+            .map(|(_, node)| node.metadata())
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+        self.inner
+            .resolve(path, false)
+            .map(|(_, node)| node.metadata())
+    }
+
+    fn remove_file(&self, _path: &Path) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn readlink(&self, path: &Path) -> Result<PathBuf> {
+        match self.inner.resolve(path, false)? {
+            (
+                _,
+                Node {
+                    kind: NodeKind::Symlink(target),
+                    ..
+                },
+            ) => Ok(target.clone()),
+            _ => Err(FsError::InvalidInput),
+        }
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(Box::new(ArchiveFileOpener {
+            filesystem: self.clone(),
+        }))
+    }
+}
+
+/// The type that is responsible to open a file of an [`ArchiveFileSystem`].
+#[derive(Debug, Clone)]
+pub struct ArchiveFileOpener {
+    filesystem: ArchiveFileSystem,
+}
+
+impl FileOpener for ArchiveFileOpener {
+    fn open(
+        &mut self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        if conf.write() || conf.append() || conf.truncate() || conf.create_new() {
+            return Err(FsError::PermissionDenied);
+        }
+
+        let node = match self.filesystem.inner.resolve(path, true) {
+            Ok((_, node)) => node,
+            Err(FsError::EntityNotFound) if conf.create() => return Err(FsError::PermissionDenied),
+            Err(error) => return Err(error),
+        };
+        let data = match node.kind {
+            NodeKind::File(data) => data,
+            _ => return Err(FsError::NotAFile),
+        };
+
+        Ok(Box::new(ArchiveFile {
+            filesystem: self.filesystem.clone(),
+            data,
+            len: node.len,
+            modified: node.modified,
+            content: None,
+            cursor: 0,
+        }))
+    }
+}
+
+/// A file of an [`ArchiveFileSystem`], opened for reading.
+#[derive(Debug)]
+pub struct ArchiveFile {
+    filesystem: ArchiveFileSystem,
+    data: Data,
+    len: u64,
+    modified: u64,
+    /// The decompressed content, loaded by the first read when the file
+    /// can't be read straight from the archive
+    content: Option<Arc<[u8]>>,
+    cursor: u64,
+}
+
+impl Read for ArchiveFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.cursor);
+        let max = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        if max == 0 {
+            return Ok(0);
+        }
+
+        if self.content.is_none() {
+            let inner = &self.filesystem.inner;
+            if let Some(read) = inner.read_at(self.data, self.cursor, &mut buf[..max])? {
+                self.cursor += read as u64;
+                return Ok(read);
+            }
+            self.content = Some(inner.load(self.data, self.len)?);
+        }
+
+        // SAFETY: the content was loaded above.
+        let content = self.content.as_ref().unwrap();
+        let start = self.cursor as usize;
+        buf[..max].copy_from_slice(&content[start..start + max]);
+        self.cursor += max as u64;
+        Ok(max)
+    }
+}
+
+impl Seek for ArchiveFile {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let cursor = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => checked_add_signed(self.len, offset),
+            SeekFrom::Current(offset) => checked_add_signed(self.cursor, offset),
+        };
+        self.cursor = cursor.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.cursor)
+    }
+}
+
+fn checked_add_signed(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
+
+impl Write for ArchiveFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the archive is read-only",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl VirtualFile for ArchiveFile {
+    fn last_accessed(&self) -> u64 {
+        self.modified
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.modified
+    }
+
+    fn created_time(&self) -> u64 {
+        self.modified
+    }
+
+    fn size(&self) -> u64 {
+        self.len
+    }
+
+    fn set_len(&mut self, _new_size: u64) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn bytes_available_read(&self) -> Result<Option<usize>> {
+        Ok(Some(
+            self.len
+                .saturating_sub(self.cursor)
+                .try_into()
+                .unwrap_or(usize::MAX),
+        ))
+    }
+
+    fn bytes_available_write(&self) -> Result<Option<usize>> {
+        Ok(Some(0))
+    }
+
+    fn get_fd(&self) -> Option<FileDescriptor> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test_archive_fs {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Cursor;
+    use zip::write::{FileOptions, ZipWriter};
+
+    macro_rules! path {
+        ($path:expr) => {
+            std::path::Path::new($path)
+        };
+    }
+
+    /// Builds a tar archive with `/etc/hosts`, a hard link to it, a symlink
+    /// to `/etc` and a nested file whose parent directories are implicit
+    fn tar() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_mtime(1_600_000_000);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "./etc/", io::empty())
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_mtime(1_600_000_000);
+        header.set_size(19);
+        builder
+            .append_data(&mut header, "./etc/hosts", &b"127.0.0.1 localhost"[..])
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "etc/hosts.bak", "etc/hosts")
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "config", "etc").unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(5);
+        builder
+            .append_data(&mut header, "usr/share/doc/README", &b"hello"[..])
+            .unwrap();
+
+        builder.into_inner().unwrap()
+    }
+
+    fn tar_gz() -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Builds a zip archive with the same entries as [`tar`], except the
+    /// hard link
+    fn zip() -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().last_modified_time(
+            zip::DateTime::from_date_and_time(2020, 9, 13, 12, 26, 40).unwrap(),
+        );
+
+        writer.add_directory("etc/", options).unwrap();
+        writer.start_file("etc/hosts", options).unwrap();
+        writer.write_all(b"127.0.0.1 localhost").unwrap();
+        writer.add_symlink("config", "etc", options).unwrap();
+        writer
+            .start_file(
+                "usr/share/doc/README",
+                options.compression_method(zip::CompressionMethod::Stored),
+            )
+            .unwrap();
+        writer.write_all(b"hello").unwrap();
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn read(fs: &dyn FileSystem, path: &str) -> Result<String> {
+        let mut data = String::new();
+        fs.new_open_options()
+            .read(true)
+            .open(path!(path))?
+            .read_to_string(&mut data)?;
+        Ok(data)
+    }
+
+    fn names(fs: &dyn FileSystem, path: &str) -> Vec<String> {
+        fs.read_dir(path!(path))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect()
+    }
+
+    /// The checks shared by every archive format
+    fn check(fs: &ArchiveFileSystem) {
+        assert_eq!(names(fs, "/"), vec!["config", "etc", "usr"]);
+        assert_eq!(names(fs, "/usr/share"), vec!["doc"]);
+
+        assert_eq!(
+            read(fs, "/etc/hosts"),
+            Ok("127.0.0.1 localhost".to_string())
+        );
+        assert_eq!(read(fs, "/usr/share/doc/README"), Ok("hello".to_string()));
+        assert_eq!(
+            read(fs, "/config/hosts"),
+            Ok("127.0.0.1 localhost".to_string()),
+            "symlinks are followed",
+        );
+        assert_eq!(read(fs, "/etc/nope"), Err(FsError::EntityNotFound));
+        assert_eq!(read(fs, "/etc"), Err(FsError::NotAFile));
+
+        let metadata = fs.metadata(path!("/etc/hosts")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len(), 19);
+        assert_eq!(metadata.modified(), 1_600_000_000_000_000_000);
+        assert!(fs.metadata(path!("/usr/share")).unwrap().is_dir());
+        assert!(fs.metadata(path!("/config")).unwrap().is_dir());
+        assert!(fs
+            .symlink_metadata(path!("/config"))
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs.readlink(path!("/config")), Ok(PathBuf::from("etc")));
+    }
+
+    #[test]
+    fn test_tar() {
+        let fs = ArchiveFileSystem::from_reader(Cursor::new(tar())).unwrap();
+        check(&fs);
+        assert_eq!(
+            read(&fs, "/etc/hosts.bak"),
+            Ok("127.0.0.1 localhost".to_string()),
+            "hard links share the content of their target",
+        );
+    }
+
+    #[test]
+    fn test_tar_gz() {
+        let fs = ArchiveFileSystem::from_reader(Cursor::new(tar_gz())).unwrap();
+        check(&fs);
+        assert_eq!(names(&fs, "/etc"), vec!["hosts", "hosts.bak"]);
+    }
+
+    /// A reader that counts the bytes read from it
+    struct CountingReader {
+        inner: Cursor<Vec<u8>>,
+        read: Arc<Mutex<usize>>,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.inner.read(buf)?;
+            *self.read.lock().unwrap() += read;
+            Ok(read)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    #[test]
+    fn test_tar_gz_is_decompressed_once() {
+        let archive = tar_gz();
+        let len = archive.len();
+        let consumed = Arc::new(Mutex::new(0));
+        let fs = ArchiveFileSystem::from_tar_gz(CountingReader {
+            inner: Cursor::new(archive),
+            read: consumed.clone(),
+        })
+        .unwrap();
+        *consumed.lock().unwrap() = 0;
+
+        for path in ["/etc/hosts", "/usr/share/doc/README"] {
+            let mut file = fs.new_open_options().read(true).open(path!(path)).unwrap();
+            let mut byte = [0];
+            while file.read(&mut byte).unwrap() != 0 {}
+        }
+        assert!(*consumed.lock().unwrap() <= len);
+
+        // The content of the files that were read is shared.
+        let before = *consumed.lock().unwrap();
+        assert_eq!(read(&fs, "/usr/share/doc/README"), Ok("hello".to_string()));
+        assert_eq!(
+            read(&fs, "/etc/hosts.bak"),
+            Ok("127.0.0.1 localhost".to_string())
+        );
+        assert_eq!(*consumed.lock().unwrap(), before);
+    }
+
+    #[test]
+    fn test_zip() {
+        let fs = ArchiveFileSystem::from_reader(Cursor::new(zip())).unwrap();
+        check(&fs);
+    }
+
+    #[test]
+    fn test_seek() {
+        let fs = ArchiveFileSystem::from_tar(Cursor::new(tar())).unwrap();
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .open(path!("/etc/hosts"))
+            .unwrap();
+
+        let mut data = String::new();
+        file.seek(SeekFrom::Start(10)).unwrap();
+        file.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "localhost");
+
+        data.clear();
+        file.seek(SeekFrom::End(-9)).unwrap();
+        assert_eq!(file.bytes_available_read(), Ok(Some(9)));
+        (&mut file).take(5).read_to_string(&mut data).unwrap();
+        assert_eq!(data, "local");
+        assert!(file.seek(SeekFrom::Current(-100)).is_err());
+    }
+
+    #[test]
+    fn test_read_only() {
+        let fs = ArchiveFileSystem::from_zip(Cursor::new(zip())).unwrap();
+
+        assert_eq!(fs.create_dir(path!("/tmp")), Err(FsError::PermissionDenied));
+        assert_eq!(
+            fs.remove_file(path!("/etc/hosts")),
+            Err(FsError::PermissionDenied)
+        );
+        assert_eq!(
+            fs.rename(path!("/etc"), path!("/config2")),
+            Err(FsError::PermissionDenied)
+        );
+        let open = |write, append, create| {
+            fs.new_open_options()
+                .write(write)
+                .append(append)
+                .create(create)
+                .open(path!("/etc/new"))
+                .map(|_| ())
+        };
+        assert_eq!(open(true, false, false), Err(FsError::PermissionDenied));
+        assert_eq!(open(false, true, false), Err(FsError::PermissionDenied));
+        assert_eq!(open(false, false, true), Err(FsError::PermissionDenied));
+
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .open(path!("/etc/hosts"))
+            .unwrap();
+        assert!(file.write(b"nope").is_err());
+        assert_eq!(file.set_len(0), Err(FsError::PermissionDenied));
+    }
+}
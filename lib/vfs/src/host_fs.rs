@@ -113,6 +113,42 @@ impl crate::FileSystem for FileSystem {
             .and_then(TryInto::try_into)
             .map_err(Into::into)
     }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+        fs::symlink_metadata(path)
+            .and_then(TryInto::try_into)
+            .map_err(Into::into)
+    }
+
+    fn symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(original, link).map_err(Into::into)
+        }
+        #[cfg(windows)]
+        {
+            // Windows needs to know upfront whether the link points to a
+            // directory, relative links are resolved from the link's parent.
+            let target = match link.parent() {
+                Some(parent) if original.is_relative() => parent.join(original),
+                _ => original.to_path_buf(),
+            };
+            if target.is_dir() {
+                std::os::windows::fs::symlink_dir(original, link).map_err(Into::into)
+            } else {
+                std::os::windows::fs::symlink_file(original, link).map_err(Into::into)
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = (original, link);
+            Err(FsError::PermissionDenied)
+        }
+    }
+
+    fn readlink(&self, path: &Path) -> Result<PathBuf> {
+        fs::read_link(path).map_err(Into::into)
+    }
 }
 
 impl TryInto<Metadata> for fs::Metadata {
@@ -446,6 +482,33 @@ fn host_file_bytes_available(_host_fd: FileDescriptor) -> Result<usize> {
     unimplemented!("host_file_bytes_available not yet implemented for non-Unix-like targets.  This probably means the program tried to use wasi::poll_oneoff")
 }
 
+/// Returns how many bytes can be read from `host_fd` without blocking, or
+/// `None` if a read would not block but the amount is unknown (which is
+/// the case at the end of the input)
+#[cfg(unix)]
+fn host_file_bytes_available_read(host_fd: FileDescriptor) -> Result<Option<usize>> {
+    let mut fds = libc::pollfd {
+        fd: host_fd.try_into()?,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let result = unsafe { libc::poll(&mut fds, 1, 0) };
+
+    match result {
+        0 => Ok(Some(0)),
+        1 => match host_file_bytes_available(FileDescriptor::from(fds.fd as u32)) {
+            Ok(0) | Err(_) => Ok(None),
+            Ok(bytes_found) => Ok(Some(bytes_found)),
+        },
+        _ => Err(FsError::IOError),
+    }
+}
+
+#[cfg(not(unix))]
+fn host_file_bytes_available_read(_host_fd: FileDescriptor) -> Result<Option<usize>> {
+    Ok(None)
+}
+
 /// A wrapper type around Stdout that implements `VirtualFile` and
 /// `Serialize` + `Deserialize`.
 #[derive(Debug, Default)]
@@ -728,6 +791,10 @@ impl VirtualFile for Stdin {
         host_file_bytes_available(io::stdin().try_into_filedescriptor()?)
     }
 
+    fn bytes_available_read(&self) -> Result<Option<usize>> {
+        host_file_bytes_available_read(io::stdin().try_into_filedescriptor()?)
+    }
+
     fn get_fd(&self) -> Option<FileDescriptor> {
         io::stdin().try_into_filedescriptor().ok()
     }
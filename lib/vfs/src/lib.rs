@@ -11,10 +11,16 @@ compile_error!("At least the `host-fs` or the `mem-fs` feature must be enabled.
 #[cfg(all(feature = "mem-fs", feature = "enable-serde"))]
 compile_error!("`mem-fs` does not support `enable-serde` for the moment.");
 
+#[cfg(all(feature = "archive-fs", feature = "enable-serde"))]
+compile_error!("`archive-fs` does not support `enable-serde` for the moment.");
+
+#[cfg(feature = "archive-fs")]
+pub mod archive_fs;
 #[cfg(feature = "host-fs")]
 pub mod host_fs;
 #[cfg(feature = "mem-fs")]
 pub mod mem_fs;
+pub mod overlay_fs;
 
 pub type Result<T> = std::result::Result<T, FsError>;
 
@@ -40,13 +46,28 @@ pub trait FileSystem: fmt::Debug + Send + Sync + 'static + Upcastable {
     fn remove_dir(&self, path: &Path) -> Result<()>;
     fn rename(&self, from: &Path, to: &Path) -> Result<()>;
     fn metadata(&self, path: &Path) -> Result<Metadata>;
-    /// This method gets metadata without following symlinks in the path.
-    /// Currently identical to `metadata` because symlinks aren't implemented
-    /// yet.
+    /// This method gets metadata without following a symlink at the end of
+    /// the path. File systems that don't support symlinks can rely on the
+    /// default implementation, which is identical to `metadata`.
     fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
         self.metadata(path)
     }
     fn remove_file(&self, path: &Path) -> Result<()>;
+    /// Creates a new symlink at `link` whose content is `original`.
+    ///
+    /// `original` is stored as is, it is not required to exist. By default
+    /// symlinks are not supported and `FsError::PermissionDenied` is
+    /// returned.
+    fn symlink(&self, _original: &Path, _link: &Path) -> Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+    /// Reads the content of the symlink at `path`.
+    ///
+    /// `FsError::InvalidInput` is returned if `path` is not a symlink, which
+    /// is always the case for file systems that don't support symlinks.
+    fn readlink(&self, _path: &Path) -> Result<PathBuf> {
+        Err(FsError::InvalidInput)
+    }
 
     fn new_open_options(&self) -> OpenOptions;
 }
@@ -342,6 +363,9 @@ pub enum FsError {
     /// Directory not Empty
     #[error("directory not empty")]
     DirectoryNotEmpty,
+    /// Too many symlinks were encountered while resolving a path
+    #[error("too many levels of symbolic links")]
+    SymlinkLoop,
     /// Some other unhandled error. If you see this, it's probably a bug.
     #[error("unknown error found")]
     UnknownError,
@@ -865,6 +865,14 @@ impl File {
     pub(super) fn len(&self) -> usize {
         self.buffer.len()
     }
+
+    pub(super) fn from_bytes(buffer: Vec<u8>) -> Self {
+        Self { buffer, cursor: 0 }
+    }
+
+    pub(super) fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
 }
 
 impl Read for File {
@@ -47,6 +47,15 @@ impl crate::FileOpener for FileOpener {
                 .try_read()
                 .map_err(|_| FsError::Lock)?;
 
+            // Open the file a symlink points to, or create it if the
+            // symlink is dangling. When a new file must be created, the
+            // symlink itself counts as an existing file.
+            let path = if create_new {
+                path.to_path_buf()
+            } else {
+                fs.follow_symlinks(path)?
+            };
+
             // Check the path has a parent.
             let parent_of_path = path.parent().ok_or(FsError::BaseNotDirectory)?;
 
@@ -236,6 +236,109 @@ impl crate::FileSystem for FileSystem {
             .clone())
     }
 
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+        // Read lock.
+        let fs = self.inner.try_read().map_err(|_| FsError::Lock)?;
+
+        // Canonicalize the path, without following a symlink at the end.
+        let path = fs.canonicalize_without_inode(path)?;
+
+        Ok(fs
+            .storage
+            .get(fs.inode_of_nofollow(&path)?)
+            .ok_or(FsError::UnknownError)?
+            .metadata()
+            .clone())
+    }
+
+    fn symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        // An empty symlink can't be resolved to anything.
+        if original.as_os_str().is_empty() {
+            return Err(FsError::EntityNotFound);
+        }
+
+        let (inode_of_parent, name_of_link) = {
+            // Read lock.
+            let fs = self.inner.try_read().map_err(|_| FsError::Lock)?;
+
+            // Canonicalize the path without checking the path exists,
+            // because it's about to be created.
+            let path = fs.canonicalize_without_inode(link)?;
+
+            // Check the path has a parent.
+            let parent_of_path = path.parent().ok_or(FsError::BaseNotDirectory)?;
+
+            // Check the symlink name.
+            let name_of_link = path
+                .file_name()
+                .ok_or(FsError::InvalidInput)?
+                .to_os_string();
+
+            // Find the parent inode.
+            let inode_of_parent = fs.inode_of_parent(parent_of_path)?;
+
+            // Check nothing exists with the same name.
+            if fs
+                .from_parent_get_position_and_inode(inode_of_parent, &name_of_link)?
+                .is_some()
+            {
+                return Err(FsError::AlreadyExists);
+            }
+
+            (inode_of_parent, name_of_link)
+        };
+
+        {
+            // Write lock.
+            let mut fs = self.inner.try_write().map_err(|_| FsError::Lock)?;
+
+            // Creating the symlink in the storage.
+            let inode_of_link = fs.storage.vacant_entry().key();
+            let real_inode_of_link = fs.storage.insert(Node::Symlink {
+                inode: inode_of_link,
+                name: name_of_link,
+                target: original.to_path_buf(),
+                metadata: {
+                    let time = time();
+
+                    Metadata {
+                        ft: FileType {
+                            symlink: true,
+                            ..Default::default()
+                        },
+                        accessed: time,
+                        created: time,
+                        modified: time,
+                        len: original.as_os_str().len() as u64,
+                    }
+                },
+            });
+
+            assert_eq!(
+                inode_of_link, real_inode_of_link,
+                "new symlink inode should have been correctly calculated",
+            );
+
+            // Adding the new symlink to its parent.
+            fs.add_child_to_node(inode_of_parent, inode_of_link)?;
+        }
+
+        Ok(())
+    }
+
+    fn readlink(&self, path: &Path) -> Result<PathBuf> {
+        // Read lock.
+        let fs = self.inner.try_read().map_err(|_| FsError::Lock)?;
+
+        // Canonicalize the path, without following the symlink itself.
+        let path = fs.canonicalize_without_inode(path)?;
+
+        match fs.storage.get(fs.inode_of_nofollow(&path)?) {
+            Some(Node::Symlink { target, .. }) => Ok(target.clone()),
+            _ => Err(FsError::InvalidInput),
+        }
+    }
+
     fn remove_file(&self, path: &Path) -> Result<()> {
         let (inode_of_parent, position, inode_of_file) = {
             // Read lock.
@@ -302,10 +405,25 @@ pub(super) struct FileSystemInner {
 }
 
 impl FileSystemInner {
-    /// Get the inode associated to a path if it exists.
+    /// Get the inode associated to a path if it exists. Symlinks are
+    /// followed, including the one at the end of the path.
     pub(super) fn inode_of(&self, path: &Path) -> Result<Inode> {
-        // SAFETY: The root node always exists, so it's safe to unwrap here.
-        let mut node = self.storage.get(ROOT_INODE).unwrap();
+        self.resolve(path, true).map(|(_, inode)| inode)
+    }
+
+    /// Like `Self::inode_of` but a symlink at the end of the path is
+    /// not followed, i.e. the inode of the symlink itself is returned.
+    pub(super) fn inode_of_nofollow(&self, path: &Path) -> Result<Inode> {
+        self.resolve(path, false).map(|(_, inode)| inode)
+    }
+
+    /// Walk `path` from the root, component by component, and return
+    /// the real path of the node it designates along with its inode.
+    ///
+    /// The symlinks met in the middle of the path are always followed;
+    /// the last one only if `follow_last` is true. `FsError::SymlinkLoop`
+    /// is returned when more than `MAX_SYMLINKS` symlinks are followed.
+    fn resolve<'a>(&'a self, path: &'a Path, follow_last: bool) -> Result<(PathBuf, Inode)> {
         let mut components = path.components();
 
         match components.next() {
@@ -313,24 +431,100 @@ impl FileSystemInner {
             _ => return Err(FsError::BaseNotDirectory),
         }
 
-        for component in components {
-            node = match node {
-                Node::Directory { children, .. } => children
-                    .iter()
-                    .filter_map(|inode| self.storage.get(*inode))
-                    .find_map(|node| {
-                        if node.name() == component.as_os_str() {
-                            Some(node)
-                        } else {
-                            None
+        // The components that are left to walk, in reverse order so
+        // that the target of a symlink can be spliced in front of them.
+        let mut pending: Vec<Component<'a>> = components.rev().collect();
+        // The inodes from the root to the current node.
+        let mut ancestors = vec![ROOT_INODE];
+        let mut real_path = PathBuf::from("/");
+        let mut followed_symlinks = 0;
+
+        while let Some(component) = pending.pop() {
+            match component {
+                Component::RootDir => {
+                    ancestors.truncate(1);
+                    real_path = PathBuf::from("/");
+                }
+
+                Component::CurDir => (),
+
+                // The parent of the root is the root itself.
+                Component::ParentDir => {
+                    if ancestors.len() > 1 {
+                        ancestors.pop();
+                        real_path.pop();
+                    }
+                }
+
+                Component::Normal(name) => {
+                    // SAFETY: `ancestors` always contains the root.
+                    let node = match self.storage.get(*ancestors.last().unwrap()) {
+                        Some(Node::Directory { children, .. }) => children
+                            .iter()
+                            .filter_map(|inode| self.storage.get(*inode))
+                            .find(|node| node.name() == name)
+                            .ok_or(FsError::NotAFile)?,
+                        _ => return Err(FsError::BaseNotDirectory),
+                    };
+
+                    match node {
+                        Node::Symlink { target, .. } if follow_last || !pending.is_empty() => {
+                            followed_symlinks += 1;
+
+                            if followed_symlinks > MAX_SYMLINKS {
+                                return Err(FsError::SymlinkLoop);
+                            }
+
+                            // A relative target is walked from the
+                            // directory containing the symlink, an
+                            // absolute one restarts from the root.
+                            pending.extend(target.components().rev());
                         }
-                    })
-                    .ok_or(FsError::NotAFile)?,
-                _ => return Err(FsError::BaseNotDirectory),
-            };
+
+                        _ => {
+                            ancestors.push(node.inode());
+                            real_path.push(name);
+                        }
+                    }
+                }
+
+                // We don't support Windows path prefix.
+                Component::Prefix(_) => return Err(FsError::InvalidInput),
+            }
         }
 
-        Ok(node.inode())
+        // SAFETY: `ancestors` always contains the root.
+        Ok((real_path, *ancestors.last().unwrap()))
+    }
+
+    /// Follow the symlinks at the end of `path` until it designates
+    /// something that is not a symlink, and return that path. The
+    /// returned path doesn't necessarily exist, e.g. when `path` is a
+    /// dangling symlink.
+    pub(super) fn follow_symlinks(&self, path: &Path) -> Result<PathBuf> {
+        let mut path = self.canonicalize_without_inode(path)?;
+
+        for _ in 0..MAX_SYMLINKS {
+            let parent_of_path = path.parent().ok_or(FsError::BaseNotDirectory)?;
+            let name_of_path = path
+                .file_name()
+                .ok_or(FsError::InvalidInput)?
+                .to_os_string();
+            let (real_parent_of_path, inode_of_parent) = self.resolve(parent_of_path, true)?;
+
+            let target =
+                match self.from_parent_get_position_and_inode(inode_of_parent, &name_of_path)? {
+                    Some((_, inode)) => match self.storage.get(inode) {
+                        Some(Node::Symlink { target, .. }) => target,
+                        _ => return Ok(path),
+                    },
+                    None => return Ok(path),
+                };
+
+            path = self.canonicalize_without_inode(&real_parent_of_path.join(target))?;
+        }
+
+        Err(FsError::SymlinkLoop)
     }
 
     /// Get the inode associated to a “parent path”. The returned
@@ -381,7 +575,8 @@ impl FileSystemInner {
     }
 
     /// From the inode of a parent node (so, a directory), returns the
-    /// child index of `name_of_file` along with its inode.
+    /// child index of `name_of_file` along with its inode. Symlinks are
+    /// considered as files here, they are not followed.
     pub(super) fn from_parent_get_position_and_inode_of_file(
         &self,
         inode_of_parent: Inode,
@@ -393,7 +588,9 @@ impl FileSystemInner {
                 .enumerate()
                 .filter_map(|(nth, inode)| self.storage.get(*inode).map(|node| (nth, node)))
                 .find_map(|(nth, node)| match node {
-                    Node::File { inode, name, .. } if name.as_os_str() == name_of_file => {
+                    Node::File { inode, name, .. } | Node::Symlink { inode, name, .. }
+                        if name.as_os_str() == name_of_file =>
+                    {
                         Some(Some((nth, *inode)))
                     }
 
@@ -408,7 +605,7 @@ impl FileSystemInner {
 
     /// From the inode of a parent node (so, a directory), returns the
     /// child index of `name_of` along with its inode, whatever the
-    /// type of inode is (directory or file).
+    /// type of inode is (directory, file or symlink).
     fn from_parent_get_position_and_inode(
         &self,
         inode_of_parent: Inode,
@@ -420,7 +617,9 @@ impl FileSystemInner {
                 .enumerate()
                 .filter_map(|(nth, inode)| self.storage.get(*inode).map(|node| (nth, node)))
                 .find_map(|(nth, node)| match node {
-                    Node::File { inode, name, .. } | Node::Directory { inode, name, .. }
+                    Node::File { inode, name, .. }
+                    | Node::Directory { inode, name, .. }
+                    | Node::Symlink { inode, name, .. }
                         if name.as_os_str() == name_of =>
                     {
                         Some(Some((nth, *inode)))
@@ -574,6 +773,7 @@ impl fmt::Debug for FileSystemInner {
                     ty = match node {
                         Node::File { .. } => "file",
                         Node::Directory { .. } => "dir",
+                        Node::Symlink { .. } => "link",
                     },
                     name = node.name().to_string_lossy(),
                     indentation_symbol = " ",
@@ -1361,6 +1561,205 @@ mod test_filesystem {
             "canonicalizing a crazily stupid path name",
         );
     }
+
+    #[test]
+    fn test_symlink() {
+        let fs = FileSystem::default();
+
+        assert_eq!(fs.create_dir(path!("/foo")), Ok(()));
+        assert_eq!(
+            fs.symlink(path!("foo"), path!("/bar")),
+            Ok(()),
+            "creating a relative symlink",
+        );
+        assert_eq!(
+            fs.symlink(path!("/foo"), path!("/bar")),
+            Err(FsError::AlreadyExists),
+            "creating a symlink over an existing node",
+        );
+        assert_eq!(
+            fs.symlink(path!(""), path!("/baz")),
+            Err(FsError::EntityNotFound),
+            "creating an empty symlink",
+        );
+
+        {
+            let fs_inner = fs.inner.read().unwrap();
+            assert!(
+                matches!(
+                    fs_inner.storage.get(2),
+                    Some(Node::Symlink {
+                        inode: 2,
+                        name,
+                        target,
+                        ..
+                    }) if name == "bar" && target == path!("foo"),
+                ),
+                "`bar` exists and is a symlink",
+            );
+        }
+
+        assert_eq!(fs.readlink(path!("/bar")), Ok(path!(buf "foo")));
+        assert_eq!(
+            fs.readlink(path!("/foo")),
+            Err(FsError::InvalidInput),
+            "reading a link that is not a symlink",
+        );
+
+        assert!(
+            matches!(
+                fs.metadata(path!("/bar")),
+                Ok(Metadata {
+                    ft: FileType {
+                        dir: true,
+                        symlink: false,
+                        ..
+                    },
+                    ..
+                }),
+            ),
+            "`metadata` follows the symlink",
+        );
+        assert!(
+            matches!(
+                fs.symlink_metadata(path!("/bar")),
+                Ok(Metadata {
+                    ft: FileType {
+                        dir: false,
+                        symlink: true,
+                        ..
+                    },
+                    len: 3,
+                    ..
+                }),
+            ),
+            "`symlink_metadata` doesn't follow the symlink",
+        );
+
+        assert_eq!(
+            fs.create_dir(path!("/bar/qux")),
+            Ok(()),
+            "creating a directory through a symlink",
+        );
+        assert!(fs.metadata(path!("/foo/qux")).unwrap().is_dir());
+
+        assert_eq!(
+            fs.remove_file(path!("/bar")),
+            Ok(()),
+            "removing the symlink"
+        );
+        assert!(
+            fs.metadata(path!("/foo/qux")).is_ok(),
+            "the target of the symlink is untouched",
+        );
+        assert_eq!(fs.metadata(path!("/bar")).err(), Some(FsError::NotAFile));
+    }
+
+    #[test]
+    fn test_symlink_resolution() {
+        let fs = FileSystem::default();
+
+        assert_eq!(fs.create_dir(path!("/foo")), Ok(()));
+        assert_eq!(fs.create_dir(path!("/foo/bar")), Ok(()));
+        assert_eq!(fs.symlink(path!("/foo/bar"), path!("/abs")), Ok(()));
+        assert_eq!(fs.symlink(path!("../foo/./bar"), path!("/foo/rel")), Ok(()));
+        assert_eq!(fs.symlink(path!("rel"), path!("/foo/chain")), Ok(()));
+        assert_eq!(fs.symlink(path!("../../../foo"), path!("/foo/up")), Ok(()));
+
+        {
+            let fs_inner = fs.inner.read().unwrap();
+            let inode_of_bar = fs_inner.inode_of(path!("/foo/bar")).unwrap();
+
+            assert_eq!(fs_inner.inode_of(path!("/abs")), Ok(inode_of_bar));
+            assert_eq!(fs_inner.inode_of(path!("/foo/rel")), Ok(inode_of_bar));
+            assert_eq!(
+                fs_inner.inode_of(path!("/foo/chain")),
+                Ok(inode_of_bar),
+                "following a symlink to a symlink",
+            );
+            assert_eq!(
+                fs_inner.inode_of(path!("/foo/up/bar")),
+                Ok(inode_of_bar),
+                "the parent of the root is the root",
+            );
+            assert_ne!(
+                fs_inner.inode_of_nofollow(path!("/foo/chain")),
+                Ok(inode_of_bar),
+                "the last symlink is not followed",
+            );
+            assert_eq!(
+                fs_inner.inode_of_nofollow(path!("/foo/chain/../bar")),
+                Ok(inode_of_bar),
+                "a symlink in the middle of a path is followed",
+            );
+        }
+
+        assert_eq!(fs.symlink(path!("loop2"), path!("/loop1")), Ok(()));
+        assert_eq!(fs.symlink(path!("loop1"), path!("/loop2")), Ok(()));
+        assert_eq!(
+            fs.metadata(path!("/loop1")).err(),
+            Some(FsError::SymlinkLoop),
+            "detecting symlink loops",
+        );
+        assert!(
+            fs.symlink_metadata(path!("/loop1")).is_ok(),
+            "a symlink loop can be inspected",
+        );
+        assert_eq!(
+            fs.new_open_options().read(true).open(path!("/loop1")).err(),
+            Some(FsError::SymlinkLoop),
+            "opening a symlink loop",
+        );
+    }
+
+    #[test]
+    fn test_open_through_symlink() {
+        use std::io::{Read, Write};
+
+        let fs = FileSystem::default();
+
+        assert_eq!(fs.create_dir(path!("/foo")), Ok(()));
+        assert_eq!(fs.symlink(path!("foo/hello.txt"), path!("/link")), Ok(()));
+
+        assert_eq!(
+            fs.new_open_options().read(true).open(path!("/link")).err(),
+            Some(FsError::PermissionDenied),
+            "opening a dangling symlink without creating",
+        );
+
+        {
+            let mut file = fs
+                .new_open_options()
+                .write(true)
+                .create(true)
+                .open(path!("/link"))
+                .expect("failed to create the target of the symlink");
+            file.write_all(b"hello").unwrap();
+        }
+
+        assert!(
+            fs.metadata(path!("/foo/hello.txt")).unwrap().is_file(),
+            "the target of a dangling symlink is created",
+        );
+        assert_eq!(
+            fs.new_open_options()
+                .write(true)
+                .create_new(true)
+                .open(path!("/link"))
+                .err(),
+            Some(FsError::AlreadyExists),
+            "a symlink counts as an existing file for `create_new`",
+        );
+
+        let mut content = String::new();
+        fs.new_open_options()
+            .read(true)
+            .open(path!("/link"))
+            .expect("failed to open the symlink")
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "hello");
+    }
 }
 
 #[allow(dead_code)] // The `No` variant.
@@ -1,6 +1,7 @@
 mod file;
 mod file_opener;
 mod filesystem;
+mod snapshot;
 mod stdio;
 
 use file::{File, FileHandle};
@@ -10,10 +11,15 @@ pub use stdio::{Stderr, Stdin, Stdout};
 
 use crate::Metadata;
 use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
 
 type Inode = usize;
 const ROOT_INODE: Inode = 0;
 
+/// The maximum number of symlinks that are followed while resolving a
+/// single path, mirroring `MAXSYMLINKS` on Linux.
+const MAX_SYMLINKS: usize = 40;
+
 #[derive(Debug)]
 enum Node {
     File {
@@ -28,6 +34,12 @@ enum Node {
         children: Vec<Inode>,
         metadata: Metadata,
     },
+    Symlink {
+        inode: Inode,
+        name: OsString,
+        target: PathBuf,
+        metadata: Metadata,
+    },
 }
 
 impl Node {
@@ -35,6 +47,7 @@ impl Node {
         *match self {
             Self::File { inode, .. } => inode,
             Self::Directory { inode, .. } => inode,
+            Self::Symlink { inode, .. } => inode,
         }
     }
 
@@ -42,6 +55,7 @@ impl Node {
         match self {
             Self::File { name, .. } => name.as_os_str(),
             Self::Directory { name, .. } => name.as_os_str(),
+            Self::Symlink { name, .. } => name.as_os_str(),
         }
     }
 
@@ -49,6 +63,7 @@ impl Node {
         match self {
             Self::File { metadata, .. } => metadata,
             Self::Directory { metadata, .. } => metadata,
+            Self::Symlink { metadata, .. } => metadata,
         }
     }
 
@@ -56,6 +71,7 @@ impl Node {
         match self {
             Self::File { metadata, .. } => metadata,
             Self::Directory { metadata, .. } => metadata,
+            Self::Symlink { metadata, .. } => metadata,
         }
     }
 
@@ -63,6 +79,7 @@ impl Node {
         match self {
             Self::File { name, .. } => *name = new_name,
             Self::Directory { name, .. } => *name = new_name,
+            Self::Symlink { name, .. } => *name = new_name,
         }
     }
 }
@@ -0,0 +1,432 @@
+//! Serialization of a whole [`FileSystem`] into a byte blob, and back.
+//!
+//! The blob starts with [`MAGIC`] and a format version, followed by the
+//! nodes of the tree in depth-first order. Every node is made of:
+//!
+//! * its kind, as a `u8`: `0` for a file, `1` for a directory and `2` for
+//!   a symlink,
+//! * its name, as a `u32` length followed by the bytes of the name,
+//! * its access, creation and modification times, as three `u64`,
+//! * for a file, its content as a `u64` length followed by the bytes,
+//! * for a directory, its number of children as a `u32`, the children
+//!   being the nodes that follow,
+//! * for a symlink, its target as a `u32` length followed by the bytes.
+//!
+//! All the integers are little-endian.
+
+use super::*;
+use crate::{FileType, FsError, Result};
+use filesystem::FileSystemInner;
+use slab::Slab;
+use std::convert::TryInto;
+use std::sync::{Arc, RwLock};
+
+/// The first bytes of a snapshot.
+const MAGIC: &[u8; 8] = b"wmemfs\0\0";
+/// The version of the format described in the module documentation.
+const VERSION: u32 = 1;
+
+const KIND_FILE: u8 = 0;
+const KIND_DIRECTORY: u8 = 1;
+const KIND_SYMLINK: u8 = 2;
+
+impl FileSystem {
+    /// Serializes the whole tree, i.e. the directories, the files with
+    /// their content, the symlinks and their timestamps, into a byte blob
+    /// that [`FileSystem::from_snapshot`] turns back into a file system.
+    ///
+    /// Restoring a snapshot is cheap compared to creating the same tree
+    /// file by file, and every restored file system is independent from
+    /// the others, which makes it possible to populate a file system once
+    /// and to hand a fresh copy of it to many instances.
+    pub fn snapshot(&self) -> Result<Vec<u8>> {
+        // Read lock.
+        let fs = self.inner.try_read().map_err(|_| FsError::Lock)?;
+
+        let mut snapshot = MAGIC.to_vec();
+        snapshot.extend_from_slice(&VERSION.to_le_bytes());
+
+        // The nodes left to write, the last one is written next.
+        let mut pending = vec![ROOT_INODE];
+
+        while let Some(inode) = pending.pop() {
+            let node = fs.storage.get(inode).ok_or(FsError::UnknownError)?;
+            let kind = match node {
+                Node::File { .. } => KIND_FILE,
+                Node::Directory { .. } => KIND_DIRECTORY,
+                Node::Symlink { .. } => KIND_SYMLINK,
+            };
+            snapshot.push(kind);
+            write_bytes_u32(&mut snapshot, os_str_as_bytes(node.name())?)?;
+
+            let metadata = node.metadata();
+            for time in [metadata.accessed, metadata.created, metadata.modified] {
+                snapshot.extend_from_slice(&time.to_le_bytes());
+            }
+
+            match node {
+                Node::File { file, .. } => {
+                    let content = file.as_bytes();
+                    snapshot.extend_from_slice(&(content.len() as u64).to_le_bytes());
+                    snapshot.extend_from_slice(content);
+                }
+
+                Node::Directory { children, .. } => {
+                    let len: u32 = children
+                        .len()
+                        .try_into()
+                        .map_err(|_| FsError::InvalidData)?;
+                    snapshot.extend_from_slice(&len.to_le_bytes());
+                    pending.extend(children.iter().rev());
+                }
+
+                Node::Symlink { target, .. } => {
+                    write_bytes_u32(&mut snapshot, os_str_as_bytes(target.as_os_str())?)?;
+                }
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Rebuilds a file system from a snapshot made by
+    /// [`FileSystem::snapshot`].
+    ///
+    /// `FsError::InvalidData` is returned if `snapshot` is not a valid
+    /// snapshot.
+    pub fn from_snapshot(snapshot: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes: snapshot };
+
+        if reader.take(MAGIC.len())? != MAGIC || reader.u32()? != VERSION {
+            return Err(FsError::InvalidData);
+        }
+
+        let mut storage = Slab::new();
+
+        // The root is the first node, and it must be a directory.
+        let (root, number_of_children) = reader.node(ROOT_INODE)?;
+        if !matches!(root, Node::Directory { .. }) {
+            return Err(FsError::InvalidData);
+        }
+        storage.insert(root);
+
+        // The directories whose children are being read, along with the
+        // number of children that are left to read.
+        let mut parents = vec![(ROOT_INODE, number_of_children)];
+
+        while let Some((inode_of_parent, remaining)) = parents.last_mut() {
+            if *remaining == 0 {
+                parents.pop();
+                continue;
+            }
+            *remaining -= 1;
+            let inode_of_parent = *inode_of_parent;
+
+            let inode = storage.vacant_entry().key();
+            let (node, number_of_children) = reader.node(inode)?;
+            if node.name().is_empty()
+                || node.name() == "."
+                || node.name() == ".."
+                || os_str_as_bytes(node.name())?.contains(&b'/')
+            {
+                return Err(FsError::InvalidData);
+            }
+            storage.insert(node);
+
+            match storage.get_mut(inode_of_parent) {
+                Some(Node::Directory { children, .. }) => children.push(inode),
+                _ => return Err(FsError::InvalidData),
+            }
+
+            if number_of_children > 0 {
+                parents.push((inode, number_of_children));
+            }
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(FsError::InvalidData);
+        }
+
+        Ok(Self {
+            inner: Arc::new(RwLock::new(FileSystemInner { storage })),
+        })
+    }
+}
+
+fn write_bytes_u32(snapshot: &mut Vec<u8>, bytes: &[u8]) -> Result<()> {
+    let len: u32 = bytes.len().try_into().map_err(|_| FsError::InvalidData)?;
+    snapshot.extend_from_slice(&len.to_le_bytes());
+    snapshot.extend_from_slice(bytes);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn os_str_as_bytes(os_str: &OsStr) -> Result<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Ok(os_str.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_str_as_bytes(os_str: &OsStr) -> Result<&[u8]> {
+    os_str
+        .to_str()
+        .map(str::as_bytes)
+        .ok_or(FsError::InvalidInput)
+}
+
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: &[u8]) -> Result<OsString> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Ok(OsStr::from_bytes(bytes).to_os_string())
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: &[u8]) -> Result<OsString> {
+    std::str::from_utf8(bytes)
+        .map(OsString::from)
+        .map_err(|_| FsError::InvalidData)
+}
+
+/// Reads the content of a snapshot.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(FsError::InvalidData);
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        // SAFETY: `take` returns exactly 4 bytes.
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        // SAFETY: `take` returns exactly 8 bytes.
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes_u32(&mut self) -> Result<&'a [u8]> {
+        let len = self.u32()?;
+        self.take(len.try_into().map_err(|_| FsError::InvalidData)?)
+    }
+
+    /// Reads the next node, which gets the inode `inode`. The number of
+    /// children of a directory is returned along with the node, the
+    /// children themselves are not read.
+    fn node(&mut self, inode: Inode) -> Result<(Node, u32)> {
+        let kind = self.take(1)?[0];
+        let name = os_string_from_bytes(self.bytes_u32()?)?;
+        let (accessed, created, modified) = (self.u64()?, self.u64()?, self.u64()?);
+        let metadata = |ft, len| Metadata {
+            ft,
+            accessed,
+            created,
+            modified,
+            len,
+        };
+
+        Ok(match kind {
+            KIND_FILE => {
+                let len = self.u64()?;
+                let content = self.take(len.try_into().map_err(|_| FsError::InvalidData)?)?;
+                let file_type = FileType {
+                    file: true,
+                    ..Default::default()
+                };
+
+                let node = Node::File {
+                    inode,
+                    name,
+                    file: File::from_bytes(content.to_vec()),
+                    metadata: metadata(file_type, len),
+                };
+                (node, 0)
+            }
+
+            KIND_DIRECTORY => {
+                let number_of_children = self.u32()?;
+                let file_type = FileType {
+                    dir: true,
+                    ..Default::default()
+                };
+
+                let node = Node::Directory {
+                    inode,
+                    name,
+                    children: Vec::new(),
+                    metadata: metadata(file_type, 0),
+                };
+                (node, number_of_children)
+            }
+
+            KIND_SYMLINK => {
+                let target = PathBuf::from(os_string_from_bytes(self.bytes_u32()?)?);
+                let file_type = FileType {
+                    symlink: true,
+                    ..Default::default()
+                };
+
+                let node = Node::Symlink {
+                    inode,
+                    name,
+                    metadata: metadata(file_type, target.as_os_str().len() as u64),
+                    target,
+                };
+                (node, 0)
+            }
+
+            _ => return Err(FsError::InvalidData),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_snapshot {
+    use crate::{mem_fs::*, FileSystem as FS, FsError};
+    use std::io::{Read, Write};
+
+    macro_rules! path {
+        ($path:expr) => {
+            std::path::Path::new($path)
+        };
+    }
+
+    fn read(fs: &FileSystem, path: &str) -> String {
+        let mut content = String::new();
+        fs.new_open_options()
+            .read(true)
+            .open(path!(path))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    /// Creates a file system with nested directories, files and a symlink
+    fn populated() -> FileSystem {
+        let fs = FileSystem::default();
+        fs.create_dir(path!("/etc")).unwrap();
+        fs.create_dir(path!("/var")).unwrap();
+        fs.create_dir(path!("/var/log")).unwrap();
+        for (path, content) in [
+            ("/etc/hosts", "127.0.0.1 localhost"),
+            ("/var/log/empty", ""),
+        ] {
+            fs.new_open_options()
+                .write(true)
+                .create_new(true)
+                .open(path!(path))
+                .unwrap()
+                .write_all(content.as_bytes())
+                .unwrap();
+        }
+        fs.symlink(path!("../etc/hosts"), path!("/var/hosts"))
+            .unwrap();
+        fs
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let fs = populated();
+        let snapshot = fs.snapshot().unwrap();
+        let restored = FileSystem::from_snapshot(&snapshot).unwrap();
+
+        assert_eq!(read(&restored, "/etc/hosts"), "127.0.0.1 localhost");
+        assert_eq!(read(&restored, "/var/log/empty"), "");
+        assert_eq!(read(&restored, "/var/hosts"), "127.0.0.1 localhost");
+        assert_eq!(
+            restored.readlink(path!("/var/hosts")),
+            Ok(path!("../etc/hosts").to_path_buf())
+        );
+
+        for path in ["/", "/etc", "/etc/hosts", "/var/log/empty", "/var/hosts"] {
+            let expected = fs.symlink_metadata(path!(path)).unwrap();
+            let metadata = restored.symlink_metadata(path!(path)).unwrap();
+            assert_eq!(
+                (
+                    metadata.accessed,
+                    metadata.created,
+                    metadata.modified,
+                    metadata.len,
+                    metadata.is_dir(),
+                    metadata.is_file(),
+                ),
+                (
+                    expected.accessed,
+                    expected.created,
+                    expected.modified,
+                    expected.len,
+                    expected.is_dir(),
+                    expected.is_file(),
+                ),
+                "the metadata of `{}` are restored",
+                path,
+            );
+        }
+
+        assert_eq!(restored.snapshot(), Ok(snapshot), "snapshots are stable");
+    }
+
+    #[test]
+    fn test_restored_file_systems_are_independent() {
+        let snapshot = populated().snapshot().unwrap();
+        let first = FileSystem::from_snapshot(&snapshot).unwrap();
+        let second = FileSystem::from_snapshot(&snapshot).unwrap();
+
+        first
+            .new_open_options()
+            .write(true)
+            .truncate(true)
+            .open(path!("/etc/hosts"))
+            .unwrap()
+            .write_all(b"changed")
+            .unwrap();
+        first.remove_file(path!("/var/hosts")).unwrap();
+
+        assert_eq!(read(&first, "/etc/hosts"), "changed");
+        assert_eq!(read(&second, "/etc/hosts"), "127.0.0.1 localhost");
+        assert!(second.readlink(path!("/var/hosts")).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_snapshots() {
+        let snapshot = populated().snapshot().unwrap();
+
+        for len in 0..snapshot.len() {
+            assert!(
+                matches!(
+                    FileSystem::from_snapshot(&snapshot[..len]),
+                    Err(FsError::InvalidData)
+                ),
+                "a snapshot truncated to {} bytes is rejected",
+                len,
+            );
+        }
+
+        let mut trailing = snapshot.clone();
+        trailing.push(0);
+        assert!(matches!(
+            FileSystem::from_snapshot(&trailing),
+            Err(FsError::InvalidData)
+        ));
+
+        let mut version = snapshot;
+        version[8] = 2;
+        assert!(matches!(
+            FileSystem::from_snapshot(&version),
+            Err(FsError::InvalidData)
+        ));
+    }
+}
@@ -0,0 +1,595 @@
+//! An overlay file system, which stacks a writable upper [`FileSystem`]
+//! over one or more read-only lower ones.
+//!
+//! Reads fall through the layers from the top to the bottom, while every
+//! modification lands in the upper layer: files and directories of a lower
+//! layer are copied up before they get modified, and deleting them leaves a
+//! whiteout behind that hides the lower entries. The lower layers are never
+//! written to, which makes it possible to run a program against a host
+//! directory and then inspect or throw away what it changed.
+//!
+//! ```no_run
+//! use wasmer_vfs::{host_fs, mem_fs, overlay_fs::OverlayFileSystem};
+//!
+//! let upper = mem_fs::FileSystem::default();
+//! let fs = OverlayFileSystem::new(
+//!     Box::new(upper.clone()),
+//!     vec![Box::new(host_fs::FileSystem::default())],
+//! );
+//! // `fs` can now be handed to `WasiStateBuilder::set_fs`, and `upper`
+//! // holds everything the program wrote once it has finished.
+//! ```
+
+use crate::{FileOpener, VirtualFile};
+use crate::{FileSystem, FsError, Metadata, OpenOptions, OpenOptionsConfig, ReadDir, Result};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// A file system made of a writable upper layer stacked over read-only
+/// lower layers.
+///
+/// This type can be cloned, the clones share the same layers.
+#[derive(Debug, Clone)]
+pub struct OverlayFileSystem {
+    inner: Arc<OverlayInner>,
+}
+
+#[derive(Debug)]
+struct OverlayInner {
+    upper: Box<dyn FileSystem>,
+    /// The read-only layers, the first one has the highest priority
+    lowers: Vec<Box<dyn FileSystem>>,
+    /// Paths below which the lower layers are hidden, either because the
+    /// entry was deleted or because it was replaced in the upper layer
+    whiteouts: RwLock<HashSet<PathBuf>>,
+}
+
+impl OverlayFileSystem {
+    /// Creates an overlay of `upper` over `lowers`, where the first lower
+    /// layer takes precedence over the following ones.
+    pub fn new(upper: Box<dyn FileSystem>, lowers: Vec<Box<dyn FileSystem>>) -> Self {
+        Self {
+            inner: Arc::new(OverlayInner {
+                upper,
+                lowers,
+                whiteouts: RwLock::new(HashSet::new()),
+            }),
+        }
+    }
+
+    /// The layer that receives all the modifications.
+    pub fn upper(&self) -> &dyn FileSystem {
+        self.inner.upper.as_ref()
+    }
+
+    /// The read-only layers, from the highest to the lowest priority.
+    pub fn lowers(&self) -> impl Iterator<Item = &dyn FileSystem> {
+        self.inner.lowers.iter().map(|fs| fs.as_ref())
+    }
+
+    /// Returns the paths that were deleted or replaced in the upper layer,
+    /// and whose content in the lower layers is hidden.
+    pub fn whiteouts(&self) -> Result<Vec<PathBuf>> {
+        let whiteouts = self.inner.whiteouts.read().map_err(|_| FsError::Lock)?;
+        let mut whiteouts: Vec<_> = whiteouts.iter().cloned().collect();
+        whiteouts.sort();
+        Ok(whiteouts)
+    }
+
+    /// Checks if the lower layers are hidden at `path`.
+    fn is_whiteout(&self, path: &Path) -> Result<bool> {
+        let whiteouts = self.inner.whiteouts.read().map_err(|_| FsError::Lock)?;
+        Ok(path.ancestors().any(|path| whiteouts.contains(path)))
+    }
+
+    fn add_whiteout(&self, path: &Path) -> Result<()> {
+        let mut whiteouts = self.inner.whiteouts.write().map_err(|_| FsError::Lock)?;
+        whiteouts.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Returns the lower layers that are visible at `path`.
+    fn visible_lowers(&self, path: &Path) -> Result<&[Box<dyn FileSystem>]> {
+        Ok(match self.is_whiteout(path)? {
+            true => &[],
+            false => &self.inner.lowers,
+        })
+    }
+
+    /// Returns the first lower layer that has an entry at `path`, along
+    /// with its metadata.
+    fn find_lower(&self, path: &Path) -> Result<Option<(&dyn FileSystem, Metadata)>> {
+        for lower in self.visible_lowers(path)? {
+            if let Ok(metadata) = lower.symlink_metadata(path) {
+                return Ok(Some((lower.as_ref(), metadata)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn in_upper(&self, path: &Path) -> bool {
+        self.inner.upper.symlink_metadata(path).is_ok()
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool> {
+        Ok(self.in_upper(path) || self.find_lower(path)?.is_some())
+    }
+
+    /// Makes sure that the directory `path` exists in the upper layer, by
+    /// creating it and its parents after the ones of the lower layers.
+    fn copy_up_dir(&self, path: &Path) -> Result<()> {
+        if let Ok(metadata) = self.inner.upper.metadata(path) {
+            return match metadata.is_dir() {
+                true => Ok(()),
+                false => Err(FsError::BaseNotDirectory),
+            };
+        }
+        match self.metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return Err(FsError::BaseNotDirectory),
+            Err(err) => return Err(err),
+        }
+        if let Some(parent) = path.parent() {
+            self.copy_up_dir(parent)?;
+        }
+        self.inner.upper.create_dir(path)
+    }
+
+    /// Copies the entry at `path` from the lower layers to the upper one,
+    /// including all the children of directories.
+    fn copy_up(&self, path: &Path) -> Result<()> {
+        match self.inner.upper.symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return Ok(()),
+            Err(_) => {
+                let (lower, metadata) = self.find_lower(path)?.ok_or(FsError::EntityNotFound)?;
+                if let Some(parent) = path.parent() {
+                    self.copy_up_dir(parent)?;
+                }
+
+                if metadata.file_type().is_symlink() {
+                    return self.inner.upper.symlink(&lower.readlink(path)?, path);
+                }
+                if !metadata.is_dir() {
+                    let mut data = Vec::with_capacity(metadata.len() as usize);
+                    lower
+                        .new_open_options()
+                        .read(true)
+                        .open(path)?
+                        .read_to_end(&mut data)?;
+                    return self
+                        .inner
+                        .upper
+                        .new_open_options()
+                        .write(true)
+                        .create_new(true)
+                        .open(path)?
+                        .write_all(&data)
+                        .map_err(Into::into);
+                }
+                self.inner.upper.create_dir(path)?;
+            }
+        }
+
+        // The directory may still be merged with the lower layers, whose
+        // entries have to come along
+        for entry in self.read_dir(path)? {
+            self.copy_up(&entry?.path)?;
+        }
+        Ok(())
+    }
+
+    /// Prepares the creation of a new entry at `path` in the upper layer.
+    fn prepare_create(&self, path: &Path) -> Result<()> {
+        if self.exists(path)? {
+            return Err(FsError::AlreadyExists);
+        }
+        let parent = path.parent().ok_or(FsError::BaseNotDirectory)?;
+        self.copy_up_dir(parent)
+    }
+}
+
+/// Removes the `.` and `..` components of `path`, so that whiteouts can be
+/// matched against the ancestors of a path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+impl FileSystem for OverlayFileSystem {
+    fn read_dir(&self, path: &Path) -> Result<ReadDir> {
+        let path = normalize(path);
+        let mut entries = Vec::new();
+        let mut names = HashSet::new();
+        let mut found = false;
+
+        match self.inner.upper.metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => {
+                for entry in self.inner.upper.read_dir(&path)? {
+                    let entry = entry?;
+                    names.insert(entry.file_name());
+                    entries.push(entry);
+                }
+                found = true;
+            }
+            Ok(_) => return self.inner.upper.read_dir(&path),
+            Err(_) => {}
+        }
+        for lower in self.visible_lowers(&path)? {
+            match lower.metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {}
+                // A file shadows the directories of the layers below it
+                Ok(_) if found => break,
+                Ok(_) => return lower.read_dir(&path),
+                Err(_) => continue,
+            }
+            for entry in lower.read_dir(&path)? {
+                let entry = entry?;
+                if !self.is_whiteout(&entry.path)? && names.insert(entry.file_name()) {
+                    entries.push(entry);
+                }
+            }
+            found = true;
+        }
+
+        match found {
+            true => Ok(ReadDir::new(entries)),
+            false => Err(FsError::EntityNotFound),
+        }
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        let path = normalize(path);
+        self.prepare_create(&path)?;
+        self.inner.upper.create_dir(&path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<()> {
+        let path = normalize(path);
+        if !self.metadata(&path)?.is_dir() {
+            return Err(FsError::BaseNotDirectory);
+        }
+        if self.read_dir(&path)?.next().is_some() {
+            return Err(FsError::DirectoryNotEmpty);
+        }
+        if self.in_upper(&path) {
+            self.inner.upper.remove_dir(&path)?;
+        }
+        if self.find_lower(&path)?.is_some() {
+            self.add_whiteout(&path)?;
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        if !self.exists(&from)? {
+            return Err(FsError::EntityNotFound);
+        }
+        self.copy_up(&from)?;
+        if let Some(parent) = to.parent() {
+            self.copy_up_dir(parent)?;
+        }
+        self.inner.upper.rename(&from, &to)?;
+
+        // Neither the lower entries of the source nor those of the
+        // destination may reappear
+        if self.find_lower(&from)?.is_some() {
+            self.add_whiteout(&from)?;
+        }
+        if self.find_lower(&to)?.is_some() {
+            self.add_whiteout(&to)?;
+        }
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> Result<Metadata> {
+        let path = normalize(path);
+        if let Ok(metadata) = self.inner.upper.metadata(&path) {
+            return Ok(metadata);
+        }
+        for lower in self.visible_lowers(&path)? {
+            if let Ok(metadata) = lower.metadata(&path) {
+                return Ok(metadata);
+            }
+        }
+        Err(FsError::EntityNotFound)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<Metadata> {
+        let path = normalize(path);
+        if let Ok(metadata) = self.inner.upper.symlink_metadata(&path) {
+            return Ok(metadata);
+        }
+        match self.find_lower(&path)? {
+            Some((_, metadata)) => Ok(metadata),
+            None => Err(FsError::EntityNotFound),
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        let path = normalize(path);
+        if self.symlink_metadata(&path)?.is_dir() {
+            return Err(FsError::NotAFile);
+        }
+        if self.in_upper(&path) {
+            self.inner.upper.remove_file(&path)?;
+        }
+        if self.find_lower(&path)?.is_some() {
+            self.add_whiteout(&path)?;
+        }
+        Ok(())
+    }
+
+    fn symlink(&self, original: &Path, link: &Path) -> Result<()> {
+        let link = normalize(link);
+        self.prepare_create(&link)?;
+        self.inner.upper.symlink(original, &link)
+    }
+
+    fn readlink(&self, path: &Path) -> Result<PathBuf> {
+        let path = normalize(path);
+        if self.in_upper(&path) {
+            return self.inner.upper.readlink(&path);
+        }
+        match self.find_lower(&path)? {
+            Some((lower, _)) => lower.readlink(&path),
+            None => Err(FsError::EntityNotFound),
+        }
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(Box::new(OverlayFileOpener {
+            filesystem: self.clone(),
+        }))
+    }
+}
+
+/// The type that is responsible to open a file of an [`OverlayFileSystem`].
+#[derive(Debug, Clone)]
+pub struct OverlayFileOpener {
+    filesystem: OverlayFileSystem,
+}
+
+impl FileOpener for OverlayFileOpener {
+    fn open(
+        &mut self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let fs = &self.filesystem;
+        let path = normalize(path);
+        let modifies = conf.write() || conf.append() || conf.truncate();
+
+        if conf.create_new() {
+            fs.prepare_create(&path)?;
+        } else if fs.in_upper(&path) {
+            // Opened straight from the upper layer below
+        } else if let Some((lower, _)) = fs.find_lower(&path)? {
+            if !modifies {
+                return lower.new_open_options().options(conf.clone()).open(&path);
+            }
+            fs.copy_up(&path)?;
+        } else if conf.create() {
+            let parent = path.parent().ok_or(FsError::BaseNotDirectory)?;
+            fs.copy_up_dir(parent)?;
+        } else {
+            return Err(FsError::EntityNotFound);
+        }
+
+        fs.inner
+            .upper
+            .new_open_options()
+            .options(conf.clone())
+            .open(&path)
+    }
+}
+
+#[cfg(all(test, feature = "mem-fs"))]
+mod test_overlay_fs {
+    use super::*;
+    use crate::mem_fs;
+
+    macro_rules! path {
+        ($path:expr) => {
+            std::path::Path::new($path)
+        };
+    }
+
+    /// Creates a lower layer with `/etc/hosts`, `/etc/passwd` and an empty
+    /// `/var` directory
+    fn lower() -> mem_fs::FileSystem {
+        let fs = mem_fs::FileSystem::default();
+        fs.create_dir(path!("/etc")).unwrap();
+        fs.create_dir(path!("/var")).unwrap();
+        write(&fs, "/etc/hosts", b"127.0.0.1 localhost");
+        write(&fs, "/etc/passwd", b"root:x:0:0");
+        fs
+    }
+
+    fn write(fs: &dyn FileSystem, path: &str, data: &[u8]) {
+        fs.new_open_options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path!(path))
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+
+    fn read(fs: &dyn FileSystem, path: &str) -> Result<String> {
+        let mut data = String::new();
+        fs.new_open_options()
+            .read(true)
+            .open(path!(path))?
+            .read_to_string(&mut data)
+            .unwrap();
+        Ok(data)
+    }
+
+    fn names(fs: &dyn FileSystem, path: &str) -> Vec<String> {
+        let mut names: Vec<_> = fs
+            .read_dir(path!(path))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_reads_fall_through() {
+        let lower = lower();
+        let upper = mem_fs::FileSystem::default();
+        let fs = OverlayFileSystem::new(Box::new(upper.clone()), vec![Box::new(lower)]);
+
+        assert_eq!(
+            read(&fs, "/etc/hosts"),
+            Ok("127.0.0.1 localhost".to_string())
+        );
+        assert!(fs.metadata(path!("/etc")).unwrap().is_dir());
+        assert_eq!(names(&fs, "/etc"), vec!["hosts", "passwd"]);
+        assert_eq!(
+            fs.metadata(path!("/etc/shadow")).err(),
+            Some(FsError::EntityNotFound)
+        );
+        // Nothing was copied by reading
+        assert!(upper.metadata(path!("/etc")).is_err());
+    }
+
+    #[test]
+    fn test_layers_are_ordered() {
+        let first = mem_fs::FileSystem::default();
+        first.create_dir(path!("/etc")).unwrap();
+        write(&first, "/etc/hosts", b"first");
+        let fs = OverlayFileSystem::new(
+            Box::new(mem_fs::FileSystem::default()),
+            vec![Box::new(first), Box::new(lower())],
+        );
+
+        assert_eq!(read(&fs, "/etc/hosts"), Ok("first".to_string()));
+        assert_eq!(names(&fs, "/etc"), vec!["hosts", "passwd"]);
+    }
+
+    #[test]
+    fn test_copy_up_on_write() {
+        let lower = lower();
+        let upper = mem_fs::FileSystem::default();
+        let fs = OverlayFileSystem::new(Box::new(upper.clone()), vec![Box::new(lower.clone())]);
+
+        fs.new_open_options()
+            .append(true)
+            .open(path!("/etc/hosts"))
+            .unwrap()
+            .write_all(b"\n::1 localhost")
+            .unwrap();
+        let expected = "127.0.0.1 localhost\n::1 localhost".to_string();
+        assert_eq!(read(&fs, "/etc/hosts"), Ok(expected.clone()));
+        assert_eq!(read(&upper, "/etc/hosts"), Ok(expected));
+        assert_eq!(
+            read(&lower, "/etc/hosts"),
+            Ok("127.0.0.1 localhost".to_string())
+        );
+
+        write(&fs, "/var/log", b"started");
+        assert_eq!(read(&upper, "/var/log"), Ok("started".to_string()));
+        assert!(lower.metadata(path!("/var/log")).is_err());
+        assert_eq!(names(&fs, "/etc"), vec!["hosts", "passwd"]);
+    }
+
+    #[test]
+    fn test_whiteouts() {
+        let lower = lower();
+        let fs = OverlayFileSystem::new(
+            Box::new(mem_fs::FileSystem::default()),
+            vec![Box::new(lower.clone())],
+        );
+
+        fs.remove_file(path!("/etc/passwd")).unwrap();
+        assert_eq!(names(&fs, "/etc"), vec!["hosts"]);
+        assert_eq!(read(&fs, "/etc/passwd"), Err(FsError::EntityNotFound));
+        assert!(lower.metadata(path!("/etc/passwd")).is_ok());
+        assert_eq!(fs.whiteouts(), Ok(vec![PathBuf::from("/etc/passwd")]));
+
+        // A recreated file doesn't bring back the old content
+        write(&fs, "/etc/passwd", b"nobody");
+        assert_eq!(read(&fs, "/etc/passwd"), Ok("nobody".to_string()));
+
+        assert_eq!(
+            fs.remove_dir(path!("/etc")),
+            Err(FsError::DirectoryNotEmpty)
+        );
+        fs.remove_file(path!("/etc/hosts")).unwrap();
+        fs.remove_file(path!("/etc/passwd")).unwrap();
+        fs.remove_dir(path!("/etc")).unwrap();
+        assert_eq!(names(&fs, "/"), vec!["var"]);
+
+        // A recreated directory is empty
+        fs.create_dir(path!("/etc")).unwrap();
+        assert!(names(&fs, "/etc").is_empty());
+    }
+
+    #[test]
+    fn test_rename() {
+        let lower = lower();
+        let fs = OverlayFileSystem::new(
+            Box::new(mem_fs::FileSystem::default()),
+            vec![Box::new(lower.clone())],
+        );
+
+        fs.rename(path!("/etc/hosts"), path!("/var/hosts")).unwrap();
+        assert_eq!(
+            read(&fs, "/var/hosts"),
+            Ok("127.0.0.1 localhost".to_string())
+        );
+        assert_eq!(names(&fs, "/etc"), vec!["passwd"]);
+
+        fs.rename(path!("/etc"), path!("/config")).unwrap();
+        assert_eq!(names(&fs, "/"), vec!["config", "var"]);
+        assert_eq!(names(&fs, "/config"), vec!["passwd"]);
+        assert_eq!(names(&lower, "/etc"), vec!["hosts", "passwd"]);
+    }
+
+    #[test]
+    fn test_create() {
+        let fs = OverlayFileSystem::new(
+            Box::new(mem_fs::FileSystem::default()),
+            vec![Box::new(lower())],
+        );
+
+        assert_eq!(fs.create_dir(path!("/etc")), Err(FsError::AlreadyExists));
+        assert_eq!(
+            fs.new_open_options()
+                .write(true)
+                .create_new(true)
+                .open(path!("/etc/hosts"))
+                .err(),
+            Some(FsError::AlreadyExists)
+        );
+        assert_eq!(
+            fs.create_dir(path!("/missing/dir")),
+            Err(FsError::EntityNotFound)
+        );
+
+        fs.create_dir(path!("/etc/ssl")).unwrap();
+        fs.symlink(path!("../hosts"), path!("/etc/ssl/hosts"))
+            .unwrap();
+        assert_eq!(
+            fs.readlink(path!("/etc/ssl/hosts")),
+            Ok(PathBuf::from("../hosts"))
+        );
+        assert_eq!(names(&fs, "/etc"), vec!["hosts", "passwd", "ssl"]);
+    }
+}
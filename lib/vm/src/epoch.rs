@@ -0,0 +1,75 @@
+//! The epoch counter and deadlines used to interrupt running wasm.
+//!
+//! Code compiled with epoch interruption enabled loads the current epoch and
+//! the store's deadline on function entry and at every loop header, and traps
+//! with `TrapCode::Interrupt` once the epoch has reached the deadline.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A counter shared by everything running on an engine.
+///
+/// Clones share the same counter, so a host thread can keep one around and
+/// call [`EpochCounter::increment`] periodically to enforce wall-clock
+/// timeouts.
+#[derive(Debug, Clone, Default)]
+pub struct EpochCounter(Arc<AtomicU64>);
+
+impl EpochCounter {
+    /// Creates a counter starting at epoch 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current epoch.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Advances the epoch by one and returns the new epoch.
+    pub fn increment(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
+    }
+
+    /// The pointer stored in the `VMContext` of instances and read by
+    /// compiled code.
+    pub(crate) fn as_ptr(&self) -> *const AtomicU64 {
+        &*self.0
+    }
+}
+
+/// The epoch at which running wasm gets interrupted.
+///
+/// Clones share the same deadline. A new deadline never fires, since it's
+/// set to `u64::MAX`.
+#[derive(Debug, Clone)]
+pub struct EpochDeadline(Arc<AtomicU64>);
+
+impl Default for EpochDeadline {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU64::new(u64::MAX)))
+    }
+}
+
+impl EpochDeadline {
+    /// Creates a deadline that never fires.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the epoch at which wasm gets interrupted.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Interrupts wasm once the epoch reaches `epoch`.
+    pub fn set(&self, epoch: u64) {
+        self.0.store(epoch, Ordering::Relaxed)
+    }
+
+    /// The pointer stored in the `VMContext` of instances and read by
+    /// compiled code.
+    pub(crate) fn as_ptr(&self) -> *const AtomicU64 {
+        &*self.0
+    }
+}
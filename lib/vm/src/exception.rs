@@ -0,0 +1,163 @@
+//! Exception tags, and the exceptions thrown by wasm.
+//!
+//! Compiled code doesn't unwind native frames to throw an exception: it
+//! records the exception as pending in the [`ExceptionState`] of the call
+//! from the host into wasm that runs, and branches to the innermost handler
+//! of the function, or returns to its caller, which checks for a pending
+//! exception after every call. An exception that is still pending when wasm
+//! returns to the host is reported as a trap with
+//! `TrapCode::UncaughtException`.
+//!
+//! The state of a call is only reachable from the thread that runs it, so
+//! compiled code first checks [`EXCEPTIONS_PENDING`], which counts the calls
+//! with an exception pending on every thread, and only asks the runtime
+//! whether the exception is its own when it isn't zero.
+
+use std::collections::BTreeMap;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use wasmer_types::TagType;
+
+/// A WebAssembly exception tag.
+///
+/// Tags are compared by identity: two tags of the same type are still
+/// different tags, and a `catch` only handles the exceptions thrown with
+/// its own tag.
+#[derive(Debug)]
+pub struct Tag {
+    ty: TagType,
+}
+
+impl Tag {
+    /// Creates a new tag of the given type.
+    pub fn new(ty: TagType) -> Self {
+        Self { ty }
+    }
+
+    /// Get the type of the tag.
+    pub fn ty(&self) -> &TagType {
+        &self.ty
+    }
+}
+
+/// An exception thrown by wasm.
+#[derive(Debug)]
+pub struct VMException {
+    /// The tag the exception was thrown with, only used for its identity.
+    tag: *const Tag,
+    /// The values carried by the exception, as the raw bits of `u128`s.
+    payload: Box<[u128]>,
+}
+
+/// # Safety
+/// The tag pointer is never dereferenced.
+unsafe impl Send for VMException {}
+/// # Safety
+/// The tag pointer is never dereferenced.
+unsafe impl Sync for VMException {}
+
+impl VMException {
+    /// The values carried by the exception.
+    pub fn payload(&self) -> &[u128] {
+        &self.payload
+    }
+}
+
+/// The number of calls from the host into wasm that have an exception
+/// pending, on every thread. The `VMContext` of instances points to it.
+static EXCEPTIONS_PENDING: AtomicUsize = AtomicUsize::new(0);
+
+/// The pointer stored in the `VMContext` of instances. Compiled code loads
+/// whether an exception may be pending from it.
+pub(crate) fn exceptions_pending_ptr() -> *const AtomicUsize {
+    &EXCEPTIONS_PENDING
+}
+
+/// Returns an address below the frames of the wasm functions that run, when
+/// called by a builtin on the wasm stack.
+#[inline(never)]
+pub(crate) fn stack_pointer() -> usize {
+    let marker = 0u8;
+    &marker as *const u8 as usize
+}
+
+/// The exceptions in flight in a call from the host into wasm.
+#[derive(Debug, Default)]
+pub(crate) struct ExceptionState {
+    /// The exception being propagated.
+    pending: Option<Arc<VMException>>,
+    /// The exceptions caught by the catch clauses that may still rethrow
+    /// them, by the address of the stack slot that identifies the clause.
+    caught: BTreeMap<usize, Arc<VMException>>,
+}
+
+impl ExceptionState {
+    /// Whether an exception is pending.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn set_pending(&mut self, exception: Option<Arc<VMException>>) {
+        match (self.pending.is_some(), exception.is_some()) {
+            (false, true) => EXCEPTIONS_PENDING.fetch_add(1, Ordering::Relaxed),
+            (true, false) => EXCEPTIONS_PENDING.fetch_sub(1, Ordering::Relaxed),
+            _ => 0,
+        };
+        self.pending = exception;
+    }
+
+    /// Throws a new exception with `tag`.
+    pub(crate) fn throw(&mut self, tag: *const Tag, payload: Box<[u128]>) {
+        self.set_pending(Some(Arc::new(VMException { tag, payload })));
+    }
+
+    /// Throws `exception` again.
+    ///
+    /// # Panics
+    ///
+    /// If `exception` isn't caught by a catch clause that is still running.
+    pub(crate) fn rethrow(&mut self, exception: *const VMException) {
+        let exception = self
+            .caught
+            .values()
+            .find(|caught| ptr::eq(Arc::as_ptr(caught), exception))
+            .expect("rethrown exception isn't caught")
+            .clone();
+        self.set_pending(Some(exception));
+    }
+
+    /// Catches the pending exception if it was thrown with `tag`, or
+    /// whatever its tag if `tag` is `None`.
+    ///
+    /// The catch clause is identified by `slot`, the address of a stack
+    /// slot of its function, and `stack_pointer` is an address below the
+    /// frames of the functions that run. The exception stays alive until
+    /// the clause catches another one, or its function returns and another
+    /// exception is caught below it.
+    pub(crate) fn catch(
+        &mut self,
+        tag: Option<*const Tag>,
+        slot: usize,
+        stack_pointer: usize,
+    ) -> Option<Arc<VMException>> {
+        let exception = self.pending.clone()?;
+        if let Some(tag) = tag {
+            if !ptr::eq(tag, exception.tag) {
+                return None;
+            }
+        }
+        self.set_pending(None);
+        // The stack grows down, so the slots below the stack pointer belong
+        // to functions that returned, whose clauses can't rethrow anymore.
+        self.caught = self.caught.split_off(&stack_pointer);
+        self.caught.insert(slot, exception.clone());
+        Some(exception)
+    }
+}
+
+impl Drop for ExceptionState {
+    fn drop(&mut self) {
+        self.set_pending(None);
+    }
+}
@@ -1,6 +1,7 @@
 // This file contains code from external sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
+use crate::exception::Tag;
 use crate::global::Global;
 use crate::instance::WeakOrStrongInstanceRef;
 use crate::memory::Memory;
@@ -8,7 +9,7 @@ use crate::table::Table;
 use crate::vmcontext::{VMFunctionEnvironment, VMFunctionKind, VMTrampoline};
 use crate::VMFunctionBody;
 use std::sync::Arc;
-use wasmer_types::{FunctionType, MemoryStyle, MemoryType, TableStyle, TableType};
+use wasmer_types::{FunctionType, MemoryStyle, MemoryType, TableStyle, TableType, TagType};
 
 /// The value of an export passed from one instance to another.
 #[derive(Debug)]
@@ -24,6 +25,9 @@ pub enum VMExtern {
 
     /// A global export value.
     Global(VMGlobal),
+
+    /// A tag export value.
+    Tag(VMTag),
 }
 
 /// A function export value.
@@ -233,3 +237,50 @@ impl From<VMGlobal> for VMExtern {
         Self::Global(global)
     }
 }
+
+/// A tag export value.
+#[derive(Debug, Clone)]
+pub struct VMTag {
+    /// The tag, whose identity is what `catch` matches exceptions against.
+    pub from: Arc<Tag>,
+
+    /// A “reference” to the instance through the
+    /// `InstanceRef`. `None` if it is a host tag.
+    pub instance_ref: Option<WeakOrStrongInstanceRef>,
+}
+
+/// # Safety
+/// There is no non-threadsafe logic directly in this type, and tags are
+/// immutable.
+unsafe impl Send for VMTag {}
+
+/// # Safety
+/// Tags are immutable.
+unsafe impl Sync for VMTag {}
+
+impl VMTag {
+    /// Get the type for this exported tag.
+    pub fn ty(&self) -> &TagType {
+        self.from.ty()
+    }
+
+    /// Returns whether or not the two `VMTag`s refer to the same Tag.
+    pub fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.from, &other.from)
+    }
+
+    /// Converts the stored instance ref into a strong `InstanceRef` if it is weak.
+    /// Returns None if it cannot be upgraded.
+    pub fn upgrade_instance_ref(&mut self) -> Option<()> {
+        if let Some(ref mut ir) = self.instance_ref {
+            *ir = ir.upgrade()?;
+        }
+        Some(())
+    }
+}
+
+impl From<VMTag> for VMExtern {
+    fn from(tag: VMTag) -> Self {
+        Self::Tag(tag)
+    }
+}
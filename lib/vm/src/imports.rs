@@ -1,10 +1,12 @@
 // This file contains code from external sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
+use crate::exception::Tag;
 use crate::instance::ImportFunctionEnv;
 use crate::vmcontext::{VMFunctionImport, VMGlobalImport, VMMemoryImport, VMTableImport};
+use std::sync::Arc;
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
-use wasmer_types::{FunctionIndex, GlobalIndex, MemoryIndex, TableIndex};
+use wasmer_types::{FunctionIndex, GlobalIndex, MemoryIndex, TableIndex, TagIndex};
 
 /// Resolved import pointers.
 #[derive(Clone)]
@@ -32,6 +34,9 @@ pub struct Imports {
 
     /// Resolved addresses for imported globals.
     pub globals: BoxedSlice<GlobalIndex, VMGlobalImport>,
+
+    /// Resolved imported tags.
+    pub tags: BoxedSlice<TagIndex, Arc<Tag>>,
 }
 
 impl Imports {
@@ -42,6 +47,7 @@ impl Imports {
         table_imports: PrimaryMap<TableIndex, VMTableImport>,
         memory_imports: PrimaryMap<MemoryIndex, VMMemoryImport>,
         global_imports: PrimaryMap<GlobalIndex, VMGlobalImport>,
+        tag_imports: PrimaryMap<TagIndex, Arc<Tag>>,
     ) -> Self {
         Self {
             functions: function_imports.into_boxed_slice(),
@@ -49,6 +55,7 @@ impl Imports {
             tables: table_imports.into_boxed_slice(),
             memories: memory_imports.into_boxed_slice(),
             globals: global_imports.into_boxed_slice(),
+            tags: tag_imports.into_boxed_slice(),
         }
     }
 
@@ -60,6 +67,7 @@ impl Imports {
             tables: PrimaryMap::new().into_boxed_slice(),
             memories: PrimaryMap::new().into_boxed_slice(),
             globals: PrimaryMap::new().into_boxed_slice(),
+            tags: PrimaryMap::new().into_boxed_slice(),
         }
     }
 
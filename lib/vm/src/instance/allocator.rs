@@ -1,4 +1,5 @@
 use super::{Instance, InstanceRef};
+use crate::pool::{PoolSlot, PoolingAllocator};
 use crate::vmcontext::{VMMemoryDefinition, VMTableDefinition};
 use crate::VMOffsets;
 use std::alloc::{self, Layout};
@@ -41,11 +42,16 @@ pub struct InstanceAllocator {
     /// `instance_ptr` buffer. If it has not when being dropped,
     /// the buffer should be freed.
     consumed: bool,
+
+    /// The pool slot holding the `instance_ptr` buffer, if the
+    /// instance is pooled. The slot is released instead of freeing
+    /// the buffer.
+    slot: Option<PoolSlot>,
 }
 
 impl Drop for InstanceAllocator {
     fn drop(&mut self) {
-        if !self.consumed {
+        if !self.consumed && self.slot.is_none() {
             // If `consumed` has not been set, then we still have ownership
             // over the buffer and must free it.
             let instance_ptr = self.instance_ptr.as_ptr();
@@ -86,11 +92,60 @@ impl InstanceAllocator {
             alloc::handle_alloc_error(instance_layout);
         };
 
+        Self::with_instance_ptr(instance_ptr, instance_layout, offsets, None)
+    }
+
+    /// Allocates instance data in a slot of `pool`, like
+    /// [`InstanceAllocator::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module exceeds the limits of the pool,
+    /// or if all of its instance slots are in use.
+    pub fn new_pooled(
+        module: &ModuleInfo,
+        pool: &PoolingAllocator,
+    ) -> Result<
+        (
+            Self,
+            Vec<NonNull<VMMemoryDefinition>>,
+            Vec<NonNull<VMTableDefinition>>,
+        ),
+        String,
+    > {
+        let offsets = VMOffsets::new(mem::size_of::<usize>() as u8, module);
+        let instance_layout = Self::instance_layout(&offsets);
+
+        let mut slot = pool.allocate_instance(module, instance_layout)?;
+        #[allow(clippy::cast_ptr_alignment)]
+        let instance_ptr = NonNull::new(slot.as_mut_ptr() as *mut Instance).unwrap();
+
+        Ok(Self::with_instance_ptr(
+            instance_ptr,
+            instance_layout,
+            offsets,
+            Some(slot),
+        ))
+    }
+
+    /// Wraps an allocated `instance_ptr` buffer, and returns the
+    /// locations of the local memories and tables in it.
+    fn with_instance_ptr(
+        instance_ptr: NonNull<Instance>,
+        instance_layout: Layout,
+        offsets: VMOffsets,
+        slot: Option<PoolSlot>,
+    ) -> (
+        Self,
+        Vec<NonNull<VMMemoryDefinition>>,
+        Vec<NonNull<VMTableDefinition>>,
+    ) {
         let allocator = Self {
             instance_ptr,
             instance_layout,
             offsets,
             consumed: false,
+            slot,
         };
 
         // # Safety
@@ -204,7 +259,7 @@ impl InstanceAllocator {
 
         // This is correct because of the invariants of `Self` and
         // because we write `Instance` to the pointer in this function.
-        unsafe { InstanceRef::new(instance, instance_layout) }
+        unsafe { InstanceRef::new(instance, instance_layout, self.slot.take()) }
     }
 
     /// Get the [`VMOffsets`] for the allocated buffer.
@@ -13,20 +13,23 @@ mod r#ref;
 pub use allocator::InstanceAllocator;
 pub use r#ref::{InstanceRef, WeakInstanceRef, WeakOrStrongInstanceRef};
 
+use crate::epoch::{EpochCounter, EpochDeadline};
+use crate::exception::{exceptions_pending_ptr, Tag, VMException};
 use crate::export::VMExtern;
 use crate::func_data_registry::VMFuncRef;
 use crate::global::Global;
 use crate::imports::Imports;
 use crate::memory::{Memory, MemoryError};
+use crate::memory_image::MemoryImages;
 use crate::table::{Table, TableElement};
-use crate::trap::{catch_traps, Trap, TrapCode, TrapHandler};
+use crate::trap::{catch_traps, with_activation, Trap, TrapCode, TrapHandler};
 use crate::vmcontext::{
     VMBuiltinFunctionsArray, VMCallerCheckedAnyfunc, VMContext, VMFunctionEnvironment,
     VMFunctionImport, VMFunctionKind, VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition,
     VMMemoryImport, VMSharedSignatureIndex, VMTableDefinition, VMTableImport, VMTrampoline,
 };
 use crate::{FunctionBodyPtr, VMFunctionBody, VMOffsets};
-use crate::{VMFunction, VMGlobal, VMMemory, VMTable};
+use crate::{VMFunction, VMGlobal, VMMemory, VMTable, VMTag};
 use memoffset::offset_of;
 use more_asserts::assert_lt;
 use std::any::Any;
@@ -38,12 +41,13 @@ use std::fmt;
 use std::mem;
 use std::ptr::{self, NonNull};
 use std::slice;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{
     DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
     LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
-    ModuleInfo, Pages, SignatureIndex, TableIndex, TableInitializer,
+    ModuleInfo, Pages, SignatureIndex, TableIndex, TableInitializer, TagIndex,
 };
 
 /// The function pointer to call with data and an [`Instance`] pointer to
@@ -74,6 +78,9 @@ pub(crate) struct Instance {
     /// WebAssembly global data.
     globals: BoxedSlice<LocalGlobalIndex, Arc<Global>>,
 
+    /// WebAssembly tags, imported ones first.
+    tags: BoxedSlice<TagIndex, Arc<Tag>>,
+
     /// Pointers to functions in executable memory.
     functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
 
@@ -103,6 +110,12 @@ pub(crate) struct Instance {
     /// functions from other Wasm modules.
     imported_function_envs: BoxedSlice<FunctionIndex, ImportFunctionEnv>,
 
+    /// The epoch counter and deadline read by compiled code with epoch
+    /// interruption enabled, set at instantiation. They are kept here so
+    /// that the pointers written in the `vmctx` stay valid.
+    epoch_counter: EpochCounter,
+    epoch_deadline: EpochDeadline,
+
     /// Additional context used by compiled WebAssembly code. This
     /// field is last, and represents a dynamically-sized array that
     /// extends beyond the nominal end of the struct (similar to a
@@ -367,6 +380,43 @@ impl Instance {
         unsafe { self.vmctx_plus_offset(self.offsets.vmctx_builtin_functions_begin()) }
     }
 
+    /// Points the `vmctx` at the epoch counter and deadline.
+    fn write_epoch(&self) {
+        unsafe {
+            *self.vmctx_plus_offset(self.offsets.vmctx_epoch_counter()) =
+                self.epoch_counter.as_ptr();
+            *self.vmctx_plus_offset(self.offsets.vmctx_epoch_deadline()) =
+                self.epoch_deadline.as_ptr();
+        }
+    }
+
+    /// Return the fuel left to the instance.
+    fn fuel_remaining(&self) -> &AtomicU64 {
+        unsafe { &*self.vmctx_plus_offset(self.offsets.vmctx_fuel_remaining()) }
+    }
+
+    /// Return the flag set when the instance ran out of fuel.
+    fn fuel_exhausted(&self) -> &AtomicU64 {
+        unsafe { &*self.vmctx_plus_offset(self.offsets.vmctx_fuel_exhausted()) }
+    }
+
+    /// Gives `fuel` to the instance, and clears the exhausted flag.
+    fn set_fuel(&self, fuel: u64) {
+        self.fuel_remaining().store(fuel, Ordering::SeqCst);
+        self.fuel_exhausted().store(0, Ordering::SeqCst);
+    }
+
+    /// Adds `fuel` to the fuel left to the instance, and clears the
+    /// exhausted flag.
+    fn add_fuel(&self, fuel: u64) {
+        let _ =
+            self.fuel_remaining()
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |remaining| {
+                    Some(remaining.saturating_add(fuel))
+                });
+        self.fuel_exhausted().store(0, Ordering::SeqCst);
+    }
+
     /// Return a reference to the vmctx used by compiled wasm code.
     fn vmctx(&self) -> &VMContext {
         &self.vmctx
@@ -748,6 +798,27 @@ impl Instance {
         unsafe { memory.memory_copy(dst, src, len) }
     }
 
+    /// Do a `memory.copy` between two different memories, each of which may
+    /// be locally defined or imported.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error when the source or destination ranges are out of
+    /// bounds.
+    pub(crate) fn memory_copy_between(
+        &self,
+        dst_memory_index: MemoryIndex,
+        src_memory_index: MemoryIndex,
+        dst: u32,
+        src: u32,
+        len: u32,
+    ) -> Result<(), Trap> {
+        let dst_memory = self.get_memory(dst_memory_index);
+        let src_memory = self.get_memory(src_memory_index);
+        // The following memory copy is not synchronized and is not atomic:
+        unsafe { dst_memory.memory_copy_from(&src_memory, dst, src, len) }
+    }
+
     /// Perform the `memory.fill` operation on a locally defined memory.
     ///
     /// # Errors
@@ -783,6 +854,110 @@ impl Instance {
         unsafe { memory.memory_fill(dst, val, len) }
     }
 
+    /// Perform the `memory.atomic.wait32` operation on a locally defined memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned,
+    /// or if the memory isn't shared.
+    pub(crate) fn local_memory_atomic_wait32(
+        &self,
+        memory_index: LocalMemoryIndex,
+        dst: u32,
+        val: u32,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let memory = self.memory(memory_index);
+        let wait_queue = self.memories[memory_index].wait_queue();
+        memory.memory_atomic_wait32(wait_queue, dst, val, timeout)
+    }
+
+    /// Perform the `memory.atomic.wait32` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned,
+    /// or if the memory isn't shared.
+    pub(crate) fn imported_memory_atomic_wait32(
+        &self,
+        memory_index: MemoryIndex,
+        dst: u32,
+        val: u32,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let import = self.imported_memory(memory_index);
+        let memory = unsafe { import.definition.as_ref() };
+        memory.memory_atomic_wait32(import.from.wait_queue(), dst, val, timeout)
+    }
+
+    /// Perform the `memory.atomic.wait64` operation on a locally defined memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned,
+    /// or if the memory isn't shared.
+    pub(crate) fn local_memory_atomic_wait64(
+        &self,
+        memory_index: LocalMemoryIndex,
+        dst: u32,
+        val: u64,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let memory = self.memory(memory_index);
+        let wait_queue = self.memories[memory_index].wait_queue();
+        memory.memory_atomic_wait64(wait_queue, dst, val, timeout)
+    }
+
+    /// Perform the `memory.atomic.wait64` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned,
+    /// or if the memory isn't shared.
+    pub(crate) fn imported_memory_atomic_wait64(
+        &self,
+        memory_index: MemoryIndex,
+        dst: u32,
+        val: u64,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let import = self.imported_memory(memory_index);
+        let memory = unsafe { import.definition.as_ref() };
+        memory.memory_atomic_wait64(import.from.wait_queue(), dst, val, timeout)
+    }
+
+    /// Perform the `memory.atomic.notify` operation on a locally defined memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned.
+    pub(crate) fn local_memory_atomic_notify(
+        &self,
+        memory_index: LocalMemoryIndex,
+        dst: u32,
+        count: u32,
+    ) -> Result<u32, Trap> {
+        let memory = self.memory(memory_index);
+        let wait_queue = self.memories[memory_index].wait_queue();
+        memory.memory_atomic_notify(wait_queue, dst, count)
+    }
+
+    /// Perform the `memory.atomic.notify` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned.
+    pub(crate) fn imported_memory_atomic_notify(
+        &self,
+        memory_index: MemoryIndex,
+        dst: u32,
+        count: u32,
+    ) -> Result<u32, Trap> {
+        let import = self.imported_memory(memory_index);
+        let memory = unsafe { import.definition.as_ref() };
+        memory.memory_atomic_notify(import.from.wait_queue(), dst, count)
+    }
+
     /// Performs the `memory.init` operation.
     ///
     /// # Errors
@@ -831,6 +1006,144 @@ impl Instance {
         passive_data.remove(&data_index);
     }
 
+    /// The number of values carried by the exceptions of tag `tag_index`.
+    fn tag_payload_len(&self, tag_index: TagIndex) -> usize {
+        self.tags[tag_index].ty().params().len()
+    }
+
+    /// Throw an exception with tag `tag_index`, reading its payload from
+    /// `values`.
+    ///
+    /// # Safety
+    ///
+    /// `values` must hold one value every 16 bytes for every parameter of
+    /// the tag.
+    pub(crate) unsafe fn throw(&self, tag_index: TagIndex, values: *const u8) {
+        let payload = (0..self.tag_payload_len(tag_index))
+            .map(|i| ptr::read_unaligned(values.add(i * 16) as *const u128))
+            .collect();
+        let tag = Arc::as_ptr(&self.tags[tag_index]);
+        with_activation(|activation| activation.exceptions.throw(tag, payload));
+    }
+
+    /// Catch the pending exception if it was thrown with tag `tag_index`,
+    /// writing its payload to `values`. Returns the exception, which is
+    /// null if there was no match.
+    ///
+    /// The catch clause is identified by `slot`, see
+    /// [`ExceptionState::catch`].
+    ///
+    /// # Safety
+    ///
+    /// `values` must have room for one value every 16 bytes for every
+    /// parameter of the tag.
+    ///
+    /// [`ExceptionState::catch`]: crate::exception::ExceptionState::catch
+    pub(crate) unsafe fn catch(
+        &self,
+        tag_index: TagIndex,
+        values: *mut u8,
+        slot: usize,
+        stack_pointer: usize,
+    ) -> *const VMException {
+        let tag = Arc::as_ptr(&self.tags[tag_index]);
+        let exception = with_activation(|activation| {
+            activation.exceptions.catch(Some(tag), slot, stack_pointer)
+        });
+        match exception {
+            Some(exception) => {
+                for (i, value) in exception.payload().iter().enumerate() {
+                    ptr::write_unaligned(values.add(i * 16) as *mut u128, *value);
+                }
+                Arc::as_ptr(&exception)
+            }
+            None => ptr::null(),
+        }
+    }
+
+    /// Request a tail call to function `function_index`, reading its
+    /// arguments from `values`.
+    ///
+    /// # Safety
+    ///
+    /// `values` must hold one value every 16 bytes for every parameter of
+    /// the function.
+    pub(crate) unsafe fn return_call(&self, function_index: FunctionIndex, values: *const u8) {
+        let (callee, vmctx) = match self.module.local_func_index(function_index) {
+            Some(local_index) => (
+                self.functions[local_index].0,
+                VMFunctionEnvironment {
+                    vmctx: self.vmctx_ptr(),
+                },
+            ),
+            None => {
+                let import = self.imported_function(function_index);
+                (import.body, import.environment)
+            }
+        };
+        self.request_tail_call(self.module.functions[function_index], callee, vmctx, values);
+    }
+
+    /// Request a tail call to the function at `elem_index` in table
+    /// `table_index`, which must have signature `sig_index`, reading its
+    /// arguments from `values`.
+    ///
+    /// # Safety
+    ///
+    /// `values` must hold one value every 16 bytes for every parameter of
+    /// the signature.
+    pub(crate) unsafe fn return_call_indirect(
+        &self,
+        table_index: TableIndex,
+        sig_index: SignatureIndex,
+        elem_index: u32,
+        values: *const u8,
+    ) -> Result<(), Trap> {
+        let func_ref = match self.get_table(table_index).get(elem_index) {
+            Some(TableElement::FuncRef(func_ref)) => func_ref,
+            _ => return Err(Trap::lib(TrapCode::TableAccessOutOfBounds)),
+        };
+        let anyfunc = match (*func_ref).as_ref() {
+            Some(anyfunc) => anyfunc,
+            None => return Err(Trap::lib(TrapCode::IndirectCallToNull)),
+        };
+        let expected = *self.signature_ids_ptr().add(sig_index.index());
+        if anyfunc.type_index != expected {
+            return Err(Trap::lib(TrapCode::BadSignature));
+        }
+        self.request_tail_call(sig_index, anyfunc.func_ptr, anyfunc.vmctx, values);
+        Ok(())
+    }
+
+    unsafe fn request_tail_call(
+        &self,
+        sig_index: SignatureIndex,
+        callee: *const VMFunctionBody,
+        vmctx: VMFunctionEnvironment,
+        values: *const u8,
+    ) {
+        let signature = &self.module.signatures[sig_index];
+        let args = (0..signature.params().len())
+            .map(|i| ptr::read_unaligned(values.add(i * 16) as *const u128));
+        with_activation(|activation| {
+            activation.tail_calls.request(
+                self.function_call_trampolines[sig_index],
+                callee,
+                vmctx,
+                args,
+                signature.results().len(),
+            )
+        });
+    }
+
+    /// Whether the loop that makes the tail calls of the running call called
+    /// the function at `function_index`, which makes tail calls and is
+    /// entering.
+    pub(crate) fn tail_call_entry(&self, function_index: LocalFunctionIndex) -> bool {
+        let callee = self.functions[function_index].0;
+        with_activation(|activation| activation.tail_calls.enter(callee))
+    }
+
     /// Get a table by index regardless of whether it is locally-defined or an
     /// imported, foreign table.
     pub(crate) fn get_table(&self, table_index: TableIndex) -> &dyn Table {
@@ -867,6 +1180,9 @@ pub struct InstanceHandle {
 impl InstanceHandle {
     /// Create a new `InstanceHandle` pointing at a new [`InstanceRef`].
     ///
+    /// Compiled code with epoch interruption enabled traps once
+    /// `epoch_counter` reaches `epoch_deadline`.
+    ///
     /// # Safety
     ///
     /// This method is not necessarily inherently unsafe to call, but in general
@@ -899,6 +1215,8 @@ impl InstanceHandle {
         vmshared_signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
         host_state: Box<dyn Any>,
         imported_function_envs: BoxedSlice<FunctionIndex, ImportFunctionEnv>,
+        epoch_counter: EpochCounter,
+        epoch_deadline: EpochDeadline,
     ) -> Result<Self, Trap> {
         let vmctx_globals = finished_globals
             .values()
@@ -906,6 +1224,19 @@ impl InstanceHandle {
             .collect::<PrimaryMap<LocalGlobalIndex, _>>()
             .into_boxed_slice();
         let passive_data = RefCell::new(module.passive_data.clone());
+        let tags = imports
+            .tags
+            .values()
+            .cloned()
+            .chain(
+                module
+                    .tags
+                    .keys()
+                    .skip(module.num_imported_tags)
+                    .map(|index| Arc::new(Tag::new(module.tag_type(index)))),
+            )
+            .collect::<PrimaryMap<TagIndex, _>>()
+            .into_boxed_slice();
 
         let handle = {
             let offsets = allocator.offsets().clone();
@@ -918,6 +1249,7 @@ impl InstanceHandle {
                 memories: finished_memories,
                 tables: finished_tables,
                 globals: finished_globals,
+                tags,
                 functions: finished_functions,
                 function_call_trampolines: finished_function_call_trampolines,
                 passive_elements: Default::default(),
@@ -925,6 +1257,8 @@ impl InstanceHandle {
                 host_state,
                 funcrefs,
                 imported_function_envs,
+                epoch_counter,
+                epoch_deadline,
                 vmctx: VMContext {},
             };
 
@@ -986,6 +1320,10 @@ impl InstanceHandle {
             instance.builtin_functions_ptr() as *mut VMBuiltinFunctionsArray,
             VMBuiltinFunctionsArray::initialized(),
         );
+        instance.write_epoch();
+        *instance.vmctx_plus_offset(instance.offsets.vmctx_exceptions_pending()) =
+            exceptions_pending_ptr();
+        instance.set_fuel(instance.module.initial_fuel.unwrap_or(0));
 
         // Perform infallible initialization in this constructor, while fallible
         // initialization is deferred to the `initialize` method.
@@ -995,6 +1333,37 @@ impl InstanceHandle {
         Ok(handle)
     }
 
+    /// Returns the fuel left to code compiled with fuel metering.
+    pub fn remaining_fuel(&self) -> u64 {
+        self.instance()
+            .as_ref()
+            .fuel_remaining()
+            .load(Ordering::SeqCst)
+    }
+
+    /// Whether code compiled with fuel metering trapped because it didn't
+    /// have enough fuel left.
+    pub fn fuel_exhausted(&self) -> bool {
+        self.instance()
+            .as_ref()
+            .fuel_exhausted()
+            .load(Ordering::SeqCst)
+            != 0
+    }
+
+    /// Sets the fuel left to code compiled with fuel metering, and clears
+    /// the exhausted flag so that it can run again.
+    pub fn set_fuel(&self, fuel: u64) {
+        self.instance().as_ref().set_fuel(fuel)
+    }
+
+    /// Adds to the fuel left to code compiled with fuel metering in a
+    /// single atomic update, saturating at `u64::MAX`, and clears the
+    /// exhausted flag.
+    pub fn add_fuel(&self, fuel: u64) {
+        self.instance().as_ref().add_fuel(fuel)
+    }
+
     /// Return a reference to the contained `Instance`.
     pub(crate) fn instance(&self) -> &InstanceRef {
         &self.instance
@@ -1002,6 +1371,9 @@ impl InstanceHandle {
 
     /// Finishes the instantiation process started by `Instance::new`.
     ///
+    /// The local memories with an image in `memory_images` are initialized
+    /// by mapping it, instead of copying their data initializers.
+    ///
     /// # Safety
     ///
     /// Only safe to call immediately after instantiation.
@@ -1009,12 +1381,13 @@ impl InstanceHandle {
         &self,
         trap_handler: &(dyn TrapHandler + 'static),
         data_initializers: &[DataInitializer<'_>],
+        memory_images: Option<&MemoryImages>,
     ) -> Result<(), Trap> {
         let instance = self.instance().as_ref();
 
         // Apply the initializers.
         initialize_tables(instance)?;
-        initialize_memories(instance, data_initializers)?;
+        initialize_memories(instance, data_initializers, memory_images)?;
 
         // The WebAssembly spec specifies that the start function is
         // invoked automatically at instantiation time.
@@ -1137,6 +1510,11 @@ impl InstanceHandle {
                 }
                 .into()
             }
+            ExportIndex::Tag(index) => VMTag {
+                from: instance_ref.tags[*index].clone(),
+                instance_ref: Some(WeakOrStrongInstanceRef::Strong(instance)),
+            }
+            .into(),
         }
     }
 
@@ -1369,8 +1747,28 @@ fn initialize_passive_elements(instance: &Instance) {
 fn initialize_memories(
     instance: &Instance,
     data_initializers: &[DataInitializer<'_>],
+    memory_images: Option<&MemoryImages>,
 ) -> Result<(), Trap> {
+    let mapped = instance
+        .memories
+        .iter()
+        .map(|(index, memory)| {
+            match memory_images.and_then(|images| images.get(index, data_initializers)) {
+                Some(image) => memory
+                    .initialize_with_image(&image)
+                    .map_err(|e| Trap::User(Box::new(e))),
+                None => Ok(false),
+            }
+        })
+        .collect::<Result<PrimaryMap<LocalMemoryIndex, bool>, _>>()?;
+
     for init in data_initializers {
+        let local_memory_index = instance
+            .module
+            .local_memory_index(init.location.memory_index);
+        if local_memory_index.map_or(false, |index| mapped[index]) {
+            continue;
+        }
         let memory = instance.get_memory(init.location.memory_index);
 
         let start = get_memory_init_start(init, instance);
@@ -1,4 +1,5 @@
 use super::Instance;
+use crate::pool::PoolSlot;
 use std::alloc::Layout;
 use std::convert::TryFrom;
 use std::ptr::{self, NonNull};
@@ -19,6 +20,10 @@ struct InstanceInner {
     /// The layout of `Instance` (which can vary).
     instance_layout: Layout,
 
+    /// The pool slot holding `Instance`, if it's pooled. The slot is
+    /// released instead of deallocating `Instance`.
+    slot: Option<PoolSlot>,
+
     /// The `Instance` itself. It must be the last field of
     /// `InstanceRef` since `Instance` is dyamically-sized.
     ///
@@ -43,7 +48,9 @@ impl InstanceInner {
         let instance_ptr = self.instance.as_ptr();
 
         ptr::drop_in_place(instance_ptr);
-        std::alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
+        if self.slot.take().is_none() {
+            std::alloc::dealloc(instance_ptr as *mut u8, self.instance_layout);
+        }
     }
 
     /// Get a reference to the `Instance`.
@@ -113,9 +120,14 @@ impl InstanceRef {
     /// and correctly initialized pointer to `Instance`. See
     /// [`InstanceAllocator`] for an example of how to correctly use
     /// this API.
-    pub(super) unsafe fn new(instance: NonNull<Instance>, instance_layout: Layout) -> Self {
+    pub(super) unsafe fn new(
+        instance: NonNull<Instance>,
+        instance_layout: Layout,
+        slot: Option<PoolSlot>,
+    ) -> Self {
         Self(Arc::new(InstanceInner {
             instance_layout,
+            slot,
             instance,
         }))
     }
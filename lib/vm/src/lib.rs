@@ -20,21 +20,29 @@
     )
 )]
 
+mod epoch;
+mod exception;
 mod export;
 mod func_data_registry;
 mod global;
 mod imports;
 mod instance;
 mod memory;
+mod memory_image;
 mod mmap;
+mod pool;
 mod probestack;
 mod sig_registry;
 mod table;
+mod tail_call;
 mod trap;
 mod vmcontext;
+mod wait_queue;
 
 pub mod libcalls;
 
+pub use crate::epoch::{EpochCounter, EpochDeadline};
+pub use crate::exception::{Tag, VMException};
 pub use crate::export::*;
 pub use crate::func_data_registry::{FuncDataRegistry, VMFuncRef};
 pub use crate::global::*;
@@ -44,7 +52,9 @@ pub use crate::instance::{
     WeakOrStrongInstanceRef,
 };
 pub use crate::memory::{LinearMemory, Memory, MemoryError};
+pub use crate::memory_image::{MemoryImage, MemoryImages};
 pub use crate::mmap::Mmap;
+pub use crate::pool::{PoolingAllocator, PoolingLimits};
 pub use crate::probestack::PROBESTACK;
 pub use crate::sig_registry::SignatureRegistry;
 pub use crate::table::{LinearTable, Table, TableElement};
@@ -54,6 +64,7 @@ pub use crate::vmcontext::{
     VMFunctionImport, VMFunctionKind, VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition,
     VMMemoryImport, VMSharedSignatureIndex, VMTableDefinition, VMTableImport, VMTrampoline,
 };
+pub use crate::wait_queue::{WaitQueue, WaitResult};
 pub use wasmer_types::LibCall;
 pub use wasmer_types::MemoryStyle;
 pub use wasmer_types::TableStyle;
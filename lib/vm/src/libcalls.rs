@@ -37,16 +37,19 @@
 
 #![allow(missing_docs)] // For some reason lint fails saying that `LibCall` is not documented, when it actually is
 
+use crate::exception::{stack_pointer, VMException};
 use crate::func_data_registry::VMFuncRef;
 use crate::probestack::PROBESTACK;
 use crate::table::{RawTableElement, TableElement};
-use crate::trap::{raise_lib_trap, Trap, TrapCode};
+use crate::tail_call;
+use crate::trap::{raise_lib_trap, with_activation, Trap, TrapCode};
 use crate::vmcontext::VMContext;
 use crate::{on_host_stack, VMExternRef};
+use std::sync::Arc;
 pub use wasmer_types::LibCall;
 use wasmer_types::{
-    DataIndex, ElemIndex, FunctionIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
-    TableIndex, Type,
+    DataIndex, ElemIndex, FunctionIndex, LocalFunctionIndex, LocalMemoryIndex, LocalTableIndex,
+    MemoryIndex, SignatureIndex, TableIndex, TagIndex, Type,
 };
 
 /// Implementation of f32.ceil
@@ -583,6 +586,32 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_copy(
     }
 }
 
+/// Implementation of `memory.copy` between two different memories, which
+/// are indexed like in the module, imported memories first.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory32_copy_between(
+    vmctx: *mut VMContext,
+    dst_memory_index: u32,
+    src_memory_index: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
+) {
+    let result = {
+        let dst_memory_index = MemoryIndex::from_u32(dst_memory_index);
+        let src_memory_index = MemoryIndex::from_u32(src_memory_index);
+        let instance = (&*vmctx).instance();
+        instance.memory_copy_between(dst_memory_index, src_memory_index, dst, src, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
 /// Implementation of `memory.fill` for locally defined memories.
 ///
 /// # Safety
@@ -629,6 +658,148 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_fill(
     }
 }
 
+/// Implementation of `memory.atomic.wait32` for locally defined memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory32_atomic_wait32(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u32,
+    val: u32,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_atomic_wait32(memory_index, dst, val, timeout)
+    };
+    match result {
+        Ok(result) => result,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.wait32` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory32_atomic_wait32(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u32,
+    val: u32,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_atomic_wait32(memory_index, dst, val, timeout)
+    };
+    match result {
+        Ok(result) => result,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.wait64` for locally defined memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory32_atomic_wait64(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u32,
+    val: u64,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_atomic_wait64(memory_index, dst, val, timeout)
+    };
+    match result {
+        Ok(result) => result,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.wait64` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory32_atomic_wait64(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u32,
+    val: u64,
+    timeout: i64,
+) -> u32 {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_atomic_wait64(memory_index, dst, val, timeout)
+    };
+    match result {
+        Ok(result) => result,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.notify` for locally defined memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_memory32_atomic_notify(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u32,
+    count: u32,
+) -> u32 {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_atomic_notify(memory_index, dst, count)
+    };
+    match result {
+        Ok(result) => result,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
+/// Implementation of `memory.atomic.notify` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_imported_memory32_atomic_notify(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    dst: u32,
+    count: u32,
+) -> u32 {
+    let result = {
+        let memory_index = MemoryIndex::from_u32(memory_index);
+        let instance = (&*vmctx).instance();
+        instance.imported_memory_atomic_notify(memory_index, dst, count)
+    };
+    match result {
+        Ok(result) => result,
+        Err(trap) => raise_lib_trap(trap),
+    }
+}
+
 /// Implementation of `memory.init`.
 ///
 /// # Safety
@@ -668,6 +839,162 @@ pub unsafe extern "C" fn wasmer_vm_data_drop(vmctx: *mut VMContext, data_index:
     })
 }
 
+/// Implementation of `throw`.
+///
+/// The payload of the exception is read from `values`, one value every 16
+/// bytes.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must hold as many values as
+/// the tag has parameters.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_throw(vmctx: *mut VMContext, tag_index: u32, values: *const u8) {
+    on_host_stack(|| {
+        let tag_index = TagIndex::from_u32(tag_index);
+        let instance = (&*vmctx).instance();
+        instance.throw(tag_index, values)
+    })
+}
+
+/// Implementation of `rethrow`.
+///
+/// # Safety
+///
+/// `exception` must have been returned by [`wasmer_vm_catch`] or
+/// [`wasmer_vm_catch_all`] for a catch clause that is still running.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_rethrow(_vmctx: *mut VMContext, exception: *const VMException) {
+    on_host_stack(|| with_activation(|activation| activation.exceptions.rethrow(exception)))
+}
+
+/// Implementation of `catch`.
+///
+/// Returns the pending exception and writes its payload to `values` if it
+/// was thrown with tag `tag_index`, and returns null otherwise. The catch
+/// clause is identified by `slot`, a stack slot of the catching function
+/// that no other clause uses.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must have room for as many
+/// values as the tag has parameters.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_catch(
+    vmctx: *mut VMContext,
+    tag_index: u32,
+    values: *mut u8,
+    slot: *mut u8,
+) -> *const VMException {
+    let stack_pointer = stack_pointer();
+    on_host_stack(|| {
+        let tag_index = TagIndex::from_u32(tag_index);
+        let instance = (&*vmctx).instance();
+        instance.catch(tag_index, values, slot as usize, stack_pointer)
+    })
+}
+
+/// Implementation of `catch_all`.
+///
+/// Returns the pending exception. The catch clause is identified by `slot`,
+/// like for [`wasmer_vm_catch`].
+#[no_mangle]
+pub extern "C" fn wasmer_vm_catch_all(_vmctx: *mut VMContext, slot: *mut u8) -> *const VMException {
+    let stack_pointer = stack_pointer();
+    on_host_stack(|| {
+        let exception = with_activation(|activation| {
+            activation
+                .exceptions
+                .catch(None, slot as usize, stack_pointer)
+        });
+        exception.map_or(std::ptr::null(), |exception| Arc::as_ptr(&exception))
+    })
+}
+
+/// Returns whether an exception is pending in the call from the host into
+/// wasm that runs, which compiled code asks after a call when the number of
+/// calls with an exception pending isn't zero.
+#[no_mangle]
+pub extern "C" fn wasmer_vm_exception_pending(_vmctx: *mut VMContext) -> u32 {
+    on_host_stack(|| with_activation(|activation| activation.exceptions.is_pending() as u32))
+}
+
+/// Implementation of `return_call`.
+///
+/// The arguments of the call are read from `values`, one value every 16
+/// bytes.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must hold as many values as
+/// the function has parameters.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_return_call(
+    vmctx: *mut VMContext,
+    function_index: u32,
+    values: *const u8,
+) {
+    let function_index = FunctionIndex::from_u32(function_index);
+    let instance = (&*vmctx).instance();
+    instance.return_call(function_index, values)
+}
+
+/// Implementation of `return_call_indirect`.
+///
+/// The arguments of the call are read from `values`, one value every 16
+/// bytes.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable, and `values` must hold as many values as
+/// the signature has parameters.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_return_call_indirect(
+    vmctx: *mut VMContext,
+    table_index: u32,
+    sig_index: u32,
+    elem_index: u32,
+    values: *const u8,
+) {
+    let table_index = TableIndex::from_u32(table_index);
+    let sig_index = SignatureIndex::from_u32(sig_index);
+    let instance = (&*vmctx).instance();
+    if let Err(trap) = instance.return_call_indirect(table_index, sig_index, elem_index, values) {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Returns whether the local function at `function_index`, which makes tail
+/// calls and is entering, was called by the loop that makes the tail calls of
+/// the running call. The function then leaves the tail calls it requests to
+/// that loop, and makes them with [`wasmer_vm_tail_call`] otherwise.
+///
+/// # Safety
+///
+/// `vmctx` must be dereferenceable.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_tail_call_entry(
+    vmctx: *mut VMContext,
+    function_index: u32,
+) -> u32 {
+    let function_index = LocalFunctionIndex::from_u32(function_index);
+    let instance = (&*vmctx).instance();
+    on_host_stack(|| instance.tail_call_entry(function_index) as u32)
+}
+
+/// Makes the tail call that the function requested, and the tail calls
+/// requested in turn, and writes the results of the last one to `results`,
+/// one value every 16 bytes.
+///
+/// # Safety
+///
+/// Must be called right after the function requested the tail call, and
+/// `results` must have room for its results.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_vm_tail_call(_vmctx: *mut VMContext, results: *mut u8) {
+    tail_call::run(results)
+}
+
 /// Implementation for raising a trap
 ///
 /// # Safety
@@ -717,9 +1044,25 @@ pub fn function_pointer(libcall: LibCall) -> usize {
         LibCall::ElemDrop => wasmer_vm_elem_drop as usize,
         LibCall::Memory32Copy => wasmer_vm_memory32_copy as usize,
         LibCall::ImportedMemory32Copy => wasmer_vm_imported_memory32_copy as usize,
+        LibCall::Memory32CopyBetween => wasmer_vm_memory32_copy_between as usize,
+        LibCall::ExceptionPending => wasmer_vm_exception_pending as usize,
+        LibCall::TailCallEntry => wasmer_vm_tail_call_entry as usize,
         LibCall::Memory32Fill => wasmer_vm_memory32_fill as usize,
         LibCall::ImportedMemory32Fill => wasmer_vm_memory32_fill as usize,
         LibCall::Memory32Init => wasmer_vm_memory32_init as usize,
+        LibCall::Memory32AtomicWait32 => wasmer_vm_memory32_atomic_wait32 as usize,
+        LibCall::ImportedMemory32AtomicWait32 => wasmer_vm_imported_memory32_atomic_wait32 as usize,
+        LibCall::Memory32AtomicWait64 => wasmer_vm_memory32_atomic_wait64 as usize,
+        LibCall::ImportedMemory32AtomicWait64 => wasmer_vm_imported_memory32_atomic_wait64 as usize,
+        LibCall::Memory32AtomicNotify => wasmer_vm_memory32_atomic_notify as usize,
+        LibCall::ImportedMemory32AtomicNotify => wasmer_vm_imported_memory32_atomic_notify as usize,
+        LibCall::Throw => wasmer_vm_throw as usize,
+        LibCall::Rethrow => wasmer_vm_rethrow as usize,
+        LibCall::Catch => wasmer_vm_catch as usize,
+        LibCall::CatchAll => wasmer_vm_catch_all as usize,
+        LibCall::ReturnCall => wasmer_vm_return_call as usize,
+        LibCall::ReturnCallIndirect => wasmer_vm_return_call_indirect as usize,
+        LibCall::TailCall => wasmer_vm_tail_call as usize,
         LibCall::DataDrop => wasmer_vm_data_drop as usize,
         LibCall::Probestack => wasmer_vm_probestack as usize,
         LibCall::RaiseTrap => wasmer_vm_raise_trap as usize,
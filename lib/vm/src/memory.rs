@@ -5,8 +5,11 @@
 //!
 //! `LinearMemory` is to WebAssembly linear memories what `Table` is to WebAssembly tables.
 
+use crate::memory_image::MemoryImage;
 use crate::mmap::Mmap;
+use crate::pool::PoolSlot;
 use crate::vmcontext::VMMemoryDefinition;
+use crate::wait_queue::WaitQueue;
 use more_asserts::assert_ge;
 use std::borrow::BorrowMut;
 use std::cell::UnsafeCell;
@@ -77,6 +80,19 @@ pub trait Memory: fmt::Debug + Send + Sync {
     ///
     /// The pointer returned in [`VMMemoryDefinition`] must be valid for the lifetime of this memory.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition>;
+
+    /// Returns the queue of threads waiting on this memory with
+    /// `memory.atomic.wait`, or `None` if the memory doesn't support waiting.
+    fn wait_queue(&self) -> Option<&WaitQueue> {
+        None
+    }
+
+    /// Initializes the memory with a copy-on-write mapping of `image`, and
+    /// returns whether it did. The data segments of a memory that doesn't map
+    /// the image are copied into it instead.
+    fn initialize_with_image(&self, _image: &MemoryImage) -> Result<bool, MemoryError> {
+        Ok(false)
+    }
 }
 
 /// A linear memory instance.
@@ -100,6 +116,9 @@ pub struct LinearMemory {
 
     /// The owned memory definition used by the generated code
     vm_memory_definition: VMMemoryDefinitionOwnership,
+
+    /// The threads waiting on this memory with `memory.atomic.wait`.
+    wait_queue: WaitQueue,
 }
 
 /// A type to help manage who is responsible for the backing memory of them
@@ -130,18 +149,64 @@ unsafe impl Sync for LinearMemory {}
 #[derive(Debug)]
 struct WasmMmap {
     // Our OS allocation of mmap'd memory.
-    alloc: Mmap,
+    alloc: MemoryAlloc,
     // The current logical size in wasm pages of this linear memory.
     size: Pages,
 }
 
+/// The memory backing a linear memory.
+#[derive(Debug)]
+enum MemoryAlloc {
+    /// A mapping of its own.
+    Mmap(Mmap),
+    /// A slot of a `PoolingAllocator`.
+    Pooled(PoolSlot),
+}
+
+impl MemoryAlloc {
+    fn len(&self) -> usize {
+        match self {
+            Self::Mmap(mmap) => mmap.len(),
+            Self::Pooled(slot) => slot.len(),
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Mmap(mmap) => mmap.as_slice(),
+            Self::Pooled(slot) => slot.as_slice(),
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        match self {
+            Self::Mmap(mmap) => mmap.as_mut_ptr(),
+            Self::Pooled(slot) => slot.as_mut_ptr(),
+        }
+    }
+
+    fn make_accessible(&mut self, start: usize, len: usize) -> Result<(), String> {
+        match self {
+            Self::Mmap(mmap) => mmap.make_accessible(start, len),
+            Self::Pooled(slot) => slot.make_accessible(start, len),
+        }
+    }
+
+    unsafe fn map_image(&mut self, image: &MemoryImage) -> Result<(), String> {
+        match self {
+            Self::Mmap(mmap) => image.map_at(mmap.as_mut_ptr()),
+            Self::Pooled(slot) => slot.map_image(image),
+        }
+    }
+}
+
 impl LinearMemory {
     /// Create a new linear memory instance with specified minimum and maximum number of wasm pages.
     ///
     /// This creates a `LinearMemory` with owned metadata: this can be used to create a memory
     /// that will be imported into Wasm modules.
     pub fn new(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
-        unsafe { Self::new_internal(memory, style, None) }
+        unsafe { Self::new_internal(memory, style, None, None) }
     }
 
     /// Create a new linear memory instance with specified minimum and maximum number of wasm pages.
@@ -156,14 +221,32 @@ impl LinearMemory {
         style: &MemoryStyle,
         vm_memory_location: NonNull<VMMemoryDefinition>,
     ) -> Result<Self, MemoryError> {
-        Self::new_internal(memory, style, Some(vm_memory_location))
+        Self::new_internal(memory, style, Some(vm_memory_location), None)
     }
 
-    /// Build a `LinearMemory` with either self-owned or VM owned metadata.
+    /// Create a new linear memory instance in a slot of a `PoolingAllocator`, with
+    /// metadata owned by a VM.
+    ///
+    /// The memory can't grow beyond the static bound of its style.
+    ///
+    /// # Safety
+    /// - `vm_memory_location` must point to a valid location in VM memory.
+    pub(crate) unsafe fn from_pool_slot(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        vm_memory_location: NonNull<VMMemoryDefinition>,
+        slot: PoolSlot,
+    ) -> Result<Self, MemoryError> {
+        Self::new_internal(memory, style, Some(vm_memory_location), Some(slot))
+    }
+
+    /// Build a `LinearMemory` with either self-owned or VM owned metadata, in its own
+    /// mapping or in a pool slot.
     unsafe fn new_internal(
         memory: &MemoryType,
         style: &MemoryStyle,
         vm_memory_location: Option<NonNull<VMMemoryDefinition>>,
+        slot: Option<PoolSlot>,
     ) -> Result<Self, MemoryError> {
         if memory.minimum > Pages::max_value() {
             return Err(MemoryError::MinimumMemoryTooLarge {
@@ -189,9 +272,18 @@ impl LinearMemory {
             }
         }
 
+        if memory.shared && memory.maximum.is_none() {
+            return Err(MemoryError::InvalidMemory {
+                reason: "shared memories must have a maximum size".to_string(),
+            });
+        }
+
         let offset_guard_bytes = style.offset_guard_size() as usize;
 
         let minimum_pages = match style {
+            // Shared memories may be accessed from other threads while they
+            // grow, so they reserve their maximum size and never move.
+            MemoryStyle::Dynamic { .. } if memory.shared => memory.maximum.unwrap(),
             MemoryStyle::Dynamic { .. } => memory.minimum,
             MemoryStyle::Static { bound, .. } => {
                 assert_ge!(*bound, memory.minimum);
@@ -203,9 +295,39 @@ impl LinearMemory {
         let mapped_pages = memory.minimum;
         let mapped_bytes = mapped_pages.bytes();
 
+        let (alloc, maximum) = match slot {
+            Some(mut slot) => {
+                let bound = match style {
+                    MemoryStyle::Static { bound, .. } => *bound,
+                    MemoryStyle::Dynamic { .. } => {
+                        return Err(MemoryError::InvalidMemory {
+                            reason: "pooled memories must have a static style".to_string(),
+                        })
+                    }
+                };
+                if request_bytes > slot.len() {
+                    return Err(MemoryError::InvalidMemory {
+                        reason: format!(
+                            "it needs {} bytes, but the memory slots of the pool are {} bytes",
+                            request_bytes,
+                            slot.len()
+                        ),
+                    });
+                }
+                slot.make_accessible(0, mapped_bytes.0)
+                    .map_err(MemoryError::Region)?;
+                // Growing beyond the bound would move the memory out of its slot.
+                let maximum = memory.maximum.map_or(bound, |maximum| maximum.min(bound));
+                (MemoryAlloc::Pooled(slot), Some(maximum))
+            }
+            None => {
+                let mmap = Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
+                    .map_err(MemoryError::Region)?;
+                (MemoryAlloc::Mmap(mmap), memory.maximum)
+            }
+        };
         let mut mmap = WasmMmap {
-            alloc: Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
-                .map_err(MemoryError::Region)?,
+            alloc,
             size: memory.minimum,
         };
 
@@ -213,7 +335,7 @@ impl LinearMemory {
         let mem_length = memory.minimum.bytes().0;
         Ok(Self {
             mmap: Mutex::new(mmap),
-            maximum: memory.maximum,
+            maximum,
             offset_guard_size: offset_guard_bytes,
             vm_memory_definition: if let Some(mem_loc) = vm_memory_location {
                 {
@@ -233,6 +355,7 @@ impl LinearMemory {
             },
             memory: *memory,
             style: style.clone(),
+            wait_queue: WaitQueue::new(),
         })
     }
 
@@ -339,7 +462,7 @@ impl Memory for LinearMemory {
             let copy_len = mmap.alloc.len() - self.offset_guard_size;
             new_mmap.as_mut_slice()[..copy_len].copy_from_slice(&mmap.alloc.as_slice()[..copy_len]);
 
-            mmap.alloc = new_mmap;
+            mmap.alloc = MemoryAlloc::Mmap(new_mmap);
         } else if delta_bytes > 0 {
             // Make the newly allocated pages accessible.
             mmap.alloc
@@ -365,4 +488,24 @@ impl Memory for LinearMemory {
         let _mmap_guard = self.mmap.lock().unwrap();
         unsafe { self.get_vm_memory_definition() }
     }
+
+    /// Only shared memories can be waited on.
+    fn wait_queue(&self) -> Option<&WaitQueue> {
+        if self.memory.shared {
+            Some(&self.wait_queue)
+        } else {
+            None
+        }
+    }
+
+    /// Map `image` over the accessible pages of the memory.
+    fn initialize_with_image(&self, image: &MemoryImage) -> Result<bool, MemoryError> {
+        let mut mmap_guard = self.mmap.lock().unwrap();
+        let mmap = mmap_guard.borrow_mut();
+        if image.end() > mmap.size.bytes().0 {
+            return Ok(false);
+        }
+        unsafe { mmap.alloc.map_image(image) }.map_err(MemoryError::Region)?;
+        Ok(true)
+    }
 }
@@ -0,0 +1,312 @@
+//! Copy-on-write images of the initial contents of linear memories.
+//!
+//! The active data segments of a module are laid out once, page-aligned, in
+//! an in-memory file when the module is first instantiated. Instantiation
+//! then maps the image copy-on-write into each new memory instead of copying
+//! the segments, which takes the same time whatever the size of the data.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::{DataInitializer, LocalMemoryIndex, ModuleInfo, OwnedDataInitializer};
+
+/// The amount of data below which a memory has no image: copying it is
+/// cheaper than creating and mapping the image.
+const MIN_IMAGE_DATA: usize = 64 * 1024;
+
+/// The initial contents of a memory, mappable copy-on-write.
+#[derive(Debug)]
+pub struct MemoryImage {
+    /// The in-memory file holding the image.
+    file: std::fs::File,
+    /// The offset of the image in the memory, page-aligned.
+    offset: usize,
+    /// The size of the image, page-aligned.
+    len: usize,
+}
+
+impl MemoryImage {
+    /// Lays out the `(offset, data)` segments in a new image, later segments
+    /// overwriting earlier ones.
+    ///
+    /// Returns `None` if there is no data.
+    #[cfg(target_os = "linux")]
+    fn new(segments: &[(usize, &[u8])]) -> io::Result<Option<Self>> {
+        use crate::mmap::round_up_to_page_size;
+        use std::os::unix::fs::FileExt;
+        use std::os::unix::io::FromRawFd;
+
+        let segments = segments
+            .iter()
+            .filter(|(_, data)| !data.is_empty())
+            .collect::<Vec<_>>();
+        let page_size = region::page::size();
+        let start = match segments.iter().map(|(offset, _)| *offset).min() {
+            Some(start) => start / page_size * page_size,
+            None => return Ok(None),
+        };
+        let end = segments
+            .iter()
+            .map(|(offset, data)| offset + data.len())
+            .max()
+            .unwrap();
+        let end = round_up_to_page_size(end, page_size);
+
+        let fd =
+            unsafe { libc::memfd_create(b"wasm-memory-image\0".as_ptr() as _, libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = unsafe { std::fs::File::from_raw_fd(fd) };
+        file.set_len((end - start) as u64)?;
+        for (offset, data) in segments {
+            file.write_all_at(data, (offset - start) as u64)?;
+        }
+
+        Ok(Some(Self {
+            file,
+            offset: start,
+            len: end - start,
+        }))
+    }
+
+    /// Memory images need `memfd_create`, other platforms copy the data
+    /// segments on every instantiation.
+    #[cfg(not(target_os = "linux"))]
+    fn new(_segments: &[(usize, &[u8])]) -> io::Result<Option<Self>> {
+        Ok(None)
+    }
+
+    /// Returns the offset in the memory of the end of the image.
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    /// Maps the image copy-on-write into the memory at `base`.
+    ///
+    /// # Safety
+    /// - `base` must be page-aligned, and the memory must be a mapping of at
+    ///   least [`MemoryImage::end`] accessible bytes.
+    #[cfg(not(target_os = "windows"))]
+    pub(crate) unsafe fn map_at(&self, base: *mut u8) -> Result<(), String> {
+        use std::os::unix::io::AsRawFd;
+
+        let ptr = libc::mmap(
+            base.add(self.offset) as *mut libc::c_void,
+            self.len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_FIXED,
+            self.file.as_raw_fd(),
+            0,
+        );
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        Ok(())
+    }
+
+    /// Maps the image copy-on-write into the memory at `base`.
+    ///
+    /// # Safety
+    /// - `base` must be page-aligned, and the memory must be a mapping of at
+    ///   least [`MemoryImage::end`] accessible bytes.
+    #[cfg(target_os = "windows")]
+    pub(crate) unsafe fn map_at(&self, _base: *mut u8) -> Result<(), String> {
+        Err("memory images aren't supported on Windows".to_string())
+    }
+}
+
+/// The images of the local memories of a module.
+#[derive(Debug, Default)]
+pub struct MemoryImages {
+    /// The indices of the data initializers making up the image of each
+    /// local memory, `None` if the memory has no image.
+    segments: PrimaryMap<LocalMemoryIndex, Option<Vec<usize>>>,
+    /// The images, created by the first instantiation.
+    images: Mutex<Option<PrimaryMap<LocalMemoryIndex, Option<Arc<MemoryImage>>>>>,
+}
+
+impl MemoryImages {
+    /// Picks the local memories of `module` that get an image built from
+    /// their `data_initializers`, the images themselves are only created
+    /// when the module is first instantiated.
+    ///
+    /// A memory has no image if one of its segments has an offset based on a
+    /// global, or doesn't fit in its minimum size: its segments are copied at
+    /// instantiation, which reports the out of bounds ones. Neither has a
+    /// memory with little data.
+    pub fn new(module: &ModuleInfo, data_initializers: &[OwnedDataInitializer]) -> Self {
+        let mut segments = module
+            .memories
+            .keys()
+            .skip(module.num_imported_memories)
+            .map(|_| Some(Vec::new()))
+            .collect::<PrimaryMap<LocalMemoryIndex, _>>();
+        for (index, init) in data_initializers.iter().enumerate() {
+            let memory_index = init.location.memory_index;
+            let local_memory_index = match module.local_memory_index(memory_index) {
+                Some(local_memory_index) => local_memory_index,
+                None => continue,
+            };
+            let minimum = module.memories[memory_index].minimum.bytes().0;
+            let fits = init.location.base.is_none()
+                && init
+                    .location
+                    .offset
+                    .checked_add(init.data.len())
+                    .map_or(false, |end| end <= minimum);
+            match &mut segments[local_memory_index] {
+                Some(segments) if fits => segments.push(index),
+                segments => *segments = None,
+            }
+        }
+
+        for (_, segments) in segments.iter_mut() {
+            let len = segments.iter().flatten().fold(0usize, |len, &index| {
+                len.saturating_add(data_initializers[index].data.len())
+            });
+            if len < MIN_IMAGE_DATA {
+                *segments = None;
+            }
+        }
+
+        Self {
+            segments,
+            images: Mutex::new(None),
+        }
+    }
+
+    /// Returns the image of a local memory, if it has one.
+    ///
+    /// The images are created by the first call, from `data_initializers`,
+    /// which must be those [`MemoryImages::new`] was given.
+    pub fn get(
+        &self,
+        index: LocalMemoryIndex,
+        data_initializers: &[DataInitializer<'_>],
+    ) -> Option<Arc<MemoryImage>> {
+        let mut images = self.images.lock().unwrap();
+        let images = images.get_or_insert_with(|| self.create(data_initializers));
+        images.get(index).cloned().flatten()
+    }
+
+    fn create(
+        &self,
+        data_initializers: &[DataInitializer<'_>],
+    ) -> PrimaryMap<LocalMemoryIndex, Option<Arc<MemoryImage>>> {
+        self.segments
+            .iter()
+            .map(|(index, segments)| {
+                let segments = segments
+                    .as_ref()?
+                    .iter()
+                    .map(|&i| (data_initializers[i].location.offset, data_initializers[i].data))
+                    .collect::<Vec<_>>();
+                match MemoryImage::new(&segments) {
+                    Ok(image) => image.map(Arc::new),
+                    Err(e) => {
+                        // Failing to create an image only makes instantiation
+                        // slower.
+                        tracing::warn!(
+                            "failed to create the image of memory {}, its data is copied instead: {}",
+                            index.index(),
+                            e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::memory::{LinearMemory, Memory};
+    use wasmer_types::{
+        DataInitializerLocation, GlobalIndex, MemoryIndex, MemoryStyle, MemoryType, Pages,
+    };
+
+    fn data(memory_index: u32, offset: usize, data: &[u8]) -> OwnedDataInitializer {
+        OwnedDataInitializer {
+            location: DataInitializerLocation {
+                memory_index: MemoryIndex::from_u32(memory_index),
+                base: None,
+                offset,
+            },
+            data: data.into(),
+        }
+    }
+
+    fn borrow(data_initializers: &[OwnedDataInitializer]) -> Vec<DataInitializer<'_>> {
+        data_initializers
+            .iter()
+            .map(|init| DataInitializer {
+                location: init.location.clone(),
+                data: &init.data,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn images_are_mapped_copy_on_write() {
+        let mut module = ModuleInfo::new();
+        let ty = MemoryType::new(2, None, false);
+        module.memories.push(ty);
+        let data_initializers = [
+            data(0, 0, &[1; MIN_IMAGE_DATA]),
+            data(0, 0x1_0010, b"hello"),
+            data(0, 0x1_0012, b"LLO!"),
+        ];
+        let images = MemoryImages::new(&module, &data_initializers);
+        let image = images
+            .get(LocalMemoryIndex::from_u32(0), &borrow(&data_initializers))
+            .unwrap();
+        assert_eq!(image.offset, 0);
+        assert_eq!(image.end(), 0x1_0000 + region::page::size());
+
+        let style = MemoryStyle::Dynamic {
+            offset_guard_size: 0,
+        };
+        let first = LinearMemory::new(&ty, &style).unwrap();
+        let second = LinearMemory::new(&ty, &style).unwrap();
+        assert!(first.initialize_with_image(&image).unwrap());
+        assert!(second.initialize_with_image(&image).unwrap());
+
+        unsafe {
+            let first = first.vmmemory().as_ref().base;
+            let second = second.vmmemory().as_ref().base;
+            let bytes = std::slice::from_raw_parts(first.add(0x1_0010), 7);
+            assert_eq!(bytes, b"heLLO!\0");
+            *first.add(0x1_0010) = b'j';
+            assert_eq!(*second.add(0x1_0010), b'h', "writes are private");
+        }
+        assert_eq!(first.grow(Pages(1)).unwrap(), Pages(2));
+    }
+
+    #[test]
+    fn unmappable_segments() {
+        let mut module = ModuleInfo::new();
+        for _ in 0..4 {
+            module.memories.push(MemoryType::new(1, None, false));
+        }
+        let filler = [0; MIN_IMAGE_DATA];
+        let mut based = data(1, 0, &filler);
+        based.location.base = Some(GlobalIndex::from_u32(0));
+        let data_initializers = [
+            data(0, 0, &filler),
+            data(0, 0x1_0000, b"out of bounds"),
+            based,
+            data(2, 0, b""),
+            data(3, 0, b"too little data"),
+        ];
+        let images = MemoryImages::new(&module, &data_initializers);
+        let data_initializers = borrow(&data_initializers);
+        for index in 0..4 {
+            assert!(images
+                .get(LocalMemoryIndex::from_u32(index), &data_initializers)
+                .is_none());
+        }
+    }
+}
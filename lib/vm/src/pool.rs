@@ -0,0 +1,551 @@
+//! Pooling allocation of instances, with their memories and tables.
+//!
+//! A [`PoolingAllocator`] reserves the address space of all its slots when
+//! it's created, so that instantiating a module doesn't map or unmap any
+//! memory: a slot is reset when what it holds is dropped, and it's reused by
+//! a later instantiation.
+
+use crate::memory::{LinearMemory, MemoryError};
+use crate::memory_image::MemoryImage;
+use crate::mmap::{round_up_to_page_size, Mmap};
+use crate::table::{LinearTable, RawTableElement};
+use crate::vmcontext::{VMMemoryDefinition, VMTableDefinition};
+use more_asserts::assert_le;
+use std::alloc::Layout;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+use wasmer_types::{MemoryStyle, MemoryType, ModuleInfo, Pages, TableStyle, TableType};
+
+/// The limits of a [`PoolingAllocator`], fixed when it's created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolingLimits {
+    /// The maximum number of instances allocated at the same time.
+    pub instances: u32,
+
+    /// The maximum number of memories defined by an instance.
+    pub memories: u32,
+
+    /// The maximum number of tables defined by an instance.
+    pub tables: u32,
+
+    /// The size in bytes of an instance slot, which holds the `Instance` and
+    /// its `VMContext`.
+    pub instance_size: usize,
+
+    /// The size in wasm pages of a memory slot. It's the static bound of the
+    /// pooled memories, which can't grow beyond it.
+    pub memory_pages: Pages,
+
+    /// The size in bytes of the guard after each memory slot.
+    pub memory_offset_guard_size: u64,
+
+    /// The maximum number of elements of a pooled table.
+    pub table_elements: u32,
+}
+
+impl Default for PoolingLimits {
+    /// The default pool reserves about 3.2 GiB of address space on 64-bit
+    /// hosts, and 0.9 GiB on 32-bit hosts, so that it fits common `ulimit -v`
+    /// and overcommit settings. Pools with memory slots large enough to elide
+    /// bounds checks, like the static memories of `BaseTunables`, need 6 GiB
+    /// per memory slot.
+    fn default() -> Self {
+        #[cfg(target_pointer_width = "64")]
+        let memory_pages = Pages(0x200);
+        #[cfg(not(target_pointer_width = "64"))]
+        let memory_pages = Pages(0x80);
+
+        Self {
+            instances: 100,
+            memories: 1,
+            tables: 1,
+            instance_size: 0x10_0000,
+            memory_pages,
+            memory_offset_guard_size: 0x1_0000,
+            table_elements: 10_000,
+        }
+    }
+}
+
+/// An allocator of instances, memories and tables from pools of fixed-size
+/// slots, reserved up front.
+///
+/// The number of slots caps the number of instances alive at the same time:
+/// once all of them are in use, instantiation fails until an instance is
+/// dropped.
+///
+/// Cloning a `PoolingAllocator` shares its pools.
+#[derive(Debug, Clone)]
+pub struct PoolingAllocator {
+    limits: PoolingLimits,
+    instances: Arc<SlotPool>,
+    memories: Arc<SlotPool>,
+    tables: Arc<TablePool>,
+}
+
+impl PoolingAllocator {
+    /// Reserves the slots of a new pool with the given `limits`.
+    pub fn new(limits: PoolingLimits) -> Result<Self, MemoryError> {
+        let instances = limits.instances as usize;
+        let memory_slot_size = limits
+            .memory_pages
+            .bytes()
+            .0
+            .checked_add(limits.memory_offset_guard_size as usize)
+            .ok_or_else(|| MemoryError::Region("the memory slots are too large".to_string()))?;
+
+        Ok(Self {
+            instances: Arc::new(SlotPool::new(instances, limits.instance_size)?),
+            memories: Arc::new(SlotPool::new(
+                instances * limits.memories as usize,
+                memory_slot_size,
+            )?),
+            tables: Arc::new(TablePool::new(
+                instances * limits.tables as usize,
+                limits.table_elements,
+            )),
+            limits,
+        })
+    }
+
+    /// Returns the limits of the pool.
+    pub fn limits(&self) -> &PoolingLimits {
+        &self.limits
+    }
+
+    /// Returns the style of the memories allocated from the pool.
+    pub fn memory_style(&self) -> MemoryStyle {
+        MemoryStyle::Static {
+            bound: self.limits.memory_pages,
+            offset_guard_size: self.limits.memory_offset_guard_size,
+        }
+    }
+
+    /// Returns the number of instances currently allocated from the pool.
+    pub fn instances_in_use(&self) -> usize {
+        self.instances.in_use()
+    }
+
+    /// Creates a memory owned by the VM in a slot of the pool.
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid location in VM memory.
+    pub unsafe fn create_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<LinearMemory, MemoryError> {
+        let slot = self
+            .memories
+            .acquire()
+            .map_err(MemoryError::Region)?
+            .ok_or_else(|| {
+                MemoryError::Region(format!(
+                    "all the {} memory slots of the pool are in use",
+                    self.memories.count
+                ))
+            })?;
+        LinearMemory::from_pool_slot(ty, style, vm_definition_location, slot)
+    }
+
+    /// Creates a table owned by the VM in a slot of the pool.
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid location in VM memory.
+    pub unsafe fn create_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<LinearTable, String> {
+        LinearTable::from_pool(ty, style, vm_definition_location, &self.tables)
+    }
+
+    /// Acquires an instance slot for an instance of `module` with the given
+    /// `layout`, and makes it accessible.
+    pub(crate) fn allocate_instance(
+        &self,
+        module: &ModuleInfo,
+        layout: Layout,
+    ) -> Result<PoolSlot, String> {
+        let memories = module.memories.len() - module.num_imported_memories;
+        if memories > self.limits.memories as usize {
+            return Err(format!(
+                "the module defines {} memories, but the pool allows {} per instance",
+                memories, self.limits.memories
+            ));
+        }
+        let tables = module.tables.len() - module.num_imported_tables;
+        if tables > self.limits.tables as usize {
+            return Err(format!(
+                "the module defines {} tables, but the pool allows {} per instance",
+                tables, self.limits.tables
+            ));
+        }
+        if layout.size() > self.instances.slot_size || layout.align() > region::page::size() {
+            return Err(format!(
+                "the instance needs {} bytes, but the instance slots of the pool are {} bytes",
+                layout.size(),
+                self.instances.slot_size
+            ));
+        }
+
+        let mut slot = self.instances.acquire()?.ok_or_else(|| {
+            format!(
+                "all the {} instance slots of the pool are in use",
+                self.instances.count
+            )
+        })?;
+        slot.make_accessible(
+            0,
+            round_up_to_page_size(layout.size(), region::page::size()),
+        )?;
+        Ok(slot)
+    }
+}
+
+/// A pool of fixed-size slots in a single reserved mapping.
+#[derive(Debug)]
+struct SlotPool {
+    mapping: Mmap,
+    slot_size: usize,
+    count: usize,
+    /// The free slots, the most recently released last so that it's reused
+    /// first, while its pages are still warm. A slot that failed to be reset
+    /// when it was released comes with the size of its prefix to reset.
+    free: Mutex<Vec<(usize, usize)>>,
+}
+
+impl SlotPool {
+    fn new(count: usize, slot_size: usize) -> Result<Self, MemoryError> {
+        let slot_size = round_up_to_page_size(slot_size, region::page::size());
+        let mapping_size = slot_size
+            .checked_mul(count)
+            .ok_or_else(|| MemoryError::Region("the pool is too large".to_string()))?;
+        Ok(Self {
+            mapping: Mmap::accessible_reserved(0, mapping_size).map_err(MemoryError::Region)?,
+            slot_size,
+            count,
+            free: Mutex::new((0..count).rev().map(|index| (index, 0)).collect()),
+        })
+    }
+
+    /// Acquires a free slot, or returns `None` if all the slots are in use.
+    ///
+    /// A slot that failed to be reset is reset again first, it's put back
+    /// behind the other free slots if that fails too.
+    fn acquire(self: &Arc<Self>) -> Result<Option<PoolSlot>, String> {
+        let mut free = self.free.lock().unwrap();
+        let (index, dirty) = match free.pop() {
+            Some(slot) => slot,
+            None => return Ok(None),
+        };
+        if dirty != 0 {
+            let ptr = unsafe { self.mapping.as_ptr().add(index * self.slot_size) as *mut u8 };
+            if let Err(e) = unsafe { remap(ptr, dirty) } {
+                free.insert(0, (index, dirty));
+                return Err(format!("failed to reset pool slot {}: {}", index, e));
+            }
+        }
+        Ok(Some(PoolSlot {
+            pool: self.clone(),
+            index,
+            accessible: 0,
+            image_mapped: false,
+        }))
+    }
+
+    fn in_use(&self) -> usize {
+        self.count - self.free.lock().unwrap().len()
+    }
+}
+
+/// A slot acquired from a pool, released back to it when dropped.
+///
+/// The slot is inaccessible until [`PoolSlot::make_accessible`] is called,
+/// and it's reset to zeroed, inaccessible pages when it's released. A slot
+/// that fails to be reset is reset again before it's handed out.
+#[derive(Debug)]
+pub(crate) struct PoolSlot {
+    pool: Arc<SlotPool>,
+    index: usize,
+    /// The size of the accessible prefix of the slot.
+    accessible: usize,
+    /// Whether a memory image is mapped in the slot, which must then be
+    /// mapped again to be reset.
+    image_mapped: bool,
+}
+
+impl PoolSlot {
+    /// Returns the size in bytes of the slot.
+    pub(crate) fn len(&self) -> usize {
+        self.pool.slot_size
+    }
+
+    /// Returns a pointer to the start of the slot.
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        unsafe {
+            self.pool
+                .mapping
+                .as_ptr()
+                .add(self.index * self.pool.slot_size)
+        }
+    }
+
+    /// Returns a mutable pointer to the start of the slot.
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.as_ptr() as *mut u8
+    }
+
+    /// Returns the slot as a slice of u8.
+    ///
+    /// Only the accessible part of the slot can be read.
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+
+    /// Make the memory starting at `start` and extending for `len` bytes
+    /// accessible. `start` and `len` must be native page-size multiples and
+    /// describe a range within the slot.
+    pub(crate) fn make_accessible(&mut self, start: usize, len: usize) -> Result<(), String> {
+        assert_le!(start + len, self.len());
+        if len == 0 {
+            return Ok(());
+        }
+        unsafe { commit(self.as_mut_ptr().add(start), len)? };
+        self.accessible = self.accessible.max(start + len);
+        Ok(())
+    }
+
+    /// Maps `image` copy-on-write into the slot.
+    ///
+    /// # Safety
+    /// - The image must be within the accessible part of the slot.
+    pub(crate) unsafe fn map_image(&mut self, image: &MemoryImage) -> Result<(), String> {
+        assert_le!(image.end(), self.accessible);
+        self.image_mapped = true;
+        image.map_at(self.as_mut_ptr())
+    }
+}
+
+impl Drop for PoolSlot {
+    fn drop(&mut self) {
+        let mut dirty = 0;
+        if self.accessible != 0 {
+            let ptr = self.as_mut_ptr();
+            let r = if self.image_mapped {
+                unsafe { remap(ptr, self.accessible) }
+            } else {
+                // Replacing the pages is slower, but may work when
+                // discarding them didn't
+                unsafe { decommit(ptr, self.accessible).or_else(|_| remap(ptr, self.accessible)) }
+            };
+            // The slot may still hold the data of its instance, so it must
+            // be reset before it's handed to another one
+            if let Err(e) = r {
+                tracing::warn!(
+                    "failed to reset pool slot {}, it's reset again before reuse: {}",
+                    self.index,
+                    e
+                );
+                dirty = self.accessible;
+            }
+        }
+        self.pool.free.lock().unwrap().push((self.index, dirty));
+    }
+}
+
+/// Makes the `len` bytes at `ptr` readable and writable.
+#[cfg(not(target_os = "windows"))]
+unsafe fn commit(ptr: *mut u8, len: usize) -> Result<(), String> {
+    region::protect(ptr as *const u8, len, region::Protection::READ_WRITE)
+        .map_err(|e| e.to_string())
+}
+
+/// Makes the `len` bytes at `ptr` readable and writable.
+#[cfg(target_os = "windows")]
+unsafe fn commit(ptr: *mut u8, len: usize) -> Result<(), String> {
+    use winapi::ctypes::c_void;
+    use winapi::um::memoryapi::VirtualAlloc;
+    use winapi::um::winnt::{MEM_COMMIT, PAGE_READWRITE};
+
+    if VirtualAlloc(ptr as *mut c_void, len, MEM_COMMIT, PAGE_READWRITE).is_null() {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+/// Discards the `len` bytes at `ptr` and makes them inaccessible. They are
+/// zeroed when they're made accessible again.
+#[cfg(target_os = "linux")]
+unsafe fn decommit(ptr: *mut u8, len: usize) -> Result<(), String> {
+    if libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_DONTNEED) != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    region::protect(ptr as *const u8, len, region::Protection::NONE).map_err(|e| e.to_string())
+}
+
+/// Discards the `len` bytes at `ptr` and makes them inaccessible. They are
+/// zeroed when they're made accessible again.
+///
+/// `madvise(MADV_DONTNEED)` doesn't zero the pages on every platform, so
+/// they're mapped again instead.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+unsafe fn decommit(ptr: *mut u8, len: usize) -> Result<(), String> {
+    remap(ptr, len)
+}
+
+/// Replaces the `len` bytes at `ptr`, and any file mapped over them, with
+/// new inaccessible pages. They are zeroed when they're made accessible
+/// again.
+#[cfg(not(target_os = "windows"))]
+unsafe fn remap(ptr: *mut u8, len: usize) -> Result<(), String> {
+    let r = libc::mmap(
+        ptr as *mut libc::c_void,
+        len,
+        libc::PROT_NONE,
+        libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_FIXED,
+        -1,
+        0,
+    );
+    if r == libc::MAP_FAILED {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+/// Discards the `len` bytes at `ptr` and makes them inaccessible. They are
+/// zeroed when they're made accessible again.
+#[cfg(target_os = "windows")]
+unsafe fn decommit(ptr: *mut u8, len: usize) -> Result<(), String> {
+    use winapi::ctypes::c_void;
+    use winapi::um::memoryapi::VirtualFree;
+    use winapi::um::winnt::MEM_DECOMMIT;
+
+    if VirtualFree(ptr as *mut c_void, len, MEM_DECOMMIT) == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+/// Memory images aren't mapped on Windows, so decommitting is enough.
+#[cfg(target_os = "windows")]
+unsafe fn remap(ptr: *mut u8, len: usize) -> Result<(), String> {
+    decommit(ptr, len)
+}
+
+/// A pool of table buffers.
+///
+/// Tables are backed by a `Vec`, so a table slot is a buffer that keeps its
+/// capacity between uses instead of a range of reserved memory.
+#[derive(Debug)]
+pub(crate) struct TablePool {
+    elements: u32,
+    count: usize,
+    free: Mutex<Vec<Vec<RawTableElement>>>,
+}
+
+/// This is correct because the buffers are empty while they are in the pool.
+unsafe impl Send for TablePool {}
+/// This is correct because all internal mutability is protected by a mutex.
+unsafe impl Sync for TablePool {}
+
+impl TablePool {
+    fn new(count: usize, elements: u32) -> Self {
+        Self {
+            elements,
+            count,
+            free: Mutex::new((0..count).map(|_| Vec::new()).collect()),
+        }
+    }
+
+    /// Returns the maximum number of elements of a pooled table.
+    pub(crate) fn max_elements(&self) -> u32 {
+        self.elements
+    }
+
+    /// Acquires an empty buffer, with room for the maximum number of
+    /// elements.
+    pub(crate) fn acquire(&self) -> Result<Vec<RawTableElement>, String> {
+        let mut buffer =
+            self.free.lock().unwrap().pop().ok_or_else(|| {
+                format!("all the {} table slots of the pool are in use", self.count)
+            })?;
+        buffer.reserve_exact(self.elements as usize);
+        Ok(buffer)
+    }
+
+    /// Clears `buffer` and returns it to the pool.
+    pub(crate) fn release(&self, mut buffer: Vec<RawTableElement>) {
+        buffer.clear();
+        self.free.lock().unwrap().push(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_reset_and_reused() {
+        let pool = Arc::new(SlotPool::new(2, 1).unwrap());
+        let page_size = region::page::size();
+        assert_eq!(pool.slot_size, page_size);
+
+        let mut first = pool.acquire().unwrap().unwrap();
+        let second = pool.acquire().unwrap().unwrap();
+        assert!(pool.acquire().unwrap().is_none(), "the pool is exhausted");
+        assert_eq!(pool.in_use(), 2);
+        assert_eq!(
+            second.as_ptr() as usize - first.as_ptr() as usize,
+            page_size
+        );
+
+        first.make_accessible(0, page_size).unwrap();
+        unsafe { *first.as_mut_ptr() = 42 };
+        let ptr = first.as_ptr();
+        drop(first);
+        assert_eq!(pool.in_use(), 1);
+
+        let mut first = pool.acquire().unwrap().unwrap();
+        assert_eq!(first.as_ptr(), ptr, "the released slot is reused");
+        first.make_accessible(0, page_size).unwrap();
+        assert_eq!(unsafe { *first.as_ptr() }, 0, "the released slot is zeroed");
+    }
+
+    #[test]
+    fn slots_that_failed_to_reset_are_reset_on_reuse() {
+        let pool = Arc::new(SlotPool::new(1, 1).unwrap());
+        let page_size = region::page::size();
+
+        let mut slot = pool.acquire().unwrap().unwrap();
+        slot.make_accessible(0, page_size).unwrap();
+        unsafe { *slot.as_mut_ptr() = 42 };
+        // Release the slot as if resetting it failed.
+        let index = slot.index;
+        std::mem::forget(slot);
+        pool.free.lock().unwrap().push((index, page_size));
+
+        let mut slot = pool.acquire().unwrap().unwrap();
+        slot.make_accessible(0, page_size).unwrap();
+        assert_eq!(unsafe { *slot.as_ptr() }, 0, "the slot is zeroed");
+        drop(slot);
+        assert_eq!(*pool.free.lock().unwrap(), vec![(index, 0)]);
+    }
+
+    #[test]
+    fn table_buffers_are_reused() {
+        let pool = TablePool::new(1, 16);
+        let mut buffer = pool.acquire().unwrap();
+        assert!(buffer.capacity() >= 16);
+        assert!(pool.acquire().is_err(), "the pool is exhausted");
+
+        buffer.push(RawTableElement::default());
+        let ptr = buffer.as_ptr();
+        pool.release(buffer);
+        let buffer = pool.acquire().unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr(), ptr);
+    }
+}
@@ -6,6 +6,7 @@
 //! `Table` is to WebAssembly tables what `LinearMemory` is to WebAssembly linear memories.
 
 use crate::func_data_registry::VMFuncRef;
+use crate::pool::TablePool;
 use crate::vmcontext::VMTableDefinition;
 use crate::Trap;
 use crate::VMExternRef;
@@ -13,8 +14,9 @@ use std::borrow::{Borrow, BorrowMut};
 use std::cell::UnsafeCell;
 use std::convert::TryFrom;
 use std::fmt;
+use std::mem;
 use std::ptr::NonNull;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use wasmer_types::{ExternRef, TableStyle, TableType, TrapCode, Type as ValType};
 
 /// Trait for implementing the interface of a Wasm table.
@@ -164,6 +166,8 @@ pub struct LinearTable {
     /// Our chosen implementation style.
     style: TableStyle,
     vm_table_definition: VMTableDefinitionOwnership,
+    /// The pool the `vec` buffer is returned to, if the table is pooled.
+    pool: Option<Arc<TablePool>>,
 }
 
 /// A type to help manage who is responsible for the backing table of the
@@ -190,7 +194,7 @@ impl LinearTable {
     /// This creates a `LinearTable` with metadata owned by a VM, pointed to by
     /// `vm_table_location`: this can be used to create a local table.
     pub fn new(table: &TableType, style: &TableStyle) -> Result<Self, String> {
-        unsafe { Self::new_inner(table, style, None) }
+        unsafe { Self::new_inner(table, style, None, None) }
     }
 
     /// Create a new linear table instance with specified minimum and maximum number of elements.
@@ -205,7 +209,23 @@ impl LinearTable {
         style: &TableStyle,
         vm_table_location: NonNull<VMTableDefinition>,
     ) -> Result<Self, String> {
-        Self::new_inner(table, style, Some(vm_table_location))
+        Self::new_inner(table, style, Some(vm_table_location), None)
+    }
+
+    /// Create a new linear table instance with a buffer from `pool`, and
+    /// metadata owned by a VM.
+    ///
+    /// The table can't grow beyond the maximum number of elements of the pool.
+    ///
+    /// # Safety
+    /// - `vm_table_location` must point to a valid location in VM memory.
+    pub(crate) unsafe fn from_pool(
+        table: &TableType,
+        style: &TableStyle,
+        vm_table_location: NonNull<VMTableDefinition>,
+        pool: &Arc<TablePool>,
+    ) -> Result<Self, String> {
+        Self::new_inner(table, style, Some(vm_table_location), Some(pool))
     }
 
     /// Create a new `LinearTable` with either self-owned or VM owned metadata.
@@ -213,6 +233,7 @@ impl LinearTable {
         table: &TableType,
         style: &TableStyle,
         vm_table_location: Option<NonNull<VMTableDefinition>>,
+        pool: Option<&Arc<TablePool>>,
     ) -> Result<Self, String> {
         match table.ty {
             ValType::FuncRef | ValType::ExternRef => (),
@@ -233,12 +254,33 @@ impl LinearTable {
         }
         let table_minimum = usize::try_from(table.minimum)
             .map_err(|_| "Table minimum is bigger than usize".to_string())?;
-        let mut vec = vec![RawTableElement::default(); table_minimum];
+        let (mut vec, maximum) = match pool {
+            Some(pool) => {
+                let max_elements = pool.max_elements();
+                if table.minimum > max_elements {
+                    return Err(format!(
+                        "Table minimum ({}) is larger than the table slots of the pool ({})!",
+                        table.minimum, max_elements
+                    ));
+                }
+                let mut vec = pool.acquire()?;
+                vec.resize(table_minimum, RawTableElement::default());
+                // Growing beyond the slot would reallocate the buffer.
+                let maximum = table
+                    .maximum
+                    .map_or(max_elements, |max| max.min(max_elements));
+                (vec, Some(maximum))
+            }
+            None => (
+                vec![RawTableElement::default(); table_minimum],
+                table.maximum,
+            ),
+        };
         let base = vec.as_mut_ptr();
         match style {
             TableStyle::CallerChecksSignature => Ok(Self {
                 vec: Mutex::new(vec),
-                maximum: table.maximum,
+                maximum,
                 table: *table,
                 style: style.clone(),
                 vm_table_definition: if let Some(table_loc) = vm_table_location {
@@ -257,6 +299,7 @@ impl LinearTable {
                         },
                     )))
                 },
+                pool: pool.cloned(),
             }),
         }
     }
@@ -276,6 +319,16 @@ impl LinearTable {
     }
 }
 
+impl Drop for LinearTable {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            if let Ok(vec) = self.vec.get_mut() {
+                pool.release(mem::take(vec));
+            }
+        }
+    }
+}
+
 impl Table for LinearTable {
     /// Returns the type for this Table.
     fn ty(&self) -> &TableType {
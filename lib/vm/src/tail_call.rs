@@ -0,0 +1,118 @@
+//! Tail calls made by wasm's `return_call` and `return_call_indirect`.
+//!
+//! Compiled code can't replace its own frame with the frame of the function
+//! it tail calls, as the callee may take more arguments on the stack than the
+//! caller was given. So a tail call is recorded as pending in the
+//! [`TailCallState`] of the call from the host into wasm that runs, and the
+//! function that requested it makes it from a loop, in [`run`], that goes
+//! through the call trampoline of the callee's signature, like the host does.
+//! The function then returns the results of the tail call to its caller,
+//! which doesn't need to know about tail calls.
+//!
+//! A function called by that loop returns right after requesting a tail
+//! call instead, and leaves it to the loop. Functions that make tail calls
+//! ask [`TailCallState::enter`] whether they were called by the loop on
+//! entry. A chain of tail calls then runs with a constant stack depth,
+//! whatever the signatures of the functions in it.
+
+use crate::trap::with_activation;
+use crate::vmcontext::{VMFunctionEnvironment, VMTrampoline};
+use crate::VMFunctionBody;
+use std::mem;
+use std::ptr;
+
+/// The tail call requested in a call from the host into wasm.
+#[derive(Debug, Default)]
+pub(crate) struct TailCallState {
+    /// The tail call that wasn't made yet.
+    pending: Option<PendingTailCall>,
+    /// The callee that the loop in [`run`] is calling, until it enters.
+    calling: Option<*const VMFunctionBody>,
+    /// The values of the last tail call made, reused by the next one.
+    buffer: Vec<u128>,
+}
+
+#[derive(Debug)]
+struct PendingTailCall {
+    trampoline: VMTrampoline,
+    callee: *const VMFunctionBody,
+    vmctx: VMFunctionEnvironment,
+    /// The arguments of the call, which the trampoline replaces with the
+    /// results.
+    values: Vec<u128>,
+    num_results: usize,
+}
+
+impl TailCallState {
+    /// Requests a tail call to `callee`, which `trampoline` calls with
+    /// `args`, and which returns `num_results` values.
+    pub(crate) fn request(
+        &mut self,
+        trampoline: VMTrampoline,
+        callee: *const VMFunctionBody,
+        vmctx: VMFunctionEnvironment,
+        args: impl Iterator<Item = u128>,
+        num_results: usize,
+    ) {
+        let mut values = mem::take(&mut self.buffer);
+        values.clear();
+        values.extend(args);
+        values.resize(values.len().max(num_results), 0);
+        self.pending = Some(PendingTailCall {
+            trampoline,
+            callee,
+            vmctx,
+            values,
+            num_results,
+        });
+    }
+
+    /// Called on entry of `callee`, a function that makes tail calls.
+    /// Returns whether the loop in [`run`] called it, in which case the loop
+    /// makes the tail call it requests.
+    pub(crate) fn enter(&mut self, callee: *const VMFunctionBody) -> bool {
+        self.calling.take() == Some(callee)
+    }
+
+    /// Takes the pending tail call, after recycling the values of the one
+    /// made before it.
+    fn next(&mut self, made: Option<Vec<u128>>) -> Option<PendingTailCall> {
+        if let Some(values) = made {
+            self.buffer = values;
+        }
+        let call = self.pending.take()?;
+        self.calling = Some(call.callee);
+        Some(call)
+    }
+}
+
+/// Makes the tail call pending in the running call from the host into wasm,
+/// and the tail calls it requests in turn, and writes the results of the
+/// last one to `results`, one value every 16 bytes.
+///
+/// # Safety
+///
+/// Must be called on the wasm stack, by the function that requested the tail
+/// call, and `results` must have room for its results.
+pub(crate) unsafe fn run(results: *mut u8) {
+    let mut made = None;
+    loop {
+        let call = with_activation(|activation| activation.tail_calls.next(made.take()));
+        let mut call = match call {
+            Some(call) => call,
+            None => break,
+        };
+        // Host functions take their environment where wasm functions take
+        // their `VMContext`.
+        (call.trampoline)(call.vmctx.vmctx, call.callee, call.values.as_mut_ptr());
+        // The callee only enters if it makes tail calls.
+        with_activation(|activation| activation.tail_calls.calling = None);
+        // The results of the last call of the chain are those of the first.
+        ptr::copy_nonoverlapping(
+            call.values.as_ptr() as *const u8,
+            results,
+            call.num_results * 16,
+        );
+        made = Some(call.values);
+    }
+}
@@ -9,9 +9,11 @@ mod trap;
 mod traphandlers;
 
 pub use trap::Trap;
+pub(crate) use traphandlers::with_activation;
 pub use traphandlers::{
-    catch_traps, on_host_stack, raise_lib_trap, raise_user_trap, wasmer_call_trampoline,
-    TrapHandler, TrapHandlerFn,
+    block_on_host, catch_traps, catch_traps_async, on_host_stack, raise_lib_trap, raise_user_trap,
+    wasmer_call_trampoline, wasmer_call_trampoline_async, AsyncWasmCall, TrapHandler,
+    TrapHandlerFn,
 };
 pub use traphandlers::{init_traps, resume_panic};
 pub use wasmer_types::TrapCode;
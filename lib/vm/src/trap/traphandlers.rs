@@ -4,6 +4,8 @@
 //! WebAssembly trap handling, which is built on top of the lower-level
 //! signalhandling mechanisms.
 
+use crate::exception::ExceptionState;
+use crate::tail_call::TailCallState;
 use crate::vmcontext::{VMFunctionEnvironment, VMTrampoline};
 use crate::{Trap, VMFunctionBody};
 use backtrace::Backtrace;
@@ -13,15 +15,18 @@ use corosensei::trap::{CoroutineTrapHandler, TrapHandlerRegs};
 use corosensei::{CoroutineResult, ScopedCoroutine, Yielder};
 use scopeguard::defer;
 use std::any::Any;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::error::Error;
+use std::future::Future;
 use std::io;
 use std::mem;
 #[cfg(unix)]
 use std::mem::MaybeUninit;
+use std::pin::Pin;
 use std::ptr::{self, NonNull};
 use std::sync::atomic::{compiler_fence, AtomicPtr, Ordering};
 use std::sync::{Mutex, Once};
+use std::task::{Context, Poll};
 use wasmer_types::TrapCode;
 
 // TrapInformation can be stored in the "Undefined Instruction" itself.
@@ -82,6 +87,10 @@ unsafe fn process_illegal_op(addr: usize) -> Option<TrapCode> {
             9 => Some(TrapCode::BadConversionToInteger),
             10 => Some(TrapCode::UnreachableCodeReached),
             11 => Some(TrapCode::UnalignedAtomic),
+            12 => Some(TrapCode::UnsharedMemoryWait),
+            13 => Some(TrapCode::Interrupt),
+            14 => Some(TrapCode::OutOfFuel),
+            15 => Some(TrapCode::UncaughtException),
             _ => None,
         },
     }
@@ -640,20 +649,95 @@ where
     // Ensure that per-thread initialization is done.
     lazy_per_thread_init()?;
 
-    on_wasm_stack(trap_handler, closure).map_err(UnwindReason::into_trap)
+    let activation = RefCell::new(Activation::default());
+    let result = Activation::install(&activation, || on_wasm_stack(trap_handler, closure));
+    activation
+        .into_inner()
+        .leave(result)
+        .map_err(UnwindReason::into_trap)
 }
 
-// We need two separate thread-local variables here:
+/// The state of a call from the host into wasm, which the builtins called by
+/// wasm reach with [`with_activation`] while it runs.
+#[derive(Debug, Default)]
+pub(crate) struct Activation {
+    /// The exceptions in flight in the call.
+    pub(crate) exceptions: ExceptionState,
+    /// The tail calls requested in the call.
+    pub(crate) tail_calls: TailCallState,
+}
+
+impl Activation {
+    /// Runs `f` with `activation` as the state of the call that runs on this
+    /// thread. The previous state is restored afterwards.
+    fn install<R>(activation: &RefCell<Self>, f: impl FnOnce() -> R) -> R {
+        let prev = ACTIVATION.with(|cell| cell.replace(Some(NonNull::from(activation))));
+        defer! {
+            ACTIVATION.with(|cell| cell.set(prev));
+        }
+        f()
+    }
+
+    /// Reports an exception that escaped from wasm as a trap, once wasm
+    /// returned to the host.
+    fn leave<R>(self, result: Result<R, UnwindReason>) -> Result<R, UnwindReason> {
+        if self.exceptions.is_pending() && result.is_ok() {
+            return Err(UnwindReason::LibTrap(Trap::lib(
+                TrapCode::UncaughtException,
+            )));
+        }
+        result
+    }
+}
+
+/// Runs `f` with the state of the call from the host into wasm that runs on
+/// this thread.
+///
+/// # Panics
+///
+/// If wasm isn't running on this thread, or if `f` calls into wasm again.
+pub(crate) fn with_activation<R>(f: impl FnOnce(&mut Activation) -> R) -> R {
+    let activation = ACTIVATION
+        .with(|cell| cell.get())
+        .expect("wasm isn't running on this thread");
+    // The activation outlives the call, during which it's installed.
+    let mut activation = unsafe { activation.as_ref() }.borrow_mut();
+    f(&mut activation)
+}
+
+// We need four separate thread-local variables here:
 // - YIELDER is set within the new stack and is used to unwind back to the root
 //   of the stack from inside it.
 // - TRAP_HANDLER is set from outside the new stack and is solely used from
 //   signal handlers. It must be atomic since it is used by signal handlers.
+// - ASYNC_CX is set from outside the new stack while an async call is being
+//   polled, and is the context host futures are polled with.
+// - ACTIVATION is set from outside the new stack while a call runs, and is the
+//   state of the call that the builtins called by wasm use.
 //
 // We also do per-thread signal stack initialization on the first time
 // TRAP_HANDLER is accessed.
 thread_local! {
-    static YIELDER: Cell<Option<NonNull<Yielder<(), UnwindReason>>>> = Cell::new(None);
+    static YIELDER: Cell<Option<NonNull<Yielder<Resume, Suspend>>>> = Cell::new(None);
     static TRAP_HANDLER: AtomicPtr<TrapHandlerContext> = AtomicPtr::new(ptr::null_mut());
+    static ASYNC_CX: Cell<Option<NonNull<Context<'static>>>> = Cell::new(None);
+    static ACTIVATION: Cell<Option<NonNull<RefCell<Activation>>>> = Cell::new(None);
+}
+
+/// Why the Wasm stack was suspended.
+enum Suspend {
+    /// The stack is unwinding, it must be reset and never resumed.
+    Unwind(UnwindReason),
+    /// A host future of an async call is pending.
+    Pending,
+}
+
+/// How a suspended Wasm stack is resumed.
+enum Resume {
+    /// Poll the pending host future again.
+    Poll,
+    /// The async call was dropped: drop the host future and unwind.
+    Cancel,
 }
 
 /// Read-only information that is used by signal handlers to handle and recover
@@ -845,12 +929,20 @@ unsafe fn unwind_with(reason: UnwindReason) -> ! {
         .with(|cell| cell.replace(None))
         .expect("not running on Wasm stack");
 
-    yielder.as_ref().suspend(reason);
+    yielder.as_ref().suspend(Suspend::Unwind(reason));
 
     // on_wasm_stack will forcibly reset the coroutine stack after yielding.
     unreachable!();
 }
 
+// Allocating a new stack is pretty expensive since it involves several
+// system calls. We therefore keep a cache of pre-allocated stacks which
+// allows them to be reused multiple times.
+// FIXME(Amanieu): We should refactor this to avoid the lock.
+lazy_static::lazy_static! {
+    static ref STACK_POOL: Mutex<Vec<DefaultStack>> = Mutex::new(vec![]);
+}
+
 /// Runs the given function on a separate stack so that its stack usage can be
 /// bounded. Stack overflows and other traps can be caught and execution
 /// returned to the root of the stack.
@@ -858,34 +950,32 @@ fn on_wasm_stack<F: FnOnce() -> T, T>(
     trap_handler: &(dyn TrapHandler + 'static),
     f: F,
 ) -> Result<T, UnwindReason> {
-    // Allocating a new stack is pretty expensive since it involves several
-    // system calls. We therefore keep a cache of pre-allocated stacks which
-    // allows them to be reused multiple times.
-    // FIXME(Amanieu): We should refactor this to avoid the lock.
-    lazy_static::lazy_static! {
-        static ref STACK_POOL: Mutex<Vec<DefaultStack>> = Mutex::new(vec![]);
-    }
     let stack = STACK_POOL.lock().unwrap().pop().unwrap_or_default();
     let mut stack = scopeguard::guard(stack, |stack| STACK_POOL.lock().unwrap().push(stack));
 
     // Create a coroutine with a new stack to run the function on.
-    let mut coro = ScopedCoroutine::with_stack(&mut *stack, move |yielder, ()| {
+    let mut coro = ScopedCoroutine::with_stack(&mut *stack, move |yielder, _| {
         // Save the yielder to TLS so that it can be used later.
         YIELDER.with(|cell| cell.set(Some(yielder.into())));
 
         Ok(f())
     });
 
+    // A synchronous call can't be suspended, even when it's made by a host
+    // function of an async call.
+    let async_cx = ASYNC_CX.with(|cell| cell.replace(None));
+
     // Ensure that YIELDER is reset on exit even if the coroutine panics,
     defer! {
         YIELDER.with(|cell| cell.set(None));
+        ASYNC_CX.with(|cell| cell.set(async_cx));
     }
 
     // Set up metadata for the trap handler for the duration of the coroutine
     // execution. This is restored to its previous value afterwards.
     TrapHandlerContext::install(trap_handler, coro.trap_handler(), || {
-        match coro.resume(()) {
-            CoroutineResult::Yield(trap) => {
+        match coro.resume(Resume::Poll) {
+            CoroutineResult::Yield(Suspend::Unwind(trap)) => {
                 // This came from unwind_with which requires that there be only
                 // Wasm code on the stack.
                 unsafe {
@@ -893,11 +983,205 @@ fn on_wasm_stack<F: FnOnce() -> T, T>(
                 }
                 Err(trap)
             }
+            CoroutineResult::Yield(Suspend::Pending) => {
+                unreachable!("synchronous calls can't be suspended")
+            }
             CoroutineResult::Return(result) => result,
         }
     })
 }
 
+/// Calls the Wasm function `callee` through `trampoline` like
+/// [`wasmer_call_trampoline`], but returns a future that runs the call on its
+/// own stack.
+///
+/// Host functions called by Wasm can wait for futures with
+/// [`block_on_host`]: the Wasm stack is then suspended, and the call future
+/// is pending until they are ready.
+///
+/// # Safety
+///
+/// Same as [`wasmer_call_trampoline`]. In addition `values_vec` must stay
+/// valid until the future is ready or dropped, and the host functions called
+/// must only leave data that can be sent to another thread on the Wasm stack
+/// when they suspend it.
+pub unsafe fn wasmer_call_trampoline_async(
+    trap_handler: Box<dyn TrapHandler + Send + Sync>,
+    vmctx: VMFunctionEnvironment,
+    trampoline: VMTrampoline,
+    callee: *const VMFunctionBody,
+    values_vec: *mut u8,
+) -> AsyncWasmCall<()> {
+    catch_traps_async(trap_handler, move || {
+        mem::transmute::<_, extern "C" fn(VMFunctionEnvironment, *const VMFunctionBody, *mut u8)>(
+            trampoline,
+        )(vmctx, callee, values_vec);
+    })
+}
+
+/// Returns a future that runs `closure` on a separate stack like
+/// [`catch_traps`] does, and resolves to its result or to the trap it raised.
+///
+/// # Safety
+///
+/// Highly unsafe since `closure` won't have any dtors run, and since the
+/// future can move to another thread while `closure` is suspended.
+pub unsafe fn catch_traps_async<F, R>(
+    trap_handler: Box<dyn TrapHandler + Send + Sync>,
+    closure: F,
+) -> AsyncWasmCall<R>
+where
+    F: FnOnce() -> R + 'static,
+    R: 'static,
+{
+    let stack = STACK_POOL.lock().unwrap().pop().unwrap_or_default();
+    let coro = ScopedCoroutine::with_stack(stack, move |yielder, _| {
+        YIELDER.with(|cell| cell.set(Some(yielder.into())));
+
+        Ok(closure())
+    });
+    AsyncWasmCall {
+        coro: Some(coro),
+        trap_handler,
+        activation: Default::default(),
+    }
+}
+
+/// The future of an async call into Wasm, see [`wasmer_call_trampoline_async`].
+///
+/// Dropping the future while the call is suspended drops the host future it
+/// is waiting for and abandons the call.
+pub struct AsyncWasmCall<R: 'static> {
+    coro: Option<ScopedCoroutine<'static, Resume, Suspend, Result<R, UnwindReason>, DefaultStack>>,
+    trap_handler: Box<dyn TrapHandler + Send + Sync>,
+    activation: RefCell<Activation>,
+}
+
+// The call only leaves host futures, which are `Send`, and Wasm frames on its
+// stack when it's suspended, see `catch_traps_async`. Its activation then only
+// holds exceptions, which are `Send`: a tail call is made as soon as it's
+// requested, so none is pending while host code runs, and the callee that a
+// tail call loop is calling is only compared by address.
+unsafe impl<R: Send + 'static> Send for AsyncWasmCall<R> {}
+
+impl<R: 'static> AsyncWasmCall<R> {
+    /// Resumes the coroutine, with `cx` as the context host futures are
+    /// polled with.
+    fn resume(
+        &mut self,
+        resume: Resume,
+        cx: Option<NonNull<Context<'static>>>,
+    ) -> Option<Result<R, UnwindReason>> {
+        let coro = self
+            .coro
+            .as_mut()
+            .expect("the async call already completed");
+        let yielder = YIELDER.with(|cell| cell.get());
+        let async_cx = ASYNC_CX.with(|cell| cell.replace(cx));
+        defer! {
+            YIELDER.with(|cell| cell.set(yielder));
+            ASYNC_CX.with(|cell| cell.set(async_cx));
+        }
+
+        let trap_handler = &*self.trap_handler;
+        let result = Activation::install(&self.activation, || {
+            TrapHandlerContext::install(trap_handler, coro.trap_handler(), || {
+                match coro.resume(resume) {
+                    CoroutineResult::Yield(Suspend::Unwind(trap)) => {
+                        // This came from unwind_with, see on_wasm_stack.
+                        unsafe {
+                            coro.force_reset();
+                        }
+                        Some(Err(trap))
+                    }
+                    CoroutineResult::Yield(Suspend::Pending) => None,
+                    CoroutineResult::Return(result) => Some(result),
+                }
+            })
+        });
+        let result = result.map(|result| self.activation.take().leave(result));
+        if result.is_some() {
+            let stack = self.coro.take().unwrap().into_stack();
+            STACK_POOL.lock().unwrap().push(stack);
+        }
+        result
+    }
+}
+
+impl<R: 'static> Future for AsyncWasmCall<R> {
+    type Output = Result<R, Trap>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        // The future may be polled from a different thread every time.
+        lazy_per_thread_init()?;
+
+        let cx = NonNull::from(cx).cast::<Context<'static>>();
+        match this.resume(Resume::Poll, Some(cx)) {
+            Some(result) => Poll::Ready(result.map_err(UnwindReason::into_trap)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<R: 'static> Drop for AsyncWasmCall<R> {
+    fn drop(&mut self) {
+        if let Some(coro) = &self.coro {
+            if coro.started() && !coro.done() {
+                // The call is suspended in block_on_host, have it drop the host
+                // future and unwind.
+                let _ = lazy_per_thread_init();
+                if let Some(Err(UnwindReason::Panic(panic))) = self.resume(Resume::Cancel, None) {
+                    std::panic::resume_unwind(panic);
+                }
+            }
+        }
+        // A call dropped before it was first polled never ran: drop its
+        // closure and put its stack back in the pool.
+        if let Some(mut coro) = self.coro.take() {
+            if !coro.started() {
+                coro.force_unwind();
+                STACK_POOL.lock().unwrap().push(coro.into_stack());
+            }
+        }
+    }
+}
+
+/// Polls a host future of an async call, with `poll`, until it's ready.
+///
+/// This must be called from the Wasm stack, by a host function called from
+/// Wasm: `poll` runs on the host stack, and the Wasm stack is suspended while
+/// it's pending.
+///
+/// Returns `None` if the host function was not called by an async call: it
+/// should then raise a trap.
+///
+/// # Safety
+///
+/// If the async call is dropped while suspended, this unwinds the Wasm stack
+/// like [`raise_user_trap`] does, after dropping `poll`.
+pub unsafe fn block_on_host<T>(mut poll: impl FnMut(&mut Context<'_>) -> Poll<T>) -> Option<T> {
+    let yielder = YIELDER.with(|cell| cell.get())?;
+    ASYNC_CX.with(|cell| cell.get())?;
+    loop {
+        // The context changes every time the call is polled.
+        let cx = ASYNC_CX.with(|cell| cell.get()).unwrap();
+        if let Poll::Ready(value) = on_host_stack(|| poll(&mut *cx.as_ptr())) {
+            return Some(value);
+        }
+
+        let resume = yielder.as_ref().suspend(Suspend::Pending);
+        // We may now run on another thread.
+        YIELDER.with(|cell| cell.set(Some(yielder)));
+        if let Resume::Cancel = resume {
+            drop(poll);
+            unwind_with(UnwindReason::UserTrap(
+                "the async call was cancelled".into(),
+            ));
+        }
+    }
+}
+
 /// When executing on the Wasm stack, temporarily switch back to the host stack
 /// to perform an operation that should not be constrainted by the Wasm stack
 /// limits.
@@ -10,6 +10,7 @@ use crate::instance::Instance;
 use crate::memory::Memory;
 use crate::table::Table;
 use crate::trap::{Trap, TrapCode};
+use crate::wait_queue::WaitQueue;
 use crate::VMBuiltinFunctionIndex;
 use crate::VMExternRef;
 use crate::VMFunctionBody;
@@ -17,7 +18,9 @@ use std::any::Any;
 use std::convert::TryFrom;
 use std::fmt;
 use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use std::u32;
 
 /// Union representing the first parameter passed when calling a function.
@@ -336,6 +339,92 @@ unsafe impl Send for VMMemoryDefinition {}
 unsafe impl Sync for VMMemoryDefinition {}
 
 impl VMMemoryDefinition {
+    /// Returns the address of the `size` bytes at `dst`, which are accessed
+    /// atomically.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the range is out of bounds or if `dst` isn't
+    /// aligned to `size`.
+    fn atomic_address(&self, dst: u32, size: u32) -> Result<*mut u8, Trap> {
+        if dst.checked_add(size).map_or(true, |end| {
+            usize::try_from(end).unwrap() > self.current_length
+        }) {
+            return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
+        }
+        if dst % size != 0 {
+            return Err(Trap::lib(TrapCode::UnalignedAtomic));
+        }
+
+        // The range is checked above, by this point we know that the address
+        // is in the memory.
+        Ok(unsafe { self.base.add(usize::try_from(dst).unwrap()) })
+    }
+
+    /// Perform the `memory.atomic.wait32` operation for the memory: blocks
+    /// until the thread is notified on `dst`, if the value there is `val`.
+    ///
+    /// A negative `timeout`, in nanoseconds, waits forever. Returns the
+    /// [`WaitResult`](crate::WaitResult) as an integer.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned,
+    /// or if the memory can't be waited on (`wait_queue` is `None`).
+    pub(crate) fn memory_atomic_wait32(
+        &self,
+        wait_queue: Option<&WaitQueue>,
+        dst: u32,
+        val: u32,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let address = self.atomic_address(dst, 4)? as *const AtomicU32;
+        let wait_queue = wait_queue.ok_or_else(|| Trap::lib(TrapCode::UnsharedMemoryWait))?;
+        // The address is checked above, and is aligned for an `AtomicU32`.
+        let expected = || unsafe { (*address).load(Ordering::SeqCst) == val };
+        Ok(wait_queue.wait(dst, expected, wait_timeout(timeout)) as u32)
+    }
+
+    /// Perform the `memory.atomic.wait64` operation for the memory, see
+    /// [`VMMemoryDefinition::memory_atomic_wait32`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned,
+    /// or if the memory can't be waited on (`wait_queue` is `None`).
+    pub(crate) fn memory_atomic_wait64(
+        &self,
+        wait_queue: Option<&WaitQueue>,
+        dst: u32,
+        val: u64,
+        timeout: i64,
+    ) -> Result<u32, Trap> {
+        let address = self.atomic_address(dst, 8)? as *const AtomicU64;
+        let wait_queue = wait_queue.ok_or_else(|| Trap::lib(TrapCode::UnsharedMemoryWait))?;
+        // The address is checked above, and is aligned for an `AtomicU64`.
+        let expected = || unsafe { (*address).load(Ordering::SeqCst) == val };
+        Ok(wait_queue.wait(dst, expected, wait_timeout(timeout)) as u32)
+    }
+
+    /// Perform the `memory.atomic.notify` operation for the memory: wakes up
+    /// to `count` threads waiting on `dst`, and returns how many were woken.
+    ///
+    /// Memories that can't be waited on (`wait_queue` is `None`) have no
+    /// threads to wake.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the address is out of bounds or misaligned.
+    pub(crate) fn memory_atomic_notify(
+        &self,
+        wait_queue: Option<&WaitQueue>,
+        dst: u32,
+        count: u32,
+    ) -> Result<u32, Trap> {
+        self.atomic_address(dst, 4)?;
+        Ok(wait_queue.map_or(0, |wait_queue| wait_queue.notify(dst, count)))
+    }
+
     /// Do an unsynchronized, non-atomic `memory.copy` for the memory.
     ///
     /// # Errors
@@ -370,6 +459,48 @@ impl VMMemoryDefinition {
         Ok(())
     }
 
+    /// Do an unsynchronized, non-atomic `memory.copy` from `src_memory` to
+    /// this memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error when the source or destination ranges are out of
+    /// bounds.
+    ///
+    /// # Safety
+    /// The memories are not copied atomically and are not synchronized: it's
+    /// the caller's responsibility to synchronize.
+    pub(crate) unsafe fn memory_copy_from(
+        &self,
+        src_memory: &Self,
+        dst: u32,
+        src: u32,
+        len: u32,
+    ) -> Result<(), Trap> {
+        // https://webassembly.github.io/multi-memory/core/exec/instructions.html#exec-memory-copy
+        let in_bounds = |memory: &Self, start: u32| {
+            matches!(
+                start.checked_add(len),
+                Some(end) if usize::try_from(end).unwrap() <= memory.current_length
+            )
+        };
+        if !in_bounds(src_memory, src) || !in_bounds(self, dst) {
+            return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
+        }
+
+        let dst = usize::try_from(dst).unwrap();
+        let src = usize::try_from(src).unwrap();
+
+        // Bounds and casts are checked above, by this point we know that
+        // everything is safe. The memories may be the same one, so the ranges
+        // may overlap.
+        let dst = self.base.add(dst);
+        let src = src_memory.base.add(src);
+        ptr::copy(src, dst, len as usize);
+
+        Ok(())
+    }
+
     /// Perform the `memory.fill` operation for the memory in an unsynchronized,
     /// non-atomic way.
     ///
@@ -400,6 +531,12 @@ impl VMMemoryDefinition {
     }
 }
 
+/// Converts the timeout of `memory.atomic.wait`, in nanoseconds, to a
+/// `Duration`. Negative timeouts never expire.
+fn wait_timeout(timeout: i64) -> Option<Duration> {
+    u64::try_from(timeout).ok().map(Duration::from_nanos)
+}
+
 #[cfg(test)]
 mod test_vmmemory_definition {
     use super::VMMemoryDefinition;
@@ -880,6 +1017,8 @@ impl VMBuiltinFunctionsArray {
             wasmer_vm_memory32_copy as usize;
         ptrs[VMBuiltinFunctionIndex::get_imported_memory_copy_index().index() as usize] =
             wasmer_vm_imported_memory32_copy as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory_copy_between_index().index() as usize] =
+            wasmer_vm_memory32_copy_between as usize;
         ptrs[VMBuiltinFunctionIndex::get_memory_fill_index().index() as usize] =
             wasmer_vm_memory32_fill as usize;
         ptrs[VMBuiltinFunctionIndex::get_imported_memory_fill_index().index() as usize] =
@@ -888,6 +1027,18 @@ impl VMBuiltinFunctionsArray {
             wasmer_vm_memory32_init as usize;
         ptrs[VMBuiltinFunctionIndex::get_data_drop_index().index() as usize] =
             wasmer_vm_data_drop as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory_atomic_wait32_index().index() as usize] =
+            wasmer_vm_memory32_atomic_wait32 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_wait32_index().index() as usize] =
+            wasmer_vm_imported_memory32_atomic_wait32 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory_atomic_wait64_index().index() as usize] =
+            wasmer_vm_memory32_atomic_wait64 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_wait64_index().index() as usize] =
+            wasmer_vm_imported_memory32_atomic_wait64 as usize;
+        ptrs[VMBuiltinFunctionIndex::get_memory_atomic_notify_index().index() as usize] =
+            wasmer_vm_memory32_atomic_notify as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_atomic_notify_index().index() as usize] =
+            wasmer_vm_imported_memory32_atomic_notify as usize;
         ptrs[VMBuiltinFunctionIndex::get_raise_trap_index().index() as usize] =
             wasmer_vm_raise_trap as usize;
         ptrs[VMBuiltinFunctionIndex::get_table_size_index().index() as usize] =
@@ -914,6 +1065,22 @@ impl VMBuiltinFunctionsArray {
             wasmer_vm_externref_inc as usize;
         ptrs[VMBuiltinFunctionIndex::get_externref_dec_index().index() as usize] =
             wasmer_vm_externref_dec as usize;
+        ptrs[VMBuiltinFunctionIndex::get_throw_index().index() as usize] = wasmer_vm_throw as usize;
+        ptrs[VMBuiltinFunctionIndex::get_rethrow_index().index() as usize] =
+            wasmer_vm_rethrow as usize;
+        ptrs[VMBuiltinFunctionIndex::get_catch_index().index() as usize] = wasmer_vm_catch as usize;
+        ptrs[VMBuiltinFunctionIndex::get_catch_all_index().index() as usize] =
+            wasmer_vm_catch_all as usize;
+        ptrs[VMBuiltinFunctionIndex::get_exception_pending_index().index() as usize] =
+            wasmer_vm_exception_pending as usize;
+        ptrs[VMBuiltinFunctionIndex::get_tail_call_entry_index().index() as usize] =
+            wasmer_vm_tail_call_entry as usize;
+        ptrs[VMBuiltinFunctionIndex::get_return_call_index().index() as usize] =
+            wasmer_vm_return_call as usize;
+        ptrs[VMBuiltinFunctionIndex::get_return_call_indirect_index().index() as usize] =
+            wasmer_vm_return_call_indirect as usize;
+        ptrs[VMBuiltinFunctionIndex::get_tail_call_index().index() as usize] =
+            wasmer_vm_tail_call as usize;
 
         debug_assert!(ptrs.iter().cloned().all(|p| p != 0));
 
@@ -0,0 +1,167 @@
+//! The queues of threads blocked in `memory.atomic.wait`, waiting for a
+//! `memory.atomic.notify` on the same address.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// The outcome of [`WaitQueue::wait`], encoded as the result of
+/// `memory.atomic.wait32` and `memory.atomic.wait64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum WaitResult {
+    /// The thread was woken by a notify.
+    Woken = 0,
+    /// The loaded value didn't match the expected value.
+    NotEqual = 1,
+    /// The timeout expired before the thread was woken.
+    TimedOut = 2,
+}
+
+#[derive(Debug, Default)]
+struct Waiter {
+    woken: Mutex<bool>,
+    condvar: Condvar,
+}
+
+/// The waiters of a memory, keyed by the address they wait on.
+///
+/// Waiters are woken in the order they started waiting.
+#[derive(Debug, Default)]
+pub struct WaitQueue {
+    waiters: Mutex<HashMap<u32, VecDeque<Arc<Waiter>>>>,
+}
+
+impl WaitQueue {
+    /// Creates an empty wait queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blocks the current thread until it's woken by [`WaitQueue::notify`] on
+    /// `addr`, or until `timeout` expires.
+    ///
+    /// `expected` is called while holding the queue lock, so that a notify
+    /// can't be missed between checking the value in memory and waiting: if
+    /// it returns `false` the thread doesn't wait at all.
+    pub fn wait(
+        &self,
+        addr: u32,
+        expected: impl FnOnce() -> bool,
+        timeout: Option<Duration>,
+    ) -> WaitResult {
+        let waiter = {
+            let mut waiters = self.waiters.lock().unwrap();
+            if !expected() {
+                return WaitResult::NotEqual;
+            }
+            let waiter = Arc::new(Waiter::default());
+            waiters.entry(addr).or_default().push_back(waiter.clone());
+            waiter
+        };
+
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut woken = waiter.woken.lock().unwrap();
+        while !*woken {
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    woken = waiter
+                        .condvar
+                        .wait_timeout(woken, deadline - now)
+                        .unwrap()
+                        .0;
+                }
+                None => woken = waiter.condvar.wait(woken).unwrap(),
+            }
+        }
+        if *woken {
+            return WaitResult::Woken;
+        }
+        drop(woken);
+
+        // The timeout expired, but a notify may still wake us before we leave
+        // the queue: in that case it has counted us, so we report it.
+        let mut waiters = self.waiters.lock().unwrap();
+        if *waiter.woken.lock().unwrap() {
+            return WaitResult::Woken;
+        }
+        if let Some(queue) = waiters.get_mut(&addr) {
+            queue.retain(|other| !Arc::ptr_eq(other, &waiter));
+            if queue.is_empty() {
+                waiters.remove(&addr);
+            }
+        }
+        WaitResult::TimedOut
+    }
+
+    /// Wakes up to `count` threads waiting on `addr`, and returns the number
+    /// of threads that were woken.
+    pub fn notify(&self, addr: u32, count: u32) -> u32 {
+        let mut waiters = self.waiters.lock().unwrap();
+        let queue = match waiters.get_mut(&addr) {
+            Some(queue) => queue,
+            None => return 0,
+        };
+
+        let mut notified = 0;
+        while notified < count {
+            let waiter = match queue.pop_front() {
+                Some(waiter) => waiter,
+                None => break,
+            };
+            *waiter.woken.lock().unwrap() = true;
+            waiter.condvar.notify_one();
+            notified += 1;
+        }
+        if queue.is_empty() {
+            waiters.remove(&addr);
+        }
+        notified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn not_equal() {
+        let queue = WaitQueue::new();
+        assert_eq!(queue.wait(0, || false, None), WaitResult::NotEqual);
+        assert_eq!(queue.notify(0, 1), 0);
+    }
+
+    #[test]
+    fn timeout() {
+        let queue = WaitQueue::new();
+        let result = queue.wait(0, || true, Some(Duration::from_millis(10)));
+        assert_eq!(result, WaitResult::TimedOut);
+        assert_eq!(queue.notify(0, 1), 0, "timed out waiters leave the queue");
+    }
+
+    #[test]
+    fn notify() {
+        let queue = Arc::new(WaitQueue::new());
+        let waiters = (0..3)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || queue.wait(8, || true, None))
+            })
+            .collect::<Vec<_>>();
+
+        let mut notified = 0;
+        while notified < 3 {
+            assert_eq!(queue.notify(4, 3), 0, "other addresses aren't woken");
+            notified += queue.notify(8, 1);
+            thread::yield_now();
+        }
+        for waiter in waiters {
+            assert_eq!(waiter.join().unwrap(), WaitResult::Woken);
+        }
+    }
+}
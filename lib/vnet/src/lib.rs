@@ -10,6 +10,9 @@ use std::sync::Mutex;
 use std::time::Duration;
 use thiserror::Error;
 
+#[cfg(feature = "mem-net")]
+pub mod mem_net;
+
 pub use bytes::Bytes;
 pub use bytes::BytesMut;
 
@@ -217,6 +220,12 @@ pub trait VirtualTcpListener: fmt::Debug + Send + Sync + 'static {
 
     /// Returns the maximum number of network hops before packets are dropped
     fn ttl(&self) -> Result<u8>;
+
+    /// Returns the number of connection attempts that can be accepted without
+    /// blocking, or `NetworkError::WouldBlock` if there are none yet
+    fn poll_accept_ready(&self) -> Result<usize> {
+        Err(NetworkError::Unsupported)
+    }
 }
 
 pub trait VirtualSocket: fmt::Debug + Send + Sync + 'static {
@@ -231,6 +240,19 @@ pub trait VirtualSocket: fmt::Debug + Send + Sync + 'static {
 
     /// Returns the status/state of the socket
     fn status(&self) -> Result<SocketStatus>;
+
+    /// Returns the number of bytes that can be received without blocking,
+    /// where zero means the peer has closed the connection, or
+    /// `NetworkError::WouldBlock` if nothing has arrived yet
+    fn poll_read_ready(&self) -> Result<usize> {
+        Err(NetworkError::Unsupported)
+    }
+
+    /// Returns the number of bytes that can be sent without blocking, or
+    /// `NetworkError::WouldBlock` if the send buffer is full
+    fn poll_write_ready(&self) -> Result<usize> {
+        Err(NetworkError::Unsupported)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -0,0 +1,540 @@
+//! An in-memory networking stack.
+//!
+//! All the [`Networking`] interfaces that are created from the same
+//! [`Switch`] are plugged into one private network that lives entirely
+//! inside the process, which makes it possible to run distributed programs
+//! deterministically without touching the sockets of the host. The switch
+//! can also inject faults (latency, packet loss and network partitions).
+
+mod networking;
+mod switch;
+mod tcp;
+mod udp;
+
+pub use networking::Networking;
+pub use switch::{FaultConfig, Switch};
+pub use tcp::{TcpListener, TcpSocket};
+pub use udp::UdpSocket;
+
+use crate::IpCidr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Condvar, MutexGuard};
+use std::time::{Duration, Instant};
+
+type InterfaceId = u64;
+
+/// First port that is handed out when a socket is bound to port zero
+const EPHEMERAL_PORT_START: u16 = 49152;
+
+/// Default size reported for the send and receive buffers of a socket
+const DEFAULT_BUF_SIZE: usize = 65536;
+
+/// Default TTL of the packets sent by a socket
+const DEFAULT_TTL: u32 = 64;
+
+/// Checks if an IP address falls within the range of a CIDR
+fn cidr_contains(cidr: &IpCidr, ip: IpAddr) -> bool {
+    match (cidr.ip, ip) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let prefix = cidr.prefix.min(32) as u32;
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            (u32::from(net) & mask) == (u32::from(ip) & mask)
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let prefix = cidr.prefix.min(128) as u32;
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            (u128::from(net) & mask) == (u128::from(ip) & mask)
+        }
+        _ => false,
+    }
+}
+
+/// Returns the unspecified address of the same family as `ip`
+fn unspecified_of(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    }
+}
+
+/// Waits on a condition variable until it is notified or the deadline
+/// passes. Returns `None` when the deadline has already passed.
+fn wait_until<'a, T>(
+    cond: &Condvar,
+    guard: MutexGuard<'a, T>,
+    deadline: Option<Instant>,
+) -> Option<MutexGuard<'a, T>> {
+    match deadline {
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            Some(cond.wait_timeout(guard, deadline - now).unwrap().0)
+        }
+        None => Some(cond.wait(guard).unwrap()),
+    }
+}
+
+/// Earliest of two optional deadlines
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+fn deadline_after(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.map(|timeout| Instant::now() + timeout)
+}
+
+#[cfg(test)]
+mod test_mem_net {
+    use super::*;
+    use crate::{Bytes, NetworkError, TimeType, VirtualNetworking};
+    use std::net::{Shutdown, SocketAddr};
+    use std::thread;
+
+    fn addr(ip: &str, port: u16) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), port)
+    }
+
+    fn host(switch: &Switch, ip: &str) -> Networking {
+        let net = Networking::new(switch);
+        net.ip_add(ip.parse().unwrap(), 24).unwrap();
+        net
+    }
+
+    #[test]
+    fn test_cidr_contains() {
+        let cidr = IpCidr {
+            ip: "10.0.1.7".parse().unwrap(),
+            prefix: 24,
+        };
+        assert!(cidr_contains(&cidr, "10.0.1.200".parse().unwrap()));
+        assert!(!cidr_contains(&cidr, "10.0.2.1".parse().unwrap()));
+        assert!(!cidr_contains(&cidr, "::1".parse().unwrap()));
+        let any = IpCidr {
+            ip: "0.0.0.0".parse().unwrap(),
+            prefix: 0,
+        };
+        assert!(cidr_contains(&any, "192.168.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_ip_configuration() {
+        let switch = Switch::new();
+        let a = Networking::new(&switch);
+        let b = Networking::new(&switch);
+
+        a.ip_add("10.0.0.1".parse().unwrap(), 24).unwrap();
+        assert_eq!(
+            b.ip_add("10.0.0.1".parse().unwrap(), 24),
+            Err(NetworkError::AddressInUse)
+        );
+        assert_eq!(
+            a.ip_add("10.0.0.2".parse().unwrap(), 33),
+            Err(NetworkError::InvalidInput)
+        );
+        assert_eq!(
+            a.ip_list().unwrap(),
+            vec![IpCidr {
+                ip: "10.0.0.1".parse().unwrap(),
+                prefix: 24
+            }]
+        );
+
+        let leased = b.dhcp_acquire().unwrap();
+        assert_eq!(leased.len(), 1);
+        assert_ne!(leased[0], "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(b.ip_list().unwrap().len(), 1);
+
+        a.ip_remove("10.0.0.1".parse().unwrap()).unwrap();
+        assert!(a.ip_list().unwrap().is_empty());
+        b.ip_add("10.0.0.1".parse().unwrap(), 24).unwrap();
+
+        b.ip_clear().unwrap();
+        assert!(b.ip_list().unwrap().is_empty());
+        assert_ne!(a.mac().unwrap(), b.mac().unwrap());
+    }
+
+    #[test]
+    fn test_addresses_are_released_on_drop() {
+        let switch = Switch::new();
+        drop(host(&switch, "10.0.0.1"));
+        host(&switch, "10.0.0.1");
+    }
+
+    #[test]
+    fn test_routes() {
+        let switch = Switch::new();
+        let a = host(&switch, "10.0.0.1");
+        let b = Networking::new(&switch);
+        b.ip_add("10.0.1.1".parse().unwrap(), 24).unwrap();
+        let _listener = b
+            .listen_tcp(addr("10.0.1.1", 80), false, false, false)
+            .unwrap();
+
+        // Without a route the other subnet is unreachable
+        assert_eq!(
+            a.connect_tcp(addr("0.0.0.0", 0), addr("10.0.1.1", 80), None)
+                .unwrap_err(),
+            NetworkError::AddressNotAvailable
+        );
+
+        let cidr = IpCidr {
+            ip: "10.0.1.0".parse().unwrap(),
+            prefix: 24,
+        };
+        a.route_add(cidr, "10.0.0.254".parse().unwrap(), None, None)
+            .unwrap();
+        assert_eq!(a.route_list().unwrap().len(), 1);
+        a.connect_tcp(addr("0.0.0.0", 0), addr("10.0.1.1", 80), None)
+            .unwrap();
+
+        a.route_remove("10.0.1.0".parse().unwrap()).unwrap();
+        assert!(a.route_list().unwrap().is_empty());
+        a.gateway_set("10.0.0.254".parse().unwrap()).unwrap();
+        a.connect_tcp(addr("0.0.0.0", 0), addr("10.0.1.1", 80), None)
+            .unwrap();
+        a.route_clear().unwrap();
+        assert!(a.route_list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tcp_between_interfaces() {
+        let switch = Switch::new();
+        let server = host(&switch, "10.0.0.1");
+        let client = host(&switch, "10.0.0.2");
+
+        let listener = server
+            .listen_tcp(addr("0.0.0.0", 8080), false, false, false)
+            .unwrap();
+        let handle = thread::spawn(move || {
+            let (mut sock, peer) = listener.accept().unwrap();
+            assert_eq!(peer.ip(), "10.0.0.2".parse::<IpAddr>().unwrap());
+            let data = sock.recv().unwrap().data;
+            sock.send(data).unwrap();
+            // Dropping the socket closes the stream
+        });
+
+        let mut sock = client
+            .connect_tcp(addr("0.0.0.0", 0), addr("10.0.0.1", 8080), None)
+            .unwrap();
+        assert_eq!(sock.addr_peer().unwrap(), addr("10.0.0.1", 8080));
+        assert_eq!(
+            sock.addr_local().unwrap().ip(),
+            "10.0.0.2".parse::<IpAddr>().unwrap()
+        );
+        sock.send(Bytes::from_static(b"hello")).unwrap();
+        assert_eq!(&sock.recv().unwrap().data[..], b"hello");
+        handle.join().unwrap();
+        assert!(sock.recv().unwrap().data.is_empty());
+        assert_eq!(
+            sock.send(Bytes::from_static(b"gone")),
+            Err(NetworkError::ConnectionReset)
+        );
+    }
+
+    #[test]
+    fn test_tcp_errors() {
+        let switch = Switch::new();
+        let server = host(&switch, "10.0.0.1");
+        let client = host(&switch, "10.0.0.2");
+
+        assert_eq!(
+            client
+                .connect_tcp(addr("0.0.0.0", 0), addr("10.0.0.1", 80), None)
+                .unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+        assert_eq!(
+            client
+                .connect_tcp(addr("0.0.0.0", 0), addr("10.0.0.3", 80), None)
+                .unwrap_err(),
+            NetworkError::TimedOut
+        );
+        assert_eq!(
+            server
+                .listen_tcp(addr("10.0.0.2", 80), false, false, false)
+                .unwrap_err(),
+            NetworkError::AddressNotAvailable
+        );
+
+        let first = server
+            .listen_tcp(addr("10.0.0.1", 80), false, false, false)
+            .unwrap();
+        assert_eq!(
+            server
+                .listen_tcp(addr("0.0.0.0", 80), false, false, false)
+                .unwrap_err(),
+            NetworkError::AddressInUse
+        );
+        drop(first);
+        let _first = server
+            .listen_tcp(addr("0.0.0.0", 80), false, true, false)
+            .unwrap();
+        let _second = server
+            .listen_tcp(addr("0.0.0.0", 80), false, true, false)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tcp_timeouts_and_options() {
+        let switch = Switch::new();
+        let net = Networking::new(&switch);
+        let mut listener = net
+            .listen_tcp(addr("127.0.0.1", 0), false, false, false)
+            .unwrap();
+        let server_addr = listener.addr_local().unwrap();
+        assert_ne!(server_addr.port(), 0);
+
+        listener
+            .set_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+        assert_eq!(listener.accept().unwrap_err(), NetworkError::TimedOut);
+
+        let mut sock = net
+            .connect_tcp(addr("0.0.0.0", 0), server_addr, None)
+            .unwrap();
+        sock.set_opt_time(TimeType::ReadTimeout, Some(Duration::from_millis(10)))
+            .unwrap();
+        assert_eq!(sock.recv().unwrap_err(), NetworkError::WouldBlock);
+
+        sock.set_nodelay(true).unwrap();
+        assert!(sock.nodelay().unwrap());
+        sock.set_recv_buf_size(1024).unwrap();
+        assert_eq!(sock.recv_buf_size().unwrap(), 1024);
+        sock.set_linger(Some(Duration::from_secs(1))).unwrap();
+        assert_eq!(
+            sock.opt_time(TimeType::Linger).unwrap(),
+            Some(Duration::from_secs(1))
+        );
+
+        let (mut accepted, _) = listener.accept().unwrap();
+        sock.shutdown(std::net::Shutdown::Write).unwrap();
+        assert_eq!(
+            sock.send(Bytes::from_static(b"x")),
+            Err(NetworkError::BrokenPipe)
+        );
+        assert!(accepted.recv().unwrap().data.is_empty());
+    }
+
+    #[test]
+    fn test_loopback_is_private() {
+        let switch = Switch::new();
+        let a = Networking::new(&switch);
+        let b = Networking::new(&switch);
+        let _listener = a
+            .listen_tcp(addr("127.0.0.1", 80), false, false, false)
+            .unwrap();
+        a.connect_tcp(addr("0.0.0.0", 0), addr("127.0.0.1", 80), None)
+            .unwrap();
+        assert_eq!(
+            b.connect_tcp(addr("0.0.0.0", 0), addr("127.0.0.1", 80), None)
+                .unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+    }
+
+    #[test]
+    fn test_udp() {
+        let switch = Switch::new();
+        let a = host(&switch, "10.0.0.1");
+        let b = host(&switch, "10.0.0.2");
+
+        let mut server = a.bind_udp(addr("0.0.0.0", 53), false, false).unwrap();
+        let mut client = b.bind_udp(addr("0.0.0.0", 0), false, false).unwrap();
+        client
+            .send_to(Bytes::from_static(b"query"), addr("10.0.0.1", 53))
+            .unwrap();
+
+        let packet = server.peek_from().unwrap();
+        assert_eq!(&packet.data[..], b"query");
+        let packet = server.recv_from().unwrap();
+        assert_eq!(&packet.data[..], b"query");
+        assert_eq!(packet.addr.ip(), "10.0.0.2".parse::<IpAddr>().unwrap());
+
+        server
+            .send_to(Bytes::from_static(b"answer"), packet.addr)
+            .unwrap();
+        client.connect(addr("10.0.0.1", 53)).unwrap();
+        assert_eq!(client.addr_peer().unwrap(), Some(addr("10.0.0.1", 53)));
+        assert_eq!(&client.recv().unwrap().data[..], b"answer");
+    }
+
+    #[test]
+    fn test_readiness() {
+        let switch = Switch::new();
+        let server = host(&switch, "10.0.0.1");
+        let client = host(&switch, "10.0.0.2");
+
+        let listener = server
+            .listen_tcp(addr("0.0.0.0", 80), false, false, false)
+            .unwrap();
+        assert_eq!(listener.poll_accept_ready(), Err(NetworkError::WouldBlock));
+        let mut sock = client
+            .connect_tcp(addr("0.0.0.0", 0), addr("10.0.0.1", 80), None)
+            .unwrap();
+        assert_eq!(listener.poll_accept_ready(), Ok(1));
+        let (mut peer, _) = listener.accept().unwrap();
+
+        assert_eq!(peer.poll_read_ready(), Err(NetworkError::WouldBlock));
+        assert!(sock.poll_write_ready().unwrap() > 0);
+        sock.send(Bytes::from_static(b"hello")).unwrap();
+        assert_eq!(peer.poll_read_ready(), Ok(5));
+        peer.recv().unwrap();
+        assert_eq!(peer.poll_read_ready(), Err(NetworkError::WouldBlock));
+        sock.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(peer.poll_read_ready(), Ok(0));
+        assert_eq!(sock.poll_write_ready(), Err(NetworkError::BrokenPipe));
+
+        let udp = server.bind_udp(addr("0.0.0.0", 53), false, false).unwrap();
+        let mut other = client.bind_udp(addr("0.0.0.0", 0), false, false).unwrap();
+        assert_eq!(udp.poll_read_ready(), Err(NetworkError::WouldBlock));
+        other
+            .send_to(Bytes::from_static(b"query"), addr("10.0.0.1", 53))
+            .unwrap();
+        assert_eq!(udp.poll_read_ready(), Ok(5));
+    }
+
+    #[test]
+    fn test_udp_broadcast_and_multicast() {
+        let switch = Switch::new();
+        let a = host(&switch, "10.0.0.1");
+        let b = host(&switch, "10.0.0.2");
+        let c = host(&switch, "10.0.0.3");
+
+        let mut sender = a.bind_udp(addr("0.0.0.0", 0), false, false).unwrap();
+        let mut listener_b = b.bind_udp(addr("0.0.0.0", 9000), false, false).unwrap();
+        let mut listener_c = c.bind_udp(addr("0.0.0.0", 9000), false, false).unwrap();
+
+        assert_eq!(
+            sender.send_to(Bytes::from_static(b"all"), addr("255.255.255.255", 9000)),
+            Err(NetworkError::PermissionDenied)
+        );
+        sender.set_broadcast(true).unwrap();
+        sender
+            .send_to(Bytes::from_static(b"all"), addr("255.255.255.255", 9000))
+            .unwrap();
+        assert_eq!(&listener_b.recv().unwrap().data[..], b"all");
+        assert_eq!(&listener_c.recv().unwrap().data[..], b"all");
+
+        let group = "239.1.2.3".parse().unwrap();
+        listener_c
+            .join_multicast_v4(group, "10.0.0.3".parse().unwrap())
+            .unwrap();
+        sender
+            .send_to(Bytes::from_static(b"group"), addr("239.1.2.3", 9000))
+            .unwrap();
+        sender
+            .send_to(Bytes::from_static(b"direct"), addr("10.0.0.2", 9000))
+            .unwrap();
+        assert_eq!(&listener_c.recv().unwrap().data[..], b"group");
+        assert_eq!(&listener_b.recv().unwrap().data[..], b"direct");
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        let switch = Switch::new();
+        let a = host(&switch, "10.0.0.1");
+        let b = host(&switch, "10.0.0.2");
+        let mut server = a.bind_udp(addr("0.0.0.0", 7), false, false).unwrap();
+        let mut client = b.bind_udp(addr("0.0.0.0", 0), false, false).unwrap();
+
+        // Every packet is lost
+        switch.set_faults(FaultConfig {
+            drop_rate: 1.0,
+            ..Default::default()
+        });
+        client
+            .send_to(Bytes::from_static(b"lost"), addr("10.0.0.1", 7))
+            .unwrap();
+        assert_eq!(switch.dropped_packets(), 1);
+
+        // Partitioned hosts can not talk to each other
+        switch.set_faults(FaultConfig::default());
+        switch.partition("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+        client
+            .send_to(Bytes::from_static(b"lost"), addr("10.0.0.1", 7))
+            .unwrap();
+        assert_eq!(switch.dropped_packets(), 2);
+        let _listener = a
+            .listen_tcp(addr("0.0.0.0", 80), false, false, false)
+            .unwrap();
+        assert_eq!(
+            b.connect_tcp(addr("0.0.0.0", 0), addr("10.0.0.1", 80), None)
+                .unwrap_err(),
+            NetworkError::TimedOut
+        );
+        switch.heal_all();
+
+        // Packets are delayed by the latency
+        switch.set_faults(FaultConfig {
+            latency: Duration::from_millis(50),
+            ..Default::default()
+        });
+        let start = Instant::now();
+        client
+            .send_to(Bytes::from_static(b"slow"), addr("10.0.0.1", 7))
+            .unwrap();
+        assert_eq!(&server.recv().unwrap().data[..], b"slow");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_drop_rate_is_deterministic() {
+        let run = || {
+            let switch = Switch::new();
+            switch.set_faults(FaultConfig {
+                drop_rate: 0.5,
+                seed: 42,
+                ..Default::default()
+            });
+            let a = host(&switch, "10.0.0.1");
+            let b = host(&switch, "10.0.0.2");
+            let _server = a.bind_udp(addr("0.0.0.0", 7), false, false).unwrap();
+            let mut client = b.bind_udp(addr("0.0.0.0", 0), false, false).unwrap();
+            for _ in 0..100 {
+                client
+                    .send_to(Bytes::from_static(b"x"), addr("10.0.0.1", 7))
+                    .unwrap();
+            }
+            switch.dropped_packets()
+        };
+        let dropped = run();
+        assert!(dropped > 0 && dropped < 100);
+        assert_eq!(dropped, run());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let switch = Switch::new();
+        let net = host(&switch, "10.0.0.1");
+        switch.add_host("db.internal", "10.0.0.5".parse().unwrap());
+
+        assert_eq!(
+            net.resolve("db.internal", None, None).unwrap(),
+            vec!["10.0.0.5".parse::<IpAddr>().unwrap()]
+        );
+        assert_eq!(
+            net.resolve("10.0.0.9", Some(80), None).unwrap(),
+            vec!["10.0.0.9".parse::<IpAddr>().unwrap()]
+        );
+        assert!(net
+            .resolve("localhost", None, None)
+            .unwrap()
+            .iter()
+            .all(|ip| ip.is_loopback()));
+        assert_eq!(
+            net.resolve("unknown.internal", None, None),
+            Err(NetworkError::AddressNotAvailable)
+        );
+        switch.remove_host("db.internal");
+        assert_eq!(
+            net.resolve("db.internal", None, None),
+            Err(NetworkError::AddressNotAvailable)
+        );
+    }
+}
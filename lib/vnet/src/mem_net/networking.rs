@@ -0,0 +1,340 @@
+//! This module contains the [`Networking`] type itself.
+
+use super::tcp::{TcpListener, TcpSocket};
+use super::udp::UdpSocket;
+use super::*;
+use crate::{
+    IpRoute, NetworkError, Result, SocketHttpRequest, StreamSecurity, VirtualIcmpSocket,
+    VirtualNetworking, VirtualRawSocket, VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
+    VirtualWebSocket,
+};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Prefix of the addresses that are handed out by `dhcp_acquire`
+const DHCP_PREFIX: u8 = 16;
+
+/// A network interface that is plugged into a [`Switch`].
+///
+/// Every `WasiEnv` that should take part in the network gets its own
+/// interface, they can then reach each other through the addresses that
+/// were assigned to them.
+#[derive(Debug)]
+pub struct Networking {
+    iface: Arc<Interface>,
+}
+
+#[derive(Debug)]
+pub(super) struct Interface {
+    pub switch: Switch,
+    pub id: InterfaceId,
+    config: Mutex<InterfaceConfig>,
+}
+
+#[derive(Debug, Default)]
+struct InterfaceConfig {
+    ips: Vec<IpCidr>,
+    routes: Vec<IpRoute>,
+}
+
+impl Networking {
+    /// Creates a new interface that is connected to the switch
+    pub fn new(switch: &Switch) -> Self {
+        let id = switch.lock().new_id();
+        Self {
+            iface: Arc::new(Interface {
+                switch: switch.clone(),
+                id,
+                config: Mutex::new(InterfaceConfig::default()),
+            }),
+        }
+    }
+
+    /// Returns the switch that this interface is connected to
+    pub fn switch(&self) -> &Switch {
+        &self.iface.switch
+    }
+
+    fn check_local(&self, ip: IpAddr) -> Result<()> {
+        match ip.is_unspecified() || ip.is_loopback() || self.iface.owns(ip) {
+            true => Ok(()),
+            false => Err(NetworkError::AddressNotAvailable),
+        }
+    }
+}
+
+impl Interface {
+    fn owns(&self, ip: IpAddr) -> bool {
+        self.config
+            .lock()
+            .unwrap()
+            .ips
+            .iter()
+            .any(|cidr| cidr.ip == ip)
+    }
+
+    /// Checks if the routing table has a way to reach the address
+    pub fn can_reach(&self, ip: IpAddr) -> bool {
+        if ip.is_loopback() {
+            return true;
+        }
+        let config = self.config.lock().unwrap();
+        config.ips.iter().any(|cidr| cidr_contains(cidr, ip))
+            || config
+                .routes
+                .iter()
+                .any(|route| cidr_contains(&route.cidr, ip))
+    }
+
+    /// Picks the source address for traffic going to `dst` from a socket
+    /// that is bound to `bound`
+    pub fn source_for(&self, bound: IpAddr, dst: IpAddr) -> Result<IpAddr> {
+        if !bound.is_unspecified() {
+            return Ok(bound);
+        }
+        if dst.is_loopback() {
+            return Ok(dst);
+        }
+        self.config
+            .lock()
+            .unwrap()
+            .ips
+            .iter()
+            .map(|cidr| cidr.ip)
+            .find(|ip| ip.is_ipv4() == dst.is_ipv4())
+            .ok_or(NetworkError::AddressNotAvailable)
+    }
+}
+
+impl Drop for Interface {
+    fn drop(&mut self) {
+        let config = self.config.get_mut().unwrap();
+        let mut switch = self.switch.lock();
+        for cidr in config.ips.drain(..) {
+            switch.release_address(self.id, cidr.ip);
+        }
+    }
+}
+
+impl VirtualNetworking for Networking {
+    fn ws_connect(&self, _url: &str) -> Result<Box<dyn VirtualWebSocket + Sync>> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn http_request(
+        &self,
+        _url: &str,
+        _method: &str,
+        _headers: &str,
+        _gzip: bool,
+    ) -> Result<SocketHttpRequest> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn bridge(&self, _network: &str, _access_token: &str, _security: StreamSecurity) -> Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn unbridge(&self) -> Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn dhcp_acquire(&self) -> Result<Vec<IpAddr>> {
+        let ip = self.iface.switch.lock().lease_address(self.iface.id)?;
+        self.iface.config.lock().unwrap().ips.push(IpCidr {
+            ip,
+            prefix: DHCP_PREFIX,
+        });
+        Ok(vec![ip])
+    }
+
+    fn ip_add(&self, ip: IpAddr, prefix: u8) -> Result<()> {
+        let max_prefix = match ip {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix > max_prefix || ip.is_unspecified() || ip.is_loopback() || ip.is_multicast() {
+            return Err(NetworkError::InvalidInput);
+        }
+        self.iface.switch.lock().claim_address(self.iface.id, ip)?;
+
+        let mut config = self.iface.config.lock().unwrap();
+        config.ips.retain(|cidr| cidr.ip != ip);
+        config.ips.push(IpCidr { ip, prefix });
+        Ok(())
+    }
+
+    fn ip_remove(&self, ip: IpAddr) -> Result<()> {
+        let mut config = self.iface.config.lock().unwrap();
+        if !config.ips.iter().any(|cidr| cidr.ip == ip) {
+            return Err(NetworkError::AddressNotAvailable);
+        }
+        config.ips.retain(|cidr| cidr.ip != ip);
+        self.iface.switch.lock().release_address(self.iface.id, ip);
+        Ok(())
+    }
+
+    fn ip_clear(&self) -> Result<()> {
+        let mut config = self.iface.config.lock().unwrap();
+        let mut switch = self.iface.switch.lock();
+        for cidr in config.ips.drain(..) {
+            switch.release_address(self.iface.id, cidr.ip);
+        }
+        Ok(())
+    }
+
+    fn ip_list(&self) -> Result<Vec<IpCidr>> {
+        Ok(self.iface.config.lock().unwrap().ips.clone())
+    }
+
+    fn mac(&self) -> Result<[u8; 6]> {
+        // Locally administered unicast address that is unique on the switch
+        let id = self.iface.id.to_be_bytes();
+        Ok([0x02, 0x00, id[4], id[5], id[6], id[7]])
+    }
+
+    fn gateway_set(&self, ip: IpAddr) -> Result<()> {
+        let cidr = IpCidr {
+            ip: unspecified_of(ip),
+            prefix: 0,
+        };
+        let mut config = self.iface.config.lock().unwrap();
+        config.routes.retain(|route| route.cidr != cidr);
+        config.routes.push(IpRoute {
+            cidr,
+            via_router: ip,
+            preferred_until: None,
+            expires_at: None,
+        });
+        Ok(())
+    }
+
+    fn route_add(
+        &self,
+        cidr: IpCidr,
+        via_router: IpAddr,
+        preferred_until: Option<Duration>,
+        expires_at: Option<Duration>,
+    ) -> Result<()> {
+        self.iface.config.lock().unwrap().routes.push(IpRoute {
+            cidr,
+            via_router,
+            preferred_until,
+            expires_at,
+        });
+        Ok(())
+    }
+
+    fn route_remove(&self, cidr: IpAddr) -> Result<()> {
+        self.iface
+            .config
+            .lock()
+            .unwrap()
+            .routes
+            .retain(|route| route.cidr.ip != cidr);
+        Ok(())
+    }
+
+    fn route_clear(&self) -> Result<()> {
+        self.iface.config.lock().unwrap().routes.clear();
+        Ok(())
+    }
+
+    fn route_list(&self) -> Result<Vec<IpRoute>> {
+        Ok(self.iface.config.lock().unwrap().routes.clone())
+    }
+
+    fn bind_raw(&self) -> Result<Box<dyn VirtualRawSocket + Sync>> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn listen_tcp(
+        &self,
+        addr: SocketAddr,
+        _only_v6: bool,
+        reuse_port: bool,
+        _reuse_addr: bool,
+    ) -> Result<Box<dyn VirtualTcpListener + Sync>> {
+        self.check_local(addr.ip())?;
+        let listener = TcpListener::new(self.iface.clone(), addr, reuse_port)?;
+        Ok(Box::new(listener))
+    }
+
+    fn bind_udp(
+        &self,
+        addr: SocketAddr,
+        reuse_port: bool,
+        reuse_addr: bool,
+    ) -> Result<Box<dyn VirtualUdpSocket + Sync>> {
+        self.check_local(addr.ip())?;
+        let socket = UdpSocket::new(self.iface.clone(), addr, reuse_port || reuse_addr)?;
+        Ok(Box::new(socket))
+    }
+
+    fn bind_icmp(&self, _addr: IpAddr) -> Result<Box<dyn VirtualIcmpSocket + Sync>> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn connect_tcp(
+        &self,
+        addr: SocketAddr,
+        peer: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Result<Box<dyn VirtualTcpSocket + Sync>> {
+        self.check_local(addr.ip())?;
+        if peer.ip().is_unspecified() || peer.port() == 0 {
+            return Err(NetworkError::InvalidInput);
+        }
+        if !self.iface.can_reach(peer.ip()) {
+            return Err(NetworkError::AddressNotAvailable);
+        }
+        let local_ip = self.iface.source_for(addr.ip(), peer.ip())?;
+
+        let mut switch = self.iface.switch.lock();
+        let local_port = match addr.port() {
+            0 => switch.allocate_port(self.iface.id)?,
+            port => port,
+        };
+        let local = SocketAddr::new(local_ip, local_port);
+
+        // Nobody answers when the address does not exist or is cut off
+        let target = switch
+            .owner(self.iface.id, peer.ip())
+            .ok_or(NetworkError::TimedOut)?;
+        if switch.is_partitioned(local.ip(), peer.ip()) {
+            return Err(NetworkError::TimedOut);
+        }
+        let queue = switch
+            .find_listener(target, local, peer)
+            .ok_or(NetworkError::ConnectionRefused)?;
+        drop(switch);
+
+        let (mut client, server) = TcpSocket::pair(&self.iface.switch, local, peer);
+        client.set_connect_timeout(timeout);
+        queue.push(server, local);
+        Ok(Box::new(client))
+    }
+
+    fn resolve(
+        &self,
+        host: &str,
+        _port: Option<u16>,
+        _dns_server: Option<IpAddr>,
+    ) -> Result<Vec<IpAddr>> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+        if host.eq_ignore_ascii_case("localhost") {
+            return Ok(vec![
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ]);
+        }
+        // The switch acts as the DNS server of the network
+        self.iface
+            .switch
+            .lock()
+            .resolve(host)
+            .ok_or(NetworkError::AddressNotAvailable)
+    }
+}
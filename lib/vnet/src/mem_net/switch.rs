@@ -0,0 +1,418 @@
+//! This module contains the [`Switch`] that connects all the in-memory
+//! interfaces together.
+
+use super::tcp::AcceptQueue;
+use super::udp::{Datagram, DatagramQueue};
+use super::*;
+use crate::{Bytes, NetworkError, Result};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Faults that the switch injects into the traffic that passes through it.
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    /// Delay that is added before every packet (or chunk of a TCP stream)
+    /// is delivered to the other side
+    pub latency: Duration,
+    /// Probability (between `0.0` and `1.0`) that a UDP datagram is lost
+    /// in transit. TCP streams are reliable and never lose data.
+    pub drop_rate: f64,
+    /// Seed for the packet loss so that test runs can be reproduced
+    pub seed: u64,
+}
+
+/// The in-memory network that interfaces are plugged into.
+///
+/// This type can be cloned, all the clones refer to the same network.
+#[derive(Debug, Clone, Default)]
+pub struct Switch {
+    inner: Arc<Mutex<SwitchState>>,
+}
+
+#[derive(Debug)]
+pub(super) struct ListenerEntry {
+    pub id: u64,
+    pub ip: IpAddr,
+    pub reuse_port: bool,
+    pub queue: Arc<AcceptQueue>,
+}
+
+#[derive(Debug)]
+pub(super) struct UdpEntry {
+    pub id: u64,
+    pub ip: IpAddr,
+    pub reuse: bool,
+    pub groups: Vec<IpAddr>,
+    pub queue: Arc<DatagramQueue>,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct SwitchState {
+    next_id: u64,
+    next_port: u16,
+    next_lease: u32,
+    addresses: HashMap<IpAddr, InterfaceId>,
+    hosts: HashMap<String, Vec<IpAddr>>,
+    tcp_listeners: HashMap<(InterfaceId, u16), Vec<ListenerEntry>>,
+    udp_sockets: HashMap<(InterfaceId, u16), Vec<UdpEntry>>,
+    partitions: HashSet<(IpAddr, IpAddr)>,
+    faults: FaultConfig,
+    rng: u64,
+    dropped: u64,
+}
+
+impl Switch {
+    /// Creates a new empty network
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn lock(&self) -> MutexGuard<'_, SwitchState> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Changes the faults that are injected into the network traffic
+    pub fn set_faults(&self, faults: FaultConfig) {
+        let mut state = self.lock();
+        state.rng = faults.seed;
+        state.faults = faults;
+    }
+
+    /// Returns the faults that are currently injected into the network
+    pub fn faults(&self) -> FaultConfig {
+        self.lock().faults.clone()
+    }
+
+    /// Cuts the network between two IP addresses so that no traffic can
+    /// flow between them until they are healed again
+    pub fn partition(&self, a: IpAddr, b: IpAddr) {
+        self.lock().partitions.insert(partition_key(a, b));
+    }
+
+    /// Restores the network between two IP addresses
+    pub fn heal(&self, a: IpAddr, b: IpAddr) {
+        self.lock().partitions.remove(&partition_key(a, b));
+    }
+
+    /// Removes all the partitions from the network
+    pub fn heal_all(&self) {
+        self.lock().partitions.clear();
+    }
+
+    /// Registers a host name that will be returned by `resolve`
+    pub fn add_host(&self, name: &str, ip: IpAddr) {
+        let mut state = self.lock();
+        let ips = state.hosts.entry(name.to_lowercase()).or_default();
+        if !ips.contains(&ip) {
+            ips.push(ip);
+        }
+    }
+
+    /// Removes a host name that was previously registered
+    pub fn remove_host(&self, name: &str) {
+        self.lock().hosts.remove(&name.to_lowercase());
+    }
+
+    /// Number of datagrams that were lost in transit (because of the
+    /// drop rate or because of a partition)
+    pub fn dropped_packets(&self) -> u64 {
+        self.lock().dropped
+    }
+}
+
+fn partition_key(a: IpAddr, b: IpAddr) -> (IpAddr, IpAddr) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Checks if a socket bound to `bound` can receive traffic sent to `dst`
+fn accepts(bound: IpAddr, dst: IpAddr) -> bool {
+    bound.is_unspecified() || bound == dst
+}
+
+/// Checks if two bound addresses overlap with each other
+fn overlaps(a: IpAddr, b: IpAddr) -> bool {
+    a.is_unspecified() || b.is_unspecified() || a == b
+}
+
+impl SwitchState {
+    pub fn new_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn latency(&self) -> Duration {
+        self.faults.latency
+    }
+
+    pub fn is_partitioned(&self, a: IpAddr, b: IpAddr) -> bool {
+        !self.partitions.is_empty() && self.partitions.contains(&partition_key(a, b))
+    }
+
+    /// Checks if a partition separates an address from all the addresses
+    /// of another interface
+    fn is_cut_off(&self, from: IpAddr, target: InterfaceId) -> bool {
+        !self.partitions.is_empty()
+            && self
+                .addresses
+                .iter()
+                .any(|(ip, owner)| *owner == target && self.is_partitioned(from, *ip))
+    }
+
+    /// Decides (deterministically) if the next datagram should be lost
+    fn should_drop(&mut self) -> bool {
+        let rate = self.faults.drop_rate;
+        if rate <= 0.0 {
+            return false;
+        }
+        if rate >= 1.0 {
+            return true;
+        }
+        // xorshift64* which is more than good enough for fault injection
+        let mut x = match self.rng {
+            0 => 0x9E37_79B9_7F4A_7C15,
+            x => x,
+        };
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng = x;
+        let val = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        (val as f64 / (1u64 << 53) as f64) < rate
+    }
+
+    pub fn claim_address(&mut self, iface: InterfaceId, ip: IpAddr) -> Result<()> {
+        match self.addresses.get(&ip) {
+            Some(owner) if *owner != iface => Err(NetworkError::AddressInUse),
+            _ => {
+                self.addresses.insert(ip, iface);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn release_address(&mut self, iface: InterfaceId, ip: IpAddr) {
+        if self.addresses.get(&ip) == Some(&iface) {
+            self.addresses.remove(&ip);
+        }
+    }
+
+    /// Hands out the next free address from the 10.0.0.0/16 range
+    pub fn lease_address(&mut self, iface: InterfaceId) -> Result<IpAddr> {
+        for _ in 0..u16::MAX {
+            self.next_lease = (self.next_lease % 0xFFFD) + 1;
+            let ip = IpAddr::V4(Ipv4Addr::from(0x0A00_0001 + self.next_lease));
+            if let Entry::Vacant(entry) = self.addresses.entry(ip) {
+                entry.insert(iface);
+                return Ok(ip);
+            }
+        }
+        Err(NetworkError::AddressNotAvailable)
+    }
+
+    /// Finds the interface that owns an address, loopback addresses always
+    /// belong to the interface that is asking
+    pub fn owner(&self, asking: InterfaceId, ip: IpAddr) -> Option<InterfaceId> {
+        if ip.is_loopback() {
+            return Some(asking);
+        }
+        self.addresses.get(&ip).cloned()
+    }
+
+    pub fn resolve(&self, host: &str) -> Option<Vec<IpAddr>> {
+        self.hosts.get(&host.to_lowercase()).cloned()
+    }
+
+    /// Picks a free ephemeral port on an interface
+    pub fn allocate_port(&mut self, iface: InterfaceId) -> Result<u16> {
+        let range = u16::MAX - EPHEMERAL_PORT_START;
+        for _ in 0..=range {
+            if self.next_port < EPHEMERAL_PORT_START {
+                self.next_port = EPHEMERAL_PORT_START;
+            }
+            let port = self.next_port;
+            self.next_port = port.checked_add(1).unwrap_or(EPHEMERAL_PORT_START);
+            if !self.tcp_listeners.contains_key(&(iface, port))
+                && !self.udp_sockets.contains_key(&(iface, port))
+            {
+                return Ok(port);
+            }
+        }
+        Err(NetworkError::AddressInUse)
+    }
+
+    pub fn add_listener(
+        &mut self,
+        iface: InterfaceId,
+        addr: SocketAddr,
+        reuse_port: bool,
+        queue: Arc<AcceptQueue>,
+    ) -> Result<u64> {
+        let id = self.new_id();
+        let entries = self.tcp_listeners.entry((iface, addr.port())).or_default();
+        if entries
+            .iter()
+            .any(|entry| overlaps(entry.ip, addr.ip()) && !(entry.reuse_port && reuse_port))
+        {
+            return Err(NetworkError::AddressInUse);
+        }
+        entries.push(ListenerEntry {
+            id,
+            ip: addr.ip(),
+            reuse_port,
+            queue,
+        });
+        Ok(id)
+    }
+
+    pub fn remove_listener(&mut self, iface: InterfaceId, port: u16, id: u64) {
+        if let Some(entries) = self.tcp_listeners.get_mut(&(iface, port)) {
+            entries.retain(|entry| entry.id != id);
+            if entries.is_empty() {
+                self.tcp_listeners.remove(&(iface, port));
+            }
+        }
+    }
+
+    /// Finds the listener that should receive a new connection
+    pub fn find_listener(
+        &self,
+        iface: InterfaceId,
+        from: SocketAddr,
+        dst: SocketAddr,
+    ) -> Option<Arc<AcceptQueue>> {
+        let entries: Vec<_> = self
+            .tcp_listeners
+            .get(&(iface, dst.port()))?
+            .iter()
+            .filter(|entry| accepts(entry.ip, dst.ip()))
+            .collect();
+        pick(&entries, from).map(|entry| entry.queue.clone())
+    }
+
+    pub fn add_udp(
+        &mut self,
+        iface: InterfaceId,
+        addr: SocketAddr,
+        reuse: bool,
+        queue: Arc<DatagramQueue>,
+    ) -> Result<u64> {
+        let id = self.new_id();
+        let entries = self.udp_sockets.entry((iface, addr.port())).or_default();
+        if entries
+            .iter()
+            .any(|entry| overlaps(entry.ip, addr.ip()) && !(entry.reuse && reuse))
+        {
+            return Err(NetworkError::AddressInUse);
+        }
+        entries.push(UdpEntry {
+            id,
+            ip: addr.ip(),
+            reuse,
+            groups: Vec::new(),
+            queue,
+        });
+        Ok(id)
+    }
+
+    pub fn remove_udp(&mut self, iface: InterfaceId, port: u16, id: u64) {
+        if let Some(entries) = self.udp_sockets.get_mut(&(iface, port)) {
+            entries.retain(|entry| entry.id != id);
+            if entries.is_empty() {
+                self.udp_sockets.remove(&(iface, port));
+            }
+        }
+    }
+
+    /// Changes the multicast groups that a UDP socket is a member of
+    pub fn update_groups(&mut self, iface: InterfaceId, port: u16, id: u64, groups: &[IpAddr]) {
+        if let Some(entry) = self
+            .udp_sockets
+            .get_mut(&(iface, port))
+            .and_then(|entries| entries.iter_mut().find(|entry| entry.id == id))
+        {
+            entry.groups = groups.to_vec();
+        }
+    }
+
+    /// Delivers a datagram to all the sockets that should receive it
+    pub fn send_datagram(
+        &mut self,
+        iface: InterfaceId,
+        from: SocketAddr,
+        to: SocketAddr,
+        data: Bytes,
+        multicast_loop: bool,
+    ) {
+        let deliver_at = Instant::now() + self.latency();
+        let broadcast = is_broadcast(to.ip());
+        let multicast = to.ip().is_multicast();
+
+        let mut targets = Vec::new();
+        if broadcast || multicast {
+            for ((target, port), entries) in self.udp_sockets.iter() {
+                if *port != to.port() {
+                    continue;
+                }
+                if multicast && *target == iface && !multicast_loop {
+                    continue;
+                }
+                for entry in entries {
+                    if broadcast || entry.groups.contains(&to.ip()) {
+                        targets.push((*target, entry.queue.clone()));
+                    }
+                }
+            }
+        } else if let Some(target) = self.owner(iface, to.ip()) {
+            if let Some(entries) = self.udp_sockets.get(&(target, to.port())) {
+                let entries: Vec<_> = entries
+                    .iter()
+                    .filter(|entry| accepts(entry.ip, to.ip()))
+                    .collect();
+                if let Some(entry) = pick(&entries, from) {
+                    targets.push((target, entry.queue.clone()));
+                }
+            }
+        }
+
+        for (target, queue) in targets {
+            // Packets that never leave the interface can not be lost
+            if target != iface && (self.is_cut_off(from.ip(), target) || self.should_drop()) {
+                self.dropped += 1;
+                continue;
+            }
+            queue.push(Datagram {
+                deliver_at,
+                from,
+                data: data.clone(),
+            });
+        }
+    }
+}
+
+fn is_broadcast(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_broadcast(),
+        IpAddr::V6(_) => false,
+    }
+}
+
+/// When multiple sockets share the same port the traffic is spread between
+/// them based on the address of the sender
+fn pick<'a, T>(entries: &[&'a T], from: SocketAddr) -> Option<&'a T> {
+    match entries.len() {
+        0 => None,
+        1 => Some(entries[0]),
+        len => {
+            let mut hasher = DefaultHasher::new();
+            from.hash(&mut hasher);
+            Some(entries[(hasher.finish() % len as u64) as usize])
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! This module contains the TCP listeners and streams of the in-memory
+//! network.
+
+use super::networking::Interface;
+use super::*;
+use crate::{
+    Bytes, NetworkError, Result, SocketReceive, SocketStatus, TimeType, VirtualConnectedSocket,
+    VirtualSocket, VirtualTcpListener, VirtualTcpSocket,
+};
+use std::collections::VecDeque;
+use std::net::{Shutdown, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+/// Connections that are waiting to be accepted by a listener
+#[derive(Debug, Default)]
+pub(super) struct AcceptQueue {
+    pending: Mutex<VecDeque<(TcpSocket, SocketAddr)>>,
+    cond: Condvar,
+}
+
+impl AcceptQueue {
+    pub fn push(&self, socket: TcpSocket, peer: SocketAddr) {
+        self.pending.lock().unwrap().push_back((socket, peer));
+        self.cond.notify_one();
+    }
+}
+
+/// One direction of a TCP stream
+#[derive(Debug, Default)]
+struct StreamPipe {
+    state: Mutex<PipeState>,
+    cond: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct PipeState {
+    chunks: VecDeque<(Instant, Bytes)>,
+    /// The writer will not send any more data
+    write_closed: bool,
+    /// The reader is no longer interested in the data
+    read_closed: bool,
+}
+
+impl StreamPipe {
+    fn close_write(&self) {
+        self.state.lock().unwrap().write_closed = true;
+        self.cond.notify_all();
+    }
+
+    fn close_read(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.read_closed = true;
+        state.chunks.clear();
+    }
+}
+
+#[derive(Debug)]
+pub struct TcpListener {
+    iface: Arc<Interface>,
+    id: u64,
+    addr: SocketAddr,
+    queue: Arc<AcceptQueue>,
+    timeout: Option<Duration>,
+    ttl: u8,
+}
+
+impl TcpListener {
+    pub(super) fn new(
+        iface: Arc<Interface>,
+        addr: SocketAddr,
+        reuse_port: bool,
+    ) -> Result<TcpListener> {
+        let queue = Arc::new(AcceptQueue::default());
+        let (id, addr) = {
+            let mut switch = iface.switch.lock();
+            let addr = match addr.port() {
+                0 => SocketAddr::new(addr.ip(), switch.allocate_port(iface.id)?),
+                _ => addr,
+            };
+            let id = switch.add_listener(iface.id, addr, reuse_port, queue.clone())?;
+            (id, addr)
+        };
+        Ok(TcpListener {
+            iface,
+            id,
+            addr,
+            queue,
+            timeout: None,
+            ttl: DEFAULT_TTL as u8,
+        })
+    }
+}
+
+impl VirtualTcpListener for TcpListener {
+    fn accept(&self) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        let deadline = deadline_after(self.timeout);
+        let mut pending = self.queue.pending.lock().unwrap();
+        loop {
+            if let Some((socket, peer)) = pending.pop_front() {
+                return Ok((Box::new(socket), peer));
+            }
+            pending =
+                wait_until(&self.queue.cond, pending, deadline).ok_or(NetworkError::TimedOut)?;
+        }
+    }
+
+    fn accept_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        let deadline = Some(Instant::now() + timeout);
+        let mut pending = self.queue.pending.lock().unwrap();
+        loop {
+            if let Some((socket, peer)) = pending.pop_front() {
+                return Ok((Box::new(socket), peer));
+            }
+            pending =
+                wait_until(&self.queue.cond, pending, deadline).ok_or(NetworkError::TimedOut)?;
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn timeout(&self) -> Result<Option<Duration>> {
+        Ok(self.timeout)
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn set_ttl(&mut self, ttl: u8) -> Result<()> {
+        self.ttl = ttl;
+        Ok(())
+    }
+
+    fn ttl(&self) -> Result<u8> {
+        Ok(self.ttl)
+    }
+
+    fn poll_accept_ready(&self) -> Result<usize> {
+        match self.queue.pending.lock().unwrap().len() {
+            0 => Err(NetworkError::WouldBlock),
+            pending => Ok(pending),
+        }
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        self.iface
+            .switch
+            .lock()
+            .remove_listener(self.iface.id, self.addr.port(), self.id);
+    }
+}
+
+#[derive(Debug)]
+pub struct TcpSocket {
+    switch: Switch,
+    addr: SocketAddr,
+    peer: SocketAddr,
+    tx: Arc<StreamPipe>,
+    rx: Arc<StreamPipe>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    linger: Option<Duration>,
+    nodelay: bool,
+    ttl: u32,
+    recv_buf_size: usize,
+    send_buf_size: usize,
+}
+
+impl TcpSocket {
+    /// Creates both ends of a new connection
+    pub(super) fn pair(
+        switch: &Switch,
+        client_addr: SocketAddr,
+        server_addr: SocketAddr,
+    ) -> (TcpSocket, TcpSocket) {
+        let up = Arc::new(StreamPipe::default());
+        let down = Arc::new(StreamPipe::default());
+        let client = TcpSocket::new(switch, client_addr, server_addr, up.clone(), down.clone());
+        let server = TcpSocket::new(switch, server_addr, client_addr, down, up);
+        (client, server)
+    }
+
+    fn new(
+        switch: &Switch,
+        addr: SocketAddr,
+        peer: SocketAddr,
+        tx: Arc<StreamPipe>,
+        rx: Arc<StreamPipe>,
+    ) -> TcpSocket {
+        TcpSocket {
+            switch: switch.clone(),
+            addr,
+            peer,
+            tx,
+            rx,
+            read_timeout: None,
+            write_timeout: None,
+            connect_timeout: None,
+            linger: None,
+            nodelay: false,
+            ttl: DEFAULT_TTL,
+            recv_buf_size: DEFAULT_BUF_SIZE,
+            send_buf_size: DEFAULT_BUF_SIZE,
+        }
+    }
+
+    pub(super) fn set_connect_timeout(&mut self, timeout: Option<Duration>) {
+        self.connect_timeout = timeout;
+    }
+
+    fn read(&mut self, consume: bool) -> Result<SocketReceive> {
+        let deadline = deadline_after(self.read_timeout);
+        let mut state = self.rx.state.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let mut wake_at = deadline;
+            if let Some((deliver_at, data)) = state.chunks.front() {
+                if *deliver_at <= now {
+                    let data = match consume {
+                        true => state.chunks.pop_front().unwrap().1,
+                        false => data.clone(),
+                    };
+                    return Ok(SocketReceive {
+                        data,
+                        truncated: false,
+                    });
+                }
+                wake_at = earliest(wake_at, Some(*deliver_at));
+            } else if state.write_closed || state.read_closed {
+                // End of the stream
+                return Ok(SocketReceive {
+                    data: Bytes::new(),
+                    truncated: false,
+                });
+            }
+            state = match wait_until(&self.rx.cond, state, wake_at) {
+                Some(state) => state,
+                None if wake_at == deadline => return Err(NetworkError::WouldBlock),
+                None => self.rx.state.lock().unwrap(),
+            };
+        }
+    }
+}
+
+impl VirtualTcpSocket for TcpSocket {
+    fn set_opt_time(&mut self, ty: TimeType, timeout: Option<Duration>) -> Result<()> {
+        match ty {
+            TimeType::ReadTimeout => self.read_timeout = timeout,
+            TimeType::WriteTimeout => self.write_timeout = timeout,
+            TimeType::ConnectTimeout => self.connect_timeout = timeout,
+            TimeType::Linger => self.linger = timeout,
+            _ => return Err(NetworkError::InvalidInput),
+        }
+        Ok(())
+    }
+
+    fn opt_time(&self, ty: TimeType) -> Result<Option<Duration>> {
+        match ty {
+            TimeType::ReadTimeout => Ok(self.read_timeout),
+            TimeType::WriteTimeout => Ok(self.write_timeout),
+            TimeType::ConnectTimeout => Ok(self.connect_timeout),
+            TimeType::Linger => Ok(self.linger),
+            _ => Err(NetworkError::InvalidInput),
+        }
+    }
+
+    fn set_recv_buf_size(&mut self, size: usize) -> Result<()> {
+        self.recv_buf_size = size;
+        Ok(())
+    }
+
+    fn recv_buf_size(&self) -> Result<usize> {
+        Ok(self.recv_buf_size)
+    }
+
+    fn set_send_buf_size(&mut self, size: usize) -> Result<()> {
+        self.send_buf_size = size;
+        Ok(())
+    }
+
+    fn send_buf_size(&self) -> Result<usize> {
+        Ok(self.send_buf_size)
+    }
+
+    fn set_nodelay(&mut self, nodelay: bool) -> Result<()> {
+        self.nodelay = nodelay;
+        Ok(())
+    }
+
+    fn nodelay(&self) -> Result<bool> {
+        Ok(self.nodelay)
+    }
+
+    fn addr_peer(&self) -> Result<SocketAddr> {
+        Ok(self.peer)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn shutdown(&mut self, how: Shutdown) -> Result<()> {
+        if let Shutdown::Write | Shutdown::Both = how {
+            self.tx.close_write();
+        }
+        if let Shutdown::Read | Shutdown::Both = how {
+            self.rx.close_read();
+        }
+        Ok(())
+    }
+}
+
+impl VirtualConnectedSocket for TcpSocket {
+    fn set_linger(&mut self, linger: Option<Duration>) -> Result<()> {
+        self.linger = linger;
+        Ok(())
+    }
+
+    fn linger(&self) -> Result<Option<Duration>> {
+        Ok(self.linger)
+    }
+
+    fn send(&mut self, data: Bytes) -> Result<usize> {
+        let latency = {
+            let switch = self.switch.lock();
+            if switch.is_partitioned(self.addr.ip(), self.peer.ip()) {
+                return Err(NetworkError::ConnectionReset);
+            }
+            switch.latency()
+        };
+
+        let mut state = self.tx.state.lock().unwrap();
+        if state.write_closed {
+            return Err(NetworkError::BrokenPipe);
+        }
+        if state.read_closed {
+            return Err(NetworkError::ConnectionReset);
+        }
+        let len = data.len();
+        state.chunks.push_back((Instant::now() + latency, data));
+        self.tx.cond.notify_all();
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<SocketReceive> {
+        self.read(true)
+    }
+
+    fn peek(&mut self) -> Result<SocketReceive> {
+        self.read(false)
+    }
+}
+
+impl VirtualSocket for TcpSocket {
+    fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        self.ttl = ttl;
+        Ok(())
+    }
+
+    fn ttl(&self) -> Result<u32> {
+        Ok(self.ttl)
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn status(&self) -> Result<SocketStatus> {
+        let closed =
+            self.tx.state.lock().unwrap().write_closed && self.rx.state.lock().unwrap().read_closed;
+        Ok(match closed {
+            true => SocketStatus::Closed,
+            false => SocketStatus::Opened,
+        })
+    }
+
+    fn poll_read_ready(&self) -> Result<usize> {
+        let state = self.rx.state.lock().unwrap();
+        let now = Instant::now();
+        let available: usize = state
+            .chunks
+            .iter()
+            .take_while(|(deliver_at, _)| *deliver_at <= now)
+            .map(|(_, data)| data.len())
+            .sum();
+        match available {
+            0 if state.chunks.is_empty() && (state.write_closed || state.read_closed) => Ok(0),
+            0 => Err(NetworkError::WouldBlock),
+            available => Ok(available),
+        }
+    }
+
+    fn poll_write_ready(&self) -> Result<usize> {
+        let state = self.tx.state.lock().unwrap();
+        if state.write_closed {
+            return Err(NetworkError::BrokenPipe);
+        }
+        if state.read_closed {
+            return Err(NetworkError::ConnectionReset);
+        }
+        Ok(self.send_buf_size)
+    }
+}
+
+impl Drop for TcpSocket {
+    fn drop(&mut self) {
+        self.tx.close_write();
+        self.rx.close_read();
+    }
+}
@@ -0,0 +1,320 @@
+//! This module contains the UDP sockets of the in-memory network.
+
+use super::networking::Interface;
+use super::*;
+use crate::{
+    Bytes, NetworkError, Result, SocketReceive, SocketReceiveFrom, SocketStatus,
+    VirtualConnectedSocket, VirtualConnectionlessSocket, VirtualSocket, VirtualUdpSocket,
+};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+pub(super) struct Datagram {
+    pub deliver_at: Instant,
+    pub from: SocketAddr,
+    pub data: Bytes,
+}
+
+/// Datagrams that were delivered to a socket but not yet received
+#[derive(Debug, Default)]
+pub(super) struct DatagramQueue {
+    packets: Mutex<VecDeque<Datagram>>,
+    cond: Condvar,
+}
+
+impl DatagramQueue {
+    pub fn push(&self, datagram: Datagram) {
+        self.packets.lock().unwrap().push_back(datagram);
+        self.cond.notify_all();
+    }
+}
+
+#[derive(Debug)]
+pub struct UdpSocket {
+    iface: Arc<Interface>,
+    id: u64,
+    addr: SocketAddr,
+    queue: Arc<DatagramQueue>,
+    peer: Option<SocketAddr>,
+    groups: Vec<IpAddr>,
+    broadcast: bool,
+    multicast_loop_v4: bool,
+    multicast_loop_v6: bool,
+    multicast_ttl_v4: u32,
+    ttl: u32,
+}
+
+impl UdpSocket {
+    pub(super) fn new(iface: Arc<Interface>, addr: SocketAddr, reuse: bool) -> Result<UdpSocket> {
+        let queue = Arc::new(DatagramQueue::default());
+        let (id, addr) = {
+            let mut switch = iface.switch.lock();
+            let addr = match addr.port() {
+                0 => SocketAddr::new(addr.ip(), switch.allocate_port(iface.id)?),
+                _ => addr,
+            };
+            let id = switch.add_udp(iface.id, addr, reuse, queue.clone())?;
+            (id, addr)
+        };
+        Ok(UdpSocket {
+            iface,
+            id,
+            addr,
+            queue,
+            peer: None,
+            groups: Vec::new(),
+            broadcast: false,
+            multicast_loop_v4: true,
+            multicast_loop_v6: true,
+            multicast_ttl_v4: 1,
+            ttl: DEFAULT_TTL,
+        })
+    }
+
+    fn join(&mut self, group: IpAddr) -> Result<()> {
+        if !group.is_multicast() {
+            return Err(NetworkError::InvalidInput);
+        }
+        if self.groups.contains(&group) {
+            return Err(NetworkError::AlreadyExists);
+        }
+        self.groups.push(group);
+        self.update_groups();
+        Ok(())
+    }
+
+    fn leave(&mut self, group: IpAddr) -> Result<()> {
+        if !self.groups.contains(&group) {
+            return Err(NetworkError::AddressNotAvailable);
+        }
+        self.groups.retain(|g| *g != group);
+        self.update_groups();
+        Ok(())
+    }
+
+    fn update_groups(&self) {
+        self.iface.switch.lock().update_groups(
+            self.iface.id,
+            self.addr.port(),
+            self.id,
+            &self.groups,
+        );
+    }
+
+    fn read(&mut self, consume: bool) -> Result<SocketReceiveFrom> {
+        let mut packets = self.queue.packets.lock().unwrap();
+        loop {
+            // A connected socket only receives datagrams from its peer
+            if let Some(peer) = self.peer {
+                packets.retain(|packet| packet.from == peer);
+            }
+            let mut wake_at = None;
+            if let Some(packet) = packets.front() {
+                if packet.deliver_at <= Instant::now() {
+                    let packet = match consume {
+                        true => packets.pop_front().unwrap(),
+                        false => Datagram {
+                            deliver_at: packet.deliver_at,
+                            from: packet.from,
+                            data: packet.data.clone(),
+                        },
+                    };
+                    return Ok(SocketReceiveFrom {
+                        data: packet.data,
+                        truncated: false,
+                        addr: packet.from,
+                    });
+                }
+                wake_at = Some(packet.deliver_at);
+            }
+            packets = match wait_until(&self.queue.cond, packets, wake_at) {
+                Some(packets) => packets,
+                None => self.queue.packets.lock().unwrap(),
+            };
+        }
+    }
+
+    fn send_datagram(&mut self, data: Bytes, to: SocketAddr) -> Result<usize> {
+        if to.ip().is_unspecified() || to.port() == 0 {
+            return Err(NetworkError::InvalidInput);
+        }
+        let broadcast = matches!(to.ip(), IpAddr::V4(ip) if ip.is_broadcast());
+        if broadcast && !self.broadcast {
+            return Err(NetworkError::PermissionDenied);
+        }
+        let from = SocketAddr::new(
+            self.iface.source_for(self.addr.ip(), to.ip())?,
+            self.addr.port(),
+        );
+        if !broadcast && !to.ip().is_multicast() && !self.iface.can_reach(to.ip()) {
+            return Err(NetworkError::AddressNotAvailable);
+        }
+        let multicast_loop = match to.ip() {
+            IpAddr::V4(_) => self.multicast_loop_v4,
+            IpAddr::V6(_) => self.multicast_loop_v6,
+        };
+
+        let len = data.len();
+        self.iface
+            .switch
+            .lock()
+            .send_datagram(self.iface.id, from, to, data, multicast_loop);
+        Ok(len)
+    }
+}
+
+impl VirtualUdpSocket for UdpSocket {
+    fn connect(&mut self, addr: SocketAddr) -> Result<()> {
+        self.peer = Some(addr);
+        Ok(())
+    }
+
+    fn set_broadcast(&mut self, broadcast: bool) -> Result<()> {
+        self.broadcast = broadcast;
+        Ok(())
+    }
+
+    fn broadcast(&self) -> Result<bool> {
+        Ok(self.broadcast)
+    }
+
+    fn set_multicast_loop_v4(&mut self, val: bool) -> Result<()> {
+        self.multicast_loop_v4 = val;
+        Ok(())
+    }
+
+    fn multicast_loop_v4(&self) -> Result<bool> {
+        Ok(self.multicast_loop_v4)
+    }
+
+    fn set_multicast_loop_v6(&mut self, val: bool) -> Result<()> {
+        self.multicast_loop_v6 = val;
+        Ok(())
+    }
+
+    fn multicast_loop_v6(&self) -> Result<bool> {
+        Ok(self.multicast_loop_v6)
+    }
+
+    fn set_multicast_ttl_v4(&mut self, ttl: u32) -> Result<()> {
+        self.multicast_ttl_v4 = ttl;
+        Ok(())
+    }
+
+    fn multicast_ttl_v4(&self) -> Result<u32> {
+        Ok(self.multicast_ttl_v4)
+    }
+
+    fn join_multicast_v4(&mut self, multiaddr: Ipv4Addr, _iface: Ipv4Addr) -> Result<()> {
+        self.join(IpAddr::V4(multiaddr))
+    }
+
+    fn leave_multicast_v4(&mut self, multiaddr: Ipv4Addr, _iface: Ipv4Addr) -> Result<()> {
+        self.leave(IpAddr::V4(multiaddr))
+    }
+
+    fn join_multicast_v6(&mut self, multiaddr: Ipv6Addr, _iface: u32) -> Result<()> {
+        self.join(IpAddr::V6(multiaddr))
+    }
+
+    fn leave_multicast_v6(&mut self, multiaddr: Ipv6Addr, _iface: u32) -> Result<()> {
+        self.leave(IpAddr::V6(multiaddr))
+    }
+
+    fn addr_peer(&self) -> Result<Option<SocketAddr>> {
+        Ok(self.peer)
+    }
+}
+
+impl VirtualConnectedSocket for UdpSocket {
+    fn set_linger(&mut self, _linger: Option<Duration>) -> Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn linger(&self) -> Result<Option<Duration>> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn send(&mut self, data: Bytes) -> Result<usize> {
+        let peer = self.peer.ok_or(NetworkError::NotConnected)?;
+        self.send_datagram(data, peer)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<SocketReceive> {
+        let packet = self.read(true)?;
+        Ok(SocketReceive {
+            data: packet.data,
+            truncated: packet.truncated,
+        })
+    }
+
+    fn peek(&mut self) -> Result<SocketReceive> {
+        let packet = self.read(false)?;
+        Ok(SocketReceive {
+            data: packet.data,
+            truncated: packet.truncated,
+        })
+    }
+}
+
+impl VirtualConnectionlessSocket for UdpSocket {
+    fn send_to(&mut self, data: Bytes, addr: SocketAddr) -> Result<usize> {
+        self.send_datagram(data, addr)
+    }
+
+    fn recv_from(&mut self) -> Result<SocketReceiveFrom> {
+        self.read(true)
+    }
+
+    fn peek_from(&mut self) -> Result<SocketReceiveFrom> {
+        self.read(false)
+    }
+}
+
+impl VirtualSocket for UdpSocket {
+    fn set_ttl(&mut self, ttl: u32) -> Result<()> {
+        self.ttl = ttl;
+        Ok(())
+    }
+
+    fn ttl(&self) -> Result<u32> {
+        Ok(self.ttl)
+    }
+
+    fn addr_local(&self) -> Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn status(&self) -> Result<SocketStatus> {
+        Ok(SocketStatus::Opened)
+    }
+
+    fn poll_read_ready(&self) -> Result<usize> {
+        let packets = self.queue.packets.lock().unwrap();
+        packets
+            .iter()
+            .find(|packet| self.peer.map(|peer| packet.from == peer).unwrap_or(true))
+            .filter(|packet| packet.deliver_at <= Instant::now())
+            .map(|packet| packet.data.len())
+            .ok_or(NetworkError::WouldBlock)
+    }
+
+    fn poll_write_ready(&self) -> Result<usize> {
+        Ok(DEFAULT_BUF_SIZE)
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        self.iface
+            .switch
+            .lock()
+            .remove_udp(self.iface.id, self.addr.port(), self.id);
+    }
+}
@@ -0,0 +1,112 @@
+//! HTTP requests that are made by the host on behalf of the sandbox
+
+use std::io::{self, Read};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use tracing::debug;
+use wasmer_vnet::{HttpStatus, NetworkError, Result, SocketHttpRequest};
+
+/// Size of the chunks that the response body is handed out in
+const RESPONSE_CHUNK_SIZE: usize = 8192;
+/// Number of response chunks that are read ahead of the sandbox, after
+/// which reading from the server waits for the sandbox to catch up
+const RESPONSE_BACKLOG: usize = 16;
+
+/// Starts a HTTP request, which is sent once the sandbox closes the
+/// request body sender and is then answered through the receivers.
+pub fn request(url: &str, method: &str, headers: &str, gzip: bool) -> Result<SocketHttpRequest> {
+    let mut request = ureq::request(method, url);
+    for line in headers.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, value) = line.split_once(':').ok_or(NetworkError::InvalidInput)?;
+        request = request.set(name.trim(), value.trim());
+    }
+    // ureq asks for gzip and decompresses it by itself unless the encoding
+    // was chosen explicitly
+    if !gzip && request.header("Accept-Encoding").is_none() {
+        request = request.set("Accept-Encoding", "identity");
+    }
+
+    let url = request.request_url().map_err(ureq_err_into_net_error)?;
+    let url = url.as_url().to_string();
+    match url.split(':').next() {
+        Some("http") => {}
+        #[cfg(feature = "tls")]
+        Some("https") => {}
+        _ => return Err(NetworkError::Unsupported),
+    }
+
+    let (request_tx, request_rx) = mpsc::channel::<Vec<u8>>();
+    let (response_tx, response_rx) = mpsc::sync_channel(RESPONSE_BACKLOG);
+    let (headers_tx, headers_rx) = mpsc::channel();
+    let (status_tx, status_rx) = mpsc::channel();
+    thread::spawn(move || {
+        // The body is complete once the sandbox closes the request sender
+        let body: Vec<u8> = request_rx.iter().flatten().collect();
+        let response = match request.send_bytes(&body[..]) {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => {
+                debug!("http request to {} failed - {}", url, err);
+                let _ = status_tx.send(Err(ureq_err_into_net_error(err)));
+                return;
+            }
+        };
+
+        for name in response.headers_names() {
+            for value in response.all(&name) {
+                let _ = headers_tx.send((name.clone(), value.to_string()));
+            }
+        }
+        drop(headers_tx);
+        let _ = status_tx.send(Ok(HttpStatus {
+            redirected: response.get_url() != url,
+            size: response
+                .header("Content-Length")
+                .and_then(|len| len.parse().ok())
+                .unwrap_or(0),
+            status: response.status(),
+            status_text: response.status_text().to_string(),
+        }));
+
+        let mut reader = response.into_reader();
+        loop {
+            let mut chunk = vec![0; RESPONSE_CHUNK_SIZE];
+            match reader.read(&mut chunk[..]) {
+                Ok(0) => break,
+                Ok(read) => {
+                    chunk.truncate(read);
+                    // The sandbox is no longer interested in the response
+                    if response_tx.send(chunk).is_err() {
+                        break;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    debug!("http response from {} failed - {}", url, err);
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(SocketHttpRequest {
+        request: Some(request_tx),
+        response: Some(response_rx),
+        headers: Some(headers_rx),
+        status: Arc::new(Mutex::new(status_rx)),
+    })
+}
+
+fn ureq_err_into_net_error(err: ureq::Error) -> NetworkError {
+    use ureq::ErrorKind;
+    match err.kind() {
+        ErrorKind::InvalidUrl | ErrorKind::BadHeader => NetworkError::InvalidInput,
+        ErrorKind::UnknownScheme | ErrorKind::InsecureRequestHttpsOnly => NetworkError::Unsupported,
+        ErrorKind::Dns | ErrorKind::ConnectionFailed => NetworkError::ConnectionRefused,
+        ErrorKind::TooManyRedirects | ErrorKind::BadStatus => NetworkError::InvalidData,
+        _ => NetworkError::IOError,
+    }
+}
@@ -1,8 +1,9 @@
 #![allow(unused_variables)]
 use bytes::{Bytes, BytesMut};
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr};
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 #[allow(unused_imports, dead_code)]
 use tracing::{debug, error, info, trace, warn};
 use wasmer_vnet::{
@@ -12,15 +13,55 @@ use wasmer_vnet::{
     VirtualSocket, VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket, VirtualWebSocket,
 };
 
+#[cfg(not(target_os = "wasi"))]
+mod http;
+#[cfg(not(target_os = "wasi"))]
+mod web_socket;
+
+#[cfg(not(target_os = "wasi"))]
+pub use web_socket::LocalWebSocket;
+
+/// Size of the buffer used when reading from a TCP stream
+const TCP_RECV_SIZE: usize = 8192;
+/// Size of the buffer used when reading from a UDP socket, which is big
+/// enough to hold the largest possible datagram
+const UDP_RECV_SIZE: usize = 65536;
+
+/// Virtual networking implementation that is backed by the sockets of
+/// the host operating system.
+///
+/// The interface configuration (IP addresses, routes, bridging) belongs
+/// to the host and hence can not be changed from inside the sandbox.
+///
+/// Web sockets and HTTP requests are made by the host as well; `wss://`
+/// and `https://` URLs need the `tls` feature.
 #[derive(Debug, Default)]
 pub struct LocalNetworking {}
 
 #[allow(unused_variables)]
 impl VirtualNetworking for LocalNetworking {
+    #[cfg(not(target_os = "wasi"))]
+    fn ws_connect(&self, url: &str) -> Result<Box<dyn VirtualWebSocket + Sync>> {
+        Ok(Box::new(web_socket::connect(url)?))
+    }
+
+    #[cfg(target_os = "wasi")]
     fn ws_connect(&self, url: &str) -> Result<Box<dyn VirtualWebSocket + Sync>> {
         Err(NetworkError::Unsupported)
     }
 
+    #[cfg(not(target_os = "wasi"))]
+    fn http_request(
+        &self,
+        url: &str,
+        method: &str,
+        headers: &str,
+        gzip: bool,
+    ) -> Result<SocketHttpRequest> {
+        http::request(url, method, headers, gzip)
+    }
+
+    #[cfg(target_os = "wasi")]
     fn http_request(
         &self,
         url: &str,
@@ -100,24 +141,28 @@ impl VirtualNetworking for LocalNetworking {
         reuse_port: bool,
         reuse_addr: bool,
     ) -> Result<Box<dyn VirtualTcpListener + Sync>> {
-        let listener = std::net::TcpListener::bind(addr)
-            .map(|sock| {
-                Box::new(LocalTcpListener {
-                    stream: sock,
-                    timeout: None,
-                })
-            })
+        let stream = sys::listen_tcp(addr, only_v6, reuse_port, reuse_addr)
+            .map_err(io_err_into_net_error)?;
+        // The listener never blocks, waiting for connections is left to
+        // `sys::wait_readable` so that every call can have its own timeout
+        stream
+            .set_nonblocking(true)
             .map_err(io_err_into_net_error)?;
-        Ok(listener)
+        Ok(Box::new(LocalTcpListener {
+            stream,
+            timeout: None,
+            ready: Mutex::new(None),
+        }))
     }
 
     fn bind_udp(
         &self,
         addr: SocketAddr,
-        _reuse_port: bool,
-        _reuse_addr: bool,
+        reuse_port: bool,
+        reuse_addr: bool,
     ) -> Result<Box<dyn VirtualUdpSocket + Sync>> {
-        let socket = std::net::UdpSocket::bind(addr).map_err(io_err_into_net_error)?;
+        let socket = sys::bind_udp(addr, reuse_port, reuse_addr).map_err(io_err_into_net_error)?;
+        let addr = socket.local_addr().map_err(io_err_into_net_error)?;
         Ok(Box::new(LocalUdpSocket(socket, addr)))
     }
 
@@ -127,21 +172,16 @@ impl VirtualNetworking for LocalNetworking {
 
     fn connect_tcp(
         &self,
-        _addr: SocketAddr,
+        addr: SocketAddr,
         peer: SocketAddr,
         timeout: Option<Duration>,
     ) -> Result<Box<dyn VirtualTcpSocket + Sync>> {
-        let stream = if let Some(timeout) = timeout {
-            std::net::TcpStream::connect_timeout(&peer, timeout)
-        } else {
-            std::net::TcpStream::connect(peer)
-        }
-        .map_err(io_err_into_net_error)?;
+        let stream = sys::connect_tcp(addr, peer, timeout).map_err(io_err_into_net_error)?;
         let peer = stream.peer_addr().map_err(io_err_into_net_error)?;
         Ok(Box::new(LocalTcpStream {
             stream,
             addr: peer,
-            connect_timeout: None,
+            connect_timeout: timeout,
         }))
     }
 
@@ -152,16 +192,23 @@ impl VirtualNetworking for LocalNetworking {
         dns_server: Option<IpAddr>,
     ) -> Result<Vec<IpAddr>> {
         use std::net::ToSocketAddrs;
-        Ok(if let Some(port) = port {
-            let host = format!("{}:{}", host, port);
-            host.to_socket_addrs()
-                .map(|a| a.map(|a| a.ip()).collect::<Vec<_>>())
-                .map_err(io_err_into_net_error)?
-        } else {
-            host.to_socket_addrs()
-                .map(|a| a.map(|a| a.ip()).collect::<Vec<_>>())
-                .map_err(io_err_into_net_error)?
-        })
+
+        // The host resolver always uses the name servers that are configured
+        // on the machine, it can not be pointed at a particular server
+        if dns_server.is_some() {
+            return Err(NetworkError::Unsupported);
+        }
+
+        let mut ret = Vec::new();
+        for addr in (host, port.unwrap_or(0))
+            .to_socket_addrs()
+            .map_err(io_err_into_net_error)?
+        {
+            if !ret.contains(&addr.ip()) {
+                ret.push(addr.ip());
+            }
+        }
+        Ok(ret)
     }
 }
 
@@ -169,58 +216,72 @@ impl VirtualNetworking for LocalNetworking {
 pub struct LocalTcpListener {
     stream: std::net::TcpListener,
     timeout: Option<Duration>,
+    /// Connection that was accepted while polling for readiness and that
+    /// is handed out by the next call to `accept`
+    ready: Mutex<Option<(std::net::TcpStream, SocketAddr)>>,
 }
 
-impl VirtualTcpListener for LocalTcpListener {
-    fn accept(&self) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
-        if let Some(timeout) = &self.timeout {
-            return self.accept_timeout(*timeout);
-        }
-        let (sock, addr) = self
-            .stream
-            .accept()
-            .map(|(sock, addr)| {
-                (
-                    Box::new(LocalTcpStream {
-                        stream: sock,
-                        addr,
-                        connect_timeout: None,
-                    }),
-                    addr,
-                )
-            })
-            .map_err(io_err_into_net_error)?;
-        Ok((sock, addr))
+impl LocalTcpListener {
+    fn take_ready(&self) -> Option<(std::net::TcpStream, SocketAddr)> {
+        self.ready.lock().unwrap().take()
     }
 
-    #[cfg(feature = "wasix")]
-    fn accept_timeout(
+    /// Accepts the next connection, waiting for it until the deadline
+    /// (if any) has passed
+    fn accept_until(
         &self,
-        timeout: Duration,
+        deadline: Option<Instant>,
     ) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
-        let (sock, addr) = self
-            .stream
-            .accept_timeout(timeout)
-            .map(|(sock, addr)| {
-                (
-                    Box::new(LocalTcpStream {
-                        stream: sock,
-                        addr: addr.clone(),
-                        connect_timeout: None,
-                    }),
-                    addr,
-                )
-            })
-            .map_err(io_err_into_net_error)?;
-        Ok((sock, addr))
+        let (sock, addr) = match self.take_ready() {
+            Some(ret) => ret,
+            None => loop {
+                match self.stream.accept() {
+                    Ok((sock, addr)) => {
+                        // On some platforms the accepted socket inherits the
+                        // non-blocking flag of the listener
+                        sock.set_nonblocking(false).map_err(io_err_into_net_error)?;
+                        break (sock, addr);
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(io_err_into_net_error(err)),
+                }
+                let timeout = match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Err(NetworkError::TimedOut);
+                        }
+                        Some(deadline - now)
+                    }
+                    None => None,
+                };
+                // Another thread may take the connection first, in which
+                // case the accept above tells us to keep waiting
+                sys::wait_readable(&self.stream, timeout).map_err(io_err_into_net_error)?;
+            },
+        };
+        Ok((
+            Box::new(LocalTcpStream {
+                stream: sock,
+                addr,
+                connect_timeout: None,
+            }),
+            addr,
+        ))
+    }
+}
+
+impl VirtualTcpListener for LocalTcpListener {
+    fn accept(&self) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        self.accept_until(self.timeout.map(|timeout| Instant::now() + timeout))
     }
 
-    #[cfg(not(feature = "wasix"))]
     fn accept_timeout(
         &self,
-        _timeout: Duration,
+        timeout: Duration,
     ) -> Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
-        self.accept()
+        self.accept_until(Some(Instant::now() + timeout))
     }
 
     /// Sets the accept timeout
@@ -250,6 +311,16 @@ impl VirtualTcpListener for LocalTcpListener {
             .map(|ttl| ttl as u8)
             .map_err(io_err_into_net_error)
     }
+
+    fn poll_accept_ready(&self) -> Result<usize> {
+        let mut ready = self.ready.lock().unwrap();
+        if ready.is_none() {
+            let (sock, addr) = self.stream.accept().map_err(io_err_into_net_error)?;
+            sock.set_nonblocking(false).map_err(io_err_into_net_error)?;
+            *ready = Some((sock, addr));
+        }
+        Ok(1)
+    }
 }
 
 #[derive(Debug)]
@@ -274,11 +345,7 @@ impl VirtualTcpSocket for LocalTcpStream {
                 self.connect_timeout = timeout;
                 Ok(())
             }
-            #[cfg(feature = "wasix")]
-            TimeType::Linger => self
-                .stream
-                .set_linger(timeout)
-                .map_err(io_err_into_net_error),
+            TimeType::Linger => self.set_linger(timeout),
             _ => Err(NetworkError::InvalidInput),
         }
     }
@@ -288,26 +355,25 @@ impl VirtualTcpSocket for LocalTcpStream {
             TimeType::ReadTimeout => self.stream.read_timeout().map_err(io_err_into_net_error),
             TimeType::WriteTimeout => self.stream.write_timeout().map_err(io_err_into_net_error),
             TimeType::ConnectTimeout => Ok(self.connect_timeout),
-            #[cfg(feature = "wasix")]
-            TimeType::Linger => self.stream.linger().map_err(io_err_into_net_error),
+            TimeType::Linger => self.linger(),
             _ => Err(NetworkError::InvalidInput),
         }
     }
 
     fn set_recv_buf_size(&mut self, size: usize) -> Result<()> {
-        Ok(())
+        sys::set_recv_buf_size(&self.stream, size).map_err(io_err_into_net_error)
     }
 
     fn recv_buf_size(&self) -> Result<usize> {
-        Err(NetworkError::Unsupported)
+        sys::recv_buf_size(&self.stream).map_err(io_err_into_net_error)
     }
 
     fn set_send_buf_size(&mut self, size: usize) -> Result<()> {
-        Ok(())
+        sys::set_send_buf_size(&self.stream, size).map_err(io_err_into_net_error)
     }
 
     fn send_buf_size(&self) -> Result<usize> {
-        Err(NetworkError::Unsupported)
+        sys::send_buf_size(&self.stream).map_err(io_err_into_net_error)
     }
 
     fn set_nodelay(&mut self, nodelay: bool) -> Result<()> {
@@ -325,7 +391,7 @@ impl VirtualTcpSocket for LocalTcpStream {
     }
 
     fn flush(&mut self) -> Result<()> {
-        Ok(())
+        self.stream.flush().map_err(io_err_into_net_error)
     }
 
     fn shutdown(&mut self, how: Shutdown) -> Result<()> {
@@ -335,21 +401,11 @@ impl VirtualTcpSocket for LocalTcpStream {
 
 impl VirtualConnectedSocket for LocalTcpStream {
     fn set_linger(&mut self, linger: Option<Duration>) -> Result<()> {
-        #[cfg(feature = "wasix")]
-        self.stream
-            .set_linger(linger)
-            .map_err(io_err_into_net_error)?;
-        Ok(())
-    }
-
-    #[cfg(feature = "wasix")]
-    fn linger(&self) -> Result<Option<Duration>> {
-        self.stream.linger().map_err(io_err_into_net_error)
+        sys::set_linger(&self.stream, linger).map_err(io_err_into_net_error)
     }
 
-    #[cfg(not(feature = "wasix"))]
     fn linger(&self) -> Result<Option<Duration>> {
-        Ok(None)
+        sys::linger(&self.stream).map_err(io_err_into_net_error)
     }
 
     fn send(&mut self, data: Bytes) -> Result<usize> {
@@ -364,8 +420,8 @@ impl VirtualConnectedSocket for LocalTcpStream {
     }
 
     fn recv(&mut self) -> Result<SocketReceive> {
-        let buf_size = 8192;
-        let mut buf = BytesMut::with_capacity(buf_size);
+        let mut buf = BytesMut::new();
+        buf.resize(TCP_RECV_SIZE, 0);
         let read = self
             .stream
             .read(&mut buf[..])
@@ -373,13 +429,13 @@ impl VirtualConnectedSocket for LocalTcpStream {
         let buf = Bytes::from(buf).slice(..read);
         Ok(SocketReceive {
             data: buf,
-            truncated: read == buf_size,
+            truncated: false,
         })
     }
 
     fn peek(&mut self) -> Result<SocketReceive> {
-        let buf_size = 8192;
-        let mut buf = BytesMut::with_capacity(buf_size);
+        let mut buf = BytesMut::new();
+        buf.resize(TCP_RECV_SIZE, 0);
         let read = self
             .stream
             .peek(&mut buf[..])
@@ -387,7 +443,7 @@ impl VirtualConnectedSocket for LocalTcpStream {
         let buf = Bytes::from(buf).slice(..read);
         Ok(SocketReceive {
             data: buf,
-            truncated: read == buf_size,
+            truncated: false,
         })
     }
 }
@@ -408,6 +464,24 @@ impl VirtualSocket for LocalTcpStream {
     fn status(&self) -> Result<SocketStatus> {
         Ok(SocketStatus::Opened)
     }
+
+    fn poll_read_ready(&self) -> Result<usize> {
+        let mut buf = BytesMut::new();
+        buf.resize(TCP_RECV_SIZE, 0);
+        self.stream
+            .set_nonblocking(true)
+            .map_err(io_err_into_net_error)?;
+        let ret = self.stream.peek(&mut buf[..]);
+        self.stream
+            .set_nonblocking(false)
+            .map_err(io_err_into_net_error)?;
+        ret.map_err(io_err_into_net_error)
+    }
+
+    fn poll_write_ready(&self) -> Result<usize> {
+        // Writes are only held back by the kernel buffer of the host socket
+        Ok(sys::send_buf_size(&self.stream).unwrap_or(TCP_RECV_SIZE))
+    }
 }
 
 #[derive(Debug)]
@@ -483,7 +557,11 @@ impl VirtualUdpSocket for LocalUdpSocket {
     }
 
     fn addr_peer(&self) -> Result<Option<SocketAddr>> {
-        self.0.peer_addr().map(Some).map_err(io_err_into_net_error)
+        match self.0.peer_addr() {
+            Ok(addr) => Ok(Some(addr)),
+            Err(err) if err.kind() == io::ErrorKind::NotConnected => Ok(None),
+            Err(err) => Err(io_err_into_net_error(err)),
+        }
     }
 }
 
@@ -505,24 +583,24 @@ impl VirtualConnectedSocket for LocalUdpSocket {
     }
 
     fn recv(&mut self) -> Result<SocketReceive> {
-        let buf_size = 8192;
-        let mut buf = BytesMut::with_capacity(buf_size);
+        let mut buf = BytesMut::new();
+        buf.resize(UDP_RECV_SIZE, 0);
         let read = self.0.recv(&mut buf[..]).map_err(io_err_into_net_error)?;
         let buf = Bytes::from(buf).slice(..read);
         Ok(SocketReceive {
             data: buf,
-            truncated: read == buf_size,
+            truncated: read == UDP_RECV_SIZE,
         })
     }
 
     fn peek(&mut self) -> Result<SocketReceive> {
-        let buf_size = 8192;
-        let mut buf = BytesMut::with_capacity(buf_size);
+        let mut buf = BytesMut::new();
+        buf.resize(UDP_RECV_SIZE, 0);
         let read = self.0.peek(&mut buf[..]).map_err(io_err_into_net_error)?;
         let buf = Bytes::from(buf).slice(..read);
         Ok(SocketReceive {
             data: buf,
-            truncated: read == buf_size,
+            truncated: read == UDP_RECV_SIZE,
         })
     }
 }
@@ -535,8 +613,8 @@ impl VirtualConnectionlessSocket for LocalUdpSocket {
     }
 
     fn recv_from(&mut self) -> Result<SocketReceiveFrom> {
-        let buf_size = 8192;
-        let mut buf = BytesMut::with_capacity(buf_size);
+        let mut buf = BytesMut::new();
+        buf.resize(UDP_RECV_SIZE, 0);
         let (read, peer) = self
             .0
             .recv_from(&mut buf[..])
@@ -544,14 +622,14 @@ impl VirtualConnectionlessSocket for LocalUdpSocket {
         let buf = Bytes::from(buf).slice(..read);
         Ok(SocketReceiveFrom {
             data: buf,
-            truncated: read == buf_size,
+            truncated: read == UDP_RECV_SIZE,
             addr: peer,
         })
     }
 
     fn peek_from(&mut self) -> Result<SocketReceiveFrom> {
-        let buf_size = 8192;
-        let mut buf = BytesMut::with_capacity(buf_size);
+        let mut buf = BytesMut::new();
+        buf.resize(UDP_RECV_SIZE, 0);
         let (read, peer) = self
             .0
             .peek_from(&mut buf[..])
@@ -559,7 +637,7 @@ impl VirtualConnectionlessSocket for LocalUdpSocket {
         let buf = Bytes::from(buf).slice(..read);
         Ok(SocketReceiveFrom {
             data: buf,
-            truncated: read == buf_size,
+            truncated: read == UDP_RECV_SIZE,
             addr: peer,
         })
     }
@@ -581,4 +659,252 @@ impl VirtualSocket for LocalUdpSocket {
     fn status(&self) -> Result<SocketStatus> {
         Ok(SocketStatus::Opened)
     }
+
+    fn poll_read_ready(&self) -> Result<usize> {
+        let mut buf = BytesMut::new();
+        buf.resize(UDP_RECV_SIZE, 0);
+        self.0
+            .set_nonblocking(true)
+            .map_err(io_err_into_net_error)?;
+        let ret = self.0.peek_from(&mut buf[..]);
+        self.0
+            .set_nonblocking(false)
+            .map_err(io_err_into_net_error)?;
+        ret.map(|(read, _)| read).map_err(io_err_into_net_error)
+    }
+
+    fn poll_write_ready(&self) -> Result<usize> {
+        Ok(UDP_RECV_SIZE)
+    }
+}
+
+/// Native hosts create their sockets through `socket2` so that all the
+/// options can be applied before the socket is bound
+#[cfg(not(target_os = "wasi"))]
+mod sys {
+    use socket2::{Domain, Protocol, SockRef, Socket, Type};
+    use std::io;
+    use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+    use std::time::Duration;
+
+    /// Same backlog that the standard library uses for its listeners
+    const LISTEN_BACKLOG: i32 = 128;
+
+    pub fn listen_tcp(
+        addr: SocketAddr,
+        only_v6: bool,
+        reuse_port: bool,
+        reuse_addr: bool,
+    ) -> io::Result<TcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        if addr.is_ipv6() {
+            socket.set_only_v6(only_v6)?;
+        }
+        socket.set_reuse_address(reuse_addr)?;
+        set_reuse_port(&socket, reuse_port)?;
+        socket.bind(&addr.into())?;
+        socket.listen(LISTEN_BACKLOG)?;
+        Ok(socket.into())
+    }
+
+    pub fn connect_tcp(
+        addr: SocketAddr,
+        peer: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<TcpStream> {
+        let socket = Socket::new(Domain::for_address(peer), Type::STREAM, Some(Protocol::TCP))?;
+        // Only bind the local end when the caller actually asked for a
+        // particular address or port, otherwise the OS picks one
+        if !addr.ip().is_unspecified() || addr.port() != 0 {
+            socket.bind(&addr.into())?;
+        }
+        match timeout {
+            Some(timeout) => socket.connect_timeout(&peer.into(), timeout)?,
+            None => socket.connect(&peer.into())?,
+        }
+        Ok(socket.into())
+    }
+
+    pub fn bind_udp(addr: SocketAddr, reuse_port: bool, reuse_addr: bool) -> io::Result<UdpSocket> {
+        let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(reuse_addr)?;
+        set_reuse_port(&socket, reuse_port)?;
+        socket.bind(&addr.into())?;
+        Ok(socket.into())
+    }
+
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    fn set_reuse_port(socket: &Socket, reuse_port: bool) -> io::Result<()> {
+        socket.set_reuse_port(reuse_port)
+    }
+
+    #[cfg(not(all(unix, not(any(target_os = "solaris", target_os = "illumos")))))]
+    fn set_reuse_port(_socket: &Socket, reuse_port: bool) -> io::Result<()> {
+        match reuse_port {
+            true => Err(io::ErrorKind::Unsupported.into()),
+            false => Ok(()),
+        }
+    }
+
+    pub fn set_linger(stream: &TcpStream, linger: Option<Duration>) -> io::Result<()> {
+        SockRef::from(stream).set_linger(linger)
+    }
+
+    pub fn linger(stream: &TcpStream) -> io::Result<Option<Duration>> {
+        SockRef::from(stream).linger()
+    }
+
+    pub fn set_recv_buf_size(stream: &TcpStream, size: usize) -> io::Result<()> {
+        SockRef::from(stream).set_recv_buffer_size(size)
+    }
+
+    pub fn recv_buf_size(stream: &TcpStream) -> io::Result<usize> {
+        SockRef::from(stream).recv_buffer_size()
+    }
+
+    pub fn set_send_buf_size(stream: &TcpStream, size: usize) -> io::Result<()> {
+        SockRef::from(stream).set_send_buffer_size(size)
+    }
+
+    pub fn send_buf_size(stream: &TcpStream) -> io::Result<usize> {
+        SockRef::from(stream).send_buffer_size()
+    }
+
+    /// Waits until the listener has a connection to accept or the timeout
+    /// has passed, returning whether it has one. Interruptions return early.
+    #[cfg(unix)]
+    pub fn wait_readable(listener: &TcpListener, timeout: Option<Duration>) -> io::Result<bool> {
+        use std::os::unix::io::AsRawFd;
+        let mut fd = libc::pollfd {
+            fd: listener.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut fd, 1, timeout_millis(timeout)) } {
+            -1 => match io::Error::last_os_error() {
+                err if err.kind() == io::ErrorKind::Interrupted => Ok(false),
+                err => Err(err),
+            },
+            ready => Ok(ready > 0),
+        }
+    }
+
+    /// Waits until the listener has a connection to accept or the timeout
+    /// has passed, returning whether it has one.
+    #[cfg(windows)]
+    pub fn wait_readable(listener: &TcpListener, timeout: Option<Duration>) -> io::Result<bool> {
+        use std::os::windows::io::AsRawSocket;
+        use winapi::um::winsock2::{WSAGetLastError, WSAPoll, POLLRDNORM, SOCKET, WSAPOLLFD};
+        let mut fd = WSAPOLLFD {
+            fd: listener.as_raw_socket() as SOCKET,
+            events: POLLRDNORM,
+            revents: 0,
+        };
+        match unsafe { WSAPoll(&mut fd, 1, timeout_millis(timeout)) } {
+            -1 => Err(io::Error::from_raw_os_error(unsafe { WSAGetLastError() })),
+            ready => Ok(ready > 0),
+        }
+    }
+
+    /// Converts a timeout into the milliseconds of `poll`, rounding up so
+    /// that short timeouts don't turn into busy loops
+    fn timeout_millis(timeout: Option<Duration>) -> i32 {
+        match timeout {
+            Some(timeout) => {
+                let mut millis = timeout.as_millis();
+                if timeout.subsec_nanos() % 1_000_000 != 0 {
+                    millis += 1;
+                }
+                millis.min(i32::MAX as u128) as i32
+            }
+            None => -1,
+        }
+    }
+}
+
+/// When compiled for WASI itself only the functionality of the standard
+/// library is available (plus the extensions of the WASIX toolchain)
+#[cfg(target_os = "wasi")]
+mod sys {
+    use std::io;
+    use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+    use std::time::Duration;
+
+    /// How long to wait between attempts while waiting for a connection
+    const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    pub fn listen_tcp(
+        addr: SocketAddr,
+        _only_v6: bool,
+        _reuse_port: bool,
+        _reuse_addr: bool,
+    ) -> io::Result<TcpListener> {
+        TcpListener::bind(addr)
+    }
+
+    pub fn connect_tcp(
+        _addr: SocketAddr,
+        peer: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> io::Result<TcpStream> {
+        match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&peer, timeout),
+            None => TcpStream::connect(peer),
+        }
+    }
+
+    pub fn bind_udp(
+        addr: SocketAddr,
+        _reuse_port: bool,
+        _reuse_addr: bool,
+    ) -> io::Result<UdpSocket> {
+        UdpSocket::bind(addr)
+    }
+
+    #[cfg(feature = "wasix")]
+    pub fn set_linger(stream: &TcpStream, linger: Option<Duration>) -> io::Result<()> {
+        stream.set_linger(linger)
+    }
+
+    #[cfg(feature = "wasix")]
+    pub fn linger(stream: &TcpStream) -> io::Result<Option<Duration>> {
+        stream.linger()
+    }
+
+    #[cfg(not(feature = "wasix"))]
+    pub fn set_linger(_stream: &TcpStream, _linger: Option<Duration>) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    #[cfg(not(feature = "wasix"))]
+    pub fn linger(_stream: &TcpStream) -> io::Result<Option<Duration>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn set_recv_buf_size(_stream: &TcpStream, _size: usize) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn recv_buf_size(_stream: &TcpStream) -> io::Result<usize> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn set_send_buf_size(_stream: &TcpStream, _size: usize) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn send_buf_size(_stream: &TcpStream) -> io::Result<usize> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// There is no way to wait for a connection, so this only waits a
+    /// little before the caller tries to accept one again
+    pub fn wait_readable(_listener: &TcpListener, timeout: Option<Duration>) -> io::Result<bool> {
+        let interval = match timeout {
+            Some(timeout) => timeout.min(ACCEPT_POLL_INTERVAL),
+            None => ACCEPT_POLL_INTERVAL,
+        };
+        std::thread::sleep(interval);
+        Ok(false)
+    }
 }
@@ -0,0 +1,67 @@
+//! Web sockets that are connected by the host on behalf of the sandbox
+
+use bytes::Bytes;
+use std::net::TcpStream;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Error, Message, WebSocket};
+use wasmer_vnet::{io_err_into_net_error, NetworkError, Result, SocketReceive, VirtualWebSocket};
+
+pub fn connect(url: &str) -> Result<LocalWebSocket> {
+    let (socket, _) = tungstenite::connect(url).map_err(ws_err_into_net_error)?;
+    Ok(LocalWebSocket { socket })
+}
+
+#[derive(Debug)]
+pub struct LocalWebSocket {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl VirtualWebSocket for LocalWebSocket {
+    fn send(&mut self, data: Bytes) -> Result<usize> {
+        let len = data.len();
+        self.socket
+            .write_message(Message::Binary(data.to_vec()))
+            .map_err(ws_err_into_net_error)?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.socket.write_pending().map_err(ws_err_into_net_error)
+    }
+
+    /// Receives the next message, an empty one once the connection was
+    /// closed. Pings are answered while waiting for it.
+    fn recv(&mut self) -> Result<SocketReceive> {
+        loop {
+            let data = match self.socket.read_message() {
+                Ok(Message::Binary(data)) => Bytes::from(data),
+                Ok(Message::Text(text)) => Bytes::from(text),
+                Ok(Message::Close(_)) | Err(Error::ConnectionClosed) => Bytes::new(),
+                Ok(_) => continue,
+                Err(err) => return Err(ws_err_into_net_error(err)),
+            };
+            return Ok(SocketReceive {
+                data,
+                truncated: false,
+            });
+        }
+    }
+}
+
+fn ws_err_into_net_error(err: Error) -> NetworkError {
+    use tungstenite::error::UrlError;
+    match err {
+        Error::Io(err) => io_err_into_net_error(err),
+        Error::Url(UrlError::TlsFeatureNotEnabled) | Error::Url(UrlError::UnsupportedUrlScheme) => {
+            NetworkError::Unsupported
+        }
+        Error::Url(UrlError::UnableToConnect(_)) | Error::Http(_) => {
+            NetworkError::ConnectionRefused
+        }
+        Error::Url(_) | Error::HttpFormat(_) => NetworkError::InvalidInput,
+        Error::ConnectionClosed | Error::AlreadyClosed => NetworkError::ConnectionReset,
+        Error::SendQueueFull(_) => NetworkError::WouldBlock,
+        Error::Capacity(_) | Error::Protocol(_) | Error::Utf8 => NetworkError::InvalidData,
+        _ => NetworkError::IOError,
+    }
+}
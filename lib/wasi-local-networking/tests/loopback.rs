@@ -0,0 +1,384 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::time::Duration;
+use wasmer_vnet::{Bytes, NetworkError, TimeType, VirtualNetworking};
+use wasmer_wasi_local_networking::LocalNetworking;
+
+fn loopback(port: u16) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+}
+
+fn unspecified() -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
+}
+
+#[test]
+fn test_tcp_echo() {
+    let net = LocalNetworking::default();
+    let listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+    let server_addr = listener.addr_local().unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (mut sock, _) = listener.accept().unwrap();
+        let data = sock.recv().unwrap().data;
+        sock.send(data).unwrap();
+    });
+
+    let mut client = net.connect_tcp(unspecified(), server_addr, None).unwrap();
+    assert_eq!(client.addr_peer().unwrap(), server_addr);
+    client.send(Bytes::from_static(b"hello world")).unwrap();
+    let received = client.recv().unwrap();
+    assert_eq!(&received.data[..], b"hello world");
+    assert!(!received.truncated);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_tcp_socket_options() {
+    let net = LocalNetworking::default();
+    let listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+    let server_addr = listener.addr_local().unwrap();
+    let mut client = net
+        .connect_tcp(unspecified(), server_addr, Some(Duration::from_secs(5)))
+        .unwrap();
+
+    client.set_nodelay(true).unwrap();
+    assert!(client.nodelay().unwrap());
+    client.set_nodelay(false).unwrap();
+    assert!(!client.nodelay().unwrap());
+
+    client.set_ttl(42).unwrap();
+    assert_eq!(client.ttl().unwrap(), 42);
+
+    // The OS is free to round the buffer sizes up
+    client.set_recv_buf_size(32768).unwrap();
+    assert!(client.recv_buf_size().unwrap() >= 32768);
+    client.set_send_buf_size(32768).unwrap();
+    assert!(client.send_buf_size().unwrap() >= 32768);
+
+    let timeout = Some(Duration::from_secs(3));
+    client.set_opt_time(TimeType::ReadTimeout, timeout).unwrap();
+    assert_eq!(client.opt_time(TimeType::ReadTimeout).unwrap(), timeout);
+    client
+        .set_opt_time(TimeType::WriteTimeout, timeout)
+        .unwrap();
+    assert_eq!(client.opt_time(TimeType::WriteTimeout).unwrap(), timeout);
+    assert_eq!(
+        client.opt_time(TimeType::ConnectTimeout).unwrap(),
+        Some(Duration::from_secs(5))
+    );
+
+    client.set_linger(Some(Duration::from_secs(1))).unwrap();
+    assert_eq!(client.linger().unwrap(), Some(Duration::from_secs(1)));
+    assert_eq!(
+        client.opt_time(TimeType::Linger).unwrap(),
+        Some(Duration::from_secs(1))
+    );
+}
+
+#[test]
+fn test_tcp_read_timeout() {
+    let net = LocalNetworking::default();
+    let listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+    let server_addr = listener.addr_local().unwrap();
+    let mut client = net.connect_tcp(unspecified(), server_addr, None).unwrap();
+    client
+        .set_opt_time(TimeType::ReadTimeout, Some(Duration::from_millis(50)))
+        .unwrap();
+    let err = client.recv().unwrap_err();
+    assert!(err == NetworkError::WouldBlock || err == NetworkError::TimedOut);
+}
+
+#[test]
+fn test_tcp_accept_timeout() {
+    let net = LocalNetworking::default();
+    let mut listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+    listener
+        .set_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    assert_eq!(listener.timeout().unwrap(), Some(Duration::from_millis(50)));
+    assert_eq!(listener.accept().unwrap_err(), NetworkError::TimedOut);
+
+    // Connections still get through once they are pending
+    let server_addr = listener.addr_local().unwrap();
+    let _client = net.connect_tcp(unspecified(), server_addr, None).unwrap();
+    let (mut sock, _) = listener.accept_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(sock.addr_local().unwrap(), server_addr);
+
+    // The accepted stream must be blocking again
+    sock.set_opt_time(TimeType::ReadTimeout, Some(Duration::from_millis(50)))
+        .unwrap();
+    let err = sock.recv().unwrap_err();
+    assert!(err == NetworkError::WouldBlock || err == NetworkError::TimedOut);
+}
+
+#[test]
+fn test_tcp_accept_timeout_while_accepting() {
+    let net = LocalNetworking::default();
+    let listener = std::sync::Arc::new(net.listen_tcp(loopback(0), false, false, false).unwrap());
+    let server_addr = listener.addr_local().unwrap();
+
+    // A timeout on one thread must not disturb a blocking accept on another
+    let accepting = {
+        let listener = listener.clone();
+        std::thread::spawn(move || listener.accept().map(|(_, addr)| addr))
+    };
+    assert_eq!(
+        listener
+            .accept_timeout(Duration::from_millis(50))
+            .unwrap_err(),
+        NetworkError::TimedOut
+    );
+    std::thread::sleep(Duration::from_millis(50));
+    let client = net.connect_tcp(unspecified(), server_addr, None).unwrap();
+    assert_eq!(
+        accepting.join().unwrap().unwrap(),
+        client.addr_local().unwrap()
+    );
+}
+
+/// Polls until `ready` stops returning `NetworkError::WouldBlock`, as the
+/// host may take a moment to deliver data over the loopback interface
+fn wait_ready(ready: impl Fn() -> Result<usize, NetworkError>) -> Result<usize, NetworkError> {
+    for _ in 0..500 {
+        match ready() {
+            Err(NetworkError::WouldBlock) => std::thread::sleep(Duration::from_millis(2)),
+            ret => return ret,
+        }
+    }
+    Err(NetworkError::WouldBlock)
+}
+
+#[test]
+fn test_tcp_readiness() {
+    let net = LocalNetworking::default();
+    let listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+    assert_eq!(listener.poll_accept_ready(), Err(NetworkError::WouldBlock));
+
+    let server_addr = listener.addr_local().unwrap();
+    let mut client = net.connect_tcp(unspecified(), server_addr, None).unwrap();
+    assert_eq!(wait_ready(|| listener.poll_accept_ready()), Ok(1));
+    // The connection that was taken while polling is handed out next
+    let (mut sock, _) = listener.accept_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(sock.addr_local().unwrap(), server_addr);
+
+    assert_eq!(sock.poll_read_ready(), Err(NetworkError::WouldBlock));
+    assert!(client.poll_write_ready().unwrap() > 0);
+    client.send(Bytes::from_static(b"hello")).unwrap();
+    assert_eq!(wait_ready(|| sock.poll_read_ready()), Ok(5));
+    assert_eq!(&sock.recv().unwrap().data[..], b"hello");
+
+    drop(client);
+    assert_eq!(wait_ready(|| sock.poll_read_ready()), Ok(0));
+}
+
+#[test]
+fn test_tcp_connect_refused() {
+    let net = LocalNetworking::default();
+    let server_addr = {
+        let listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+        listener.addr_local().unwrap()
+    };
+    let err = net
+        .connect_tcp(unspecified(), server_addr, None)
+        .unwrap_err();
+    assert_eq!(err, NetworkError::ConnectionRefused);
+}
+
+#[test]
+fn test_tcp_address_in_use() {
+    let net = LocalNetworking::default();
+    let listener = net.listen_tcp(loopback(0), false, false, false).unwrap();
+    let server_addr = listener.addr_local().unwrap();
+    let err = net
+        .listen_tcp(server_addr, false, false, false)
+        .unwrap_err();
+    assert_eq!(err, NetworkError::AddressInUse);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_tcp_reuse_port() {
+    let net = LocalNetworking::default();
+    let first = net.listen_tcp(loopback(0), false, true, false).unwrap();
+    let server_addr = first.addr_local().unwrap();
+    let second = net.listen_tcp(server_addr, false, true, false).unwrap();
+    assert_eq!(second.addr_local().unwrap(), server_addr);
+}
+
+#[test]
+fn test_udp_send_to_and_recv_from() {
+    let net = LocalNetworking::default();
+    let mut server = net.bind_udp(loopback(0), false, false).unwrap();
+    let mut client = net.bind_udp(loopback(0), false, false).unwrap();
+    let server_addr = server.addr_local().unwrap();
+    let client_addr = client.addr_local().unwrap();
+    assert_ne!(server_addr.port(), 0);
+
+    client
+        .send_to(Bytes::from_static(b"ping"), server_addr)
+        .unwrap();
+    let peeked = server.peek_from().unwrap();
+    assert_eq!(&peeked.data[..], b"ping");
+    let received = server.recv_from().unwrap();
+    assert_eq!(&received.data[..], b"ping");
+    assert_eq!(received.addr, client_addr);
+    assert!(!received.truncated);
+
+    server
+        .send_to(Bytes::from_static(b"pong"), received.addr)
+        .unwrap();
+    assert_eq!(&client.recv_from().unwrap().data[..], b"pong");
+}
+
+#[test]
+fn test_udp_connect() {
+    let net = LocalNetworking::default();
+    let mut server = net.bind_udp(loopback(0), false, false).unwrap();
+    let mut client = net.bind_udp(loopback(0), false, false).unwrap();
+    let server_addr = server.addr_local().unwrap();
+
+    assert_eq!(client.addr_peer().unwrap(), None);
+    client.connect(server_addr).unwrap();
+    assert_eq!(client.addr_peer().unwrap(), Some(server_addr));
+
+    client.send(Bytes::from_static(b"hello")).unwrap();
+    assert_eq!(&server.recv().unwrap().data[..], b"hello");
+}
+
+#[test]
+fn test_udp_socket_options() {
+    let net = LocalNetworking::default();
+    let mut socket = net.bind_udp(loopback(0), false, true).unwrap();
+
+    socket.set_broadcast(true).unwrap();
+    assert!(socket.broadcast().unwrap());
+    socket.set_ttl(7).unwrap();
+    assert_eq!(socket.ttl().unwrap(), 7);
+    socket.set_multicast_loop_v4(false).unwrap();
+    assert!(!socket.multicast_loop_v4().unwrap());
+    socket.set_multicast_ttl_v4(3).unwrap();
+    assert_eq!(socket.multicast_ttl_v4().unwrap(), 3);
+    assert_eq!(socket.linger().unwrap_err(), NetworkError::Unsupported);
+}
+
+#[test]
+fn test_resolve() {
+    let net = LocalNetworking::default();
+    let addrs = net.resolve("127.0.0.1", None, None).unwrap();
+    assert_eq!(addrs, vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+    let addrs = net.resolve("localhost", Some(80), None).unwrap();
+    assert!(addrs.iter().all(|addr| addr.is_loopback()));
+    assert_eq!(
+        net.resolve(
+            "localhost",
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)))
+        )
+        .unwrap_err(),
+        NetworkError::Unsupported
+    );
+}
+
+/// Answers a single HTTP request on `listener` with `response`, returning
+/// the head and the body of the request
+fn serve_http(listener: TcpListener, response: &'static str) -> (String, Vec<u8>) {
+    let (stream, _) = listener.accept().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut head = String::new();
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            content_length = len.trim().parse().unwrap();
+        }
+        if line == "\r\n" {
+            break;
+        }
+        head.push_str(&line);
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+    reader.get_mut().write_all(response.as_bytes()).unwrap();
+    (head, body)
+}
+
+#[test]
+fn test_http_request() {
+    let listener = TcpListener::bind(loopback(0)).unwrap();
+    let url = format!("http://{}/echo", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        serve_http(
+            listener,
+            "HTTP/1.1 201 Created\r\nContent-Length: 5\r\nX-Answer: 42\r\n\r\nhello",
+        )
+    });
+
+    let net = LocalNetworking::default();
+    let mut http = net
+        .http_request(
+            &url,
+            "POST",
+            "Content-Type: text/plain\nX-Question: six",
+            false,
+        )
+        .unwrap();
+    let request = http.request.take().unwrap();
+    request.send(b"how many ".to_vec()).unwrap();
+    request.send(b"roads".to_vec()).unwrap();
+    // The request is sent once its body is complete
+    drop(request);
+
+    let status = http.status.lock().unwrap().recv().unwrap().unwrap();
+    assert_eq!(status.status, 201);
+    assert_eq!(status.status_text, "Created");
+    assert_eq!(status.size, 5);
+    assert!(!status.redirected);
+    let headers: Vec<(String, String)> = http.headers.take().unwrap().iter().collect();
+    assert!(headers.contains(&("x-answer".to_string(), "42".to_string())));
+    let body: Vec<u8> = http.response.take().unwrap().iter().flatten().collect();
+    assert_eq!(body, b"hello");
+
+    let (head, body) = server.join().unwrap();
+    assert!(head.starts_with("POST /echo HTTP/1.1\r\n"));
+    assert!(head.contains("X-Question: six\r\n"));
+    assert_eq!(body, b"how many roads");
+}
+
+#[test]
+fn test_http_request_unsupported() {
+    let net = LocalNetworking::default();
+    let err = net
+        .http_request("ftp://localhost/", "GET", "", false)
+        .unwrap_err();
+    assert_eq!(err, NetworkError::Unsupported);
+    let err = net
+        .http_request("http://localhost/", "GET", "not a header", false)
+        .unwrap_err();
+    assert_eq!(err, NetworkError::InvalidInput);
+}
+
+#[test]
+fn test_ws_echo() {
+    let listener = TcpListener::bind(loopback(0)).unwrap();
+    let url = format!("ws://{}/", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        let message = socket.read_message().unwrap();
+        socket.write_message(message).unwrap();
+        socket.close(None).unwrap();
+        // Wait for the client to acknowledge the close
+        while socket.read_message().is_ok() {}
+    });
+
+    let net = LocalNetworking::default();
+    let mut socket = net.ws_connect(&url).unwrap();
+    assert_eq!(socket.send(Bytes::from_static(b"hello")).unwrap(), 5);
+    socket.flush().unwrap();
+    assert_eq!(&socket.recv().unwrap().data[..], b"hello");
+    assert!(socket.recv().unwrap().data.is_empty());
+    drop(socket);
+    server.join().unwrap();
+}
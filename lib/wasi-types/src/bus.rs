@@ -1,6 +1,7 @@
 use super::*;
+use std::mem::{self, MaybeUninit};
 use wasmer_derive::ValueType;
-use wasmer_types::MemorySize;
+use wasmer_types::{MemorySize, ValueType};
 
 pub type __wasi_busdataformat_t = u8;
 pub const __WASI_BUS_DATA_FORMAT_RAW: __wasi_busdataformat_t = 0;
@@ -111,3 +112,47 @@ pub struct __wasi_busevent_t<M: MemorySize> {
     pub tag: __wasi_buseventtype_t,
     pub u: __wasi_busevent_u<M>,
 }
+
+unsafe impl<M: MemorySize> ValueType for __wasi_busevent_t<M> {
+    fn zero_padding_bytes(&self, bytes: &mut [MaybeUninit<u8>]) {
+        macro_rules! field {
+            ($($f:tt)*) => {
+                &self.$($f)* as *const _ as usize - self as *const _ as usize
+            };
+        }
+        macro_rules! field_end {
+            ($($f:tt)*) => {
+                field!($($f)*) + mem::size_of_val(&self.$($f)*)
+            };
+        }
+        macro_rules! zero {
+            ($start:expr, $end:expr) => {
+                for i in $start..$end {
+                    bytes[i] = MaybeUninit::new(0);
+                }
+            };
+        }
+        macro_rules! variant {
+            ($v:ident) => {
+                unsafe {
+                    self.u
+                        .$v
+                        .zero_padding_bytes(&mut bytes[field!(u.$v)..field_end!(u.$v)]);
+                    zero!(field_end!(u.$v), field_end!(u));
+                }
+            };
+        }
+        self.tag
+            .zero_padding_bytes(&mut bytes[field!(tag)..field_end!(tag)]);
+        zero!(field_end!(tag), field!(u));
+        match self.tag {
+            __WASI_BUS_EVENT_TYPE_EXIT => variant!(exit),
+            __WASI_BUS_EVENT_TYPE_CALL => variant!(call),
+            __WASI_BUS_EVENT_TYPE_RESULT => variant!(result),
+            __WASI_BUS_EVENT_TYPE_FAULT => variant!(fault),
+            __WASI_BUS_EVENT_TYPE_CLOSE => variant!(close),
+            _ => zero!(field!(u), field_end!(u)),
+        }
+        zero!(field_end!(u), mem::size_of_val(self));
+    }
+}
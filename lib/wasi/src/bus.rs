@@ -0,0 +1,778 @@
+//! In-process implementation of the virtual bus.
+//!
+//! [`LocalBus`] resolves process names against a registry of compiled
+//! [`Module`]s and runs each spawned process in its own [`WasiEnv`] on a
+//! dedicated host thread. Calls made on the bus are delivered to the
+//! callee through its own `LocalBus` which it receives as part of its
+//! runtime, so processes spawned this way can spawn and call others.
+
+use crate::{Pipe, PluggableRuntimeImplementation, WasiEnv, WasiError, WasiState};
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Seek, Write};
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::task::{Context, Poll, Waker};
+use tracing::{debug, info, warn};
+use wasmer::{Instance, Module};
+use wasmer_vbus::{
+    BusCallEvent, BusDataFormat, BusError, BusInvocationEvent, BusSpawnedProcess, FileDescriptor,
+    Result, SpawnOptions, SpawnOptionsConfig, StdioMode, VirtualBus, VirtualBusCalled,
+    VirtualBusInvocation, VirtualBusInvokable, VirtualBusListener, VirtualBusProcess,
+    VirtualBusScope, VirtualBusSpawner,
+};
+use wasmer_vfs::{FsError, VirtualFile};
+
+/// Calls that are waiting to be picked up by the listener of a process
+#[derive(Debug, Default)]
+struct CallQueue {
+    calls: VecDeque<BusCallEvent>,
+    waker: Option<Waker>,
+    /// Set once the process has exited and will never listen again
+    closed: bool,
+}
+
+/// Events that are waiting to be picked up by the caller of an invocation
+#[derive(Debug, Default)]
+struct InvocationState {
+    events: VecDeque<BusInvocationEvent>,
+    waker: Option<Waker>,
+    /// Set once a response or fault has been sent to the caller
+    finished: bool,
+}
+
+impl InvocationState {
+    fn push(&mut self, event: BusInvocationEvent) {
+        self.events.push_back(event);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Exit status of a process that is shared with the thread that runs it
+#[derive(Debug, Default)]
+struct ExitState {
+    exit_code: Option<u32>,
+    waker: Option<Waker>,
+}
+
+/// Virtual bus that spawns processes from a registry of modules and runs
+/// them on threads within the current host process.
+#[derive(Debug, Clone, Default)]
+pub struct LocalBus {
+    modules: Arc<RwLock<HashMap<String, Module>>>,
+    /// Calls made to the process that owns this bus
+    inbox: Arc<Mutex<CallQueue>>,
+}
+
+impl LocalBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a module that will be spawned whenever a process with
+    /// this name is requested, returning the module it replaces (if any)
+    pub fn register(&self, name: impl Into<String>, module: Module) -> Option<Module> {
+        let mut modules = self.modules.write().unwrap();
+        modules.insert(name.into(), module)
+    }
+
+    /// Removes a module from the registry so it can no longer be spawned
+    pub fn unregister(&self, name: &str) -> Option<Module> {
+        let mut modules = self.modules.write().unwrap();
+        modules.remove(name)
+    }
+
+    /// Creates the bus given to a child process, it shares the registry
+    /// of modules but has its own inbox for calls
+    fn child(&self) -> LocalBus {
+        LocalBus {
+            modules: self.modules.clone(),
+            inbox: Default::default(),
+        }
+    }
+}
+
+impl VirtualBus for LocalBus {
+    fn new_spawn(&self) -> SpawnOptions {
+        SpawnOptions::new(Box::new(LocalBusSpawner { bus: self.clone() }))
+    }
+
+    fn listen(&self) -> Result<Box<dyn VirtualBusListener + Sync>> {
+        Ok(Box::new(LocalBusListener {
+            inbox: self.inbox.clone(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalBusSpawner {
+    bus: LocalBus,
+}
+
+impl LocalBusSpawner {
+    /// Builds the file that the child process will use for a particular
+    /// stdio stream along with the pipe the parent keeps (if piped)
+    fn stdio(
+        mode: StdioMode,
+        name: &str,
+    ) -> (
+        Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
+        Option<Pipe>,
+    ) {
+        match mode {
+            StdioMode::Piped => {
+                let pipe = Pipe::new();
+                (Some(Box::new(pipe.clone())), Some(pipe))
+            }
+            StdioMode::Inherit => (None, None),
+            StdioMode::Null => (Some(Box::new(StdioSink::null())), None),
+            StdioMode::Log => (Some(Box::new(StdioSink::log(name))), None),
+        }
+    }
+}
+
+impl VirtualBusSpawner for LocalBusSpawner {
+    fn spawn(&mut self, name: &str, config: &SpawnOptionsConfig) -> Result<BusSpawnedProcess> {
+        if config.remote_instance().is_some() {
+            return Err(BusError::Unsupported);
+        }
+        let module = {
+            let modules = self.bus.modules.read().unwrap();
+            modules.get(name).cloned().ok_or(BusError::InvalidWapm)?
+        };
+
+        let (stdin, stdin_pipe) = Self::stdio(config.stdin_mode(), name);
+        let (stdout, stdout_pipe) = Self::stdio(config.stdout_mode(), name);
+        let (stderr, stderr_pipe) = Self::stdio(config.stderr_mode(), name);
+
+        let child_bus = self.bus.child();
+        let mut runtime = PluggableRuntimeImplementation::default();
+        runtime.set_bus_implementation(child_bus.clone());
+
+        let mut builder = WasiState::new(name);
+        builder
+            .args(config.args().iter().filter(|arg| !arg.is_empty()))
+            .runtime(runtime);
+        for preopen in config.preopen().iter().filter(|dir| !dir.is_empty()) {
+            builder.preopen_dir(preopen).map_err(|err| {
+                debug!("failed to preopen '{}' for {} - {}", preopen, name, err);
+                BusError::BadRequest
+            })?;
+        }
+        if let Some(stdin) = stdin {
+            builder.stdin(stdin);
+        }
+        if let Some(stdout) = stdout {
+            builder.stdout(stdout);
+        }
+        if let Some(stderr) = stderr {
+            builder.stderr(stderr);
+        }
+        let mut env = builder.finalize().map_err(|err| {
+            debug!("failed to create the WASI state for {} - {}", name, err);
+            BusError::InternalError
+        })?;
+        env.state.fs.set_current_dir(config.working_dir());
+
+        let exit = Arc::new(Mutex::new(ExitState::default()));
+        let (tx_started, rx_started) = mpsc::channel();
+        let thread_name = name.to_string();
+        let thread_exit = exit.clone();
+        let thread_inbox = child_bus.inbox.clone();
+        std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let exit_code = match instantiate(&mut env, &module) {
+                    Ok(instance) => {
+                        let _ = tx_started.send(Ok(()));
+                        run(&thread_name, &instance)
+                    }
+                    Err(err) => {
+                        let _ = tx_started.send(Err(err));
+                        return;
+                    }
+                };
+                finish(&thread_exit, &thread_inbox, exit_code);
+            })
+            .map_err(|_| BusError::InternalError)?;
+        rx_started.recv().map_err(|_| BusError::InternalError)??;
+
+        Ok(BusSpawnedProcess {
+            inst: Box::new(LocalBusProcess {
+                exit,
+                inbox: child_bus.inbox,
+                stdin: stdin_pipe,
+                stdout: stdout_pipe,
+                stderr: stderr_pipe,
+            }),
+        })
+    }
+}
+
+fn instantiate(env: &mut WasiEnv, module: &Module) -> Result<Instance> {
+    let imports = env
+        .import_object_for_all_wasi_versions(module)
+        .map_err(|_| BusError::InvalidABI)?;
+    let instance = Instance::new(module, &imports).map_err(|err| {
+        debug!("failed to instantiate the process - {}", err);
+        BusError::CompileError
+    })?;
+    if instance.exports.get_function("_start").is_err() {
+        return Err(BusError::InvalidABI);
+    }
+    Ok(instance)
+}
+
+/// Runs the process to completion and returns its exit code
+fn run(name: &str, instance: &Instance) -> u32 {
+    let start = instance.exports.get_function("_start").unwrap();
+    match start.call(&[]) {
+        Ok(_) => 0,
+        Err(err) => match err.downcast::<WasiError>() {
+            Ok(WasiError::Exit(code)) => code,
+            Ok(err) => {
+                warn!("process {} failed - {}", name, err);
+                1
+            }
+            Err(err) => {
+                warn!("process {} failed - {}", name, err);
+                1
+            }
+        },
+    }
+}
+
+/// Records the exit code and aborts any calls the process never received
+fn finish(exit: &Mutex<ExitState>, inbox: &Mutex<CallQueue>, exit_code: u32) {
+    let calls = {
+        let mut inbox = inbox.lock().unwrap();
+        inbox.closed = true;
+        inbox.calls.drain(..).collect::<Vec<_>>()
+    };
+    for call in calls {
+        let _ = call.called.fault(BusError::Aborted);
+    }
+
+    let mut exit = exit.lock().unwrap();
+    exit.exit_code = Some(exit_code);
+    if let Some(waker) = exit.waker.take() {
+        waker.wake();
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalBusProcess {
+    exit: Arc<Mutex<ExitState>>,
+    inbox: Arc<Mutex<CallQueue>>,
+    stdin: Option<Pipe>,
+    stdout: Option<Pipe>,
+    stderr: Option<Pipe>,
+}
+
+impl VirtualBusScope for LocalBusProcess {
+    fn poll_finished(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut exit = self.exit.lock().unwrap();
+        if exit.exit_code.is_some() {
+            return Poll::Ready(());
+        }
+        exit.waker.replace(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl VirtualBusInvokable for LocalBusProcess {
+    fn invoke(
+        &self,
+        topic: String,
+        format: BusDataFormat,
+        buf: &[u8],
+    ) -> Result<Box<dyn VirtualBusInvocation + Sync>> {
+        invoke(&self.inbox, topic, format, buf)
+    }
+}
+
+impl VirtualBusProcess for LocalBusProcess {
+    fn exit_code(&self) -> Option<u32> {
+        let exit = self.exit.lock().unwrap();
+        exit.exit_code
+    }
+
+    fn stdin_fd(&self) -> Option<FileDescriptor> {
+        None
+    }
+
+    fn stdout_fd(&self) -> Option<FileDescriptor> {
+        None
+    }
+
+    fn stderr_fd(&self) -> Option<FileDescriptor> {
+        None
+    }
+
+    fn stdin_file(&self) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        self.stdin.clone().map(|pipe| Box::new(pipe) as _)
+    }
+
+    fn stdout_file(&self) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        self.stdout.clone().map(|pipe| Box::new(pipe) as _)
+    }
+
+    fn stderr_file(&self) -> Option<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        self.stderr.clone().map(|pipe| Box::new(pipe) as _)
+    }
+}
+
+/// Queues a call on the inbox of a process
+fn invoke(
+    inbox: &Arc<Mutex<CallQueue>>,
+    topic: String,
+    format: BusDataFormat,
+    buf: &[u8],
+) -> Result<Box<dyn VirtualBusInvocation + Sync>> {
+    let state = Arc::new(Mutex::new(InvocationState::default()));
+    {
+        let mut guard = inbox.lock().unwrap();
+        if guard.closed {
+            return Err(BusError::Aborted);
+        }
+        guard.calls.push_back(BusCallEvent {
+            topic,
+            called: Box::new(LocalBusCalled {
+                state: state.clone(),
+            }),
+            format,
+            data: buf.to_vec(),
+        });
+        if let Some(waker) = guard.waker.take() {
+            waker.wake();
+        }
+    }
+    Ok(Box::new(LocalBusInvocation {
+        state,
+        inbox: inbox.clone(),
+    }))
+}
+
+/// Caller side of a call made on the local bus
+#[derive(Debug)]
+pub struct LocalBusInvocation {
+    state: Arc<Mutex<InvocationState>>,
+    /// Inbox of the process that is handling the call, sub-calls go here
+    inbox: Arc<Mutex<CallQueue>>,
+}
+
+impl VirtualBusScope for LocalBusInvocation {
+    fn poll_finished(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.finished && state.events.is_empty() {
+            return Poll::Ready(());
+        }
+        state.waker.replace(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl VirtualBusInvokable for LocalBusInvocation {
+    fn invoke(
+        &self,
+        topic: String,
+        format: BusDataFormat,
+        buf: &[u8],
+    ) -> Result<Box<dyn VirtualBusInvocation + Sync>> {
+        invoke(&self.inbox, topic, format, buf)
+    }
+}
+
+impl VirtualBusInvocation for LocalBusInvocation {
+    fn poll_event(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<BusInvocationEvent> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            return Poll::Ready(event);
+        }
+        state.waker.replace(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Callee side of a call made on the local bus
+#[derive(Debug)]
+pub struct LocalBusCalled {
+    state: Arc<Mutex<InvocationState>>,
+}
+
+impl LocalBusCalled {
+    fn complete(&self, event: BusInvocationEvent) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.finished {
+            return Err(BusError::AlreadyConsumed);
+        }
+        state.finished = true;
+        state.push(event);
+        Ok(())
+    }
+}
+
+impl VirtualBusListener for LocalBusCalled {
+    fn poll_call(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<BusCallEvent> {
+        // Sub-calls made by the caller are queued on the inbox of this
+        // process rather than delivered through the call itself
+        Poll::Pending
+    }
+}
+
+impl VirtualBusCalled for LocalBusCalled {
+    fn callback(&self, topic: String, format: BusDataFormat, buf: &[u8]) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.finished {
+            return Err(BusError::AlreadyConsumed);
+        }
+        state.push(BusInvocationEvent::Callback {
+            topic,
+            format,
+            data: buf.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn fault(self: Box<Self>, fault: BusError) -> Result<()> {
+        self.complete(BusInvocationEvent::Fault { fault })
+    }
+
+    fn reply(self: Box<Self>, format: BusDataFormat, buf: &[u8]) -> Result<()> {
+        self.complete(BusInvocationEvent::Response {
+            format,
+            data: buf.to_vec(),
+        })
+    }
+}
+
+impl Drop for LocalBusCalled {
+    fn drop(&mut self) {
+        // Calls that are dropped without an answer are aborted so the
+        // caller is not left waiting forever
+        let _ = self.complete(BusInvocationEvent::Fault {
+            fault: BusError::Aborted,
+        });
+    }
+}
+
+/// Receives the calls made to a process on the local bus
+#[derive(Debug)]
+pub struct LocalBusListener {
+    inbox: Arc<Mutex<CallQueue>>,
+}
+
+impl VirtualBusListener for LocalBusListener {
+    fn poll_call(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<BusCallEvent> {
+        let mut inbox = self.inbox.lock().unwrap();
+        if let Some(call) = inbox.calls.pop_front() {
+            return Poll::Ready(call);
+        }
+        inbox.waker.replace(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Stdio stream of a spawned process that either discards everything
+/// written to it or forwards it to the log
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+struct StdioSink {
+    /// Name of the process that is written to the log (if logging)
+    log: Option<String>,
+    /// Partial line that has not yet been written to the log
+    line: Vec<u8>,
+}
+
+impl StdioSink {
+    fn null() -> Self {
+        Self::default()
+    }
+
+    fn log(name: &str) -> Self {
+        Self {
+            log: Some(name.to_string()),
+            line: Vec::new(),
+        }
+    }
+
+    fn flush_line(&mut self) {
+        if let Some(name) = self.log.as_ref() {
+            if !self.line.is_empty() {
+                info!("{}: {}", name, String::from_utf8_lossy(&self.line));
+            }
+        }
+        self.line.clear();
+    }
+}
+
+impl Read for StdioSink {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+
+impl Write for StdioSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.log.is_some() {
+            for byte in buf {
+                match byte {
+                    b'\n' => self.flush_line(),
+                    byte => self.line.push(*byte),
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_line();
+        Ok(())
+    }
+}
+
+impl Seek for StdioSink {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Other, "can not seek stdio"))
+    }
+}
+
+impl Drop for StdioSink {
+    fn drop(&mut self) {
+        self.flush_line();
+    }
+}
+
+#[cfg_attr(feature = "enable-serde", typetag::serde)]
+impl VirtualFile for StdioSink {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _new_size: u64) -> std::result::Result<(), FsError> {
+        Err(FsError::PermissionDenied)
+    }
+    fn unlink(&mut self) -> std::result::Result<(), FsError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::noop_waker;
+    use std::time::{Duration, Instant};
+    use wasmer::Store;
+
+    fn wait_for_exit(process: &BusSpawnedProcess) -> Option<u32> {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(10) {
+            if let Some(exit_code) = process.inst.exit_code() {
+                return Some(exit_code);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        None
+    }
+
+    #[test]
+    fn spawn_pipes_stdout_and_exit_code() {
+        let store = Store::default();
+        let module = Module::new(
+            &store,
+            br#"
+            (module
+                (import "wasi_unstable" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+                (import "wasi_unstable" "proc_exit" (func $proc_exit (param i32)))
+                (memory 1)
+                (export "memory" (memory 0))
+                (data (i32.const 8) "hello world\n")
+                (func $main (export "_start")
+                    (i32.store (i32.const 0) (i32.const 8))
+                    (i32.store (i32.const 4) (i32.const 12))
+                    (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 20))
+                    drop
+                    (call $proc_exit (i32.const 3))
+                )
+            )
+            "#,
+        )
+        .unwrap();
+
+        let bus = LocalBus::new();
+        bus.register("hello", module);
+
+        let process = bus
+            .new_spawn()
+            .stdout_mode(StdioMode::Piped)
+            .spawn("hello")
+            .unwrap();
+        assert_eq!(wait_for_exit(&process), Some(3));
+
+        let mut stdout = process.inst.stdout_file().unwrap();
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).unwrap();
+        assert_eq!(output, "hello world\n");
+        assert!(process.inst.stdin_file().is_none());
+    }
+
+    #[test]
+    fn undelivered_events_are_kept() {
+        let store = Store::default();
+        // The module doesn't export the `_malloc` it names, so the data of
+        // the call can't be copied to it; the call must stay queued instead
+        // of being lost.
+        let module = Module::new(
+            &store,
+            br#"
+            (module
+                (import "wasix_32v1" "bus_poll" (func $bus_poll (param i64 i32 i32 i32 i32 i32) (result i32)))
+                (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+                (memory 1)
+                (export "memory" (memory 0))
+                (data (i32.const 0) "_malloc")
+                (func $main (export "_start")
+                    (local $first i32)
+                    (local.set $first
+                        (call $bus_poll (i64.const 10000000000) (i32.const 64) (i32.const 1) (i32.const 0) (i32.const 7) (i32.const 8)))
+                    (call $proc_exit
+                        (i32.add
+                            (i32.mul (local.get $first) (i32.const 256))
+                            (call $bus_poll (i64.const 0) (i32.const 64) (i32.const 1) (i32.const 0) (i32.const 7) (i32.const 8))))
+                )
+            )
+            "#,
+        )
+        .unwrap();
+
+        let bus = LocalBus::new();
+        bus.register("poller", module);
+        let process = bus.new_spawn().spawn("poller").unwrap();
+        let _invocation = process
+            .inst
+            .invoke("ping".to_string(), BusDataFormat::Raw, b"1")
+            .unwrap();
+        let ebadcb = wasmer_wasi_types::__BUS_EBADCB;
+        assert_eq!(wait_for_exit(&process), Some(ebadcb * 256 + ebadcb));
+    }
+
+    #[test]
+    fn events_are_copied_with_the_named_allocator() {
+        let store = Store::default();
+        // The topic and the data of the call are copied, one after the other,
+        // to the memory returned by `alloc`.
+        let module = Module::new(
+            &store,
+            br#"
+            (module
+                (import "wasix_32v1" "bus_poll" (func $bus_poll (param i64 i32 i32 i32 i32 i32) (result i32)))
+                (import "wasix_32v1" "proc_exit" (func $proc_exit (param i32)))
+                (memory 1)
+                (export "memory" (memory 0))
+                (data (i32.const 0) "alloc")
+                (func (export "alloc") (param i64) (result i64)
+                    (i64.const 1024))
+                (func $main (export "_start")
+                    (call $proc_exit
+                        (i32.add
+                            (i32.mul
+                                (call $bus_poll (i64.const 10000000000) (i32.const 64) (i32.const 1) (i32.const 0) (i32.const 5) (i32.const 8))
+                                (i32.const 256))
+                            (i32.load8_u (i32.const 1028))))
+                )
+            )
+            "#,
+        )
+        .unwrap();
+
+        let bus = LocalBus::new();
+        bus.register("poller", module);
+        let process = bus.new_spawn().spawn("poller").unwrap();
+        let _invocation = process
+            .inst
+            .invoke("ping".to_string(), BusDataFormat::Raw, b"1")
+            .unwrap();
+        assert_eq!(wait_for_exit(&process), Some(u32::from(b'1')));
+    }
+
+    #[test]
+    fn spawn_unknown_process() {
+        let bus = LocalBus::new();
+        let err = bus.new_spawn().spawn("missing").unwrap_err();
+        assert_eq!(err, BusError::InvalidWapm);
+    }
+
+    #[test]
+    fn call_is_delivered_and_answered() {
+        let bus = LocalBus::new();
+        let callee = bus.child();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut invocation =
+            Pin::from(invoke(&callee.inbox, "ping".to_string(), BusDataFormat::Raw, b"1").unwrap());
+        assert!(invocation.as_mut().poll_event(&mut cx).is_pending());
+
+        let mut listener = Pin::from(callee.listen().unwrap());
+        let call = match listener.as_mut().poll_call(&mut cx) {
+            Poll::Ready(call) => call,
+            Poll::Pending => panic!("the call was not delivered"),
+        };
+        assert_eq!(call.topic, "ping");
+        assert_eq!(call.data, b"1".to_vec());
+        assert!(listener.as_mut().poll_call(&mut cx).is_pending());
+
+        call.called
+            .callback("progress".to_string(), BusDataFormat::Json, b"{}")
+            .unwrap();
+        call.called.reply(BusDataFormat::Raw, b"2").unwrap();
+
+        match invocation.as_mut().poll_event(&mut cx) {
+            Poll::Ready(BusInvocationEvent::Callback { topic, .. }) => {
+                assert_eq!(topic, "progress")
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        match invocation.as_mut().poll_event(&mut cx) {
+            Poll::Ready(BusInvocationEvent::Response { data, .. }) => {
+                assert_eq!(data, b"2".to_vec())
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert!(invocation.as_mut().poll_finished(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn dropped_call_is_aborted() {
+        let bus = LocalBus::new();
+        let callee = bus.child();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut invocation =
+            Pin::from(invoke(&callee.inbox, "ping".to_string(), BusDataFormat::Raw, &[]).unwrap());
+        finish(&Mutex::new(ExitState::default()), &callee.inbox, 0);
+
+        match invocation.as_mut().poll_event(&mut cx) {
+            Poll::Ready(BusInvocationEvent::Fault { fault }) => {
+                assert_eq!(fault, BusError::Aborted)
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert_eq!(
+            invoke(&callee.inbox, "ping".to_string(), BusDataFormat::Raw, &[]).unwrap_err(),
+            BusError::Aborted
+        );
+    }
+}
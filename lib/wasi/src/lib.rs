@@ -35,15 +35,23 @@ compile_error!(
 
 #[macro_use]
 mod macros;
+#[cfg(feature = "sys")]
+mod bus;
 mod runtime;
+mod signal;
 mod state;
 mod syscalls;
 mod utils;
 
 use crate::syscalls::*;
 
+#[cfg(feature = "sys")]
+pub use crate::bus::LocalBus;
+pub use crate::signal::{
+    default_signal_action, is_signal_catchable, signal_exit_code, WasiSignalAction,
+};
 pub use crate::state::{
-    Fd, Pipe, Stderr, Stdin, Stdout, WasiFs, WasiInodes, WasiState, WasiStateBuilder,
+    Fd, Pipe, Stderr, Stdin, Stdout, WasiFs, WasiFsLimits, WasiInodes, WasiState, WasiStateBuilder,
     WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
 };
 pub use crate::syscalls::types;
@@ -57,14 +65,15 @@ pub use wasmer_vfs::FsError as WasiFsError;
 pub use wasmer_vfs::VirtualFile as WasiFile;
 pub use wasmer_vfs::{FsError, VirtualFile};
 pub use wasmer_vnet::{UnsupportedVirtualNetworking, VirtualNetworking};
-use wasmer_wasi_types::__WASI_CLOCK_MONOTONIC;
+use wasmer_wasi_types::{__wasi_signal_t, __WASI_CLOCK_MONOTONIC};
 
 use derivative::*;
 use std::ops::Deref;
 use thiserror::Error;
+use tracing::debug;
 use wasmer::{
-    imports, Function, Imports, LazyInit, Memory, Memory32, MemoryAccessError, MemorySize, Module,
-    Store, TypedFunction, WasmerEnv,
+    imports, Exports, Extern, Function, HostEnvInitError, Imports, Instance, LazyInit, Memory,
+    Memory32, MemoryAccessError, MemorySize, Module, Store, TypedFunction, WasmerEnv,
 };
 
 pub use runtime::{
@@ -143,30 +152,28 @@ impl WasiThread {
 }
 
 /// The environment provided to the WASI imports.
-#[derive(Derivative, Clone, WasmerEnv)]
+#[derive(Derivative, Clone)]
 #[derivative(Debug)]
 pub struct WasiEnv {
     /// ID of this thread (zero is the main thread)
     id: WasiThreadId,
     /// Represents a reference to the memory
-    #[wasmer(export)]
+    ///
+    /// This is the memory the module exports as `memory`, which the WASI
+    /// calls read their arguments from and write their results to. Modules
+    /// with several memories can keep other data, like untrusted buffers,
+    /// in memories that WASI can't access.
     memory: LazyInit<Memory>,
     /// If the module has it then map the thread start
     #[derivative(Debug = "ignore")]
-    #[wasmer(export(optional = true, name = "_thread_start"))]
     thread_start: LazyInit<TypedFunction<u64, ()>>,
+    /// If the module has it then signals are delivered to it
     #[derivative(Debug = "ignore")]
-    #[wasmer(export(optional = true, name = "_reactor_work"))]
-    reactor_work: LazyInit<TypedFunction<u64, ()>>,
-    #[derivative(Debug = "ignore")]
-    #[wasmer(export(optional = true, name = "_reactor_finish"))]
-    reactor_finish: LazyInit<TypedFunction<u64, ()>>,
+    signal_handler: LazyInit<TypedFunction<u32, ()>>,
+    /// All the exports of the module, so that the functions it names in
+    /// calls, like the allocator of `bus_poll`, can be looked up
     #[derivative(Debug = "ignore")]
-    #[wasmer(export(optional = true, name = "_malloc"))]
-    malloc: LazyInit<TypedFunction<u64, u64>>,
-    #[derivative(Debug = "ignore")]
-    #[wasmer(export(optional = true, name = "_free"))]
-    free: LazyInit<TypedFunction<(u64, u64), ()>>,
+    exports: LazyInit<Exports>,
     /// Shared state of the WASI system. Manages all the data that the
     /// executing WASI program can see.
     pub state: Arc<WasiState>,
@@ -174,6 +181,37 @@ pub struct WasiEnv {
     pub(crate) runtime: Arc<dyn WasiRuntimeImplementation + Send + Sync + 'static>,
 }
 
+impl WasmerEnv for WasiEnv {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let memory = instance
+            .exports
+            .get_with_generics_weak::<Memory, _, _>("memory")?;
+        self.memory.initialize(memory);
+        if let Ok(thread_start) = instance
+            .exports
+            .get_with_generics_weak::<TypedFunction<u64, ()>, _, _>("_thread_start")
+        {
+            self.thread_start.initialize(thread_start);
+        }
+        if let Ok(signal_handler) = instance
+            .exports
+            .get_with_generics_weak::<TypedFunction<u32, ()>, _, _>("__wasm_signal")
+        {
+            self.signal_handler.initialize(signal_handler);
+        }
+        // Weak references, as the instance holds this environment
+        let mut exports = Exports::new();
+        for (name, _) in instance.exports.iter() {
+            let export = instance
+                .exports
+                .get_with_generics_weak::<Extern, _, _>(name)?;
+            exports.insert(name.clone(), export);
+        }
+        self.exports.initialize(exports);
+        Ok(())
+    }
+}
+
 impl WasiEnv {
     pub fn new(state: WasiState) -> Self {
         Self {
@@ -181,10 +219,8 @@ impl WasiEnv {
             state: Arc::new(state),
             memory: LazyInit::new(),
             thread_start: LazyInit::new(),
-            reactor_work: LazyInit::new(),
-            reactor_finish: LazyInit::new(),
-            malloc: LazyInit::new(),
-            free: LazyInit::new(),
+            signal_handler: LazyInit::new(),
+            exports: LazyInit::new(),
             runtime: Arc::new(PluggableRuntimeImplementation::default()),
         }
     }
@@ -248,6 +284,16 @@ impl WasiEnv {
         self.memory.clone()
     }
 
+    /// Get the `_thread_start` function, if the module exports it
+    pub(crate) fn thread_start_ref(&self) -> Option<&TypedFunction<u64, ()>> {
+        self.thread_start.get_ref()
+    }
+
+    /// Get the exports of the module
+    pub(crate) fn exports_ref(&self) -> Option<&Exports> {
+        self.exports.get_ref()
+    }
+
     /// Get an `Imports` for a specific version of WASI detected in the module.
     pub fn import_object(&mut self, module: &Module) -> Result<Imports, WasiError> {
         let wasi_version = get_wasi_version(module, false).ok_or(WasiError::UnknownWasiVersion)?;
@@ -289,9 +335,72 @@ impl WasiEnv {
     // Yields execution
     pub fn yield_now(&self) -> Result<(), WasiError> {
         self.runtime.yield_now(self.id)?;
+        self.process_signals()?;
         Ok(())
     }
 
+    /// Queues a signal for the program, it is delivered the next time the
+    /// program yields, e.g. while it sleeps, polls or waits on `stdin`.
+    ///
+    /// If the module exports a `__wasm_signal` function taking the signal
+    /// as an `i32` then it is called to handle the signal, otherwise the
+    /// [default action](default_signal_action) of the signal is applied.
+    pub fn signal(&self, sig: __wasi_signal_t) {
+        let mut guard = self.state.threading.lock().unwrap();
+        guard.signals.push_back(sig);
+    }
+
+    /// Whether signals queued with [`WasiEnv::signal`] are waiting for the
+    /// program to yield.
+    pub fn has_pending_signals(&self) -> bool {
+        !self.state.threading.lock().unwrap().signals.is_empty()
+    }
+
+    /// Delivers the signals queued with [`WasiEnv::signal`].
+    pub fn process_signals(&self) -> Result<(), WasiError> {
+        loop {
+            // The lock can't be held while the handler runs, it could raise
+            // more signals
+            let sig = self.state.threading.lock().unwrap().signals.pop_front();
+            match sig {
+                Some(sig) => self.deliver_signal(sig)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Delivers `sig` right away, a handler that traps terminates the
+    /// program as if it didn't handle the signal.
+    pub(crate) fn deliver_signal(&self, sig: __wasi_signal_t) -> Result<(), WasiError> {
+        let action = match default_signal_action(sig) {
+            Some(action) => action,
+            None => {
+                debug!("wasi::signal: discarding unknown signal {}", sig);
+                return Ok(());
+            }
+        };
+
+        if let Some(handler) = self.signal_handler.get_ref() {
+            if is_signal_catchable(sig) {
+                return match handler.call(sig as u32) {
+                    Ok(()) => Ok(()),
+                    Err(err) => match err.downcast::<WasiError>() {
+                        Ok(err) => Err(err),
+                        Err(err) => {
+                            debug!("wasi::signal: handler of signal {} failed: {}", sig, err);
+                            Err(WasiError::Exit(signal_exit_code(sig)))
+                        }
+                    },
+                };
+            }
+        }
+
+        match action {
+            WasiSignalAction::Terminate => Err(WasiError::Exit(signal_exit_code(sig))),
+            WasiSignalAction::Ignore => Ok(()),
+        }
+    }
+
     // Sleeps for a period of time
     pub fn sleep(&self, duration: Duration) -> Result<(), WasiError> {
         let duration = duration.as_nanos();
@@ -329,7 +438,7 @@ impl WasiEnv {
     pub fn bus(&self) -> &(dyn VirtualBus) {
         self.runtime.bus()
     }
-    pub(crate) fn get_memory_and_wasi_state(&self, _mem_index: u32) -> (&Memory, &WasiState) {
+    pub(crate) fn get_memory_and_wasi_state(&self) -> (&Memory, &WasiState) {
         let memory = self.memory();
         let state = self.state.deref();
         (memory, state)
@@ -337,7 +446,6 @@ impl WasiEnv {
 
     pub(crate) fn get_memory_and_wasi_state_and_inodes(
         &self,
-        _mem_index: u32,
     ) -> (&Memory, &WasiState, RwLockReadGuard<WasiInodes>) {
         let memory = self.memory();
         let state = self.state.deref();
@@ -347,7 +455,6 @@ impl WasiEnv {
 
     pub(crate) fn get_memory_and_wasi_state_and_inodes_mut(
         &self,
-        _mem_index: u32,
     ) -> (&Memory, &WasiState, RwLockWriteGuard<WasiInodes>) {
         let memory = self.memory();
         let state = self.state.deref();
@@ -71,6 +71,24 @@ macro_rules! wasi_try_bus {
     }};
 }
 
+/// Like the `try!` macro or `?` syntax: returns the value if the computation
+/// succeeded or returns the error value. Results are wrapped in an Ok
+macro_rules! wasi_try_bus_ok {
+    ($expr:expr) => {{
+        let res: Result<_, crate::syscalls::types::__bus_errno_t> = $expr;
+        match res {
+            Ok(val) => {
+                tracing::trace!("wasi::wasi_try_bus_ok::val: {:?}", val);
+                val
+            }
+            Err(err) => {
+                tracing::debug!("wasi::wasi_try_bus_ok::err: {:?}", err);
+                return Ok(err);
+            }
+        }
+    }};
+}
+
 /// Like `wasi_try` but converts a `MemoryAccessError` to a __wasi_errno_t`.
 macro_rules! wasi_try_mem {
     ($expr:expr) => {{
@@ -85,6 +103,13 @@ macro_rules! wasi_try_mem_bus {
     }};
 }
 
+/// Like `wasi_try_mem_bus` but wraps the error value in an Ok
+macro_rules! wasi_try_mem_bus_ok {
+    ($expr:expr) => {{
+        wasi_try_bus_ok!($expr.map_err($crate::mem_error_to_bus))
+    }};
+}
+
 /// Like `wasi_try` but converts a `MemoryAccessError` to a __wasi_errno_t`.
 macro_rules! wasi_try_mem_ok {
     ($expr:expr) => {{
@@ -108,3 +133,9 @@ macro_rules! get_input_str_bus {
         wasi_try_mem_bus!($data.read_utf8_string($memory, $len))
     }};
 }
+
+macro_rules! get_input_str_bus_ok {
+    ($memory:expr, $data:expr, $len:expr) => {{
+        wasi_try_mem_bus_ok!($data.read_utf8_string($memory, $len))
+    }};
+}
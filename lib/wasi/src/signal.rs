@@ -0,0 +1,37 @@
+use crate::syscalls::types::*;
+
+/// What happens to a program when it receives a signal it doesn't handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasiSignalAction {
+    /// The program exits with the code returned by [`signal_exit_code`].
+    Terminate,
+    /// The signal is discarded. Signals that would stop or continue the
+    /// program are ignored as well, as there is no job control.
+    Ignore,
+}
+
+/// Returns the default action for `sig`, or `None` if `sig` isn't a signal.
+pub fn default_signal_action(sig: __wasi_signal_t) -> Option<WasiSignalAction> {
+    match sig {
+        __WASI_SIGHUP | __WASI_SIGINT | __WASI_SIGQUIT | __WASI_SIGILL | __WASI_SIGTRAP
+        | __WASI_SIGABRT | __WASI_SIGBUS | __WASI_SIGFPE | __WASI_SIGKILL | __WASI_SIGUSR1
+        | __WASI_SIGSEGV | __WASI_SIGUSR2 | __WASI_SIGPIPE | __WASI_SIGALRM | __WASI_SIGTERM
+        | __WASI_SIGXCPU | __WASI_SIGXFSZ | __WASI_SIGVTALRM | __WASI_SIGPROF | __WASI_SIGPOLL
+        | __WASI_SIGPWR | __WASI_SIGSYS => Some(WasiSignalAction::Terminate),
+        __WASI_SIGCHLD | __WASI_SIGCONT | __WASI_SIGSTOP | __WASI_SIGTSTP | __WASI_SIGTTIN
+        | __WASI_SIGTTOU | __WASI_SIGURG | __WASI_SIGWINCH => Some(WasiSignalAction::Ignore),
+        _ => None,
+    }
+}
+
+/// Whether the program can handle `sig` itself, `SIGKILL` and `SIGSTOP`
+/// always get their default action.
+pub fn is_signal_catchable(sig: __wasi_signal_t) -> bool {
+    !matches!(sig, __WASI_SIGKILL | __WASI_SIGSTOP)
+}
+
+/// The exit code of a program terminated by `sig`, following the
+/// convention of shells.
+pub fn signal_exit_code(sig: __wasi_signal_t) -> __wasi_exitcode_t {
+    128 + sig as __wasi_exitcode_t
+}
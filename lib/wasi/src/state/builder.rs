@@ -1,6 +1,6 @@
 //! Builder system for configuring a [`WasiState`] and creating it.
 
-use crate::state::{default_fs_backing, WasiFs, WasiState};
+use crate::state::{default_fs_backing, WasiFs, WasiFsLimits, WasiState};
 use crate::syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO};
 use crate::{WasiEnv, WasiInodes};
 use generational_arena::Arena;
@@ -50,6 +50,7 @@ pub struct WasiStateBuilder {
     stderr_override: Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
     stdin_override: Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
     fs_override: Option<Box<dyn wasmer_vfs::FileSystem>>,
+    fs_limits: WasiFsLimits,
     runtime_override: Option<Arc<dyn crate::WasiRuntimeImplementation + Send + Sync + 'static>>,
 }
 
@@ -64,6 +65,7 @@ impl std::fmt::Debug for WasiStateBuilder {
             .field("stdout_override exists", &self.stdout_override.is_some())
             .field("stderr_override exists", &self.stderr_override.is_some())
             .field("stdin_override exists", &self.stdin_override.is_some())
+            .field("fs_limits", &self.fs_limits)
             .field("runtime_override_exists", &self.runtime_override.is_some())
             .finish()
     }
@@ -315,6 +317,14 @@ impl WasiStateBuilder {
         self
     }
 
+    /// Sets the limits on the resources the program can consume through
+    /// its file system, see [`WasiFsLimits`].
+    pub fn fs_limits(&mut self, limits: WasiFsLimits) -> &mut Self {
+        self.fs_limits = limits;
+
+        self
+    }
+
     /// Configure the WASI filesystem before running.
     // TODO: improve ergonomics on this function
     pub fn setup_fs(
@@ -420,7 +430,16 @@ impl WasiStateBuilder {
 
         let fs_backing = self.fs_override.take().unwrap_or_else(default_fs_backing);
 
-        // self.preopens are checked in [`PreopenDirBuilder::build`]
+        // The preopened directories must exist in the file system they are
+        // opened from, which isn't always the host, e.g. for mounted archives
+        for preopen in &self.preopens {
+            if fs_backing.metadata(&preopen.path).is_err() {
+                return Err(WasiStateCreationError::PreopenedDirectoryNotFound(
+                    preopen.path.clone(),
+                ));
+            }
+        }
+
         let inodes = RwLock::new(crate::state::WasiInodes {
             arena: Arena::new(),
             orphan_fds: HashMap::new(),
@@ -428,12 +447,12 @@ impl WasiStateBuilder {
         let wasi_fs = {
             let mut inodes = inodes.write().unwrap();
 
-            // self.preopens are checked in [`PreopenDirBuilder::build`]
             let mut wasi_fs = WasiFs::new_with_preopen(
                 inodes.deref_mut(),
                 &self.preopens,
                 &self.vfs_preopens,
                 fs_backing,
+                self.fs_limits,
             )
             .map_err(WasiStateCreationError::WasiFsCreationError)?;
 
@@ -590,12 +609,6 @@ impl PreopenDirBuilder {
         }
         let path = self.path.clone().unwrap();
 
-        /*
-        if !path.exists() {
-            return Err(WasiStateCreationError::PreopenedDirectoryNotFound(path));
-        }
-        */
-
         if let Some(alias) = &self.alias {
             validate_mapped_dir_alias(alias)?;
         }
@@ -668,4 +681,18 @@ mod test {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn preopened_directory_not_found() {
+        let output = create_wasi_state("test_prog")
+            .preopen_dir("/wasmer-wasi-test/does/not/exist")
+            .unwrap()
+            .build();
+        match output {
+            Err(WasiStateCreationError::PreopenedDirectoryNotFound(path)) => {
+                assert_eq!(path, PathBuf::from("/wasmer-wasi-test/does/not/exist"))
+            }
+            _ => assert!(false),
+        }
+    }
 }
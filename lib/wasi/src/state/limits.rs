@@ -0,0 +1,102 @@
+use super::{WasiFs, WasiInodes};
+use crate::syscalls::types::*;
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+
+/// Limits on the resources a WASI program can consume through its file
+/// system. Every limit is disabled by default.
+///
+/// ```
+/// # use wasmer_wasi::{WasiFsLimits, WasiState};
+/// let mut state_builder = WasiState::new("wasi-prog-name");
+/// state_builder.fs_limits(WasiFsLimits {
+///     max_bytes_written: Some(64 * 1024 * 1024),
+///     max_open_fds: Some(256),
+///     ..WasiFsLimits::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct WasiFsLimits {
+    /// The total number of bytes that can be written to files, growing a
+    /// file with `fd_allocate` or `fd_filestat_set_size` counts as well.
+    /// A write past this limit fails with `__WASI_ENOSPC`.
+    pub max_bytes_written: Option<u64>,
+    /// The number of inodes the file system can track, which includes
+    /// every file and directory that was opened or looked up and the
+    /// standard streams. Creating an inode past this limit fails with
+    /// `__WASI_ENOSPC`.
+    pub max_inodes: Option<usize>,
+    /// The number of file descriptors that can be open at the same time,
+    /// including the standard streams and the preopened directories.
+    /// Opening a file descriptor past this limit fails with
+    /// `__WASI_EMFILE`.
+    pub max_open_fds: Option<usize>,
+    /// The size a file can't grow beyond, writing or resizing a file past
+    /// it fails with `__WASI_EFBIG`.
+    pub max_file_size: Option<u64>,
+}
+
+impl WasiFs {
+    /// The limits enforced by this file system.
+    pub fn limits(&self) -> &WasiFsLimits {
+        &self.limits
+    }
+
+    /// The number of bytes written to files so far, as counted against
+    /// [`WasiFsLimits::max_bytes_written`].
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Acquire)
+    }
+
+    /// Checks that a new inode can be added to `inodes`.
+    pub(crate) fn check_inode_limit(&self, inodes: &WasiInodes) -> Result<(), __wasi_errno_t> {
+        match self.limits.max_inodes {
+            Some(max) if inodes.arena.len() >= max => Err(__WASI_ENOSPC),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a new file descriptor can be opened.
+    pub(crate) fn check_fd_limit(&self) -> Result<(), __wasi_errno_t> {
+        match self.limits.max_open_fds {
+            Some(max) if self.fd_map.read().unwrap().len() >= max => Err(__WASI_EMFILE),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that `len` bytes can be written to a file at `offset`. The
+    /// bytes must then be accounted for with
+    /// [`WasiFs::add_bytes_written`].
+    pub(crate) fn check_write(&self, offset: u64, len: u64) -> Result<(), __wasi_errno_t> {
+        if len == 0 {
+            return Ok(());
+        }
+
+        if let Some(max) = self.limits.max_file_size {
+            if offset.checked_add(len).map_or(true, |end| end > max) {
+                return Err(__WASI_EFBIG);
+            }
+        }
+
+        if let Some(max) = self.limits.max_bytes_written {
+            if self.bytes_written().saturating_add(len) > max {
+                return Err(__WASI_ENOSPC);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a file can be resized from `old_size` to `new_size`,
+    /// the bytes it grows by must then be accounted for with
+    /// [`WasiFs::add_bytes_written`].
+    pub(crate) fn check_resize(&self, old_size: u64, new_size: u64) -> Result<(), __wasi_errno_t> {
+        self.check_write(old_size, new_size.saturating_sub(old_size))
+    }
+
+    pub(crate) fn add_bytes_written(&self, len: u64) {
+        self.bytes_written.fetch_add(len, Ordering::AcqRel);
+    }
+}
@@ -17,12 +17,14 @@
 
 mod builder;
 mod guard;
+mod limits;
 mod pipe;
 mod socket;
 mod types;
 
 pub use self::builder::*;
 pub use self::guard::*;
+pub use self::limits::*;
 pub use self::pipe::*;
 pub use self::socket::*;
 pub use self::types::*;
@@ -38,6 +40,7 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::pin::Pin;
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::{
@@ -51,7 +54,7 @@ use std::{
     },
 };
 use tracing::{debug, trace};
-use wasmer_vbus::BusSpawnedProcess;
+use wasmer_vbus::{BusSpawnedProcess, VirtualBusCalled, VirtualBusInvocation, VirtualBusListener};
 
 use wasmer_vfs::{FileSystem, FsError, OpenOptions, VirtualFile};
 
@@ -59,13 +62,13 @@ use wasmer_vfs::{FileSystem, FsError, OpenOptions, VirtualFile};
 pub const VIRTUAL_ROOT_FD: __wasi_fd_t = 3;
 /// all the rights enabled
 pub const ALL_RIGHTS: __wasi_rights_t = 0x1FFF_FFFF;
-const STDIN_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
+pub(crate) const STDIN_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
     | __WASI_RIGHT_FD_READ
     | __WASI_RIGHT_FD_SYNC
     | __WASI_RIGHT_FD_ADVISE
     | __WASI_RIGHT_FD_FILESTAT_GET
     | __WASI_RIGHT_POLL_FD_READWRITE;
-const STDOUT_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
+pub(crate) const STDOUT_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
     | __WASI_RIGHT_FD_WRITE
     | __WASI_RIGHT_FD_SYNC
     | __WASI_RIGHT_FD_ADVISE
@@ -331,6 +334,8 @@ pub struct WasiFs {
     inode_counter: AtomicU64,
     pub current_dir: Mutex<String>,
     pub is_wasix: AtomicBool,
+    pub limits: WasiFsLimits,
+    bytes_written: AtomicU64,
     #[cfg_attr(feature = "enable-serde", serde(skip, default = "default_fs_backing"))]
     pub fs_backing: Box<dyn FileSystem>,
 }
@@ -391,8 +396,9 @@ impl WasiFs {
         preopens: &[PreopenedDir],
         vfs_preopens: &[String],
         fs_backing: Box<dyn FileSystem>,
+        limits: WasiFsLimits,
     ) -> Result<Self, String> {
-        let (wasi_fs, root_inode) = Self::new_init(fs_backing, inodes)?;
+        let (wasi_fs, root_inode) = Self::new_init(fs_backing, limits, inodes)?;
 
         for preopen_name in vfs_preopens {
             let kind = Kind::Dir {
@@ -569,6 +575,7 @@ impl WasiFs {
     /// `new_with_preopen`
     fn new_init(
         fs_backing: Box<dyn FileSystem>,
+        limits: WasiFsLimits,
         inodes: &mut WasiInodes,
     ) -> Result<(Self, Inode), String> {
         debug!("Initializing WASI filesystem");
@@ -580,6 +587,8 @@ impl WasiFs {
             inode_counter: AtomicU64::new(1024),
             current_dir: Mutex::new("/".to_string()),
             is_wasix: AtomicBool::new(false),
+            limits,
+            bytes_written: AtomicU64::new(0),
             fs_backing,
         };
         wasi_fs.create_stdin(inodes);
@@ -664,12 +673,9 @@ impl WasiFs {
                     };
 
                     drop(guard);
-                    let inode = self.create_inode_with_default_stat(
-                        inodes,
-                        kind,
-                        false,
-                        segment_name.clone(),
-                    );
+                    let inode = self
+                        .create_inode_with_default_stat(inodes, kind, false, segment_name.clone())
+                        .map_err(fs_error_from_wasi_err)?;
 
                     // reborrow to insert
                     {
@@ -881,7 +887,7 @@ impl WasiFs {
         follow_symlinks: bool,
     ) -> Result<Inode, __wasi_errno_t> {
         if symlink_count > MAX_SYMLINKS {
-            return Err(__WASI_EMLINK);
+            return Err(__WASI_ELOOP);
         }
 
         let path: &Path = Path::new(path);
@@ -893,7 +899,7 @@ impl WasiFs {
             let last_component = i + 1 == n_components;
             // for each component traverse file structure
             // loading inodes as necessary
-            'symlink_resolution: while symlink_count < MAX_SYMLINKS {
+            'symlink_resolution: loop {
                 let mut guard = inodes.arena[cur_inode].write();
                 match guard.deref_mut() {
                     Kind::Buffer { .. } => unimplemented!("state::get_inode_at_path for buffers"),
@@ -915,18 +921,20 @@ impl WasiFs {
                             "." => continue 'path_iter,
                             _ => (),
                         }
-                        // used for full resolution of symlinks
-                        let mut loop_for_symlink = false;
                         if let Some(entry) =
                             entries.get(component.as_os_str().to_string_lossy().as_ref())
                         {
                             cur_inode = *entry;
+                            drop(guard);
                         } else {
                             let file = {
                                 let mut cd = path.clone();
                                 cd.push(component);
                                 cd
                             };
+                            // the directory is locked again when the new entry is inserted,
+                            // resolving a symlink needs to read the preopened directories
+                            drop(guard);
                             let metadata = self
                                 .fs_backing
                                 .symlink_metadata(&file)
@@ -955,16 +963,14 @@ impl WasiFs {
                                 }
                             } else if file_type.is_symlink() {
                                 should_insert = false;
-                                let link_value = file.read_link().map_err(map_io_err)?;
+                                let link_value = self
+                                    .fs_backing
+                                    .readlink(&file)
+                                    .map_err(fs_error_into_wasi_err)?;
                                 debug!("attempting to decompose path {:?}", link_value);
 
-                                let (pre_open_dir_fd, relative_path) = if link_value.is_relative() {
-                                    self.path_into_pre_open_and_relative_path(inodes, &file)?
-                                } else {
-                                    unimplemented!("Absolute symlinks are not yet supported");
-                                };
-                                loop_for_symlink = true;
-                                symlink_count += 1;
+                                let (pre_open_dir_fd, relative_path) =
+                                    self.path_into_pre_open_and_relative_path(inodes, &file)?;
                                 Kind::Symlink {
                                     base_po_dir: pre_open_dir_fd,
                                     path_to_symlink: relative_path.to_owned(),
@@ -995,7 +1001,6 @@ impl WasiFs {
                                         path: file.clone(),
                                         fd: None,
                                     };
-                                    drop(guard);
                                     let new_inode = self.create_inode_with_stat(
                                         inodes,
                                         kind,
@@ -1005,7 +1010,7 @@ impl WasiFs {
                                             st_filetype: file_type,
                                             ..__wasi_filestat_t::default()
                                         },
-                                    );
+                                    )?;
 
                                     let mut guard = inodes.arena[cur_inode].write();
                                     if let Kind::Dir {
@@ -1028,7 +1033,6 @@ impl WasiFs {
                                 unimplemented!("state::get_inode_at_path unknown file type: not file, directory, or symlink");
                             };
 
-                            drop(guard);
                             let new_inode = self.create_inode(
                                 inodes,
                                 kind,
@@ -1048,11 +1052,13 @@ impl WasiFs {
                                 }
                             }
                             cur_inode = new_inode;
+                        }
 
-                            if loop_for_symlink && follow_symlinks {
-                                debug!("Following symlink to {:?}", cur_inode);
-                                continue 'symlink_resolution;
-                            }
+                        // symlinks in the middle of the path are always followed,
+                        // the last one only if we were asked to
+                        if !last_component || follow_symlinks {
+                            cur_inode =
+                                self.follow_symlink(inodes, cur_inode, &mut symlink_count)?;
                         }
                     }
                     Kind::Root { entries } => {
@@ -1078,45 +1084,10 @@ impl WasiFs {
                     | Kind::EventNotifications { .. } => {
                         return Err(__WASI_ENOTDIR);
                     }
-                    Kind::Symlink {
-                        base_po_dir,
-                        path_to_symlink,
-                        relative_path,
-                    } => {
-                        let new_base_dir = *base_po_dir;
-                        let new_base_inode = self.get_fd_inode(new_base_dir)?;
-
-                        // allocate to reborrow mutabily to recur
-                        let new_path = {
-                            /*if let Kind::Root { .. } = self.inodes[base_po_dir].kind {
-                                assert!(false, "symlinks should never be relative to the root");
-                            }*/
-                            let mut base = path_to_symlink.clone();
-                            // remove the symlink file itself from the path, leaving just the path from the base
-                            // to the dir containing the symlink
-                            base.pop();
-                            base.push(relative_path);
-                            base.to_string_lossy().to_string()
-                        };
-                        debug!("Following symlink recursively");
+                    Kind::Symlink { .. } => {
+                        // resolve the symlink, then look the component up in its target
                         drop(guard);
-                        let symlink_inode = self.get_inode_at_path_inner(
-                            inodes,
-                            new_base_inode,
-                            &new_path,
-                            symlink_count + 1,
-                            follow_symlinks,
-                        )?;
-                        cur_inode = symlink_inode;
-                        // if we're at the very end and we found a file, then we're done
-                        // TODO: figure out if this should also happen for directories?
-                        let guard = inodes.arena[cur_inode].read();
-                        if let Kind::File { .. } = guard.deref() {
-                            // check if on last step
-                            if last_component {
-                                break 'symlink_resolution;
-                            }
-                        }
+                        cur_inode = self.follow_symlink(inodes, cur_inode, &mut symlink_count)?;
                         continue 'symlink_resolution;
                     }
                 }
@@ -1127,6 +1098,55 @@ impl WasiFs {
         Ok(cur_inode)
     }
 
+    /// Returns the inode that `inode` points to if it's a symlink, or `inode`
+    /// itself otherwise. The target of a symlink is always fully resolved.
+    ///
+    /// Relative symlinks are resolved from the directory containing them,
+    /// absolute ones are paths of the backing file system and can only be
+    /// followed if a preopened directory contains their target.
+    fn follow_symlink(
+        &self,
+        inodes: &mut WasiInodes,
+        inode: Inode,
+        symlink_count: &mut u32,
+    ) -> Result<Inode, __wasi_errno_t> {
+        let (base_inode, path) = {
+            let guard = inodes.arena[inode].read();
+            match guard.deref() {
+                Kind::Symlink {
+                    base_po_dir,
+                    path_to_symlink,
+                    relative_path,
+                } => {
+                    if relative_path.is_absolute() {
+                        let (pre_open_dir_fd, relative_path) = self
+                            .path_into_pre_open_and_relative_path(inodes, relative_path)
+                            .map_err(|_| __WASI_EACCES)?;
+                        (
+                            self.get_fd_inode(pre_open_dir_fd)?,
+                            relative_path.to_string_lossy().to_string(),
+                        )
+                    } else {
+                        let mut base = path_to_symlink.clone();
+                        // remove the symlink file itself from the path, leaving just the path from the base
+                        // to the dir containing the symlink
+                        base.pop();
+                        base.push(relative_path);
+                        (
+                            self.get_fd_inode(*base_po_dir)?,
+                            base.to_string_lossy().to_string(),
+                        )
+                    }
+                }
+                _ => return Ok(inode),
+            }
+        };
+
+        *symlink_count += 1;
+        debug!("Following symlink recursively to {}", path);
+        self.get_inode_at_path_inner(inodes, base_inode, &path, *symlink_count, true)
+    }
+
     /// Finds the preopened directory that is the "best match" for the given path and
     /// returns a path relative to this preopened directory.
     ///
@@ -1221,10 +1241,10 @@ impl WasiFs {
 
     /// gets a host file from a base directory and a path
     /// this function ensures the fs remains sandboxed
-    // NOTE: follow symlinks is super weird right now
-    // even if it's false, it still follows symlinks, just not the last
-    // symlink so
-    // This will be resolved when we have tests asserting the correct behavior
+    ///
+    /// Symlinks in the middle of the path are always followed, `follow_symlinks`
+    /// only controls whether a symlink at the end of the path is followed
+    /// (`__WASI_LOOKUP_SYMLINK_FOLLOW`) or returned as is.
     pub(crate) fn get_inode_at_path(
         &self,
         inodes: &mut WasiInodes,
@@ -1410,7 +1430,7 @@ impl WasiFs {
                     } => file.flush().map_err(|_| __WASI_EIO)?,
                     // TODO: verify this behavior
                     Kind::Dir { .. } => return Err(__WASI_EISDIR),
-                    Kind::Symlink { .. } => unimplemented!("WasiFs::flush Kind::Symlink"),
+                    Kind::Symlink { .. } => return Err(__WASI_EBADF),
                     Kind::Buffer { .. } => (),
                     _ => return Err(__WASI_EIO),
                 }
@@ -1428,7 +1448,7 @@ impl WasiFs {
         name: String,
     ) -> Result<Inode, __wasi_errno_t> {
         let stat = self.get_stat_for_kind(inodes, &kind)?;
-        Ok(self.create_inode_with_stat(inodes, kind, is_preopened, name, stat))
+        self.create_inode_with_stat(inodes, kind, is_preopened, name, stat)
     }
 
     /// Creates an inode and inserts it given a Kind, does not assume the file exists.
@@ -1438,7 +1458,7 @@ impl WasiFs {
         kind: Kind,
         is_preopened: bool,
         name: String,
-    ) -> Inode {
+    ) -> Result<Inode, __wasi_errno_t> {
         let stat = __wasi_filestat_t::default();
         self.create_inode_with_stat(inodes, kind, is_preopened, name, stat)
     }
@@ -1451,15 +1471,16 @@ impl WasiFs {
         is_preopened: bool,
         name: String,
         mut stat: __wasi_filestat_t,
-    ) -> Inode {
+    ) -> Result<Inode, __wasi_errno_t> {
+        self.check_inode_limit(inodes)?;
         stat.st_ino = self.get_next_inode_index();
 
-        inodes.arena.insert(InodeVal {
+        Ok(inodes.arena.insert(InodeVal {
             stat: RwLock::new(stat),
             is_preopened,
             name,
             kind: RwLock::new(kind),
-        })
+        }))
     }
 
     pub fn create_fd(
@@ -1470,6 +1491,7 @@ impl WasiFs {
         open_flags: u16,
         inode: Inode,
     ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        self.check_fd_limit()?;
         let idx = self.next_fd.fetch_add(1, Ordering::AcqRel);
         self.fd_map.write().unwrap().insert(
             idx,
@@ -1487,6 +1509,7 @@ impl WasiFs {
 
     pub fn clone_fd(&self, fd: __wasi_fd_t) -> Result<__wasi_fd_t, __wasi_errno_t> {
         let fd = self.get_fd(fd)?;
+        self.check_fd_limit()?;
         let idx = self.next_fd.fetch_add(1, Ordering::AcqRel);
         self.fd_map.write().unwrap().insert(
             idx,
@@ -1604,6 +1627,29 @@ impl WasiFs {
         );
     }
 
+    /// Opens a file that was handed to this process (such as the stdio of
+    /// a sub-process) under a new file descriptor
+    pub(crate) fn create_fd_for_file(
+        &self,
+        inodes: &mut WasiInodes,
+        handle: Box<dyn VirtualFile + Send + Sync + 'static>,
+        name: &str,
+        rights: __wasi_rights_t,
+        open_flags: u16,
+    ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        let stat = __wasi_filestat_t {
+            st_filetype: __WASI_FILETYPE_CHARACTER_DEVICE,
+            ..__wasi_filestat_t::default()
+        };
+        let kind = Kind::File {
+            fd: None,
+            handle: Some(handle),
+            path: "".into(),
+        };
+        let inode = self.create_inode_with_stat(inodes, kind, false, name.to_string(), stat)?;
+        self.create_fd(rights, 0, 0, open_flags, inode)
+    }
+
     pub fn get_stat_for_kind(
         &self,
         inodes: &WasiInodes,
@@ -1636,27 +1682,10 @@ impl WasiFs {
                 path_to_symlink,
                 ..
             } => {
-                let base_po_inode = &self.fd_map.read().unwrap()[base_po_dir].inode;
-                let base_po_inode_v = &inodes.arena[*base_po_inode];
-                let guard = base_po_inode_v.read();
-                match guard.deref() {
-                    Kind::Root { .. } => {
-                        self.fs_backing.symlink_metadata(path_to_symlink).map_err(fs_error_into_wasi_err)?
-                    }
-                    Kind::Dir { path, .. } => {
-                        let mut real_path = path.clone();
-                        // PHASE 1: ignore all possible symlinks in `relative_path`
-                        // TODO: walk the segments of `relative_path` via the entries of the Dir
-                        //       use helper function to avoid duplicating this logic (walking this will require
-                        //       &self to be &mut sel
-                        // TODO: adjust size of symlink, too
-                        //      for all paths adjusted think about this
-                        real_path.push(path_to_symlink);
-                        self.fs_backing.symlink_metadata(&real_path).map_err(fs_error_into_wasi_err)?
-                    }
-                    // if this triggers, there's a bug in the symlink code
-                    _ => unreachable!("Symlink pointing to something that's not a directory as its base preopened directory"),
-                }
+                let real_path = self.symlink_host_path(inodes, *base_po_dir, path_to_symlink)?;
+                self.fs_backing
+                    .symlink_metadata(&real_path)
+                    .map_err(fs_error_into_wasi_err)?
             }
             _ => return Err(__WASI_EIO),
         };
@@ -1670,6 +1699,30 @@ impl WasiFs {
         })
     }
 
+    /// Returns the path of a symlink in the backing file system, given the
+    /// preopened directory it is relative to
+    pub(crate) fn symlink_host_path(
+        &self,
+        inodes: &WasiInodes,
+        base_po_dir: __wasi_fd_t,
+        path_to_symlink: &Path,
+    ) -> Result<PathBuf, __wasi_errno_t> {
+        let base_po_inode = self.get_fd_inode(base_po_dir)?;
+        let guard = inodes.arena[base_po_inode].read();
+        match guard.deref() {
+            Kind::Root { .. } => Ok(path_to_symlink.to_path_buf()),
+            Kind::Dir { path, .. } => {
+                let mut real_path = path.clone();
+                real_path.push(path_to_symlink);
+                Ok(real_path)
+            }
+            // if this triggers, there's a bug in the symlink code
+            _ => unreachable!(
+                "Symlink pointing to something that's not a directory as its base preopened directory"
+            ),
+        }
+    }
+
     /// Closes an open FD, handling all details such as FD being preopen
     pub(crate) fn close_fd(
         &self,
@@ -1740,6 +1793,8 @@ impl WasiFs {
             Kind::Root { .. } => return Err(__WASI_EACCES),
             Kind::Symlink { .. } | Kind::Buffer { .. } => return Err(__WASI_EINVAL),
         }
+        // closed file descriptors no longer count against `max_open_fds`
+        self.fd_map.write().unwrap().remove(&fd);
 
         Ok(())
     }
@@ -1789,6 +1844,17 @@ impl WasiState {
             .map_err(fs_error_into_wasi_err)
     }
 
+    pub(crate) fn fs_symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        original: P,
+        link: Q,
+    ) -> Result<(), __wasi_errno_t> {
+        self.fs
+            .fs_backing
+            .symlink(original.as_ref(), link.as_ref())
+            .map_err(fs_error_into_wasi_err)
+    }
+
     pub(crate) fn fs_new_open_options(&self) -> OpenOptions {
         self.fs.fs_backing.new_open_options()
     }
@@ -1806,6 +1872,68 @@ pub(crate) struct WasiStateThreading {
     pub processes: HashMap<WasiBusProcessId, BusSpawnedProcess>,
     pub process_reuse: HashMap<Cow<'static, str>, WasiBusProcessId>,
     pub process_seed: u32,
+    /// Calls this process has made that are still waiting on events
+    pub calls: HashMap<__wasi_cid_t, WasiBusCall>,
+    /// Calls made to this process that have not yet been replied to
+    pub called: HashMap<__wasi_cid_t, Box<dyn VirtualBusCalled + Sync>>,
+    pub call_seed: __wasi_cid_t,
+    /// Signals queued for this process that have not yet been delivered
+    pub signals: VecDeque<__wasi_signal_t>,
+    /// Listener that receives calls made to this process (created on first poll)
+    pub listener: Option<Pin<Box<dyn VirtualBusListener + Sync>>>,
+    /// Events polled from the bus that have yet to be delivered to this process
+    pub pending_events: VecDeque<BusPollEvent>,
+}
+
+impl WasiStateThreading {
+    /// Allocates a call handle that is not used by any outstanding call
+    pub fn new_call_id(&mut self) -> Option<__wasi_cid_t> {
+        for _ in 0..=__wasi_cid_t::MAX {
+            self.call_seed = self.call_seed.wrapping_add(1);
+            let cid = self.call_seed;
+            if !self.calls.contains_key(&cid) && !self.called.contains_key(&cid) {
+                return Some(cid);
+            }
+        }
+        None
+    }
+}
+
+/// An outstanding call made by this process on the bus
+#[derive(Debug)]
+pub(crate) struct WasiBusCall {
+    pub bid: WasiBusProcessId,
+    /// Keeps the call open after a response is received
+    pub keep_alive: bool,
+    pub invocation: Pin<Box<dyn VirtualBusInvocation + Sync>>,
+}
+
+/// Event received by `bus_poll` that has yet to be written to memory
+#[derive(Debug)]
+pub(crate) enum BusPollEvent {
+    Exit {
+        bid: WasiBusProcessId,
+        rval: __wasi_exitcode_t,
+    },
+    Call {
+        parent: Option<__wasi_cid_t>,
+        cid: __wasi_cid_t,
+        format: __wasi_busdataformat_t,
+        topic: String,
+        data: Vec<u8>,
+    },
+    Result {
+        cid: __wasi_cid_t,
+        format: __wasi_busdataformat_t,
+        data: Vec<u8>,
+    },
+    Fault {
+        cid: __wasi_cid_t,
+        err: __bus_errno_t,
+    },
+    Close {
+        cid: __wasi_cid_t,
+    },
 }
 
 /// Top level data type containing all* the state with which WASI can
@@ -1938,3 +2066,131 @@ pub fn virtual_file_type_to_wasi_file_type(file_type: wasmer_vfs::FileType) -> _
         __WASI_FILETYPE_UNKNOWN
     }
 }
+
+#[cfg(all(test, unix, feature = "host-fs"))]
+mod test {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// Creates a scratch directory on the host, preopened as the only
+    /// directory of a new `WasiState`
+    fn setup(name: &str) -> (PathBuf, WasiState, __wasi_fd_t) {
+        let dir = std::env::temp_dir().join(format!(
+            "wasmer-wasi-symlinks-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("dir")).unwrap();
+        std::fs::write(dir.join("dir/file.txt"), b"hello").unwrap();
+
+        let state = WasiState::new("test_prog")
+            .preopen_dir(&dir)
+            .unwrap()
+            .build()
+            .unwrap();
+        let fd = *state.fs.preopen_fds.read().unwrap().last().unwrap();
+        (dir, state, fd)
+    }
+
+    fn lookup(
+        state: &WasiState,
+        fd: __wasi_fd_t,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> Result<Inode, __wasi_errno_t> {
+        let mut inodes = state.inodes.write().unwrap();
+        state
+            .fs
+            .get_inode_at_path(inodes.deref_mut(), fd, path, follow_symlinks)
+    }
+
+    fn is_file(state: &WasiState, inode: Inode) -> bool {
+        let inodes = state.inodes.read().unwrap();
+        let guard = inodes.arena[inode].read();
+        matches!(guard.deref(), Kind::File { .. })
+    }
+
+    #[test]
+    fn follows_relative_symlinks() {
+        let (dir, state, fd) = setup("relative");
+        symlink("dir/file.txt", dir.join("link")).unwrap();
+        symlink("dir", dir.join("dirlink")).unwrap();
+
+        let inode = lookup(&state, fd, "link", true).unwrap();
+        assert!(is_file(&state, inode), "the last symlink is followed");
+
+        let inode = lookup(&state, fd, "link", false).unwrap();
+        {
+            let inodes = state.inodes.read().unwrap();
+            let guard = inodes.arena[inode].read();
+            assert!(
+                matches!(guard.deref(), Kind::Symlink { relative_path, .. } if relative_path == Path::new("dir/file.txt")),
+                "the last symlink is not followed without `__WASI_LOOKUP_SYMLINK_FOLLOW`",
+            );
+        }
+
+        let inode = lookup(&state, fd, "dirlink/file.txt", false).unwrap();
+        assert!(
+            is_file(&state, inode),
+            "a symlink in the middle of the path is always followed"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn follows_absolute_symlinks_inside_preopens() {
+        let (dir, state, fd) = setup("absolute");
+        symlink(dir.join("dir/file.txt"), dir.join("inside")).unwrap();
+        symlink("/", dir.join("outside")).unwrap();
+
+        let inode = lookup(&state, fd, "inside", true).unwrap();
+        assert!(is_file(&state, inode));
+
+        assert!(
+            lookup(&state, fd, "outside/etc", true).is_err(),
+            "a symlink can't escape the preopened directories",
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detects_symlink_loops() {
+        let (dir, state, fd) = setup("loop");
+        symlink("loop2", dir.join("loop1")).unwrap();
+        symlink("loop1", dir.join("loop2")).unwrap();
+        symlink("self/file.txt", dir.join("self")).unwrap();
+
+        assert_eq!(lookup(&state, fd, "loop1", true), Err(__WASI_ELOOP));
+        assert_eq!(
+            lookup(&state, fd, "self/file.txt", false),
+            Err(__WASI_ELOOP)
+        );
+        assert!(
+            lookup(&state, fd, "loop1", false).is_ok(),
+            "a symlink loop can be looked up without following it",
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn creates_symlinks_in_the_backing_file_system() {
+        let (dir, state, fd) = setup("create");
+
+        state
+            .fs_symlink("dir/file.txt", dir.join("created"))
+            .unwrap();
+        assert_eq!(
+            std::fs::read_link(dir.join("created")).unwrap(),
+            Path::new("dir/file.txt")
+        );
+
+        let inode = lookup(&state, fd, "created", true).unwrap();
+        assert!(is_file(&state, inode));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
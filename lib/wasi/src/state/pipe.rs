@@ -78,6 +78,27 @@ impl WasiPipe {
         Ok(buf_len)
     }
 
+    /// Returns the number of bytes that can be read without blocking, `0`
+    /// once the other end of the pipe was closed, or `__WASI_EAGAIN` when
+    /// nothing has been written to the pipe yet
+    pub fn poll_read_ready(&mut self) -> Result<usize, __wasi_errno_t> {
+        loop {
+            if let Some(buf) = self.read_buffer.as_ref() {
+                if !buf.is_empty() {
+                    return Ok(buf.len());
+                }
+            }
+            let rx = self.rx.lock().unwrap();
+            match rx.try_recv() {
+                Ok(data) => {
+                    self.read_buffer.replace(Bytes::from(data));
+                }
+                Err(mpsc::TryRecvError::Empty) => return Err(__WASI_EAGAIN),
+                Err(mpsc::TryRecvError::Disconnected) => return Ok(0),
+            }
+        }
+    }
+
     pub fn close(&mut self) {
         let (mut null_tx, _) = mpsc::channel();
         let (_, mut null_rx) = mpsc::channel();
@@ -341,6 +341,46 @@ impl InodeSocket {
         })
     }
 
+    /// Returns the number of bytes that can be received without blocking,
+    /// `0` once the peer has closed the stream, or `__WASI_EAGAIN` when
+    /// nothing has arrived yet. Listeners report the number of connections
+    /// waiting to be accepted instead
+    pub fn poll_read_ready(&self) -> Result<usize, __wasi_errno_t> {
+        if let Some(buf) = self.read_buffer.as_ref() {
+            if !buf.is_empty() {
+                return Ok(buf.len());
+            }
+        }
+        match &self.kind {
+            InodeSocketKind::TcpListener(sock) => sock.poll_accept_ready(),
+            InodeSocketKind::TcpStream(sock) => sock.poll_read_ready(),
+            InodeSocketKind::UdpSocket(sock) => sock.poll_read_ready(),
+            InodeSocketKind::Raw(sock) => sock.poll_read_ready(),
+            InodeSocketKind::Icmp(sock) => sock.poll_read_ready(),
+            InodeSocketKind::PreSocket { .. } => return Err(__WASI_ENOTCONN),
+            InodeSocketKind::Closed => return Ok(0),
+            _ => return Err(__WASI_ENOTSUP),
+        }
+        .map_err(net_error_into_wasi_err)
+    }
+
+    /// Returns the number of bytes that can be sent without blocking, or
+    /// `__WASI_EAGAIN` when the send buffer is full
+    pub fn poll_write_ready(&self) -> Result<usize, __wasi_errno_t> {
+        match &self.kind {
+            InodeSocketKind::TcpStream(sock) => sock.poll_write_ready(),
+            InodeSocketKind::UdpSocket(sock) => sock.poll_write_ready(),
+            InodeSocketKind::Raw(sock) => sock.poll_write_ready(),
+            InodeSocketKind::Icmp(sock) => sock.poll_write_ready(),
+            InodeSocketKind::PreSocket { .. } | InodeSocketKind::TcpListener(_) => {
+                return Err(__WASI_ENOTCONN)
+            }
+            InodeSocketKind::Closed => return Err(__WASI_EPIPE),
+            _ => return Err(__WASI_ENOTSUP),
+        }
+        .map_err(net_error_into_wasi_err)
+    }
+
     pub fn http_status(&self) -> Result<WasiHttpStatus, __wasi_errno_t> {
         Ok(match &self.kind {
             InodeSocketKind::HttpRequest(http, ..) => {
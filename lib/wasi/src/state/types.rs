@@ -2,15 +2,12 @@
 use crate::syscalls::types::*;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
-#[cfg(all(unix, feature = "sys-poll"))]
-use std::convert::TryInto;
 use std::{
     collections::VecDeque,
     io::{self, Read, Seek, Write},
     sync::{Arc, Mutex},
-    time::Duration,
 };
-use wasmer_vbus::BusError;
+use wasmer_vbus::{BusDataFormat, BusError};
 
 #[cfg(feature = "host-fs")]
 pub use wasmer_vfs::host_fs::{Stderr, Stdin, Stdout};
@@ -42,6 +39,7 @@ pub fn fs_error_from_wasi_err(err: __wasi_errno_t) -> FsError {
         __WASI_EAGAIN => FsError::WouldBlock,
         __WASI_ENOSPC => FsError::WriteZero,
         __WASI_ENOTEMPTY => FsError::DirectoryNotEmpty,
+        __WASI_ELOOP => FsError::SymlinkLoop,
         _ => FsError::UnknownError,
     }
 }
@@ -71,6 +69,7 @@ pub fn fs_error_into_wasi_err(fs_error: FsError) -> __wasi_errno_t {
         FsError::WouldBlock => __WASI_EAGAIN,
         FsError::WriteZero => __WASI_ENOSPC,
         FsError::DirectoryNotEmpty => __WASI_ENOTEMPTY,
+        FsError::SymlinkLoop => __WASI_ELOOP,
         FsError::Lock | FsError::UnknownError => __WASI_EIO,
     }
 }
@@ -152,6 +151,33 @@ pub fn wasi_error_into_bus_err(bus_error: __bus_errno_t) -> BusError {
     }
 }
 
+pub fn bus_format_into_wasi_format(format: BusDataFormat) -> __wasi_busdataformat_t {
+    match format {
+        BusDataFormat::Raw => __WASI_BUS_DATA_FORMAT_RAW,
+        BusDataFormat::Bincode => __WASI_BUS_DATA_FORMAT_BINCODE,
+        BusDataFormat::MessagePack => __WASI_BUS_DATA_FORMAT_MESSAGE_PACK,
+        BusDataFormat::Json => __WASI_BUS_DATA_FORMAT_JSON,
+        BusDataFormat::Yaml => __WASI_BUS_DATA_FORMAT_YAML,
+        BusDataFormat::Xml => __WASI_BUS_DATA_FORMAT_XML,
+        BusDataFormat::Rkyv => __WASI_BUS_DATA_FORMAT_RKYV,
+    }
+}
+
+pub fn wasi_format_into_bus_format(
+    format: __wasi_busdataformat_t,
+) -> Result<BusDataFormat, __bus_errno_t> {
+    Ok(match format {
+        __WASI_BUS_DATA_FORMAT_RAW => BusDataFormat::Raw,
+        __WASI_BUS_DATA_FORMAT_BINCODE => BusDataFormat::Bincode,
+        __WASI_BUS_DATA_FORMAT_MESSAGE_PACK => BusDataFormat::MessagePack,
+        __WASI_BUS_DATA_FORMAT_JSON => BusDataFormat::Json,
+        __WASI_BUS_DATA_FORMAT_YAML => BusDataFormat::Yaml,
+        __WASI_BUS_DATA_FORMAT_XML => BusDataFormat::Xml,
+        __WASI_BUS_DATA_FORMAT_RKYV => BusDataFormat::Rkyv,
+        _ => return Err(__BUS_EDES),
+    })
+}
+
 #[derive(Debug, Clone)]
 #[allow(clippy::enum_variant_names)]
 pub enum PollEvent {
@@ -218,40 +244,6 @@ pub fn iterate_poll_events(pes: PollEventSet) -> PollEventIter {
     PollEventIter { pes, i: 0 }
 }
 
-#[cfg(all(unix, feature = "sys-poll"))]
-fn poll_event_set_to_platform_poll_events(mut pes: PollEventSet) -> i16 {
-    let mut out = 0;
-    for i in 0..16 {
-        out |= match PollEvent::from_i16(pes & (1 << i)) {
-            Some(PollEvent::PollIn) => libc::POLLIN,
-            Some(PollEvent::PollOut) => libc::POLLOUT,
-            Some(PollEvent::PollError) => libc::POLLERR,
-            Some(PollEvent::PollHangUp) => libc::POLLHUP,
-            Some(PollEvent::PollInvalid) => libc::POLLNVAL,
-            _ => 0,
-        };
-        pes &= !(1 << i);
-    }
-    out
-}
-
-#[cfg(all(unix, feature = "sys-poll"))]
-fn platform_poll_events_to_pollevent_set(mut num: i16) -> PollEventSet {
-    let mut peb = PollEventBuilder::new();
-    for i in 0..16 {
-        peb = match num & (1 << i) {
-            libc::POLLIN => peb.add(PollEvent::PollIn),
-            libc::POLLOUT => peb.add(PollEvent::PollOut),
-            libc::POLLERR => peb.add(PollEvent::PollError),
-            libc::POLLHUP => peb.add(PollEvent::PollHangUp),
-            libc::POLLNVAL => peb.add(PollEvent::PollInvalid),
-            _ => peb,
-        };
-        num &= !(1 << i);
-    }
-    peb.build()
-}
-
 #[allow(dead_code)]
 impl PollEventBuilder {
     pub fn new() -> PollEventBuilder {
@@ -268,109 +260,46 @@ impl PollEventBuilder {
     }
 }
 
-#[cfg(all(unix, feature = "sys-poll"))]
-pub(crate) fn poll(
-    selfs: &[&(dyn VirtualFile + Send + Sync + 'static)],
-    events: &[PollEventSet],
-    seen_events: &mut [PollEventSet],
-    timeout: Duration,
-) -> Result<u32, FsError> {
-    if !(selfs.len() == events.len() && events.len() == seen_events.len()) {
-        return Err(FsError::InvalidInput);
-    }
-    let mut fds = selfs
-        .iter()
-        .enumerate()
-        .filter_map(|(i, s)| s.get_fd().map(|rfd| (i, rfd)))
-        .map(|(i, host_fd)| libc::pollfd {
-            fd: host_fd.try_into().unwrap(),
-            events: poll_event_set_to_platform_poll_events(events[i]),
-            revents: 0,
-        })
-        .collect::<Vec<_>>();
-    let result = unsafe {
-        libc::poll(
-            fds.as_mut_ptr(),
-            selfs.len() as _,
-            timeout.as_millis() as i32,
-        )
-    };
-
-    if result < 0 {
-        // TODO: check errno and return value
-        return Err(FsError::IOError);
-    }
-    // convert result and write back values
-    for (i, fd) in fds.into_iter().enumerate() {
-        seen_events[i] = platform_poll_events_to_pollevent_set(fd.revents);
-    }
-    // unwrap is safe because we check for negative values above
-    Ok(result.try_into().unwrap())
-}
-
-#[cfg(any(not(unix), not(feature = "sys-poll")))]
-pub(crate) fn poll(
-    files: &[&(dyn VirtualFile + Send + Sync + 'static)],
-    events: &[PollEventSet],
-    seen_events: &mut [PollEventSet],
-    timeout: Duration,
-) -> Result<u32, FsError> {
-    if !(files.len() == events.len() && events.len() == seen_events.len()) {
-        tracing::debug!("the slice length of 'files', 'events' and 'seen_events' must be the same (files={}, events={}, seen_events={})", files.len(), events.len(), seen_events.len());
-        return Err(FsError::InvalidInput);
-    }
-
-    let mut ret = 0;
-    for n in 0..files.len() {
-        let mut builder = PollEventBuilder::new();
-
-        let file = files[n];
-        let can_read = file.bytes_available_read()?.map(|_| true).unwrap_or(false);
-        let can_write = file
-            .bytes_available_write()?
-            .map(|s| s > 0)
-            .unwrap_or(false);
-        let is_closed = file.is_open() == false;
-
-        tracing::debug!(
-            "poll_evt can_read={} can_write={} is_closed={}",
-            can_read,
-            can_write,
-            is_closed
-        );
-
-        for event in iterate_poll_events(events[n]) {
-            match event {
-                PollEvent::PollIn if can_read => {
-                    builder = builder.add(PollEvent::PollIn);
-                }
-                PollEvent::PollOut if can_write => {
-                    builder = builder.add(PollEvent::PollOut);
-                }
-                PollEvent::PollHangUp if is_closed => {
-                    builder = builder.add(PollEvent::PollHangUp);
-                }
-                PollEvent::PollInvalid if is_closed => {
-                    builder = builder.add(PollEvent::PollInvalid);
-                }
-                PollEvent::PollError if is_closed => {
-                    builder = builder.add(PollEvent::PollError);
-                }
-                _ => {}
+/// Checks which of the `events` can be performed on `file` right now without
+/// blocking. Files that can't tell how many bytes they hold, such as regular
+/// files, never block
+pub(crate) fn poll_file(
+    file: &(dyn VirtualFile + Send + Sync + 'static),
+    events: PollEventSet,
+) -> Result<PollEventSet, FsError> {
+    let is_closed = !file.is_open();
+    let can_read = is_closed || file.bytes_available_read()?.map(|s| s > 0).unwrap_or(true);
+    let can_write = !is_closed && file.bytes_available_write()?.map(|s| s > 0).unwrap_or(true);
+
+    tracing::trace!(
+        "poll_evt can_read={} can_write={} is_closed={}",
+        can_read,
+        can_write,
+        is_closed
+    );
+
+    let mut builder = PollEventBuilder::new();
+    for event in iterate_poll_events(events) {
+        match event {
+            PollEvent::PollIn if can_read => {
+                builder = builder.add(PollEvent::PollIn);
             }
+            PollEvent::PollOut if can_write => {
+                builder = builder.add(PollEvent::PollOut);
+            }
+            PollEvent::PollHangUp if is_closed => {
+                builder = builder.add(PollEvent::PollHangUp);
+            }
+            PollEvent::PollInvalid if is_closed => {
+                builder = builder.add(PollEvent::PollInvalid);
+            }
+            PollEvent::PollError if is_closed => {
+                builder = builder.add(PollEvent::PollError);
+            }
+            _ => {}
         }
-        let revents = builder.build();
-        if revents != 0 {
-            ret += 1;
-        }
-        seen_events[n] = revents;
-    }
-
-    if ret == 0 && timeout > Duration::ZERO {
-        return Err(FsError::WouldBlock);
     }
-
-    Ok(ret)
+    Ok(builder.build())
 }
 
 pub trait WasiPath {}
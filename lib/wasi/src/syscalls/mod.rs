@@ -22,13 +22,18 @@ pub mod wasix32;
 pub mod wasix64;
 
 use self::types::*;
-use crate::state::{bus_error_into_wasi_err, wasi_error_into_bus_err, InodeHttpSocketType};
-use crate::utils::map_io_err;
+use crate::signal::default_signal_action;
+use crate::state::{
+    bus_error_into_wasi_err, bus_format_into_wasi_format, wasi_error_into_bus_err,
+    wasi_format_into_bus_format, BusPollEvent, InodeHttpSocketType, WasiBusCall,
+    STDIN_DEFAULT_RIGHTS, STDOUT_DEFAULT_RIGHTS,
+};
+use crate::utils::{map_io_err, noop_waker};
 use crate::WasiBusProcessId;
 use crate::{
     mem_error_to_wasi,
     state::{
-        self, fs_error_into_wasi_err, iterate_poll_events, net_error_into_wasi_err, poll,
+        self, fs_error_into_wasi_err, iterate_poll_events, net_error_into_wasi_err, poll_file,
         virtual_file_type_to_wasi_file_type, Fd, Inode, InodeSocket, InodeSocketKind, InodeVal,
         Kind, PollEvent, PollEventBuilder, WasiPipe, WasiState, MAX_SYMLINKS,
     },
@@ -36,18 +41,21 @@ use crate::{
 };
 use bytes::Bytes;
 use std::borrow::{Borrow, Cow};
+use std::collections::VecDeque;
 use std::convert::{Infallible, TryInto};
 use std::io::{self, Read, Seek, Write};
 use std::mem::transmute;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
 use std::sync::atomic::AtomicU64;
 use std::sync::{atomic::Ordering, Mutex};
 use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll};
 use std::time::Duration;
 use tracing::{debug, error, trace, warn};
 use wasmer::{Memory, Memory32, Memory64, MemorySize, RuntimeError, Value, WasmPtr, WasmSlice};
-use wasmer_vbus::{FileDescriptor, StdioMode};
+use wasmer_vbus::{BusInvocationEvent, FileDescriptor, StdioMode};
 use wasmer_vfs::{FsError, VirtualFile};
 use wasmer_vnet::{SocketHttpRequest, StreamSecurity};
 
@@ -94,6 +102,18 @@ fn write_bytes_inner<T: Write, M: MemorySize>(
     Ok(bytes_written)
 }
 
+/// Returns the total length of the buffers in `iovs_arr`.
+fn iovs_total_len<M: MemorySize>(
+    iovs_arr: WasmSlice<__wasi_ciovec_t<M>>,
+) -> Result<u64, __wasi_errno_t> {
+    let mut len = 0u64;
+    for iov in iovs_arr.iter() {
+        let iov_inner = iov.read().map_err(mem_error_to_wasi)?;
+        len = len.saturating_add(from_offset::<M>(iov_inner.buf_len)? as u64);
+    }
+    Ok(len)
+}
+
 pub(crate) fn write_bytes<T: Write, M: MemorySize>(
     mut write_loc: T,
     memory: &Memory,
@@ -143,7 +163,7 @@ fn __sock_actor<T, F>(
 where
     F: FnOnce(&crate::state::InodeSocket) -> Result<T, __wasi_errno_t>,
 {
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     let fd_entry = state.fs.get_fd(sock)?;
     let ret = {
@@ -175,7 +195,7 @@ fn __sock_actor_mut<T, F>(
 where
     F: FnOnce(&mut crate::state::InodeSocket) -> Result<T, __wasi_errno_t>,
 {
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     let fd_entry = state.fs.get_fd(sock)?;
     let ret = {
@@ -209,7 +229,7 @@ where
         &mut crate::state::InodeSocket,
     ) -> Result<Option<crate::state::InodeSocket>, __wasi_errno_t>,
 {
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     let fd_entry = state.fs.get_fd(sock)?;
     if rights != 0 && !has_rights(fd_entry.rights, rights) {
@@ -290,7 +310,7 @@ pub fn args_get<M: MemorySize>(
     argv_buf: WasmPtr<u8, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::args_get");
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
 
     let result = write_buffer_array(memory, &*state.args, argv, argv_buf);
 
@@ -321,7 +341,7 @@ pub fn args_sizes_get<M: MemorySize>(
     argv_buf_size: WasmPtr<M::Offset, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::args_sizes_get");
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
 
     let argc = argc.deref(memory);
     let argv_buf_size = argv_buf_size.deref(memory);
@@ -411,7 +431,7 @@ pub fn environ_get<M: MemorySize>(
         "wasi::environ_get. Environ: {:?}, environ_buf: {:?}",
         environ, environ_buf
     );
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
     trace!(" -> State envs: {:?}", state.envs);
 
     write_buffer_array(memory, &*state.envs, environ, environ_buf)
@@ -430,7 +450,7 @@ pub fn environ_sizes_get<M: MemorySize>(
     environ_buf_size: WasmPtr<M::Offset, M>,
 ) -> __wasi_errno_t {
     trace!("wasi::environ_sizes_get");
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
 
     let environ_count = environ_count.deref(memory);
     let environ_buf_size = environ_buf_size.deref(memory);
@@ -492,7 +512,7 @@ pub fn fd_allocate(
     len: __wasi_filesize_t,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_allocate");
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     let inode = fd_entry.inode;
 
@@ -500,6 +520,8 @@ pub fn fd_allocate(
         return __WASI_EACCES;
     }
     let new_size = wasi_try!(offset.checked_add(len).ok_or(__WASI_EINVAL));
+    let old_size = inodes.arena[inode].stat.read().unwrap().st_size;
+    wasi_try!(state.fs.check_resize(old_size, new_size));
     {
         let mut guard = inodes.arena[inode].write();
         match guard.deref_mut() {
@@ -521,6 +543,9 @@ pub fn fd_allocate(
         }
     }
     inodes.arena[inode].stat.write().unwrap().st_size = new_size;
+    state
+        .fs
+        .add_bytes_written(new_size.saturating_sub(old_size));
     debug!("New file size: {}", new_size);
 
     __WASI_ESUCCESS
@@ -538,7 +563,7 @@ pub fn fd_allocate(
 ///     If `fd` is invalid or not open
 pub fn fd_close(env: &WasiEnv, fd: __wasi_fd_t) -> __wasi_errno_t {
     debug!("wasi::fd_close: fd={}", fd);
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
 
@@ -554,7 +579,7 @@ pub fn fd_close(env: &WasiEnv, fd: __wasi_fd_t) -> __wasi_errno_t {
 ///     The file descriptor to sync
 pub fn fd_datasync(env: &WasiEnv, fd: __wasi_fd_t) -> __wasi_errno_t {
     debug!("wasi::fd_datasync");
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_DATASYNC) {
         return __WASI_EACCES;
@@ -585,7 +610,7 @@ pub fn fd_fdstat_get<M: MemorySize>(
         fd,
         buf_ptr.offset()
     );
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let stat = wasi_try!(state.fs.fdstat(inodes.deref(), fd));
 
     wasi_try_mem!(buf_ptr.write(memory, stat));
@@ -606,7 +631,7 @@ pub fn fd_fdstat_set_flags(
     flags: __wasi_fdflags_t,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_fdstat_set_flags");
-    let (_, state) = env.get_memory_and_wasi_state(0);
+    let (_, state) = env.get_memory_and_wasi_state();
     let mut fd_map = state.fs.fd_map.write().unwrap();
     let fd_entry = wasi_try!(fd_map.get_mut(&fd).ok_or(__WASI_EBADF));
 
@@ -634,7 +659,7 @@ pub fn fd_fdstat_set_rights(
     fs_rights_inheriting: __wasi_rights_t,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_fdstat_set_rights");
-    let (_, state) = env.get_memory_and_wasi_state(0);
+    let (_, state) = env.get_memory_and_wasi_state();
     let mut fd_map = state.fs.fd_map.write().unwrap();
     let fd_entry = wasi_try!(fd_map.get_mut(&fd).ok_or(__WASI_EBADF));
 
@@ -665,7 +690,7 @@ pub fn fd_filestat_get<M: MemorySize>(
     buf: WasmPtr<__wasi_filestat_t, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_filestat_get");
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_FILESTAT_GET) {
         return __WASI_EACCES;
@@ -691,13 +716,15 @@ pub fn fd_filestat_set_size(
     st_size: __wasi_filesize_t,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_filestat_set_size");
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     let inode = fd_entry.inode;
 
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_FILESTAT_SET_SIZE) {
         return __WASI_EACCES;
     }
+    let old_size = inodes.arena[inode].stat.read().unwrap().st_size;
+    wasi_try!(state.fs.check_resize(old_size, st_size));
 
     {
         let mut guard = inodes.arena[inode].write();
@@ -720,6 +747,7 @@ pub fn fd_filestat_set_size(
         }
     }
     inodes.arena[inode].stat.write().unwrap().st_size = st_size;
+    state.fs.add_bytes_written(st_size.saturating_sub(old_size));
 
     __WASI_ESUCCESS
 }
@@ -741,7 +769,7 @@ pub fn fd_filestat_set_times(
     fst_flags: __wasi_fstflags_t,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_filestat_set_times");
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
 
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_FILESTAT_SET_TIMES) {
@@ -803,7 +831,7 @@ pub fn fd_pread<M: MemorySize>(
     nread: WasmPtr<M::Offset, M>,
 ) -> Result<__wasi_errno_t, WasiError> {
     trace!("wasi::fd_pread: fd={}, offset={}", fd, offset);
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     let iovs = wasi_try_mem_ok!(iovs.slice(memory, iovs_len));
     let nread_ref = nread.deref(memory);
@@ -859,7 +887,7 @@ pub fn fd_pread<M: MemorySize>(
                 }
                 Kind::EventNotifications { .. } => return Ok(__WASI_EINVAL),
                 Kind::Dir { .. } | Kind::Root { .. } => return Ok(__WASI_EISDIR),
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_pread"),
+                Kind::Symlink { .. } => return Ok(__WASI_EBADF),
                 Kind::Buffer { buffer } => {
                     wasi_try_ok!(read_bytes(&buffer[(offset as usize)..], memory, iovs), env)
                 }
@@ -887,7 +915,7 @@ pub fn fd_prestat_get<M: MemorySize>(
     buf: WasmPtr<__wasi_prestat_t, M>,
 ) -> __wasi_errno_t {
     trace!("wasi::fd_prestat_get: fd={}", fd);
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     wasi_try_mem!(buf.write(memory, wasi_try!(state.fs.prestat_fd(inodes.deref(), fd))));
 
@@ -905,7 +933,7 @@ pub fn fd_prestat_dir_name<M: MemorySize>(
         fd,
         path_len
     );
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let path_chars = wasi_try_mem!(path.slice(memory, path_len));
 
     let real_inode = wasi_try!(state.fs.get_fd_inode(fd));
@@ -965,7 +993,7 @@ pub fn fd_pwrite<M: MemorySize>(
 ) -> Result<__wasi_errno_t, WasiError> {
     trace!("wasi::fd_pwrite");
     // TODO: refactor, this is just copied from `fd_write`...
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let iovs_arr = wasi_try_mem_ok!(iovs.slice(memory, iovs_len));
     let nwritten_ref = nwritten.deref(memory);
 
@@ -1012,13 +1040,23 @@ pub fn fd_pwrite<M: MemorySize>(
             match guard.deref_mut() {
                 Kind::File { handle, .. } => {
                     if let Some(handle) = handle {
+                        let start = if fd_entry.flags & __WASI_FDFLAG_APPEND != 0 {
+                            handle.size()
+                        } else {
+                            offset
+                        };
+                        wasi_try_ok!(state
+                            .fs
+                            .check_write(start, wasi_try_ok!(iovs_total_len(iovs_arr))));
                         wasi_try_ok!(
                             handle
                                 .seek(std::io::SeekFrom::Start(offset as u64))
                                 .map_err(map_io_err),
                             env
                         );
-                        wasi_try_ok!(write_bytes(handle, memory, iovs_arr), env)
+                        let written = wasi_try_ok!(write_bytes(handle, memory, iovs_arr), env);
+                        state.fs.add_bytes_written(written as u64);
+                        written
                     } else {
                         return Ok(__WASI_EINVAL);
                     }
@@ -1034,7 +1072,7 @@ pub fn fd_pwrite<M: MemorySize>(
                     return Ok(__WASI_EISDIR);
                 }
                 Kind::EventNotifications { .. } => return Ok(__WASI_EINVAL),
-                Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_pwrite"),
+                Kind::Symlink { .. } => return Ok(__WASI_EBADF),
                 Kind::Buffer { buffer } => {
                     wasi_try_ok!(
                         write_bytes(&mut buffer[(offset as usize)..], memory, iovs_arr),
@@ -1073,7 +1111,7 @@ pub fn fd_read<M: MemorySize>(
     nread: WasmPtr<M::Offset, M>,
 ) -> Result<__wasi_errno_t, WasiError> {
     trace!("wasi::fd_read: fd={}", fd);
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     let iovs_arr = wasi_try_mem_ok!(iovs.slice(memory, iovs_len));
     let nread_ref = nread.deref(memory);
@@ -1186,7 +1224,7 @@ pub fn fd_read<M: MemorySize>(
                         }
                         ret
                     }
-                    Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_read"),
+                    Kind::Symlink { .. } => return Ok(__WASI_EBADF),
                     Kind::Buffer { buffer } => {
                         wasi_try_ok!(read_bytes(&buffer[offset..], memory, iovs_arr), env)
                     }
@@ -1232,7 +1270,7 @@ pub fn fd_readdir<M: MemorySize>(
     bufused: WasmPtr<M::Offset, M>,
 ) -> __wasi_errno_t {
     trace!("wasi::fd_readdir");
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     // TODO: figure out how this is supposed to work;
     // is it supposed to pack the buffer full every time until it can't? or do one at a time?
 
@@ -1353,7 +1391,7 @@ pub fn fd_readdir<M: MemorySize>(
 ///     Location to copy file descriptor to
 pub fn fd_renumber(env: &WasiEnv, from: __wasi_fd_t, to: __wasi_fd_t) -> __wasi_errno_t {
     debug!("wasi::fd_renumber: from={}, to={}", from, to);
-    let (_, state) = env.get_memory_and_wasi_state(0);
+    let (_, state) = env.get_memory_and_wasi_state();
 
     let mut fd_map = state.fs.fd_map.write().unwrap();
     let fd_entry = wasi_try!(fd_map.get_mut(&from).ok_or(__WASI_EBADF));
@@ -1384,7 +1422,7 @@ pub fn fd_dup<M: MemorySize>(
 ) -> __wasi_errno_t {
     debug!("wasi::fd_dup");
 
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
     let fd = wasi_try!(state.fs.clone_fd(fd));
 
     wasi_try_mem!(ret_fd.write(memory, fd));
@@ -1402,7 +1440,7 @@ pub fn fd_event<M: MemorySize>(
 ) -> __wasi_errno_t {
     debug!("wasi::fd_event");
 
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let kind = Kind::EventNotifications {
         counter: Arc::new(AtomicU64::new(initial_val)),
@@ -1410,12 +1448,12 @@ pub fn fd_event<M: MemorySize>(
         wakers: Default::default(),
     };
 
-    let inode = state.fs.create_inode_with_default_stat(
+    let inode = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind,
         false,
         "event".to_string(),
-    );
+    ));
     let rights = __WASI_RIGHT_FD_READ | __WASI_RIGHT_FD_WRITE | __WASI_RIGHT_POLL_FD_READWRITE;
     let fd = wasi_try!(state.fs.create_fd(rights, rights, 0, 0, inode));
 
@@ -1444,7 +1482,7 @@ pub fn fd_seek<M: MemorySize>(
     newoffset: WasmPtr<__wasi_filesize_t, M>,
 ) -> Result<__wasi_errno_t, WasiError> {
     trace!("wasi::fd_seek: fd={}, offset={}", fd, offset);
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let new_offset_ref = newoffset.deref(memory);
     let fd_entry = wasi_try_ok!(state.fs.get_fd(fd));
 
@@ -1478,11 +1516,9 @@ pub fn fd_seek<M: MemorySize>(
                         return Ok(__WASI_EINVAL);
                     }
                 }
-                Kind::Symlink { .. } => {
-                    unimplemented!("wasi::fd_seek not implemented for symlinks")
-                }
                 Kind::Dir { .. }
                 | Kind::Root { .. }
+                | Kind::Symlink { .. }
                 | Kind::Socket { .. }
                 | Kind::Pipe { .. }
                 | Kind::EventNotifications { .. } => {
@@ -1522,7 +1558,7 @@ pub fn fd_seek<M: MemorySize>(
 pub fn fd_sync(env: &WasiEnv, fd: __wasi_fd_t) -> __wasi_errno_t {
     debug!("wasi::fd_sync");
     debug!("=> fd={}", fd);
-    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (_, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_SYNC) {
         return __WASI_EACCES;
@@ -1566,7 +1602,7 @@ pub fn fd_tell<M: MemorySize>(
     offset: WasmPtr<__wasi_filesize_t, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::fd_tell");
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
     let offset_ref = offset.deref(memory);
 
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
@@ -1602,7 +1638,7 @@ pub fn fd_write<M: MemorySize>(
     nwritten: WasmPtr<M::Offset, M>,
 ) -> Result<__wasi_errno_t, WasiError> {
     trace!("wasi::fd_write: fd={}", fd);
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
     let iovs_arr = wasi_try_mem_ok!(iovs.slice(memory, iovs_len));
     let nwritten_ref = nwritten.deref(memory);
 
@@ -1649,13 +1685,23 @@ pub fn fd_write<M: MemorySize>(
                 match guard.deref_mut() {
                     Kind::File { handle, .. } => {
                         if let Some(handle) = handle {
+                            let start = if fd_entry.flags & __WASI_FDFLAG_APPEND != 0 {
+                                handle.size()
+                            } else {
+                                offset as u64
+                            };
+                            wasi_try_ok!(state
+                                .fs
+                                .check_write(start, wasi_try_ok!(iovs_total_len(iovs_arr))));
                             wasi_try_ok!(
                                 handle
                                     .seek(std::io::SeekFrom::Start(offset as u64))
                                     .map_err(map_io_err),
                                 env
                             );
-                            wasi_try_ok!(write_bytes(handle, memory, iovs_arr), env)
+                            let written = wasi_try_ok!(write_bytes(handle, memory, iovs_arr), env);
+                            state.fs.add_bytes_written(written as u64);
+                            written
                         } else {
                             return Ok(__WASI_EINVAL);
                         }
@@ -1692,7 +1738,7 @@ pub fn fd_write<M: MemorySize>(
 
                         written
                     }
-                    Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_write"),
+                    Kind::Symlink { .. } => return Ok(__WASI_EBADF),
                     Kind::Buffer { buffer } => {
                         wasi_try_ok!(write_bytes(&mut buffer[offset..], memory, iovs_arr), env)
                     }
@@ -1732,22 +1778,22 @@ pub fn fd_pipe<M: MemorySize>(
 ) -> __wasi_errno_t {
     trace!("wasi::fd_pipe");
 
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let (pipe1, pipe2) = WasiPipe::new();
 
-    let inode1 = state.fs.create_inode_with_default_stat(
+    let inode1 = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         Kind::Pipe { pipe: pipe1 },
         false,
         "pipe".to_string(),
-    );
-    let inode2 = state.fs.create_inode_with_default_stat(
+    ));
+    let inode2 = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         Kind::Pipe { pipe: pipe2 },
         false,
         "pipe".to_string(),
-    );
+    ));
 
     let rights = super::state::all_socket_rights();
     let fd1 = wasi_try!(state.fs.create_fd(rights, rights, 0, 0, inode1));
@@ -1779,7 +1825,7 @@ pub fn path_create_directory<M: MemorySize>(
     path_len: M::Offset,
 ) -> __wasi_errno_t {
     debug!("wasi::path_create_directory");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let working_dir = wasi_try!(state.fs.get_fd(fd));
     {
@@ -1908,7 +1954,7 @@ pub fn path_filestat_get<M: MemorySize>(
     buf: WasmPtr<__wasi_filestat_t, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::path_filestat_get (fd={})", fd);
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let path_string = unsafe { get_input_str!(memory, path, path_len) };
 
@@ -1997,7 +2043,7 @@ pub fn path_filestat_set_times<M: MemorySize>(
     fst_flags: __wasi_fstflags_t,
 ) -> __wasi_errno_t {
     debug!("wasi::path_filestat_set_times");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     let fd_inode = fd_entry.inode;
     if !has_rights(fd_entry.rights, __WASI_RIGHT_PATH_FILESTAT_SET_TIMES) {
@@ -2077,7 +2123,7 @@ pub fn path_link<M: MemorySize>(
     if old_flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0 {
         debug!("  - will follow symlinks when opening path");
     }
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
     let old_path_str = unsafe { get_input_str!(memory, old_path, old_path_len) };
     let new_path_str = unsafe { get_input_str!(memory, new_path, new_path_len) };
     let source_fd = wasi_try!(state.fs.get_fd(old_fd));
@@ -2174,7 +2220,7 @@ pub fn path_open<M: MemorySize>(
     if dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0 {
         debug!("  - will follow symlinks when opening path");
     }
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
     /* TODO: find actual upper bound on name size (also this is a path, not a name :think-fish:) */
     let path_len64: u64 = path_len.into();
     if path_len64 > 1024u64 * 1024u64 {
@@ -2275,14 +2321,10 @@ pub fn path_open<M: MemorySize>(
             | Kind::Socket { .. }
             | Kind::Pipe { .. }
             | Kind::EventNotifications { .. } => {}
-            Kind::Symlink {
-                base_po_dir,
-                path_to_symlink,
-                relative_path,
-            } => {
-                // I think this should return an error (because symlinks should be resolved away by the path traversal)
-                // TODO: investigate this
-                unimplemented!("SYMLINKS IN PATH_OPEN");
+            Kind::Symlink { .. } => {
+                // symlinks are resolved away by the path traversal unless
+                // `__WASI_LOOKUP_SYMLINK_FOLLOW` is missing, like `O_NOFOLLOW`
+                return __WASI_ELOOP;
             }
         }
         inode
@@ -2410,7 +2452,7 @@ pub fn path_readlink<M: MemorySize>(
     buf_used: WasmPtr<M::Offset, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::path_readlink");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let base_dir = wasi_try!(state.fs.get_fd(dir_fd));
     if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_READLINK) {
@@ -2457,7 +2499,7 @@ pub fn path_remove_directory<M: MemorySize>(
 ) -> __wasi_errno_t {
     // TODO check if fd is a dir, ensure it's within sandbox, etc.
     debug!("wasi::path_remove_directory");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let base_dir = wasi_try!(state.fs.get_fd(fd));
     let path_str = unsafe { get_input_str!(memory, path, path_len) };
@@ -2546,7 +2588,7 @@ pub fn path_rename<M: MemorySize>(
         "wasi::path_rename: old_fd = {}, new_fd = {}",
         old_fd, new_fd
     );
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
     let source_str = unsafe { get_input_str!(memory, old_path, old_path_len) };
     let source_path = std::path::Path::new(&source_str);
     let target_str = unsafe { get_input_str!(memory, new_path, new_path_len) };
@@ -2705,7 +2747,7 @@ pub fn path_symlink<M: MemorySize>(
     new_path_len: M::Offset,
 ) -> __wasi_errno_t {
     debug!("wasi::path_symlink");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
     let old_path_str = unsafe { get_input_str!(memory, old_path, old_path_len) };
     let new_path_str = unsafe { get_input_str!(memory, new_path, new_path_len) };
     let base_fd = wasi_try!(state.fs.get_fd(fd));
@@ -2713,16 +2755,12 @@ pub fn path_symlink<M: MemorySize>(
         return __WASI_EACCES;
     }
 
-    // get the depth of the parent + 1 (UNDER INVESTIGATION HMMMMMMMM THINK FISH ^ THINK FISH)
+    // the symlink is stored as is in the backing file system, absolute paths
+    // can't be expressed there without escaping the sandbox
     let old_path_path = std::path::Path::new(&old_path_str);
-    let (source_inode, _) =
-        wasi_try!(state
-            .fs
-            .get_parent_inode_at_path(inodes.deref_mut(), fd, old_path_path, true));
-    let depth = wasi_try!(state
-        .fs
-        .path_depth_from_fd(inodes.deref(), fd, source_inode))
-        - 1;
+    if old_path_path.is_absolute() {
+        return __WASI_EPERM;
+    }
 
     let new_path_path = std::path::Path::new(&new_path_str);
     let (target_parent_inode, entry_name) =
@@ -2730,14 +2768,16 @@ pub fn path_symlink<M: MemorySize>(
             .fs
             .get_parent_inode_at_path(inodes.deref_mut(), fd, new_path_path, true));
 
-    // short circuit if anything is wrong, before we create an inode
-    {
+    let host_path = {
         let guard = inodes.arena[target_parent_inode].read();
         match guard.deref() {
-            Kind::Dir { entries, .. } => {
+            Kind::Dir { entries, path, .. } => {
                 if entries.contains_key(&entry_name) {
                     return __WASI_EEXIST;
                 }
+                let mut host_path = path.clone();
+                host_path.push(&entry_name);
+                host_path
             }
             Kind::Root { .. } => return __WASI_ENOTCAPABLE,
             Kind::Socket { .. } | Kind::Pipe { .. } | Kind::EventNotifications { .. } => {
@@ -2747,41 +2787,16 @@ pub fn path_symlink<M: MemorySize>(
                 unreachable!("get_parent_inode_at_path returned something other than a Dir or Root")
             }
         }
-    }
-
-    let mut source_path = std::path::Path::new(&old_path_str);
-    let mut relative_path = std::path::PathBuf::new();
-    for _ in 0..depth {
-        relative_path.push("..");
-    }
-    relative_path.push(source_path);
+    };
     debug!(
         "Symlinking {} to {}",
-        new_path_str,
-        relative_path.to_string_lossy()
-    );
-
-    let kind = Kind::Symlink {
-        base_po_dir: fd,
-        path_to_symlink: std::path::PathBuf::from(new_path_str),
-        relative_path,
-    };
-    let new_inode = state.fs.create_inode_with_default_stat(
-        inodes.deref_mut(),
-        kind,
-        false,
-        entry_name.clone(),
+        host_path.to_string_lossy(),
+        old_path_str
     );
 
-    {
-        let mut guard = inodes.arena[target_parent_inode].write();
-        if let Kind::Dir {
-            ref mut entries, ..
-        } = guard.deref_mut()
-        {
-            entries.insert(entry_name, new_inode);
-        }
-    }
+    // symlinks are not cached in the directory entries, they are loaded from
+    // the backing file system every time they are traversed
+    wasi_try!(state.fs_symlink(old_path_path, &host_path));
 
     __WASI_ESUCCESS
 }
@@ -2802,7 +2817,7 @@ pub fn path_unlink_file<M: MemorySize>(
     path_len: M::Offset,
 ) -> __wasi_errno_t {
     debug!("wasi::path_unlink_file");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let base_dir = wasi_try!(state.fs.get_fd(fd));
     if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_UNLINK_FILE) {
@@ -2814,6 +2829,30 @@ pub fn path_unlink_file<M: MemorySize>(
     let inode = wasi_try!(state
         .fs
         .get_inode_at_path(inodes.deref_mut(), fd, &path_str, false));
+
+    // symlinks are not cached in the directory entries, only the backing
+    // file system knows about them
+    let symlink_host_path = {
+        let guard = inodes.arena[inode].read();
+        match guard.deref() {
+            Kind::Symlink {
+                base_po_dir,
+                path_to_symlink,
+                ..
+            } => Some(wasi_try!(state.fs.symlink_host_path(
+                inodes.deref(),
+                *base_po_dir,
+                path_to_symlink
+            ))),
+            _ => None,
+        }
+    };
+    if let Some(host_path) = symlink_host_path {
+        wasi_try!(state.fs_remove_file(host_path));
+        unsafe { state.fs.remove_inode(inodes.deref_mut(), inode) };
+        return __WASI_ESUCCESS;
+    }
+
     let (parent_inode, childs_name) = wasi_try!(state.fs.get_parent_inode_at_path(
         inodes.deref_mut(),
         fd,
@@ -2861,9 +2900,6 @@ pub fn path_unlink_file<M: MemorySize>(
                     }
                 }
                 Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
-                Kind::Symlink { .. } => {
-                    // TODO: actually delete real symlinks and do nothing for virtual symlinks
-                }
                 _ => unimplemented!("wasi::path_unlink_file for Buffer"),
             }
         }
@@ -2893,6 +2929,97 @@ pub fn path_unlink_file<M: MemorySize>(
     __WASI_ESUCCESS
 }
 
+/// Interval at which `poll_oneoff` checks its subscriptions again while none
+/// of them has triggered yet
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A subscription of `poll_oneoff` that was validated up front
+enum PollSubscription {
+    /// Triggers once the monotonic clock reaches the deadline
+    Clock { deadline: u64 },
+    /// Triggers once the file descriptor is ready for reading or writing
+    Fd { fd: __wasi_fd_t, write: bool },
+    /// Triggers immediately with an error
+    Failed(__wasi_errno_t),
+}
+
+/// Checks if a read or write on `fd` could make progress without blocking,
+/// returning `None` while it would still block
+fn poll_fd(
+    state: &WasiState,
+    inodes: &crate::WasiInodes,
+    fd: __wasi_fd_t,
+    write: bool,
+) -> Result<Option<__wasi_event_fd_readwrite_t>, __wasi_errno_t> {
+    let fd_entry = state.fs.get_fd(fd)?;
+    let rights = match write {
+        true => __WASI_RIGHT_FD_WRITE | __WASI_RIGHT_POLL_FD_READWRITE,
+        false => __WASI_RIGHT_FD_READ | __WASI_RIGHT_POLL_FD_READWRITE,
+    };
+    if fd > __WASI_STDERR_FILENO && !has_rights(fd_entry.rights, rights) {
+        return Err(__WASI_EACCES);
+    }
+    let ready = |nbytes: usize, hangup: bool| {
+        Some(__wasi_event_fd_readwrite_t {
+            nbytes: nbytes as u64,
+            flags: match hangup {
+                true => __WASI_EVENT_FD_READWRITE_HANGUP,
+                false => 0,
+            },
+        })
+    };
+    // Streams report a read of zero bytes once the other side went away
+    let stream_ready = |ret: Result<usize, __wasi_errno_t>| match ret {
+        Ok(0) if !write => Ok(ready(0, true)),
+        Ok(nbytes) => Ok(ready(nbytes, false)),
+        Err(__WASI_EAGAIN) => Ok(None),
+        Err(err) => Err(err),
+    };
+
+    let mut guard = inodes.arena[fd_entry.inode].write();
+    match guard.deref_mut() {
+        Kind::File {
+            handle: Some(file), ..
+        } => {
+            let event = match write {
+                true => PollEvent::PollOut,
+                false => PollEvent::PollIn,
+            };
+            let events = PollEventBuilder::new()
+                .add(event)
+                .add(PollEvent::PollHangUp)
+                .build();
+            let seen = poll_file(file.as_ref(), events).map_err(fs_error_into_wasi_err)?;
+            if seen == 0 {
+                return Ok(None);
+            }
+            let hangup = iterate_poll_events(seen).any(|e| matches!(e, PollEvent::PollHangUp));
+            let nbytes = match write {
+                true => file.bytes_available_write(),
+                false => file.bytes_available_read(),
+            }
+            .map_err(fs_error_into_wasi_err)?;
+            Ok(ready(nbytes.unwrap_or(0), hangup))
+        }
+        Kind::File { handle: None, .. } => Err(__WASI_EBADF),
+        Kind::Socket { socket } => stream_ready(match write {
+            true => socket.poll_write_ready(),
+            false => socket.poll_read_ready(),
+        }),
+        Kind::Pipe { .. } if write => Ok(ready(0, false)),
+        Kind::Pipe { pipe } => stream_ready(pipe.poll_read_ready()),
+        Kind::EventNotifications { .. } if write => Ok(ready(0, false)),
+        Kind::EventNotifications { counter, .. } => match counter.load(Ordering::Acquire) {
+            0 => Ok(None),
+            _ => Ok(ready(std::mem::size_of::<u64>(), false)),
+        },
+        Kind::Buffer { .. } if write => Ok(ready(0, false)),
+        Kind::Buffer { buffer } => Ok(ready(buffer.len(), false)),
+        Kind::Dir { .. } | Kind::Root { .. } => Err(__WASI_EISDIR),
+        Kind::Symlink { .. } => Err(__WASI_EBADF),
+    }
+}
+
 /// ### `poll_oneoff()`
 /// Concurrently poll for a set of events
 /// Inputs:
@@ -2914,235 +3041,95 @@ pub fn poll_oneoff<M: MemorySize>(
 ) -> Result<__wasi_errno_t, WasiError> {
     trace!("wasi::poll_oneoff");
     trace!("  => nsubscriptions = {}", nsubscriptions);
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
 
+    if nsubscriptions == M::Offset::from(0u32) {
+        return Ok(__WASI_EINVAL);
+    }
     let subscription_array = wasi_try_mem_ok!(in_.slice(memory, nsubscriptions));
     let event_array = wasi_try_mem_ok!(out_.slice(memory, nsubscriptions));
-    let mut events_seen: u32 = 0;
     let out_ptr = nevents.deref(memory);
 
-    let mut fd_guards = vec![];
-    let mut clock_subs = vec![];
-    let mut in_events = vec![];
-    let mut time_to_sleep = Duration::from_millis(5);
-
+    // Clocks are turned into deadlines on the monotonic clock so that
+    // relative and absolute timeouts on any clock can be mixed freely
+    let start = wasi_try_ok!(platform_clock_time_get(__WASI_CLOCK_MONOTONIC, 1)) as u64;
+    let mut subscriptions = Vec::new();
     for sub in subscription_array.iter() {
-        let s: WasiSubscription = wasi_try_ok!(wasi_try_mem_ok!(sub.read()).try_into());
-        let mut peb = PollEventBuilder::new();
-
-        let fd = match s.event_type {
+        let sub = wasi_try_mem_ok!(sub.read());
+        let type_ = sub.type_;
+        let s: WasiSubscription = wasi_try_ok!(sub.try_into());
+        let subscription = match s.event_type {
             EventType::Read(__wasi_subscription_fs_readwrite_t { fd }) => {
-                match fd {
-                    __WASI_STDIN_FILENO | __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => (),
-                    _ => {
-                        let fd_entry = wasi_try_ok!(state.fs.get_fd(fd), env);
-                        if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_READ) {
-                            return Ok(__WASI_EACCES);
-                        }
-                    }
-                }
-                in_events.push(peb.add(PollEvent::PollIn).build());
-                Some(fd)
+                PollSubscription::Fd { fd, write: false }
             }
             EventType::Write(__wasi_subscription_fs_readwrite_t { fd }) => {
-                match fd {
-                    __WASI_STDIN_FILENO | __WASI_STDOUT_FILENO | __WASI_STDERR_FILENO => (),
-                    _ => {
-                        let fd_entry = wasi_try_ok!(state.fs.get_fd(fd), env);
-                        if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_WRITE) {
-                            return Ok(__WASI_EACCES);
-                        }
-                    }
-                }
-                in_events.push(peb.add(PollEvent::PollOut).build());
-                Some(fd)
+                PollSubscription::Fd { fd, write: true }
             }
-            EventType::Clock(clock_info) => {
-                if clock_info.clock_id == __WASI_CLOCK_REALTIME
-                    || clock_info.clock_id == __WASI_CLOCK_MONOTONIC
-                {
-                    // this is a hack
-                    // TODO: do this properly
-                    time_to_sleep = Duration::from_nanos(clock_info.timeout);
-                    clock_subs.push((clock_info, s.user_data));
-                    None
-                } else {
-                    unimplemented!("Polling not implemented for clocks yet");
+            EventType::Clock(clock_info) => match platform_clock_time_get(clock_info.clock_id, 1) {
+                Ok(now) => {
+                    let timeout = match clock_info.flags & __WASI_SUBSCRIPTION_CLOCK_ABSTIME {
+                        0 => clock_info.timeout,
+                        _ => clock_info.timeout.saturating_sub(now as u64),
+                    };
+                    PollSubscription::Clock {
+                        deadline: start.saturating_add(timeout),
+                    }
                 }
-            }
+                Err(err) => PollSubscription::Failed(err),
+            },
         };
+        subscriptions.push((s.user_data, type_, subscription));
+    }
 
-        if let Some(fd) = fd {
-            let wasi_file_ref = match fd {
-                __WASI_STDERR_FILENO => {
-                    wasi_try_ok!(
-                        inodes
-                            .stderr(&state.fs.fd_map)
-                            .map_err(fs_error_into_wasi_err),
-                        env
-                    )
-                }
-                __WASI_STDIN_FILENO => {
-                    wasi_try_ok!(
-                        inodes
-                            .stdin(&state.fs.fd_map)
-                            .map_err(fs_error_into_wasi_err),
-                        env
-                    )
-                }
-                __WASI_STDOUT_FILENO => {
-                    wasi_try_ok!(
-                        inodes
-                            .stdout(&state.fs.fd_map)
-                            .map_err(fs_error_into_wasi_err),
-                        env
-                    )
-                }
-                _ => {
-                    let fd_entry = wasi_try_ok!(state.fs.get_fd(fd), env);
-                    let inode = fd_entry.inode;
-                    if !has_rights(fd_entry.rights, __WASI_RIGHT_POLL_FD_READWRITE) {
-                        return Ok(__WASI_EACCES);
+    let mut events_seen: u32 = 0;
+    loop {
+        let now = wasi_try_ok!(platform_clock_time_get(__WASI_CLOCK_MONOTONIC, 1)) as u64;
+        let mut next_deadline = u64::MAX;
+        {
+            let inodes = state.inodes.read().unwrap();
+            for (userdata, type_, subscription) in subscriptions.iter() {
+                let mut fd_readwrite = __wasi_event_fd_readwrite_t {
+                    nbytes: 0,
+                    flags: 0,
+                };
+                let error = match subscription {
+                    PollSubscription::Clock { deadline } if *deadline <= now => __WASI_ESUCCESS,
+                    PollSubscription::Clock { deadline } => {
+                        next_deadline = next_deadline.min(*deadline);
+                        continue;
                     }
-
-                    {
-                        let guard = inodes.arena[inode].read();
-                        match guard.deref() {
-                            Kind::File { handle, .. } => {
-                                if let Some(h) = handle {
-                                    crate::state::InodeValFileReadGuard { guard }
-                                } else {
-                                    return Ok(__WASI_EBADF);
-                                }
-                            }
-                            Kind::Socket { .. }
-                            | Kind::Pipe { .. }
-                            | Kind::EventNotifications { .. } => {
-                                return Ok(__WASI_EBADF);
-                            }
-                            Kind::Dir { .. }
-                            | Kind::Root { .. }
-                            | Kind::Buffer { .. }
-                            | Kind::Symlink { .. } => {
-                                unimplemented!("polling read on non-files not yet supported")
+                    PollSubscription::Fd { fd, write } => {
+                        match poll_fd(state, inodes.deref(), *fd, *write) {
+                            Ok(Some(ready)) => {
+                                fd_readwrite = ready;
+                                __WASI_ESUCCESS
                             }
+                            Ok(None) => continue,
+                            Err(err) => err,
                         }
                     }
-                }
-            };
-            fd_guards.push(wasi_file_ref);
-        }
-    }
-
-    let fds = {
-        let mut f = vec![];
-        for fd in fd_guards.iter() {
-            f.push(wasi_try_ok!(fd.as_ref().ok_or(__WASI_EBADF)).deref());
-        }
-        f
-    };
-
-    let mut seen_events = vec![Default::default(); in_events.len()];
-
-    let start = platform_clock_time_get(__WASI_CLOCK_MONOTONIC, 1_000_000).unwrap() as u128;
-    let mut triggered = 0;
-    while triggered == 0 {
-        let now = platform_clock_time_get(__WASI_CLOCK_MONOTONIC, 1_000_000).unwrap() as u128;
-        let delta = match now.checked_sub(start) {
-            Some(a) => Duration::from_nanos(a as u64),
-            None => Duration::ZERO,
-        };
-        match poll(
-            fds.as_slice(),
-            in_events.as_slice(),
-            seen_events.as_mut_slice(),
-            Duration::from_millis(1),
-        ) {
-            Ok(0) => {
-                env.yield_now()?;
-            }
-            Ok(a) => {
-                triggered = a;
-            }
-            Err(FsError::WouldBlock) => {
-                env.sleep(Duration::from_millis(1))?;
-            }
-            Err(err) => {
-                return Ok(fs_error_into_wasi_err(err));
+                    PollSubscription::Failed(err) => *err,
+                };
+                let event = __wasi_event_t {
+                    userdata: *userdata,
+                    error,
+                    type_: *type_,
+                    u: __wasi_event_u { fd_readwrite },
+                };
+                wasi_try_mem_ok!(event_array.index(events_seen as u64).write(event));
+                events_seen += 1;
             }
-        };
-        if delta > time_to_sleep {
+        }
+        if events_seen > 0 {
             break;
         }
-    }
 
-    for (i, seen_event) in seen_events.into_iter().enumerate() {
-        let mut flags = 0;
-        let mut error = __WASI_EAGAIN;
-        let mut bytes_available = 0;
-        let event_iter = iterate_poll_events(seen_event);
-        for event in event_iter {
-            match event {
-                PollEvent::PollError => error = __WASI_EIO,
-                PollEvent::PollHangUp => flags = __WASI_EVENT_FD_READWRITE_HANGUP,
-                PollEvent::PollInvalid => error = __WASI_EINVAL,
-                PollEvent::PollIn => {
-                    bytes_available = wasi_try_ok!(
-                        fds[i]
-                            .bytes_available_read()
-                            .map_err(fs_error_into_wasi_err),
-                        env
-                    )
-                    .unwrap_or(0usize);
-                    error = __WASI_ESUCCESS;
-                }
-                PollEvent::PollOut => {
-                    bytes_available = wasi_try_ok!(
-                        fds[i]
-                            .bytes_available_write()
-                            .map_err(fs_error_into_wasi_err),
-                        env
-                    )
-                    .unwrap_or(0usize);
-                    error = __WASI_ESUCCESS;
-                }
-            }
-        }
-        let event = __wasi_event_t {
-            userdata: wasi_try_mem_ok!(subscription_array.index(i as u64).read()).userdata,
-            error,
-            type_: wasi_try_mem_ok!(subscription_array.index(i as u64).read()).type_,
-            u: unsafe {
-                __wasi_event_u {
-                    fd_readwrite: __wasi_event_fd_readwrite_t {
-                        nbytes: bytes_available as u64,
-                        flags,
-                    },
-                }
-            },
-        };
-        wasi_try_mem_ok!(event_array.index(events_seen as u64).write(event));
-        events_seen += 1;
-    }
-    if triggered == 0 {
-        for (clock_info, userdata) in clock_subs {
-            let event = __wasi_event_t {
-                userdata,
-                error: __WASI_ESUCCESS,
-                type_: __WASI_EVENTTYPE_CLOCK,
-                u: unsafe {
-                    __wasi_event_u {
-                        fd_readwrite: __wasi_event_fd_readwrite_t {
-                            nbytes: 0,
-                            flags: 0,
-                        },
-                    }
-                },
-            };
-            wasi_try_mem_ok!(event_array.index(events_seen as u64).write(event));
-            events_seen += 1;
-        }
+        // Nothing is ready yet so give the other threads a chance to run
+        // while waiting for the next clock or for a file descriptor
+        let remaining = Duration::from_nanos(next_deadline.saturating_sub(now));
+        env.sleep(remaining.min(POLL_INTERVAL))?;
     }
+
     let events_seen: M::Offset = wasi_try_ok!(events_seen.try_into().map_err(|_| __WASI_EOVERFLOW));
     wasi_try_mem_ok!(out_ptr.write(events_seen));
     Ok(__WASI_ESUCCESS)
@@ -3166,9 +3153,14 @@ pub fn proc_exit(env: &WasiEnv, code: __wasi_exitcode_t) -> Result<(), WasiError
 /// Inputs:
 /// - `__wasi_signal_t`
 ///   Signal to be raised for this process
-pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
-    debug!("wasi::proc_raise");
-    unimplemented!("wasi::proc_raise")
+pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> Result<__wasi_errno_t, WasiError> {
+    debug!("wasi::proc_raise: sig={}", sig);
+    if default_signal_action(sig).is_none() {
+        return Ok(__WASI_EINVAL);
+    }
+    env.deliver_signal(sig)?;
+
+    Ok(__WASI_ESUCCESS)
 }
 
 /// ### `sched_yield()`
@@ -3305,7 +3297,7 @@ pub fn getcwd<M: MemorySize>(
     path_len: WasmPtr<M::Offset, M>,
 ) -> __wasi_errno_t {
     debug!("wasi::getpwd");
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let (_, cur_dir) = wasi_try!(state
         .fs
@@ -3346,7 +3338,7 @@ pub fn chdir<M: MemorySize>(
 ) -> __wasi_errno_t {
     debug!("wasi::chdir");
 
-    let (memory, state) = env.get_memory_and_wasi_state(0);
+    let (memory, state) = env.get_memory_and_wasi_state();
     let path = unsafe { get_input_str!(memory, path, path_len) };
 
     state.fs.set_current_dir(path.as_str());
@@ -3387,12 +3379,9 @@ pub fn thread_spawn<M: MemorySize>(
     if method.as_str() != "_thread_start" {
         return __WASI_ENOTCAPABLE;
     };
-    let funct = unsafe {
-        if env.thread_start_ref().is_none() {
-            return __WASI_EADDRNOTAVAIL;
-        }
-        env.thread_start_ref_unchecked()
-    };
+    if env.thread_start_ref().is_none() {
+        return __WASI_EADDRNOTAVAIL;
+    }
 
     let reactor = match reactor {
         __WASI_BOOL_FALSE => false,
@@ -3636,10 +3625,46 @@ pub fn process_spawn<M: MemorySize>(
         },
     };
 
+    // Pipes that are not backed by a host file descriptor are opened
+    // as new file descriptors within this process
+    let conv_stdio_file = |file: Option<Box<dyn VirtualFile + Send + Sync + 'static>>,
+                           name: &str,
+                           rights: __wasi_rights_t,
+                           open_flags: u16| {
+        let file = match file {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let (_, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
+        state
+            .fs
+            .create_fd_for_file(inodes.deref_mut(), file, name, rights, open_flags)
+            .map(|fd| Some(FileDescriptor::from(fd)))
+            .map_err(|_| __BUS_EINTERNAL)
+    };
+
     // Convert the stdio
-    let stdin = conv_stdio_fd(process.inst.stdin_fd());
-    let stdout = conv_stdio_fd(process.inst.stdout_fd());
-    let stderr = conv_stdio_fd(process.inst.stderr_fd());
+    let stdin = wasi_try_bus!(conv_stdio_file(
+        process.inst.stdin_file(),
+        "stdin",
+        STDOUT_DEFAULT_RIGHTS,
+        Fd::WRITE
+    ));
+    let stdout = wasi_try_bus!(conv_stdio_file(
+        process.inst.stdout_file(),
+        "stdout",
+        STDIN_DEFAULT_RIGHTS,
+        Fd::READ
+    ));
+    let stderr = wasi_try_bus!(conv_stdio_file(
+        process.inst.stderr_file(),
+        "stderr",
+        STDIN_DEFAULT_RIGHTS,
+        Fd::READ
+    ));
+    let stdin = conv_stdio_fd(stdin.or_else(|| process.inst.stdin_fd()));
+    let stdout = conv_stdio_fd(stdout.or_else(|| process.inst.stdout_fd()));
+    let stderr = conv_stdio_fd(stderr.or_else(|| process.inst.stderr_fd()));
 
     // Add the process to the environment state
     let bid = {
@@ -3796,9 +3821,12 @@ pub fn bus_close(env: &WasiEnv, bid: __wasi_bid_t) -> __bus_errno_t {
     let bid: WasiBusProcessId = bid.into();
 
     let mut guard = env.state.threading.lock().unwrap();
-    guard.processes.remove(&bid);
+    if guard.processes.remove(&bid).is_none() {
+        return __BUS_EBADHANDLE;
+    }
+    guard.process_reuse.retain(|_, reuse_bid| *reuse_bid != bid);
 
-    __BUS_EUNSUPPORTED
+    __BUS_ESUCCESS
 }
 
 /// Invokes a call within a running bus process.
@@ -3823,7 +3851,6 @@ pub fn bus_call<M: MemorySize>(
     buf_len: M::Offset,
     ret_cid: WasmPtr<__wasi_cid_t, M>,
 ) -> __bus_errno_t {
-    let bus = env.runtime.bus();
     let memory = env.memory();
     let topic = unsafe { get_input_str_bus!(memory, topic, topic_len) };
     let keep_alive = keep_alive == __WASI_BOOL_TRUE;
@@ -3833,8 +3860,30 @@ pub fn bus_call<M: MemorySize>(
         topic,
         buf_len
     );
+    let bid: WasiBusProcessId = bid.into();
+    let format = wasi_try_bus!(wasi_format_into_bus_format(format));
+    let buf = wasi_try_mem_bus!(buf.slice(memory, buf_len));
+    let buf = wasi_try_mem_bus!(buf.read_to_vec());
+
+    let mut guard = env.state.threading.lock().unwrap();
+    let process = wasi_try_bus!(guard.processes.get(&bid).ok_or(__BUS_EBADHANDLE));
+    let invocation = wasi_try_bus!(process
+        .inst
+        .invoke(topic, format, &buf)
+        .map_err(bus_error_into_wasi_err));
+    let cid = wasi_try_bus!(guard.new_call_id().ok_or(__BUS_EINTERNAL));
+    guard.calls.insert(
+        cid,
+        WasiBusCall {
+            bid,
+            keep_alive,
+            invocation: Pin::from(invocation),
+        },
+    );
+    drop(guard);
 
-    __BUS_EUNSUPPORTED
+    wasi_try_mem_bus!(ret_cid.write(memory, cid));
+    __BUS_ESUCCESS
 }
 
 /// Invokes a call within the context of another call
@@ -3859,7 +3908,6 @@ pub fn bus_subcall<M: MemorySize>(
     buf_len: M::Offset,
     ret_cid: WasmPtr<__wasi_cid_t, M>,
 ) -> __bus_errno_t {
-    let bus = env.runtime.bus();
     let memory = env.memory();
     let topic = unsafe { get_input_str_bus!(memory, topic, topic_len) };
     let keep_alive = keep_alive == __WASI_BOOL_TRUE;
@@ -3869,8 +3917,30 @@ pub fn bus_subcall<M: MemorySize>(
         topic,
         buf_len
     );
+    let format = wasi_try_bus!(wasi_format_into_bus_format(format));
+    let buf = wasi_try_mem_bus!(buf.slice(memory, buf_len));
+    let buf = wasi_try_mem_bus!(buf.read_to_vec());
+
+    let mut guard = env.state.threading.lock().unwrap();
+    let parent = wasi_try_bus!(guard.calls.get(&parent).ok_or(__BUS_EBADHANDLE));
+    let bid = parent.bid;
+    let invocation = wasi_try_bus!(parent
+        .invocation
+        .invoke(topic, format, &buf)
+        .map_err(bus_error_into_wasi_err));
+    let cid = wasi_try_bus!(guard.new_call_id().ok_or(__BUS_EINTERNAL));
+    guard.calls.insert(
+        cid,
+        WasiBusCall {
+            bid,
+            keep_alive,
+            invocation: Pin::from(invocation),
+        },
+    );
+    drop(guard);
 
-    __BUS_EUNSUPPORTED
+    wasi_try_mem_bus!(ret_cid.write(memory, cid));
+    __BUS_ESUCCESS
 }
 
 /// Polls for any outstanding events from a particular
@@ -3882,11 +3952,14 @@ pub fn bus_subcall<M: MemorySize>(
 ///   as the timeout then this call is non blocking.
 /// * `events` - An events buffer that will hold any received bus events
 /// * `malloc` - Name of the function that will be invoked to allocate memory
-///   Function signature fn(u64) -> u64
+///   Function signature fn(u64) -> u64. Events that carry data fail with
+///   `__BUS_EBADCB` if the module doesn't export it.
 ///
 /// ## Return
 ///
-/// Returns the number of events that have occured
+/// Returns the number of events that have occured. Events that could not
+/// be written to memory are kept for the next poll; the error is only
+/// returned when none of them could be written.
 pub fn bus_poll<M: MemorySize>(
     env: &WasiEnv,
     timeout: __wasi_timestamp_t,
@@ -3895,13 +3968,296 @@ pub fn bus_poll<M: MemorySize>(
     malloc: WasmPtr<u8, M>,
     malloc_len: M::Offset,
     ret_nevents: WasmPtr<M::Offset, M>,
-) -> __bus_errno_t {
-    let bus = env.runtime.bus();
+) -> Result<__bus_errno_t, WasiError> {
     let memory = env.memory();
-    let malloc = unsafe { get_input_str_bus!(memory, malloc, malloc_len) };
+    let malloc = unsafe { get_input_str_bus_ok!(memory, malloc, malloc_len) };
     trace!("wasi::bus_poll (timeout={}, malloc={})", timeout, malloc);
 
-    __BUS_EUNSUPPORTED
+    let events = wasi_try_mem_bus_ok!(events.cast::<__wasi_busevent_t<M>>().slice(memory, nevents));
+    let max_events = wasi_try_bus_ok!(events.len().try_into().map_err(|_| __BUS_EINTERNAL));
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    // Keep polling until something happens or the timeout is reached
+    let start = platform_clock_time_get(__WASI_CLOCK_MONOTONIC, 1_000_000).unwrap() as u128;
+    let polled = loop {
+        let polled = bus_poll_events(env, max_events, &mut cx);
+        if !polled.is_empty() {
+            break polled;
+        }
+        let now = platform_clock_time_get(__WASI_CLOCK_MONOTONIC, 1_000_000).unwrap() as u128;
+        let delta = now.saturating_sub(start);
+        if delta >= timeout as u128 {
+            break polled;
+        }
+        let remaining = (timeout as u128 - delta).min(1_000_000) as u64;
+        env.sleep(Duration::from_nanos(remaining))?;
+    };
+
+    let mut polled = VecDeque::from(polled);
+    let mut nevents = 0usize;
+    let mut error = None;
+    while let Some(event) = polled.front() {
+        let written = bus_event_into_wasi::<M>(env, &malloc, event).and_then(|event| {
+            events
+                .write(nevents as u64, event)
+                .map_err(crate::mem_error_to_bus)
+        });
+        match written {
+            Ok(()) => {
+                polled.pop_front();
+                nevents += 1;
+            }
+            Err(err) => {
+                error = Some(err);
+                break;
+            }
+        }
+    }
+    // The events have been drained from the bus, so the ones that were
+    // not written are queued again to not lose them
+    if !polled.is_empty() {
+        let mut guard = env.state.threading.lock().unwrap();
+        for event in polled.into_iter().rev() {
+            guard.pending_events.push_front(event);
+        }
+    }
+    if let (0, Some(err)) = (nevents, error) {
+        return Ok(err);
+    }
+    let nevents: M::Offset = wasi_try_bus_ok!(nevents.try_into().map_err(|_| __BUS_EINTERNAL));
+    wasi_try_mem_bus_ok!(ret_nevents.write(memory, nevents));
+
+    Ok(__BUS_ESUCCESS)
+}
+
+/// Collects (up to `max_events`) of the events that are ready without blocking
+fn bus_poll_events(env: &WasiEnv, max_events: usize, cx: &mut Context<'_>) -> Vec<BusPollEvent> {
+    let mut ret = Vec::new();
+    let mut guard = env.state.threading.lock().unwrap();
+
+    // Events of a previous poll that could not be delivered
+    while ret.len() < max_events {
+        match guard.pending_events.pop_front() {
+            Some(event) => ret.push(event),
+            None => break,
+        }
+    }
+
+    // Sub-processes that have exited
+    let exited: Vec<_> = guard
+        .processes
+        .iter()
+        .filter_map(|(bid, process)| process.inst.exit_code().map(|rval| (*bid, rval)))
+        .take(max_events)
+        .collect();
+    for (bid, rval) in exited {
+        guard.processes.remove(&bid);
+        guard.process_reuse.retain(|_, reuse_bid| *reuse_bid != bid);
+        ret.push(BusPollEvent::Exit { bid, rval });
+    }
+
+    // Events on the calls that this process has made
+    let mut closed = Vec::new();
+    for (cid, call) in guard.calls.iter_mut() {
+        if ret.len() >= max_events {
+            break;
+        }
+        let cid = *cid;
+        match call.invocation.as_mut().poll_event(cx) {
+            Poll::Ready(BusInvocationEvent::Callback {
+                topic,
+                format,
+                data,
+            }) => ret.push(BusPollEvent::Call {
+                parent: Some(cid),
+                cid,
+                format: bus_format_into_wasi_format(format),
+                topic,
+                data,
+            }),
+            Poll::Ready(BusInvocationEvent::Response { format, data }) => {
+                ret.push(BusPollEvent::Result {
+                    cid,
+                    format: bus_format_into_wasi_format(format),
+                    data,
+                });
+                if !call.keep_alive {
+                    closed.push(cid);
+                }
+            }
+            Poll::Ready(BusInvocationEvent::Fault { fault }) => {
+                ret.push(BusPollEvent::Fault {
+                    cid,
+                    err: bus_error_into_wasi_err(fault),
+                });
+                closed.push(cid);
+            }
+            Poll::Pending => {
+                if call.invocation.as_mut().poll_finished(cx).is_ready() {
+                    ret.push(BusPollEvent::Close { cid });
+                    closed.push(cid);
+                }
+            }
+        }
+    }
+    for cid in closed {
+        guard.calls.remove(&cid);
+    }
+
+    // Calls that other processes have made to this process
+    if guard.listener.is_none() {
+        guard.listener = env.bus().listen().ok().map(Pin::from);
+    }
+    if let Some(mut listener) = guard.listener.take() {
+        while ret.len() < max_events {
+            let call = match listener.as_mut().poll_call(cx) {
+                Poll::Ready(call) => call,
+                Poll::Pending => break,
+            };
+            let cid = match guard.new_call_id() {
+                Some(cid) => cid,
+                None => {
+                    let _ = call.called.fault(wasmer_vbus::BusError::InternalError);
+                    break;
+                }
+            };
+            ret.push(BusPollEvent::Call {
+                parent: None,
+                cid,
+                format: bus_format_into_wasi_format(call.format),
+                topic: call.topic,
+                data: call.data,
+            });
+            guard.called.insert(cid, call.called);
+        }
+        guard.listener.replace(listener);
+    }
+
+    ret
+}
+
+/// A buffer in the memory of the guest, as its pointer and its length
+type BusBuffer<M> = (<M as MemorySize>::Offset, <M as MemorySize>::Offset);
+
+/// Copies `parts` into memory allocated by the guest with its `malloc`
+/// function, and returns where each of them is
+///
+/// All the parts go in a single allocation, so that nothing is left
+/// allocated in the guest if some of them can't be copied
+fn bus_alloc<M: MemorySize>(
+    env: &WasiEnv,
+    malloc: &str,
+    parts: &[&[u8]],
+) -> Result<Vec<BusBuffer<M>>, __bus_errno_t> {
+    let size: usize = parts.iter().map(|part| part.len()).sum();
+    let mut ptr = 0u64;
+    if size > 0 {
+        let funct = env
+            .exports_ref()
+            .and_then(|exports| exports.get_native_function::<u64, u64>(malloc).ok())
+            .ok_or(__BUS_EBADCB)?;
+        ptr = funct.call(size as u64).map_err(|_| __BUS_EALLOC)?;
+    }
+    parts
+        .iter()
+        .map(|part| {
+            let len: M::Offset = part.len().try_into().map_err(|_| __BUS_EALLOC)?;
+            if part.is_empty() {
+                return Ok((M::ZERO, len));
+            }
+            env.memory()
+                .write(ptr, part)
+                .map_err(crate::mem_error_to_bus)?;
+            let part_ptr: M::Offset = ptr.try_into().map_err(|_| __BUS_EMEMVIOLATION)?;
+            ptr += part.len() as u64;
+            Ok((part_ptr, len))
+        })
+        .collect()
+}
+
+/// Converts a polled event into its WASI form, any data that is passed
+/// with the event is copied into a buffer allocated by the guest
+fn bus_event_into_wasi<M: MemorySize>(
+    env: &WasiEnv,
+    malloc: &str,
+    event: &BusPollEvent,
+) -> Result<__wasi_busevent_t<M>, __bus_errno_t> {
+    Ok(match *event {
+        BusPollEvent::Exit { bid, rval } => __wasi_busevent_t {
+            tag: __WASI_BUS_EVENT_TYPE_EXIT,
+            u: __wasi_busevent_u {
+                exit: __wasi_busevent_exit_t {
+                    bid: bid.into(),
+                    rval,
+                },
+            },
+        },
+        BusPollEvent::Call {
+            parent,
+            cid,
+            format,
+            ref topic,
+            ref data,
+        } => {
+            let parts = bus_alloc::<M>(env, malloc, &[topic.as_bytes(), &data[..]])?;
+            let (topic_ptr, topic_len) = parts[0];
+            let (buf_ptr, buf_len) = parts[1];
+            __wasi_busevent_t {
+                tag: __WASI_BUS_EVENT_TYPE_CALL,
+                u: __wasi_busevent_u {
+                    call: __wasi_busevent_call_t {
+                        parent: match parent {
+                            Some(cid) => __wasi_option_cid_t {
+                                tag: __WASI_OPTION_SOME,
+                                cid,
+                            },
+                            None => __wasi_option_cid_t {
+                                tag: __WASI_OPTION_NONE,
+                                cid: 0,
+                            },
+                        },
+                        cid,
+                        format,
+                        topic_ptr,
+                        topic_len,
+                        buf_ptr,
+                        buf_len,
+                    },
+                },
+            }
+        }
+        BusPollEvent::Result {
+            cid,
+            format,
+            ref data,
+        } => {
+            let (buf_ptr, buf_len) = bus_alloc::<M>(env, malloc, &[&data[..]])?[0];
+            __wasi_busevent_t {
+                tag: __WASI_BUS_EVENT_TYPE_RESULT,
+                u: __wasi_busevent_u {
+                    result: __wasi_busevent_result_t {
+                        format,
+                        cid,
+                        buf_ptr,
+                        buf_len,
+                    },
+                },
+            }
+        }
+        BusPollEvent::Fault { cid, err } => __wasi_busevent_t {
+            tag: __WASI_BUS_EVENT_TYPE_FAULT,
+            u: __wasi_busevent_u {
+                fault: __wasi_busevent_fault_t { cid, err },
+            },
+        },
+        BusPollEvent::Close { cid } => __wasi_busevent_t {
+            tag: __WASI_BUS_EVENT_TYPE_CLOSE,
+            u: __wasi_busevent_u {
+                close: __wasi_busevent_close_t { cid },
+            },
+        },
+    })
 }
 
 /// Replies to a call that was made to this process
@@ -3921,15 +4277,24 @@ pub fn call_reply<M: MemorySize>(
     buf: WasmPtr<u8, M>,
     buf_len: M::Offset,
 ) -> __bus_errno_t {
-    let bus = env.runtime.bus();
+    let memory = env.memory();
     trace!(
         "wasi::call_reply (cid={}, format={}, data_len={})",
         cid,
         format,
         buf_len
     );
+    let format = wasi_try_bus!(wasi_format_into_bus_format(format));
+    let buf = wasi_try_mem_bus!(buf.slice(memory, buf_len));
+    let buf = wasi_try_mem_bus!(buf.read_to_vec());
 
-    __BUS_EUNSUPPORTED
+    let called = {
+        let mut guard = env.state.threading.lock().unwrap();
+        wasi_try_bus!(guard.called.remove(&cid).ok_or(__BUS_EBADHANDLE))
+    };
+    wasi_try_bus!(called.reply(format, &buf).map_err(bus_error_into_wasi_err));
+
+    __BUS_ESUCCESS
 }
 
 /// Causes a fault on a particular call that was made
@@ -3941,10 +4306,17 @@ pub fn call_reply<M: MemorySize>(
 /// * `cid` - Handle of the call to raise a fault on
 /// * `fault` - Fault to be raised on the bus
 pub fn call_fault(env: &WasiEnv, cid: __wasi_cid_t, fault: __bus_errno_t) -> __bus_errno_t {
-    let bus = env.runtime.bus();
     debug!("wasi::call_fault (cid={}, fault={})", cid, fault);
 
-    __BUS_EUNSUPPORTED
+    let called = {
+        let mut guard = env.state.threading.lock().unwrap();
+        wasi_try_bus!(guard.called.remove(&cid).ok_or(__BUS_EBADHANDLE))
+    };
+    wasi_try_bus!(called
+        .fault(wasi_error_into_bus_err(fault))
+        .map_err(bus_error_into_wasi_err));
+
+    __BUS_ESUCCESS
 }
 
 /// Closes a bus call based on its bus call handle
@@ -3953,10 +4325,17 @@ pub fn call_fault(env: &WasiEnv, cid: __wasi_cid_t, fault: __bus_errno_t) -> __b
 ///
 /// * `cid` - Handle of the bus call handle to be dropped
 pub fn call_close(env: &WasiEnv, cid: __wasi_cid_t) -> __bus_errno_t {
-    let bus = env.runtime.bus();
     trace!("wasi::call_close (cid={})", cid);
 
-    __BUS_EUNSUPPORTED
+    let mut guard = env.state.threading.lock().unwrap();
+    let call = guard.calls.remove(&cid);
+    let called = guard.called.remove(&cid);
+    drop(guard);
+    if call.is_none() && called.is_none() {
+        return __BUS_EBADHANDLE;
+    }
+
+    __BUS_ESUCCESS
 }
 
 /// ### `ws_connect()`
@@ -3984,18 +4363,18 @@ pub fn ws_connect<M: MemorySize>(
         .ws_connect(url.as_str())
         .map_err(net_error_into_wasi_err));
 
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let kind = Kind::Socket {
         socket: InodeSocket::new(InodeSocketKind::WebSocket(socket)),
     };
 
-    let inode = state.fs.create_inode_with_default_stat(
+    let inode = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind,
         false,
         "socket".to_string(),
-    );
+    ));
     let rights = super::state::all_socket_rights();
     let fd = wasi_try!(state.fs.create_fd(rights, rights, 0, 0, inode));
 
@@ -4066,7 +4445,7 @@ pub fn http_request<M: MemorySize>(
         status: socket.status,
     };
 
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let kind_req = Kind::Socket {
         socket: InodeSocket::new(InodeSocketKind::HttpRequest(
@@ -4087,24 +4466,24 @@ pub fn http_request<M: MemorySize>(
         )),
     };
 
-    let inode_req = state.fs.create_inode_with_default_stat(
+    let inode_req = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind_req,
         false,
         "http_request".to_string(),
-    );
-    let inode_res = state.fs.create_inode_with_default_stat(
+    ));
+    let inode_res = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind_res,
         false,
         "http_response".to_string(),
-    );
-    let inode_hdr = state.fs.create_inode_with_default_stat(
+    ));
+    let inode_hdr = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind_hdr,
         false,
         "http_headers".to_string(),
-    );
+    ));
     let rights = super::state::all_socket_rights();
 
     let handles = __wasi_http_handles_t {
@@ -4553,7 +4932,7 @@ pub fn sock_open<M: MemorySize>(
 ) -> __wasi_errno_t {
     debug!("wasi::sock_open");
 
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let kind = match ty {
         __WASI_SOCK_TYPE_STREAM | __WASI_SOCK_TYPE_DGRAM => Kind::Socket {
@@ -4576,12 +4955,12 @@ pub fn sock_open<M: MemorySize>(
         _ => return __WASI_ENOTSUP,
     };
 
-    let inode = state.fs.create_inode_with_default_stat(
+    let inode = wasi_try!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind,
         false,
         "socket".to_string(),
-    );
+    ));
     let rights = super::state::all_socket_rights();
     let fd = wasi_try!(state.fs.create_fd(rights, rights, 0, 0, inode));
 
@@ -4985,7 +5364,7 @@ pub fn sock_accept<M: MemorySize>(
 
     let (child, addr) = {
         let mut ret;
-        let (_, state) = env.get_memory_and_wasi_state(0);
+        let (_, state) = env.get_memory_and_wasi_state();
         loop {
             wasi_try_ok!(
                 match __sock_actor(env, sock, __WASI_RIGHT_SOCK_ACCEPT, |socket| socket
@@ -5010,17 +5389,17 @@ pub fn sock_accept<M: MemorySize>(
         ret
     };
 
-    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut(0);
+    let (memory, state, mut inodes) = env.get_memory_and_wasi_state_and_inodes_mut();
 
     let kind = Kind::Socket {
         socket: InodeSocket::new(InodeSocketKind::TcpStream(child)),
     };
-    let inode = state.fs.create_inode_with_default_stat(
+    let inode = wasi_try_ok!(state.fs.create_inode_with_default_stat(
         inodes.deref_mut(),
         kind,
         false,
         "socket".to_string(),
-    );
+    ));
 
     let rights = super::state::all_socket_rights();
     let fd = wasi_try_ok!(state.fs.create_fd(rights, rights, 0, 0, inode));
@@ -5252,7 +5631,7 @@ pub unsafe fn sock_send_file<M: MemorySize>(
     ret_sent: WasmPtr<__wasi_filesize_t, M>,
 ) -> Result<__wasi_errno_t, WasiError> {
     debug!("wasi::send_file");
-    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes(0);
+    let (memory, state, inodes) = env.get_memory_and_wasi_state_and_inodes();
 
     // Set the offset of the file
     {
@@ -5322,7 +5701,7 @@ pub unsafe fn sock_send_file<M: MemorySize>(
                         Kind::EventNotifications { .. } => {
                             return Ok(__WASI_EINVAL);
                         }
-                        Kind::Symlink { .. } => unimplemented!("Symlinks in wasi::fd_read"),
+                        Kind::Symlink { .. } => return Ok(__WASI_EBADF),
                         Kind::Buffer { buffer } => {
                             let mut buf_read = &buffer[offset..];
                             wasi_try_ok!(buf_read.read(&mut buf).map_err(map_io_err))
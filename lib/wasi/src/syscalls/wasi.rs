@@ -387,7 +387,7 @@ pub(crate) fn proc_exit(env: &WasiEnv, code: __wasi_exitcode_t) -> Result<(), Wa
     super::proc_exit(env, code)
 }
 
-pub(crate) fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
+pub(crate) fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> Result<__wasi_errno_t, WasiError> {
     super::proc_raise(env, sig)
 }
 
@@ -1,5 +1,6 @@
 use super::types::*;
 use std::collections::BTreeSet;
+use std::task::{RawWaker, RawWakerVTable, Waker};
 use wasmer::Module;
 
 #[allow(dead_code)]
@@ -216,6 +217,19 @@ pub fn get_wasi_versions(module: &Module, strict: bool) -> Option<BTreeSet<WasiV
     }
 }
 
+/// Returns a waker that does nothing when woken, this is used by the
+/// syscalls that poll the virtual bus in a loop rather than waiting
+/// to be notified
+pub(crate) fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,280 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use wasmer::{Instance, Memory, Module, Store};
+use wasmer_wasi::{WasiFsLimits, WasiState};
+
+mod sys {
+    #[test]
+    fn test_max_file_size() {
+        super::test_max_file_size()
+    }
+
+    #[test]
+    fn test_max_bytes_written() {
+        super::test_max_bytes_written()
+    }
+
+    #[test]
+    fn test_max_open_fds() {
+        super::test_max_open_fds()
+    }
+
+    #[test]
+    fn test_max_inodes() {
+        super::test_max_inodes()
+    }
+}
+
+const ESUCCESS: u16 = 0;
+const EFBIG: u16 = 22;
+const EMFILE: u16 = 33;
+const ENOSPC: u16 = 51;
+
+/// Where the module expects the path to open and the data to write in its
+/// memory
+const PATH_OFFSET: u64 = 0;
+const FD_OFFSET: u64 = 1024;
+
+/// A module running in a scratch directory on the host, which is preopened
+/// as the only directory
+struct Sandbox {
+    dir: PathBuf,
+    state: Arc<WasiState>,
+    instance: Instance,
+    memory: Memory,
+    preopen_fd: u32,
+}
+
+impl Sandbox {
+    fn new(name: &str, limits: WasiFsLimits) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "wasmer-wasi-fs-limits-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let store = Store::default();
+        let module = Module::new(
+            &store,
+            br#"
+        (module
+            (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "fd_allocate" (func $fd_allocate (param i32 i64 i64) (result i32)))
+            (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+
+            (memory 1)
+            (export "memory" (memory 0))
+
+            (func (export "open") (param $dirfd i32) (param $path_len i32) (result i32)
+                (call $path_open
+                    (local.get $dirfd)
+                    (i32.const 0)    ;; dirflags
+                    (i32.const 0)    ;; path
+                    (local.get $path_len)
+                    (i32.const 1)    ;; __WASI_O_CREAT
+                    (i64.const -1)   ;; rights
+                    (i64.const -1)   ;; inherited rights
+                    (i32.const 0)    ;; fdflags
+                    (i32.const 1024) ;; fd
+                )
+            )
+
+            (func (export "write") (param $fd i32) (param $len i32) (result i32)
+                (i32.store (i32.const 2048) (i32.const 4096))
+                (i32.store (i32.const 2052) (local.get $len))
+                (call $fd_write
+                    (local.get $fd)
+                    (i32.const 2048) ;; iovs
+                    (i32.const 1)    ;; number of iovs
+                    (i32.const 2056) ;; number of bytes written
+                )
+            )
+
+            (func (export "allocate") (param $fd i32) (param $offset i64) (param $len i64) (result i32)
+                (call $fd_allocate (local.get $fd) (local.get $offset) (local.get $len))
+            )
+
+            (func (export "close") (param $fd i32) (result i32)
+                (call $fd_close (local.get $fd))
+            )
+        )
+        "#,
+        )
+        .unwrap();
+
+        let mut wasi_env = WasiState::new("command-name")
+            .preopen_dir(&dir)
+            .unwrap()
+            .fs_limits(limits)
+            .finalize()
+            .unwrap();
+        let import_object = wasi_env.import_object(&module).unwrap();
+        let instance = Instance::new(&module, &import_object).unwrap();
+        let memory = instance.exports.get_memory("memory").unwrap().clone();
+        let state = wasi_env.state.clone();
+        let preopen_fd = *state.fs.preopen_fds.read().unwrap().last().unwrap();
+
+        Self {
+            dir,
+            state,
+            instance,
+            memory,
+            preopen_fd,
+        }
+    }
+
+    fn call(&self, name: &str, params: &[wasmer::Val]) -> u16 {
+        let function = self.instance.exports.get_function(name).unwrap();
+        function.call(params).unwrap()[0].unwrap_i32() as u16
+    }
+
+    /// Opens or creates `path` and returns its file descriptor
+    fn open(&self, path: &str) -> Result<u32, u16> {
+        self.memory.write(PATH_OFFSET, path.as_bytes()).unwrap();
+        match self.call(
+            "open",
+            &[(self.preopen_fd as i32).into(), (path.len() as i32).into()],
+        ) {
+            ESUCCESS => {
+                let mut fd = [0u8; 4];
+                self.memory.read(FD_OFFSET, &mut fd).unwrap();
+                Ok(u32::from_le_bytes(fd))
+            }
+            errno => Err(errno),
+        }
+    }
+
+    fn write(&self, fd: u32, len: u32) -> u16 {
+        self.call("write", &[(fd as i32).into(), (len as i32).into()])
+    }
+
+    fn allocate(&self, fd: u32, offset: u64, len: u64) -> u16 {
+        self.call(
+            "allocate",
+            &[
+                (fd as i32).into(),
+                (offset as i64).into(),
+                (len as i64).into(),
+            ],
+        )
+    }
+
+    fn close(&self, fd: u32) -> u16 {
+        self.call("close", &[(fd as i32).into()])
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn test_max_file_size() {
+    let sandbox = Sandbox::new(
+        "file-size",
+        WasiFsLimits {
+            max_file_size: Some(16),
+            ..WasiFsLimits::default()
+        },
+    );
+    let fd = sandbox.open("file").unwrap();
+
+    assert_eq!(sandbox.write(fd, 10), ESUCCESS);
+    assert_eq!(sandbox.write(fd, 10), EFBIG);
+    assert_eq!(sandbox.write(fd, 6), ESUCCESS);
+    assert_eq!(sandbox.write(fd, 0), ESUCCESS, "empty writes always work");
+    assert_eq!(sandbox.allocate(fd, 0, 32), EFBIG);
+    assert_eq!(
+        std::fs::metadata(sandbox.dir.join("file")).unwrap().len(),
+        16
+    );
+
+    let other = sandbox.open("other").unwrap();
+    assert_eq!(
+        sandbox.write(other, 16),
+        ESUCCESS,
+        "the limit applies to each file on its own"
+    );
+}
+
+fn test_max_bytes_written() {
+    let sandbox = Sandbox::new(
+        "bytes-written",
+        WasiFsLimits {
+            max_bytes_written: Some(32),
+            ..WasiFsLimits::default()
+        },
+    );
+    let first = sandbox.open("first").unwrap();
+    let second = sandbox.open("second").unwrap();
+
+    assert_eq!(sandbox.write(first, 10), ESUCCESS);
+    assert_eq!(sandbox.allocate(second, 0, 10), ESUCCESS);
+    assert_eq!(sandbox.write(second, 20), ENOSPC);
+    assert_eq!(sandbox.write(second, 12), ESUCCESS);
+    assert_eq!(sandbox.write(first, 1), ENOSPC);
+    assert_eq!(sandbox.state.fs.bytes_written(), 32);
+}
+
+fn test_max_open_fds() {
+    let max_open_fds = 8;
+    let sandbox = Sandbox::new(
+        "open-fds",
+        WasiFsLimits {
+            max_open_fds: Some(max_open_fds),
+            ..WasiFsLimits::default()
+        },
+    );
+
+    // The standard streams and the preopened directories count as well
+    let mut fds = Vec::new();
+    let errno = loop {
+        match sandbox.open(&format!("file{}", fds.len())) {
+            Ok(fd) => fds.push(fd),
+            Err(errno) => break errno,
+        }
+    };
+    assert_eq!(errno, EMFILE);
+    assert!(!fds.is_empty());
+    assert_eq!(sandbox.state.fs.fd_map.read().unwrap().len(), max_open_fds);
+
+    assert_eq!(sandbox.close(fds.pop().unwrap()), ESUCCESS);
+    assert!(
+        sandbox.open("file0").is_ok(),
+        "closing a file descriptor makes room for a new one"
+    );
+}
+
+fn test_max_inodes() {
+    let max_inodes = 8;
+    let sandbox = Sandbox::new(
+        "inodes",
+        WasiFsLimits {
+            max_inodes: Some(max_inodes),
+            ..WasiFsLimits::default()
+        },
+    );
+
+    let mut opened = 0;
+    let errno = loop {
+        match sandbox.open(&format!("file{}", opened)) {
+            Ok(fd) => {
+                assert_eq!(sandbox.close(fd), ESUCCESS);
+                opened += 1;
+            }
+            Err(errno) => break errno,
+        }
+    };
+    assert_eq!(errno, ENOSPC);
+    assert!(opened > 0);
+    assert_eq!(sandbox.state.inodes.read().unwrap().arena.len(), max_inodes);
+    assert!(
+        sandbox.open("file0").is_ok(),
+        "files that already have an inode can still be opened"
+    );
+}
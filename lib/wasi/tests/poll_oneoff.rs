@@ -0,0 +1,258 @@
+use std::convert::TryInto;
+use std::io::Write;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use wasmer::{Instance, Module, Store};
+use wasmer_wasi::{Pipe, WasiState};
+
+mod sys {
+    #[test]
+    fn test_relative_clock() {
+        super::test_relative_clock()
+    }
+
+    #[test]
+    fn test_absolute_clock() {
+        super::test_absolute_clock()
+    }
+
+    #[test]
+    fn test_stdin_readiness() {
+        super::test_stdin_readiness()
+    }
+
+    #[test]
+    fn test_invalid_subscriptions() {
+        super::test_invalid_subscriptions()
+    }
+}
+
+#[cfg(feature = "js")]
+mod js {
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_relative_clock() {
+        super::test_relative_clock()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_absolute_clock() {
+        super::test_absolute_clock()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_stdin_readiness() {
+        super::test_stdin_readiness()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_invalid_subscriptions() {
+        super::test_invalid_subscriptions()
+    }
+}
+
+const CLOCK_REALTIME: u32 = 0;
+const CLOCK_MONOTONIC: u32 = 1;
+const SUBSCRIPTION_CLOCK_ABSTIME: u16 = 1;
+const EVENTTYPE_CLOCK: u8 = 0;
+const EVENTTYPE_FD_READ: u8 = 1;
+const ESUCCESS: u16 = 0;
+const EBADF: u16 = 8;
+const EINVAL: u16 = 28;
+
+/// Where the module expects the subscriptions, the events and the number of
+/// events in its memory
+const IN_OFFSET: u64 = 0;
+const OUT_OFFSET: u64 = 4096;
+const NEVENTS_OFFSET: u64 = 8192;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Event {
+    userdata: u64,
+    error: u16,
+    type_: u8,
+    nbytes: u64,
+}
+
+fn clock_subscription(userdata: u64, clock_id: u32, timeout: u64, flags: u16) -> [u8; 48] {
+    let mut sub = [0u8; 48];
+    sub[0..8].copy_from_slice(&userdata.to_le_bytes());
+    sub[8] = EVENTTYPE_CLOCK;
+    sub[16..20].copy_from_slice(&clock_id.to_le_bytes());
+    sub[24..32].copy_from_slice(&timeout.to_le_bytes());
+    sub[40..42].copy_from_slice(&flags.to_le_bytes());
+    sub
+}
+
+fn read_subscription(userdata: u64, fd: u32) -> [u8; 48] {
+    let mut sub = [0u8; 48];
+    sub[0..8].copy_from_slice(&userdata.to_le_bytes());
+    sub[8] = EVENTTYPE_FD_READ;
+    sub[16..20].copy_from_slice(&fd.to_le_bytes());
+    sub
+}
+
+/// Runs `poll_oneoff` inside a module with the given subscriptions and
+/// returns the errno of the call along with the events that were seen
+fn poll_oneoff(stdin: Pipe, subscriptions: &[[u8; 48]]) -> (u16, Vec<Event>) {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        br#"
+    (module
+        (import "wasi_snapshot_preview1" "poll_oneoff" (func $poll_oneoff (param i32 i32 i32 i32) (result i32)))
+
+        (memory 1)
+        (export "memory" (memory 0))
+
+        (func (export "poll") (param $nsubscriptions i32) (result i32)
+            (call $poll_oneoff
+                (i32.const 0)    ;; subscriptions
+                (i32.const 4096) ;; events
+                (local.get $nsubscriptions)
+                (i32.const 8192) ;; number of events
+            )
+        )
+    )
+    "#,
+    )
+    .unwrap();
+
+    let mut wasi_env = WasiState::new("command-name")
+        .stdin(Box::new(stdin))
+        .finalize()
+        .unwrap();
+    let import_object = wasi_env.import_object(&module).unwrap();
+    let instance = Instance::new(&module, &import_object).unwrap();
+    let memory = instance.exports.get_memory("memory").unwrap();
+
+    for (i, sub) in subscriptions.iter().enumerate() {
+        memory.write(IN_OFFSET + 48 * i as u64, sub).unwrap();
+    }
+    let poll = instance.exports.get_function("poll").unwrap();
+    let ret = poll.call(&[(subscriptions.len() as i32).into()]).unwrap();
+    let errno = ret[0].unwrap_i32() as u16;
+
+    let mut nevents = [0u8; 4];
+    memory.read(NEVENTS_OFFSET, &mut nevents).unwrap();
+    let events = (0..u32::from_le_bytes(nevents) as u64)
+        .map(|i| {
+            let mut event = [0u8; 32];
+            memory.read(OUT_OFFSET + 32 * i, &mut event).unwrap();
+            Event {
+                userdata: u64::from_le_bytes(event[0..8].try_into().unwrap()),
+                error: u16::from_le_bytes(event[8..10].try_into().unwrap()),
+                type_: event[10],
+                nbytes: u64::from_le_bytes(event[16..24].try_into().unwrap()),
+            }
+        })
+        .collect();
+    (errno, events)
+}
+
+fn test_relative_clock() {
+    let timeout = Duration::from_millis(20);
+    let start = Instant::now();
+    let (errno, events) = poll_oneoff(
+        Pipe::new(),
+        &[
+            clock_subscription(1, CLOCK_MONOTONIC, 10_000_000_000, 0),
+            clock_subscription(2, CLOCK_MONOTONIC, timeout.as_nanos() as u64, 0),
+        ],
+    );
+    assert!(start.elapsed() >= timeout);
+    assert_eq!(errno, ESUCCESS);
+    assert_eq!(
+        events,
+        vec![Event {
+            userdata: 2,
+            error: ESUCCESS,
+            type_: EVENTTYPE_CLOCK,
+            nbytes: 0,
+        }]
+    );
+}
+
+fn test_absolute_clock() {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    let (errno, events) = poll_oneoff(
+        Pipe::new(),
+        &[
+            // Nothing was written to stdin so only the clock can trigger
+            read_subscription(1, 0),
+            clock_subscription(
+                2,
+                CLOCK_REALTIME,
+                now + 20_000_000,
+                SUBSCRIPTION_CLOCK_ABSTIME,
+            ),
+        ],
+    );
+    assert_eq!(errno, ESUCCESS);
+    assert_eq!(
+        events,
+        vec![Event {
+            userdata: 2,
+            error: ESUCCESS,
+            type_: EVENTTYPE_CLOCK,
+            nbytes: 0,
+        }]
+    );
+}
+
+fn test_stdin_readiness() {
+    let mut stdin = Pipe::new();
+    stdin.write_all(b"hello").unwrap();
+    let start = Instant::now();
+    let (errno, events) = poll_oneoff(
+        stdin,
+        &[
+            clock_subscription(1, CLOCK_MONOTONIC, 10_000_000_000, 0),
+            read_subscription(2, 0),
+        ],
+    );
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(errno, ESUCCESS);
+    assert_eq!(
+        events,
+        vec![Event {
+            userdata: 2,
+            error: ESUCCESS,
+            type_: EVENTTYPE_FD_READ,
+            nbytes: 5,
+        }]
+    );
+}
+
+fn test_invalid_subscriptions() {
+    let (errno, events) = poll_oneoff(
+        Pipe::new(),
+        &[read_subscription(1, 42), clock_subscription(2, 99, 0, 0)],
+    );
+    assert_eq!(errno, ESUCCESS);
+    assert_eq!(
+        events,
+        vec![
+            Event {
+                userdata: 1,
+                error: EBADF,
+                type_: EVENTTYPE_FD_READ,
+                nbytes: 0,
+            },
+            Event {
+                userdata: 2,
+                error: EINVAL,
+                type_: EVENTTYPE_CLOCK,
+                nbytes: 0,
+            },
+        ]
+    );
+
+    let (errno, events) = poll_oneoff(Pipe::new(), &[]);
+    assert_eq!(errno, EINVAL);
+    assert!(events.is_empty());
+}
@@ -0,0 +1,175 @@
+use wasmer::{Instance, Module, RuntimeError, Store};
+use wasmer_wasi::{WasiEnv, WasiError, WasiState};
+
+mod sys {
+    #[test]
+    fn test_default_actions() {
+        super::test_default_actions()
+    }
+
+    #[test]
+    fn test_invalid_signals() {
+        super::test_invalid_signals()
+    }
+
+    #[test]
+    fn test_handled_signals() {
+        super::test_handled_signals()
+    }
+
+    #[test]
+    fn test_queued_signals() {
+        super::test_queued_signals()
+    }
+}
+
+#[cfg(feature = "js")]
+mod js {
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_default_actions() {
+        super::test_default_actions()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_invalid_signals() {
+        super::test_invalid_signals()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_handled_signals() {
+        super::test_handled_signals()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_queued_signals() {
+        super::test_queued_signals()
+    }
+}
+
+const ESUCCESS: i32 = 0;
+const EINVAL: i32 = 28;
+const SIGINT: i32 = 2;
+const SIGKILL: i32 = 9;
+const SIGTERM: i32 = 15;
+const SIGCHLD: i32 = 16;
+const SIGWINCH: i32 = 27;
+
+/// A module that raises signals, it records the signals it handled when
+/// `handler` is set
+struct Program {
+    env: WasiEnv,
+    instance: Instance,
+}
+
+impl Program {
+    fn new(handler: bool) -> Self {
+        let handler = if handler {
+            r#"
+            (func (export "__wasm_signal") (param $sig i32)
+                (global.set $signals (i32.add (i32.mul (global.get $signals) (i32.const 100)) (local.get $sig)))
+            )
+            "#
+        } else {
+            ""
+        };
+        let wat = format!(
+            r#"
+        (module
+            (import "wasi_snapshot_preview1" "proc_raise" (func $proc_raise (param i32) (result i32)))
+            (import "wasi_snapshot_preview1" "sched_yield" (func $sched_yield (result i32)))
+
+            (memory 1)
+            (export "memory" (memory 0))
+
+            ;; The handled signals, as base 100 digits
+            (global $signals (export "signals") (mut i32) (i32.const 0))
+
+            (func (export "raise") (param $sig i32) (result i32)
+                (call $proc_raise (local.get $sig))
+            )
+
+            (func (export "yield") (result i32)
+                (call $sched_yield)
+            )
+
+            {}
+        )
+        "#,
+            handler
+        );
+
+        let store = Store::default();
+        let module = Module::new(&store, wat).unwrap();
+        let mut env = WasiState::new("command-name").finalize().unwrap();
+        let import_object = env.import_object(&module).unwrap();
+        let instance = Instance::new(&module, &import_object).unwrap();
+        Self { env, instance }
+    }
+
+    fn raise(&self, sig: i32) -> Result<i32, RuntimeError> {
+        let raise = self.instance.exports.get_function("raise").unwrap();
+        Ok(raise.call(&[sig.into()])?[0].unwrap_i32())
+    }
+
+    fn yield_now(&self) -> Result<i32, RuntimeError> {
+        let yield_now = self.instance.exports.get_function("yield").unwrap();
+        Ok(yield_now.call(&[])?[0].unwrap_i32())
+    }
+
+    fn handled_signals(&self) -> i32 {
+        let signals = self.instance.exports.get_global("signals").unwrap();
+        signals.get().unwrap_i32()
+    }
+}
+
+fn exit_code(result: Result<i32, RuntimeError>) -> u32 {
+    match result.unwrap_err().downcast::<WasiError>() {
+        Ok(WasiError::Exit(code)) => code,
+        other => panic!("expected the program to exit, got {:?}", other),
+    }
+}
+
+fn test_default_actions() {
+    let program = Program::new(false);
+    assert_eq!(program.raise(SIGWINCH).unwrap(), ESUCCESS);
+    assert_eq!(program.raise(SIGCHLD).unwrap(), ESUCCESS);
+    assert_eq!(exit_code(program.raise(SIGTERM)), 128 + SIGTERM as u32);
+    assert_eq!(exit_code(program.raise(SIGINT)), 128 + SIGINT as u32);
+}
+
+fn test_invalid_signals() {
+    let program = Program::new(true);
+    assert_eq!(program.raise(0).unwrap(), EINVAL);
+    assert_eq!(program.raise(64).unwrap(), EINVAL);
+    assert_eq!(program.handled_signals(), 0);
+}
+
+fn test_handled_signals() {
+    let program = Program::new(true);
+    assert_eq!(program.raise(SIGTERM).unwrap(), ESUCCESS);
+    assert_eq!(program.raise(SIGWINCH).unwrap(), ESUCCESS);
+    assert_eq!(program.handled_signals(), SIGTERM * 100 + SIGWINCH);
+
+    assert_eq!(
+        exit_code(program.raise(SIGKILL)),
+        128 + SIGKILL as u32,
+        "SIGKILL can't be handled"
+    );
+}
+
+fn test_queued_signals() {
+    let program = Program::new(true);
+    program.env.signal(SIGINT as u8);
+    program.env.signal(SIGTERM as u8);
+    assert_eq!(program.handled_signals(), 0, "signals wait for a yield");
+    assert!(program.env.has_pending_signals());
+    assert_eq!(program.yield_now().unwrap(), ESUCCESS);
+    assert_eq!(program.handled_signals(), SIGINT * 100 + SIGTERM);
+    assert!(!program.env.has_pending_signals());
+
+    let program = Program::new(false);
+    program.env.signal(SIGINT as u8);
+    assert_eq!(exit_code(program.yield_now()), 128 + SIGINT as u32);
+}
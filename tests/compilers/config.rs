@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use wasmer::{CompilerConfig, Engine, Features, ModuleMiddleware, Store};
+use wasmer::{CompilerConfig, Engine, Features, FuelMetering, ModuleMiddleware, Store};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Compiler {
@@ -14,6 +14,8 @@ pub struct Config {
     pub features: Option<Features>,
     pub middlewares: Vec<Arc<dyn ModuleMiddleware>>,
     pub canonicalize_nans: bool,
+    pub epoch_interruption: bool,
+    pub fuel: Option<FuelMetering>,
 }
 
 impl Config {
@@ -22,6 +24,8 @@ impl Config {
             compiler,
             features: None,
             canonicalize_nans: false,
+            epoch_interruption: false,
+            fuel: None,
             middlewares: vec![],
         }
     }
@@ -38,6 +42,14 @@ impl Config {
         self.canonicalize_nans = canonicalize_nans;
     }
 
+    pub fn set_epoch_interruption(&mut self, epoch_interruption: bool) {
+        self.epoch_interruption = epoch_interruption;
+    }
+
+    pub fn set_fuel_metering(&mut self, fuel: FuelMetering) {
+        self.fuel = Some(fuel);
+    }
+
     pub fn store(&self) -> Store {
         let compiler_config = self.compiler_config(self.canonicalize_nans);
         let engine = self.engine(compiler_config);
@@ -70,6 +82,8 @@ impl Config {
             Compiler::Cranelift => {
                 let mut compiler = wasmer_compiler_cranelift::Cranelift::new();
                 compiler.canonicalize_nans(canonicalize_nans);
+                compiler.epoch_interruption(self.epoch_interruption);
+                compiler.fuel_metering(self.fuel.clone());
                 compiler.enable_verifier();
                 self.add_middlewares(&mut compiler);
                 Box::new(compiler)
@@ -78,6 +92,8 @@ impl Config {
             Compiler::LLVM => {
                 let mut compiler = wasmer_compiler_llvm::LLVM::new();
                 compiler.canonicalize_nans(canonicalize_nans);
+                compiler.epoch_interruption(self.epoch_interruption);
+                compiler.fuel_metering(self.fuel.clone());
                 compiler.enable_verifier();
                 self.add_middlewares(&mut compiler);
                 Box::new(compiler)
@@ -86,6 +102,8 @@ impl Config {
             Compiler::Singlepass => {
                 let mut compiler = wasmer_compiler_singlepass::Singlepass::new();
                 compiler.canonicalize_nans(canonicalize_nans);
+                compiler.epoch_interruption(self.epoch_interruption);
+                compiler.fuel_metering(self.fuel.clone());
                 compiler.enable_verifier();
                 self.add_middlewares(&mut compiler);
                 Box::new(compiler)
@@ -0,0 +1,79 @@
+//! Tests for epoch-based interruption
+use anyhow::Result;
+use std::thread;
+use std::time::Duration;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn new_instance(config: &mut crate::Config) -> Result<Instance> {
+    config.set_epoch_interruption(true);
+    let store = config.store();
+    let wat = r#"
+    (module
+        (func (export "spin")
+            (loop $l (br $l)))
+        (func (export "count") (param $n i32) (result i32)
+            (local $i i32)
+            (loop $l
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $l (i32.lt_u (local.get $i) (local.get $n))))
+            (local.get $i))
+    )
+    "#;
+    let module = Module::new(&store, wat)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+#[compiler_test(epoch)]
+fn interrupt_infinite_loop(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(&mut config)?;
+    let store = instance.module().store();
+    store.set_epoch_deadline(1);
+
+    let epoch = store.engine().epoch_counter().clone();
+    let watchdog = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        epoch.increment();
+    });
+
+    let spin = instance.exports.get_function("spin")?;
+    let error = spin.call(&[]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::Interrupt));
+    watchdog.join().unwrap();
+    Ok(())
+}
+
+#[compiler_test(epoch)]
+fn interrupt_on_function_entry(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(&mut config)?;
+    let store = instance.module().store();
+    store.set_epoch_deadline(0);
+
+    let count = instance.exports.get_function("count")?;
+    let error = count.call(&[Val::I32(1)]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::Interrupt));
+
+    // Moving the deadline forward lets the same instance run again.
+    store.set_epoch_deadline(1);
+    assert_eq!(
+        count.call(&[Val::I32(1000)])?.to_vec(),
+        vec![Val::I32(1000)]
+    );
+    Ok(())
+}
+
+#[compiler_test(epoch)]
+fn no_deadline(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(&mut config)?;
+    let store = instance.module().store();
+    for _ in 0..3 {
+        store.engine().epoch_counter().increment();
+    }
+
+    let count = instance.exports.get_function("count")?;
+    assert_eq!(
+        count.call(&[Val::I32(1000)])?.to_vec(),
+        vec![Val::I32(1000)]
+    );
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! Tests for the exception handling proposal
+use anyhow::Result;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn new_store(config: &mut crate::Config) -> Store {
+    let mut features = Features::default();
+    features.exceptions(true);
+    config.set_features(features);
+    config.store()
+}
+
+fn new_instance(config: &mut crate::Config, wat: &str) -> Result<Instance> {
+    let store = new_store(config);
+    let module = Module::new(&store, wat)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+#[compiler_test(exceptions)]
+fn throw_and_catch(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(
+        &mut config,
+        r#"
+    (module
+        (tag $e (param i32 i64))
+        (func (export "run") (param i32) (result i64)
+            (local $x i64)
+            try (result i64)
+                (throw $e (local.get 0) (i64.const 40))
+            catch $e
+                (local.set $x)
+                (i64.add (i64.extend_i32_u) (local.get $x))
+            end))
+    "#,
+    )?;
+    let run: TypedFunction<i32, i64> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(2)?, 42);
+    Ok(())
+}
+
+#[compiler_test(exceptions)]
+fn catch_clauses_match_tags(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(
+        &mut config,
+        r#"
+    (module
+        (tag $a (param f64))
+        (tag $b (param f64))
+        (tag $c)
+        (func $throw (param i32)
+            (if (i32.eq (local.get 0) (i32.const 0)) (then (throw $a (f64.const 1.5))))
+            (if (i32.eq (local.get 0) (i32.const 1)) (then (throw $b (f64.const 2.5))))
+            (throw $c))
+        (func (export "run") (param i32) (result f64)
+            try (result f64)
+                (call $throw (local.get 0))
+                (f64.const 0)
+            catch $b
+            catch $a
+                (f64.neg)
+            catch_all
+                (f64.const 10)
+            end))
+    "#,
+    )?;
+    let run: TypedFunction<i32, f64> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(0)?, -1.5);
+    assert_eq!(run.call(1)?, 2.5);
+    assert_eq!(run.call(2)?, 10.0);
+    Ok(())
+}
+
+#[compiler_test(exceptions)]
+fn rethrow(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(
+        &mut config,
+        r#"
+    (module
+        (tag $e (param i32))
+        (func (export "run") (result i32)
+            try (result i32)
+                try
+                    (throw $e (i32.const 7))
+                catch_all
+                    (rethrow 0)
+                end
+                (i32.const 0)
+            catch $e
+            end))
+    "#,
+    )?;
+    let run: TypedFunction<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 7);
+    Ok(())
+}
+
+#[compiler_test(exceptions)]
+fn delegate(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(
+        &mut config,
+        r#"
+    (module
+        (tag $e)
+        (func (export "run") (param i32) (result i32)
+            try $outer (result i32)
+                try $inner (result i32)
+                    try (result i32)
+                        (if (local.get 0) (then (throw $e)))
+                        (i32.const 1)
+                    delegate $outer
+                catch $e
+                    (i32.const 2)
+                end
+            catch $e
+                (i32.const 3)
+            end))
+    "#,
+    )?;
+    let run: TypedFunction<i32, i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(0)?, 1);
+    assert_eq!(run.call(1)?, 3);
+    Ok(())
+}
+
+#[compiler_test(exceptions)]
+fn uncaught_exception(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(
+        &mut config,
+        r#"
+    (module
+        (tag $e (param i32))
+        (tag $other)
+        (func $throw (throw $e (i32.const 1)))
+        (func (export "run") (param i32) (result i32)
+            (if (local.get 0)
+                (then
+                    try
+                        (call $throw)
+                    catch $other
+                    end))
+            (i32.const 5)))
+    "#,
+    )?;
+    let run: TypedFunction<i32, i32> = instance.exports.get_native_function("run")?;
+    let error = run.call(1).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::UncaughtException));
+
+    // The exception doesn't stay pending once it reached the host.
+    assert_eq!(run.call(0)?, 5);
+    Ok(())
+}
+
+#[compiler_test(exceptions)]
+fn import_and_export_tags(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let thrower = Module::new(
+        &store,
+        r#"
+    (module
+        (tag $e (export "e") (param i32))
+        (func (export "throw") (param i32)
+            (throw $e (local.get 0))))
+    "#,
+    )?;
+    let catcher = Module::new(
+        &store,
+        r#"
+    (module
+        (import "thrower" "e" (tag $e (param i32)))
+        (import "thrower" "throw" (func $throw (param i32)))
+        (func (export "run") (param i32) (result i32)
+            try (result i32)
+                (call $throw (local.get 0))
+                (i32.const 0)
+            catch $e
+                (i32.add (i32.const 1))
+            end))
+    "#,
+    )?;
+    let thrower = Instance::new(&thrower, &imports! {})?;
+    let e = thrower.exports.get_tag("e")?;
+    assert_eq!(e.ty().params(), &[Type::I32]);
+    let throw = thrower.exports.get_function("throw")?;
+
+    let imports = imports! {
+        "thrower" => {
+            "e" => e.clone(),
+            "throw" => throw.clone(),
+        },
+    };
+    let catcher = Instance::new(&catcher, &imports)?;
+    let run: TypedFunction<i32, i32> = catcher.exports.get_native_function("run")?;
+    assert_eq!(run.call(41)?, 42);
+
+    // A tag of the same type is still a different tag.
+    let other = Tag::new(&store, TagType::new([Type::I32]));
+    assert!(!other.same(e));
+    let imports = imports! {
+        "thrower" => {
+            "e" => other,
+            "throw" => throw.clone(),
+        },
+    };
+    let catcher = Instance::new(catcher.module(), &imports)?;
+    let run: TypedFunction<i32, i32> = catcher.exports.get_native_function("run")?;
+    let error = run.call(41).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::UncaughtException));
+    Ok(())
+}
+
+#[compiler_test(exceptions)]
+fn exceptions_stay_in_their_call(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let thrower = Module::new(
+        &store,
+        r#"
+    (module
+        (tag $e)
+        (func (export "throw") (throw $e)))
+    "#,
+    )?;
+    let thrower = Instance::new(&thrower, &imports! {})?;
+    let throw: TypedFunction<(), ()> = thrower.exports.get_native_function("throw")?;
+
+    // The host catches the exception of the inner call, so the outer call
+    // must not see it.
+    let host = Function::new(
+        &store,
+        FunctionType::new(vec![], vec![Type::I32]),
+        move |_values| {
+            let error = throw.call().unwrap_err();
+            assert_eq!(error.to_trap(), Some(TrapCode::UncaughtException));
+            Ok(vec![Value::I32(1)])
+        },
+    );
+    let catcher = Module::new(
+        &store,
+        r#"
+    (module
+        (import "env" "host" (func $host (result i32)))
+        (func (export "run") (result i32)
+            try (result i32)
+                (call $host)
+            catch_all
+                (i32.const 2)
+            end))
+    "#,
+    )?;
+    let catcher = Instance::new(&catcher, &imports! { "env" => { "host" => host } })?;
+    let run: TypedFunction<(), i32> = catcher.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 1);
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Tests for the fuel metering of the compilers
+use anyhow::Result;
+use wasmer::wasmparser::Operator;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn cost_always_one(_: &Operator) -> u64 {
+    1
+}
+
+fn new_instance(mut config: crate::Config, initial_fuel: u64) -> Result<Instance> {
+    config.set_fuel_metering(FuelMetering::new(initial_fuel, cost_always_one));
+    let store = config.store();
+    let wat = r#"(module
+        (func (export "add") (param i32 i32) (result i32)
+           (i32.add (local.get 0)
+                    (local.get 1)))
+        (func (export "test") (param i32)
+           (local i32)
+           (local.set 1 (i32.const 0))
+           (loop
+            (local.get 1)
+            (i32.const 1)
+            (i32.add)
+            (local.tee 1)
+            (local.get 0)
+            (i32.ne)
+            (br_if 0)
+           )
+        )
+)"#;
+    let module = Module::new(&store, wat)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+fn run_add(config: crate::Config, initial_fuel: u64) -> Result<(), RuntimeError> {
+    let instance = new_instance(config, initial_fuel).unwrap();
+    let f: TypedFunction<(i32, i32), i32> = instance.exports.get_native_function("add").unwrap();
+    f.call(4, 6)?;
+    Ok(())
+}
+
+fn run_loop(config: crate::Config, initial_fuel: u64, iter_count: i32) -> Result<(), RuntimeError> {
+    let instance = new_instance(config, initial_fuel).unwrap();
+    let f: TypedFunction<i32, ()> = instance.exports.get_native_function("test").unwrap();
+    f.call(iter_count)
+}
+
+#[compiler_test(fuel)]
+fn fuel_ok(config: crate::Config) -> Result<()> {
+    assert!(run_add(config, 4).is_ok());
+    Ok(())
+}
+
+#[compiler_test(fuel)]
+fn fuel_fail(config: crate::Config) -> Result<()> {
+    let error = run_add(config, 3).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::OutOfFuel));
+    Ok(())
+}
+
+#[compiler_test(fuel)]
+fn loop_once(config: crate::Config) -> Result<()> {
+    assert!(run_loop(config.clone(), 12, 1).is_ok());
+    assert!(run_loop(config, 11, 1).is_err());
+    Ok(())
+}
+
+#[compiler_test(fuel)]
+fn loop_twice(config: crate::Config) -> Result<()> {
+    assert!(run_loop(config.clone(), 19, 2).is_ok());
+    assert!(run_loop(config, 18, 2).is_err());
+    Ok(())
+}
+
+#[compiler_test(fuel)]
+fn add_fuel_and_resume(config: crate::Config) -> Result<()> {
+    let instance = new_instance(config, 6)?;
+    let f: TypedFunction<(i32, i32), i32> = instance.exports.get_native_function("add")?;
+
+    f.call(4, 6)?;
+    assert_eq!(instance.remaining_fuel(), Some(2));
+    assert!(!instance.fuel_exhausted());
+
+    let error = f.call(4, 6).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::OutOfFuel));
+    assert_eq!(instance.remaining_fuel(), Some(2));
+    assert!(instance.fuel_exhausted());
+
+    instance.add_fuel(2);
+    assert!(!instance.fuel_exhausted());
+    assert_eq!(f.call(4, 6)?, 10);
+    assert_eq!(instance.remaining_fuel(), Some(0));
+    Ok(())
+}
+
+#[compiler_test(fuel)]
+fn no_fuel_metering(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    let module = Module::new(&store, "(module)")?;
+    let instance = Instance::new(&module, &imports! {})?;
+    assert_eq!(instance.remaining_fuel(), None);
+    assert!(!instance.fuel_exhausted());
+    Ok(())
+}
@@ -7,13 +7,19 @@ extern crate compiler_test_derive;
 
 mod config;
 mod deterministic;
+mod epoch;
+mod exceptions;
+mod fuel;
 mod imports;
 mod issues;
 mod metering;
 mod middlewares;
+mod multi_memory;
 // mod multi_value_imports;
 mod native_functions;
 mod serialize;
+mod tail_calls;
+mod threads;
 mod traps;
 mod wasi;
 mod wast;
@@ -0,0 +1,103 @@
+//! Tests for the multi-memory proposal
+use anyhow::Result;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn new_store(config: &mut crate::Config) -> Store {
+    let mut features = Features::default();
+    features.multi_memory(true);
+    config.set_features(features);
+    config.store()
+}
+
+const WAT: &str = r#"
+    (module
+        (import "host" "heap" (memory $heap 1))
+        ;; Untrusted input is copied into its own memory, so out of bounds
+        ;; accesses to it can't reach the heap.
+        (memory $buffers (export "buffers") 1 2)
+        (func (export "read_buffer") (param i32) (result i32)
+            (i32.load $buffers offset=4 (local.get 0)))
+        (func (export "write_buffer") (param i32 i32)
+            (i32.store $buffers offset=4 (local.get 0) (local.get 1)))
+        (func (export "take_buffer") (param $dst i32) (param $src i32) (param $len i32)
+            (memory.copy $heap $buffers (local.get $dst) (local.get $src) (local.get $len)))
+        (func (export "heap_size") (result i32)
+            (memory.size $heap))
+        (func (export "grow_buffers") (param i32) (result i32)
+            (memory.grow $buffers (local.get 0))))
+"#;
+
+#[compiler_test(multi_memory)]
+fn multi_memory_requires_the_feature(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    assert!(Module::new(&store, WAT).is_err());
+    Ok(())
+}
+
+#[compiler_test(multi_memory)]
+fn import_and_export_memories(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let module = Module::new(&store, WAT)?;
+    let heap = Memory::new(&store, MemoryType::new(2, None, false))?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "heap" => heap.clone(),
+            },
+        },
+    )?;
+
+    let buffers = instance.exports.get_memory("buffers")?;
+    assert!(!buffers.same(&heap));
+    assert_eq!(buffers.size(), Pages(1));
+    buffers.write(8, &[1, 2, 3, 4])?;
+    heap.write(8, &[5, 6, 7, 8])?;
+    heap.write(0x10004, &[9, 10, 11, 12])?;
+
+    let read_buffer: TypedFunction<i32, i32> =
+        instance.exports.get_native_function("read_buffer")?;
+    assert_eq!(read_buffer.call(4)?, 0x04030201);
+
+    let write_buffer: TypedFunction<(i32, i32), ()> =
+        instance.exports.get_native_function("write_buffer")?;
+    write_buffer.call(8, 0x0d0c0b0a)?;
+    let mut written = [0; 4];
+    buffers.read(12, &mut written)?;
+    assert_eq!(written, [10, 11, 12, 13]);
+    heap.read(12, &mut written)?;
+    assert_eq!(written, [0; 4]);
+
+    let take_buffer: TypedFunction<(i32, i32, i32), ()> =
+        instance.exports.get_native_function("take_buffer")?;
+    take_buffer.call(0x10000, 8, 4)?;
+    let mut taken = [0; 4];
+    heap.read(0x10000, &mut taken)?;
+    assert_eq!(taken, [1, 2, 3, 4]);
+
+    // The heap was imported with two pages, but the buffers only have one.
+    let error = take_buffer.call(0, 0xfffe, 4).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    let error = read_buffer.call(0xfffc).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    let error = write_buffer.call(0xfffc, 0).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    heap.read(8, &mut taken)?;
+    assert_eq!(taken, [5, 6, 7, 8]);
+    heap.read(0x10004, &mut taken)?;
+    assert_eq!(taken, [9, 10, 11, 12]);
+
+    let heap_size: TypedFunction<(), i32> = instance.exports.get_native_function("heap_size")?;
+    assert_eq!(heap_size.call()?, 2);
+    heap.grow(1)?;
+    assert_eq!(heap_size.call()?, 3);
+
+    let grow_buffers: TypedFunction<i32, i32> =
+        instance.exports.get_native_function("grow_buffers")?;
+    assert_eq!(grow_buffers.call(1)?, 1);
+    assert_eq!(grow_buffers.call(1)?, -1);
+    assert_eq!(buffers.size(), Pages(2));
+    assert_eq!(heap.size(), Pages(3));
+    Ok(())
+}
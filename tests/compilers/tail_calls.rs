@@ -0,0 +1,162 @@
+//! Tests for the tail call proposal
+use anyhow::Result;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+fn new_store(config: &mut crate::Config) -> Store {
+    let mut features = Features::default();
+    features.tail_call(true);
+    config.set_features(features);
+    config.store()
+}
+
+#[compiler_test(tail_calls)]
+fn deep_tail_calls_across_signatures(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+        ;; The two functions take different numbers of arguments, so their
+        ;; frames can't simply be reused by each other.
+        (func $ping (export "ping") (param i64) (result i64)
+            (if (result i64) (i64.eqz (local.get 0))
+                (then (i64.const 7))
+                (else
+                    (return_call $pong
+                        (i64.sub (local.get 0) (i64.const 1))
+                        (i64.const 1) (i64.const 2) (i64.const 3)))))
+        (func $pong (param i64 i64 i64 i64) (result i64)
+            (return_call $ping (local.get 0)))
+        (func (export "call_ping") (param i64) (result i64)
+            (i64.add (call $ping (local.get 0)) (i64.const 1))))
+    "#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let ping: TypedFunction<i64, i64> = instance.exports.get_native_function("ping")?;
+    assert_eq!(ping.call(1_000_000)?, 7);
+    let call_ping: TypedFunction<i64, i64> = instance.exports.get_native_function("call_ping")?;
+    assert_eq!(call_ping.call(1_000_000)?, 8);
+    Ok(())
+}
+
+#[compiler_test(tail_calls)]
+fn tail_calls_from_other_modules(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let callee = Module::new(
+        &store,
+        r#"
+    (module
+        (func $count (export "count") (param i64 i64) (result i64)
+            (if (result i64) (i64.eqz (local.get 0))
+                (then (local.get 1))
+                (else
+                    (return_call $count
+                        (i64.sub (local.get 0) (i64.const 1))
+                        (i64.add (local.get 1) (i64.const 2)))))))
+    "#,
+    )?;
+    // The caller makes no tail calls, so it knows nothing of the ones its
+    // callee makes.
+    let caller = Module::new(
+        &store,
+        r#"
+    (module
+        (import "callee" "count" (func $count (param i64 i64) (result i64)))
+        (func (export "call_count") (param i64) (result i64)
+            (i64.add (call $count (local.get 0) (i64.const 0)) (i64.const 1))))
+    "#,
+    )?;
+    let callee = Instance::new(&callee, &imports! {})?;
+    let caller = Instance::new(
+        &caller,
+        &imports! {
+            "callee" => {
+                "count" => callee.exports.get_function("count")?.clone(),
+            },
+        },
+    )?;
+
+    let call_count: TypedFunction<i64, i64> = caller.exports.get_native_function("call_count")?;
+    assert_eq!(call_count.call(100_000)?, 200_001);
+    Ok(())
+}
+
+#[compiler_test(tail_calls)]
+fn tail_call_host_functions(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+        (import "host" "double" (func $double (param i32) (result i32)))
+        (type $t (func (param i32) (result i32)))
+        (table 2 funcref)
+        (elem (i32.const 0) $double)
+        (func (export "direct") (param i32) (result i32)
+            (return_call $double (local.get 0)))
+        (func (export "indirect") (param i32 i32) (result i32)
+            (return_call_indirect (type $t) (local.get 0) (local.get 1))))
+    "#,
+    )?;
+    let double = Function::new_native(&store, |x: i32| x * 2);
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "double" => double,
+            },
+        },
+    )?;
+
+    let direct: TypedFunction<i32, i32> = instance.exports.get_native_function("direct")?;
+    assert_eq!(direct.call(21)?, 42);
+    let indirect: TypedFunction<(i32, i32), i32> =
+        instance.exports.get_native_function("indirect")?;
+    assert_eq!(indirect.call(4, 0)?, 8);
+
+    let error = indirect.call(4, 1).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::IndirectCallToNull));
+    let error = indirect.call(4, 2).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::TableAccessOutOfBounds));
+    Ok(())
+}
+
+#[compiler_test(tail_calls)]
+fn tail_calls_on_many_threads(mut config: crate::Config) -> Result<()> {
+    let store = new_store(&mut config);
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+        (func $count (export "count") (param i64 i64) (result i64)
+            (if (result i64) (i64.eqz (local.get 0))
+                (then (local.get 1))
+                (else
+                    (return_call $count
+                        (i64.sub (local.get 0) (i64.const 1))
+                        (i64.add (local.get 1) (i64.const 1)))))))
+    "#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    // Every thread makes its own tail calls, so the chains don't mix.
+    let threads = (1..=4)
+        .map(|i| {
+            let instance = instance.clone();
+            std::thread::spawn(move || -> Result<()> {
+                let count: TypedFunction<(i64, i64), i64> =
+                    instance.exports.get_native_function("count")?;
+                for _ in 0..10 {
+                    assert_eq!(count.call(i * 10_000, i)?, i * 10_001);
+                }
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Tests for `memory.atomic.wait` and `memory.atomic.notify`
+use anyhow::Result;
+use std::thread;
+use wasmer::*;
+use wasmer_types::TrapCode;
+
+const WOKEN: i32 = 0;
+const NOT_EQUAL: i32 = 1;
+const TIMED_OUT: i32 = 2;
+
+fn new_instance(config: &mut crate::Config, memory: &str) -> Result<Instance> {
+    let mut features = Features::default();
+    features.threads(true);
+    config.set_features(features);
+    let store = config.store();
+
+    let wat = format!(
+        r#"
+    (module
+        {}
+
+        (func (export "wait32") (param $addr i32) (param $expected i32) (param $timeout i64) (result i32)
+            (memory.atomic.wait32 offset=8 (local.get $addr) (local.get $expected) (local.get $timeout)))
+        (func (export "wait64") (param $addr i32) (param $expected i64) (param $timeout i64) (result i32)
+            (memory.atomic.wait64 offset=8 (local.get $addr) (local.get $expected) (local.get $timeout)))
+        (func (export "notify") (param $addr i32) (param $count i32) (result i32)
+            (memory.atomic.notify offset=8 (local.get $addr) (local.get $count)))
+        (func (export "store") (param $addr i32) (param $value i64)
+            (i64.atomic.store offset=8 (local.get $addr) (local.get $value)))
+    )
+    "#,
+        memory
+    );
+    let module = Module::new(&store, wat)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+fn call(instance: &Instance, name: &str, params: &[Val]) -> Result<i32, RuntimeError> {
+    let function = instance.exports.get_function(name).unwrap();
+    Ok(function.call(params)?[0].unwrap_i32())
+}
+
+#[compiler_test(threads)]
+fn wait_without_notify(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(&mut config, "(memory 1 1 shared)")?;
+    let store = instance.exports.get_function("store")?;
+    store.call(&[0.into(), 0x1_0000_0002i64.into()])?;
+
+    let result = call(&instance, "wait32", &[0.into(), 1.into(), 0i64.into()])?;
+    assert_eq!(result, NOT_EQUAL);
+    let result = call(
+        &instance,
+        "wait32",
+        &[0.into(), 2.into(), 1_000_000i64.into()],
+    )?;
+    assert_eq!(result, TIMED_OUT);
+    let result = call(&instance, "wait64", &[0.into(), 2i64.into(), 0i64.into()])?;
+    assert_eq!(result, NOT_EQUAL);
+    let result = call(
+        &instance,
+        "wait64",
+        &[0.into(), 0x1_0000_0002i64.into(), 0i64.into()],
+    )?;
+    assert_eq!(result, TIMED_OUT);
+    assert_eq!(call(&instance, "notify", &[0.into(), 1.into()])?, 0);
+    Ok(())
+}
+
+#[compiler_test(threads)]
+fn wait_and_notify(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(&mut config, "(memory 1 1 shared)")?;
+
+    let waiters = (0..2)
+        .map(|_| {
+            let instance = instance.clone();
+            thread::spawn(move || call(&instance, "wait32", &[16.into(), 0.into(), (-1i64).into()]))
+        })
+        .collect::<Vec<_>>();
+
+    // Wake the waiters one at a time, as they may not be waiting yet
+    let mut woken = 0;
+    while woken < 2 {
+        assert_eq!(call(&instance, "notify", &[20.into(), 2.into()])?, 0);
+        woken += call(&instance, "notify", &[16.into(), 1.into()])?;
+        thread::yield_now();
+    }
+    for waiter in waiters {
+        assert_eq!(waiter.join().unwrap()?, WOKEN);
+    }
+    Ok(())
+}
+
+#[compiler_test(threads)]
+fn wait_traps(mut config: crate::Config) -> Result<()> {
+    let instance = new_instance(&mut config, "(memory 1 1 shared)")?;
+
+    let error = call(&instance, "wait32", &[2.into(), 0.into(), 0i64.into()]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::UnalignedAtomic));
+    let error = call(&instance, "wait64", &[4.into(), 0i64.into(), 0i64.into()]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::UnalignedAtomic));
+    let error = call(&instance, "notify", &[65528.into(), 0.into()]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    let error = call(&instance, "notify", &[(-4).into(), 0.into()]).unwrap_err();
+    assert_eq!(
+        error.to_trap(),
+        Some(TrapCode::HeapAccessOutOfBounds),
+        "the address with its offset doesn't fit in 32 bits"
+    );
+
+    let instance = new_instance(&mut config, "(memory 1 1)")?;
+    let error = call(&instance, "wait32", &[0.into(), 0.into(), 0i64.into()]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::UnsharedMemoryWait));
+    assert_eq!(
+        call(&instance, "notify", &[0.into(), 1.into()])?,
+        0,
+        "unshared memories have no waiters"
+    );
+    Ok(())
+}
@@ -22,12 +22,27 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     let mut features = Features::default();
     let is_bulkmemory = wast_path.contains("bulk-memory");
     let is_simd = wast_path.contains("simd");
+    let is_threads = wast_path.contains("threads");
+    let is_tail_call = wast_path.contains("tail-call");
+    let is_multi_memory = wast_path.contains("multi-memory");
     if is_bulkmemory {
         features.bulk_memory(true);
     }
     if is_simd {
         features.simd(true);
     }
+    if is_threads {
+        features.threads(true);
+        // The tests of the threads proposal predate reference types, and
+        // expect modules with several tables to be invalid
+        features.reference_types(false);
+    }
+    if is_tail_call {
+        features.tail_call(true);
+    }
+    if is_multi_memory {
+        features.multi_memory(true);
+    }
     if config.compiler == crate::Compiler::Singlepass {
         features.multi_value(false);
     }
@@ -56,7 +71,7 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     if config.compiler == crate::Compiler::Singlepass {
         // We don't support multivalue yet in singlepass
         wast.allow_instantiation_failures(&[
-            "Validation error: invalid result arity: func type returns multiple values",
+            "Validation error: func type returns multiple values but the multi-value feature is not enabled",
             "Validation error: blocks, loops, and ifs may only produce a resulttype when multi-value is not enabled",
         ]);
     }
@@ -0,0 +1,68 @@
+//! Basic tests for the `cache` subcommand
+
+use anyhow::bail;
+use std::path::Path;
+use std::process::Command;
+use wasmer_integration_tests_cli::WASMER_PATH;
+
+/// Runs `wasmer` with `args` on the cache in `cache_dir`, returning its
+/// standard output.
+fn wasmer(cache_dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new(WASMER_PATH)
+        .env("WASMER_CACHE_DIR", cache_dir)
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "wasmer {} failed with: stdout: {}\n\nstderr: {}",
+            args.join(" "),
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[test]
+fn cache_list_stats_and_prune() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let cache_dir = temp_dir.path().join("cache");
+    // Modules smaller than 4KiB aren't cached.
+    let wat_path = temp_dir.path().join("answer.wat");
+    std::fs::write(
+        &wat_path,
+        format!(
+            "(module (func (export \"answer\") (result i32) i32.const 42))\n;;{}\n",
+            " ".repeat(0x1000)
+        ),
+    )?;
+    let wat_path = wat_path.to_str().unwrap();
+
+    for _ in 0..3 {
+        let output = wasmer(&cache_dir, &["run", wat_path, "--invoke", "answer"])?;
+        assert_eq!(output.trim(), "42");
+    }
+
+    let list = wasmer(&cache_dir, &["cache", "list"])?;
+    let lines: Vec<&str> = list.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", list);
+    assert!(lines[0].starts_with("HASH"));
+    assert!(lines[1].contains("universal/"));
+    assert!(lines[1].trim_end().ends_with(" 2"));
+
+    let stats = wasmer(&cache_dir, &["cache", "stats"])?;
+    let total = stats.lines().last().unwrap();
+    let fields: Vec<&str> = total.split_whitespace().collect();
+    assert_eq!(fields[0], "total");
+    assert_eq!(fields[1], "1");
+    assert_eq!(&fields[4..], ["2", "1", "66.7%"]);
+
+    wasmer(&cache_dir, &["cache", "prune", "--older-than", "1h"])?;
+    assert_eq!(wasmer(&cache_dir, &["cache", "list"])?.lines().count(), 2);
+    wasmer(&cache_dir, &["cache", "prune", "--max-size", "1KiB"])?;
+    assert_eq!(wasmer(&cache_dir, &["cache", "list"])?.lines().count(), 1);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! Basic tests for the `inspect` subcommand
+
+use anyhow::bail;
+use std::process::Command;
+use wasmer_integration_tests_cli::WASMER_PATH;
+
+const WAT: &str = r#"
+(module $demo
+  (import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 8) "hello")
+  (data $passive "passive!")
+  (func $main (export "_start")
+    (memory.init $passive (i32.const 0) (i32.const 0) (i32.const 4))
+    (call $exit (i32.const 0)))
+  (func $pair (result i32 i32) (i32.const 1) (i32.const 2))
+  (start $main))
+"#;
+
+#[test]
+fn inspect_json_output() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let wat_path = temp_dir.path().join("demo.wat");
+    std::fs::write(&wat_path, WAT)?;
+
+    let output = Command::new(WASMER_PATH)
+        .arg("inspect")
+        .arg("--format")
+        .arg("json")
+        .arg(&wat_path)
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "inspect failed with: stdout: {}\n\nstderr: {}",
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["type"], "wat");
+    assert_eq!(report["module_name"], "demo");
+    assert_eq!(report["imports"][0]["name"], "proc_exit");
+    assert_eq!(report["start_function"]["name"], "main");
+    assert_eq!(report["functions"]["imported"], 1);
+    assert_eq!(report["functions"]["defined"], 2);
+    assert_eq!(report["functions"]["code_sizes"][1]["name"], "pair");
+    assert_eq!(report["data_segments"][0]["memory"], 0);
+    assert_eq!(report["data_segments"][0]["size"], 5);
+    assert_eq!(
+        report["data_segments"][1]["memory"],
+        serde_json::Value::Null
+    );
+    assert_eq!(report["features"]["bulk_memory"], true);
+    assert_eq!(report["features"]["multi_value"], true);
+    assert_eq!(report["features"]["simd"], false);
+    assert_eq!(report["wasi_versions"][0], "wasi_snapshot_preview1");
+
+    Ok(())
+}
@@ -16,6 +16,14 @@ fn test_no_start_wat_path() -> String {
     format!("{}/{}", ASSET_PATH, "no_start.wat")
 }
 
+fn wasi_list_dir_wasm_path() -> String {
+    concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../../tests/wasi-wast/wasi/snapshot1/mapdir.wasm"
+    )
+    .to_string()
+}
+
 #[test]
 fn run_wasi_works() -> anyhow::Result<()> {
     let output = Command::new(WASMER_PATH)
@@ -75,3 +83,97 @@ fn run_no_start_wasm_report_error() -> anyhow::Result<()> {
     assert_eq!(result.contains("Can not find any export functions."), true);
     Ok(())
 }
+
+#[test]
+fn run_wasi_mount_archive_works() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let archive_path = temp_dir.path().join("assets.tar");
+    let mut builder = tar::Builder::new(std::fs::File::create(&archive_path)?);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, "README.md", &b"hello"[..])?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, "act1/scene1.txt", &b"hello"[..])?;
+    builder.finish()?;
+    drop(builder);
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(format!("--mount-archive=.:{}", archive_path.display()))
+        .arg(wasi_list_dir_wasm_path())
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "running failed with: stdout: {}\n\nstderr: {}",
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+
+    let stdout_output = std::str::from_utf8(&output.stdout).unwrap();
+    assert_eq!(stdout_output, "\"./README.md\"\n\"./act1\"\n");
+
+    Ok(())
+}
+
+/// Runs `wat` with the given limits, and returns the exit code and stderr.
+fn run_with_limits(wat: &str, limits: &[&str]) -> anyhow::Result<(Option<i32>, String)> {
+    let temp_dir = tempfile::tempdir()?;
+    let wat_path = temp_dir.path().join("module.wat");
+    std::fs::write(&wat_path, wat)?;
+
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .args(limits)
+        .arg(&wat_path)
+        .output()?;
+    let stderr = std::str::from_utf8(&output.stderr)
+        .expect("stderr is not utf8! need to handle arbitrary bytes")
+        .to_string();
+    Ok((output.status.code(), stderr))
+}
+
+#[test]
+fn run_with_limits_reports_the_limit_hit() -> anyhow::Result<()> {
+    let infinite_loop = r#"(module (func (export "_start") (loop (br 0))))"#;
+    let (code, stderr) = run_with_limits(infinite_loop, &["--timeout", "500ms"])?;
+    assert_eq!(code, Some(124), "{}", stderr);
+    assert!(
+        stderr.contains("the module timed out after 500ms"),
+        "{}",
+        stderr
+    );
+
+    let (code, stderr) = run_with_limits(infinite_loop, &["--fuel", "1000"])?;
+    assert_eq!(code, Some(122), "{}", stderr);
+    assert!(stderr.contains("ran out of fuel"), "{}", stderr);
+
+    let grow = r#"(module
+        (memory 1)
+        (func (export "_start")
+            (if (i32.eq (memory.grow (i32.const 16)) (i32.const -1))
+                (then unreachable))))"#;
+    let (code, stderr) = run_with_limits(grow, &[])?;
+    assert_eq!(code, Some(0), "{}", stderr);
+    let (code, stderr) = run_with_limits(grow, &["--max-memory", "1MiB"])?;
+    assert_eq!(code, Some(121), "{}", stderr);
+    assert!(
+        stderr.contains("needed more memory than the limit of 1.0 MiB"),
+        "{}",
+        stderr
+    );
+
+    let too_big = r#"(module (memory 17) (func (export "_start")))"#;
+    let (code, stderr) = run_with_limits(too_big, &["--max-memory", "1MiB"])?;
+    assert_eq!(code, Some(121), "{}", stderr);
+    let (code, stderr) = run_with_limits(too_big, &["--max-memory", "2MiB"])?;
+    assert_eq!(code, Some(0), "{}", stderr);
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+;; Tests the multi-memory proposal with several defined and imported memories.
+
+(module $M
+  (memory (export "mem") 1)
+  (func (export "load") (param i32) (result i32)
+    (i32.load8_u (local.get 0))))
+(register "M" $M)
+
+(module
+  ;; The imported memory is memory 0, so the defined memories start at 1.
+  (import "M" "mem" (memory $imported 1))
+  (memory $heap 1 3)
+  (memory $buffers 0 1)
+  (data (memory $heap) (i32.const 8) "heap")
+  (data (memory $buffers) (i32.const 0) "")
+  (data $passive "buffer")
+
+  (func (export "store") (param $mem i32) (param $addr i32) (param $val i64)
+    (if (i32.eq (local.get $mem) (i32.const 0))
+      (then (i64.store $imported (local.get $addr) (local.get $val))))
+    (if (i32.eq (local.get $mem) (i32.const 1))
+      (then (i64.store $heap (local.get $addr) (local.get $val))))
+    (if (i32.eq (local.get $mem) (i32.const 2))
+      (then (i64.store $buffers (local.get $addr) (local.get $val)))))
+  (func (export "load") (param $mem i32) (param $addr i32) (result i64)
+    (if (i32.eq (local.get $mem) (i32.const 0))
+      (then (return (i64.load $imported (local.get $addr)))))
+    (if (i32.eq (local.get $mem) (i32.const 1))
+      (then (return (i64.load $heap (local.get $addr)))))
+    (i64.load $buffers (local.get $addr)))
+  (func (export "load8") (param $mem i32) (param $addr i32) (result i32)
+    (if (i32.eq (local.get $mem) (i32.const 0))
+      (then (return (i32.load8_u $imported (local.get $addr)))))
+    (if (i32.eq (local.get $mem) (i32.const 1))
+      (then (return (i32.load8_u $heap (local.get $addr)))))
+    (i32.load8_u $buffers (local.get $addr)))
+
+  (func (export "size") (param $mem i32) (result i32)
+    (if (i32.eq (local.get $mem) (i32.const 0))
+      (then (return (memory.size $imported))))
+    (if (i32.eq (local.get $mem) (i32.const 1))
+      (then (return (memory.size $heap))))
+    (memory.size $buffers))
+  (func (export "grow_heap") (param i32) (result i32)
+    (memory.grow $heap (local.get 0)))
+  (func (export "grow_buffers") (param i32) (result i32)
+    (memory.grow $buffers (local.get 0)))
+
+  (func (export "fill_buffers") (param i32 i32 i32)
+    (memory.fill $buffers (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "init_buffers") (param i32 i32 i32)
+    (memory.init $buffers $passive (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy_heap") (param i32 i32 i32)
+    (memory.copy $heap $heap (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy_buffers_to_heap") (param i32 i32 i32)
+    (memory.copy $heap $buffers (local.get 0) (local.get 1) (local.get 2)))
+  (func (export "copy_heap_to_imported") (param i32 i32 i32)
+    (memory.copy $imported $heap (local.get 0) (local.get 1) (local.get 2))))
+
+;; Data segments initialize the memory they name.
+(assert_return (invoke "load8" (i32.const 1) (i32.const 8)) (i32.const 0x68))
+(assert_return (invoke "load8" (i32.const 0) (i32.const 8)) (i32.const 0))
+
+;; Each memory has its own size.
+(assert_return (invoke "size" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "size" (i32.const 1)) (i32.const 1))
+(assert_return (invoke "size" (i32.const 2)) (i32.const 0))
+(assert_trap (invoke "load" (i32.const 2) (i32.const 0)) "out of bounds memory access")
+(assert_return (invoke "grow_buffers" (i32.const 1)) (i32.const 0))
+(assert_return (invoke "grow_buffers" (i32.const 1)) (i32.const -1))
+(assert_return (invoke "grow_heap" (i32.const 2)) (i32.const 1))
+(assert_return (invoke "size" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "size" (i32.const 1)) (i32.const 3))
+(assert_return (invoke "size" (i32.const 2)) (i32.const 1))
+
+;; Loads and stores only touch the memory they name.
+(assert_return (invoke "store" (i32.const 0) (i32.const 16) (i64.const 1)))
+(assert_return (invoke "store" (i32.const 1) (i32.const 16) (i64.const 2)))
+(assert_return (invoke "store" (i32.const 2) (i32.const 16) (i64.const 3)))
+(assert_return (invoke "load" (i32.const 0) (i32.const 16)) (i64.const 1))
+(assert_return (invoke "load" (i32.const 1) (i32.const 16)) (i64.const 2))
+(assert_return (invoke "load" (i32.const 2) (i32.const 16)) (i64.const 3))
+(assert_return (invoke $M "load" (i32.const 16)) (i32.const 1))
+(assert_return (invoke "store" (i32.const 1) (i32.const 0x2fff8) (i64.const 4)))
+(assert_return (invoke "load" (i32.const 1) (i32.const 0x2fff8)) (i64.const 4))
+(assert_trap (invoke "load" (i32.const 0) (i32.const 0x2fff8)) "out of bounds memory access")
+(assert_trap (invoke "store" (i32.const 2) (i32.const 0xfff9) (i64.const 0)) "out of bounds memory access")
+
+;; Bulk memory operations.
+(assert_return (invoke "fill_buffers" (i32.const 32) (i32.const 0x61) (i32.const 4)))
+(assert_return (invoke "load8" (i32.const 2) (i32.const 35)) (i32.const 0x61))
+(assert_return (invoke "load8" (i32.const 1) (i32.const 35)) (i32.const 0))
+(assert_return (invoke "init_buffers" (i32.const 40) (i32.const 0) (i32.const 6)))
+(assert_return (invoke "load8" (i32.const 2) (i32.const 40)) (i32.const 0x62))
+(assert_return (invoke "copy_buffers_to_heap" (i32.const 100) (i32.const 40) (i32.const 6)))
+(assert_return (invoke "load8" (i32.const 1) (i32.const 105)) (i32.const 0x72))
+(assert_return (invoke "copy_heap" (i32.const 101) (i32.const 100) (i32.const 6)))
+(assert_return (invoke "load8" (i32.const 1) (i32.const 106)) (i32.const 0x72))
+(assert_return (invoke "copy_heap_to_imported" (i32.const 200) (i32.const 100) (i32.const 7)))
+(assert_return (invoke $M "load" (i32.const 206)) (i32.const 0x72))
+
+;; Copies between memories check the bounds of each memory.
+(assert_trap (invoke "copy_buffers_to_heap" (i32.const 0) (i32.const 0xfffe) (i32.const 4)) "out of bounds memory access")
+(assert_trap (invoke "copy_heap_to_imported" (i32.const 0xfffe) (i32.const 0) (i32.const 4)) "out of bounds memory access")
+(assert_return (invoke "copy_heap_to_imported" (i32.const 0) (i32.const 0x2fff8) (i32.const 8)))
+(assert_return (invoke $M "load" (i32.const 0)) (i32.const 4))
+
+;; Memory indices must exist.
+(assert_invalid
+  (module (memory 1) (func (drop (i32.load 1 (i32.const 0)))))
+  "unknown memory 1")